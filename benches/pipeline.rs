@@ -0,0 +1,78 @@
+#![feature(test)]
+
+extern crate test;
+
+use gpx::testutil::synthetic_gpx;
+use gpx::{Gpx, Track, TrackSegment};
+
+/// Five tracks of 100 000 points each.
+fn document() -> Gpx {
+    synthetic_gpx(5, 100_000)
+}
+
+/// Reverses every segment, borrowing the document and cloning the points.
+fn cloning_pipeline(gpx: &Gpx) -> Gpx {
+    let tracks = gpx
+        .tracks
+        .iter()
+        .map(|track| {
+            let segments = track
+                .segments
+                .iter()
+                .map(|segment| {
+                    let mut points = segment.points.clone();
+                    points.reverse();
+                    TrackSegment::from_points(points)
+                })
+                .collect();
+            Track::from_segments(segments)
+        })
+        .collect();
+    Gpx::from_parts(
+        gpx.metadata.clone(),
+        gpx.waypoints.clone(),
+        gpx.routes.clone(),
+        tracks,
+        gpx.version,
+        gpx.creator.clone(),
+    )
+}
+
+/// Reverses every segment, taking the document apart and moving the points.
+fn moving_pipeline(gpx: Gpx) -> Gpx {
+    let (metadata, waypoints, routes, tracks, version, creator) = gpx.into_parts();
+    let tracks = tracks
+        .into_iter()
+        .map(|track| {
+            let segments = track
+                .into_segments()
+                .into_iter()
+                .map(|segment| {
+                    let mut points = segment.into_points();
+                    points.reverse();
+                    TrackSegment::from_points(points)
+                })
+                .collect();
+            Track::from_segments(segments)
+        })
+        .collect();
+    Gpx::from_parts(metadata, waypoints, routes, tracks, version, creator)
+}
+
+#[bench]
+fn bench_cloning_pipeline(bencher: &mut test::Bencher) {
+    let mut gpx = document();
+    bencher.iter(|| {
+        gpx = cloning_pipeline(&gpx);
+        test::black_box(&gpx);
+    });
+}
+
+#[bench]
+fn bench_moving_pipeline(bencher: &mut test::Bencher) {
+    let mut gpx = Some(document());
+    bencher.iter(|| {
+        gpx = Some(moving_pipeline(gpx.take().unwrap()));
+        test::black_box(&gpx);
+    });
+}
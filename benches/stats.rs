@@ -0,0 +1,16 @@
+#![feature(test)]
+
+extern crate test;
+
+use gpx::testutil::synthetic_gpx;
+use gpx::StatsOptions;
+
+/// Statistics of five tracks of 200 000 points each, with just positions,
+/// elevations and times, as most recorded tracks have. The points are small
+/// enough for the stats loops to stay in the cache.
+#[bench]
+fn bench_track_stats(bencher: &mut test::Bencher) {
+    let gpx = synthetic_gpx(5, 200_000);
+    let options = StatsOptions::default();
+    bencher.iter(|| test::black_box(gpx.track_stats(&options)));
+}
@@ -0,0 +1,207 @@
+//! accuracy estimates how far off the recorded position of a point may be,
+//! from the dilutions of precision its receiver gave.
+
+use crate::{ChangeReport, Gpx, TrackSegment, Waypoint};
+
+/// Options for [`Waypoint::accuracy_estimate`] and the quality buckets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccuracyOptions {
+    /// The user equivalent range error in meters, the error of a single
+    /// satellite range that the dilution of precision multiplies.
+    pub uere: f64,
+
+    /// The highest estimate in meters that is [`GpsQuality::Excellent`].
+    pub excellent: f64,
+
+    /// The highest estimate in meters that is [`GpsQuality::Good`].
+    pub good: f64,
+
+    /// The highest estimate in meters that is [`GpsQuality::Moderate`].
+    /// Anything above is [`GpsQuality::Poor`].
+    pub moderate: f64,
+}
+
+impl Default for AccuracyOptions {
+    fn default() -> AccuracyOptions {
+        AccuracyOptions {
+            uere: 5.0,
+            excellent: 10.0,
+            good: 25.0,
+            moderate: 50.0,
+        }
+    }
+}
+
+/// How good the position of a point is, as given by
+/// [`Waypoint::quality`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GpsQuality {
+    /// Within [`AccuracyOptions::excellent`].
+    Excellent,
+    /// Within [`AccuracyOptions::good`].
+    Good,
+    /// Within [`AccuracyOptions::moderate`].
+    Moderate,
+    /// Worse than [`AccuracyOptions::moderate`].
+    Poor,
+    /// The point has no dilution of precision to tell.
+    Unknown,
+}
+
+impl Waypoint {
+    /// Estimates the horizontal error of the position in meters, as the
+    /// `hdop` times [`AccuracyOptions::uere`].
+    ///
+    /// Without an `hdop`, the `pdop` is used instead, which also covers the
+    /// vertical error and so gives a larger estimate. Without either, or if
+    /// they are negative or not finite, there is no estimate.
+    ///
+    /// ```
+    /// use gpx::{AccuracyOptions, Waypoint};
+    ///
+    /// let mut point = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+    /// assert_eq!(point.accuracy_estimate(&AccuracyOptions::default()), None);
+    ///
+    /// point.extras_mut().pdop = Some(2.0);
+    /// assert_eq!(point.accuracy_estimate(&AccuracyOptions::default()), Some(10.0));
+    /// point.extras_mut().hdop = Some(1.2);
+    /// assert_eq!(point.accuracy_estimate(&AccuracyOptions::default()), Some(6.0));
+    /// ```
+    pub fn accuracy_estimate(&self, options: &AccuracyOptions) -> Option<f64> {
+        let usable = |dop: Option<f64>| dop.filter(|dop| dop.is_finite() && *dop >= 0.0);
+        let dop = usable(self.extras().hdop).or_else(|| usable(self.extras().pdop))?;
+        Some(dop * options.uere)
+    }
+
+    /// Gives the quality of the position by its
+    /// [`Waypoint::accuracy_estimate`], with the default options.
+    pub fn quality(&self) -> GpsQuality {
+        self.quality_with_options(&AccuracyOptions::default())
+    }
+
+    /// Like [`Waypoint::quality`], tuned by `options`. An estimate exactly
+    /// at a threshold counts towards the better quality.
+    pub fn quality_with_options(&self, options: &AccuracyOptions) -> GpsQuality {
+        match self.accuracy_estimate(options) {
+            None => GpsQuality::Unknown,
+            Some(estimate) if estimate <= options.excellent => GpsQuality::Excellent,
+            Some(estimate) if estimate <= options.good => GpsQuality::Good,
+            Some(estimate) if estimate <= options.moderate => GpsQuality::Moderate,
+            Some(_) => GpsQuality::Poor,
+        }
+    }
+}
+
+impl TrackSegment {
+    /// Removes the points with an [`Waypoint::accuracy_estimate`] of
+    /// `meters` or more, and reports them as removed. Points without an
+    /// estimate are kept.
+    pub fn retain_accuracy_below(
+        &mut self,
+        meters: f64,
+        options: &AccuracyOptions,
+    ) -> ChangeReport {
+        let before = self.points.len();
+        self.points.retain(|point| {
+            point
+                .accuracy_estimate(options)
+                .map_or(true, |estimate| estimate < meters)
+        });
+        ChangeReport::of_segment("retain_accuracy_below", before, self.points.len(), 0)
+    }
+}
+
+impl Gpx {
+    /// Removes the track points with an accuracy estimate of `meters` or
+    /// more, see [`TrackSegment::retain_accuracy_below`].
+    pub fn retain_accuracy_below(
+        &mut self,
+        meters: f64,
+        options: &AccuracyOptions,
+    ) -> ChangeReport {
+        self.report_segments("retain_accuracy_below", |segment| {
+            segment.retain_accuracy_below(meters, options)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccuracyOptions, GpsQuality};
+    use crate::{TrackSegment, Waypoint};
+
+    fn point(hdop: Option<f64>, pdop: Option<f64>) -> Waypoint {
+        let mut point = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+        point.extras_mut().hdop = hdop;
+        point.extras_mut().pdop = pdop;
+        point
+    }
+
+    #[test]
+    fn quality_buckets() {
+        let cases = [
+            (0.0, GpsQuality::Excellent),
+            (2.0, GpsQuality::Excellent),
+            (2.2, GpsQuality::Good),
+            (5.0, GpsQuality::Good),
+            (5.2, GpsQuality::Moderate),
+            (10.0, GpsQuality::Moderate),
+            (10.2, GpsQuality::Poor),
+            (99.9, GpsQuality::Poor),
+        ];
+        for (hdop, quality) in cases.iter() {
+            assert_eq!(point(Some(*hdop), None).quality(), *quality, "{}", hdop);
+        }
+
+        let strict = AccuracyOptions {
+            uere: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            point(Some(2.0), None).quality_with_options(&strict),
+            GpsQuality::Good
+        );
+        assert_eq!(point(None, None).quality(), GpsQuality::Unknown);
+    }
+
+    #[test]
+    fn pdop_is_the_fallback() {
+        let options = AccuracyOptions::default();
+        assert_eq!(
+            point(None, Some(3.0)).accuracy_estimate(&options),
+            Some(15.0)
+        );
+        assert_eq!(
+            point(Some(1.0), Some(3.0)).accuracy_estimate(&options),
+            Some(5.0)
+        );
+        assert_eq!(
+            point(Some(f64::NAN), Some(3.0)).accuracy_estimate(&options),
+            Some(15.0)
+        );
+        assert_eq!(point(Some(-1.0), None).accuracy_estimate(&options), None);
+        assert_eq!(point(None, None).accuracy_estimate(&options), None);
+    }
+
+    #[test]
+    fn retain_accurate_points() {
+        let mut segment = TrackSegment::new();
+        segment.points = vec![
+            point(Some(0.8), None),
+            point(Some(4.0), None),
+            point(None, None),
+            point(None, Some(1.5)),
+            point(Some(2.0), Some(1.0)),
+            point(None, Some(6.0)),
+        ];
+
+        let report = segment.retain_accuracy_below(10.0, &AccuracyOptions::default());
+        assert_eq!((report.before, report.after, report.removed), (6, 3, 3));
+        let kept: Vec<(Option<f64>, Option<f64>)> = segment
+            .points
+            .iter()
+            .map(|point| (point.extras().hdop, point.extras().pdop))
+            .collect();
+        assert_eq!(kept, [(Some(0.8), None), (None, None), (None, Some(1.5))]);
+    }
+}
@@ -0,0 +1,436 @@
+//! activity splits recordings that mix standing, walking, cycling and
+//! driving into spans of one activity each, by their speed.
+
+use std::cmp::Ordering;
+
+use time::{Duration, OffsetDateTime};
+
+use crate::distance::haversine;
+use crate::{Track, TrackSegment};
+
+/// Options for [`TrackSegment::classify_activity`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActivityOptions {
+    /// Speed in meters per second below which the track counts as
+    /// [`ActivityKind::Stationary`].
+    pub stationary_below_mps: f64,
+
+    /// Speed in meters per second below which the track counts as
+    /// [`ActivityKind::Walking`].
+    pub walking_below_mps: f64,
+
+    /// Speed in meters per second below which the track counts as
+    /// [`ActivityKind::Cycling`]. Anything faster is
+    /// [`ActivityKind::Driving`].
+    pub cycling_below_mps: f64,
+
+    /// Shortest time of a span. Shorter stretches, such as waiting at a
+    /// light during a walk, become part of the longer span next to them.
+    pub min_span_duration: Duration,
+}
+
+impl Default for ActivityOptions {
+    fn default() -> ActivityOptions {
+        ActivityOptions {
+            stationary_below_mps: 0.5,
+            walking_below_mps: 2.5,
+            cycling_below_mps: 10.0,
+            min_span_duration: Duration::seconds(60),
+        }
+    }
+}
+
+impl ActivityOptions {
+    fn kind(&self, speed: f64) -> ActivityKind {
+        if speed < self.stationary_below_mps {
+            ActivityKind::Stationary
+        } else if speed < self.walking_below_mps {
+            ActivityKind::Walking
+        } else if speed < self.cycling_below_mps {
+            ActivityKind::Cycling
+        } else {
+            ActivityKind::Driving
+        }
+    }
+}
+
+/// The coarse activity of an [`ActivitySpan`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ActivityKind {
+    /// Below [`ActivityOptions::stationary_below_mps`].
+    Stationary,
+    /// Below [`ActivityOptions::walking_below_mps`].
+    Walking,
+    /// Below [`ActivityOptions::cycling_below_mps`].
+    Cycling,
+    /// Faster than walking or cycling.
+    Driving,
+    /// The speed is not known, because points have no time or times don't
+    /// go forward.
+    Unknown,
+}
+
+/// A stretch of a track segment with one activity, as found by
+/// [`TrackSegment::classify_activity`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActivitySpan {
+    /// Index of the point where the span starts.
+    pub start_index: usize,
+
+    /// Index of the point where the span ends, which is where the next span
+    /// starts.
+    pub end_index: usize,
+
+    /// The activity.
+    pub kind: ActivityKind,
+
+    /// The distance covered divided by the time taken, in meters per
+    /// second, or `None` for [`ActivityKind::Unknown`].
+    pub average_speed: Option<f64>,
+}
+
+/// Consecutive pairs of points of the same activity, in seconds and meters.
+struct Run {
+    kind: ActivityKind,
+    first: usize,
+    last: usize,
+    seconds: f64,
+    meters: f64,
+}
+
+impl Run {
+    fn absorb(&mut self, other: &Run) {
+        self.first = self.first.min(other.first);
+        self.last = self.last.max(other.last);
+        self.seconds += other.seconds;
+        self.meters += other.meters;
+    }
+}
+
+impl TrackSegment {
+    /// Splits the segment into spans of one activity each, in order.
+    ///
+    /// The speed between two consecutive points is their distance divided
+    /// by the time between them, which sorts the pair into an activity by
+    /// the thresholds of `options`. Runs of pairs with the same activity
+    /// make the spans. Spans shorter than
+    /// [`ActivityOptions::min_span_duration`] are merged into the longer
+    /// span next to them, shortest first. Pairs whose speed can't be told
+    /// give [`ActivityKind::Unknown`] spans, which are never merged, and
+    /// neither are the spans next to them.
+    ///
+    /// A segment with a single point gives one unknown span, and an empty
+    /// segment none.
+    ///
+    /// ```
+    /// use gpx::{ActivityKind, ActivityOptions, TrackSegment, Waypoint};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// // Standing for two minutes, then walking 1.1 km in ten minutes.
+    /// for (seconds, x) in [(0, 0.0), (60, 0.0), (120, 0.0), (420, 0.005), (720, 0.01)].iter() {
+    ///     let mut point = Waypoint::new_lat_lon(0.0, *x).unwrap();
+    ///     point.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(*seconds)).into());
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// let spans = segment.classify_activity(&ActivityOptions::default());
+    /// assert_eq!(spans.len(), 2);
+    /// assert_eq!((spans[0].start_index, spans[0].end_index), (0, 2));
+    /// assert_eq!(spans[0].kind, ActivityKind::Stationary);
+    /// assert_eq!(spans[1].kind, ActivityKind::Walking);
+    /// ```
+    pub fn classify_activity(&self, options: &ActivityOptions) -> Vec<ActivitySpan> {
+        if self.points.len() < 2 {
+            return self
+                .points
+                .iter()
+                .map(|_| ActivitySpan {
+                    start_index: 0,
+                    end_index: 0,
+                    kind: ActivityKind::Unknown,
+                    average_speed: None,
+                })
+                .collect();
+        }
+
+        let mut runs: Vec<Run> = Vec::new();
+        for (n, pair) in self.points.windows(2).enumerate() {
+            let seconds = match (pair[0].time, pair[1].time) {
+                (Some(from), Some(to)) => {
+                    (OffsetDateTime::from(to) - OffsetDateTime::from(from)).as_seconds_f64()
+                }
+                _ => 0.0,
+            };
+            let run = if seconds > 0.0 {
+                let meters = haversine(pair[0].point(), pair[1].point());
+                Run {
+                    kind: options.kind(meters / seconds),
+                    first: n,
+                    last: n,
+                    seconds,
+                    meters,
+                }
+            } else {
+                Run {
+                    kind: ActivityKind::Unknown,
+                    first: n,
+                    last: n,
+                    seconds: 0.0,
+                    meters: 0.0,
+                }
+            };
+            match runs.last_mut() {
+                Some(last) if last.kind == run.kind => last.absorb(&run),
+                _ => runs.push(run),
+            }
+        }
+        smooth(&mut runs, options.min_span_duration.as_seconds_f64());
+
+        runs.iter()
+            .map(|run| ActivitySpan {
+                start_index: run.first,
+                end_index: run.last + 1,
+                kind: run.kind,
+                average_speed: match run.kind {
+                    ActivityKind::Unknown => None,
+                    _ => Some(run.meters / run.seconds),
+                },
+            })
+            .collect()
+    }
+}
+
+impl Track {
+    /// Splits the track into one track per span of
+    /// [`TrackSegment::classify_activity`], in order, each with a single
+    /// segment and the name, links and other fields of this track.
+    ///
+    /// Consecutive spans share the point where one ends and the next
+    /// starts. Spans of different segments are never joined.
+    pub fn split_by_activity(&self, options: &ActivityOptions) -> Vec<(ActivityKind, Track)> {
+        let mut tracks = Vec::new();
+        for segment in &self.segments {
+            for span in segment.classify_activity(options) {
+                let part = TrackSegment {
+                    points: segment.points[span.start_index..=span.end_index].to_vec(),
+                    extensions: segment.extensions.clone(),
+                };
+                let track = Track {
+                    name: self.name.clone(),
+                    comment: self.comment.clone(),
+                    description: self.description.clone(),
+                    source: self.source.clone(),
+                    links: self.links.clone(),
+                    _type: self._type.clone(),
+                    number: self.number,
+                    extensions: self.extensions.clone(),
+                    segments: vec![part],
+                    unknown_attributes: self.unknown_attributes.clone(),
+                };
+                tracks.push((span.kind, track));
+            }
+        }
+        tracks
+    }
+}
+
+/// Merges runs shorter than `min_seconds` into the longer of their
+/// neighbours, shortest first, until every short run is alone or next to a
+/// run of unknown activity.
+fn smooth(runs: &mut Vec<Run>, min_seconds: f64) {
+    let known = |run: &Run| run.kind != ActivityKind::Unknown;
+    loop {
+        let mergeable = |i: usize| {
+            let previous = i.checked_sub(1).map(|previous| &runs[previous]);
+            let next = runs.get(i + 1);
+            known(&runs[i])
+                && runs[i].seconds < min_seconds
+                && (previous.is_some() || next.is_some())
+                && previous.map_or(true, known)
+                && next.map_or(true, known)
+        };
+        let shortest = (0..runs.len()).filter(|&i| mergeable(i)).min_by(|&a, &b| {
+            runs[a]
+                .seconds
+                .partial_cmp(&runs[b].seconds)
+                .unwrap_or(Ordering::Equal)
+        });
+        let short = match shortest {
+            Some(short) => short,
+            None => return,
+        };
+        let into = match (short.checked_sub(1), runs.get(short + 1)) {
+            (Some(previous), Some(next)) if next.seconds > runs[previous].seconds => short + 1,
+            (Some(previous), _) => previous,
+            (None, _) => short + 1,
+        };
+        let run = runs.remove(short);
+        let mut into = if into > short { into - 1 } else { into };
+        runs[into].absorb(&run);
+        // The run may now join runs of the same activity on either side.
+        if into + 1 < runs.len() && runs[into + 1].kind == runs[into].kind {
+            let next = runs.remove(into + 1);
+            runs[into].absorb(&next);
+        }
+        if into > 0 && runs[into - 1].kind == runs[into].kind {
+            let current = runs.remove(into);
+            into -= 1;
+            runs[into].absorb(&current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::{ActivityKind, ActivityOptions};
+    use crate::{Track, TrackSegment, Waypoint};
+
+    /// Meters per degree of longitude on the equator.
+    const METERS_PER_DEGREE: f64 = 111_195.0;
+
+    /// A segment along the equator with a point every five seconds, moving
+    /// through `phases` of (seconds, meters per second). Standing still
+    /// jitters by 20 cm.
+    fn recording(phases: &[(i64, f64)]) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        let (mut seconds, mut meters) = (0, 0.0);
+        let mut push = |seconds: i64, meters: f64| {
+            let mut point = Waypoint::new(Point::new(meters / METERS_PER_DEGREE, 0.0));
+            point.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(seconds)).into());
+            segment.points.push(point);
+        };
+        push(seconds, meters);
+        for &(duration, speed) in phases {
+            for step in 0..duration / 5 {
+                seconds += 5;
+                meters += if speed == 0.0 {
+                    if step % 2 == 0 {
+                        0.2
+                    } else {
+                        -0.2
+                    }
+                } else {
+                    5.0 * speed
+                };
+                push(seconds, meters);
+            }
+        }
+        segment
+    }
+
+    fn spans(
+        segment: &TrackSegment,
+        options: &ActivityOptions,
+    ) -> Vec<(usize, usize, ActivityKind)> {
+        segment
+            .classify_activity(options)
+            .iter()
+            .map(|span| (span.start_index, span.end_index, span.kind))
+            .collect()
+    }
+
+    /// Standing for a minute, walking with a brief stop, standing again and
+    /// walking on.
+    fn stop_and_go() -> TrackSegment {
+        recording(&[
+            (60, 0.0),
+            (50, 1.4),
+            (5, 0.3),
+            (45, 1.4),
+            (90, 0.0),
+            (100, 1.4),
+        ])
+    }
+
+    #[test]
+    fn alternating_stationary_and_walking() {
+        let segment = stop_and_go();
+        let options = ActivityOptions::default();
+        assert_eq!(
+            spans(&segment, &options),
+            [
+                (0, 12, ActivityKind::Stationary),
+                (12, 32, ActivityKind::Walking),
+                (32, 50, ActivityKind::Stationary),
+                (50, 70, ActivityKind::Walking),
+            ]
+        );
+        let walk = &segment.classify_activity(&options)[1];
+        let speed = walk.average_speed.unwrap();
+        assert!((speed - 1.345).abs() < 0.01, "{}", speed);
+
+        // Without smoothing, the brief stop is a span of its own.
+        let unsmoothed = ActivityOptions {
+            min_span_duration: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(
+            spans(&segment, &unsmoothed)[1..4],
+            [
+                (12, 22, ActivityKind::Walking),
+                (22, 23, ActivityKind::Stationary),
+                (23, 32, ActivityKind::Walking),
+            ]
+        );
+    }
+
+    #[test]
+    fn untimed_points_are_unknown() {
+        let mut segment = stop_and_go();
+        for point in &mut segment.points[40..=42] {
+            point.time = None;
+        }
+        assert_eq!(
+            spans(&segment, &ActivityOptions::default()),
+            [
+                (0, 12, ActivityKind::Stationary),
+                (12, 32, ActivityKind::Walking),
+                (32, 39, ActivityKind::Stationary),
+                (39, 43, ActivityKind::Unknown),
+                (43, 50, ActivityKind::Stationary),
+                (50, 70, ActivityKind::Walking),
+            ]
+        );
+        assert_eq!(
+            segment.classify_activity(&ActivityOptions::default())[3].average_speed,
+            None
+        );
+
+        segment.points.truncate(1);
+        assert_eq!(
+            spans(&segment, &ActivityOptions::default()),
+            [(0, 0, ActivityKind::Unknown)]
+        );
+    }
+
+    #[test]
+    fn split_track_by_activity() {
+        let mut track = Track::new();
+        track.name = Some("Commute".into());
+        track.segments.push(recording(&[(120, 6.0), (300, 15.0)]));
+        track.segments.push(stop_and_go());
+
+        let parts = track.split_by_activity(&ActivityOptions::default());
+        let kinds: Vec<ActivityKind> = parts.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ActivityKind::Cycling,
+                ActivityKind::Driving,
+                ActivityKind::Stationary,
+                ActivityKind::Walking,
+                ActivityKind::Stationary,
+                ActivityKind::Walking,
+            ]
+        );
+        let (_, driving) = &parts[1];
+        assert_eq!(driving.name.as_deref(), Some("Commute"));
+        assert_eq!(driving.segments.len(), 1);
+        assert_eq!(driving.segments[0].points.len(), 61);
+        assert_eq!(driving.segments[0].points[0], track.segments[0].points[24]);
+    }
+}
@@ -0,0 +1,472 @@
+//! annotations attaches data of the application to points, such as the road
+//! a point was matched to, and keeps it on the same points through the
+//! operations that remove points or split segments.
+
+use std::collections::HashMap;
+
+use crate::{ElementPath, Gpx, TrackSegment, Waypoint};
+
+/// Identifies a point of an [`AnnotatedGpx`] from the time the document was
+/// wrapped, whatever happens to its index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PointId(u64);
+
+/// A document with a value of type `T` attached to some of its points.
+///
+/// The values are kept aside, by [`PointId`], and only the operations of
+/// this type know how to keep them on their points. The document itself can
+/// only be read while it is wrapped; [`AnnotatedGpx::into_parts`] gives it
+/// back with the values by where their points ended up.
+///
+/// Operations that remove points return the values of the points they
+/// removed, by where the points were before.
+///
+/// ```
+/// use gpx::{AnnotatedGpx, ElementPath, Gpx, Track, TrackSegment, Waypoint};
+///
+/// let mut segment = TrackSegment::new();
+/// for x in [0.0, 0.0, 0.001].iter() {
+///     segment.points.push(Waypoint::new_lat_lon(47.0, 8.0 + x).unwrap());
+/// }
+/// let mut track = Track::new();
+/// track.segments.push(segment);
+/// let mut gpx = Gpx::default();
+/// gpx.tracks.push(track);
+///
+/// let mut annotated = AnnotatedGpx::new(gpx);
+/// annotated.annotate(ElementPath::TrackPoint(0, 0, 1), "gravel");
+/// annotated.annotate(ElementPath::TrackPoint(0, 0, 2), "asphalt");
+///
+/// // The second point repeats the first one.
+/// let dropped = annotated.dedup_exact(6);
+/// assert_eq!(dropped, [(ElementPath::TrackPoint(0, 0, 1), "gravel")]);
+/// assert_eq!(annotated.annotation(ElementPath::TrackPoint(0, 0, 1)), Some(&"asphalt"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct AnnotatedGpx<T> {
+    gpx: Gpx,
+    waypoints: Vec<PointId>,
+    routes: Vec<Vec<PointId>>,
+    tracks: Vec<Vec<Vec<PointId>>>,
+    annotations: HashMap<PointId, T>,
+    next_id: u64,
+}
+
+impl<T> AnnotatedGpx<T> {
+    /// Wraps `gpx`, giving every waypoint, route point and track point an
+    /// id, and no values.
+    pub fn new(gpx: Gpx) -> AnnotatedGpx<T> {
+        let mut annotated = AnnotatedGpx {
+            gpx: Gpx::default(),
+            waypoints: Vec::new(),
+            routes: Vec::new(),
+            tracks: Vec::new(),
+            annotations: HashMap::new(),
+            next_id: 0,
+        };
+        annotated.waypoints = annotated.new_ids(gpx.waypoints.len());
+        annotated.routes = gpx
+            .routes
+            .iter()
+            .map(|route| annotated.new_ids(route.points.len()))
+            .collect();
+        annotated.tracks = gpx
+            .tracks
+            .iter()
+            .map(|track| {
+                track
+                    .segments
+                    .iter()
+                    .map(|segment| annotated.new_ids(segment.points.len()))
+                    .collect()
+            })
+            .collect();
+        annotated.gpx = gpx;
+        annotated
+    }
+
+    fn new_ids(&mut self, count: usize) -> Vec<PointId> {
+        (0..count).map(|_| self.new_id()).collect()
+    }
+
+    fn new_id(&mut self) -> PointId {
+        self.next_id += 1;
+        PointId(self.next_id)
+    }
+
+    /// Gives the document.
+    pub fn gpx(&self) -> &Gpx {
+        &self.gpx
+    }
+
+    /// Gives the document back, and the values by where their points are
+    /// now, in document order.
+    pub fn into_parts(mut self) -> (Gpx, Vec<(ElementPath, T)>) {
+        let mut annotations = Vec::with_capacity(self.annotations.len());
+        for (path, id) in ids(&self.waypoints, &self.routes, &self.tracks) {
+            if let Some(value) = self.annotations.remove(&id) {
+                annotations.push((path, value));
+            }
+        }
+        (self.gpx, annotations)
+    }
+
+    /// Gives the id of the point at `path`, or nothing if `path` doesn't
+    /// lead to a waypoint, route point or track point.
+    pub fn point_id(&self, path: ElementPath) -> Option<PointId> {
+        match path {
+            ElementPath::Waypoint(w) => self.waypoints.get(w),
+            ElementPath::RoutePoint(r, p) => self.routes.get(r)?.get(p),
+            ElementPath::TrackPoint(t, s, p) => self.tracks.get(t)?.get(s)?.get(p),
+            _ => None,
+        }
+        .copied()
+    }
+
+    /// Gives where the point `id` is now, or nothing if it was removed.
+    pub fn path(&self, id: PointId) -> Option<ElementPath> {
+        ids(&self.waypoints, &self.routes, &self.tracks)
+            .find(|&(_, other)| other == id)
+            .map(|(path, _)| path)
+    }
+
+    /// Attaches `value` to the point at `path`, and returns the value it
+    /// had before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` doesn't lead to a waypoint, route point or track
+    /// point.
+    pub fn annotate(&mut self, path: ElementPath, value: T) -> Option<T> {
+        let id = self
+            .point_id(path)
+            .unwrap_or_else(|| panic!("there is no point at {}", path));
+        self.annotations.insert(id, value)
+    }
+
+    /// Gives the value of the point at `path`.
+    pub fn annotation(&self, path: ElementPath) -> Option<&T> {
+        self.annotations.get(&self.point_id(path)?)
+    }
+
+    /// Gives the value of the point at `path`, to change it.
+    pub fn annotation_mut(&mut self, path: ElementPath) -> Option<&mut T> {
+        let id = self.point_id(path)?;
+        self.annotations.get_mut(&id)
+    }
+
+    /// Takes the value off the point at `path`.
+    pub fn remove_annotation(&mut self, path: ElementPath) -> Option<T> {
+        let id = self.point_id(path)?;
+        self.annotations.remove(&id)
+    }
+
+    /// Gives the values by where their points are, in document order.
+    pub fn annotations(&self) -> impl Iterator<Item = (ElementPath, &T)> {
+        ids(&self.waypoints, &self.routes, &self.tracks)
+            .filter_map(move |(path, id)| Some((path, self.annotations.get(&id)?)))
+    }
+
+    /// Removes the points for which `keep` returns false, such as those
+    /// outside of an area to crop a document to, and returns their values.
+    /// `keep` is called with every waypoint, route point and track point,
+    /// in document order. Segments and routes left without points stay.
+    pub fn retain_points(
+        &mut self,
+        mut keep: impl FnMut(ElementPath, &Waypoint) -> bool,
+    ) -> Vec<(ElementPath, T)> {
+        let mut dropped = Vec::new();
+        let annotations = &mut self.annotations;
+        let mut retain = |points: &mut Vec<Waypoint>,
+                          ids: &mut Vec<PointId>,
+                          path: &dyn Fn(usize) -> ElementPath| {
+            let kept: Vec<bool> = points
+                .iter()
+                .enumerate()
+                .map(|(index, point)| keep(path(index), point))
+                .collect();
+            for (index, id) in ids.iter().enumerate() {
+                if !kept[index] {
+                    if let Some(value) = annotations.remove(id) {
+                        dropped.push((path(index), value));
+                    }
+                }
+            }
+            let mut mask = kept.iter();
+            points.retain(|_| *mask.next().unwrap());
+            let mut mask = kept.iter();
+            ids.retain(|_| *mask.next().unwrap());
+        };
+
+        retain(
+            &mut self.gpx.waypoints,
+            &mut self.waypoints,
+            &ElementPath::Waypoint,
+        );
+        for (r, (route, ids)) in self.gpx.routes.iter_mut().zip(&mut self.routes).enumerate() {
+            retain(&mut route.points, ids, &|p| ElementPath::RoutePoint(r, p));
+        }
+        for (t, (track, track_ids)) in self.gpx.tracks.iter_mut().zip(&mut self.tracks).enumerate()
+        {
+            for (s, (segment, ids)) in track.segments.iter_mut().zip(track_ids).enumerate() {
+                retain(&mut segment.points, ids, &|p| {
+                    ElementPath::TrackPoint(t, s, p)
+                });
+            }
+        }
+        dropped
+    }
+
+    /// Runs `f` on every track segment, and keeps the values on the points
+    /// `f` leaves, as for operations that only remove points.
+    ///
+    /// The points are followed by comparing them before and after, in
+    /// order: any point `f` adds or changes counts as new, without a value,
+    /// and the points it removes or changes have their values returned.
+    /// Fields that are NaN before and after count as unchanged. Of equal
+    /// points, the earlier ones are taken as the ones left, so if `f`
+    /// removes the first of two equal points, the value of the second is
+    /// returned and the first one's stays.
+    pub fn edit_segments(&mut self, mut f: impl FnMut(&mut TrackSegment)) -> Vec<(ElementPath, T)> {
+        let mut dropped = Vec::new();
+        for t in 0..self.gpx.tracks.len() {
+            for s in 0..self.gpx.tracks[t].segments.len() {
+                let segment = &mut self.gpx.tracks[t].segments[s];
+                let before = segment.points.clone();
+                f(segment);
+                let before_ids = std::mem::take(&mut self.tracks[t][s]);
+                let after = std::mem::take(&mut self.gpx.tracks[t].segments[s].points);
+                self.tracks[t][s] = self.follow(
+                    &before,
+                    &before_ids,
+                    &after,
+                    &mut |p| ElementPath::TrackPoint(t, s, p),
+                    &mut dropped,
+                );
+                self.gpx.tracks[t].segments[s].points = after;
+            }
+        }
+        dropped
+    }
+
+    /// Gives the ids of the points `after`, matching them in order with the
+    /// points `before` that have `ids`, and moves the values of the points
+    /// that didn't match into `dropped`.
+    fn follow(
+        &mut self,
+        before: &[Waypoint],
+        ids: &[PointId],
+        after: &[Waypoint],
+        path: &mut dyn FnMut(usize) -> ElementPath,
+        dropped: &mut Vec<(ElementPath, T)>,
+    ) -> Vec<PointId> {
+        let mut matched = vec![false; before.len()];
+        let mut next = 0;
+        let mut after_ids = Vec::with_capacity(after.len());
+        for point in after {
+            match (next..before.len()).find(|&index| same_point(&before[index], point)) {
+                Some(index) => {
+                    matched[index] = true;
+                    after_ids.push(ids[index]);
+                    next = index + 1;
+                }
+                None => after_ids.push(self.new_id()),
+            }
+        }
+        for (index, &id) in ids.iter().enumerate() {
+            if !matched[index] {
+                if let Some(value) = self.annotations.remove(&id) {
+                    dropped.push((path(index), value));
+                }
+            }
+        }
+        after_ids
+    }
+
+    /// Removes spikes from every track segment, see
+    /// [`TrackSegment::remove_spikes`], and returns the values of the
+    /// removed points.
+    pub fn remove_spikes(&mut self, max_speed_mps: f64) -> Vec<(ElementPath, T)> {
+        self.edit_segments(|segment| {
+            segment.remove_spikes(max_speed_mps);
+        })
+    }
+
+    /// Removes spikes from every track segment, see
+    /// [`TrackSegment::remove_spikes_by_distance`], and returns the values
+    /// of the removed points.
+    pub fn remove_spikes_by_distance(&mut self, max_jump_m: f64) -> Vec<(ElementPath, T)> {
+        self.edit_segments(|segment| {
+            segment.remove_spikes_by_distance(max_jump_m);
+        })
+    }
+
+    /// Removes repeated points from every track segment, see
+    /// [`TrackSegment::dedup_exact`], and returns the values of the removed
+    /// points.
+    pub fn dedup_exact(&mut self, precision: u32) -> Vec<(ElementPath, T)> {
+        self.edit_segments(|segment| {
+            segment.dedup_exact(precision);
+        })
+    }
+
+    /// Splits a segment of the track at `track` in two, see
+    /// [`Track::split_segment`](crate::Track::split_segment). The values
+    /// move with their points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such track or segment, or if `index` is greater
+    /// than the number of points of the segment.
+    pub fn split_segment(&mut self, track: usize, segment: usize, index: usize) {
+        self.gpx.tracks[track].split_segment(segment, index);
+        let ids = &mut self.tracks[track];
+        let second = ids[segment].split_off(index);
+        ids.insert(segment + 1, second);
+    }
+}
+
+/// Whether `a` and `b` are equal, counting fields that are NaN in both as
+/// equal, which `==` doesn't.
+fn same_point(a: &Waypoint, b: &Waypoint) -> bool {
+    a == b || (a.point() == b.point() && format!("{:?}", a) == format!("{:?}", b))
+}
+
+/// Gives the paths and ids of all points, in document order.
+fn ids<'a>(
+    waypoints: &'a [PointId],
+    routes: &'a [Vec<PointId>],
+    tracks: &'a [Vec<Vec<PointId>>],
+) -> impl Iterator<Item = (ElementPath, PointId)> + 'a {
+    let waypoints = waypoints
+        .iter()
+        .enumerate()
+        .map(|(w, &id)| (ElementPath::Waypoint(w), id));
+    let routes = routes.iter().enumerate().flat_map(|(r, ids)| {
+        ids.iter()
+            .enumerate()
+            .map(move |(p, &id)| (ElementPath::RoutePoint(r, p), id))
+    });
+    let tracks = tracks.iter().enumerate().flat_map(|(t, segments)| {
+        segments.iter().enumerate().flat_map(move |(s, ids)| {
+            ids.iter()
+                .enumerate()
+                .map(move |(p, &id)| (ElementPath::TrackPoint(t, s, p), id))
+        })
+    });
+    waypoints.chain(routes).chain(tracks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::AnnotatedGpx;
+    use crate::{read, ElementPath, Gpx, Waypoint};
+
+    /// Annotates every 10th track point with its coordinates.
+    fn annotated() -> AnnotatedGpx<(f64, f64)> {
+        let gpx = read(File::open("tests/fixtures/garmin-activity.gpx").unwrap()).unwrap();
+        let mut annotated = AnnotatedGpx::new(gpx);
+        let points = annotated.gpx().tracks[0].segments[0].points.clone();
+        for (p, point) in points.iter().enumerate().step_by(10) {
+            annotated.annotate(ElementPath::TrackPoint(0, 0, p), (point.lat(), point.lon()));
+        }
+        annotated
+    }
+
+    fn point(gpx: &Gpx, path: ElementPath) -> &Waypoint {
+        match path {
+            ElementPath::Waypoint(w) => &gpx.waypoints[w],
+            ElementPath::RoutePoint(r, p) => &gpx.routes[r].points[p],
+            ElementPath::TrackPoint(t, s, p) => &gpx.tracks[t].segments[s].points[p],
+            _ => unreachable!(),
+        }
+    }
+
+    /// Checks that every value is on a point at its coordinates, and that
+    /// `dropped` and the values left make up the 99 attached.
+    fn assert_on_their_points(annotated: &AnnotatedGpx<(f64, f64)>, dropped: usize) {
+        let mut left = 0;
+        for (path, &(lat, lon)) in annotated.annotations() {
+            let point = point(annotated.gpx(), path);
+            assert_eq!((point.lat(), point.lon()), (lat, lon), "{}", path);
+            left += 1;
+        }
+        assert_eq!(left + dropped, 99);
+    }
+
+    #[test]
+    fn annotations_follow_removed_points() {
+        let mut annotated = annotated();
+        // Thin the track out, as simplifying it would.
+        let dropped = annotated.edit_segments(|segment| {
+            let mut index = 0;
+            segment.points.retain(|_| {
+                index += 1;
+                index % 3 == 1
+            });
+        });
+        assert_eq!(annotated.gpx().tracks[0].segments[0].points.len(), 330);
+        assert_eq!(dropped.len(), 66);
+        assert_eq!(dropped[0].0, ElementPath::TrackPoint(0, 0, 10));
+        assert_on_their_points(&annotated, dropped.len());
+
+        let mut all_dropped = dropped.len();
+        all_dropped += annotated.remove_spikes(3.0).len();
+        assert!(annotated.gpx().tracks[0].segments[0].points.len() < 330);
+        assert_on_their_points(&annotated, all_dropped);
+
+        // Changed points lose their values.
+        let first = ElementPath::TrackPoint(0, 0, 0);
+        let dropped = annotated.edit_segments(|segment| segment.points[0].elevation = None);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].0, first);
+        assert_eq!(annotated.annotation(first), None);
+        assert_on_their_points(&annotated, all_dropped + 1);
+    }
+
+    #[test]
+    fn annotations_follow_points_with_nan() {
+        let mut annotated = annotated();
+        let path = ElementPath::TrackPoint(0, 0, 10);
+        annotated.edit_segments(|segment| segment.points[10].elevation = Some(f64::NAN));
+        annotated.annotate(path, (0.0, 0.0));
+
+        let dropped = annotated.edit_segments(|segment| {
+            segment.points.remove(0);
+        });
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(
+            annotated.annotation(ElementPath::TrackPoint(0, 0, 9)),
+            Some(&(0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn annotations_follow_crop_and_split() {
+        let mut annotated = annotated();
+        let id = annotated
+            .point_id(ElementPath::TrackPoint(0, 0, 500))
+            .unwrap();
+        let dropped = annotated.retain_points(|path, _| match path {
+            ElementPath::TrackPoint(_, _, p) => p >= 300,
+            _ => true,
+        });
+        assert_eq!(dropped.len(), 30);
+        assert_eq!(dropped[0].0, ElementPath::TrackPoint(0, 0, 0));
+        assert_eq!(annotated.path(id), Some(ElementPath::TrackPoint(0, 0, 200)));
+        assert_on_their_points(&annotated, 30);
+
+        annotated.split_segment(0, 0, 100);
+        assert_eq!(annotated.path(id), Some(ElementPath::TrackPoint(0, 1, 100)));
+        assert!(annotated
+            .annotation(ElementPath::TrackPoint(0, 1, 100))
+            .is_some());
+        assert_on_their_points(&annotated, 30);
+
+        let (gpx, annotations) = annotated.into_parts();
+        assert_eq!(annotations.len(), 69);
+        assert_eq!(gpx.tracks[0].segments.len(), 2);
+    }
+}
@@ -0,0 +1,284 @@
+//! assembler builds a document from batches of track points that arrive
+//! in any order, such as from the queues of several devices.
+
+use std::collections::BTreeMap;
+
+use time::{Duration, OffsetDateTime};
+
+use crate::errors::{GpxError, GpxResult};
+use crate::{Gpx, GpxVersion, Metadata, Time, Track, TrackSegment, Waypoint};
+
+/// Puts points into tracks as they come, keeping each track in order of
+/// time, and gives the document once all points are in.
+///
+/// The tracks are told apart by a key, which becomes their name. The
+/// assembler is [`Send`], so batches from several threads can be added to
+/// one behind a [`Mutex`](std::sync::Mutex).
+///
+/// ```
+/// use gpx::{GpxAssembler, GpxVersion, Waypoint};
+/// use time::{Duration, OffsetDateTime};
+///
+/// let start = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+/// let point = |seconds: i64| {
+///     let mut point = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+///     point.time = Some((start + Duration::seconds(seconds)).into());
+///     point
+/// };
+///
+/// let mut assembler = GpxAssembler::new(GpxVersion::Gpx11);
+/// assembler.add_points("bike", vec![point(20), point(30)]);
+/// assembler.add_points("bike", vec![point(0), point(10)]);
+/// let gpx = assembler.finish().unwrap();
+///
+/// let track = &gpx.tracks[0];
+/// assert_eq!(track.name.as_deref(), Some("bike"));
+/// assert_eq!(track.segments[0].points[0].time, Some(start.into()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GpxAssembler {
+    version: GpxVersion,
+    metadata: Option<Metadata>,
+    segment_gap: Option<Duration>,
+    tracks: BTreeMap<String, Points>,
+}
+
+/// The points of a track, with the time each is ordered by: its own, or
+/// that of the last point before it with a time. These never go down.
+#[derive(Clone, Debug, Default)]
+struct Points {
+    points: Vec<Waypoint>,
+    keys: Vec<Option<Time>>,
+}
+
+impl GpxAssembler {
+    /// Creates an assembler for a document of `version`, without tracks.
+    /// Segments are split at gaps of more than a minute.
+    pub fn new(version: GpxVersion) -> GpxAssembler {
+        GpxAssembler {
+            version,
+            metadata: None,
+            segment_gap: Some(Duration::minutes(1)),
+            tracks: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the metadata of the document.
+    pub fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = Some(metadata);
+    }
+
+    /// Sets the longest time between consecutive points of a segment. A
+    /// longer gap starts a new segment; `None` keeps each track in one
+    /// segment.
+    pub fn set_segment_gap(&mut self, gap: Option<Duration>) {
+        self.segment_gap = gap;
+    }
+
+    /// Adds `points` to the track of `track_key`, each in order of its
+    /// time. Points with the same time keep the order they were added in,
+    /// and points without a time go after the points added before them.
+    ///
+    /// Points that come after all others are added at the end right away,
+    /// and other points are found a place by searching back from the end,
+    /// so that input in mostly the right order is quick to add.
+    pub fn add_points(&mut self, track_key: &str, points: Vec<Waypoint>) {
+        let track = match self.tracks.get_mut(track_key) {
+            Some(track) => track,
+            None => self.tracks.entry(track_key.to_owned()).or_default(),
+        };
+        for point in points {
+            track.insert(point);
+        }
+    }
+
+    /// Gives the document, with the tracks ordered by the time of their
+    /// first point and the tracks without times last, by key.
+    ///
+    /// Fails with [`GpxError::UnknownVersionError`] if the assembler was
+    /// created for [`GpxVersion::Unknown`].
+    pub fn finish(self) -> GpxResult<Gpx> {
+        if self.version == GpxVersion::Unknown {
+            return Err(GpxError::UnknownVersionError(self.version));
+        }
+        let segment_gap = self.segment_gap;
+        let mut tracks: Vec<(Option<Time>, Track)> = self
+            .tracks
+            .into_iter()
+            .map(|(key, points)| {
+                let start = points.points.iter().find_map(|point| point.time);
+                let mut track = Track::new();
+                track.name = Some(key);
+                track.segments = segments(points.points, segment_gap);
+                (start, track)
+            })
+            .collect();
+        // Stable, so that tracks with the same start stay ordered by key.
+        tracks.sort_by_key(|(start, _)| (start.is_none(), *start));
+        Ok(Gpx {
+            version: self.version,
+            metadata: self.metadata,
+            tracks: tracks.into_iter().map(|(_, track)| track).collect(),
+            ..Default::default()
+        })
+    }
+}
+
+impl Points {
+    fn insert(&mut self, point: Waypoint) {
+        let time = match point.time {
+            Some(time) => time,
+            None => {
+                let key = self.keys.last().copied().flatten();
+                self.points.push(point);
+                self.keys.push(key);
+                return;
+            }
+        };
+        let index = self.insertion_index(time);
+        self.points.insert(index, point);
+        self.keys.insert(index, Some(time));
+    }
+
+    /// Gives the index after the last point ordered at or before `time`.
+    fn insertion_index(&self, time: Time) -> usize {
+        let key = Some(time);
+        // Everything from `high` on is ordered after `time`. Steps back
+        // from the end double, so finding a place `n` points back takes
+        // about `2 log n` comparisons.
+        let mut high = self.keys.len();
+        let mut step = 1;
+        let low = loop {
+            let low = high.saturating_sub(step);
+            if low == 0 || self.keys[low] <= key {
+                break low;
+            }
+            high = low;
+            step *= 2;
+        };
+        low + self.keys[low..high].partition_point(|other| *other <= key)
+    }
+}
+
+/// Splits `points` into segments wherever consecutive points are more than
+/// `gap` apart.
+fn segments(points: Vec<Waypoint>, gap: Option<Duration>) -> Vec<TrackSegment> {
+    let mut segments = vec![TrackSegment::new()];
+    let mut previous: Option<Time> = None;
+    for point in points {
+        if let (Some(gap), Some(previous), Some(time)) = (gap, previous, point.time) {
+            let elapsed = OffsetDateTime::from(time) - OffsetDateTime::from(previous);
+            if elapsed > gap {
+                segments.push(TrackSegment::new());
+            }
+        }
+        previous = point.time.or(previous);
+        segments
+            .last_mut()
+            .expect("there is a segment")
+            .points
+            .push(point);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use super::GpxAssembler;
+    use crate::{GpxVersion, Metadata, Waypoint};
+
+    fn point(seconds: i64) -> Waypoint {
+        let start = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let mut point = Waypoint::new_lat_lon(47.0 + seconds as f64 * 1e-5, 8.0).unwrap();
+        point.time = Some((start + Duration::seconds(seconds)).into());
+        point
+    }
+
+    fn times(points: &[Waypoint]) -> Vec<i64> {
+        points
+            .iter()
+            .map(|point| OffsetDateTime::from(point.time.unwrap()).unix_timestamp() - 1_700_000_000)
+            .collect()
+    }
+
+    /// Batches of ten points for two devices, the second starting earlier.
+    fn batches() -> Vec<(&'static str, Vec<Waypoint>)> {
+        let mut batches = Vec::new();
+        for batch in 0..20 {
+            let points = (0..10).map(|i| point(batch * 100 + i * 10)).collect();
+            batches.push(("a", points));
+            let points = (0..10).map(|i| point(batch * 50 + i * 5 - 500)).collect();
+            batches.push(("b", points));
+        }
+        batches
+    }
+
+    #[test]
+    fn shuffled_batches_give_the_same_document() {
+        let metadata = Metadata {
+            name: Some("Fleet".to_string()),
+            ..Default::default()
+        };
+        let assemble = |batches: Vec<(&str, Vec<Waypoint>)>| {
+            let mut assembler = GpxAssembler::new(GpxVersion::Gpx11);
+            assembler.set_metadata(metadata.clone());
+            for (key, points) in batches {
+                assembler.add_points(key, points);
+            }
+            assembler.finish().unwrap()
+        };
+
+        let in_order = assemble(batches());
+        // A fixed shuffle: every seventh batch, wrapping around, with the
+        // points of each batch reversed.
+        let mut shuffled = batches();
+        let count = shuffled.len();
+        let mut order = Vec::new();
+        for i in 0..count {
+            let (key, mut points) = std::mem::take(&mut shuffled[i * 7 % count]);
+            points.reverse();
+            order.push((key, points));
+        }
+        assert_eq!(assemble(order), in_order);
+
+        let names: Vec<&str> = in_order
+            .tracks
+            .iter()
+            .map(|track| track.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["b", "a"]);
+        let a = &in_order.tracks[1].segments;
+        assert_eq!(a.len(), 1);
+        assert_eq!(
+            times(&a[0].points),
+            (0..200).map(|i| i * 10).collect::<Vec<_>>()
+        );
+        assert_eq!(in_order.metadata, Some(metadata));
+    }
+
+    #[test]
+    fn gaps_split_segments() {
+        let mut assembler = GpxAssembler::new(GpxVersion::Gpx11);
+        assembler.add_points("a", vec![point(600), point(0), point(60), point(61)]);
+        let mut untimed = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+        untimed.name = Some("untimed".to_string());
+        assembler.add_points("a", vec![untimed, point(650), point(300)]);
+        assembler.add_points("c", vec![Waypoint::new_lat_lon(47.0, 8.0).unwrap()]);
+        let gpx = assembler.clone().finish().unwrap();
+
+        let segments = &gpx.tracks[0].segments;
+        assert_eq!(segments.len(), 3);
+        assert_eq!(times(&segments[0].points), [0, 60, 61]);
+        assert_eq!(times(&segments[1].points), [300]);
+        assert_eq!(segments[2].points.len(), 3);
+        assert_eq!(segments[2].points[1].name.as_deref(), Some("untimed"));
+        assert_eq!(gpx.tracks[1].name.as_deref(), Some("c"));
+
+        assembler.set_segment_gap(Some(Duration::minutes(10)));
+        let gpx = assembler.clone().finish().unwrap();
+        assert_eq!(gpx.tracks[0].segments.len(), 1);
+        assert!(GpxAssembler::new(GpxVersion::Unknown).finish().is_err());
+    }
+}
@@ -0,0 +1,433 @@
+//! capabilities sums up which optional parts of GPX a document uses, and
+//! checks them against the limits of a device that is to load it.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Gpx, GpxVersion, Waypoint};
+
+/// Whether all, some or none of a number of points have a field.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub enum Presence {
+    /// Every point has the field.
+    All,
+    /// Some points have the field, others do not.
+    Some,
+    /// No point has the field, or there are no points.
+    #[default]
+    None,
+}
+
+impl Presence {
+    fn of(present: usize, points: usize) -> Presence {
+        if present == 0 {
+            Presence::None
+        } else if present == points {
+            Presence::All
+        } else {
+            Presence::Some
+        }
+    }
+}
+
+/// Which elements of a document have `<extensions>`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct ExtensionUse {
+    pub document: bool,
+    pub metadata: bool,
+    pub waypoints: bool,
+    pub routes: bool,
+    pub route_points: bool,
+    pub tracks: bool,
+    pub track_segments: bool,
+    pub track_points: bool,
+}
+
+impl ExtensionUse {
+    /// Whether any element has extensions.
+    pub fn any(&self) -> bool {
+        self.document
+            || self.metadata
+            || self.waypoints
+            || self.routes
+            || self.route_points
+            || self.tracks
+            || self.track_segments
+            || self.track_points
+    }
+}
+
+/// The optional parts of GPX a document uses, as given by
+/// [`Gpx::capabilities`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct GpxCapabilities {
+    /// The version of the document.
+    pub version: GpxVersion,
+    /// The number of waypoints.
+    pub waypoints: usize,
+    /// The number of routes.
+    pub routes: usize,
+    /// The number of tracks.
+    pub tracks: usize,
+    /// The number of waypoints, route points and track points.
+    pub points: usize,
+    /// The number of points of the longest route.
+    pub max_route_points: usize,
+    /// The number of points of the longest track segment.
+    pub max_segment_points: usize,
+    /// Which elements have extensions.
+    pub extensions: ExtensionUse,
+    /// How many of the waypoints, route points and track points have a
+    /// time.
+    pub times: Presence,
+    /// How many of the waypoints, route points and track points have an
+    /// elevation.
+    pub elevations: Presence,
+    /// The length in characters of the longest name of the metadata, a
+    /// waypoint, route, track or point.
+    pub max_name_length: usize,
+    /// The distinct symbols of the waypoints, route points and track points.
+    pub symbols: BTreeSet<String>,
+}
+
+impl GpxCapabilities {
+    /// Whether any point has a symbol.
+    pub fn uses_symbols(&self) -> bool {
+        !self.symbols.is_empty()
+    }
+
+    /// Gives everything about the document that `profile` does not allow, in
+    /// the order of the fields of [`DeviceProfile`].
+    pub fn check(&self, profile: &DeviceProfile) -> Vec<Incompatibility> {
+        let mut found = Vec::new();
+        let limits = [
+            (self.waypoints, profile.max_waypoints, Limit::Waypoints),
+            (self.routes, profile.max_routes, Limit::Routes),
+            (self.tracks, profile.max_tracks, Limit::Tracks),
+            (
+                self.max_route_points,
+                profile.max_route_points,
+                Limit::RoutePoints,
+            ),
+            (
+                self.max_segment_points,
+                profile.max_segment_points,
+                Limit::SegmentPoints,
+            ),
+            (
+                self.max_name_length,
+                profile.max_name_length,
+                Limit::NameLength,
+            ),
+        ];
+        for &(value, limit, kind) in limits.iter() {
+            if let Some(limit) = limit.filter(|&limit| value > limit) {
+                found.push(Incompatibility::Exceeded { kind, value, limit });
+            }
+        }
+        if !profile.allows_routes && self.routes > 0 {
+            found.push(Incompatibility::RoutesNotAllowed);
+        }
+        if !profile.allows_extensions && self.extensions.any() {
+            found.push(Incompatibility::ExtensionsNotAllowed);
+        }
+        if profile.requires_times && self.points > 0 && self.times != Presence::All {
+            found.push(Incompatibility::MissingTimes);
+        }
+        if profile.requires_elevations && self.points > 0 && self.elevations != Presence::All {
+            found.push(Incompatibility::MissingElevations);
+        }
+        if let Some(allowed) = &profile.symbols {
+            let unknown: Vec<String> = self
+                .symbols
+                .iter()
+                .filter(|symbol| !allowed.contains(symbol))
+                .cloned()
+                .collect();
+            if !unknown.is_empty() {
+                found.push(Incompatibility::UnsupportedSymbols(unknown));
+            }
+        }
+        found
+    }
+}
+
+/// The limits of a device, for [`GpxCapabilities::check`]. By default
+/// there are none.
+///
+/// ```
+/// use gpx::DeviceProfile;
+///
+/// // A watch that holds one track of up to 10000 points per segment.
+/// let watch = DeviceProfile {
+///     max_tracks: Some(1),
+///     max_segment_points: Some(10_000),
+///     allows_routes: false,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceProfile {
+    pub max_waypoints: Option<usize>,
+    pub max_routes: Option<usize>,
+    pub max_tracks: Option<usize>,
+    pub max_route_points: Option<usize>,
+    pub max_segment_points: Option<usize>,
+    /// The longest name in characters.
+    pub max_name_length: Option<usize>,
+    pub allows_routes: bool,
+    pub allows_extensions: bool,
+    /// Whether every point needs a time.
+    pub requires_times: bool,
+    /// Whether every point needs an elevation.
+    pub requires_elevations: bool,
+    /// The symbols the device knows, or `None` if any will do.
+    pub symbols: Option<Vec<String>>,
+}
+
+impl Default for DeviceProfile {
+    fn default() -> DeviceProfile {
+        DeviceProfile {
+            max_waypoints: None,
+            max_routes: None,
+            max_tracks: None,
+            max_route_points: None,
+            max_segment_points: None,
+            max_name_length: None,
+            allows_routes: true,
+            allows_extensions: true,
+            requires_times: false,
+            requires_elevations: false,
+            symbols: None,
+        }
+    }
+}
+
+/// What a limit of a [`DeviceProfile`] is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Limit {
+    Waypoints,
+    Routes,
+    Tracks,
+    RoutePoints,
+    SegmentPoints,
+    NameLength,
+}
+
+/// Something about a document a [`DeviceProfile`] does not allow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Incompatibility {
+    /// The document has `value` where the device allows at most `limit`.
+    Exceeded {
+        kind: Limit,
+        value: usize,
+        limit: usize,
+    },
+    RoutesNotAllowed,
+    ExtensionsNotAllowed,
+    /// Some points have no time.
+    MissingTimes,
+    /// Some points have no elevation.
+    MissingElevations,
+    /// The symbols the device does not know.
+    UnsupportedSymbols(Vec<String>),
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Incompatibility::Exceeded { kind, value, limit } => {
+                let what = match kind {
+                    Limit::Waypoints => "waypoints",
+                    Limit::Routes => "routes",
+                    Limit::Tracks => "tracks",
+                    Limit::RoutePoints => "points in a route",
+                    Limit::SegmentPoints => "points in a track segment",
+                    Limit::NameLength => "characters in a name",
+                };
+                write!(f, "{} {}, more than the {} allowed", value, what, limit)
+            }
+            Incompatibility::RoutesNotAllowed => f.write_str("routes are not allowed"),
+            Incompatibility::ExtensionsNotAllowed => f.write_str("extensions are not allowed"),
+            Incompatibility::MissingTimes => f.write_str("some points have no time"),
+            Incompatibility::MissingElevations => f.write_str("some points have no elevation"),
+            Incompatibility::UnsupportedSymbols(symbols) => {
+                write!(f, "unsupported symbols: {}", symbols.join(", "))
+            }
+        }
+    }
+}
+
+impl Gpx {
+    /// Sums up which optional parts of GPX the document uses, in one pass
+    /// over it.
+    pub fn capabilities(&self) -> GpxCapabilities {
+        let mut capabilities = GpxCapabilities {
+            version: self.version,
+            waypoints: self.waypoints.len(),
+            routes: self.routes.len(),
+            tracks: self.tracks.len(),
+            ..Default::default()
+        };
+        let mut counter = PointCounter::default();
+        let extensions = &mut capabilities.extensions;
+        extensions.document = self.extensions.is_some();
+        if let Some(metadata) = &self.metadata {
+            extensions.metadata = metadata.extensions.is_some();
+            counter.name(&metadata.name);
+        }
+        for waypoint in &self.waypoints {
+            extensions.waypoints |= waypoint.extras().extensions.is_some();
+            counter.point(waypoint);
+        }
+        for route in &self.routes {
+            extensions.routes |= route.extensions.is_some();
+            counter.name(&route.name);
+            capabilities.max_route_points = capabilities.max_route_points.max(route.points.len());
+            for point in &route.points {
+                extensions.route_points |= point.extras().extensions.is_some();
+                counter.point(point);
+            }
+        }
+        for track in &self.tracks {
+            extensions.tracks |= track.extensions.is_some();
+            counter.name(&track.name);
+            for segment in &track.segments {
+                extensions.track_segments |= segment.extensions.is_some();
+                capabilities.max_segment_points =
+                    capabilities.max_segment_points.max(segment.points.len());
+                for point in &segment.points {
+                    extensions.track_points |= point.extras().extensions.is_some();
+                    counter.point(point);
+                }
+            }
+        }
+
+        capabilities.points = counter.points;
+        capabilities.times = Presence::of(counter.times, counter.points);
+        capabilities.elevations = Presence::of(counter.elevations, counter.points);
+        capabilities.max_name_length = counter.max_name_length;
+        capabilities.symbols = counter.symbols;
+        capabilities
+    }
+}
+
+#[derive(Default)]
+struct PointCounter {
+    points: usize,
+    times: usize,
+    elevations: usize,
+    max_name_length: usize,
+    symbols: BTreeSet<String>,
+}
+
+impl PointCounter {
+    fn point(&mut self, point: &Waypoint) {
+        self.points += 1;
+        self.times += point.time.is_some() as usize;
+        self.elevations += point.elevation.is_some() as usize;
+        self.name(&point.name);
+        if let Some(symbol) = &point.extras().symbol {
+            if !self.symbols.contains(symbol) {
+                self.symbols.insert(symbol.clone());
+            }
+        }
+    }
+
+    fn name(&mut self, name: &Option<String>) {
+        if let Some(name) = name {
+            self.max_name_length = self.max_name_length.max(name.chars().count());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use super::{DeviceProfile, Incompatibility, Limit, Presence};
+    use crate::{read, Gpx, GpxVersion};
+
+    fn fixture() -> Gpx {
+        let file = File::open("tests/fixtures/field_coverage.gpx").unwrap();
+        read(BufReader::new(file)).unwrap()
+    }
+
+    /// A small handheld with room for 50 points per segment and short
+    /// names, which needs times and cannot show routes or extensions.
+    fn tiny_handheld() -> DeviceProfile {
+        DeviceProfile {
+            max_waypoints: Some(10),
+            max_segment_points: Some(50),
+            max_name_length: Some(8),
+            allows_routes: false,
+            allows_extensions: false,
+            requires_times: true,
+            symbols: Some(vec!["Flag, Blue".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn capabilities_of_fixture() {
+        let capabilities = fixture().capabilities();
+        assert_eq!(capabilities.version, GpxVersion::Gpx11);
+        assert_eq!(
+            (
+                capabilities.waypoints,
+                capabilities.routes,
+                capabilities.tracks
+            ),
+            (3, 1, 1)
+        );
+        assert_eq!(capabilities.points, 107);
+        assert_eq!(capabilities.max_route_points, 4);
+        assert_eq!(capabilities.max_segment_points, 100);
+        assert_eq!(capabilities.times, Presence::Some);
+        assert_eq!(capabilities.elevations, Presence::Some);
+        assert_eq!(capabilities.max_name_length, "Waypoint 0".len());
+        assert!(!capabilities.uses_symbols());
+        assert!(capabilities.extensions.track_points);
+        assert!(!capabilities.extensions.waypoints);
+
+        let empty = Gpx::default().capabilities();
+        assert_eq!(empty.times, Presence::None);
+        assert!(empty.check(&tiny_handheld()).is_empty());
+    }
+
+    #[test]
+    fn check_against_tiny_profile() {
+        let mut gpx = fixture();
+        gpx.waypoints[0].extras_mut().symbol = Some("Flag, Blue".to_string());
+        gpx.waypoints[1].extras_mut().symbol = Some("Summit".to_string());
+
+        let capabilities = gpx.capabilities();
+        assert_eq!(
+            capabilities.check(&tiny_handheld()),
+            vec![
+                Incompatibility::Exceeded {
+                    kind: Limit::SegmentPoints,
+                    value: 100,
+                    limit: 50,
+                },
+                Incompatibility::Exceeded {
+                    kind: Limit::NameLength,
+                    value: 10,
+                    limit: 8,
+                },
+                Incompatibility::RoutesNotAllowed,
+                Incompatibility::ExtensionsNotAllowed,
+                Incompatibility::MissingTimes,
+                Incompatibility::UnsupportedSymbols(vec!["Summit".to_string()]),
+            ]
+        );
+        assert!(capabilities.check(&DeviceProfile::default()).is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+//! chrono_time gives access to timestamps as chrono types.
+
+use std::convert::TryFrom;
+
+use chrono::{DateTime, Utc};
+
+use crate::errors::GpxResult;
+use crate::{Metadata, Time, Waypoint};
+
+impl Waypoint {
+    /// Gives the time of the waypoint as a chrono `DateTime`, keeping
+    /// fractions of a second.
+    pub fn time_chrono(&self) -> Option<DateTime<Utc>> {
+        self.time.map(DateTime::from)
+    }
+
+    /// Sets the time of the waypoint from a chrono `DateTime`, see
+    /// [`Time`]'s `TryFrom` conversion for the times that can't be stored.
+    pub fn set_time_chrono(&mut self, time: Option<DateTime<Utc>>) -> GpxResult<()> {
+        self.time = time.map(Time::try_from).transpose()?;
+        Ok(())
+    }
+}
+
+impl Metadata {
+    /// Gives the creation time of the file as a chrono `DateTime`, keeping
+    /// fractions of a second.
+    pub fn time_chrono(&self) -> Option<DateTime<Utc>> {
+        self.time.map(DateTime::from)
+    }
+
+    /// Sets the creation time of the file from a chrono `DateTime`, see
+    /// [`Time`]'s `TryFrom` conversion for the times that can't be stored.
+    pub fn set_time_chrono(&mut self, time: Option<DateTime<Utc>>) -> GpxResult<()> {
+        self.time = time.map(Time::try_from).transpose()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use chrono::{NaiveDate, TimeZone, Timelike, Utc};
+
+    use crate::{read, write_to_string};
+
+    const GPX: &str = r#"<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+        <metadata><time>2021-03-04T05:06:07.123Z</time></metadata>
+        <wpt lat="1.0" lon="2.0"><time>2021-03-04T05:06:08.456Z</time></wpt>
+    </gpx>"#;
+
+    #[test]
+    fn time_chrono_keeps_milliseconds() {
+        let gpx = read(BufReader::new(GPX.as_bytes())).unwrap();
+
+        let time = gpx.metadata.as_ref().unwrap().time_chrono().unwrap();
+        assert_eq!(
+            time,
+            Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap()
+                + chrono::Duration::milliseconds(123)
+        );
+        let time = gpx.waypoints[0].time_chrono().unwrap();
+        assert_eq!(time.nanosecond(), 456_000_000);
+    }
+
+    #[test]
+    fn set_time_chrono_round_trip() {
+        let original = read(BufReader::new(GPX.as_bytes())).unwrap();
+        let mut gpx = original.clone();
+
+        let metadata = gpx.metadata.as_mut().unwrap();
+        metadata.set_time_chrono(metadata.time_chrono()).unwrap();
+        let waypoint = &mut gpx.waypoints[0];
+        waypoint.set_time_chrono(waypoint.time_chrono()).unwrap();
+
+        assert_eq!(gpx, original);
+        assert_eq!(
+            write_to_string(&gpx).unwrap(),
+            write_to_string(&original).unwrap()
+        );
+        let written = read(BufReader::new(write_to_string(&gpx).unwrap().as_bytes())).unwrap();
+        assert_eq!(written.waypoints[0].time, original.waypoints[0].time);
+    }
+
+    #[test]
+    fn set_time_chrono_out_of_range() {
+        let far_future = NaiveDate::from_ymd_opt(20_000, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let mut gpx = read(BufReader::new(GPX.as_bytes())).unwrap();
+        assert!(gpx.waypoints[0].set_time_chrono(Some(far_future)).is_err());
+        assert!(gpx.waypoints[0].time.is_some());
+
+        gpx.waypoints[0].set_time_chrono(None).unwrap();
+        assert_eq!(gpx.waypoints[0].time, None);
+    }
+}
@@ -0,0 +1,322 @@
+//! cluster groups waypoints that lie close together, to declutter a map
+//! that would otherwise show thousands of them.
+//!
+//! The clusters are found like DBSCAN does: a point with at least
+//! [`ClusterOptions::min_points`] points within
+//! [`ClusterOptions::eps_m`] of it, counting itself, starts a cluster, which
+//! grows by the points within reach of its members, as long as those have
+//! enough neighbours in turn. Points that no cluster reaches are noise.
+//!
+//! ```
+//! use gpx::cluster::{cluster_waypoints, ClusterOptions};
+//! use gpx::Waypoint;
+//!
+//! let points: Vec<Waypoint> = [(47.0, 8.0), (47.0001, 8.0), (47.0, 8.0001), (48.0, 9.0)]
+//!     .iter()
+//!     .map(|&(lat, lon)| Waypoint::new_lat_lon(lat, lon).unwrap())
+//!     .collect();
+//! let clusters = cluster_waypoints(&points, &ClusterOptions::default());
+//! assert_eq!(clusters.len(), 2);
+//! assert_eq!(clusters[0].members, [0, 1, 2]);
+//! // The lone point is noise, kept as a cluster of its own.
+//! assert_eq!(clusters[1].members, [3]);
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use geo_types::{coord, Point, Rect};
+
+use crate::distance::{haversine, EARTH_RADIUS_M};
+use crate::{Gpx, Waypoint};
+
+/// Options for [`cluster_waypoints`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClusterOptions {
+    /// How close, in meters, points have to be to count as neighbours.
+    pub eps_m: f64,
+
+    /// The number of neighbours a point needs to start or grow a cluster,
+    /// counting itself.
+    pub min_points: usize,
+
+    /// Whether to give each noise point as a cluster of its own, after the
+    /// other clusters. Otherwise noise is left out, and [`unclustered`]
+    /// gives it.
+    pub noise_as_clusters: bool,
+}
+
+impl Default for ClusterOptions {
+    fn default() -> ClusterOptions {
+        ClusterOptions {
+            eps_m: 50.0,
+            min_points: 3,
+            noise_as_clusters: true,
+        }
+    }
+}
+
+/// A group of waypoints, as found by [`cluster_waypoints`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    /// The mean position of the members.
+    pub centroid: Point<f64>,
+
+    /// The indices of the members in the points that were clustered, in
+    /// ascending order.
+    pub members: Vec<usize>,
+
+    /// The smallest rectangle containing all members. It never crosses the
+    /// antimeridian.
+    pub bounds: Rect<f64>,
+}
+
+/// Groups `points` into clusters as tuned by `options`.
+///
+/// The result only depends on the points and their order: the clusters are
+/// ordered by their first member, and a point within reach of two clusters
+/// goes to the one found first, going through the points in order. Points
+/// whose position is not finite are noise.
+pub fn cluster_waypoints(points: &[Waypoint], options: &ClusterOptions) -> Vec<Cluster> {
+    let index = LatitudeIndex::new(points);
+    let neighbours = |i: usize| index.within(points, i, options.eps_m);
+
+    let mut assigned = vec![false; points.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for start in 0..points.len() {
+        if assigned[start] {
+            continue;
+        }
+        let reach = neighbours(start);
+        if reach.len() < options.min_points {
+            continue;
+        }
+        let mut members = Vec::new();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        assigned[start] = true;
+        members.push(start);
+        queue.extend(reach);
+        while let Some(point) = queue.pop_front() {
+            if assigned[point] {
+                continue;
+            }
+            assigned[point] = true;
+            members.push(point);
+            let reach = neighbours(point);
+            if reach.len() >= options.min_points {
+                queue.extend(reach.into_iter().filter(|&next| !assigned[next]));
+            }
+        }
+        members.sort_unstable();
+        groups.push(members);
+    }
+    // A cluster can reach back to border points before its first core point.
+    groups.sort_by_key(|members| members[0]);
+
+    if options.noise_as_clusters {
+        let noise = (0..points.len()).filter(|&point| !assigned[point]);
+        groups.extend(noise.map(|point| vec![point]));
+    }
+    groups
+        .into_iter()
+        .map(|members| Cluster::new(points, members))
+        .collect()
+}
+
+/// Gives the indices of the points that are in none of `clusters`, in
+/// ascending order, which are the noise if the clusters were found without
+/// [`ClusterOptions::noise_as_clusters`].
+pub fn unclustered(points: &[Waypoint], clusters: &[Cluster]) -> Vec<usize> {
+    let mut clustered = vec![false; points.len()];
+    for &member in clusters.iter().flat_map(|cluster| &cluster.members) {
+        clustered[member] = true;
+    }
+    (0..points.len())
+        .filter(|&point| !clustered[point])
+        .collect()
+}
+
+impl Gpx {
+    /// Groups the waypoints of the document into clusters, see
+    /// [`cluster_waypoints`]. Route and track points are left out.
+    pub fn cluster_waypoints(&self, options: &ClusterOptions) -> Vec<Cluster> {
+        cluster_waypoints(&self.waypoints, options)
+    }
+}
+
+impl Cluster {
+    fn new(points: &[Waypoint], members: Vec<usize>) -> Cluster {
+        let first = points[members[0]].point();
+        let (mut min, mut max) = (first, first);
+        let (mut x, mut y) = (0.0, 0.0);
+        for &member in &members {
+            let point = points[member].point();
+            min = Point::new(min.x().min(point.x()), min.y().min(point.y()));
+            max = Point::new(max.x().max(point.x()), max.y().max(point.y()));
+            x += point.x();
+            y += point.y();
+        }
+        let count = members.len() as f64;
+        Cluster {
+            centroid: Point::new(x / count, y / count),
+            members,
+            bounds: Rect::new(
+                coord! { x: min.x(), y: min.y() },
+                coord! { x: max.x(), y: max.y() },
+            ),
+        }
+    }
+}
+
+/// The points with a finite position, by latitude, to find the neighbours
+/// of a point among those in a band of latitude around it.
+struct LatitudeIndex {
+    /// The latitudes and indices of the points, ascending.
+    sorted: Vec<(f64, usize)>,
+}
+
+impl LatitudeIndex {
+    fn new(points: &[Waypoint]) -> LatitudeIndex {
+        let mut sorted: Vec<(f64, usize)> = points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| point.lat().is_finite() && point.lon().is_finite())
+            .map(|(index, point)| (point.lat(), index))
+            .collect();
+        sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        LatitudeIndex { sorted }
+    }
+
+    /// Gives the points within `eps_m` meters of point `index`, including
+    /// itself, in ascending order.
+    fn within(&self, points: &[Waypoint], index: usize, eps_m: f64) -> Vec<usize> {
+        let center = points[index].point();
+        if !center.x().is_finite() || !center.y().is_finite() {
+            return Vec::new();
+        }
+        // Points further apart in latitude alone are out of reach.
+        let band = (eps_m / EARTH_RADIUS_M).to_degrees();
+        let low = self
+            .sorted
+            .partition_point(|(lat, _)| *lat < center.y() - band);
+        let high = self
+            .sorted
+            .partition_point(|(lat, _)| *lat <= center.y() + band);
+        let mut found: Vec<usize> = self.sorted[low..high]
+            .iter()
+            .map(|(_, other)| *other)
+            .filter(|&other| haversine(center, points[other].point()) <= eps_m)
+            .collect();
+        found.sort_unstable();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cluster_waypoints, unclustered, ClusterOptions};
+    use crate::{Gpx, Waypoint};
+
+    /// Offsets of about 10 m around `center`.
+    fn group(center: (f64, f64), count: usize) -> Vec<Waypoint> {
+        (0..count)
+            .map(|i| {
+                let angle = i as f64 * 2.0 * std::f64::consts::PI / count as f64;
+                let lat = center.0 + 0.0001 * angle.sin();
+                let lon = center.1 + 0.0001 * angle.cos();
+                Waypoint::new_lat_lon(lat, lon).unwrap()
+            })
+            .collect()
+    }
+
+    /// Three groups of points mixed together, with outliers between them.
+    fn points() -> Vec<Waypoint> {
+        let mut zurich = group((47.3769, 8.5417), 5).into_iter();
+        let mut bern = group((46.9480, 7.4474), 4).into_iter();
+        let mut fiji = group((-17.7134, 179.9990), 3).into_iter();
+        let outlier = |lat, lon| Waypoint::new_lat_lon(lat, lon).unwrap();
+        vec![
+            outlier(46.0, 8.0),        // 0
+            zurich.next().unwrap(),    // 1
+            bern.next().unwrap(),      // 2
+            zurich.next().unwrap(),    // 3
+            fiji.next().unwrap(),      // 4
+            outlier(47.3769, 8.5437),  // 5, 150 m from Zurich
+            bern.next().unwrap(),      // 6
+            zurich.next().unwrap(),    // 7
+            fiji.next().unwrap(),      // 8
+            bern.next().unwrap(),      // 9
+            zurich.next().unwrap(),    // 10
+            fiji.next().unwrap(),      // 11
+            bern.next().unwrap(),      // 12
+            zurich.next().unwrap(),    // 13
+            outlier(-17.7134, -179.9), // 14
+        ]
+    }
+
+    #[test]
+    fn three_groups_and_outliers() {
+        let points = points();
+        let options = ClusterOptions::default();
+        let clusters = cluster_waypoints(&points, &options);
+        let members: Vec<&[usize]> = clusters
+            .iter()
+            .map(|cluster| cluster.members.as_slice())
+            .collect();
+        assert_eq!(
+            members,
+            [
+                &[1, 3, 7, 10, 13][..],
+                &[2, 6, 9, 12],
+                &[4, 8, 11],
+                &[0],
+                &[5],
+                &[14]
+            ]
+        );
+
+        let zurich = &clusters[0];
+        assert!((zurich.centroid.y() - 47.3769).abs() < 1e-9);
+        assert!((zurich.centroid.x() - 8.5417).abs() < 1e-9);
+        assert!(zurich.bounds.min().y < 47.3769 && zurich.bounds.max().y > 47.3769);
+        assert_eq!(clusters[3].centroid, points[0].point());
+
+        let without_noise = ClusterOptions {
+            noise_as_clusters: false,
+            ..Default::default()
+        };
+        let clusters = cluster_waypoints(&points, &without_noise);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(unclustered(&points, &clusters), [0, 5, 14]);
+
+        // Within reach of Zurich now.
+        let wide = ClusterOptions {
+            eps_m: 160.0,
+            ..Default::default()
+        };
+        let clusters = cluster_waypoints(&points, &wide);
+        assert_eq!(clusters[0].members, [1, 3, 5, 7, 10, 13]);
+    }
+
+    #[test]
+    fn clusters_of_the_document() {
+        let gpx = Gpx {
+            waypoints: points(),
+            ..Default::default()
+        };
+        let first = gpx.cluster_waypoints(&ClusterOptions::default());
+        assert_eq!(
+            first,
+            cluster_waypoints(&gpx.waypoints, &ClusterOptions::default())
+        );
+        assert_eq!(first.len(), 6);
+
+        let strict = ClusterOptions {
+            min_points: 6,
+            ..Default::default()
+        };
+        let clusters = gpx.cluster_waypoints(&strict);
+        assert!(clusters.iter().all(|cluster| cluster.members.len() == 1));
+        assert_eq!(clusters.len(), 15);
+    }
+}
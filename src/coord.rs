@@ -0,0 +1,452 @@
+//! coord parses and formats coordinates in degrees, minutes and seconds, as
+//! they are written by people rather than by GPS receivers.
+//!
+//! ```
+//! use gpx::coord::{format_dms, parse_dms, DmsStyle};
+//!
+//! let point = parse_dms("47°38'24.1\"N 122°19'47.9\"W").unwrap();
+//! assert_eq!(
+//!     format_dms(&point, DmsStyle::Ddm { decimals: 3 }),
+//!     "47°38.402'N 122°19.798'W"
+//! );
+//! ```
+
+use geo_types::Point;
+
+use crate::errors::{GpxError, GpxResult};
+use crate::types::{validate_latitude, validate_longitude};
+use crate::Waypoint;
+
+/// How [`format_dms`] writes coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DmsStyle {
+    /// Degrees, minutes and seconds with `decimals` decimal places, like
+    /// `47°38'24.1"N`.
+    Dms { decimals: usize },
+    /// Degrees and minutes with `decimals` decimal places, like
+    /// `47°38.402'N`.
+    Ddm { decimals: usize },
+    /// Degrees with `decimals` decimal places, like `47.64003°N`.
+    Dd { decimals: usize },
+}
+
+/// Parses a latitude and a longitude, as degrees, minutes and seconds,
+/// degrees and minutes, or degrees.
+///
+/// The hemisphere is given by a letter `N`, `S`, `E` or `W`, either before
+/// or after each angle, or by a sign in front of it, but not both. Minutes
+/// and seconds are marked by `'` and `"` (or `′` and `″`) or just follow
+/// the degrees, and only the last of them may have a fraction. The
+/// latitude comes first, unless the hemisphere letters say otherwise. Both
+/// angles may be separated by a comma.
+///
+/// ```
+/// use gpx::coord::parse_dms;
+///
+/// let a = parse_dms("47°38'24.1\"N 122°19'47.9\"W").unwrap();
+/// let b = parse_dms("47 38.40167, -122 19.79833").unwrap();
+/// let c = parse_dms("47.640028, -122.329972").unwrap();
+/// assert!((a.x() - b.x()).abs() < 1e-6 && (a.y() - c.y()).abs() < 1e-6);
+/// assert!(parse_dms("-47°N 122°W").is_err());
+/// ```
+pub fn parse_dms(s: &str) -> GpxResult<Point<f64>> {
+    let error = |reason| GpxError::InvalidCoordinate(s.to_owned(), reason);
+    let mut angles = split_angles(s).map_err(error)?;
+    // Without separators, an even number of bare numbers is cut in half.
+    if let [angle] = angles.as_mut_slice() {
+        let parts = &mut angle.parts;
+        if angle.sign.is_none()
+            && angle.hemisphere.is_none()
+            && parts.len() % 2 == 0
+            && parts.iter().all(|part| part.unit.is_none())
+        {
+            let second = Angle {
+                parts: parts.split_off(parts.len() / 2),
+                ..Default::default()
+            };
+            angles.push(second);
+        }
+    }
+    let (first, second) = match angles.as_slice() {
+        [first, second] => (first, second),
+        _ => return Err(error("expected a latitude and a longitude")),
+    };
+    let is_longitude = |angle: &Angle| {
+        angle
+            .hemisphere
+            .map(|letter| matches!(letter.to_ascii_uppercase(), 'E' | 'W'))
+    };
+    let (latitude, longitude) = match (is_longitude(first), is_longitude(second)) {
+        (Some(true), Some(true)) => return Err(error("two longitudes")),
+        (Some(false), Some(false)) => return Err(error("two latitudes")),
+        (Some(true), _) | (None, Some(false)) => (second, first),
+        _ => (first, second),
+    };
+    let latitude = latitude.value().map_err(error)?;
+    let longitude = longitude.value().map_err(error)?;
+    validate_latitude(latitude)?;
+    validate_longitude(longitude)?;
+    Ok(Point::new(longitude, latitude))
+}
+
+/// Formats the latitude and the longitude of `point` in `style`, separated
+/// by a space and with hemisphere letters instead of signs.
+///
+/// The last field is rounded, carrying into the others. More than 9
+/// decimal places are written as 9.
+///
+/// ```
+/// use gpx::coord::{format_dms, DmsStyle};
+/// use geo_types::Point;
+///
+/// let point = Point::new(-122.329972, 47.640028);
+/// assert_eq!(
+///     format_dms(&point, DmsStyle::Dms { decimals: 1 }),
+///     "47°38'24.1\"N 122°19'47.9\"W"
+/// );
+/// assert_eq!(
+///     format_dms(&point, DmsStyle::Dd { decimals: 4 }),
+///     "47.6400°N 122.3300°W"
+/// );
+/// ```
+pub fn format_dms(point: &Point<f64>, style: DmsStyle) -> String {
+    format!(
+        "{} {}",
+        format_angle(point.y(), style, ('N', 'S')),
+        format_angle(point.x(), style, ('E', 'W'))
+    )
+}
+
+impl Waypoint {
+    /// Creates a waypoint at a position given as degrees, minutes and
+    /// seconds, see [`parse_dms`].
+    pub fn from_dms(s: &str) -> GpxResult<Waypoint> {
+        parse_dms(s).map(Waypoint::new)
+    }
+}
+
+/// Formats one angle, with the `letters` of the positive and the negative
+/// hemisphere.
+fn format_angle(value: f64, style: DmsStyle, letters: (char, char)) -> String {
+    let (divisions, decimals) = match style {
+        DmsStyle::Dms { decimals } => (3600, decimals),
+        DmsStyle::Ddm { decimals } => (60, decimals),
+        DmsStyle::Dd { decimals } => (1, decimals),
+    };
+    let decimals = decimals.min(9);
+    let scale = 10u64.pow(decimals as u32);
+    // Counted in units of the last digit, so that rounding carries.
+    let units = (value.abs() * (divisions * scale) as f64).round() as u64;
+    let letter = if value < 0.0 && units > 0 {
+        letters.1
+    } else {
+        letters.0
+    };
+    let degrees = units / (divisions * scale);
+    let rest = units % (divisions * scale);
+    match style {
+        DmsStyle::Dms { .. } => format!(
+            "{}°{:02}'{}\"{}",
+            degrees,
+            rest / (60 * scale),
+            fixed(rest % (60 * scale), decimals, 2),
+            letter
+        ),
+        DmsStyle::Ddm { .. } => format!("{}°{}'{}", degrees, fixed(rest, decimals, 2), letter),
+        DmsStyle::Dd { .. } => format!("{}°{}", fixed(units, decimals, 1), letter),
+    }
+}
+
+/// Writes `units` of the last of `decimals` decimal places, with at least
+/// `width` digits before the point.
+fn fixed(units: u64, decimals: usize, width: usize) -> String {
+    let scale = 10u64.pow(decimals as u32);
+    if decimals == 0 {
+        format!("{:0width$}", units, width = width)
+    } else {
+        format!(
+            "{:0width$}.{:0decimals$}",
+            units / scale,
+            units % scale,
+            width = width,
+            decimals = decimals
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Unit {
+    Degrees,
+    Minutes,
+    Seconds,
+}
+
+const UNITS: [Unit; 3] = [Unit::Degrees, Unit::Minutes, Unit::Seconds];
+
+/// A number of an angle, with the unit given after it.
+#[derive(Clone, Debug)]
+struct Part {
+    value: f64,
+    fraction: bool,
+    unit: Option<Unit>,
+}
+
+/// One angle as written, before it is checked.
+#[derive(Clone, Debug, Default)]
+struct Angle {
+    sign: Option<char>,
+    hemisphere: Option<char>,
+    parts: Vec<Part>,
+}
+
+impl Angle {
+    fn value(&self) -> Result<f64, &'static str> {
+        if self.parts.is_empty() || self.parts.len() > 3 {
+            return Err("an angle needs one to three numbers");
+        }
+        if self.sign.is_some() && self.hemisphere.is_some() {
+            return Err("an angle with both a sign and a hemisphere");
+        }
+        let mut value = 0.0;
+        let mut divisor = 1.0;
+        for (index, (part, unit)) in self.parts.iter().zip(&UNITS).enumerate() {
+            if part.unit.is_some_and(|given| given != *unit) {
+                return Err("units out of order");
+            }
+            if part.fraction && index + 1 < self.parts.len() {
+                return Err("a fraction before the last number of an angle");
+            }
+            if index > 0 && part.value >= 60.0 {
+                return Err("minutes or seconds of 60 or more");
+            }
+            value += part.value / divisor;
+            divisor *= 60.0;
+        }
+        let negative = self.sign == Some('-')
+            || self
+                .hemisphere
+                .is_some_and(|letter| matches!(letter.to_ascii_uppercase(), 'S' | 'W'));
+        Ok(if negative { -value } else { value })
+    }
+}
+
+fn is_hemisphere(c: char) -> bool {
+    matches!(c.to_ascii_uppercase(), 'N' | 'S' | 'E' | 'W')
+}
+
+/// Splits `s` into the angles it is written as. Hemisphere letters are
+/// taken to come before the angles if `s` starts with one, and after them
+/// otherwise.
+fn split_angles(s: &str) -> Result<Vec<Angle>, &'static str> {
+    let prefixed = s.trim_start().starts_with(is_hemisphere);
+    let mut angles = vec![Angle::default()];
+    // Whether the last angle was ended by a hemisphere letter after it.
+    let mut closed = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let unit = match c {
+            '°' | 'º' | '˚' => Some(Unit::Degrees),
+            '\'' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                Some(Unit::Seconds)
+            }
+            '\'' | '′' | '’' => Some(Unit::Minutes),
+            '"' | '″' | '”' => Some(Unit::Seconds),
+            _ => None,
+        };
+        let current = angles.last_mut().unwrap();
+        if let Some(unit) = unit {
+            let part = match current.parts.last_mut() {
+                Some(part) if part.unit.is_none() => part,
+                _ => return Err("a unit without a number"),
+            };
+            part.unit = Some(unit);
+            if unit == Unit::Degrees && current.parts.len() > 1 {
+                // The degrees of the next angle.
+                let part = current.parts.pop().unwrap();
+                angles.push(Angle {
+                    parts: vec![part],
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => {}
+            ',' | ';' => {
+                if current.parts.is_empty() {
+                    return Err("a separator without an angle before it");
+                }
+                angles.push(Angle::default());
+                closed = false;
+            }
+            '+' | '-' | '−' => {
+                if closed || !current.parts.is_empty() {
+                    angles.push(Angle::default());
+                    closed = false;
+                }
+                let current = angles.last_mut().unwrap();
+                if current.sign.is_some() {
+                    return Err("two signs");
+                }
+                current.sign = Some(if c == '+' { '+' } else { '-' });
+            }
+            c if is_hemisphere(c) => {
+                if prefixed {
+                    if current.hemisphere.is_some() || !current.parts.is_empty() {
+                        angles.push(Angle::default());
+                    }
+                    angles.last_mut().unwrap().hemisphere = Some(c);
+                } else {
+                    if current.parts.is_empty() || closed {
+                        return Err("a hemisphere without an angle before it");
+                    }
+                    current.hemisphere = Some(c);
+                    closed = true;
+                }
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_digit() || c == '.') {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                let value = number.parse().map_err(|_| "an invalid number")?;
+                if closed {
+                    angles.push(Angle::default());
+                    closed = false;
+                }
+                angles.last_mut().unwrap().parts.push(Part {
+                    value,
+                    fraction: number.contains('.'),
+                    unit: None,
+                });
+            }
+            _ => return Err("an unexpected character"),
+        }
+    }
+    if angles.last().is_some_and(|angle| angle.parts.is_empty()) {
+        return Err("an angle without numbers");
+    }
+    Ok(angles)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use geo_types::Point;
+
+    use super::{format_dms, parse_dms, DmsStyle};
+    use crate::Waypoint;
+
+    const LATITUDE: f64 = 47.0 + 38.0 / 60.0 + 24.1 / 3600.0;
+    const LONGITUDE: f64 = -(122.0 + 19.0 / 60.0 + 47.9 / 3600.0);
+
+    #[test]
+    fn formats_parse_to_the_same_point() {
+        let inputs = [
+            "47°38'24.1\"N 122°19'47.9\"W",
+            "47°38'24.1\" N, 122°19'47.9\" W",
+            "N 47°38'24.1\" W 122°19'47.9\"",
+            "47 38 24.1 N 122 19 47.9 W",
+            "47° 38′ 24.1″ N 122° 19′ 47.9″ W",
+            "47º38'24.1''n 122º19'47.9''w",
+            "47°38'24.1\", -122°19'47.9\"",
+            "122°19'47.9\"W 47°38'24.1\"N",
+            "47°38.401667'N 122°19.798333'W",
+            "47 38.401667, -122 19.798333",
+            "47.6400278, -122.3299722",
+            "+47.6400278 -122.3299722",
+            "47.6400278N 122.3299722W",
+            "47 38 24.1 -122 19 47.9",
+        ];
+        for input in inputs.iter() {
+            let point = parse_dms(input).unwrap_or_else(|e| panic!("{}: {}", input, e));
+            assert_approx_eq!(point.y(), LATITUDE, 1e-6);
+            assert_approx_eq!(point.x(), LONGITUDE, 1e-6);
+        }
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        let inputs = [
+            "",
+            "47°N",
+            "-47°N 122°W",
+            "47°N +122°W",
+            "47°N 48°N",
+            "122°E 8°W",
+            "47°61'N 122°W",
+            "47.5°30'N 122°W",
+            "47'30°N 122°W",
+            "91°N 0°E",
+            "47°N 122°W 5",
+            "47°N, , 122°W",
+            "47 38 24.1",
+            "47x 122",
+            "--47 122",
+        ];
+        for input in inputs.iter() {
+            assert!(parse_dms(input).is_err(), "{} was accepted", input);
+        }
+    }
+
+    #[test]
+    fn styles_round_trip() {
+        let points = [
+            Point::new(LONGITUDE, LATITUDE),
+            Point::new(151.2153, -33.8568),
+            Point::new(0.0, 0.0),
+            Point::new(-179.999999, 89.999999),
+        ];
+        let styles = [
+            (DmsStyle::Dms { decimals: 3 }, 1e-6),
+            (DmsStyle::Dms { decimals: 0 }, 1.0 / 3600.0),
+            (DmsStyle::Ddm { decimals: 4 }, 1e-6),
+            (DmsStyle::Dd { decimals: 7 }, 1e-7),
+            (DmsStyle::Dd { decimals: 0 }, 1.0),
+        ];
+        for point in points.iter() {
+            for (style, tolerance) in styles.iter() {
+                let formatted = format_dms(point, *style);
+                let parsed = parse_dms(&formatted).unwrap();
+                assert!(
+                    (parsed.x() - point.x()).abs() <= *tolerance
+                        && (parsed.y() - point.y()).abs() <= *tolerance,
+                    "{:?} became {} and {:?}",
+                    point,
+                    formatted,
+                    parsed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rounding_carries() {
+        let point = Point::new(-8.0 - 59.995 / 60.0, 0.0);
+        assert_eq!(
+            format_dms(&point, DmsStyle::Dms { decimals: 0 }),
+            "0°00'00\"N 9°00'00\"W"
+        );
+        assert_eq!(
+            format_dms(&point, DmsStyle::Ddm { decimals: 1 }),
+            "0°00.0'N 9°00.0'W"
+        );
+        let tiny = Point::new(-1e-9, -1e-9);
+        assert_eq!(
+            format_dms(&tiny, DmsStyle::Dd { decimals: 2 }),
+            "0.00°N 0.00°E"
+        );
+    }
+
+    #[test]
+    fn waypoint_from_dms() {
+        let waypoint = Waypoint::from_dms("33°51'24.5\"S 151°12'55.1\"E").unwrap();
+        assert_approx_eq!(waypoint.point().y(), -33.856806, 1e-6);
+        assert_approx_eq!(waypoint.point().x(), 151.215306, 1e-6);
+    }
+}
@@ -0,0 +1,273 @@
+//! coverage counts how many points have each of their optional fields, to
+//! see what a document from elsewhere actually contains.
+
+use std::fmt;
+
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Gpx, Track, Waypoint};
+
+/// The fields of a point counted by [`FieldCounts`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub enum PointField {
+    /// `ele`
+    Elevation,
+    /// `time`
+    Time,
+    /// `speed`
+    Speed,
+    /// `fix`
+    Fix,
+    /// `sat`
+    Sat,
+    /// `hdop`
+    Hdop,
+    /// `vdop`
+    Vdop,
+    /// `pdop`
+    Pdop,
+    /// `extensions`
+    Extensions,
+    /// `name`
+    Name,
+}
+
+impl PointField {
+    /// All fields, in the order of [`FieldCoverage`]'s table.
+    pub const ALL: [PointField; 10] = [
+        PointField::Elevation,
+        PointField::Time,
+        PointField::Speed,
+        PointField::Fix,
+        PointField::Sat,
+        PointField::Hdop,
+        PointField::Vdop,
+        PointField::Pdop,
+        PointField::Extensions,
+        PointField::Name,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PointField::Elevation => "elevation",
+            PointField::Time => "time",
+            PointField::Speed => "speed",
+            PointField::Fix => "fix",
+            PointField::Sat => "sat",
+            PointField::Hdop => "hdop",
+            PointField::Vdop => "vdop",
+            PointField::Pdop => "pdop",
+            PointField::Extensions => "extensions",
+            PointField::Name => "name",
+        }
+    }
+}
+
+/// How many of a number of points have each field. Each count is named
+/// after the field of [`Waypoint`] it counts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct FieldCounts {
+    /// The number of points counted.
+    pub points: usize,
+    pub elevation: usize,
+    pub time: usize,
+    pub speed: usize,
+    pub fix: usize,
+    pub sat: usize,
+    pub hdop: usize,
+    pub vdop: usize,
+    pub pdop: usize,
+    pub extensions: usize,
+    pub name: usize,
+}
+
+impl FieldCounts {
+    /// Gives the number of points that have `field`.
+    pub fn count(&self, field: PointField) -> usize {
+        match field {
+            PointField::Elevation => self.elevation,
+            PointField::Time => self.time,
+            PointField::Speed => self.speed,
+            PointField::Fix => self.fix,
+            PointField::Sat => self.sat,
+            PointField::Hdop => self.hdop,
+            PointField::Vdop => self.vdop,
+            PointField::Pdop => self.pdop,
+            PointField::Extensions => self.extensions,
+            PointField::Name => self.name,
+        }
+    }
+
+    /// Gives the percentage of points that have `field`, or `None` if no
+    /// points were counted.
+    pub fn percentage(&self, field: PointField) -> Option<f64> {
+        if self.points == 0 {
+            return None;
+        }
+        Some(self.count(field) as f64 * 100.0 / self.points as f64)
+    }
+
+    fn add(&mut self, point: &Waypoint) {
+        let has = |present: bool| present as usize;
+        self.points += 1;
+        self.elevation += has(point.elevation.is_some());
+        self.time += has(point.time.is_some());
+        self.speed += has(point.extras().speed.is_some());
+        self.fix += has(point.extras().fix.is_some());
+        self.sat += has(point.extras().sat.is_some());
+        self.hdop += has(point.extras().hdop.is_some());
+        self.vdop += has(point.extras().vdop.is_some());
+        self.pdop += has(point.extras().pdop.is_some());
+        self.extensions += has(point.extras().extensions.is_some());
+        self.name += has(point.name.is_some());
+    }
+}
+
+/// How many waypoints, route points and track points of a document have
+/// each field, as given by [`Gpx::field_coverage`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct FieldCoverage {
+    /// The counts of the waypoints of the document.
+    pub waypoints: FieldCounts,
+    /// The counts of the points of all routes.
+    pub route_points: FieldCounts,
+    /// The counts of the points of all tracks.
+    pub track_points: FieldCounts,
+}
+
+impl Gpx {
+    /// Counts the fields of all waypoints, route points and track points.
+    ///
+    /// ```
+    /// use gpx::{Gpx, PointField, Waypoint};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// let mut waypoint = Waypoint::new_lat_lon(47.3, 8.5).unwrap();
+    /// waypoint.elevation = Some(408.0);
+    /// gpx.waypoints.push(waypoint);
+    /// gpx.waypoints.push(Waypoint::new_lat_lon(47.4, 8.6).unwrap());
+    ///
+    /// let coverage = gpx.field_coverage();
+    /// assert_eq!(coverage.waypoints.percentage(PointField::Elevation), Some(50.0));
+    /// assert_eq!(coverage.track_points.percentage(PointField::Elevation), None);
+    /// ```
+    pub fn field_coverage(&self) -> FieldCoverage {
+        let mut coverage = FieldCoverage::default();
+        for point in &self.waypoints {
+            coverage.waypoints.add(point);
+        }
+        for point in self.routes.iter().flat_map(|route| &route.points) {
+            coverage.route_points.add(point);
+        }
+        for track in &self.tracks {
+            track.count_fields(&mut coverage.track_points);
+        }
+        coverage
+    }
+}
+
+impl Track {
+    /// Counts the fields of the points of all segments.
+    pub fn field_coverage(&self) -> FieldCounts {
+        let mut counts = FieldCounts::default();
+        self.count_fields(&mut counts);
+        counts
+    }
+
+    fn count_fields(&self, counts: &mut FieldCounts) {
+        for point in self.segments.iter().flat_map(|segment| &segment.points) {
+            counts.add(point);
+        }
+    }
+}
+
+impl fmt::Display for FieldCoverage {
+    /// Gives a table with a row for each field and a column for each kind
+    /// of point, and the percentage of points that have the field in each
+    /// cell.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = [&self.waypoints, &self.route_points, &self.track_points];
+        write!(
+            f,
+            "{:<10}{:>11}{:>14}{:>14}",
+            "", "waypoints", "route points", "track points"
+        )?;
+        write!(f, "\n{:<10}", "points")?;
+        for (counts, width) in columns.iter().zip(&[11, 14, 14]) {
+            write!(f, "{:>width$}", counts.points, width = width)?;
+        }
+        for field in PointField::ALL.iter() {
+            write!(f, "\n{:<10}", field.label())?;
+            for (counts, width) in columns.iter().zip(&[11, 14, 14]) {
+                match counts.percentage(*field) {
+                    Some(percentage) => write!(f, "{:>width$.1}%", percentage, width = width - 1)?,
+                    None => write!(f, "{:>width$}", "-", width = width)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use super::PointField;
+    use crate::{read, Gpx};
+
+    fn fixture() -> Gpx {
+        let file = File::open("tests/fixtures/field_coverage.gpx").unwrap();
+        read(BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn track_point_coverage() {
+        let gpx = fixture();
+        let counts = gpx.tracks[0].field_coverage();
+        assert_eq!(counts.points, 100);
+        assert_eq!(counts.elevation, 90);
+        assert_eq!(counts.percentage(PointField::Elevation), Some(90.0));
+        assert_eq!(counts.percentage(PointField::Time), Some(100.0));
+        assert_eq!(counts.percentage(PointField::Fix), Some(20.0));
+        assert_eq!(counts.percentage(PointField::Hdop), Some(50.0));
+        assert_eq!(counts.percentage(PointField::Extensions), Some(25.0));
+        assert_eq!(counts.percentage(PointField::Speed), Some(0.0));
+        assert_eq!(gpx.field_coverage().track_points, counts);
+    }
+
+    #[test]
+    fn coverage_table() {
+        let coverage = fixture().field_coverage();
+        assert_eq!(coverage.waypoints.points, 3);
+        assert_eq!(coverage.route_points.name, 2);
+        let expected = [
+            "            waypoints  route points  track points",
+            "points              3             4           100",
+            "elevation       33.3%          0.0%         90.0%",
+            "time             0.0%          0.0%        100.0%",
+            "speed            0.0%          0.0%          0.0%",
+            "fix              0.0%          0.0%         20.0%",
+            "sat              0.0%          0.0%         50.0%",
+            "hdop             0.0%          0.0%         50.0%",
+            "vdop             0.0%          0.0%          0.0%",
+            "pdop             0.0%          0.0%          0.0%",
+            "extensions       0.0%          0.0%         25.0%",
+            "name           100.0%         50.0%          0.0%",
+        ]
+        .join("\n");
+        assert_eq!(coverage.to_string(), expected);
+
+        let empty = Gpx::default().field_coverage().to_string();
+        assert!(empty
+            .lines()
+            .nth(2)
+            .unwrap()
+            .ends_with("-             -             -"));
+    }
+}
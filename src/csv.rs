@@ -0,0 +1,150 @@
+//! csv exports track segments as comma separated values.
+
+use std::io::Write;
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::errors::GpxResult;
+use crate::TrackSegment;
+
+/// Options for [`TrackSegment::write_csv`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    /// Character separating the columns.
+    pub delimiter: char,
+
+    /// Whether to start with a row naming the columns.
+    pub header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: ',',
+            header: true,
+        }
+    }
+}
+
+const COLUMNS: [&str; 5] = ["lat", "lon", "ele", "time", "speed"];
+
+impl TrackSegment {
+    /// Writes the points of the segment as CSV, one row per point.
+    ///
+    /// The columns are `lat`, `lon`, `ele`, `time` (RFC 3339) and `speed`.
+    /// Missing values are written as empty cells. Since all columns are
+    /// numbers or timestamps, no quoting is done.
+    ///
+    /// ```
+    /// use gpx::{CsvOptions, TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// segment.points.push(Waypoint::new_lat_lon(37.24, -121.97).unwrap());
+    ///
+    /// let mut csv: Vec<u8> = Vec::new();
+    /// segment.write_csv(&mut csv, &CsvOptions::default()).unwrap();
+    /// assert_eq!(csv, b"lat,lon,ele,time,speed\n37.24,-121.97,,,\n");
+    /// ```
+    pub fn write_csv<W: Write>(&self, mut writer: W, options: &CsvOptions) -> GpxResult<()> {
+        let delimiter = options.delimiter.to_string();
+        if options.header {
+            writeln!(writer, "{}", COLUMNS.join(&delimiter))?;
+        }
+        for point in &self.points {
+            let time = match point.time {
+                Some(time) => OffsetDateTime::from(time).format(&Rfc3339)?,
+                None => String::new(),
+            };
+            let row = [
+                point.lat().to_string(),
+                point.lon().to_string(),
+                optional_to_string(point.elevation),
+                time,
+                optional_to_string(point.extras().speed),
+            ];
+            writeln!(writer, "{}", row.join(&delimiter))?;
+        }
+        Ok(())
+    }
+}
+
+fn optional_to_string(value: Option<f64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::CsvOptions;
+    use crate::{TrackSegment, Waypoint};
+
+    fn segment() -> TrackSegment {
+        let mut first = Waypoint::new(Point::new(-122.326897, 47.644548));
+        first.elevation = Some(4.46);
+        first.time = Some(OffsetDateTime::UNIX_EPOCH.into());
+        first.extras_mut().speed = Some(1.5);
+
+        let mut second = Waypoint::new(Point::new(-122.326787, 47.6445));
+        second.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::milliseconds(1500)).into());
+
+        let mut third = Waypoint::new(Point::new(-122.3261, 47.6441));
+        third.elevation = Some(-2.0);
+
+        TrackSegment {
+            points: vec![first, second, third],
+            ..Default::default()
+        }
+    }
+
+    fn to_csv(segment: &TrackSegment, options: &CsvOptions) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        segment.write_csv(&mut buffer, options).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn write_csv_default() {
+        let csv = to_csv(&segment(), &CsvOptions::default());
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "lat,lon,ele,time,speed",
+                "47.644548,-122.326897,4.46,1970-01-01T00:00:00Z,1.5",
+                "47.6445,-122.326787,,1970-01-01T00:00:01.5Z,",
+                "47.6441,-122.3261,-2,,",
+            ]
+        );
+    }
+
+    #[test]
+    fn write_csv_custom_delimiter_without_header() {
+        let options = CsvOptions {
+            delimiter: ';',
+            header: false,
+        };
+        let csv = to_csv(&segment(), &options);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "47.644548;-122.326897;4.46;1970-01-01T00:00:00Z;1.5",
+                "47.6445;-122.326787;;1970-01-01T00:00:01.5Z;",
+                "47.6441;-122.3261;-2;;",
+            ]
+        );
+    }
+
+    #[test]
+    fn write_csv_empty_segment() {
+        assert_eq!(
+            to_csv(&TrackSegment::new(), &CsvOptions::default()),
+            "lat,lon,ele,time,speed\n"
+        );
+    }
+}
@@ -0,0 +1,502 @@
+//! dedup finds tracks that were recorded more than once, and points that
+//! are the same.
+
+use std::cmp::Ordering;
+
+use time::{Duration, OffsetDateTime};
+
+use crate::distance::{haversine, track_length, EARTH_RADIUS_M};
+use crate::{ChangeReport, Gpx, Track, TrackSegment, Waypoint};
+
+/// The most decimal places [`Waypoint::key`] rounds to. Coordinates have no
+/// more significant digits than that.
+const MAX_KEY_PRECISION: u32 = 17;
+
+/// The magnitude from which [`round_decimal`] gives up on the digits, which
+/// would soon not fit, and adds the bits of the value to it instead.
+const HUGE_KEY: i128 = 10i128.pow(38);
+
+/// How far apart points may be while still counting as the same.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tolerance {
+    /// Maximum distance between two points, in meters.
+    pub distance: f64,
+
+    /// Maximum time between two points. Times are only compared if both
+    /// points have one; `None` ignores times altogether.
+    pub time: Option<Duration>,
+
+    /// Share of points, between 0 and 1, that must match for two tracks to
+    /// be considered duplicates.
+    pub min_similarity: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Tolerance {
+        Tolerance {
+            distance: 20.0,
+            time: Some(Duration::minutes(1)),
+            min_similarity: 0.9,
+        }
+    }
+}
+
+/// Which of two duplicate tracks [`Gpx::dedup_tracks`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeepPolicy {
+    /// The track covering the longer distance.
+    Longer,
+    /// The track with more points.
+    MorePoints,
+    /// The track that comes first in the document.
+    First,
+}
+
+impl Gpx {
+    /// Finds pairs of tracks that cover the same ground.
+    ///
+    /// The similarity of two tracks is the share of points of one of them
+    /// that lie within the `tolerance` of some point of the other, taking
+    /// whichever track gives the higher share. A track that was thinned out
+    /// is therefore still a duplicate of the original, as is a track that is
+    /// part of a longer one. Pairs with a similarity of at least
+    /// `tolerance.min_similarity` are returned as `(first index, second
+    /// index, similarity)`, ordered by index.
+    ///
+    /// Every pair of tracks is compared, so this gets slow for documents with
+    /// many tracks.
+    pub fn find_duplicate_tracks(&self, tolerance: &Tolerance) -> Vec<(usize, usize, f64)> {
+        let points: Vec<Vec<&Waypoint>> = self
+            .tracks
+            .iter()
+            .map(|track| track_points(track).collect())
+            .collect();
+        let mut duplicates = Vec::new();
+        for (i, first) in points.iter().enumerate() {
+            for (j, second) in points.iter().enumerate().skip(i + 1) {
+                let similarity =
+                    coverage(first, second, tolerance).max(coverage(second, first, tolerance));
+                if similarity > 0.0 && similarity >= tolerance.min_similarity {
+                    duplicates.push((i, j, similarity));
+                }
+            }
+        }
+        duplicates
+    }
+
+    /// Removes duplicate tracks, as found by [`Gpx::find_duplicate_tracks`].
+    ///
+    /// Of each pair of duplicates, the track chosen by `keep` stays. Ties
+    /// keep the track that comes first. The report lists every segment of
+    /// the removed tracks.
+    pub fn dedup_tracks(&mut self, tolerance: &Tolerance, keep: KeepPolicy) -> ChangeReport {
+        self.bump_generation();
+        let mut duplicates = self.find_duplicate_tracks(tolerance);
+        // Settle the most similar pairs first.
+        duplicates.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
+
+        let mut removed = vec![false; self.tracks.len()];
+        for (first, second, _) in duplicates {
+            if removed[first] || removed[second] {
+                continue;
+            }
+            let keep_second = match keep {
+                KeepPolicy::Longer => {
+                    track_length(&self.tracks[second]) > track_length(&self.tracks[first])
+                }
+                KeepPolicy::MorePoints => {
+                    track_points(&self.tracks[second]).count()
+                        > track_points(&self.tracks[first]).count()
+                }
+                KeepPolicy::First => false,
+            };
+            removed[if keep_second { first } else { second }] = true;
+        }
+
+        let mut report = ChangeReport::new("dedup_tracks", self.all_points().count());
+        for (t, track) in self.tracks.iter().enumerate() {
+            if removed[t] {
+                for (s, segment) in track.segments.iter().enumerate() {
+                    report.record_segment(t, s, segment.points.len(), 0, 0);
+                }
+            }
+        }
+        let mut index = 0;
+        self.tracks.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+        report
+    }
+}
+
+/// What identifies a point for [`Waypoint::key`], which can be hashed and
+/// compared exactly.
+///
+/// Keys made with different precisions are never equal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WaypointKey {
+    precision: u32,
+    longitude: i128,
+    latitude: i128,
+    elevation: Option<i128>,
+    time: Option<i64>,
+}
+
+impl Waypoint {
+    /// Gives a key that is equal for points at the same position, rounded
+    /// to `precision` decimal places, and with the same time, truncated to
+    /// whole seconds. The elevation is left out, see
+    /// [`Waypoint::key_with_elevation`].
+    ///
+    /// Rounding works on the exact decimal value of the coordinates, so
+    /// keys are the same on every platform. Precisions above 17 are treated
+    /// as 17.
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    ///
+    /// use gpx::Waypoint;
+    ///
+    /// let points = [
+    ///     Waypoint::new_lat_lon(47.3769, 8.5417001).unwrap(),
+    ///     Waypoint::new_lat_lon(47.3769, 8.5417004).unwrap(),
+    ///     Waypoint::new_lat_lon(47.3769, 8.5418).unwrap(),
+    /// ];
+    /// let unique: HashSet<_> = points.iter().map(|point| point.key(6)).collect();
+    /// assert_eq!(unique.len(), 2);
+    /// ```
+    pub fn key(&self, precision: u32) -> WaypointKey {
+        let precision = precision.min(MAX_KEY_PRECISION);
+        let point = self.point();
+        WaypointKey {
+            precision,
+            longitude: round_decimal(point.x(), precision),
+            latitude: round_decimal(point.y(), precision),
+            elevation: None,
+            time: self
+                .time
+                .map(|time| OffsetDateTime::from(time).unix_timestamp()),
+        }
+    }
+
+    /// Like [`Waypoint::key`], but also with the elevation, rounded to
+    /// `precision` decimal places as well.
+    pub fn key_with_elevation(&self, precision: u32) -> WaypointKey {
+        let mut key = self.key(precision);
+        key.elevation = self
+            .elevation
+            .map(|elevation| round_decimal(elevation, key.precision));
+        key
+    }
+}
+
+impl TrackSegment {
+    /// Removes points that have the same [`Waypoint::key`] as the point
+    /// before them, at the given `precision`, and reports them as removed.
+    /// The first of each run of equal points stays.
+    pub fn dedup_exact(&mut self, precision: u32) -> ChangeReport {
+        let before = self.points.len();
+        let mut previous: Option<WaypointKey> = None;
+        self.points.retain(|point| {
+            let key = point.key(precision);
+            let keep = previous.as_ref() != Some(&key);
+            previous = Some(key);
+            keep
+        });
+        ChangeReport::of_segment("dedup_exact", before, self.points.len(), 0)
+    }
+}
+
+impl Gpx {
+    /// Removes repeated points from every track segment, see
+    /// [`TrackSegment::dedup_exact`].
+    pub fn dedup_exact(&mut self, precision: u32) -> ChangeReport {
+        self.report_segments("dedup_exact", |segment| segment.dedup_exact(precision))
+    }
+}
+
+/// Rounds `value` to `precision` decimal places and gives the digits as an
+/// integer, so that 8.54 becomes 854 for a precision of 2.
+///
+/// Values with 39 digits or more, such as elevations of 1e300, give
+/// [`HUGE_KEY`] plus the bits of their magnitude instead. Floats that large
+/// are further apart than the rounding, so they stay as distinct as their
+/// digits.
+fn round_decimal(value: f64, precision: u32) -> i128 {
+    if !value.is_finite() {
+        return match value {
+            value if value.is_nan() => i128::MIN,
+            value if value > 0.0 => i128::MAX,
+            _ => i128::MIN + 1,
+        };
+    }
+    // Formatting rounds the exact decimal expansion of the value, unlike
+    // multiplying by a power of ten, which rounds on its own.
+    let text = format!("{:.*}", precision as usize, value);
+    let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+    let magnitude = match digits.parse::<i128>() {
+        Ok(magnitude) if magnitude < HUGE_KEY => magnitude,
+        _ => HUGE_KEY + value.abs().to_bits() as i128,
+    };
+    if text.starts_with('-') {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn track_points(track: &Track) -> impl Iterator<Item = &Waypoint> {
+    track
+        .segments
+        .iter()
+        .flat_map(|segment| segment.points.iter())
+}
+
+/// Gives the share of `points` that are close to one of `others`. Stops
+/// early once it is clear that `tolerance.min_similarity` can't be reached.
+fn coverage(points: &[&Waypoint], others: &[&Waypoint], tolerance: &Tolerance) -> f64 {
+    if points.is_empty() || others.is_empty() {
+        return 0.0;
+    }
+    // A degree of latitude is the same distance everywhere, so sorting by
+    // latitude narrows the candidates down to a band around each point.
+    let mut others = others.to_vec();
+    others.sort_by(|a, b| {
+        latitude(a)
+            .partial_cmp(&latitude(b))
+            .unwrap_or(Ordering::Equal)
+    });
+    let band = (tolerance.distance / EARTH_RADIUS_M).to_degrees();
+
+    let needed = (tolerance.min_similarity * points.len() as f64).ceil() as usize;
+    let mut matched = 0;
+    for (index, point) in points.iter().enumerate() {
+        let start = others.partition_point(|other| latitude(other) < latitude(point) - band);
+        let close = others[start..]
+            .iter()
+            .take_while(|other| latitude(other) <= latitude(point) + band)
+            .any(|other| is_close(point, other, tolerance));
+        if close {
+            matched += 1;
+        } else if matched + (points.len() - index - 1) < needed {
+            return 0.0;
+        }
+    }
+    matched as f64 / points.len() as f64
+}
+
+fn latitude(point: &Waypoint) -> f64 {
+    point.lat()
+}
+
+fn is_close(a: &Waypoint, b: &Waypoint, tolerance: &Tolerance) -> bool {
+    if let (Some(max), Some(ta), Some(tb)) = (tolerance.time, a.time, b.time) {
+        if (OffsetDateTime::from(ta) - OffsetDateTime::from(tb)).abs() > max {
+            return false;
+        }
+    }
+    haversine(a.point(), b.point()) <= tolerance.distance
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::{round_decimal, KeepPolicy, Tolerance};
+    use crate::{read, ChangeReport, Gpx, Track, TrackSegment, Waypoint};
+
+    fn fixture_track(filename: &str) -> Track {
+        let file = File::open(filename).unwrap();
+        let mut gpx = read(BufReader::new(file)).unwrap();
+        gpx.tracks.remove(0)
+    }
+
+    /// A copy of `track`, moved a few meters north and a few seconds later,
+    /// keeping only every third point.
+    fn offset_thinned_copy(track: &Track) -> Track {
+        let mut copy = Track::new();
+        for segment in &track.segments {
+            let mut thinned = TrackSegment::new();
+            for point in segment.points.iter().step_by(3) {
+                let position = point.point();
+                let mut moved = Waypoint::new(Point::new(position.x(), position.y() + 0.00003));
+                moved.elevation = point.elevation;
+                moved.time = point
+                    .time
+                    .map(|time| (OffsetDateTime::from(time) + Duration::seconds(2)).into());
+                thinned.points.push(moved);
+            }
+            copy.segments.push(thinned);
+        }
+        copy
+    }
+
+    fn sample_gpx() -> Gpx {
+        let original = fixture_track("tests/fixtures/garmin_with_extensions.gpx");
+        let copy = offset_thinned_copy(&original);
+        let other = fixture_track("tests/fixtures/wikipedia_example.gpx");
+        Gpx {
+            tracks: vec![other, original, copy],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn find_duplicate_tracks() {
+        let gpx = sample_gpx();
+
+        let duplicates = gpx.find_duplicate_tracks(&Tolerance::default());
+
+        assert_eq!(duplicates.len(), 1);
+        let (first, second, similarity) = duplicates[0];
+        assert_eq!((first, second), (1, 2));
+        assert!(similarity > 0.99);
+    }
+
+    #[test]
+    fn find_duplicate_tracks_respects_time() {
+        let gpx = sample_gpx();
+        let tolerance = Tolerance {
+            time: Some(Duration::seconds(1)),
+            ..Default::default()
+        };
+
+        assert!(gpx.find_duplicate_tracks(&tolerance).is_empty());
+
+        // Without looking at times, the copy matches again.
+        let tolerance = Tolerance {
+            time: None,
+            ..Default::default()
+        };
+        assert_eq!(gpx.find_duplicate_tracks(&tolerance).len(), 1);
+    }
+
+    #[test]
+    fn different_tracks_are_not_duplicates() {
+        let mut first = TrackSegment::new();
+        let mut second = TrackSegment::new();
+        for i in 0..20 {
+            first
+                .points
+                .push(Waypoint::new(Point::new(i as f64 * 0.001, 0.0)));
+            second
+                .points
+                .push(Waypoint::new(Point::new(i as f64 * 0.001, 0.01)));
+        }
+        let mut gpx = Gpx::default();
+        for segment in [first, second] {
+            let mut track = Track::new();
+            track.segments.push(segment);
+            gpx.tracks.push(track);
+        }
+
+        assert!(gpx.find_duplicate_tracks(&Tolerance::default()).is_empty());
+        assert!(gpx
+            .dedup_tracks(&Tolerance::default(), KeepPolicy::First)
+            .is_unchanged());
+        assert_eq!(gpx.tracks.len(), 2);
+    }
+
+    fn removed_tracks(report: ChangeReport) -> Vec<usize> {
+        let mut tracks: Vec<usize> = report.segments.iter().map(|change| change.track).collect();
+        tracks.dedup();
+        tracks
+    }
+
+    #[test]
+    fn dedup_tracks_keep_policies() {
+        let tolerance = Tolerance::default();
+
+        let mut gpx = sample_gpx();
+        assert_eq!(
+            removed_tracks(gpx.dedup_tracks(&tolerance, KeepPolicy::MorePoints)),
+            [2]
+        );
+        assert_eq!(gpx.tracks.len(), 2);
+        assert_eq!(gpx.tracks[1], sample_gpx().tracks[1]);
+
+        let mut gpx = sample_gpx();
+        gpx.tracks.swap(1, 2);
+        assert_eq!(
+            removed_tracks(gpx.dedup_tracks(&tolerance, KeepPolicy::Longer)),
+            [1]
+        );
+        assert_eq!(gpx.tracks[1], sample_gpx().tracks[1]);
+
+        let mut gpx = sample_gpx();
+        gpx.tracks.swap(1, 2);
+        assert_eq!(
+            removed_tracks(gpx.dedup_tracks(&tolerance, KeepPolicy::First)),
+            [2]
+        );
+        assert_eq!(gpx.tracks[1], sample_gpx().tracks[2]);
+    }
+
+    #[test]
+    fn keys_round_to_precision() {
+        let first = Waypoint::new(Point::new(8.541_700_01, 47.376_900_02));
+        let second = Waypoint::new(Point::new(8.541_700_04, 47.376_900_02));
+        assert_eq!(first.key(6), second.key(6));
+        assert_ne!(first.key(8), second.key(8));
+        assert_ne!(first.key(6), first.key(7));
+
+        let mut timed = first.clone();
+        timed.time = Some(OffsetDateTime::UNIX_EPOCH.into());
+        let mut later = first.clone();
+        later.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::milliseconds(900)).into());
+        assert_ne!(first.key(6), timed.key(6));
+        assert_eq!(timed.key(6), later.key(6));
+
+        let mut high = first.clone();
+        high.elevation = Some(420.0);
+        assert_eq!(first.key(6), high.key(6));
+        assert_ne!(first.key_with_elevation(6), high.key_with_elevation(6));
+
+        // 0.125 is exact in binary and rounds half to even, 2.675 is just
+        // below its decimal value.
+        assert_eq!(round_decimal(0.125, 2), 12);
+        assert_eq!(round_decimal(2.675, 2), 267);
+        assert_eq!(round_decimal(-0.0001, 2), 0);
+        assert_eq!(round_decimal(-8.5, 0), -8);
+
+        // Values too large for their digits still differ by sign and
+        // magnitude, and from everything smaller.
+        let huge = round_decimal(1e300, 17);
+        assert_eq!(huge, round_decimal(1e300, 17));
+        assert_ne!(huge, round_decimal(2e300, 17));
+        assert_eq!(round_decimal(-1e300, 17), -huge);
+        assert!(huge > round_decimal(9.9e20, 17));
+        assert!(huge < round_decimal(f64::INFINITY, 17));
+        assert_ne!(round_decimal(1e21, 17), round_decimal(1e21 + 262_144.0, 17));
+    }
+
+    #[test]
+    fn dedup_exact_removes_stationary_points() {
+        let mut segment = TrackSegment::new();
+        let positions = [
+            (8.5, 47.3),
+            (8.5, 47.3),
+            (8.500_000_01, 47.3),
+            (8.6, 47.3),
+            (8.6, 47.3),
+            (8.5, 47.3),
+        ];
+        for &(x, y) in positions.iter() {
+            segment.points.push(Waypoint::new(Point::new(x, y)));
+        }
+
+        let mut exact = segment.clone();
+        assert_eq!(exact.dedup_exact(8).removed, 2);
+        assert_eq!(exact.points.len(), 4);
+
+        assert_eq!(segment.dedup_exact(6).removed, 3);
+        assert_eq!(segment.points.len(), 3);
+        // Only consecutive points are removed.
+        assert_eq!(segment.points[2].point(), Point::new(8.5, 47.3));
+    }
+}
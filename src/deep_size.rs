@@ -0,0 +1,445 @@
+//! deep_size estimates how much memory a document takes, heap allocations
+//! included, for bounding caches of parsed documents.
+//!
+//! Every type of the document adds its own allocations through the
+//! [`DeepSize`] trait. The implementations take their value apart field by
+//! field, so that a new field doesn't compile until it is counted.
+
+use std::mem::size_of;
+
+use crate::dom::{Element, Node, OwnedAttribute, PrologNode, UnknownAttributes};
+use crate::{
+    Fix, Gpx, GpxCopyright, Link, Metadata, Person, Route, Track, TrackSegment, Waypoint,
+    WaypointExtras,
+};
+
+/// The memory a document takes, in bytes, by what it holds, as estimated
+/// by [`Gpx::size_breakdown`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SizeBreakdown {
+    /// The waypoints, route points and track points, with their
+    /// [`WaypointExtras`].
+    pub points: usize,
+
+    /// The text of names, descriptions, links and the other strings outside
+    /// of extensions.
+    pub strings: usize,
+
+    /// The extensions, unknown elements and unknown attributes.
+    pub extensions: usize,
+
+    /// Everything else, such as the document itself and the lists of tracks,
+    /// segments and links.
+    pub other: usize,
+}
+
+impl SizeBreakdown {
+    /// Gives the sum of all parts, which is what
+    /// [`Gpx::deep_size_estimate`] gives.
+    pub fn total(&self) -> usize {
+        self.points + self.strings + self.extensions + self.other
+    }
+}
+
+impl Gpx {
+    /// Estimates the bytes this document takes in memory, both the [`Gpx`]
+    /// itself and everything it allocated, see [`Gpx::size_breakdown`].
+    ///
+    /// ```
+    /// use gpx::{Gpx, Track, TrackSegment, Waypoint};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// let empty = gpx.deep_size_estimate();
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// segment.points = vec![Waypoint::new_lat_lon(47.0, 8.0).unwrap(); 1000];
+    /// let mut track = Track::new();
+    /// track.segments.push(segment);
+    /// gpx.tracks.push(track);
+    /// assert!(gpx.deep_size_estimate() > empty + 1000 * std::mem::size_of::<Waypoint>());
+    /// ```
+    pub fn deep_size_estimate(&self) -> usize {
+        self.size_breakdown().total()
+    }
+
+    /// Estimates the bytes this document takes in memory, by what they hold.
+    ///
+    /// The estimate counts the size of every value and the capacity of
+    /// every vector and string, which is what was asked of the allocator.
+    /// It leaves out what the allocator adds to that for its books and for
+    /// rounding up, which is a few bytes per allocation. The names of
+    /// extension elements and attributes are left out too: the reader
+    /// shares the same few of them between all points, so they take next to
+    /// nothing per point.
+    pub fn size_breakdown(&self) -> SizeBreakdown {
+        let mut sizes = SizeBreakdown {
+            other: size_of::<Gpx>(),
+            ..Default::default()
+        };
+        self.add_heap_size(&mut sizes);
+        sizes
+    }
+}
+
+/// Adds the memory a value has allocated, not counting the value itself,
+/// which is part of the allocation of whatever holds it.
+trait DeepSize {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown);
+}
+
+/// Gives the bytes of the buffer of `items`.
+fn buffer_size<T>(items: &Vec<T>) -> usize {
+    items.capacity() * size_of::<T>()
+}
+
+impl<T: DeepSize> DeepSize for Option<T> {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        if let Some(value) = self {
+            value.add_heap_size(sizes);
+        }
+    }
+}
+
+impl<T: DeepSize> DeepSize for Vec<T> {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        sizes.other += buffer_size(self);
+        for item in self {
+            item.add_heap_size(sizes);
+        }
+    }
+}
+
+impl DeepSize for String {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        sizes.strings += self.capacity();
+    }
+}
+
+/// Adds points, counted as [`SizeBreakdown::points`] rather than
+/// [`SizeBreakdown::other`].
+fn add_points(points: &Vec<Waypoint>, sizes: &mut SizeBreakdown) {
+    sizes.points += buffer_size(points);
+    for point in points {
+        point.add_heap_size(sizes);
+    }
+}
+
+/// Adds unknown attributes, counted as [`SizeBreakdown::extensions`].
+fn add_unknown_attributes(unknown: &Vec<UnknownAttributes>, sizes: &mut SizeBreakdown) {
+    sizes.extensions += buffer_size(unknown);
+    for stashed in unknown {
+        let UnknownAttributes {
+            element,
+            attributes,
+        } = stashed;
+        sizes.extensions += element.capacity() + attributes_size(attributes);
+    }
+}
+
+fn attributes_size(attributes: &Vec<OwnedAttribute>) -> usize {
+    let values: usize = attributes
+        .iter()
+        .map(|attribute| {
+            let OwnedAttribute { name: _, value } = attribute;
+            value.capacity()
+        })
+        .sum();
+    buffer_size(attributes) + values
+}
+
+impl DeepSize for Element {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        // Going through the tree with a list of the elements left to look
+        // at, rather than recursing, copes with deeply nested extensions.
+        let mut open = vec![self];
+        while let Some(element) = open.pop() {
+            let Element {
+                name: _,
+                attributes,
+                children,
+            } = element;
+            sizes.extensions += attributes_size(attributes) + buffer_size(children);
+            for child in children {
+                match child {
+                    Node::Element(child) => open.push(child),
+                    Node::Text(text) => sizes.extensions += text.capacity(),
+                }
+            }
+        }
+    }
+}
+
+impl DeepSize for PrologNode {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        match self {
+            PrologNode::Comment(text) => text.add_heap_size(sizes),
+            PrologNode::ProcessingInstruction(instruction) => {
+                instruction.name.add_heap_size(sizes);
+                instruction.data.add_heap_size(sizes);
+            }
+        }
+    }
+}
+
+impl DeepSize for Gpx {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Gpx {
+            version: _,
+            creator,
+            metadata,
+            waypoints,
+            tracks,
+            routes,
+            extensions,
+            unknown_elements,
+            unknown_attributes,
+            prolog,
+            generation: _,
+        } = self;
+        creator.add_heap_size(sizes);
+        metadata.add_heap_size(sizes);
+        add_points(waypoints, sizes);
+        tracks.add_heap_size(sizes);
+        routes.add_heap_size(sizes);
+        extensions.add_heap_size(sizes);
+        sizes.extensions += buffer_size(unknown_elements);
+        for element in unknown_elements {
+            element.add_heap_size(sizes);
+        }
+        add_unknown_attributes(unknown_attributes, sizes);
+        prolog.add_heap_size(sizes);
+    }
+}
+
+impl DeepSize for Metadata {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Metadata {
+            name,
+            description,
+            author,
+            links,
+            time: _,
+            keywords,
+            copyright,
+            bounds: _,
+            extensions,
+            unknown_attributes,
+        } = self;
+        name.add_heap_size(sizes);
+        description.add_heap_size(sizes);
+        author.add_heap_size(sizes);
+        links.add_heap_size(sizes);
+        keywords.add_heap_size(sizes);
+        copyright.add_heap_size(sizes);
+        extensions.add_heap_size(sizes);
+        add_unknown_attributes(unknown_attributes, sizes);
+    }
+}
+
+impl DeepSize for Person {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Person { name, email, link } = self;
+        name.add_heap_size(sizes);
+        email.add_heap_size(sizes);
+        link.add_heap_size(sizes);
+    }
+}
+
+impl DeepSize for Link {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Link { href, text, _type } = self;
+        href.add_heap_size(sizes);
+        text.add_heap_size(sizes);
+        _type.add_heap_size(sizes);
+    }
+}
+
+impl DeepSize for GpxCopyright {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let GpxCopyright {
+            author,
+            year: _,
+            license,
+        } = self;
+        author.add_heap_size(sizes);
+        license.add_heap_size(sizes);
+    }
+}
+
+impl DeepSize for Route {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Route {
+            name,
+            comment,
+            description,
+            source,
+            links,
+            number: _,
+            _type,
+            extensions,
+            points,
+            unknown_attributes,
+        } = self;
+        name.add_heap_size(sizes);
+        comment.add_heap_size(sizes);
+        description.add_heap_size(sizes);
+        source.add_heap_size(sizes);
+        links.add_heap_size(sizes);
+        _type.add_heap_size(sizes);
+        extensions.add_heap_size(sizes);
+        add_points(points, sizes);
+        add_unknown_attributes(unknown_attributes, sizes);
+    }
+}
+
+impl DeepSize for Track {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Track {
+            name,
+            comment,
+            description,
+            source,
+            links,
+            _type,
+            number: _,
+            extensions,
+            segments,
+            unknown_attributes,
+        } = self;
+        name.add_heap_size(sizes);
+        comment.add_heap_size(sizes);
+        description.add_heap_size(sizes);
+        source.add_heap_size(sizes);
+        links.add_heap_size(sizes);
+        _type.add_heap_size(sizes);
+        extensions.add_heap_size(sizes);
+        segments.add_heap_size(sizes);
+        add_unknown_attributes(unknown_attributes, sizes);
+    }
+}
+
+impl DeepSize for TrackSegment {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let TrackSegment { points, extensions } = self;
+        add_points(points, sizes);
+        extensions.add_heap_size(sizes);
+    }
+}
+
+impl DeepSize for Waypoint {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        let Waypoint {
+            point: _,
+            elevation: _,
+            time: _,
+            name,
+            extras,
+        } = self;
+        name.add_heap_size(sizes);
+        if let Some(extras) = extras {
+            sizes.points += size_of::<WaypointExtras>();
+            extras.add_heap_size(sizes);
+        }
+    }
+}
+
+impl DeepSize for WaypointExtras {
+    fn add_heap_size(&self, sizes: &mut SizeBreakdown) {
+        #[allow(deprecated)]
+        let WaypointExtras {
+            speed: _,
+            comment,
+            description,
+            source,
+            links,
+            symbol,
+            _type,
+            geoidheight: _,
+            fix,
+            sat: _,
+            hdop: _,
+            vdop: _,
+            pdop: _,
+            age: _,
+            dgps_age: _,
+            dgpsid: _,
+            extensions,
+            unknown_attributes,
+        } = self;
+        comment.add_heap_size(sizes);
+        description.add_heap_size(sizes);
+        source.add_heap_size(sizes);
+        links.add_heap_size(sizes);
+        symbol.add_heap_size(sizes);
+        _type.add_heap_size(sizes);
+        if let Some(Fix::Other(fix)) = fix {
+            fix.add_heap_size(sizes);
+        }
+        extensions.add_heap_size(sizes);
+        add_unknown_attributes(unknown_attributes, sizes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::mem::size_of;
+
+    use crate::dom::{Element, Node, OwnedName};
+    use crate::testutil::synthetic_gpx;
+    use crate::{read, Gpx, Link, Waypoint, WaypointExtras};
+
+    /// A generated document without spare capacity in its point vectors.
+    fn generated(points: usize) -> Gpx {
+        let mut gpx = synthetic_gpx(2, points);
+        for track in &mut gpx.tracks {
+            for segment in &mut track.segments {
+                segment.points.shrink_to_fit();
+            }
+        }
+        gpx
+    }
+
+    #[test]
+    fn estimate_scales_with_points() {
+        let small = generated(1000).size_breakdown();
+        let large = generated(10_000).size_breakdown();
+        let ratio = large.total() as f64 / small.total() as f64;
+        assert!(9.5 < ratio && ratio < 10.5, "{}", ratio);
+        assert_eq!(small.points, 2000 * size_of::<Waypoint>());
+        assert_eq!(large.points, 20_000 * size_of::<Waypoint>());
+        assert_eq!(large.strings, small.strings);
+        assert_eq!((small.extensions, large.extensions), (0, 0));
+    }
+
+    #[test]
+    fn estimate_counts_every_part() {
+        let mut gpx = generated(10);
+        let before = gpx.size_breakdown();
+        gpx.tracks[0].segments[0].points.reserve_exact(10);
+        let reserved = gpx.size_breakdown();
+        assert_eq!(reserved.points, before.points + 10 * size_of::<Waypoint>());
+
+        let point = &mut gpx.tracks[0].segments[0].points[0];
+        let extras = point.extras_mut();
+        extras.comment = Some("x".repeat(100));
+        extras.links.push(Link {
+            href: "h".repeat(50),
+            ..Default::default()
+        });
+        let mut extensions = Element::new(OwnedName::local("extensions"));
+        let mut child = Element::new(OwnedName::local("hr"));
+        child.children.push(Node::Text("1".repeat(20)));
+        extensions.children.push(Node::Element(child));
+        extras.extensions = Some(extensions);
+        let sizes = gpx.size_breakdown();
+        assert!(sizes.points - reserved.points >= size_of::<WaypointExtras>());
+        assert!(sizes.strings >= reserved.strings + 150);
+        assert!(sizes.extensions >= 20 + size_of::<Node>());
+        assert_eq!(gpx.deep_size_estimate(), sizes.total());
+
+        let garmin = read(File::open("tests/fixtures/garmin-activity.gpx").unwrap()).unwrap();
+        let sizes = garmin.size_breakdown();
+        assert!(sizes.extensions > sizes.strings, "{:?}", sizes);
+    }
+}
@@ -0,0 +1,661 @@
+//! diff describes the differences between two documents in plain text.
+//!
+//! ```
+//! use gpx::diff::{format_diff, DiffOptions};
+//! use gpx::{Gpx, Metadata};
+//!
+//! let before = Gpx::default();
+//! let after = Gpx {
+//!     creator: Some("gpx".into()),
+//!     metadata: Some(Metadata {
+//!         name: Some("Morning Ride".into()),
+//!         ..Default::default()
+//!     }),
+//!     ..Default::default()
+//! };
+//!
+//! assert_eq!(
+//!     format_diff(&before, &after, &DiffOptions::default()),
+//!     "gpx: creator none → \"gpx\"\nmetadata added\n",
+//! );
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use crate::dom::Element;
+use crate::{Gpx, Metadata, Route, Time, Track, TrackSegment, Waypoint};
+
+/// Options for [`format_diff`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffOptions {
+    /// Largest difference between two decimals, such as coordinates and
+    /// elevations, that still counts as equal.
+    pub tolerance: f64,
+
+    /// Whether to give every removed, added and changed point of a route or
+    /// segment. By default, only the number of points of each kind is given.
+    pub list_points: bool,
+
+    /// Whether to compare `extensions`. Off by default.
+    pub include_extensions: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            tolerance: 1e-9,
+            list_points: false,
+            include_extensions: false,
+        }
+    }
+}
+
+/// Describes how `b` differs from `a`, one change per line.
+///
+/// Waypoints, routes and tracks are matched by name when every one of them
+/// has a name that is unique in both documents, and by index otherwise.
+/// Points are matched by position, so that removed points are reported as
+/// removed even if all of the following points shift to a lower index.
+/// Equal documents give an empty string.
+pub fn format_diff(a: &Gpx, b: &Gpx, options: &DiffOptions) -> String {
+    let mut diff = Diff {
+        options,
+        lines: Vec::new(),
+    };
+    diff.gpx(a, b);
+    let mut output = String::new();
+    for line in diff.lines {
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Gives the names of `items` if all of them have one and no name appears
+/// twice.
+fn unique_names<'a, T>(
+    items: &'a [T],
+    name: impl Fn(&'a T) -> Option<&'a String>,
+) -> Option<Vec<&'a String>> {
+    let mut seen = HashSet::new();
+    let mut names = Vec::with_capacity(items.len());
+    for item in items {
+        let item_name = name(item)?;
+        if !seen.insert(item_name) {
+            return None;
+        }
+        names.push(item_name);
+    }
+    Some(names)
+}
+
+/// Describes the value of an optional field.
+fn describe<T: Debug>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => format!("{:?}", value),
+        None => "none".into(),
+    }
+}
+
+fn describe_time(time: &Option<Time>) -> String {
+    match time {
+        Some(time) => time.format().unwrap_or_else(|_| format!("{:?}", time)),
+        None => "none".into(),
+    }
+}
+
+/// What happened to a point of `a` or `b` when aligning two lists of points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edit {
+    /// Point `.0` of `a` matches point `.1` of `b`.
+    Keep(usize, usize),
+    /// Point `.0` of `a` is not in `b`.
+    Remove(usize),
+    /// Point `.0` of `b` is not in `a`.
+    Add(usize),
+}
+
+/// Number of removed and added points beyond which [`align`] gives up
+/// looking for the smallest set of edits, to bound time and memory.
+const MAX_EDITS: usize = 1000;
+
+/// Finds the fewest removals and additions that turn a list of `n` items into
+/// one of `m` items, where `same(i, j)` tells whether item `i` of the first
+/// list matches item `j` of the second (Myers' algorithm).
+///
+/// If more than [`MAX_EDITS`] edits are needed, the items are matched by
+/// index instead.
+fn align(n: usize, m: usize, same: impl Fn(usize, usize) -> bool) -> Vec<Edit> {
+    let max = (n + m).min(MAX_EDITS);
+    let offset = max as isize + 1;
+    // furthest[k] is the furthest index into the first list reached on
+    // diagonal k - offset, and trace[d] the diagonals -d..=d before step d.
+    let mut furthest = vec![0isize; 2 * max + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=max as isize {
+        trace.push(furthest[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d
+                || (k != d
+                    && furthest[(offset + k - 1) as usize] < furthest[(offset + k + 1) as usize]);
+            let mut x = if down {
+                furthest[(offset + k + 1) as usize]
+            } else {
+                furthest[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while (x as usize) < n && (y as usize) < m && same(x as usize, y as usize) {
+                x += 1;
+                y += 1;
+            }
+            furthest[(offset + k) as usize] = x;
+            if x as usize >= n && y as usize >= m {
+                return backtrack(&trace, n, m);
+            }
+        }
+    }
+
+    let mut edits: Vec<Edit> = (0..n.min(m)).map(|i| Edit::Keep(i, i)).collect();
+    edits.extend((m..n).map(Edit::Remove));
+    edits.extend((n..m).map(Edit::Add));
+    edits
+}
+
+/// Follows the steps recorded by [`align`] back from the end of both lists.
+fn backtrack(trace: &[Vec<isize>], n: usize, m: usize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n as isize, m as isize);
+    for d in (1..trace.len() as isize).rev() {
+        let furthest = &trace[d as usize];
+        let at = |k: isize| furthest[(k + d) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Keep(x as usize, y as usize));
+        }
+        if x == previous_x {
+            edits.push(Edit::Add(previous_y as usize));
+        } else {
+            edits.push(Edit::Remove(previous_x as usize));
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        edits.push(Edit::Keep(x as usize, y as usize));
+    }
+    edits.reverse();
+    edits
+}
+
+/// Describes runs of consecutive indices, such as `points 3-5, 9`.
+fn describe_runs(indices: &[usize]) -> String {
+    let mut runs: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < indices.len() {
+        let start = indices[i];
+        while i + 1 < indices.len() && indices[i + 1] == indices[i] + 1 {
+            i += 1;
+        }
+        if indices[i] == start {
+            runs.push(start.to_string());
+        } else {
+            runs.push(format!("{}-{}", start, indices[i]));
+        }
+        i += 1;
+    }
+    let noun = if indices.len() == 1 {
+        "point"
+    } else {
+        "points"
+    };
+    format!("{} {}", noun, runs.join(", "))
+}
+
+fn count(number: usize, what: &str) -> String {
+    if number == 1 {
+        format!("1 point {}", what)
+    } else {
+        format!("{} points {}", number, what)
+    }
+}
+
+struct Diff<'a> {
+    options: &'a DiffOptions,
+    lines: Vec<String>,
+}
+
+impl Diff<'_> {
+    fn changed(&mut self, subject: &str, field: &str, old: String, new: String) {
+        self.lines
+            .push(format!("{}: {} {} → {}", subject, field, old, new));
+    }
+
+    fn value<T: Debug + PartialEq>(&mut self, subject: &str, field: &str, a: &T, b: &T) {
+        if a != b {
+            self.changed(subject, field, format!("{:?}", a), format!("{:?}", b));
+        }
+    }
+
+    fn optional<T: Debug + PartialEq>(
+        &mut self,
+        subject: &str,
+        field: &str,
+        a: &Option<T>,
+        b: &Option<T>,
+    ) {
+        if a != b {
+            self.changed(subject, field, describe(a), describe(b));
+        }
+    }
+
+    fn decimals_equal(&self, a: Option<f64>, b: Option<f64>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => (a - b).abs() <= self.options.tolerance,
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn decimal(&mut self, subject: &str, field: &str, a: Option<f64>, b: Option<f64>) {
+        if !self.decimals_equal(a, b) {
+            self.changed(subject, field, describe(&a), describe(&b));
+        }
+    }
+
+    fn time(&mut self, subject: &str, field: &str, a: &Option<Time>, b: &Option<Time>) {
+        if a != b {
+            self.changed(subject, field, describe_time(a), describe_time(b));
+        }
+    }
+
+    /// Notes a difference in a field that is too large to be described in a
+    /// single line, such as the links.
+    fn summary<T: PartialEq>(&mut self, subject: &str, field: &str, a: &T, b: &T) {
+        if a != b {
+            self.lines.push(format!("{}: {} changed", subject, field));
+        }
+    }
+
+    fn extensions(&mut self, subject: &str, a: &Option<Element>, b: &Option<Element>) {
+        if self.options.include_extensions {
+            self.summary(subject, "extensions", a, b);
+        }
+    }
+
+    fn gpx(&mut self, a: &Gpx, b: &Gpx) {
+        self.value("gpx", "version", &a.version, &b.version);
+        self.optional("gpx", "creator", &a.creator, &b.creator);
+        self.extensions("gpx", &a.extensions, &b.extensions);
+        self.summary("gpx", "prolog", &a.prolog, &b.prolog);
+        self.summary(
+            "gpx",
+            "unknown elements",
+            &a.unknown_elements,
+            &b.unknown_elements,
+        );
+        match (&a.metadata, &b.metadata) {
+            (Some(a), Some(b)) => self.metadata(a, b),
+            (Some(_), None) => self.lines.push("metadata removed".into()),
+            (None, Some(_)) => self.lines.push("metadata added".into()),
+            (None, None) => {}
+        }
+        self.matched(
+            "waypoint",
+            &a.waypoints,
+            &b.waypoints,
+            |waypoint| waypoint.name.as_ref(),
+            |diff, subject, a, b| diff.waypoint(subject, a, b),
+        );
+        self.matched(
+            "route",
+            &a.routes,
+            &b.routes,
+            |route| route.name.as_ref(),
+            Diff::route,
+        );
+        self.matched(
+            "track",
+            &a.tracks,
+            &b.tracks,
+            |track| track.name.as_ref(),
+            Diff::track,
+        );
+    }
+
+    fn metadata(&mut self, a: &Metadata, b: &Metadata) {
+        let subject = "metadata";
+        self.optional(subject, "name", &a.name, &b.name);
+        self.optional(subject, "description", &a.description, &b.description);
+        self.summary(subject, "author", &a.author, &b.author);
+        self.summary(subject, "links", &a.links, &b.links);
+        self.time(subject, "time", &a.time, &b.time);
+        self.optional(subject, "keywords", &a.keywords, &b.keywords);
+        self.summary(subject, "copyright", &a.copyright, &b.copyright);
+        self.summary(subject, "bounds", &a.bounds, &b.bounds);
+        self.extensions(subject, &a.extensions, &b.extensions);
+    }
+
+    /// Compares two lists of items, matched by name if possible, and by
+    /// index otherwise.
+    fn matched<'t, T>(
+        &mut self,
+        kind: &str,
+        a: &'t [T],
+        b: &'t [T],
+        name: impl Fn(&'t T) -> Option<&'t String> + Copy,
+        compare: impl Fn(&mut Self, &str, &'t T, &'t T),
+    ) {
+        if let (Some(a_names), Some(b_names)) = (unique_names(a, name), unique_names(b, name)) {
+            for (item, item_name) in a.iter().zip(&a_names) {
+                let subject = format!("{} '{}'", kind, item_name);
+                match b_names.iter().position(|other| other == item_name) {
+                    Some(j) => compare(self, &subject, item, &b[j]),
+                    None => self.lines.push(format!("{} removed", subject)),
+                }
+            }
+            for item_name in b_names.iter().filter(|name| !a_names.contains(name)) {
+                self.lines.push(format!("{} '{}' added", kind, item_name));
+            }
+            return;
+        }
+
+        let subject = |i: usize, item: &'t T| match name(item) {
+            Some(item_name) => format!("{} {} '{}'", kind, i, item_name),
+            None => format!("{} {}", kind, i),
+        };
+        for (i, (a_item, b_item)) in a.iter().zip(b).enumerate() {
+            compare(self, &subject(i, a_item), a_item, b_item);
+        }
+        for (i, item) in a.iter().enumerate().skip(b.len()) {
+            self.lines.push(format!("{} removed", subject(i, item)));
+        }
+        for (i, item) in b.iter().enumerate().skip(a.len()) {
+            self.lines.push(format!("{} added", subject(i, item)));
+        }
+    }
+
+    fn waypoint(&mut self, subject: &str, a: &Waypoint, b: &Waypoint) {
+        if !self.decimals_equal(Some(a.lat()), Some(b.lat()))
+            || !self.decimals_equal(Some(a.lon()), Some(b.lon()))
+        {
+            self.changed(
+                subject,
+                "position",
+                format!("({:?}, {:?})", a.lat(), a.lon()),
+                format!("({:?}, {:?})", b.lat(), b.lon()),
+            );
+        }
+        self.decimal(subject, "elevation", a.elevation, b.elevation);
+        self.decimal(subject, "speed", a.extras().speed, b.extras().speed);
+        self.time(subject, "time", &a.time, &b.time);
+        self.optional(subject, "name", &a.name, &b.name);
+        self.optional(subject, "comment", &a.extras().comment, &b.extras().comment);
+        self.optional(
+            subject,
+            "description",
+            &a.extras().description,
+            &b.extras().description,
+        );
+        self.optional(subject, "source", &a.extras().source, &b.extras().source);
+        self.summary(subject, "links", &a.extras().links, &b.extras().links);
+        self.optional(subject, "symbol", &a.extras().symbol, &b.extras().symbol);
+        self.optional(subject, "type", &a.extras()._type, &b.extras()._type);
+        self.decimal(
+            subject,
+            "geoidheight",
+            a.extras().geoidheight,
+            b.extras().geoidheight,
+        );
+        self.optional(subject, "fix", &a.extras().fix, &b.extras().fix);
+        self.optional(subject, "sat", &a.extras().sat, &b.extras().sat);
+        self.decimal(subject, "hdop", a.extras().hdop, b.extras().hdop);
+        self.decimal(subject, "vdop", a.extras().vdop, b.extras().vdop);
+        self.decimal(subject, "pdop", a.extras().pdop, b.extras().pdop);
+        self.decimal(
+            subject,
+            "dgps_age",
+            a.extras().dgps_age,
+            b.extras().dgps_age,
+        );
+        self.optional(subject, "dgpsid", &a.extras().dgpsid, &b.extras().dgpsid);
+        self.extensions(subject, &a.extras().extensions, &b.extras().extensions);
+    }
+
+    fn route(&mut self, subject: &str, a: &Route, b: &Route) {
+        self.optional(subject, "name", &a.name, &b.name);
+        self.optional(subject, "comment", &a.comment, &b.comment);
+        self.optional(subject, "description", &a.description, &b.description);
+        self.optional(subject, "source", &a.source, &b.source);
+        self.summary(subject, "links", &a.links, &b.links);
+        self.optional(subject, "number", &a.number, &b.number);
+        self.optional(subject, "type", &a._type, &b._type);
+        self.extensions(subject, &a.extensions, &b.extensions);
+        self.points(subject, &a.points, &b.points);
+    }
+
+    fn track(&mut self, subject: &str, a: &Track, b: &Track) {
+        self.optional(subject, "name", &a.name, &b.name);
+        self.optional(subject, "comment", &a.comment, &b.comment);
+        self.optional(subject, "description", &a.description, &b.description);
+        self.optional(subject, "source", &a.source, &b.source);
+        self.summary(subject, "links", &a.links, &b.links);
+        self.optional(subject, "type", &a._type, &b._type);
+        self.optional(subject, "number", &a.number, &b.number);
+        self.extensions(subject, &a.extensions, &b.extensions);
+        for (i, (a_segment, b_segment)) in a.segments.iter().zip(&b.segments).enumerate() {
+            self.segment(&format!("{} segment {}", subject, i), a_segment, b_segment);
+        }
+        for i in b.segments.len()..a.segments.len() {
+            self.lines
+                .push(format!("{} segment {} removed", subject, i));
+        }
+        for i in a.segments.len()..b.segments.len() {
+            self.lines.push(format!("{} segment {} added", subject, i));
+        }
+    }
+
+    fn segment(&mut self, subject: &str, a: &TrackSegment, b: &TrackSegment) {
+        self.extensions(subject, &a.extensions, &b.extensions);
+        self.points(subject, &a.points, &b.points);
+    }
+
+    /// Compares the points of two routes or segments.
+    fn points(&mut self, subject: &str, a: &[Waypoint], b: &[Waypoint]) {
+        let edits = align(a.len(), b.len(), |i, j| {
+            let (a_point, b_point) = (a[i].point(), b[j].point());
+            self.decimals_equal(Some(a_point.y()), Some(b_point.y()))
+                && self.decimals_equal(Some(a_point.x()), Some(b_point.x()))
+        });
+
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for edit in edits {
+            match edit {
+                Edit::Remove(i) => removed.push(i),
+                Edit::Add(j) => added.push(j),
+                Edit::Keep(i, j) => {
+                    let mut point_diff = Diff {
+                        options: self.options,
+                        lines: Vec::new(),
+                    };
+                    point_diff.waypoint(&format!("{} point {}", subject, i), &a[i], &b[j]);
+                    if !point_diff.lines.is_empty() {
+                        changed.push(point_diff.lines);
+                    }
+                }
+            }
+        }
+
+        if self.options.list_points {
+            if !removed.is_empty() {
+                self.lines
+                    .push(format!("{}: {} removed", subject, describe_runs(&removed)));
+            }
+            if !added.is_empty() {
+                self.lines
+                    .push(format!("{}: {} added", subject, describe_runs(&added)));
+            }
+            self.lines.extend(changed.into_iter().flatten());
+            return;
+        }
+
+        let mut counts = Vec::new();
+        if !removed.is_empty() {
+            counts.push(count(removed.len(), "removed"));
+        }
+        if !added.is_empty() {
+            counts.push(count(added.len(), "added"));
+        }
+        if !changed.is_empty() {
+            counts.push(count(changed.len(), "changed"));
+        }
+        // Only removing points in between is what simplifying a line does.
+        let endpoints_kept =
+            removed.first() != Some(&0) && removed.last() != a.len().checked_sub(1).as_ref();
+        if added.is_empty() && changed.is_empty() && !removed.is_empty() && endpoints_kept {
+            counts[0].push_str(" (simplified)");
+        }
+        if !counts.is_empty() {
+            self.lines
+                .push(format!("{}: {}", subject, counts.join(", ")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use super::{align, describe_runs, format_diff, DiffOptions, Edit};
+    use crate::{Gpx, Track, TrackSegment, Waypoint};
+
+    fn waypoint(name: Option<&str>, elevation: f64) -> Waypoint {
+        let mut waypoint = Waypoint::new(Point::new(11.4, 47.2));
+        waypoint.name = name.map(String::from);
+        waypoint.elevation = Some(elevation);
+        waypoint
+    }
+
+    fn edits(a: &str, b: &str) -> Vec<Edit> {
+        let (a, b) = (a.as_bytes(), b.as_bytes());
+        align(a.len(), b.len(), |i, j| a[i] == b[j])
+    }
+
+    #[test]
+    fn align_finds_fewest_edits() {
+        assert_eq!(
+            edits("abcde", "ace"),
+            vec![
+                Edit::Keep(0, 0),
+                Edit::Remove(1),
+                Edit::Keep(2, 1),
+                Edit::Remove(3),
+                Edit::Keep(4, 2),
+            ]
+        );
+        assert_eq!(
+            edits("ab", "xab"),
+            vec![Edit::Add(0), Edit::Keep(0, 1), Edit::Keep(1, 2)]
+        );
+        assert_eq!(edits("", ""), vec![]);
+        assert_eq!(edits("a", ""), vec![Edit::Remove(0)]);
+    }
+
+    #[test]
+    fn align_falls_back_to_indices() {
+        let a = vec![0; 600];
+        let b = vec![1; 700];
+        let edits = align(a.len(), b.len(), |i, j| a[i] == b[j]);
+
+        assert_eq!(edits.len(), 700);
+        assert_eq!(edits[0], Edit::Keep(0, 0));
+        assert_eq!(edits[699], Edit::Add(699));
+    }
+
+    #[test]
+    fn runs() {
+        assert_eq!(describe_runs(&[3]), "point 3");
+        assert_eq!(describe_runs(&[3, 4, 5, 9, 11, 12]), "points 3-5, 9, 11-12");
+    }
+
+    #[test]
+    fn waypoints_matched_by_name() {
+        let a = Gpx {
+            waypoints: vec![
+                waypoint(Some("Hut"), 1800.0),
+                waypoint(Some("Summit"), 2101.0),
+            ],
+            ..Default::default()
+        };
+        let b = Gpx {
+            waypoints: vec![
+                waypoint(Some("Summit"), 2103.5),
+                waypoint(Some("Lake"), 1500.0),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_diff(&a, &b, &DiffOptions::default()),
+            "waypoint 'Hut' removed\n\
+             waypoint 'Summit': elevation 2101.0 → 2103.5\n\
+             waypoint 'Lake' added\n"
+        );
+    }
+
+    #[test]
+    fn waypoints_matched_by_index() {
+        let a = Gpx {
+            waypoints: vec![waypoint(Some("Summit"), 2101.0), waypoint(None, 1800.0)],
+            ..Default::default()
+        };
+        let mut b = a.clone();
+        b.waypoints[1].elevation = Some(1800.5);
+        b.waypoints.push(waypoint(Some("Summit"), 0.0));
+
+        assert_eq!(
+            format_diff(&a, &b, &DiffOptions::default()),
+            "waypoint 1: elevation 1800.0 → 1800.5\nwaypoint 2 'Summit' added\n"
+        );
+        let options = DiffOptions {
+            tolerance: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(format_diff(&a, &b, &options), "waypoint 2 'Summit' added\n");
+    }
+
+    #[test]
+    fn empty_segments() {
+        let mut track = Track::new();
+        track.segments.push(TrackSegment::new());
+        let a = Gpx {
+            tracks: vec![track],
+            ..Default::default()
+        };
+        assert_eq!(format_diff(&a, &a, &DiffOptions::default()), "");
+
+        let mut b = a.clone();
+        b.tracks[0].segments[0].points.push(waypoint(None, 1800.0));
+        assert_eq!(
+            format_diff(&b, &a, &DiffOptions::default()),
+            "track 0 segment 0: 1 point removed\n"
+        );
+        assert_eq!(
+            format_diff(&a, &b, &DiffOptions::default()),
+            "track 0 segment 0: 1 point added\n"
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! display reads and writes how mapping apps draw a track, from the
+//! `gpx_style` extension or Garmin's track extension.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dom::{Element, Node, OwnedName};
+use crate::errors::{GpxError, GpxResult};
+use crate::garmin::{gpxx_element, is_gpxx, push_text, GarminColor};
+use crate::Track;
+
+/// The namespace of the `gpx_style` extension, version 0.2.
+pub const GPX_STYLE_NAMESPACE: &str = "http://www.topografix.com/GPX/gpx_style/0/2";
+
+/// The prefix new elements in [`GPX_STYLE_NAMESPACE`] are written with.
+const GPX_STYLE_PREFIX: &str = "gpx_style";
+
+/// An RGB color.
+///
+/// Colors are parsed from hex values like `FF0000` or `#ff0000`, or from
+/// the names of [`GarminColor`]s like `DarkRed`, and are displayed as hex
+/// values without `#`.
+///
+/// ```
+/// use gpx::Color;
+///
+/// let red: Color = "#ff0000".parse().unwrap();
+/// assert_eq!(red, "Red".parse().unwrap());
+/// assert_eq!(red.to_string(), "FF0000");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl FromStr for Color {
+    type Err = GpxError;
+
+    fn from_str(value: &str) -> GpxResult<Color> {
+        let trimmed = value.trim();
+        let hex = trimmed.strip_prefix('#').unwrap_or(trimmed);
+        if hex.len() == 6 && hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            let channel = |start: usize| u8::from_str_radix(&hex[start..start + 2], 16);
+            if let (Ok(red), Ok(green), Ok(blue)) = (channel(0), channel(2), channel(4)) {
+                return Ok(Color { red, green, blue });
+            }
+        }
+        GarminColor::from_str(trimmed)
+            .ok()
+            .and_then(GarminColor::to_color)
+            .ok_or_else(|| GpxError::InvalidColor(value.into()))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+    }
+}
+
+/// How a track is drawn on a map.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrackDisplay {
+    /// The color of the line.
+    pub color: Option<Color>,
+
+    /// The opacity of the line, from 0 for invisible to 1 for opaque.
+    pub opacity: Option<f64>,
+
+    /// The width of the line, in millimeters.
+    pub width: Option<f64>,
+}
+
+/// The extension [`Track::set_display`] writes to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplaySchema {
+    /// `<gpx_style:line>`, which has a color, opacity and width.
+    GpxStyle,
+    /// Garmin's `<gpxx:TrackExtension>`, which only has a color. Colors are
+    /// written as the nearest [`GarminColor`].
+    Garmin,
+}
+
+impl Track {
+    /// Reads how the track is drawn from its extensions, or gives `None` if
+    /// they say nothing about it.
+    ///
+    /// `<gpx_style:line>` is preferred. A color from
+    /// `<gpxx:TrackExtension>` is used if the style has none. Values that
+    /// cannot be parsed are left out.
+    ///
+    /// ```
+    /// use gpx::{DisplaySchema, Track, TrackDisplay};
+    ///
+    /// let mut track = Track::new();
+    /// let display = TrackDisplay {
+    ///     color: Some("DarkRed".parse().unwrap()),
+    ///     opacity: Some(0.5),
+    ///     width: None,
+    /// };
+    /// track.set_display(&display, DisplaySchema::GpxStyle);
+    ///
+    /// assert_eq!(track.display(), Some(display));
+    /// ```
+    pub fn display(&self) -> Option<TrackDisplay> {
+        let extensions = self.extensions.as_ref()?;
+        let mut display = TrackDisplay::default();
+        if let Some(line) = extensions
+            .elements()
+            .find(|element| is_gpx_style(&element.name, "line"))
+        {
+            for child in line.elements() {
+                if child.name.namespace.as_deref() != Some(GPX_STYLE_NAMESPACE) {
+                    continue;
+                }
+                let text = child.text();
+                match child.name.local_name.as_str() {
+                    "color" => display.color = text.parse().ok(),
+                    "opacity" => display.opacity = text.trim().parse().ok(),
+                    "width" => display.width = text.trim().parse().ok(),
+                    _ => {}
+                }
+            }
+        }
+        if display.color.is_none() {
+            display.color = extensions
+                .elements()
+                .filter(|element| is_gpxx(&element.name, "TrackExtension"))
+                .flat_map(Element::elements)
+                .find(|element| is_gpxx(&element.name, "DisplayColor"))
+                .and_then(|element| element.text().parse::<GarminColor>().ok())
+                .and_then(GarminColor::to_color);
+        }
+        if display == TrackDisplay::default() {
+            None
+        } else {
+            Some(display)
+        }
+    }
+
+    /// Stores how the track is drawn in the extension given by `schema`,
+    /// creating the extensions if needed.
+    ///
+    /// The values of an existing element of that extension are replaced,
+    /// with `None` removing them. Its other children, and other extensions,
+    /// are left alone.
+    pub fn set_display(&mut self, display: &TrackDisplay, schema: DisplaySchema) {
+        let extensions = self
+            .extensions
+            .get_or_insert_with(|| Element::new(OwnedName::local("extensions")));
+        let (local_name, replaced): (&str, &[&str]) = match schema {
+            DisplaySchema::GpxStyle => ("line", &["color", "opacity", "width"]),
+            DisplaySchema::Garmin => ("TrackExtension", &["DisplayColor"]),
+        };
+        let is_target = |name: &OwnedName| match schema {
+            DisplaySchema::GpxStyle => is_gpx_style(name, local_name),
+            DisplaySchema::Garmin => is_gpxx(name, local_name),
+        };
+
+        let position = extensions.children.iter().position(|child| match child {
+            Node::Element(child) => is_target(&child.name),
+            Node::Text(_) => false,
+        });
+        let index = position.unwrap_or_else(|| {
+            let element = match schema {
+                DisplaySchema::GpxStyle => gpx_style_element(local_name),
+                DisplaySchema::Garmin => gpxx_element(local_name),
+            };
+            extensions.children.push(Node::Element(element));
+            extensions.children.len() - 1
+        });
+        let target = match &mut extensions.children[index] {
+            Node::Element(target) => target,
+            Node::Text(_) => unreachable!("the index is that of an element"),
+        };
+
+        // The values come first in both schemas, so they are put in front
+        // of whatever else the element holds.
+        let rest: Vec<Node> = std::mem::take(&mut target.children)
+            .into_iter()
+            .filter(|child| match child {
+                Node::Element(child) => {
+                    !(replaced.contains(&child.name.local_name.as_str())
+                        && is_in_schema(child, schema))
+                }
+                Node::Text(_) => true,
+            })
+            .collect();
+        match schema {
+            DisplaySchema::GpxStyle => {
+                push_gpx_style_text(target, "color", display.color);
+                push_gpx_style_text(target, "opacity", display.opacity);
+                push_gpx_style_text(target, "width", display.width);
+            }
+            DisplaySchema::Garmin => {
+                let color = display
+                    .color
+                    .map(|color| GarminColor::nearest(color).name());
+                push_text(target, "DisplayColor", color);
+            }
+        }
+        target.children.extend(rest);
+    }
+}
+
+fn is_in_schema(element: &Element, schema: DisplaySchema) -> bool {
+    let namespace = match schema {
+        DisplaySchema::GpxStyle => GPX_STYLE_NAMESPACE,
+        DisplaySchema::Garmin => crate::garmin::GPXX_NAMESPACE,
+    };
+    element.name.namespace.as_deref() == Some(namespace)
+}
+
+fn is_gpx_style(name: &OwnedName, local_name: &str) -> bool {
+    name.local_name == local_name && name.namespace.as_deref() == Some(GPX_STYLE_NAMESPACE)
+}
+
+fn gpx_style_element(local_name: &str) -> Element {
+    Element::new(OwnedName::qualified(
+        local_name,
+        GPX_STYLE_NAMESPACE,
+        Some(GPX_STYLE_PREFIX.into()),
+    ))
+}
+
+fn push_gpx_style_text(parent: &mut Element, local_name: &str, value: Option<impl ToString>) {
+    if let Some(value) = value {
+        let mut element = gpx_style_element(local_name);
+        element.children.push(Node::Text(value.to_string()));
+        parent.children.push(Node::Element(element));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, DisplaySchema, TrackDisplay};
+    use crate::dom::Element;
+    use crate::garmin::GarminColor;
+    use crate::{read, write_to_string, Gpx, GpxVersion, Track};
+
+    fn round_trip(track: Track) -> Track {
+        let gpx = Gpx {
+            version: GpxVersion::Gpx11,
+            tracks: vec![track],
+            ..Default::default()
+        };
+        read(write_to_string(&gpx).unwrap().as_bytes())
+            .unwrap()
+            .tracks
+            .remove(0)
+    }
+
+    #[test]
+    fn gpx_style_round_trip() {
+        let display = TrackDisplay {
+            color: Some(Color {
+                red: 0x12,
+                green: 0xab,
+                blue: 0xef,
+            }),
+            opacity: Some(0.75),
+            width: Some(2.5),
+        };
+        let mut track = Track::new();
+        track.set_display(&display, DisplaySchema::GpxStyle);
+        let track = round_trip(track);
+        assert_eq!(track.display(), Some(display));
+
+        // Replacing keeps only what is given.
+        let mut track = track;
+        let color_only = TrackDisplay {
+            color: Some("#00ff00".parse().unwrap()),
+            ..Default::default()
+        };
+        track.set_display(&color_only, DisplaySchema::GpxStyle);
+        assert_eq!(round_trip(track).display(), Some(color_only));
+    }
+
+    #[test]
+    fn garmin_round_trip() {
+        let mut track = Track::new();
+        let display = TrackDisplay {
+            color: Some(GarminColor::DarkCyan.to_color().unwrap()),
+            opacity: Some(0.5),
+            width: None,
+        };
+        track.set_display(&display, DisplaySchema::Garmin);
+        let track = round_trip(track);
+
+        let extension: Vec<&Element> = track
+            .extensions
+            .iter()
+            .flat_map(Element::elements)
+            .collect();
+        assert_eq!(extension.len(), 1);
+        assert_eq!(extension[0].name.local_name, "TrackExtension");
+        assert_eq!(extension[0].elements().next().unwrap().text(), "DarkCyan");
+        // Garmin has no opacity.
+        assert_eq!(
+            track.display(),
+            Some(TrackDisplay {
+                opacity: None,
+                ..display
+            })
+        );
+    }
+
+    #[test]
+    fn gpx_style_is_preferred() {
+        let mut track = Track::new();
+        let garmin = TrackDisplay {
+            color: Some(GarminColor::Blue.to_color().unwrap()),
+            ..Default::default()
+        };
+        track.set_display(&garmin, DisplaySchema::Garmin);
+        let width = TrackDisplay {
+            width: Some(4.0),
+            ..Default::default()
+        };
+        track.set_display(&width, DisplaySchema::GpxStyle);
+        assert_eq!(
+            track.display(),
+            Some(TrackDisplay {
+                width: Some(4.0),
+                ..garmin.clone()
+            })
+        );
+
+        let red = TrackDisplay {
+            color: Some("FF0000".parse().unwrap()),
+            ..width
+        };
+        track.set_display(&red, DisplaySchema::GpxStyle);
+        assert_eq!(track.display(), Some(red));
+        assert_eq!(Track::new().display(), None);
+    }
+
+    #[test]
+    fn garmin_color_names_and_hex() {
+        let dark_red: Color = "DarkRed".parse().unwrap();
+        assert_eq!(dark_red.to_string(), "8B0000");
+        let hex: Color = dark_red.to_string().parse().unwrap();
+        assert_eq!(GarminColor::nearest(hex), GarminColor::DarkRed);
+        assert_eq!(GarminColor::nearest(hex).name(), "DarkRed");
+
+        assert_eq!(
+            "magenta".parse::<GarminColor>().unwrap(),
+            GarminColor::Magenta
+        );
+        assert_eq!(GarminColor::Transparent.to_color(), None);
+        assert!("Transparent".parse::<Color>().is_err());
+        assert!("12345".parse::<Color>().is_err());
+        assert!("Mauve".parse::<GarminColor>().is_err());
+    }
+}
@@ -0,0 +1,424 @@
+//! distance measures tracks along their path.
+
+use geo_types::Point;
+
+use crate::{Track, TrackSegment, Waypoint};
+
+/// Mean earth radius in meters, used for all distances on the sphere, such as
+/// [`TrackSegment::cumulative_distances`] and
+/// [`Waypoint::haversine_distance_to`].
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Gives the great-circle distance between two points in meters, using the
+/// haversine formula.
+pub(crate) fn haversine(a: Point<f64>, b: Point<f64>) -> f64 {
+    let (lat_a, lat_b) = (a.y().to_radians(), b.y().to_radians());
+    let delta_lat = lat_b - lat_a;
+    let delta_lon = (b.x() - a.x()).to_radians();
+    let h = (delta_lat / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * (delta_lon / 2.0).sin().powi(2);
+    // Rounding can push h just above 1 for antipodal points.
+    2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+}
+
+impl Waypoint {
+    /// Gives the great-circle distance to `other` in meters, using the
+    /// haversine formula, as [`TrackSegment::cumulative_distances`] does.
+    pub fn haversine_distance_to(&self, other: &Waypoint) -> f64 {
+        haversine(self.point(), other.point())
+    }
+
+    /// Gives the initial bearing of the great circle to `other`, in degrees
+    /// clockwise from north, from 0 up to 360. The bearing to the same
+    /// position is 0.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let origin = Waypoint::new_lat_lon(0.0, 0.0).unwrap();
+    /// let east = Waypoint::new_lat_lon(0.0, 1.0).unwrap();
+    ///
+    /// assert!((origin.bearing_to(&east) - 90.0).abs() < 1e-9);
+    /// assert!((east.bearing_to(&origin) - 270.0).abs() < 1e-9);
+    /// ```
+    pub fn bearing_to(&self, other: &Waypoint) -> f64 {
+        let (lat_a, lat_b) = (self.lat().to_radians(), other.lat().to_radians());
+        let delta_lon = (other.lon() - self.lon()).to_radians();
+        let y = delta_lon.sin() * lat_b.cos();
+        let x = lat_a.cos() * lat_b.sin() - lat_a.sin() * lat_b.cos() * delta_lon.cos();
+        let bearing = y.atan2(x).to_degrees().rem_euclid(360.0);
+        // rem_euclid gives 360 for tiny negative bearings.
+        if bearing >= 360.0 {
+            0.0
+        } else {
+            bearing
+        }
+    }
+
+    /// Gives the position reached by following the great circle with the
+    /// initial bearing `bearing_deg`, in degrees clockwise from north, for
+    /// `distance_m` meters. The longitude is normalized to [-180, 180).
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let origin = Waypoint::new_lat_lon(0.0, 0.0).unwrap();
+    /// let north = origin.destination(0.0, 111_195.08);
+    ///
+    /// assert!(north.x().abs() < 1e-9);
+    /// assert!((north.y() - 1.0).abs() < 1e-6);
+    /// ```
+    pub fn destination(&self, bearing_deg: f64, distance_m: f64) -> Point<f64> {
+        let start = self.point();
+        let (lat, lon) = (start.y().to_radians(), start.x().to_radians());
+        let bearing = bearing_deg.to_radians();
+        let angle = distance_m / EARTH_RADIUS_M;
+
+        let sin_lat = lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos();
+        let lat_end = sin_lat.clamp(-1.0, 1.0).asin();
+        let lon_end = lon
+            + (bearing.sin() * angle.sin() * lat.cos()).atan2(angle.cos() - lat.sin() * sin_lat);
+        let lon_end = (lon_end.to_degrees() + 180.0).rem_euclid(360.0) - 180.0;
+        Point::new(lon_end, lat_end.to_degrees())
+    }
+}
+
+/// A position along a track, as found by [`Track::point_at_distance`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackPosition {
+    /// Index of the segment the position lies in.
+    pub segment: usize,
+
+    /// Index of the point in the segment at or right before the position.
+    pub point: usize,
+
+    /// The position, interpolated between the bracketing points.
+    pub position: Point<f64>,
+
+    /// The elevation, interpolated between the bracketing points. Only set
+    /// if both of them have an elevation.
+    pub elevation: Option<f64>,
+}
+
+impl TrackSegment {
+    /// Gives the distance in meters from the first point to each point of the
+    /// segment, measured along the segment.
+    ///
+    /// The result has one entry per point, starting with `0.0`. Distances are
+    /// great-circle distances using the haversine formula.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// segment.points.push(Waypoint::new_lat_lon(0.0, 0.0).unwrap());
+    /// segment.points.push(Waypoint::new_lat_lon(1.0, 0.0).unwrap());
+    ///
+    /// let distances = segment.cumulative_distances();
+    /// assert_eq!(distances[0], 0.0);
+    /// assert!((distances[1] - 111_195.0).abs() < 1.0);
+    /// ```
+    pub fn cumulative_distances(&self) -> Vec<f64> {
+        cumulative_distances_from(&self.points, 0.0)
+    }
+}
+
+impl Track {
+    /// Gives the cumulative distances of the points of each segment, see
+    /// [`TrackSegment::cumulative_distances`].
+    ///
+    /// The result has one entry per segment. If `reset_per_segment` is set,
+    /// every segment starts at `0.0`. Otherwise, the distances continue from
+    /// the end of the previous segment. The gap between two segments is never
+    /// counted, as it is not known how it was covered.
+    pub fn cumulative_distances(&self, reset_per_segment: bool) -> Vec<Vec<f64>> {
+        let mut offset = 0.0;
+        self.segments
+            .iter()
+            .map(|segment| {
+                let distances = cumulative_distances_from(&segment.points, offset);
+                if !reset_per_segment {
+                    offset = distances.last().copied().unwrap_or(offset);
+                }
+                distances
+            })
+            .collect()
+    }
+
+    /// Finds the position that lies `meters` along the track, counting the
+    /// distances continuously across segments.
+    ///
+    /// The position and elevation are interpolated linearly between the two
+    /// points bracketing the distance. Returns `None` for distances outside
+    /// of the track, and for tracks without length.
+    ///
+    /// ```
+    /// use gpx::{Track, TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// segment.points.push(Waypoint::new_lat_lon(0.0, 0.0).unwrap());
+    /// segment.points.push(Waypoint::new_lat_lon(0.0, 1.0).unwrap());
+    /// let mut track = Track::new();
+    /// track.segments.push(segment);
+    ///
+    /// let halfway = track.point_at_distance(55_597.5).unwrap();
+    /// assert_eq!((halfway.segment, halfway.point), (0, 0));
+    /// assert!((halfway.position.x() - 0.5).abs() < 1e-6);
+    /// assert!(track.point_at_distance(200_000.0).is_none());
+    /// ```
+    pub fn point_at_distance(&self, meters: f64) -> Option<TrackPosition> {
+        let distances = self.cumulative_distances(false);
+        let total = distances.iter().rev().find_map(|d| d.last().copied())?;
+        if total <= 0.0 || !(0.0..=total).contains(&meters) {
+            return None;
+        }
+        for (segment_index, (segment, distances)) in
+            self.segments.iter().zip(&distances).enumerate()
+        {
+            for (index, point) in segment.points.iter().enumerate() {
+                if distances[index] == meters {
+                    return Some(TrackPosition {
+                        segment: segment_index,
+                        point: index,
+                        position: point.point(),
+                        elevation: point.elevation,
+                    });
+                }
+                let next = match segment.points.get(index + 1) {
+                    Some(next) if distances[index + 1] > meters => next,
+                    _ => continue,
+                };
+                let fraction =
+                    (meters - distances[index]) / (distances[index + 1] - distances[index]);
+                return Some(TrackPosition {
+                    segment: segment_index,
+                    point: index,
+                    position: interpolate_point(point.point(), next.point(), fraction),
+                    elevation: interpolate_elevation(point, next, fraction),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Gives the length of `track` in meters, without the gaps between segments.
+pub(crate) fn track_length(track: &Track) -> f64 {
+    track
+        .segments
+        .iter()
+        .filter_map(|segment| segment.cumulative_distances().last().copied())
+        .sum()
+}
+
+fn cumulative_distances_from(points: &[Waypoint], offset: f64) -> Vec<f64> {
+    let mut total = offset;
+    let mut distances = Vec::with_capacity(points.len());
+    let mut previous: Option<Point<f64>> = None;
+    for point in points {
+        if let Some(previous) = previous {
+            total += haversine(previous, point.point());
+        }
+        distances.push(total);
+        previous = Some(point.point());
+    }
+    distances
+}
+
+/// Interpolates linearly between `a` (at `fraction` 0) and `b` (at 1).
+///
+/// The longitude takes the short way, so between 179.9 and -179.9 it
+/// crosses the antimeridian rather than going around the globe.
+pub(crate) fn interpolate_point(a: Point<f64>, b: Point<f64>, fraction: f64) -> Point<f64> {
+    let delta_lon = (b.x() - a.x() + 180.0).rem_euclid(360.0) - 180.0;
+    let mut lon = a.x() + delta_lon * fraction;
+    if !(-180.0..=180.0).contains(&lon) {
+        lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    }
+    Point::new(lon, a.y() + (b.y() - a.y()) * fraction)
+}
+
+/// Interpolates the elevation between `a` and `b`, if both have one.
+pub(crate) fn interpolate_elevation(a: &Waypoint, b: &Waypoint, fraction: f64) -> Option<f64> {
+    match (a.elevation, b.elevation) {
+        // Unlike a + (b - a) * fraction, this does not overflow for
+        // elevations near the ends of f64.
+        (Some(a), Some(b)) => Some(a * (1.0 - fraction) + b * fraction),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use geo_types::Point;
+
+    use super::{haversine, EARTH_RADIUS_M};
+    use crate::{Track, TrackSegment, Waypoint};
+
+    /// Distance between two points on the equator, 0.001° of longitude apart.
+    const SPACING: f64 = 111.19508;
+
+    /// A straight line along the equator, with one point every `SPACING`
+    /// meters and the elevation rising by 10 meters per point.
+    fn straight_segment(start: usize, count: usize) -> TrackSegment {
+        TrackSegment {
+            points: (start..start + count)
+                .map(|i| {
+                    let mut wpt = Waypoint::new(Point::new(i as f64 * 0.001, 0.0));
+                    wpt.elevation = Some(i as f64 * 10.0);
+                    wpt
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn straight_track() -> Track {
+        let mut track = Track::new();
+        track.segments.push(straight_segment(0, 3));
+        track.segments.push(straight_segment(2, 3));
+        track
+    }
+
+    #[test]
+    fn haversine_known_distance() {
+        // One degree of latitude.
+        assert_approx_eq!(
+            haversine(Point::new(0.0, 0.0), Point::new(0.0, 1.0)),
+            111_195.08,
+            0.01
+        );
+    }
+
+    /// Los Angeles and New York airports, from the Aviation Formulary's
+    /// worked example.
+    fn lax_jfk() -> (Waypoint, Waypoint) {
+        (
+            Waypoint::new(Point::new(-(118.0 + 24.0 / 60.0), 33.0 + 57.0 / 60.0)),
+            Waypoint::new(Point::new(-(73.0 + 47.0 / 60.0), 40.0 + 38.0 / 60.0)),
+        )
+    }
+
+    #[test]
+    fn waypoint_distance_and_bearing() {
+        let (lax, jfk) = lax_jfk();
+
+        // 2144 nautical miles, initial course 66°.
+        let distance = lax.haversine_distance_to(&jfk);
+        assert!((distance / (2144.0 * 1852.0) - 1.0).abs() < 0.005);
+        assert_eq!(
+            distance,
+            haversine(lax.point(), jfk.point()),
+            "must match the segment lengths"
+        );
+        assert!((lax.bearing_to(&jfk) / 66.0 - 1.0).abs() < 0.005);
+    }
+
+    #[test]
+    fn waypoint_destination_reaches_other() {
+        let (lax, jfk) = lax_jfk();
+
+        let reached = lax.destination(lax.bearing_to(&jfk), lax.haversine_distance_to(&jfk));
+        assert_approx_eq!(reached.x(), jfk.point().x(), 1e-9);
+        assert_approx_eq!(reached.y(), jfk.point().y(), 1e-9);
+
+        // Across the antimeridian.
+        let fiji = Waypoint::new(Point::new(179.5, -17.0));
+        let across = fiji.destination(90.0, 200_000.0).x();
+        assert!((-179.0..-178.0).contains(&across));
+    }
+
+    #[test]
+    fn waypoint_identical_and_antipodal() {
+        let point = Waypoint::new(Point::new(13.4, 52.5));
+        assert_eq!(point.haversine_distance_to(&point), 0.0);
+        assert_eq!(point.bearing_to(&point), 0.0);
+        let unmoved = point.destination(45.0, 0.0);
+        assert_approx_eq!(unmoved.x(), 13.4, 1e-9);
+        assert_approx_eq!(unmoved.y(), 52.5, 1e-9);
+
+        let antipode = Waypoint::new(Point::new(13.4 - 180.0, -52.5));
+        let distance = point.haversine_distance_to(&antipode);
+        assert_approx_eq!(distance, std::f64::consts::PI * EARTH_RADIUS_M, 1e-6);
+        let bearing = point.bearing_to(&antipode);
+        assert!((0.0..360.0).contains(&bearing));
+    }
+
+    #[test]
+    fn segment_cumulative_distances() {
+        let distances = straight_segment(0, 4).cumulative_distances();
+
+        assert_eq!(distances.len(), 4);
+        assert_eq!(distances[0], 0.0);
+        for (i, distance) in distances.iter().enumerate() {
+            assert_approx_eq!(*distance, i as f64 * SPACING, 1e-3);
+        }
+        assert!(TrackSegment::new().cumulative_distances().is_empty());
+    }
+
+    #[test]
+    fn track_cumulative_distances() {
+        let track = straight_track();
+
+        let continued = track.cumulative_distances(false);
+        assert_approx_eq!(continued[1][0], 2.0 * SPACING, 1e-3);
+        assert_approx_eq!(continued[1][2], 4.0 * SPACING, 1e-3);
+
+        let reset = track.cumulative_distances(true);
+        assert_eq!(reset[1][0], 0.0);
+        assert_approx_eq!(reset[1][2], 2.0 * SPACING, 1e-3);
+    }
+
+    #[test]
+    fn point_at_distance_interpolates() {
+        let track = straight_track();
+
+        let position = track.point_at_distance(1.5 * SPACING).unwrap();
+        assert_eq!((position.segment, position.point), (0, 1));
+        assert_approx_eq!(position.position.x(), 0.0015, 1e-9);
+        assert_approx_eq!(position.elevation.unwrap(), 15.0, 1e-6);
+
+        let position = track.point_at_distance(3.25 * SPACING).unwrap();
+        assert_eq!((position.segment, position.point), (1, 1));
+        assert_approx_eq!(position.position.x(), 0.00325, 1e-9);
+    }
+
+    #[test]
+    fn point_at_distance_ends() {
+        let track = straight_track();
+        let distances = track.cumulative_distances(false);
+        let total = distances[1][2];
+
+        let start = track.point_at_distance(0.0).unwrap();
+        assert_eq!((start.segment, start.point), (0, 0));
+        assert_eq!(start.elevation, Some(0.0));
+
+        let end = track.point_at_distance(total).unwrap();
+        assert_eq!((end.segment, end.point), (1, 2));
+        assert_eq!(end.position, Point::new(0.004, 0.0));
+    }
+
+    #[test]
+    fn point_at_distance_without_elevation() {
+        let mut track = straight_track();
+        track.segments[0].points[1].elevation = None;
+
+        let position = track.point_at_distance(0.5 * SPACING).unwrap();
+        assert_eq!(position.elevation, None);
+    }
+
+    #[test]
+    fn point_at_distance_out_of_range() {
+        let track = straight_track();
+
+        assert!(track.point_at_distance(-1.0).is_none());
+        assert!(track.point_at_distance(5.0 * SPACING).is_none());
+        assert!(track.point_at_distance(f64::NAN).is_none());
+
+        let mut single = Track::new();
+        single.segments.push(straight_segment(0, 1));
+        assert!(single.point_at_distance(0.0).is_none());
+        assert!(Track::new().point_at_distance(0.0).is_none());
+    }
+}
@@ -0,0 +1,375 @@
+//! dom holds XML content that is kept as-is rather than mapped onto the GPX
+//! types, such as extensions and attributes the GPX schema does not define.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub use crate::namespaces::{Namespace, PrefixConflict, PrefixRenames};
+pub use crate::point_sequence::point_sequence_to_element;
+
+/// The namespace of the `xml:` prefix, which is always bound.
+pub const NS_XML_URI: &str = xml::namespace::NS_XML_URI;
+
+/// An immutable string that is cheap to clone, as clones share one
+/// allocation.
+///
+/// The parts of [`OwnedName`] are kept this way, as the same few names and
+/// namespaces repeat for every point of a track with extensions. The reader
+/// interns them, so every `<gpxtpx:hr>` of a document shares its
+/// `"gpxtpx"`, `"hr"` and namespace URI.
+///
+/// It compares, hashes, orders and serializes like the string it holds.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SharedStr(Arc<str>);
+
+impl SharedStr {
+    /// Gives the string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for SharedStr {
+    fn default() -> SharedStr {
+        SharedStr::from("")
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SharedStr {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for SharedStr {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(string: &str) -> SharedStr {
+        SharedStr(Arc::from(string))
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(string: String) -> SharedStr {
+        SharedStr(Arc::from(string))
+    }
+}
+
+impl From<SharedStr> for String {
+    fn from(string: SharedStr) -> String {
+        string.0.as_ref().to_owned()
+    }
+}
+
+impl PartialEq<str> for SharedStr {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for SharedStr {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for SharedStr {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+impl PartialEq<SharedStr> for str {
+    fn eq(&self, other: &SharedStr) -> bool {
+        self == &*other.0
+    }
+}
+
+impl PartialEq<SharedStr> for &str {
+    fn eq(&self, other: &SharedStr) -> bool {
+        *self == &*other.0
+    }
+}
+
+#[cfg(feature = "use-serde")]
+impl Serialize for SharedStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "use-serde")]
+impl<'de> Deserialize<'de> for SharedStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SharedStr, D::Error> {
+        String::deserialize(deserializer).map(SharedStr::from)
+    }
+}
+
+/// Interner hands out one [`SharedStr`] per distinct string.
+#[derive(Debug, Default)]
+pub(crate) struct Interner {
+    strings: HashSet<SharedStr>,
+}
+
+impl Interner {
+    pub(crate) fn intern(&mut self, string: String) -> SharedStr {
+        if let Some(shared) = self.strings.get(string.as_str()) {
+            return shared.clone();
+        }
+        let shared = SharedStr::from(string);
+        self.strings.insert(shared.clone());
+        shared
+    }
+
+    /// Converts a name from the XML reader, interning its parts.
+    pub(crate) fn name(&mut self, name: xml::name::OwnedName) -> OwnedName {
+        OwnedName {
+            local_name: self.intern(name.local_name),
+            namespace: name.namespace.map(|namespace| self.intern(namespace)),
+            prefix: name.prefix.map(|prefix| self.intern(prefix)),
+        }
+    }
+
+    /// Converts an attribute from the XML reader, interning the parts of
+    /// its name.
+    pub(crate) fn attribute(
+        &mut self,
+        attribute: xml::attribute::OwnedAttribute,
+    ) -> OwnedAttribute {
+        OwnedAttribute {
+            name: self.name(attribute.name),
+            value: attribute.value,
+        }
+    }
+}
+
+/// A qualified XML name, as found in the document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct OwnedName {
+    /// The local name, without the prefix.
+    pub local_name: SharedStr,
+
+    /// The namespace URI the name belongs to, if any.
+    pub namespace: Option<SharedStr>,
+
+    /// The prefix the name was written with, if any.
+    pub prefix: Option<SharedStr>,
+}
+
+impl OwnedName {
+    /// Creates a name without namespace and prefix.
+    pub fn local(local_name: impl Into<SharedStr>) -> OwnedName {
+        OwnedName {
+            local_name: local_name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a name in the given namespace, written with `prefix`, which
+    /// is given like `Some("gpxtpx".into())`.
+    pub fn qualified(
+        local_name: impl Into<SharedStr>,
+        namespace: impl Into<SharedStr>,
+        prefix: Option<SharedStr>,
+    ) -> OwnedName {
+        OwnedName {
+            local_name: local_name.into(),
+            namespace: Some(namespace.into()),
+            prefix,
+        }
+    }
+
+    pub(crate) fn borrow(&self) -> xml::name::Name<'_> {
+        xml::name::Name {
+            local_name: &self.local_name,
+            namespace: self.namespace.as_deref(),
+            prefix: self.prefix.as_deref(),
+        }
+    }
+}
+
+impl From<xml::name::OwnedName> for OwnedName {
+    fn from(name: xml::name::OwnedName) -> OwnedName {
+        OwnedName {
+            local_name: name.local_name.into(),
+            namespace: name.namespace.map(SharedStr::from),
+            prefix: name.prefix.map(SharedStr::from),
+        }
+    }
+}
+
+/// An XML attribute, as found in the document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct OwnedAttribute {
+    /// The qualified name of the attribute.
+    pub name: OwnedName,
+
+    /// The (unescaped) value of the attribute.
+    pub value: String,
+}
+
+impl OwnedAttribute {
+    /// Creates an attribute.
+    pub fn new(name: OwnedName, value: impl Into<String>) -> OwnedAttribute {
+        OwnedAttribute {
+            name,
+            value: value.into(),
+        }
+    }
+}
+
+impl From<xml::attribute::OwnedAttribute> for OwnedAttribute {
+    fn from(attribute: xml::attribute::OwnedAttribute) -> OwnedAttribute {
+        OwnedAttribute {
+            name: attribute.name.into(),
+            value: attribute.value,
+        }
+    }
+}
+
+/// Attributes of an element that are not defined by the GPX schema.
+///
+/// They are only collected when
+/// [`ReadOptions::preserve_unknown_attributes`](crate::ReadOptions::preserve_unknown_attributes)
+/// is enabled, and are written back out by the writer.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct UnknownAttributes {
+    /// The element carrying the attributes, relative to the struct holding
+    /// them: empty for the element of the struct itself (e.g. `<trkpt>` for
+    /// a track point), or the local name of one of its simple child elements
+    /// (e.g. `name` or `desc`).
+    pub element: String,
+
+    /// The attributes, in document order.
+    pub attributes: Vec<OwnedAttribute>,
+}
+
+/// An XML element with its content, as found in the document.
+///
+/// This is how the content of `<extensions>` is kept: the `extensions`
+/// fields of the GPX types hold the `<extensions>` element itself, with the
+/// actual extensions as its children.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct Element {
+    /// The qualified name of the element.
+    pub name: OwnedName,
+
+    /// The attributes of the element, in document order.
+    pub attributes: Vec<OwnedAttribute>,
+
+    /// The content of the element, in document order.
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    /// Creates an element without attributes and content.
+    pub fn new(name: OwnedName) -> Element {
+        Element {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Gives the child elements, skipping text.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Gives the text directly inside this element, not including the text
+    /// of child elements.
+    pub fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                Node::Text(text) => Some(text.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect()
+    }
+
+    /// Gives whether the element keeps its whitespace as written, from its
+    /// `xml:space` attribute: `Some(true)` for `"preserve"`, `Some(false)`
+    /// for `"default"` and `None` without the attribute, in which case the
+    /// element inherits the setting of its parent.
+    pub fn xml_space(&self) -> Option<bool> {
+        self.attributes
+            .iter()
+            .find(|attribute| {
+                attribute.name.local_name.as_str() == "space"
+                    && attribute.name.prefix.as_ref().map(SharedStr::as_str) == Some("xml")
+            })
+            .and_then(|attribute| match attribute.value.as_str() {
+                "preserve" => Some(true),
+                "default" => Some(false),
+                _ => None,
+            })
+    }
+}
+
+/// A processing instruction, such as `<?gpx-pipeline version="3"?>`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct ProcessingInstruction {
+    /// The target of the instruction, `gpx-pipeline` in the example.
+    pub name: String,
+
+    /// Everything after the target, `version="3"` in the example.
+    pub data: Option<String>,
+}
+
+/// A comment or processing instruction before the root element of a
+/// document.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub enum PrologNode {
+    /// The text of a comment, without the `<!--` and `-->`.
+    Comment(String),
+    ProcessingInstruction(ProcessingInstruction),
+}
+
+/// A piece of content of an [`Element`].
+///
+/// Text that is only whitespace, such as indentation, is not kept.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
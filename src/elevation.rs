@@ -0,0 +1,367 @@
+//! elevation fills in missing elevations from an outside source, such as a
+//! service for digital elevation models.
+//!
+//! The source is an [`ElevationProvider`]. Only simple in-memory providers
+//! come with the crate: [`ConstantElevation`] and [`ElevationTable`].
+
+use geo_types::Point;
+
+use crate::distance::haversine;
+use crate::errors::{GpxError, GpxResult};
+use crate::{Gpx, Waypoint};
+
+/// A source of elevations, as used by [`Gpx::fill_elevations`].
+pub trait ElevationProvider {
+    /// Gives the elevation in meters above mean sea level at each of
+    /// `points`, whose `x` is the longitude and `y` the latitude, or `None`
+    /// where the provider has none.
+    ///
+    /// The points come in batches, so that a provider can look them up all
+    /// at once, such as with a single request. The result must have one
+    /// entry per point. Failures of the provider are best reported as
+    /// [`GpxError::ElevationProviderError`].
+    fn elevation(&mut self, points: &[Point<f64>]) -> GpxResult<Vec<Option<f64>>>;
+}
+
+/// Gives the same elevation everywhere.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstantElevation(pub f64);
+
+impl ElevationProvider for ConstantElevation {
+    fn elevation(&mut self, points: &[Point<f64>]) -> GpxResult<Vec<Option<f64>>> {
+        Ok(vec![Some(self.0); points.len()])
+    }
+}
+
+/// Gives the elevation of the nearest of a list of known points, if it is
+/// close enough. Every lookup goes through the whole list, so this is meant
+/// for tests and small areas.
+///
+/// ```
+/// use geo_types::Point;
+/// use gpx::{ElevationProvider, ElevationTable};
+///
+/// let mut table = ElevationTable::new(100.0);
+/// table.insert(Point::new(7.6586, 45.9763), 4478.0);
+///
+/// let near = Point::new(7.6590, 45.9765);
+/// let far = Point::new(7.7, 46.0);
+/// assert_eq!(table.elevation(&[near, far]).unwrap(), [Some(4478.0), None]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ElevationTable {
+    max_distance: f64,
+    entries: Vec<(Point<f64>, f64)>,
+}
+
+impl ElevationTable {
+    /// Creates an empty table that gives elevations for points within
+    /// `max_distance` meters of a known point.
+    pub fn new(max_distance: f64) -> ElevationTable {
+        ElevationTable {
+            max_distance,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds a point with a known elevation.
+    pub fn insert(&mut self, point: Point<f64>, elevation: f64) {
+        self.entries.push((point, elevation));
+    }
+
+    fn lookup(&self, point: Point<f64>) -> Option<f64> {
+        self.entries
+            .iter()
+            .map(|&(known, elevation)| (haversine(known, point), elevation))
+            .filter(|&(distance, _)| distance <= self.max_distance)
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, elevation)| elevation)
+    }
+}
+
+impl ElevationProvider for ElevationTable {
+    fn elevation(&mut self, points: &[Point<f64>]) -> GpxResult<Vec<Option<f64>>> {
+        Ok(points.iter().map(|&point| self.lookup(point)).collect())
+    }
+}
+
+/// Options for [`Gpx::fill_elevations`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillOptions {
+    /// Whether to replace elevations the points already have.
+    pub overwrite: bool,
+
+    /// How many points to give the provider at once.
+    pub batch_size: usize,
+
+    /// Whether to go on with the next batch when the provider fails,
+    /// counting the points of the failed batch as failed, instead of
+    /// returning the error.
+    pub skip_errors: bool,
+}
+
+impl Default for FillOptions {
+    fn default() -> FillOptions {
+        FillOptions {
+            overwrite: false,
+            batch_size: 100,
+            skip_errors: false,
+        }
+    }
+}
+
+/// What [`Gpx::fill_elevations`] did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FillReport {
+    /// Points that got an elevation from the provider.
+    pub filled: usize,
+
+    /// Points left alone, as they already had an elevation.
+    pub skipped: usize,
+
+    /// Points the provider gave no elevation for, or failed on.
+    pub failed: usize,
+}
+
+impl Gpx {
+    /// Sets the elevations of the waypoints, route points and track points
+    /// from `provider`, in batches of [`FillOptions::batch_size`] points.
+    ///
+    /// Points that already have an elevation are skipped, unless
+    /// [`FillOptions::overwrite`] is set. Elevations that are not finite
+    /// count as missing. If the provider fails, or gives the wrong number of
+    /// elevations, the error is returned right away, keeping the elevations
+    /// of the batches before, unless [`FillOptions::skip_errors`] is set.
+    ///
+    /// ```
+    /// use gpx::{ConstantElevation, FillOptions, Gpx, Waypoint};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// gpx.waypoints.push(Waypoint::new_lat_lon(47.4, 8.5).unwrap());
+    ///
+    /// let report = gpx
+    ///     .fill_elevations(&mut ConstantElevation(408.0), &FillOptions::default())
+    ///     .unwrap();
+    /// assert_eq!(report.filled, 1);
+    /// assert_eq!(gpx.waypoints[0].elevation, Some(408.0));
+    /// ```
+    pub fn fill_elevations(
+        &mut self,
+        provider: &mut dyn ElevationProvider,
+        options: &FillOptions,
+    ) -> GpxResult<FillReport> {
+        let mut report = FillReport::default();
+        let route_points = self.routes.iter_mut().flat_map(|route| &mut route.points);
+        let track_points = self
+            .tracks
+            .iter_mut()
+            .flat_map(|track| &mut track.segments)
+            .flat_map(|segment| &mut segment.points);
+        let mut targets: Vec<&mut Waypoint> = Vec::new();
+        for point in self
+            .waypoints
+            .iter_mut()
+            .chain(route_points)
+            .chain(track_points)
+        {
+            let missing = point
+                .elevation
+                .filter(|elevation| elevation.is_finite())
+                .is_none();
+            if options.overwrite || missing {
+                targets.push(point);
+            } else {
+                report.skipped += 1;
+            }
+        }
+
+        for batch in targets.chunks_mut(options.batch_size.max(1)) {
+            let points: Vec<Point<f64>> = batch.iter().map(|point| point.point()).collect();
+            let elevations = provider.elevation(&points).and_then(|elevations| {
+                if elevations.len() == points.len() {
+                    Ok(elevations)
+                } else {
+                    Err(GpxError::ElevationProviderError(format!(
+                        "gave {} elevations for {} points",
+                        elevations.len(),
+                        points.len()
+                    )))
+                }
+            });
+            let elevations = match elevations {
+                Ok(elevations) => elevations,
+                Err(_) if options.skip_errors => {
+                    report.failed += batch.len();
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            for (point, elevation) in batch.iter_mut().zip(elevations) {
+                match elevation.filter(|elevation| elevation.is_finite()) {
+                    Some(elevation) => {
+                        point.elevation = Some(elevation);
+                        report.filled += 1;
+                    }
+                    None => report.failed += 1,
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use super::{ConstantElevation, ElevationProvider, ElevationTable, FillOptions, FillReport};
+    use crate::errors::{GpxError, GpxResult};
+    use crate::{Gpx, Route, Waypoint};
+
+    /// A drawn route up a hill, without elevations, and a table with the
+    /// elevation of every point but the last.
+    fn hill() -> (Gpx, ElevationTable) {
+        let mut route = Route::new();
+        let mut table = ElevationTable::new(10.0);
+        for i in 0..5 {
+            let point = Point::new(8.0 + f64::from(i) * 0.001, 47.0);
+            route.points.push(Waypoint::new(point));
+            if i < 4 {
+                table.insert(point, 400.0 + f64::from(i) * 25.0);
+            }
+        }
+        let gpx = Gpx {
+            routes: vec![route],
+            ..Default::default()
+        };
+        (gpx, table)
+    }
+
+    fn elevations(gpx: &Gpx) -> Vec<Option<f64>> {
+        gpx.routes[0]
+            .points
+            .iter()
+            .map(|point| point.elevation)
+            .collect()
+    }
+
+    #[test]
+    fn fill_route_from_table() {
+        let (mut gpx, mut table) = hill();
+        let options = FillOptions {
+            batch_size: 2,
+            ..Default::default()
+        };
+        let report = gpx.fill_elevations(&mut table, &options).unwrap();
+        assert_eq!(
+            report,
+            FillReport {
+                filled: 4,
+                skipped: 0,
+                failed: 1,
+            }
+        );
+        assert_eq!(
+            elevations(&gpx),
+            [Some(400.0), Some(425.0), Some(450.0), Some(475.0), None]
+        );
+
+        // Existing elevations stay, unless overwriting.
+        let report = gpx
+            .fill_elevations(&mut ConstantElevation(0.0), &FillOptions::default())
+            .unwrap();
+        assert_eq!((report.filled, report.skipped), (1, 4));
+        assert_eq!(elevations(&gpx)[..2], [Some(400.0), Some(425.0)]);
+        assert_eq!(elevations(&gpx)[4], Some(0.0));
+
+        let overwrite = FillOptions {
+            overwrite: true,
+            ..Default::default()
+        };
+        let report = gpx
+            .fill_elevations(&mut ConstantElevation(1.0), &overwrite)
+            .unwrap();
+        assert_eq!(report.filled, 5);
+        assert!(elevations(&gpx)
+            .iter()
+            .all(|elevation| *elevation == Some(1.0)));
+
+        // Elevations that are not finite count as missing.
+        gpx.routes[0].points[1].elevation = Some(f64::NAN);
+        gpx.routes[0].points[2].elevation = Some(f64::INFINITY);
+        let report = gpx
+            .fill_elevations(&mut ConstantElevation(2.0), &FillOptions::default())
+            .unwrap();
+        assert_eq!((report.filled, report.skipped), (2, 3));
+        assert_eq!(
+            elevations(&gpx),
+            [Some(1.0), Some(2.0), Some(2.0), Some(1.0), Some(1.0)]
+        );
+    }
+
+    /// Fails on the second batch.
+    struct Flaky {
+        calls: usize,
+    }
+
+    impl ElevationProvider for Flaky {
+        fn elevation(&mut self, points: &[Point<f64>]) -> GpxResult<Vec<Option<f64>>> {
+            self.calls += 1;
+            if self.calls == 2 {
+                return Err(GpxError::ElevationProviderError("timeout".into()));
+            }
+            Ok(vec![Some(500.0); points.len()])
+        }
+    }
+
+    #[test]
+    fn provider_errors() {
+        let skip = FillOptions {
+            batch_size: 2,
+            skip_errors: true,
+            ..Default::default()
+        };
+        let (mut gpx, _) = hill();
+        let report = gpx.fill_elevations(&mut Flaky { calls: 0 }, &skip).unwrap();
+        assert_eq!(
+            report,
+            FillReport {
+                filled: 3,
+                skipped: 0,
+                failed: 2,
+            }
+        );
+        assert_eq!(
+            elevations(&gpx),
+            [Some(500.0), Some(500.0), None, None, Some(500.0)]
+        );
+
+        let abort = FillOptions {
+            skip_errors: false,
+            ..skip
+        };
+        let (mut gpx, _) = hill();
+        let error = gpx
+            .fill_elevations(&mut Flaky { calls: 0 }, &abort)
+            .unwrap_err();
+        assert!(matches!(error, GpxError::ElevationProviderError(_)));
+        assert_eq!(elevations(&gpx)[..3], [Some(500.0), Some(500.0), None]);
+
+        /// Gives one elevation too few.
+        struct Short;
+        impl ElevationProvider for Short {
+            fn elevation(&mut self, points: &[Point<f64>]) -> GpxResult<Vec<Option<f64>>> {
+                Ok(vec![Some(1.0); points.len() - 1])
+            }
+        }
+        let (mut gpx, _) = hill();
+        let error = gpx
+            .fill_elevations(&mut Short, &FillOptions::default())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "elevation provider failed: gave 4 elevations for 5 points"
+        );
+        assert_eq!(elevations(&gpx), [None; 5]);
+    }
+}
@@ -1,7 +1,14 @@
 //! errors provides error generics for the gpx parser.
 
+use std::error::Error as _;
 use std::num::{ParseFloatError, ParseIntError};
+
+#[cfg(feature = "use-serde")]
+use serde::Serialize;
 use thiserror::Error;
+use xml::common::Position;
+
+use crate::{DetectedFormat, SourcePosition};
 
 pub(crate) type GpxResult<T> = Result<T, GpxError>;
 
@@ -51,4 +58,603 @@ pub enum GpxError {
     Iso8601Error(#[from] time::error::Parse),
     #[error("error trying to write ISO8601 formatted date")]
     Iso8601ErrorWriting(#[from] time::error::Format),
+    #[error("time out of range")]
+    TimeOutOfRange(#[from] time::error::ComponentRange),
+    #[error("point {0} has no timestamp")]
+    MissingTimestamp(usize),
+    #[error("I/O error")]
+    IoError(#[from] std::io::Error),
+    #[error("element `{element}` in `{parent}` must come before `{expected_after}`")]
+    ElementOutOfOrder {
+        parent: &'static str,
+        element: String,
+        expected_after: String,
+    },
+    #[error("interval must be positive")]
+    NonPositiveInterval,
+    #[error("point {0} has no valid sequence number")]
+    InvalidPointNumber(usize),
+    #[error("point {0} has the same sequence number as point {1}")]
+    DuplicatePointNumber(usize, usize),
+    #[error("invalid numeric value `{1}` for `{0}`")]
+    InvalidNumericValue(&'static str, String),
+    #[error("invalid polyline at byte {0}")]
+    InvalidPolyline(usize),
+    #[error("not an XML document")]
+    NotXmlDocument(#[source] xml::reader::Error),
+    #[error("not a GPX document, the root element is `{root_element}`")]
+    NotGpxDocument { root_element: String },
+    #[error("cannot map the coordinates of {0}: {1}")]
+    CoordinateMappingError(crate::positions::ElementPath, #[source] Box<GpxError>),
+    #[error("invalid link href `{0}`")]
+    InvalidLinkHref(String),
+    #[error("`{element}` is {length} bytes long, more than the limit of {limit}")]
+    StringTooLong {
+        element: &'static str,
+        length: usize,
+        limit: usize,
+    },
+    #[error("invalid color `{0}`")]
+    InvalidColor(String),
+    #[error("event `{event}` is not allowed in `{parent}`")]
+    UnexpectedEvent {
+        event: &'static str,
+        parent: &'static str,
+    },
+    #[error("the events ended in `{0}`")]
+    UnfinishedEvents(&'static str),
+    #[error("misnested extension: {0}")]
+    MisnestedExtension(&'static str),
+    #[error("speed {0} m/s is not positive")]
+    NonPositiveSpeed(f64),
+    #[error("invalid coordinate `{0}`: {1}")]
+    InvalidCoordinate(String, &'static str),
+    #[error("the point has no geoid height")]
+    MissingGeoidHeight,
+    #[error("not a GPX document but {0}{}", .0.hint())]
+    LikelyWrongFormat(DetectedFormat),
+    #[error("tracks {0} and {1} overlap so that they cannot be put in order")]
+    InterleavedTracks(usize, usize),
+    #[error("zip entry `{0}` is there twice")]
+    DuplicateZipEntry(String),
+    #[error("in zip entry `{0}`: {1}")]
+    ZipEntry(String, #[source] Box<GpxError>),
+    #[error("invalid zip archive: {0}")]
+    InvalidZip(&'static str),
+    #[error("no {0} with {1}")]
+    NoMatchingElement(&'static str, String),
+    #[error("elevation provider failed: {0}")]
+    ElevationProviderError(String),
+    #[error("time `{0}` has a leap second")]
+    LeapSecondTimestamp(String),
+    #[error("synthesized times run out of the range of dates")]
+    SynthesizedTimeOutOfRange,
+    #[error("reversed times run out of the range of dates")]
+    RemappedTimeOutOfRange,
+}
+
+impl GpxError {
+    /// Gives a short machine-readable code for the kind of error, such as
+    /// `"invalid_latitude"` or `"missing_closing_tag"`.
+    ///
+    /// The codes are part of the public API: once released, a code keeps
+    /// its meaning and is not given to another kind of error. New kinds of
+    /// errors get new codes. Unlike the messages, which are meant for
+    /// people and may be reworded, they can be relied on by programs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GpxError::ParseFloatError(_) => "invalid_float",
+            GpxError::ParseIntegerError(_) => "invalid_integer",
+            GpxError::InvalidChildElement(..) => "invalid_child_element",
+            GpxError::InvalidClosingTag(..) => "invalid_closing_tag",
+            GpxError::MissingClosingTag(_) => "missing_closing_tag",
+            GpxError::MissingOpeningTag(_) => "missing_opening_tag",
+            GpxError::InvalidElementLacksAttribute(..) => "missing_attribute",
+            GpxError::OutOfBounds(_) => "out_of_bounds",
+            GpxError::XmlParseError(_) => "xml_syntax",
+            GpxError::UnknownVersionError(_) => "unknown_version",
+            GpxError::TagOpenedTwice(_) => "tag_opened_twice",
+            GpxError::TrackSegmentError() => "invalid_track_segment",
+            GpxError::NoStringContent => "no_string_content",
+            GpxError::XmlWriteError(_) => "xml_write",
+            GpxError::MissingEmailPartError(_) => "missing_email_part",
+            GpxError::TooManyAtsError => "too_many_ats",
+            GpxError::EventParsingError(_) => "parse_error",
+            GpxError::MetadataParsingError() => "invalid_metadata",
+            GpxError::LonLatOutOfBoundsError(field, ..)
+                if field.eq_ignore_ascii_case("latitude") =>
+            {
+                "invalid_latitude"
+            }
+            GpxError::LonLatOutOfBoundsError(..) => "invalid_longitude",
+            GpxError::Iso8601Error(_) => "invalid_time",
+            GpxError::Iso8601ErrorWriting(_) => "time_format",
+            GpxError::TimeOutOfRange(_) => "time_out_of_range",
+            GpxError::MissingTimestamp(_) => "missing_timestamp",
+            GpxError::IoError(_) => "io",
+            GpxError::ElementOutOfOrder { .. } => "element_out_of_order",
+            GpxError::NonPositiveInterval => "non_positive_interval",
+            GpxError::InvalidPointNumber(_) => "invalid_point_number",
+            GpxError::DuplicatePointNumber(..) => "duplicate_point_number",
+            GpxError::InvalidNumericValue(..) => "invalid_numeric_value",
+            GpxError::InvalidPolyline(_) => "invalid_polyline",
+            GpxError::NotXmlDocument(_) => "not_xml",
+            GpxError::NotGpxDocument { .. } => "not_gpx",
+            GpxError::CoordinateMappingError(..) => "coordinate_mapping",
+            GpxError::InvalidLinkHref(_) => "invalid_link_href",
+            GpxError::StringTooLong { .. } => "string_too_long",
+            GpxError::InvalidColor(_) => "invalid_color",
+            GpxError::UnexpectedEvent { .. } => "unexpected_event",
+            GpxError::UnfinishedEvents(_) => "unfinished_events",
+            GpxError::MisnestedExtension(_) => "misnested_extension",
+            GpxError::NonPositiveSpeed(_) => "non_positive_speed",
+            GpxError::InvalidCoordinate(..) => "invalid_coordinate",
+            GpxError::MissingGeoidHeight => "missing_geoid_height",
+            GpxError::LikelyWrongFormat(_) => "wrong_format",
+            GpxError::InterleavedTracks(..) => "interleaved_tracks",
+            GpxError::DuplicateZipEntry(_) => "duplicate_zip_entry",
+            GpxError::ZipEntry(..) => "zip_entry",
+            GpxError::InvalidZip(_) => "invalid_zip",
+            GpxError::NoMatchingElement(..) => "no_matching_element",
+            GpxError::ElevationProviderError(_) => "elevation_provider",
+            GpxError::LeapSecondTimestamp(_) => "leap_second_timestamp",
+            GpxError::SynthesizedTimeOutOfRange => "synthesized_time_out_of_range",
+            GpxError::RemappedTimeOutOfRange => "remapped_time_out_of_range",
+        }
+    }
+
+    /// Gives the broad kind of problem, to decide how to handle the error
+    /// without matching every variant.
+    ///
+    /// Like the codes, the categories are stable: an error keeps its
+    /// category, and new variants are put in one of the existing ones.
+    /// Errors that wrap another one, such as [`GpxError::ZipEntry`], have
+    /// the category of the wrapped error.
+    ///
+    /// ```
+    /// use gpx::errors::ErrorCategory;
+    /// use gpx::Waypoint;
+    ///
+    /// let error = Waypoint::new_lat_lon(91.0, 0.0).unwrap_err();
+    /// assert_eq!(error.category(), ErrorCategory::InvalidData);
+    /// ```
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            GpxError::ParseFloatError(_)
+            | GpxError::ParseIntegerError(_)
+            | GpxError::InvalidChildElement(..)
+            | GpxError::InvalidElementLacksAttribute(..)
+            | GpxError::OutOfBounds(_)
+            | GpxError::TagOpenedTwice(_)
+            | GpxError::TrackSegmentError()
+            | GpxError::NoStringContent
+            | GpxError::MissingEmailPartError(_)
+            | GpxError::TooManyAtsError
+            | GpxError::MetadataParsingError()
+            | GpxError::LonLatOutOfBoundsError(..)
+            | GpxError::Iso8601Error(_)
+            | GpxError::Iso8601ErrorWriting(_)
+            | GpxError::TimeOutOfRange(_)
+            | GpxError::MissingTimestamp(_)
+            | GpxError::ElementOutOfOrder { .. }
+            | GpxError::NonPositiveInterval
+            | GpxError::InvalidPointNumber(_)
+            | GpxError::DuplicatePointNumber(..)
+            | GpxError::InvalidNumericValue(..)
+            | GpxError::InvalidPolyline(_)
+            | GpxError::NotGpxDocument { .. }
+            | GpxError::InvalidLinkHref(_)
+            | GpxError::StringTooLong { .. }
+            | GpxError::InvalidColor(_)
+            | GpxError::NonPositiveSpeed(_)
+            | GpxError::InvalidCoordinate(..)
+            | GpxError::MissingGeoidHeight
+            | GpxError::LikelyWrongFormat(_)
+            | GpxError::InterleavedTracks(..)
+            | GpxError::DuplicateZipEntry(_)
+            | GpxError::InvalidZip(_)
+            | GpxError::NoMatchingElement(..)
+            | GpxError::LeapSecondTimestamp(_)
+            | GpxError::SynthesizedTimeOutOfRange
+            | GpxError::RemappedTimeOutOfRange => ErrorCategory::InvalidData,
+            GpxError::InvalidClosingTag(..)
+            | GpxError::MissingClosingTag(_)
+            | GpxError::MissingOpeningTag(_)
+            | GpxError::XmlParseError(_)
+            | GpxError::EventParsingError(_)
+            | GpxError::NotXmlDocument(_) => ErrorCategory::MalformedXml,
+            GpxError::IoError(_)
+            | GpxError::XmlWriteError(xml::writer::Error::Io(_))
+            | GpxError::ElevationProviderError(_) => ErrorCategory::Io,
+            GpxError::UnknownVersionError(_) => ErrorCategory::Unsupported,
+            GpxError::XmlWriteError(_)
+            | GpxError::UnexpectedEvent { .. }
+            | GpxError::UnfinishedEvents(_)
+            | GpxError::MisnestedExtension(_) => ErrorCategory::Internal,
+            GpxError::CoordinateMappingError(_, error) | GpxError::ZipEntry(_, error) => {
+                error.category()
+            }
+        }
+    }
+
+    /// Gives the position in the source document the error was found at,
+    /// where it is known, which is for XML syntax errors.
+    pub fn position(&self) -> Option<SourcePosition> {
+        match self {
+            GpxError::XmlParseError(error) | GpxError::NotXmlDocument(error) => {
+                Some(error.position().into())
+            }
+            GpxError::CoordinateMappingError(_, error) | GpxError::ZipEntry(_, error) => {
+                error.position()
+            }
+            _ => None,
+        }
+    }
+
+    /// Gives the error as an [`ErrorReport`], to be sent on.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let error = Waypoint::new_lat_lon(91.0, 0.0).unwrap_err();
+    /// let report = error.to_report();
+    /// assert_eq!(report.code, "invalid_latitude");
+    /// assert_eq!(report.position, None);
+    /// ```
+    pub fn to_report(&self) -> ErrorReport {
+        // Add the messages of the errors this one was caused by, unless it
+        // already includes them.
+        let mut message = self.to_string();
+        let mut source = self.source();
+        while let Some(error) = source {
+            let text = error.to_string();
+            if !message.contains(&text) {
+                message.push_str(": ");
+                message.push_str(&text);
+            }
+            source = error.source();
+        }
+        let path = match self {
+            GpxError::CoordinateMappingError(path, _) => Some(path.to_string()),
+            _ => None,
+        };
+        ErrorReport {
+            code: self.code(),
+            message,
+            position: self.position(),
+            path,
+        }
+    }
+}
+
+/// The broad kind of a [`GpxError`], as given by [`GpxError::category`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(Serialize))]
+pub enum ErrorCategory {
+    /// The document is well-formed XML, but its content is wrong, such as a
+    /// coordinate out of range, a time that can't be read or a missing
+    /// attribute, or it is not a GPX document at all. Fixing the document
+    /// fixes the error.
+    InvalidData,
+    /// The document is not well-formed XML, or is cut off.
+    MalformedXml,
+    /// Reading or writing failed underneath.
+    Io,
+    /// The document is valid, but uses something this crate does not
+    /// support, such as an unknown GPX version.
+    Unsupported,
+    /// The crate was used in a way it does not allow, such as events
+    /// written out of order, or ran into a bug of its own.
+    Internal,
+}
+
+/// A [`GpxError`] taken apart for programs, such as the client of a web
+/// service, as given by [`GpxError::to_report`]. With the `use-serde`
+/// feature, it serializes to an object with these fields, where the missing
+/// position and path are `null`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize))]
+pub struct ErrorReport {
+    /// The kind of error, see [`GpxError::code`] for what can be relied on.
+    pub code: &'static str,
+
+    /// The message for people, with the messages of the errors that caused
+    /// it.
+    pub message: String,
+
+    /// Where in the source document the error was found, if known.
+    pub position: Option<SourcePosition>,
+
+    /// The element the error is about, such as
+    /// `tracks[0].segments[1].points[2]`, if known.
+    pub path: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use time::format_description::well_known::Iso8601;
+    use time::{Date, Month, OffsetDateTime};
+
+    use super::{ErrorCategory, GpxError};
+    use crate::{read, DetectedFormat, ElementPath, GpxVersion, SourcePosition, Waypoint};
+
+    #[test]
+    fn out_of_range_latitude_report() {
+        let xml = r#"<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+            <wpt lat="91.5" lon="8.0"/>
+        </gpx>"#;
+        let report = read(xml.as_bytes()).unwrap_err().to_report();
+        assert_eq!(report.code, "invalid_latitude");
+        assert!(report.message.contains("91.5"), "{}", report.message);
+        assert_eq!(report.position, None);
+
+        let report = Waypoint::new_lat_lon(0.0, 180.0).unwrap_err().to_report();
+        assert_eq!(report.code, "invalid_longitude");
+    }
+
+    #[test]
+    fn xml_syntax_error_report() {
+        let xml = r#"<gpx version="1.1" creator="test">
+<wpt lat="1" lon="2"><name>a &bogus; b</name></wpt></gpx>"#;
+        let report = read(xml.as_bytes()).unwrap_err().to_report();
+        assert_eq!(report.code, "xml_syntax");
+        assert!(
+            report.message.starts_with("error while parsing XML: "),
+            "{}",
+            report.message
+        );
+        assert_eq!(
+            report.position,
+            Some(SourcePosition {
+                line: 2,
+                column: 36
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "use-serde")]
+    fn reports_serialize_to_json() {
+        use serde_json::json;
+
+        let error = Waypoint::new_lat_lon(-95.0, 0.0).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(error.to_report()).unwrap(),
+            json!({
+                "code": "invalid_latitude",
+                "message": error.to_string(),
+                "position": null,
+                "path": null,
+            })
+        );
+
+        let xml =
+            "<gpx version=\"1.1\">\n<wpt lat=\"1\" lon=\"2\"><name>&bogus;</name></wpt></gpx>";
+        let error = read(xml.as_bytes()).unwrap_err();
+        assert_eq!(
+            serde_json::to_value(error.to_report()).unwrap(),
+            json!({
+                "code": "xml_syntax",
+                "message": "error while parsing XML: 2:34 Unexpected entity: bogus",
+                "position": { "line": 2, "column": 34 },
+                "path": null,
+            })
+        );
+    }
+
+    fn xml_error() -> xml::reader::Error {
+        xml::EventReader::new("<a></b>".as_bytes())
+            .into_iter()
+            .find_map(Result::err)
+            .unwrap()
+    }
+
+    fn io_error() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, "disk on fire")
+    }
+
+    /// Numbers the variants. As the match has no catch-all, a new variant
+    /// has to be added here, and then to the samples below.
+    fn variant(error: &GpxError) -> usize {
+        match error {
+            GpxError::ParseFloatError(_) => 0,
+            GpxError::ParseIntegerError(_) => 1,
+            GpxError::InvalidChildElement(..) => 2,
+            GpxError::InvalidClosingTag(..) => 3,
+            GpxError::MissingClosingTag(_) => 4,
+            GpxError::MissingOpeningTag(_) => 5,
+            GpxError::InvalidElementLacksAttribute(..) => 6,
+            GpxError::OutOfBounds(_) => 7,
+            GpxError::XmlParseError(_) => 8,
+            GpxError::UnknownVersionError(_) => 9,
+            GpxError::TagOpenedTwice(_) => 10,
+            GpxError::TrackSegmentError() => 11,
+            GpxError::NoStringContent => 12,
+            GpxError::XmlWriteError(_) => 13,
+            GpxError::MissingEmailPartError(_) => 14,
+            GpxError::TooManyAtsError => 15,
+            GpxError::EventParsingError(_) => 16,
+            GpxError::MetadataParsingError() => 17,
+            GpxError::LonLatOutOfBoundsError(..) => 18,
+            GpxError::Iso8601Error(_) => 19,
+            GpxError::Iso8601ErrorWriting(_) => 20,
+            GpxError::TimeOutOfRange(_) => 21,
+            GpxError::MissingTimestamp(_) => 22,
+            GpxError::IoError(_) => 23,
+            GpxError::ElementOutOfOrder { .. } => 24,
+            GpxError::NonPositiveInterval => 25,
+            GpxError::InvalidPointNumber(_) => 26,
+            GpxError::DuplicatePointNumber(..) => 27,
+            GpxError::InvalidNumericValue(..) => 28,
+            GpxError::InvalidPolyline(_) => 29,
+            GpxError::NotXmlDocument(_) => 30,
+            GpxError::NotGpxDocument { .. } => 31,
+            GpxError::CoordinateMappingError(..) => 32,
+            GpxError::InvalidLinkHref(_) => 33,
+            GpxError::StringTooLong { .. } => 34,
+            GpxError::InvalidColor(_) => 35,
+            GpxError::UnexpectedEvent { .. } => 36,
+            GpxError::UnfinishedEvents(_) => 37,
+            GpxError::MisnestedExtension(_) => 38,
+            GpxError::NonPositiveSpeed(_) => 39,
+            GpxError::InvalidCoordinate(..) => 40,
+            GpxError::MissingGeoidHeight => 41,
+            GpxError::LikelyWrongFormat(_) => 42,
+            GpxError::InterleavedTracks(..) => 43,
+            GpxError::DuplicateZipEntry(_) => 44,
+            GpxError::ZipEntry(..) => 45,
+            GpxError::InvalidZip(_) => 46,
+            GpxError::NoMatchingElement(..) => 47,
+            GpxError::ElevationProviderError(_) => 48,
+            GpxError::LeapSecondTimestamp(_) => 49,
+            GpxError::SynthesizedTimeOutOfRange => 50,
+            GpxError::RemappedTimeOutOfRange => 51,
+        }
+    }
+
+    const VARIANTS: usize = 52;
+
+    #[test]
+    fn every_variant_has_a_category() {
+        use ErrorCategory::*;
+
+        let samples = vec![
+            (GpxError::from("x".parse::<f64>().unwrap_err()), InvalidData),
+            (GpxError::from("x".parse::<u32>().unwrap_err()), InvalidData),
+            (
+                GpxError::InvalidChildElement("a".into(), "wpt"),
+                InvalidData,
+            ),
+            (GpxError::InvalidClosingTag("a".into(), "wpt"), MalformedXml),
+            (GpxError::MissingClosingTag("wpt"), MalformedXml),
+            (GpxError::MissingOpeningTag("wpt"), MalformedXml),
+            (
+                GpxError::InvalidElementLacksAttribute("lat", "wpt"),
+                InvalidData,
+            ),
+            (GpxError::OutOfBounds("lat"), InvalidData),
+            (GpxError::XmlParseError(xml_error()), MalformedXml),
+            (
+                GpxError::UnknownVersionError(GpxVersion::Unknown),
+                Unsupported,
+            ),
+            (GpxError::TagOpenedTwice("name"), InvalidData),
+            (GpxError::TrackSegmentError(), InvalidData),
+            (GpxError::NoStringContent, InvalidData),
+            (
+                GpxError::XmlWriteError(xml::writer::Error::Io(io_error())),
+                Io,
+            ),
+            (
+                GpxError::XmlWriteError(xml::writer::Error::LastElementNameNotAvailable),
+                Internal,
+            ),
+            (GpxError::MissingEmailPartError("id"), InvalidData),
+            (GpxError::TooManyAtsError, InvalidData),
+            (GpxError::EventParsingError("wpt"), MalformedXml),
+            (GpxError::MetadataParsingError(), InvalidData),
+            (Waypoint::new_lat_lon(91.0, 0.0).unwrap_err(), InvalidData),
+            (
+                GpxError::from(OffsetDateTime::parse("noon", &Iso8601::PARSING).unwrap_err()),
+                InvalidData,
+            ),
+            (
+                GpxError::from(time::error::Format::InvalidComponent("year")),
+                InvalidData,
+            ),
+            (
+                GpxError::from(Date::from_calendar_date(2020, Month::February, 30).unwrap_err()),
+                InvalidData,
+            ),
+            (GpxError::MissingTimestamp(3), InvalidData),
+            (GpxError::from(io_error()), Io),
+            (
+                GpxError::ElementOutOfOrder {
+                    parent: "wpt",
+                    element: "ele".into(),
+                    expected_after: "name".into(),
+                },
+                InvalidData,
+            ),
+            (GpxError::NonPositiveInterval, InvalidData),
+            (GpxError::InvalidPointNumber(1), InvalidData),
+            (GpxError::DuplicatePointNumber(1, 2), InvalidData),
+            (
+                GpxError::InvalidNumericValue("hdop", "x".into()),
+                InvalidData,
+            ),
+            (GpxError::InvalidPolyline(4), InvalidData),
+            (GpxError::NotXmlDocument(xml_error()), MalformedXml),
+            (
+                GpxError::NotGpxDocument {
+                    root_element: "kml".into(),
+                },
+                InvalidData,
+            ),
+            (
+                GpxError::CoordinateMappingError(
+                    ElementPath::Waypoint(0),
+                    Box::new(GpxError::UnknownVersionError(GpxVersion::Unknown)),
+                ),
+                Unsupported,
+            ),
+            (GpxError::InvalidLinkHref("a b".into()), InvalidData),
+            (
+                GpxError::StringTooLong {
+                    element: "name",
+                    length: 10,
+                    limit: 5,
+                },
+                InvalidData,
+            ),
+            (GpxError::InvalidColor("mauve".into()), InvalidData),
+            (
+                GpxError::UnexpectedEvent {
+                    event: "point",
+                    parent: "gpx",
+                },
+                Internal,
+            ),
+            (GpxError::UnfinishedEvents("trk"), Internal),
+            (GpxError::MisnestedExtension("end without start"), Internal),
+            (GpxError::NonPositiveSpeed(0.0), InvalidData),
+            (
+                GpxError::InvalidCoordinate("N91".into(), "out of range"),
+                InvalidData,
+            ),
+            (GpxError::MissingGeoidHeight, InvalidData),
+            (
+                GpxError::LikelyWrongFormat(DetectedFormat::Zip),
+                InvalidData,
+            ),
+            (GpxError::InterleavedTracks(0, 1), InvalidData),
+            (GpxError::DuplicateZipEntry("a.gpx".into()), InvalidData),
+            (
+                GpxError::ZipEntry("a.gpx".into(), Box::new(GpxError::from(io_error()))),
+                Io,
+            ),
+            (GpxError::InvalidZip("truncated"), InvalidData),
+            (
+                GpxError::NoMatchingElement("track", "index 3".into()),
+                InvalidData,
+            ),
+            (GpxError::ElevationProviderError("timeout".into()), Io),
+            (
+                GpxError::LeapSecondTimestamp("2016-12-31T23:59:60Z".into()),
+                InvalidData,
+            ),
+            (GpxError::SynthesizedTimeOutOfRange, InvalidData),
+            (GpxError::RemappedTimeOutOfRange, InvalidData),
+        ];
+
+        let mut covered = [false; VARIANTS];
+        for (error, category) in &samples {
+            assert_eq!(error.category(), *category, "{:?}", error);
+            covered[variant(error)] = true;
+        }
+        let missing: Vec<usize> = (0..VARIANTS).filter(|index| !covered[*index]).collect();
+        assert!(missing.is_empty(), "no sample of variants {:?}", missing);
+    }
 }
@@ -0,0 +1,461 @@
+//! events reads and writes documents as a sequence of [`GpxEvent`]s, for
+//! transformations that should not hold a whole document in memory.
+//!
+//! A document is a `StartGpx`, an optional `Metadata`, then waypoints,
+//! routes, tracks and extensions, and an `EndGpx`. Routes and tracks are
+//! opened with the values of their own children, and their points follow as
+//! separate events.
+//!
+//! ```
+//! use gpx::events::{self, GpxEvent, GpxEventWriter};
+//!
+//! let data = r#"<gpx version="1.1" creator="logger">
+//!   <trk><name>Run</name><trkseg>
+//!     <trkpt lat="47.1" lon="8.5"><ele>500</ele></trkpt>
+//!     <trkpt lat="47.2" lon="8.6"><ele>510</ele></trkpt>
+//!   </trkseg></trk>
+//! </gpx>"#;
+//!
+//! let mut writer = GpxEventWriter::new(Vec::new());
+//! for event in events::read(data.as_bytes()) {
+//!     let event = match event.unwrap() {
+//!         GpxEvent::Point(mut point) => {
+//!             point.elevation = point.elevation.map(|elevation| elevation + 100.0);
+//!             GpxEvent::Point(point)
+//!         }
+//!         event => event,
+//!     };
+//!     writer.write(event).unwrap();
+//! }
+//! let output = writer.finish().unwrap();
+//!
+//! let gpx = gpx::read(output.as_slice()).unwrap();
+//! assert_eq!(gpx.tracks[0].segments[0].points[1].elevation, Some(610.0));
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Read, Write};
+
+use xml::reader::XmlEvent;
+use xml::writer::EventWriter;
+
+use crate::dom::Element;
+use crate::errors::{GpxError, GpxResult};
+use crate::parser::{
+    create_context_with_options, extensions, gpx, order, route, track, verify_starting_tag,
+    waypoint, Context, TrimStart,
+};
+use crate::writer::{
+    gpx_start_element, write_declaration, write_element, write_generated_by_comment,
+    write_metadata, write_route_start, write_track_segment, write_track_start, write_waypoint,
+    write_xml_event, DEFAULT_CREATOR,
+};
+use crate::{Gpx, GpxVersion, Metadata, ReadOptions, Route, Track, Waypoint, WriteOptions};
+
+/// A part of a document, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq)]
+pub enum GpxEvent {
+    /// The start of the document, always the first event.
+    StartGpx {
+        version: GpxVersion,
+        creator: Option<String>,
+    },
+    /// The metadata, which comes right after `StartGpx` if there is any.
+    Metadata(Metadata),
+    /// A standalone waypoint.
+    Waypoint(Waypoint),
+    /// The start of a route. Its points follow as `RoutePoint`s, and are
+    /// not in `points` when reading.
+    StartRoute(Route),
+    /// A point of the current route.
+    RoutePoint(Waypoint),
+    /// The end of the current route.
+    EndRoute,
+    /// The start of a track. Its segments follow as `StartSegment`s, and
+    /// are not in `segments` when reading.
+    StartTrack(Track),
+    /// The start of a segment of the current track.
+    StartSegment,
+    /// A point of the current segment.
+    Point(Waypoint),
+    /// The end of the current segment.
+    EndSegment,
+    /// The end of the current track.
+    EndTrack,
+    /// The extensions of the current segment, after its points, or of the
+    /// document, after its routes and tracks.
+    Extensions(Element),
+    /// The end of the document, always the last event.
+    EndGpx,
+}
+
+impl GpxEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            GpxEvent::StartGpx { .. } => "StartGpx",
+            GpxEvent::Metadata(_) => "Metadata",
+            GpxEvent::Waypoint(_) => "Waypoint",
+            GpxEvent::StartRoute(_) => "StartRoute",
+            GpxEvent::RoutePoint(_) => "RoutePoint",
+            GpxEvent::EndRoute => "EndRoute",
+            GpxEvent::StartTrack(_) => "StartTrack",
+            GpxEvent::StartSegment => "StartSegment",
+            GpxEvent::Point(_) => "Point",
+            GpxEvent::EndSegment => "EndSegment",
+            GpxEvent::EndTrack => "EndTrack",
+            GpxEvent::Extensions(_) => "Extensions",
+            GpxEvent::EndGpx => "EndGpx",
+        }
+    }
+}
+
+/// Reads the events of a document, see [`GpxEvents`].
+pub fn read<R: Read>(reader: R) -> GpxEvents<R> {
+    read_with_options(reader, ReadOptions::default())
+}
+
+/// Reads the events of a document, using the given [`ReadOptions`].
+///
+/// [`ReadOptions::record_source_positions`] has no effect, as there is no
+/// document to give the positions for.
+pub fn read_with_options<R: Read>(reader: R, options: ReadOptions) -> GpxEvents<R> {
+    GpxEvents {
+        context: create_context_with_options(reader, GpxVersion::Unknown, options),
+        state: ReadState::Start,
+        pending: VecDeque::new(),
+    }
+}
+
+/// Where [`GpxEvents`] is in the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReadState {
+    Start,
+    Gpx,
+    Route,
+    Track,
+    Segment,
+    Done,
+}
+
+/// The next tag in the document.
+enum Tag {
+    Start(String),
+    End(String),
+}
+
+/// An iterator over the events of a document, given by [`read`].
+///
+/// Only the element being read is held in memory, so any number of points
+/// can be read. The children of routes and tracks other than their points
+/// and segments must come before these, as the schema asks. Unknown elements
+/// of the document are skipped, and comments before it are not passed on.
+///
+/// After an error, the iterator ends.
+pub struct GpxEvents<R: Read> {
+    context: Context<TrimStart<R>>,
+    state: ReadState,
+    pending: VecDeque<GpxEvent>,
+}
+
+impl<R: Read> GpxEvents<R> {
+    fn advance(&mut self) -> GpxResult<Option<GpxEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            let context = &mut self.context;
+            let event = match self.state {
+                ReadState::Start => {
+                    let mut gpx = Gpx::default();
+                    let body = gpx::consume_parts(context, &mut gpx, true)?;
+                    self.pending.extend(gpx.metadata.map(GpxEvent::Metadata));
+                    if body {
+                        self.state = ReadState::Gpx;
+                    } else {
+                        self.pending.push_back(GpxEvent::EndGpx);
+                        self.state = ReadState::Done;
+                    }
+                    GpxEvent::StartGpx {
+                        version: gpx.version,
+                        creator: gpx.creator,
+                    }
+                }
+                ReadState::Gpx => match next_tag(context, "gpx")? {
+                    Tag::Start(name) => match name.as_str() {
+                        "wpt" => GpxEvent::Waypoint(waypoint::consume(context, "wpt")?),
+                        "rte" => {
+                            let mut route = Route::default();
+                            if route::consume_parts(context, &mut route, true)? {
+                                self.state = ReadState::Route;
+                            } else {
+                                self.pending.push_back(GpxEvent::EndRoute);
+                            }
+                            GpxEvent::StartRoute(route)
+                        }
+                        "trk" => {
+                            let mut track = Track::default();
+                            if track::consume_parts(context, &mut track, true)? {
+                                self.state = ReadState::Track;
+                            } else {
+                                self.pending.push_back(GpxEvent::EndTrack);
+                            }
+                            GpxEvent::StartTrack(track)
+                        }
+                        "extensions" => GpxEvent::Extensions(extensions::consume(context)?),
+                        child if !order::GPX.contains(&child) => {
+                            extensions::consume_unknown(context)?;
+                            continue;
+                        }
+                        _ => return Err(GpxError::InvalidChildElement(name, "gpx")),
+                    },
+                    Tag::End(name) => {
+                        end_tag(name, "gpx")?;
+                        self.state = ReadState::Done;
+                        GpxEvent::EndGpx
+                    }
+                },
+                ReadState::Route => match next_tag(context, "route")? {
+                    Tag::Start(name) if name == "rtept" => {
+                        GpxEvent::RoutePoint(waypoint::consume(context, "rtept")?)
+                    }
+                    Tag::Start(name) => return Err(GpxError::InvalidChildElement(name, "route")),
+                    Tag::End(name) => {
+                        end_tag(name, "rte")?;
+                        self.state = ReadState::Gpx;
+                        GpxEvent::EndRoute
+                    }
+                },
+                ReadState::Track => match next_tag(context, "track")? {
+                    Tag::Start(name) if name == "trkseg" => {
+                        verify_starting_tag(context, "trkseg")?;
+                        self.state = ReadState::Segment;
+                        GpxEvent::StartSegment
+                    }
+                    Tag::Start(name) => return Err(GpxError::InvalidChildElement(name, "track")),
+                    Tag::End(name) => {
+                        end_tag(name, "trk")?;
+                        self.state = ReadState::Gpx;
+                        GpxEvent::EndTrack
+                    }
+                },
+                ReadState::Segment => match next_tag(context, "tracksegment")? {
+                    Tag::Start(name) => match name.as_str() {
+                        "trkpt" => GpxEvent::Point(waypoint::consume(context, "trkpt")?),
+                        "extensions" => GpxEvent::Extensions(extensions::consume(context)?),
+                        _ => return Err(GpxError::InvalidChildElement(name, "tracksegment")),
+                    },
+                    Tag::End(name) => {
+                        end_tag(name, "trkseg")?;
+                        self.state = ReadState::Track;
+                        GpxEvent::EndSegment
+                    }
+                },
+                ReadState::Done => return Ok(None),
+            };
+            return Ok(Some(event));
+        }
+    }
+}
+
+impl<R: Read> Iterator for GpxEvents<R> {
+    type Item = GpxResult<GpxEvent>;
+
+    fn next(&mut self) -> Option<GpxResult<GpxEvent>> {
+        match self.advance() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = ReadState::Done;
+                self.pending.clear();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Gives the next start tag without consuming it, or consumes and gives the
+/// next end tag, skipping text in between.
+fn next_tag<R: Read>(context: &mut Context<R>, parent: &'static str) -> GpxResult<Tag> {
+    loop {
+        let tag = match context.reader().peek() {
+            Some(Ok(XmlEvent::StartElement { name, .. })) => {
+                return Ok(Tag::Start(name.local_name.clone()))
+            }
+            Some(Ok(XmlEvent::EndElement { name })) => Tag::End(name.local_name.clone()),
+            Some(Ok(_)) => {
+                context.reader().next();
+                continue;
+            }
+            Some(Err(_)) => return Err(GpxError::EventParsingError("gpx event")),
+            None => return Err(GpxError::MissingClosingTag(parent)),
+        };
+        context.reader().next();
+        return Ok(tag);
+    }
+}
+
+fn end_tag(name: String, expected: &'static str) -> GpxResult<()> {
+    if name == expected {
+        Ok(())
+    } else {
+        Err(GpxError::InvalidClosingTag(name, expected))
+    }
+}
+
+/// Writes a sequence of events as a document, see [`GpxEventWriter`].
+pub fn write<W, I>(events: I, writer: W) -> GpxResult<W>
+where
+    W: Write,
+    I: IntoIterator<Item = GpxEvent>,
+{
+    let mut writer = GpxEventWriter::new(writer);
+    for event in events {
+        writer.write(event)?;
+    }
+    writer.finish()
+}
+
+/// Where [`GpxEventWriter`] is in the document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WriteState {
+    Start,
+    /// Right after `StartGpx`, where the metadata may come.
+    Header,
+    Gpx,
+    Route,
+    Track,
+    Segment,
+    Done,
+}
+
+impl WriteState {
+    fn name(self) -> &'static str {
+        match self {
+            WriteState::Start => "start",
+            WriteState::Header | WriteState::Gpx => "gpx",
+            WriteState::Route => "route",
+            WriteState::Track => "track",
+            WriteState::Segment => "tracksegment",
+            WriteState::Done => "end",
+        }
+    }
+}
+
+/// Writes events as a document, checking that each event is allowed where
+/// it comes.
+///
+/// Routes and tracks given with points or segments have these written right
+/// away, before the events that follow. Each event is written as it comes,
+/// so any number of points can be written.
+pub struct GpxEventWriter<W: Write> {
+    writer: EventWriter<W>,
+    options: WriteOptions,
+    version: GpxVersion,
+    state: WriteState,
+}
+
+impl<W: Write> GpxEventWriter<W> {
+    /// Creates a writer with the default [`WriteOptions`].
+    pub fn new(writer: W) -> GpxEventWriter<W> {
+        GpxEventWriter::with_options(writer, WriteOptions::default())
+    }
+
+    /// Creates a writer with the given [`WriteOptions`].
+    pub fn with_options(writer: W, options: WriteOptions) -> GpxEventWriter<W> {
+        GpxEventWriter {
+            writer: options.emitter_config().create_writer(writer),
+            options,
+            version: GpxVersion::Unknown,
+            state: WriteState::Start,
+        }
+    }
+
+    /// Writes the next event. Events that are not allowed where they come
+    /// fail with [`GpxError::UnexpectedEvent`] without writing anything.
+    pub fn write(&mut self, event: GpxEvent) -> GpxResult<()> {
+        let (writer, options) = (&mut self.writer, &self.options);
+        let next = match (self.state, &event) {
+            (WriteState::Start, GpxEvent::StartGpx { version, creator }) => {
+                write_declaration(options, writer)?;
+                write_generated_by_comment(options, writer)?;
+                let creator = creator.as_deref().unwrap_or(DEFAULT_CREATOR);
+                write_xml_event(
+                    gpx_start_element(*version, creator, &BTreeMap::new(), options)?,
+                    writer,
+                )?;
+                self.version = *version;
+                WriteState::Header
+            }
+            (WriteState::Header, GpxEvent::Metadata(metadata)) => {
+                write_metadata(self.version, Some(metadata), options, writer)?;
+                WriteState::Gpx
+            }
+            (WriteState::Header | WriteState::Gpx, GpxEvent::Waypoint(point)) => {
+                write_waypoint("wpt", point, options, writer)?;
+                WriteState::Gpx
+            }
+            (WriteState::Header | WriteState::Gpx, GpxEvent::StartRoute(route)) => {
+                write_route_start(route, self.version, options, writer)?;
+                for point in &route.points {
+                    write_waypoint("rtept", point, options, writer)?;
+                }
+                WriteState::Route
+            }
+            (WriteState::Header | WriteState::Gpx, GpxEvent::StartTrack(track)) => {
+                write_track_start(track, self.version, options, writer)?;
+                for segment in &track.segments {
+                    write_track_segment(segment, options, writer)?;
+                }
+                WriteState::Track
+            }
+            (WriteState::Header | WriteState::Gpx, GpxEvent::Extensions(extensions)) => {
+                write_element(extensions, writer)?;
+                WriteState::Gpx
+            }
+            (WriteState::Header | WriteState::Gpx, GpxEvent::EndGpx) => {
+                write_xml_event(xml::writer::XmlEvent::end_element(), writer)?;
+                WriteState::Done
+            }
+            (WriteState::Route, GpxEvent::RoutePoint(point)) => {
+                write_waypoint("rtept", point, options, writer)?;
+                WriteState::Route
+            }
+            (WriteState::Track, GpxEvent::StartSegment) => {
+                write_xml_event(xml::writer::XmlEvent::start_element("trkseg"), writer)?;
+                WriteState::Segment
+            }
+            (WriteState::Segment, GpxEvent::Point(point)) => {
+                write_waypoint("trkpt", point, options, writer)?;
+                WriteState::Segment
+            }
+            (WriteState::Segment, GpxEvent::Extensions(extensions)) => {
+                write_element(extensions, writer)?;
+                WriteState::Segment
+            }
+            (WriteState::Route, GpxEvent::EndRoute) | (WriteState::Track, GpxEvent::EndTrack) => {
+                write_xml_event(xml::writer::XmlEvent::end_element(), writer)?;
+                WriteState::Gpx
+            }
+            (WriteState::Segment, GpxEvent::EndSegment) => {
+                write_xml_event(xml::writer::XmlEvent::end_element(), writer)?;
+                WriteState::Track
+            }
+            (state, event) => {
+                return Err(GpxError::UnexpectedEvent {
+                    event: event.name(),
+                    parent: state.name(),
+                })
+            }
+        };
+        self.state = next;
+        Ok(())
+    }
+
+    /// Gives back the underlying writer, once `EndGpx` was written, or fails
+    /// with [`GpxError::UnfinishedEvents`].
+    pub fn finish(self) -> GpxResult<W> {
+        match self.state {
+            WriteState::Done => Ok(self.writer.into_inner()),
+            state => Err(GpxError::UnfinishedEvents(state.name())),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! extension_sink writes the extensions of points straight from a callback,
+//! without building a [`dom::Element`](crate::dom::Element) for each point.
+
+use std::fmt;
+use std::io::Write;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use xml::writer::{EventWriter, XmlEvent};
+
+use crate::dom::OwnedName;
+use crate::errors::{GpxError, GpxResult};
+use crate::writer::{with_namespace, write_element_open, write_xml_event};
+use crate::{Waypoint, WriteOptions};
+
+/// Which kind of point [`WriteOptions::point_extension_writer`] is called
+/// for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointKind {
+    /// A `<wpt>` of the document.
+    Waypoint,
+    /// A `<rtept>` of a route.
+    RoutePoint,
+    /// A `<trkpt>` of a track segment.
+    TrackPoint,
+}
+
+type Callback = dyn FnMut(&Waypoint, PointKind, &mut ExtensionSink<'_>) -> GpxResult<()> + Send;
+
+/// The callback of [`WriteOptions::point_extension_writer`], with the
+/// buffers it reuses between points.
+#[derive(Clone)]
+pub(crate) struct PointExtensionWriter(Arc<Mutex<Hook>>);
+
+struct Hook {
+    callback: Box<Callback>,
+    state: SinkState,
+}
+
+impl fmt::Debug for PointExtensionWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PointExtensionWriter")
+    }
+}
+
+impl WriteOptions {
+    /// Calls `f` for every waypoint, route point and track point written,
+    /// to write extensions for it through an [`ExtensionSink`].
+    ///
+    /// The `<extensions>` element of the point is only written if `f`
+    /// writes something. Extensions the point already has come first, in
+    /// the same `<extensions>` element. The namespaces of the names given
+    /// to the sink are declared on the outermost element using them, unless
+    /// they are declared for the whole document with
+    /// [`WriteOptions::declare_namespace`].
+    ///
+    /// `f` has to own what it uses, as the options may be sent to other
+    /// threads. Clones of the options share the same `f`.
+    ///
+    /// ```
+    /// use gpx::dom::OwnedName;
+    /// use gpx::{write_with_options, Gpx, PointKind, Waypoint, WriteOptions};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// gpx.version = gpx::GpxVersion::Gpx11;
+    /// gpx.waypoints.push(Waypoint::new_lat_lon(47.3, 8.5).unwrap());
+    ///
+    /// let depth = OwnedName::qualified("depth", "https://example.com/dive", Some("d".into()));
+    /// let options = WriteOptions::new().point_extension_writer(move |_, kind, sink| {
+    ///     if kind == PointKind::Waypoint {
+    ///         sink.start_element(&depth)?;
+    ///         sink.text("12.5")?;
+    ///         sink.end_element()?;
+    ///     }
+    ///     Ok(())
+    /// });
+    ///
+    /// let mut buffer = Vec::new();
+    /// write_with_options(&gpx, &mut buffer, options).unwrap();
+    /// let xml = String::from_utf8(buffer).unwrap();
+    /// assert!(xml.contains(r#"<d:depth xmlns:d="https://example.com/dive">12.5</d:depth>"#));
+    /// ```
+    pub fn point_extension_writer(
+        mut self,
+        f: impl FnMut(&Waypoint, PointKind, &mut ExtensionSink<'_>) -> GpxResult<()> + Send + 'static,
+    ) -> WriteOptions {
+        self.point_extensions = Some(PointExtensionWriter(Arc::new(Mutex::new(Hook {
+            callback: Box::new(f),
+            state: SinkState::default(),
+        }))));
+        self
+    }
+}
+
+/// Writes the content of the `<extensions>` element of one point, as given
+/// to the callback of [`WriteOptions::point_extension_writer`].
+///
+/// Elements are written as they are started, so the attributes of an
+/// element have to be given right after [`ExtensionSink::start_element`],
+/// before its content. Calls that do not nest fail with
+/// [`GpxError::MisnestedExtension`].
+pub struct ExtensionSink<'a> {
+    writer: &'a mut dyn XmlSink,
+    state: &'a mut SinkState,
+}
+
+/// What an [`ExtensionSink`] keeps between its calls, and whose buffers are
+/// reused for the next point.
+#[derive(Default)]
+struct SinkState {
+    /// Whether the `<extensions>` element is open.
+    opened: bool,
+    /// The number of elements started inside it and not yet ended.
+    depth: usize,
+    /// The element whose start tag is not written yet, as attributes may
+    /// still follow.
+    pending: Option<OwnedName>,
+    attributes: Vec<(OwnedName, Range<usize>)>,
+    values: String,
+}
+
+impl<'a> ExtensionSink<'a> {
+    /// Starts an element named `name`, opening the `<extensions>` element
+    /// first if nothing was written yet.
+    pub fn start_element(&mut self, name: &OwnedName) -> GpxResult<()> {
+        self.flush()?;
+        if !self.state.opened {
+            self.writer
+                .write_event(XmlEvent::start_element("extensions").into())?;
+            self.state.opened = true;
+        }
+        self.state.pending = Some(name.clone());
+        self.state.depth += 1;
+        Ok(())
+    }
+
+    /// Adds an attribute to the element just started.
+    pub fn attribute(&mut self, name: &OwnedName, value: &str) -> GpxResult<()> {
+        if self.state.pending.is_none() {
+            return Err(GpxError::MisnestedExtension(
+                "attribute after the content of an element",
+            ));
+        }
+        let start = self.state.values.len();
+        self.state.values.push_str(value);
+        let range = start..self.state.values.len();
+        self.state.attributes.push((name.clone(), range));
+        Ok(())
+    }
+
+    /// Writes text inside the current element.
+    pub fn text(&mut self, value: &str) -> GpxResult<()> {
+        self.flush()?;
+        if self.state.depth == 0 {
+            return Err(GpxError::MisnestedExtension("text outside of an element"));
+        }
+        self.writer.write_event(XmlEvent::characters(value))
+    }
+
+    /// Ends the current element.
+    pub fn end_element(&mut self) -> GpxResult<()> {
+        self.flush()?;
+        if self.state.depth == 0 {
+            return Err(GpxError::MisnestedExtension(
+                "end of an element that was not started",
+            ));
+        }
+        self.state.depth -= 1;
+        self.writer.write_event(XmlEvent::end_element().into())
+    }
+
+    /// Writes the start tag of the pending element.
+    fn flush(&mut self) -> GpxResult<()> {
+        let state = &mut *self.state;
+        let name = match &state.pending {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let mut start = with_namespace(XmlEvent::start_element(name.borrow()), name);
+        for (name, range) in &state.attributes {
+            start = with_namespace(start, name).attr(name.borrow(), &state.values[range.clone()]);
+        }
+        self.writer.write_event(start.into())?;
+        state.pending = None;
+        state.attributes.clear();
+        state.values.clear();
+        Ok(())
+    }
+}
+
+/// The writer behind an [`ExtensionSink`], without its type parameter.
+trait XmlSink {
+    fn write_event(&mut self, event: XmlEvent<'_>) -> GpxResult<()>;
+}
+
+impl<W: Write> XmlSink for EventWriter<W> {
+    fn write_event(&mut self, event: XmlEvent<'_>) -> GpxResult<()> {
+        write_xml_event(event, self)
+    }
+}
+
+/// Writes the extensions of `waypoint`, those it has and those the
+/// callback of [`WriteOptions::point_extension_writer`] gives.
+pub(crate) fn write_point_extensions<W: Write>(
+    waypoint: &Waypoint,
+    kind: PointKind,
+    hook: &PointExtensionWriter,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    let mut hook = hook
+        .0
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Hook { callback, state } = &mut *hook;
+    // A callback that failed for the previous point may have left anything.
+    state.opened = false;
+    state.depth = 0;
+    state.pending = None;
+    state.attributes.clear();
+    state.values.clear();
+    if let Some(extensions) = &waypoint.extras().extensions {
+        write_element_open(extensions, writer)?;
+        state.opened = true;
+    }
+    callback(
+        waypoint,
+        kind,
+        &mut ExtensionSink {
+            writer,
+            state: &mut *state,
+        },
+    )?;
+    if state.depth > 0 {
+        return Err(GpxError::MisnestedExtension("element left open"));
+    }
+    if state.opened {
+        write_xml_event(XmlEvent::end_element(), writer)?;
+    }
+    Ok(())
+}
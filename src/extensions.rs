@@ -0,0 +1,219 @@
+//! extensions walks and filters the extensions of a document.
+
+use std::iter::once;
+
+use crate::dom::{Element, Node, OwnedName};
+use crate::{ChangeReport, Gpx, Route, Track, Waypoint};
+
+impl Gpx {
+    /// Removes all extensions, from the document itself, its metadata,
+    /// waypoints, routes, route points, tracks, track segments and track
+    /// points. The report counts the points that had extensions as
+    /// modified; the document, its metadata, routes, tracks and segments,
+    /// not being points, are left out.
+    pub fn strip_extensions(&mut self) -> ChangeReport {
+        self.for_each_extensions("strip_extensions", |extensions| extensions.take().is_some())
+    }
+
+    /// Keeps only the extensions whose name `keep` accepts, everywhere
+    /// [`Gpx::strip_extensions`] looks.
+    ///
+    /// `keep` is called with the name of every child of each `<extensions>`
+    /// element, but not with the names of the elements nested deeper down:
+    /// a child that is kept is kept with all of its content. `<extensions>`
+    /// elements that end up without children are removed. The report counts
+    /// the points that lost extensions as modified.
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    ///
+    /// let file = File::open("tests/fixtures/garmin_with_extensions.gpx").unwrap();
+    /// let mut gpx = gpx::read(BufReader::new(file)).unwrap();
+    /// assert!(gpx.tracks[0].extensions.is_some());
+    ///
+    /// // Only keep Garmin's track point extensions.
+    /// gpx.retain_extensions(|name| {
+    ///     name.namespace.as_deref()
+    ///         == Some("http://www.garmin.com/xmlschemas/TrackPointExtension/v1")
+    /// });
+    /// assert!(gpx.tracks[0].extensions.is_none());
+    /// ```
+    pub fn retain_extensions(&mut self, keep: impl Fn(&OwnedName) -> bool) -> ChangeReport {
+        self.for_each_extensions("retain_extensions", |extensions| {
+            let element = match extensions {
+                Some(element) => element,
+                None => return false,
+            };
+            let before = element.children.len();
+            retain_children(element, &keep);
+            if element.elements().next().is_none() {
+                *extensions = None;
+                return true;
+            }
+            element.children.len() != before
+        })
+    }
+
+    /// Gives all extensions elements of the document.
+    pub(crate) fn all_extensions(&self) -> impl Iterator<Item = &Element> {
+        let metadata = self.metadata.iter().map(|metadata| &metadata.extensions);
+        let waypoints = self
+            .waypoints
+            .iter()
+            .map(|waypoint| &waypoint.extras().extensions);
+        once(&self.extensions)
+            .chain(metadata)
+            .chain(waypoints)
+            .filter_map(Option::as_ref)
+            .chain(self.routes.iter().flat_map(Route::all_extensions))
+            .chain(self.tracks.iter().flat_map(Track::all_extensions))
+    }
+
+    /// Calls `f` with all extensions of the document, and reports the
+    /// points whose extensions `f` changed, as it says, as modified by
+    /// `operation`.
+    fn for_each_extensions(
+        &mut self,
+        operation: &str,
+        mut f: impl FnMut(&mut Option<Element>) -> bool,
+    ) -> ChangeReport {
+        let mut report = ChangeReport::new(operation, self.all_points().count());
+        f(&mut self.extensions);
+        if let Some(metadata) = &mut self.metadata {
+            f(&mut metadata.extensions);
+        }
+        let mut modified = points_extensions(&mut self.waypoints, &mut f);
+        for route in &mut self.routes {
+            f(&mut route.extensions);
+            modified += points_extensions(&mut route.points, &mut f);
+        }
+        report.record(0, modified);
+        for (t, track) in self.tracks.iter_mut().enumerate() {
+            f(&mut track.extensions);
+            for (s, segment) in track.segments.iter_mut().enumerate() {
+                f(&mut segment.extensions);
+                let len = segment.points.len();
+                let modified = points_extensions(&mut segment.points, &mut f);
+                report.record_segment(t, s, len, len, modified);
+            }
+        }
+        report
+    }
+}
+
+/// Calls `f` with the extensions of the points that have any extras, and
+/// gives how many of them `f` changed.
+fn points_extensions(
+    points: &mut [Waypoint],
+    f: &mut impl FnMut(&mut Option<Element>) -> bool,
+) -> usize {
+    points
+        .iter_mut()
+        .filter_map(Waypoint::allocated_extras_mut)
+        .map(|extras| f(&mut extras.extensions) as usize)
+        .sum()
+}
+
+impl Route {
+    /// Gives the extensions elements of the route and its points.
+    pub(crate) fn all_extensions(&self) -> impl Iterator<Item = &Element> {
+        once(&self.extensions)
+            .chain(self.points.iter().map(|point| &point.extras().extensions))
+            .filter_map(Option::as_ref)
+    }
+}
+
+impl Track {
+    /// Gives the extensions elements of the track, its segments and their
+    /// points.
+    pub(crate) fn all_extensions(&self) -> impl Iterator<Item = &Element> {
+        let segments = self.segments.iter().flat_map(|segment| {
+            once(&segment.extensions).chain(
+                segment
+                    .points
+                    .iter()
+                    .map(|point| &point.extras().extensions),
+            )
+        });
+        once(&self.extensions)
+            .chain(segments)
+            .filter_map(Option::as_ref)
+    }
+}
+
+fn retain_children(element: &mut Element, keep: &impl Fn(&OwnedName) -> bool) {
+    element.children.retain(|child| match child {
+        Node::Element(child) => keep(&child.name),
+        Node::Text(_) => true,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dom::{Element, Node, OwnedName};
+    use crate::{Gpx, Track, TrackSegment, Waypoint};
+    use geo_types::Point;
+
+    fn extensions(children: &[&str]) -> Option<Element> {
+        let mut element = Element::new(OwnedName::local("extensions"));
+        for &child in children {
+            let mut child = Element::new(OwnedName::local(child));
+            child
+                .children
+                .push(Node::Element(Element::new(OwnedName::local("nested"))));
+            element.children.push(Node::Element(child));
+        }
+        Some(element)
+    }
+
+    fn gpx() -> Gpx {
+        let mut point = Waypoint::new(Point::new(1.0, 2.0));
+        point.extras_mut().extensions = extensions(&["keep", "drop"]);
+        let mut segment = TrackSegment::new();
+        segment.points.push(point);
+        segment.extensions = extensions(&["drop"]);
+        let mut track = Track::new();
+        track.segments.push(segment);
+        track.extensions = extensions(&["keep"]);
+        Gpx {
+            tracks: vec![track],
+            extensions: extensions(&["drop", "drop"]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn strip_extensions() {
+        let mut gpx = gpx();
+        assert_eq!(gpx.strip_extensions().modified, 1);
+
+        assert_eq!(gpx.extensions, None);
+        assert_eq!(gpx.tracks[0].extensions, None);
+        assert_eq!(gpx.tracks[0].segments[0].extensions, None);
+        assert_eq!(
+            gpx.tracks[0].segments[0].points[0].extras().extensions,
+            None
+        );
+    }
+
+    #[test]
+    fn retain_extensions() {
+        let mut gpx = gpx();
+        // "nested" is never asked about, only the direct children are.
+        let report = gpx.retain_extensions(|name| {
+            assert_ne!(name.local_name, "nested");
+            name.local_name == "keep"
+        });
+        assert_eq!(report.modified, 1);
+        assert!(gpx.retain_extensions(|_| true).is_unchanged());
+
+        assert_eq!(gpx.extensions, None);
+        assert_eq!(gpx.tracks[0].extensions, extensions(&["keep"]));
+        assert_eq!(gpx.tracks[0].segments[0].extensions, None);
+        assert_eq!(
+            gpx.tracks[0].segments[0].points[0].extras().extensions,
+            extensions(&["keep"])
+        );
+    }
+}
@@ -0,0 +1,363 @@
+//! fingerprint sums up documents in a few numbers, to find duplicates among
+//! many documents without keeping or reading them again.
+//!
+//! A [`GpxFingerprint`] is a heuristic, not a cryptographic hash: different
+//! documents can have the same fingerprint, and anyone can make a document
+//! with a given one.
+
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Serialize};
+
+use geo_types::{coord, Rect};
+use time::{Duration, OffsetDateTime};
+
+use crate::{Gpx, Time, Waypoint};
+
+/// How far apart, in seconds, the start or end of two documents may be
+/// before they count as different.
+const TIME_TOLERANCE: f64 = 600.0;
+
+/// How far, in degrees, bounds are widened on each side before comparing
+/// them, so that the bounds of a single point or a straight line can
+/// overlap.
+const BOUNDS_SLACK: f64 = 1e-5;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Options for [`Gpx::fingerprint`] and [`FingerprintBuilder`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FingerprintOptions {
+    /// The number of decimal places the coordinates are rounded to before
+    /// they are hashed, at most 9. The default of 5 is about a meter, so
+    /// that the float noise of a program that read and wrote the document
+    /// again rarely changes the hash.
+    pub precision: u32,
+}
+
+impl Default for FingerprintOptions {
+    fn default() -> FingerprintOptions {
+        FingerprintOptions { precision: 5 }
+    }
+}
+
+/// A summary of the points of a document, as made by [`Gpx::fingerprint`].
+///
+/// Fingerprints are the same on every platform, so they can be stored and
+/// compared with those of new documents later, see
+/// [`GpxFingerprint::similarity`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct GpxFingerprint {
+    /// The number of waypoints, route points and track points.
+    pub points: usize,
+
+    /// The time of the first point with a time.
+    pub start: Option<Time>,
+
+    /// The time of the last point with a time.
+    pub end: Option<Time>,
+
+    /// The time from `start` to `end`.
+    pub duration: Option<Duration>,
+
+    /// The smallest rectangle containing all points.
+    pub bounds: Option<Rect<f64>>,
+
+    /// A 64-bit FNV-1a hash of the rounded coordinates, in order. Equal
+    /// hashes mean the documents most likely have the same points, but as a
+    /// single moved point changes the hash, it is left out of the
+    /// similarity.
+    pub hash: u64,
+}
+
+/// Makes a [`GpxFingerprint`] from points given one at a time, such as
+/// from the events of [`events::read`](crate::events::read), without
+/// holding the document in memory.
+///
+/// Giving it the waypoints, then the route points and then the track points
+/// of a document makes the same fingerprint as [`Gpx::fingerprint`].
+///
+/// ```
+/// use gpx::events::{self, GpxEvent};
+/// use gpx::{FingerprintBuilder, FingerprintOptions};
+///
+/// let data = r#"<gpx version="1.1" creator="logger">
+///   <wpt lat="47.0" lon="8.0"/>
+///   <trk><trkseg>
+///     <trkpt lat="47.1" lon="8.5"><time>2021-06-01T12:00:00Z</time></trkpt>
+///     <trkpt lat="47.2" lon="8.6"><time>2021-06-01T12:30:00Z</time></trkpt>
+///   </trkseg></trk>
+/// </gpx>"#;
+///
+/// let mut builder = FingerprintBuilder::new(&FingerprintOptions::default());
+/// for event in events::read(data.as_bytes()) {
+///     match event.unwrap() {
+///         GpxEvent::Waypoint(point) | GpxEvent::RoutePoint(point) | GpxEvent::Point(point) => {
+///             builder.push(&point)
+///         }
+///         _ => {}
+///     }
+/// }
+/// let fingerprint = builder.finish();
+/// assert_eq!(fingerprint.points, 3);
+/// assert_eq!(fingerprint.duration, Some(time::Duration::minutes(30)));
+///
+/// let gpx = gpx::read(data.as_bytes()).unwrap();
+/// assert_eq!(gpx.fingerprint(&FingerprintOptions::default()), fingerprint);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FingerprintBuilder {
+    scale: f64,
+    points: usize,
+    start: Option<Time>,
+    end: Option<Time>,
+    bounds: Option<(f64, f64, f64, f64)>,
+    hash: u64,
+}
+
+impl FingerprintBuilder {
+    /// Creates a builder that has seen no points yet.
+    pub fn new(options: &FingerprintOptions) -> FingerprintBuilder {
+        // Multiplying by ten stays exact, unlike `powi`, which may differ
+        // between platforms.
+        let scale = (0..options.precision.min(9)).fold(1.0, |scale, _| scale * 10.0);
+        FingerprintBuilder {
+            scale,
+            points: 0,
+            start: None,
+            end: None,
+            bounds: None,
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Adds the next point.
+    pub fn push(&mut self, point: &Waypoint) {
+        let (lat, lon) = (point.lat(), point.lon());
+        self.points += 1;
+        if let Some(time) = point.time {
+            self.start.get_or_insert(time);
+            self.end = Some(time);
+        }
+        self.bounds = Some(match self.bounds {
+            None => (lon, lat, lon, lat),
+            Some((min_x, min_y, max_x, max_y)) => (
+                min_x.min(lon),
+                min_y.min(lat),
+                max_x.max(lon),
+                max_y.max(lat),
+            ),
+        });
+        for value in [lat, lon].iter() {
+            let quantized = (value * self.scale).round() as i64;
+            for byte in quantized.to_le_bytes().iter() {
+                self.hash ^= u64::from(*byte);
+                self.hash = self.hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+    }
+
+    /// Gives the fingerprint of the points so far.
+    pub fn finish(&self) -> GpxFingerprint {
+        let duration = match (self.start, self.end) {
+            (Some(start), Some(end)) => {
+                Some(OffsetDateTime::from(end) - OffsetDateTime::from(start))
+            }
+            _ => None,
+        };
+        GpxFingerprint {
+            points: self.points,
+            start: self.start,
+            end: self.end,
+            duration,
+            bounds: self.bounds.map(|(min_x, min_y, max_x, max_y)| {
+                Rect::new(coord! { x: min_x, y: min_y }, coord! { x: max_x, y: max_y })
+            }),
+            hash: self.hash,
+        }
+    }
+}
+
+impl GpxFingerprint {
+    /// Gives how alike the documents of two fingerprints are, from 0 for
+    /// nothing in common to 1 for the same.
+    ///
+    /// The number of points, the duration, the start, the end and the
+    /// bounds each give a score from 0 to 1, and the similarity is their
+    /// product, so that a single clear difference, such as starts an hour
+    /// apart, makes the documents different. Documents with a few points
+    /// more or less, or coordinates a little off, stay close to 1.
+    pub fn similarity(&self, other: &GpxFingerprint) -> f64 {
+        let points = ratio(self.points as f64, other.points as f64);
+        let duration = both(self.duration, other.duration, |a, b| {
+            ratio(a.as_seconds_f64().abs(), b.as_seconds_f64().abs())
+        });
+        let start = both(self.start, other.start, time_score);
+        let end = both(self.end, other.end, time_score);
+        let bounds = both(self.bounds, other.bounds, |a, b| {
+            overlap(a.min().x, a.max().x, b.min().x, b.max().x)
+                * overlap(a.min().y, a.max().y, b.min().y, b.max().y)
+        });
+        points * duration * start * end * bounds
+    }
+}
+
+impl Gpx {
+    /// Makes the fingerprint of the waypoints, route points and track
+    /// points of the document, see [`GpxFingerprint`].
+    ///
+    /// ```
+    /// use gpx::{FingerprintOptions, Gpx, Waypoint};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// gpx.waypoints.push(Waypoint::new_lat_lon(47.0, 8.0).unwrap());
+    /// let mut copy = gpx.clone();
+    /// copy.waypoints[0] = Waypoint::new_lat_lon(47.000_000_1, 8.0).unwrap();
+    ///
+    /// let options = FingerprintOptions::default();
+    /// let (a, b) = (gpx.fingerprint(&options), copy.fingerprint(&options));
+    /// assert_eq!(a.hash, b.hash);
+    /// assert!(a.similarity(&b) > 0.95);
+    /// ```
+    pub fn fingerprint(&self, options: &FingerprintOptions) -> GpxFingerprint {
+        let mut builder = FingerprintBuilder::new(options);
+        let route_points = self.routes.iter().flat_map(|route| &route.points);
+        let track_points = self
+            .tracks
+            .iter()
+            .flat_map(|track| &track.segments)
+            .flat_map(|segment| &segment.points);
+        for point in self
+            .waypoints
+            .iter()
+            .chain(route_points)
+            .chain(track_points)
+        {
+            builder.push(point);
+        }
+        builder.finish()
+    }
+}
+
+/// Scores two optional components with `score`, giving 1 if both are
+/// missing and 0 if only one is.
+fn both<T>(a: Option<T>, b: Option<T>, score: impl FnOnce(T, T) -> f64) -> f64 {
+    match (a, b) {
+        (Some(a), Some(b)) => score(a, b),
+        (None, None) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// The smaller of two non-negative numbers divided by the larger.
+fn ratio(a: f64, b: f64) -> f64 {
+    if a == b {
+        1.0
+    } else {
+        a.min(b) / a.max(b)
+    }
+}
+
+fn time_score(a: Time, b: Time) -> f64 {
+    let apart = (OffsetDateTime::from(a) - OffsetDateTime::from(b))
+        .as_seconds_f64()
+        .abs();
+    1.0 - (apart / TIME_TOLERANCE).min(1.0)
+}
+
+/// How much of the union of two ranges their intersection covers, after
+/// widening them by [`BOUNDS_SLACK`].
+fn overlap(a_min: f64, a_max: f64, b_min: f64, b_max: f64) -> f64 {
+    let union = a_max.max(b_max) - a_min.min(b_min) + 2.0 * BOUNDS_SLACK;
+    let intersection = a_max.min(b_max) - a_min.max(b_min) + 2.0 * BOUNDS_SLACK;
+    intersection.max(0.0) / union
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::FingerprintOptions;
+    use crate::{read, Gpx};
+
+    fn fixture(name: &str) -> Gpx {
+        read(File::open(format!("tests/fixtures/{}", name)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn identical_and_unrelated_documents() {
+        let options = FingerprintOptions::default();
+        let activity = fixture("garmin-activity.gpx").fingerprint(&options);
+        let again = fixture("garmin-activity.gpx").fingerprint(&options);
+        assert_eq!(activity, again);
+        assert_eq!(activity.similarity(&again), 1.0);
+        assert_eq!(activity.points, 988);
+
+        let unrelated = fixture("mousehole_to_paul.gpx").fingerprint(&options);
+        assert_ne!(activity.hash, unrelated.hash);
+        assert!(activity.similarity(&unrelated) < 0.2);
+        assert!(unrelated.similarity(&activity) < 0.2);
+
+        // The same track an hour later.
+        let mut later = fixture("garmin-activity.gpx");
+        for point in &mut later.tracks[0].segments[0].points {
+            point.time = point
+                .time
+                .map(|time| (time::OffsetDateTime::from(time) + time::Duration::hours(1)).into());
+        }
+        let later = later.fingerprint(&options);
+        assert_eq!(later.hash, activity.hash);
+        assert!(activity.similarity(&later) < 0.2);
+    }
+
+    /// Moves every track point by `noise` degrees, in alternating
+    /// directions.
+    fn with_noise(gpx: &Gpx, noise: f64) -> Gpx {
+        let mut gpx = gpx.clone();
+        let points = gpx.tracks[0].segments[0].points.iter_mut();
+        for (i, point) in points.enumerate() {
+            let noise = if i % 2 == 0 { noise } else { -noise };
+            point.set_lat(point.lat() + noise).unwrap();
+            point.set_lon(point.lon() - noise).unwrap();
+        }
+        gpx
+    }
+
+    #[test]
+    fn float_noise_keeps_documents_alike() {
+        let options = FingerprintOptions::default();
+        let gpx = fixture("garmin-activity.gpx");
+        let noisy = with_noise(&gpx, 1e-7);
+        let original = gpx.fingerprint(&options);
+        let fingerprint = noisy.fingerprint(&options);
+        assert_ne!(original.bounds, fingerprint.bounds);
+        assert!(original.similarity(&fingerprint) > 0.95);
+
+        // Rounding to 8 places keeps the noise.
+        let precise = FingerprintOptions { precision: 8 };
+        assert_ne!(
+            gpx.fingerprint(&precise).hash,
+            noisy.fingerprint(&precise).hash
+        );
+    }
+
+    #[cfg(feature = "use-serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let fingerprint =
+            fixture("garmin-activity.gpx").fingerprint(&FingerprintOptions::default());
+        let json = serde_json::to_string(&fingerprint).unwrap();
+        let back: super::GpxFingerprint = serde_json::from_str(&json).unwrap();
+        // The bounds may lose their last digit.
+        assert_eq!(
+            (back.points, back.hash),
+            (fingerprint.points, fingerprint.hash)
+        );
+        assert_eq!(
+            (back.start, back.duration),
+            (fingerprint.start, fingerprint.duration)
+        );
+        assert!(back.similarity(&fingerprint) > 0.999_999);
+    }
+}
@@ -0,0 +1,300 @@
+//! format renders durations, distances and speeds for reports, such as
+//! `2h14m`, `42.3 km` and `17.8 km/h`.
+//!
+//! The output is always in English, with a `.` before the decimals and no
+//! grouping of thousands, whatever the locale of the system, so the same
+//! value always gives the same text.
+//!
+//! ```
+//! use gpx::format::{format_distance, format_duration, format_speed, DurationStyle, Units};
+//! use time::Duration;
+//!
+//! let duration = Duration::seconds(2 * 3600 + 14 * 60 + 5);
+//! assert_eq!(format_duration(duration, DurationStyle::Compact), "2h14m");
+//! assert_eq!(format_duration(duration, DurationStyle::Spaced), "2 h 14 min");
+//! assert_eq!(format_duration(duration, DurationStyle::Clock), "2:14:05");
+//!
+//! assert_eq!(format_distance(42_300.0, Units::Metric, 1), "42.3 km");
+//! assert_eq!(format_distance(42_300.0, Units::Imperial, 1), "26.3 mi");
+//! assert_eq!(format_speed(4.95, Units::Metric, 1), "17.8 km/h");
+//! ```
+
+use time::Duration;
+
+use crate::TrackStats;
+
+const METERS_PER_FOOT: f64 = 0.3048;
+const METERS_PER_MILE: f64 = 1609.344;
+const METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+
+/// How [`format_duration`] writes a duration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DurationStyle {
+    /// `2h14m`, `14m5s` or `45s`.
+    Compact,
+    /// `2 h 14 min`, `14 min 5 s` or `45 s`.
+    Spaced,
+    /// `2:14:05`, `0:14:05` or `0:00:45`.
+    Clock,
+}
+
+/// The units that distances and speeds are given in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Units {
+    /// Meters and kilometers, and kilometers per hour.
+    Metric,
+    /// Feet and miles, and miles per hour.
+    Imperial,
+    /// Meters and nautical miles, and knots.
+    Nautical,
+}
+
+impl Units {
+    /// The short unit, used below 1000 of it, and how many meters it is.
+    fn short(self) -> (&'static str, f64) {
+        match self {
+            Units::Metric | Units::Nautical => ("m", 1.0),
+            Units::Imperial => ("ft", METERS_PER_FOOT),
+        }
+    }
+
+    /// The long unit and how many meters it is.
+    fn long(self) -> (&'static str, f64) {
+        match self {
+            Units::Metric => ("km", 1000.0),
+            Units::Imperial => ("mi", METERS_PER_MILE),
+            Units::Nautical => ("nmi", METERS_PER_NAUTICAL_MILE),
+        }
+    }
+}
+
+/// Writes `duration` in the given style, cut to whole seconds.
+///
+/// The compact and spaced styles give hours and minutes for durations of an
+/// hour or more, minutes and seconds for durations of a minute or more, and
+/// seconds otherwise. Hours are not grouped into days. Negative durations
+/// start with a `-`.
+pub fn format_duration(duration: Duration, style: DurationStyle) -> String {
+    let sign = if duration.is_negative() && duration.whole_seconds() != 0 {
+        "-"
+    } else {
+        ""
+    };
+    let total = duration.whole_seconds().unsigned_abs();
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    match style {
+        DurationStyle::Compact if hours > 0 => format!("{}{}h{}m", sign, hours, minutes),
+        DurationStyle::Compact if minutes > 0 => format!("{}{}m{}s", sign, minutes, seconds),
+        DurationStyle::Compact => format!("{}{}s", sign, seconds),
+        DurationStyle::Spaced if hours > 0 => {
+            format!("{}{} h {} min", sign, hours, minutes)
+        }
+        DurationStyle::Spaced if minutes > 0 => {
+            format!("{}{} min {} s", sign, minutes, seconds)
+        }
+        DurationStyle::Spaced => format!("{}{} s", sign, seconds),
+        DurationStyle::Clock => format!("{}{}:{:02}:{:02}", sign, hours, minutes, seconds),
+    }
+}
+
+/// Writes a distance of `meters` in `units`.
+///
+/// Distances that round to less than 1000 meters, or feet for
+/// [`Units::Imperial`], are given in whole meters or feet. Longer ones are
+/// given in kilometers, miles or nautical miles, with `precision` decimals.
+///
+/// ```
+/// use gpx::format::{format_distance, Units};
+///
+/// assert_eq!(format_distance(999.0, Units::Metric, 1), "999 m");
+/// assert_eq!(format_distance(1000.0, Units::Metric, 1), "1.0 km");
+/// assert_eq!(format_distance(1852.0, Units::Nautical, 2), "1.00 nmi");
+/// ```
+pub fn format_distance(meters: f64, units: Units, precision: usize) -> String {
+    let (short, short_meters) = units.short();
+    // Adding 0 turns -0, as -0.2 rounds to, into 0.
+    let in_short = (meters / short_meters).round() + 0.0;
+    if in_short.abs() < 1000.0 {
+        return format!("{} {}", in_short, short);
+    }
+    let (long, long_meters) = units.long();
+    format!("{:.*} {}", precision, meters / long_meters, long)
+}
+
+/// Writes a speed of `mps` meters per second in `units`, as kilometers per
+/// hour, miles per hour or knots, with `precision` decimals.
+///
+/// ```
+/// use gpx::format::{format_speed, Units};
+///
+/// assert_eq!(format_speed(10.0, Units::Metric, 1), "36.0 km/h");
+/// assert_eq!(format_speed(10.0, Units::Imperial, 1), "22.4 mph");
+/// assert_eq!(format_speed(10.0, Units::Nautical, 0), "19 kn");
+/// ```
+pub fn format_speed(mps: f64, units: Units, precision: usize) -> String {
+    let (unit, meters) = match units {
+        Units::Metric => ("km/h", 1000.0),
+        Units::Imperial => ("mph", METERS_PER_MILE),
+        Units::Nautical => ("kn", METERS_PER_NAUTICAL_MILE),
+    };
+    format!("{:.*} {}", precision, mps * 3600.0 / meters, unit)
+}
+
+/// Writes an elevation difference in whole meters, or feet for
+/// [`Units::Imperial`], without switching to longer units.
+fn format_elevation(meters: f64, units: Units) -> String {
+    let (unit, unit_meters) = units.short();
+    format!("{} {}", (meters / unit_meters).round() + 0.0, unit)
+}
+
+impl TrackStats {
+    /// Sums up the statistics in one line, such as `42.3 km in 2h14m at
+    /// 18.9 km/h, 512 m up and 498 m down`. The duration and speed are left
+    /// out if the track has no times.
+    ///
+    /// ```
+    /// use gpx::format::Units;
+    /// use gpx::TrackStats;
+    /// use time::Duration;
+    ///
+    /// let stats = TrackStats {
+    ///     distance: 42_300.0,
+    ///     elevation_gain: 512.0,
+    ///     elevation_loss: 498.0,
+    ///     duration: Some(Duration::minutes(134)),
+    ///     moving_duration: None,
+    ///     points: 1000,
+    /// };
+    /// assert_eq!(
+    ///     stats.format(Units::Metric),
+    ///     "42.3 km in 2h14m at 18.9 km/h, 512 m up and 498 m down"
+    /// );
+    /// ```
+    pub fn format(&self, units: Units) -> String {
+        let mut line = format_distance(self.distance, units, 1);
+        if let Some(duration) = self.duration {
+            line.push_str(" in ");
+            line.push_str(&format_duration(duration, DurationStyle::Compact));
+        }
+        if let Some(speed) = self.average_speed() {
+            line.push_str(" at ");
+            line.push_str(&format_speed(speed, units, 1));
+        }
+        line.push_str(&format!(
+            ", {} up and {} down",
+            format_elevation(self.elevation_gain, units),
+            format_elevation(self.elevation_loss, units)
+        ));
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::{format_distance, format_duration, format_speed, DurationStyle, Units};
+    use crate::TrackStats;
+
+    fn durations(style: DurationStyle) -> Vec<String> {
+        [0, 45, 59, 60, 65, 3599, 3600, 8045, 90_000, -65]
+            .iter()
+            .map(|&seconds| format_duration(Duration::seconds(seconds), style))
+            .collect()
+    }
+
+    #[test]
+    fn duration_styles() {
+        assert_eq!(
+            durations(DurationStyle::Compact),
+            ["0s", "45s", "59s", "1m0s", "1m5s", "59m59s", "1h0m", "2h14m", "25h0m", "-1m5s"]
+        );
+        assert_eq!(
+            durations(DurationStyle::Spaced),
+            [
+                "0 s",
+                "45 s",
+                "59 s",
+                "1 min 0 s",
+                "1 min 5 s",
+                "59 min 59 s",
+                "1 h 0 min",
+                "2 h 14 min",
+                "25 h 0 min",
+                "-1 min 5 s"
+            ]
+        );
+        assert_eq!(
+            durations(DurationStyle::Clock),
+            [
+                "0:00:00", "0:00:45", "0:00:59", "0:01:00", "0:01:05", "0:59:59", "1:00:00",
+                "2:14:05", "25:00:00", "-0:01:05"
+            ]
+        );
+        // Fractions of a second are cut off.
+        let almost = Duration::milliseconds(59_999);
+        assert_eq!(format_duration(almost, DurationStyle::Compact), "59s");
+        assert_eq!(format_duration(-almost, DurationStyle::Compact), "-59s");
+        let tiny = Duration::milliseconds(-400);
+        assert_eq!(format_duration(tiny, DurationStyle::Clock), "0:00:00");
+    }
+
+    #[test]
+    fn distance_switches_units() {
+        let distances = |units: Units, precision| -> Vec<String> {
+            [0.0, 999.0, 999.4, 999.6, 1000.0, 1049.0, 42_195.0, -1500.0]
+                .iter()
+                .map(|&meters| format_distance(meters, units, precision))
+                .collect()
+        };
+        assert_eq!(
+            distances(Units::Metric, 1),
+            ["0 m", "999 m", "999 m", "1.0 km", "1.0 km", "1.0 km", "42.2 km", "-1.5 km"]
+        );
+        assert_eq!(
+            distances(Units::Imperial, 2),
+            [
+                "0 ft", "0.62 mi", "0.62 mi", "0.62 mi", "0.62 mi", "0.65 mi", "26.22 mi",
+                "-0.93 mi"
+            ]
+        );
+        assert_eq!(
+            distances(Units::Nautical, 0),
+            ["0 m", "999 m", "999 m", "1 nmi", "1 nmi", "1 nmi", "23 nmi", "-1 nmi"]
+        );
+        assert_eq!(format_distance(304.0, Units::Imperial, 1), "997 ft");
+        assert_eq!(format_distance(304.8, Units::Imperial, 1), "0.2 mi");
+        assert_eq!(format_distance(-0.2, Units::Metric, 1), "0 m");
+    }
+
+    #[test]
+    fn speeds() {
+        assert_eq!(format_speed(0.0, Units::Metric, 1), "0.0 km/h");
+        assert_eq!(format_speed(4.95, Units::Metric, 2), "17.82 km/h");
+        assert_eq!(format_speed(4.95, Units::Imperial, 1), "11.1 mph");
+        assert_eq!(format_speed(4.95, Units::Nautical, 1), "9.6 kn");
+    }
+
+    #[test]
+    fn stats_line() {
+        let mut stats = TrackStats {
+            distance: 850.0,
+            elevation_gain: 12.4,
+            elevation_loss: 0.0,
+            duration: Some(Duration::seconds(45)),
+            moving_duration: None,
+            points: 20,
+        };
+        assert_eq!(
+            stats.format(Units::Imperial),
+            "0.5 mi in 45s at 42.3 mph, 41 ft up and 0 ft down"
+        );
+        stats.duration = Some(Duration::ZERO);
+        assert_eq!(
+            stats.format(Units::Metric),
+            "850 m in 0s, 12 m up and 0 m down"
+        );
+        stats.duration = None;
+        assert_eq!(stats.format(Units::Nautical), "850 m, 12 m up and 0 m down");
+    }
+}
@@ -0,0 +1,302 @@
+//! gaps finds where a recording was interrupted, between the segments of a
+//! track and within them.
+
+use std::fmt;
+
+use geo_types::Point;
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+use crate::distance::haversine;
+use crate::{Time, Track, Waypoint};
+
+/// Thresholds for [`Track::point_gaps`]. A gap is reported if either is
+/// exceeded; `None` leaves a threshold out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GapOptions {
+    /// Longest time between consecutive points that is not a gap.
+    pub max_time: Option<Duration>,
+
+    /// Longest distance between consecutive points, in meters, that is not
+    /// a gap.
+    pub max_distance_m: Option<f64>,
+}
+
+impl Default for GapOptions {
+    fn default() -> GapOptions {
+        GapOptions {
+            max_time: Some(Duration::minutes(1)),
+            max_distance_m: Some(500.0),
+        }
+    }
+}
+
+/// The gap between the end of one segment and the start of the next, as
+/// found by [`Track::gap_report`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentGap {
+    /// Index of the segment before the gap.
+    pub from_segment: usize,
+
+    /// Index of the segment after the gap.
+    pub to_segment: usize,
+
+    /// Time of the last point of `from_segment`.
+    pub from_time: Option<Time>,
+
+    /// Time of the first point of `to_segment`.
+    pub to_time: Option<Time>,
+
+    /// Time between the two points, or `None` if either has no time.
+    pub time_gap: Option<Duration>,
+
+    /// Distance between the two points, in meters.
+    pub distance_m: f64,
+
+    /// The last point of `from_segment`.
+    pub from_point: Point<f64>,
+
+    /// The first point of `to_segment`.
+    pub to_point: Point<f64>,
+}
+
+/// A gap between consecutive points of a segment, as found by
+/// [`Track::point_gaps`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointGap {
+    /// Index of the segment.
+    pub segment: usize,
+
+    /// Index of the point before the gap. The point after it is the next
+    /// one.
+    pub from_index: usize,
+
+    /// Time between the two points, or `None` if either has no time.
+    pub time_gap: Option<Duration>,
+
+    /// Distance between the two points, in meters.
+    pub distance_m: f64,
+
+    /// The point before the gap.
+    pub from_point: Point<f64>,
+
+    /// The point after the gap.
+    pub to_point: Point<f64>,
+}
+
+impl Track {
+    /// Describes the gaps between consecutive segments, in order. Empty
+    /// segments are passed over, so a gap may span several indices.
+    ///
+    /// ```
+    /// use gpx::{Track, TrackSegment, Waypoint};
+    ///
+    /// let mut track = Track::new();
+    /// for x in [0.0, 0.01].iter() {
+    ///     let mut segment = TrackSegment::new();
+    ///     segment.points.push(Waypoint::new_lat_lon(0.0, *x).unwrap());
+    ///     track.segments.push(segment);
+    /// }
+    ///
+    /// let gaps = track.gap_report();
+    /// assert_eq!(gaps.len(), 1);
+    /// assert_eq!(gaps[0].time_gap, None);
+    /// assert_eq!(
+    ///     gaps[0].to_string(),
+    ///     "segment 0 ends, segment 1 starts 1.1 km away",
+    /// );
+    /// ```
+    pub fn gap_report(&self) -> Vec<SegmentGap> {
+        let mut ends = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter_map(|(index, segment)| {
+                Some((index, segment.points.first()?, segment.points.last()?))
+            });
+        let mut gaps = Vec::new();
+        let mut previous = match ends.next() {
+            Some(first) => first,
+            None => return gaps,
+        };
+        for next in ends {
+            let (from, to) = (previous.2, next.1);
+            gaps.push(SegmentGap {
+                from_segment: previous.0,
+                to_segment: next.0,
+                from_time: from.time,
+                to_time: to.time,
+                time_gap: time_between(from, to),
+                distance_m: haversine(from.point(), to.point()),
+                from_point: from.point(),
+                to_point: to.point(),
+            });
+            previous = next;
+        }
+        gaps
+    }
+
+    /// Finds the consecutive points within each segment that are further
+    /// apart in time or space than `options` allow, such as where the
+    /// receiver lost its fix.
+    pub fn point_gaps(&self, options: &GapOptions) -> Vec<PointGap> {
+        let mut gaps = Vec::new();
+        for (segment, points) in self.segments.iter().enumerate() {
+            for (from_index, pair) in points.points.windows(2).enumerate() {
+                let (from, to) = (&pair[0], &pair[1]);
+                let time_gap = time_between(from, to);
+                let distance_m = haversine(from.point(), to.point());
+                let too_long = match (time_gap, options.max_time) {
+                    (Some(gap), Some(max)) => gap > max,
+                    _ => false,
+                };
+                let too_far = options.max_distance_m.is_some_and(|max| distance_m > max);
+                if too_long || too_far {
+                    gaps.push(PointGap {
+                        segment,
+                        from_index,
+                        time_gap,
+                        distance_m,
+                        from_point: from.point(),
+                        to_point: to.point(),
+                    });
+                }
+            }
+        }
+        gaps
+    }
+}
+
+fn time_between(from: &Waypoint, to: &Waypoint) -> Option<Duration> {
+    Some(OffsetDateTime::from(to.time?) - OffsetDateTime::from(from.time?))
+}
+
+impl fmt::Display for SegmentGap {
+    /// Gives a sentence like "segment 2 ends at 10:31:04, segment 3 starts
+    /// at 10:47:51, 2.3 km away". Times are in UTC, with the date if the
+    /// two are on different days.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let times = match (self.from_time, self.to_time) {
+            (Some(from), Some(to)) => {
+                let utc = |time: Time| OffsetDateTime::from(time).to_offset(UtcOffset::UTC);
+                let (from, to) = (utc(from), utc(to));
+                Some((from, to, from.date() == to.date()))
+            }
+            _ => None,
+        };
+        write!(f, "segment {} ends", self.from_segment)?;
+        if let Some((from, _, same_day)) = times {
+            write!(f, " at {}", clock(from, same_day))?;
+        }
+        write!(f, ", segment {} starts", self.to_segment)?;
+        if let Some((_, to, same_day)) = times {
+            write!(f, " at {},", clock(to, same_day))?;
+        }
+        if self.distance_m < 1000.0 {
+            write!(f, " {:.0} m away", self.distance_m)
+        } else {
+            write!(f, " {:.1} km away", self.distance_m / 1000.0)
+        }
+    }
+}
+
+/// Formats the time of day of `time`, with the date unless `same_day`.
+fn clock(time: OffsetDateTime, same_day: bool) -> String {
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        time.hour(),
+        time.minute(),
+        time.second()
+    );
+    if same_day {
+        clock
+    } else {
+        format!(
+            "{}-{:02}-{:02} {}",
+            time.year(),
+            u8::from(time.month()),
+            time.day(),
+            clock
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::GapOptions;
+    use crate::{read, Track, TrackSegment, Waypoint};
+
+    fn fixture() -> Track {
+        let file = File::open("tests/fixtures/segment_gaps.gpx").unwrap();
+        read(BufReader::new(file)).unwrap().tracks.remove(0)
+    }
+
+    #[test]
+    fn gap_between_segments() {
+        let gaps = fixture().gap_report();
+        assert_eq!(gaps.len(), 1);
+        let gap = &gaps[0];
+        assert_eq!((gap.from_segment, gap.to_segment), (0, 1));
+        assert_eq!(gap.time_gap, Some(Duration::seconds(16 * 60 + 47)));
+        assert!((gap.distance_m - 2300.0).abs() < 10.0);
+        assert_eq!(gap.from_point, Point::new(8.0125, 47.0));
+        assert_eq!(gap.to_point, Point::new(8.0125, 47.0207));
+        assert_eq!(
+            gap.to_string(),
+            "segment 0 ends at 10:31:04, segment 1 starts at 10:47:51, 2.3 km away"
+        );
+    }
+
+    #[test]
+    fn dropout_within_segment() {
+        let track = fixture();
+        let gaps = track.point_gaps(&GapOptions::default());
+        assert_eq!(gaps.len(), 1);
+        assert_eq!((gaps[0].segment, gaps[0].from_index), (0, 3));
+        assert_eq!(gaps[0].time_gap, Some(Duration::minutes(5)));
+        assert!(gaps[0].distance_m > 700.0 && gaps[0].distance_m < 900.0);
+
+        // The dropout is only a gap because of its time.
+        let options = GapOptions {
+            max_time: None,
+            max_distance_m: Some(1000.0),
+        };
+        assert!(track.point_gaps(&options).is_empty());
+    }
+
+    #[test]
+    fn untimed_and_empty_segments() {
+        let mut track = fixture();
+        track.segments.insert(1, TrackSegment::new());
+        let mut untimed = TrackSegment::new();
+        untimed
+            .points
+            .push(Waypoint::new(Point::new(8.0135, 47.0210)));
+        track.segments.push(untimed);
+
+        let gaps = track.gap_report();
+        assert_eq!(gaps.len(), 2);
+        assert_eq!((gaps[0].from_segment, gaps[0].to_segment), (0, 2));
+        assert_eq!((gaps[1].from_segment, gaps[1].to_segment), (2, 3));
+        assert_eq!(gaps[1].time_gap, None);
+        assert_eq!(
+            gaps[1].to_string(),
+            "segment 2 ends, segment 3 starts 33 m away"
+        );
+
+        // Across midnight, the dates are given too.
+        let mut gap = gaps[0].clone();
+        gap.to_time = Some((OffsetDateTime::UNIX_EPOCH + Duration::days(1)).into());
+        gap.from_time = Some(OffsetDateTime::UNIX_EPOCH.into());
+        assert_eq!(
+            gap.to_string(),
+            "segment 0 ends at 1970-01-01 00:00:00, segment 2 starts at 1970-01-02 00:00:00, \
+             2.3 km away"
+        );
+    }
+}
@@ -0,0 +1,671 @@
+//! garmin gives typed access to Garmin's GPX extensions.
+//!
+//! Extensions are kept as [`Element`]s on the GPX types. The structs here
+//! are converted from and to those elements, so they can be read and edited
+//! without walking the DOM by hand. Track colors are read and written by
+//! [`Track::display`](crate::Track::display) and
+//! [`Track::set_display`](crate::Track::set_display).
+//!
+//! ```
+//! use gpx::garmin::GarminWaypointExtension;
+//! use gpx::Waypoint;
+//!
+//! let mut waypoint = Waypoint::new_lat_lon(47.3769, 8.5417).unwrap();
+//! let mut extension = GarminWaypointExtension::default();
+//! extension.phone_numbers.push("+41 44 000 00 00".into());
+//! extension.apply_to(&mut waypoint);
+//!
+//! let read_back = GarminWaypointExtension::from_waypoint(&waypoint).unwrap();
+//! assert_eq!(read_back.phone_numbers, vec!["+41 44 000 00 00"]);
+//! ```
+
+use std::str::FromStr;
+
+use crate::dom::{Element, Node, OwnedName};
+use crate::errors::{GpxError, GpxResult};
+use crate::{Color, Route, Waypoint};
+
+/// The namespace of Garmin's GPX extensions, version 3.
+pub const GPXX_NAMESPACE: &str = "http://www.garmin.com/xmlschemas/GpxExtensions/v3";
+
+/// The prefix new elements in [`GPXX_NAMESPACE`] are written with.
+const GPXX_PREFIX: &str = "gpxx";
+
+/// The colors Garmin devices know, as in `<gpxx:DisplayColor>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GarminColor {
+    Black,
+    DarkRed,
+    DarkGreen,
+    DarkYellow,
+    DarkBlue,
+    DarkMagenta,
+    DarkCyan,
+    LightGray,
+    DarkGray,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Transparent,
+}
+
+/// The red, green and blue channels of a color.
+type Rgb = (u8, u8, u8);
+
+/// All colors with their names and, except for `Transparent`, their RGB
+/// values.
+const GARMIN_COLORS: &[(GarminColor, &str, Option<Rgb>)] = &[
+    (GarminColor::Black, "Black", Some((0x00, 0x00, 0x00))),
+    (GarminColor::DarkRed, "DarkRed", Some((0x8b, 0x00, 0x00))),
+    (
+        GarminColor::DarkGreen,
+        "DarkGreen",
+        Some((0x00, 0x64, 0x00)),
+    ),
+    (
+        GarminColor::DarkYellow,
+        "DarkYellow",
+        Some((0x8b, 0x8b, 0x00)),
+    ),
+    (GarminColor::DarkBlue, "DarkBlue", Some((0x00, 0x00, 0x8b))),
+    (
+        GarminColor::DarkMagenta,
+        "DarkMagenta",
+        Some((0x8b, 0x00, 0x8b)),
+    ),
+    (GarminColor::DarkCyan, "DarkCyan", Some((0x00, 0x8b, 0x8b))),
+    (
+        GarminColor::LightGray,
+        "LightGray",
+        Some((0xd3, 0xd3, 0xd3)),
+    ),
+    (GarminColor::DarkGray, "DarkGray", Some((0xa9, 0xa9, 0xa9))),
+    (GarminColor::Red, "Red", Some((0xff, 0x00, 0x00))),
+    (GarminColor::Green, "Green", Some((0x00, 0xff, 0x00))),
+    (GarminColor::Yellow, "Yellow", Some((0xff, 0xff, 0x00))),
+    (GarminColor::Blue, "Blue", Some((0x00, 0x00, 0xff))),
+    (GarminColor::Magenta, "Magenta", Some((0xff, 0x00, 0xff))),
+    (GarminColor::Cyan, "Cyan", Some((0x00, 0xff, 0xff))),
+    (GarminColor::White, "White", Some((0xff, 0xff, 0xff))),
+    (GarminColor::Transparent, "Transparent", None),
+];
+
+impl GarminColor {
+    /// Gives the name of the color, as written in `<gpxx:DisplayColor>`.
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Gives the RGB value of the color, or `None` for `Transparent`.
+    pub fn to_color(self) -> Option<Color> {
+        self.entry()
+            .2
+            .map(|(red, green, blue)| Color { red, green, blue })
+    }
+
+    fn entry(self) -> &'static (GarminColor, &'static str, Option<Rgb>) {
+        GARMIN_COLORS
+            .iter()
+            .find(|(garmin, _, _)| *garmin == self)
+            .expect("every color is listed")
+    }
+
+    /// Gives the Garmin color closest to `color`, which is exact for the
+    /// values given by [`GarminColor::to_color`].
+    ///
+    /// ```
+    /// use gpx::garmin::GarminColor;
+    /// use gpx::Color;
+    ///
+    /// let scarlet: Color = "F01414".parse().unwrap();
+    /// assert_eq!(GarminColor::nearest(scarlet), GarminColor::Red);
+    /// ```
+    pub fn nearest(color: Color) -> GarminColor {
+        let distance = |(red, green, blue): (u8, u8, u8)| {
+            let delta = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+            delta(red, color.red) + delta(green, color.green) + delta(blue, color.blue)
+        };
+        GARMIN_COLORS
+            .iter()
+            .filter_map(|&(garmin, _, rgb)| Some((garmin, distance(rgb?))))
+            .min_by_key(|&(_, distance)| distance)
+            .map_or(GarminColor::Black, |(garmin, _)| garmin)
+    }
+}
+
+impl FromStr for GarminColor {
+    type Err = GpxError;
+
+    /// Parses the name of a color, ignoring case.
+    fn from_str(name: &str) -> GpxResult<GarminColor> {
+        GARMIN_COLORS
+            .iter()
+            .find(|(_, known, _)| known.eq_ignore_ascii_case(name.trim()))
+            .map(|&(garmin, _, _)| garmin)
+            .ok_or_else(|| GpxError::InvalidColor(name.into()))
+    }
+}
+
+/// A postal address, as in `<gpxx:Address>`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Address {
+    /// The lines of the street address; the schema allows up to two.
+    pub street_address: Vec<String>,
+
+    /// The city.
+    pub city: Option<String>,
+
+    /// The state or province.
+    pub state: Option<String>,
+
+    /// The country.
+    pub country: Option<String>,
+
+    /// The postal code.
+    pub postal_code: Option<String>,
+}
+
+/// The content of `<gpxx:WaypointExtension>`, as written by Garmin devices
+/// and map software for points of interest.
+///
+/// Child elements that are missing are `None` or empty. Nested extensions
+/// and the `Category` attribute of phone numbers are not kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GarminWaypointExtension {
+    /// Distance in meters at which the device announces the waypoint.
+    pub proximity: Option<f64>,
+
+    /// Water temperature in degrees Celsius.
+    pub temperature: Option<f64>,
+
+    /// Water depth in meters.
+    pub depth: Option<f64>,
+
+    /// How the waypoint is shown on the map, such as `SymbolAndName`.
+    pub display_mode: Option<String>,
+
+    /// The categories the waypoint is listed under.
+    pub categories: Vec<String>,
+
+    /// The postal address.
+    pub address: Option<Address>,
+
+    /// The phone numbers.
+    pub phone_numbers: Vec<String>,
+}
+
+impl GarminWaypointExtension {
+    /// Reads the extension from a `<gpxx:WaypointExtension>` element, or
+    /// gives `None` for any other element.
+    pub fn from_element(element: &Element) -> Option<GarminWaypointExtension> {
+        if !is_gpxx(&element.name, "WaypointExtension") {
+            return None;
+        }
+        let mut extension = GarminWaypointExtension::default();
+        for child in element.elements() {
+            if child.name.namespace.as_deref() != Some(GPXX_NAMESPACE) {
+                continue;
+            }
+            match child.name.local_name.as_str() {
+                "Proximity" => extension.proximity = child.text().trim().parse().ok(),
+                "Temperature" => extension.temperature = child.text().trim().parse().ok(),
+                "Depth" => extension.depth = child.text().trim().parse().ok(),
+                "DisplayMode" => extension.display_mode = Some(child.text()),
+                "Categories" => {
+                    extension.categories = child
+                        .elements()
+                        .filter(|category| is_gpxx(&category.name, "Category"))
+                        .map(Element::text)
+                        .collect()
+                }
+                "Address" => extension.address = Some(read_address(child)),
+                "PhoneNumber" => extension.phone_numbers.push(child.text()),
+                _ => {}
+            }
+        }
+        Some(extension)
+    }
+
+    /// Reads the extension from the extensions of `waypoint`, if it has one.
+    pub fn from_waypoint(waypoint: &Waypoint) -> Option<GarminWaypointExtension> {
+        waypoint
+            .extras()
+            .extensions
+            .as_ref()?
+            .elements()
+            .find_map(GarminWaypointExtension::from_element)
+    }
+
+    /// Gives the `<gpxx:WaypointExtension>` element, with its children in
+    /// schema order.
+    pub fn to_element(&self) -> Element {
+        let mut element = gpxx_element("WaypointExtension");
+        push_text(&mut element, "Proximity", self.proximity);
+        push_text(&mut element, "Temperature", self.temperature);
+        push_text(&mut element, "Depth", self.depth);
+        push_text(&mut element, "DisplayMode", self.display_mode.as_ref());
+        if !self.categories.is_empty() {
+            let mut categories = gpxx_element("Categories");
+            for category in &self.categories {
+                push_text(&mut categories, "Category", Some(category));
+            }
+            element.children.push(Node::Element(categories));
+        }
+        if let Some(address) = &self.address {
+            let mut child = gpxx_element("Address");
+            for line in &address.street_address {
+                push_text(&mut child, "StreetAddress", Some(line));
+            }
+            push_text(&mut child, "City", address.city.as_ref());
+            push_text(&mut child, "State", address.state.as_ref());
+            push_text(&mut child, "Country", address.country.as_ref());
+            push_text(&mut child, "PostalCode", address.postal_code.as_ref());
+            element.children.push(Node::Element(child));
+        }
+        for number in &self.phone_numbers {
+            push_text(&mut element, "PhoneNumber", Some(number));
+        }
+        element
+    }
+
+    /// Stores the extension in the extensions of `waypoint`, replacing the
+    /// `<gpxx:WaypointExtension>` it already has, if any, in place. Other
+    /// extensions are left alone.
+    pub fn apply_to(&self, waypoint: &mut Waypoint) {
+        let extensions = waypoint
+            .extras_mut()
+            .extensions
+            .get_or_insert_with(|| Element::new(OwnedName::local("extensions")));
+        let element = self.to_element();
+        let existing = extensions.children.iter_mut().find(|child| match child {
+            Node::Element(child) => is_gpxx(&child.name, "WaypointExtension"),
+            Node::Text(_) => false,
+        });
+        match existing {
+            Some(existing) => *existing = Node::Element(element),
+            None => extensions.children.push(Node::Element(element)),
+        }
+    }
+}
+
+/// The kind of a course point, as in the `<gpxx:PointType>` of a
+/// `<gpxx:CoursePointExtension>`, which Garmin Edge devices announce as cues
+/// along a course.
+///
+/// The known types are those of Garmin's course point schema; any other
+/// name is kept as [`CoursePointType::Other`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CoursePointType {
+    /// A point without a more specific kind.
+    Generic,
+    /// The top of a climb.
+    Summit,
+    /// The bottom of a descent.
+    Valley,
+    /// A place to get water.
+    Water,
+    /// A place to get food.
+    Food,
+    /// A hazard ahead.
+    Danger,
+    /// A left turn.
+    Left,
+    /// A right turn.
+    Right,
+    /// Going straight on, as at a junction.
+    Straight,
+    /// A first aid station.
+    FirstAid,
+    /// The top of a fourth category climb, the easiest of those ranked.
+    FourthCategory,
+    /// The top of a third category climb.
+    ThirdCategory,
+    /// The top of a second category climb.
+    SecondCategory,
+    /// The top of a first category climb.
+    FirstCategory,
+    /// The top of a climb too hard for the categories.
+    HorsCategory,
+    /// The start of a sprint.
+    Sprint,
+    /// A type not listed above, with its name as written.
+    Other(String),
+}
+
+/// All known course point types with their names.
+const COURSE_POINT_TYPES: &[(CoursePointType, &str)] = &[
+    (CoursePointType::Generic, "Generic"),
+    (CoursePointType::Summit, "Summit"),
+    (CoursePointType::Valley, "Valley"),
+    (CoursePointType::Water, "Water"),
+    (CoursePointType::Food, "Food"),
+    (CoursePointType::Danger, "Danger"),
+    (CoursePointType::Left, "Left"),
+    (CoursePointType::Right, "Right"),
+    (CoursePointType::Straight, "Straight"),
+    (CoursePointType::FirstAid, "First Aid"),
+    (CoursePointType::FourthCategory, "4th Category"),
+    (CoursePointType::ThirdCategory, "3rd Category"),
+    (CoursePointType::SecondCategory, "2nd Category"),
+    (CoursePointType::FirstCategory, "1st Category"),
+    (CoursePointType::HorsCategory, "Hors Category"),
+    (CoursePointType::Sprint, "Sprint"),
+];
+
+impl CoursePointType {
+    /// Gives the type for `name`, ignoring case for the known types.
+    pub fn from_name(name: &str) -> CoursePointType {
+        let name = name.trim();
+        COURSE_POINT_TYPES
+            .iter()
+            .find(|(_, known)| known.eq_ignore_ascii_case(name))
+            .map_or_else(
+                || CoursePointType::Other(name.into()),
+                |(known, _)| known.clone(),
+            )
+    }
+
+    /// Gives the name of the type, as written in `<gpxx:PointType>`.
+    pub fn name(&self) -> &str {
+        match self {
+            CoursePointType::Other(name) => name,
+            known => COURSE_POINT_TYPES
+                .iter()
+                .find(|(other, _)| other == known)
+                .map_or("", |(_, name)| name),
+        }
+    }
+}
+
+impl Waypoint {
+    /// Gives the course point type from the `<gpxx:CoursePointExtension>`
+    /// in the extensions of the point, if it has one.
+    ///
+    /// ```
+    /// use gpx::garmin::CoursePointType;
+    /// use gpx::Waypoint;
+    ///
+    /// let mut point = Waypoint::new_lat_lon(46.02, 7.75).unwrap();
+    /// assert_eq!(point.course_point_type(), None);
+    /// point.set_course_point_type(CoursePointType::Left);
+    /// assert_eq!(point.course_point_type(), Some(CoursePointType::Left));
+    /// ```
+    pub fn course_point_type(&self) -> Option<CoursePointType> {
+        let extension = self
+            .extras()
+            .extensions
+            .as_ref()?
+            .elements()
+            .find(|element| is_gpxx(&element.name, "CoursePointExtension"))?;
+        extension
+            .elements()
+            .find(|element| is_gpxx(&element.name, "PointType"))
+            .map(|element| CoursePointType::from_name(&element.text()))
+    }
+
+    /// Sets the course point type in the `<gpxx:CoursePointExtension>` of
+    /// the point, adding the extension if the point has none. Other
+    /// extensions, and other children of the course point extension, are
+    /// left alone.
+    pub fn set_course_point_type(&mut self, point_type: CoursePointType) {
+        let extensions = self
+            .extras_mut()
+            .extensions
+            .get_or_insert_with(|| Element::new(OwnedName::local("extensions")));
+        let is_extension = |child: &Node| match child {
+            Node::Element(child) => is_gpxx(&child.name, "CoursePointExtension"),
+            Node::Text(_) => false,
+        };
+        if !extensions.children.iter().any(is_extension) {
+            extensions
+                .children
+                .push(Node::Element(gpxx_element("CoursePointExtension")));
+        }
+        let extension = extensions
+            .children
+            .iter_mut()
+            .find_map(|child| match child {
+                Node::Element(child) if is_gpxx(&child.name, "CoursePointExtension") => Some(child),
+                _ => None,
+            })
+            .expect("there is a course point extension");
+        extension.children.retain(|child| match child {
+            Node::Element(child) => !is_gpxx(&child.name, "PointType"),
+            Node::Text(_) => true,
+        });
+        push_text(extension, "PointType", Some(point_type.name()));
+    }
+}
+
+impl Route {
+    /// Gives the index and type of every route point that is a course
+    /// point, as read by [`Waypoint::course_point_type`].
+    pub fn course_points(&self) -> Vec<(usize, CoursePointType)> {
+        self.points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, point)| Some((index, point.course_point_type()?)))
+            .collect()
+    }
+}
+
+fn read_address(element: &Element) -> Address {
+    let mut address = Address::default();
+    for child in element.elements() {
+        if child.name.namespace.as_deref() != Some(GPXX_NAMESPACE) {
+            continue;
+        }
+        match child.name.local_name.as_str() {
+            "StreetAddress" => address.street_address.push(child.text()),
+            "City" => address.city = Some(child.text()),
+            "State" => address.state = Some(child.text()),
+            "Country" => address.country = Some(child.text()),
+            "PostalCode" => address.postal_code = Some(child.text()),
+            _ => {}
+        }
+    }
+    address
+}
+
+pub(crate) fn is_gpxx(name: &OwnedName, local_name: &str) -> bool {
+    name.local_name == local_name && name.namespace.as_deref() == Some(GPXX_NAMESPACE)
+}
+
+pub(crate) fn gpxx_element(local_name: &str) -> Element {
+    Element::new(OwnedName::qualified(
+        local_name,
+        GPXX_NAMESPACE,
+        Some(GPXX_PREFIX.into()),
+    ))
+}
+
+pub(crate) fn push_text(parent: &mut Element, local_name: &str, value: Option<impl ToString>) {
+    if let Some(value) = value {
+        let mut element = gpxx_element(local_name);
+        element.children.push(Node::Text(value.to_string()));
+        parent.children.push(Node::Element(element));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use geo_types::Point;
+
+    use super::{
+        Address, CoursePointType, GarminColor, GarminWaypointExtension, GARMIN_COLORS,
+        GPXX_NAMESPACE,
+    };
+    use crate::dom::{Element, Node, OwnedName};
+    use crate::{read, write_to_string, Gpx, GpxVersion, Route, Waypoint};
+
+    fn fixture() -> Gpx {
+        let file = File::open("tests/fixtures/garmin_poi.gpx").unwrap();
+        read(BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn read_waypoint_extension() {
+        let gpx = fixture();
+
+        let bakery = GarminWaypointExtension::from_waypoint(&gpx.waypoints[0]).unwrap();
+        assert_eq!(bakery.display_mode.as_deref(), Some("SymbolAndName"));
+        assert_eq!(bakery.categories, vec!["Food", "Bakery"]);
+        assert_eq!(
+            bakery.address,
+            Some(Address {
+                street_address: vec!["Musterstrasse 1".into(), "Hinterhaus".into()],
+                city: Some("Zuerich".into()),
+                state: Some("ZH".into()),
+                country: Some("Switzerland".into()),
+                postal_code: Some("8000".into()),
+            })
+        );
+        assert_eq!(
+            bakery.phone_numbers,
+            vec!["+41 44 000 00 00", "+41 79 000 00 00"]
+        );
+
+        let viewpoint = GarminWaypointExtension::from_waypoint(&gpx.waypoints[1]).unwrap();
+        assert_eq!(viewpoint.proximity, Some(150.0));
+        assert_eq!(viewpoint.display_mode, None);
+        assert!(viewpoint.categories.is_empty());
+        assert!(viewpoint.phone_numbers.is_empty());
+        let address = viewpoint.address.unwrap();
+        assert!(address.street_address.is_empty());
+        assert_eq!(address.city.as_deref(), Some("Zuerich"));
+        assert_eq!(address.postal_code, None);
+
+        assert_eq!(
+            GarminWaypointExtension::from_waypoint(&gpx.waypoints[2]),
+            None
+        );
+    }
+
+    #[test]
+    fn waypoint_extension_round_trip() {
+        let original = fixture();
+
+        // Rebuild the extensions from the typed data alone.
+        let mut rebuilt = original.clone();
+        for waypoint in &mut rebuilt.waypoints {
+            if let Some(extension) = GarminWaypointExtension::from_waypoint(waypoint) {
+                waypoint.extras_mut().extensions = None;
+                extension.apply_to(waypoint);
+            }
+        }
+        let reread = read(write_to_string(&rebuilt).unwrap().as_bytes()).unwrap();
+
+        let extensions = |gpx: &Gpx| -> Vec<Option<Element>> {
+            gpx.waypoints
+                .iter()
+                .map(|waypoint| waypoint.extras().extensions.clone())
+                .collect()
+        };
+        assert_eq!(extensions(&reread), extensions(&original));
+    }
+
+    #[test]
+    fn apply_to_replaces_in_place() {
+        let mut waypoint = Waypoint::new(Point::new(8.5, 47.4));
+        let other = Element::new(OwnedName::qualified("other", "http://example.com", None));
+        let mut extensions = Element::new(OwnedName::local("extensions"));
+        extensions.children.push(Node::Element(other.clone()));
+        waypoint.extras_mut().extensions = Some(extensions);
+
+        let mut extension = GarminWaypointExtension::default();
+        extension.apply_to(&mut waypoint);
+        extension.display_mode = Some("SymbolOnly".into());
+        extension.apply_to(&mut waypoint);
+
+        let children: Vec<&Element> = waypoint
+            .extras()
+            .extensions
+            .as_ref()
+            .unwrap()
+            .elements()
+            .collect();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0], &other);
+        assert_eq!(
+            GarminWaypointExtension::from_element(children[1]),
+            Some(extension)
+        );
+    }
+
+    #[test]
+    fn course_point_round_trip() {
+        let mut route = Route::new();
+        for i in 0..5 {
+            let point = Waypoint::new_lat_lon(46.0 + f64::from(i) * 0.01, 7.7).unwrap();
+            route.points.push(point);
+        }
+        route.points[0].set_course_point_type(CoursePointType::Food);
+        route.points[2].set_course_point_type(CoursePointType::Right);
+        route.points[2].set_course_point_type(CoursePointType::Left);
+        route.points[3].set_course_point_type(CoursePointType::Other("Segment Start".into()));
+        let gpx = Gpx {
+            version: GpxVersion::Gpx11,
+            routes: vec![route],
+            ..Default::default()
+        };
+
+        let written = write_to_string(&gpx).unwrap();
+        assert!(written.contains("<gpxx:PointType>Segment Start</gpxx:PointType>"));
+        let reread = read(written.as_bytes()).unwrap();
+        let expected = vec![
+            (0, CoursePointType::Food),
+            (2, CoursePointType::Left),
+            (3, CoursePointType::Other("Segment Start".into())),
+        ];
+        assert_eq!(reread.routes[0].course_points(), expected);
+
+        // Only the point type is replaced.
+        let point = &reread.routes[0].points[2];
+        let extension = point
+            .extras()
+            .extensions
+            .as_ref()
+            .unwrap()
+            .elements()
+            .next()
+            .unwrap();
+        assert_eq!(extension.name.namespace.as_deref(), Some(GPXX_NAMESPACE));
+        assert_eq!(extension.elements().count(), 1);
+    }
+
+    #[test]
+    fn color_names() {
+        assert_eq!(GarminColor::DarkCyan.name(), "DarkCyan");
+        assert_eq!(GarminColor::Transparent.to_color(), None);
+        assert_eq!(GARMIN_COLORS.len(), GarminColor::Transparent as usize + 1);
+        for &(garmin, name, _) in GARMIN_COLORS {
+            assert_eq!(garmin.name(), name);
+            assert_eq!(name.parse::<GarminColor>().unwrap(), garmin);
+            if let Some(color) = garmin.to_color() {
+                assert_eq!(GarminColor::nearest(color), garmin);
+            }
+        }
+    }
+
+    #[test]
+    fn course_point_type_names() {
+        assert_eq!(
+            CoursePointType::from_name(" first aid "),
+            CoursePointType::FirstAid
+        );
+        assert_eq!(CoursePointType::FirstAid.name(), "First Aid");
+        assert_eq!(CoursePointType::HorsCategory.name(), "Hors Category");
+        assert_eq!(
+            CoursePointType::from_name("U-Turn"),
+            CoursePointType::Other("U-Turn".into())
+        );
+        assert_eq!(CoursePointType::Other("U-Turn".into()).name(), "U-Turn");
+    }
+}
@@ -0,0 +1,340 @@
+//! geofence tests tracks against areas such as park boundaries.
+//!
+//! Polygons are taken as drawn on a plain longitude/latitude map, with
+//! straight edges between their corners, which is how boundaries are
+//! usually given. Points on an edge count as within the polygon, and
+//! points in a hole do not. This is done directly rather than through the
+//! `geo` crate, which would be a large dependency for these few tests.
+
+use geo_types::{Coord, LineString, Polygon};
+
+use crate::distance::haversine;
+use crate::{Gpx, TrackSegment};
+
+/// Options for measuring how much of a track is within a polygon, as with
+/// [`TrackSegment::fraction_within_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GeofenceOptions {
+    /// Whether to weigh the legs between points by their length, so that
+    /// the fraction is one of the distance rather than of the points. A leg
+    /// counts as within if its middle is.
+    pub by_distance: bool,
+}
+
+/// Where a point is, relative to a ring or polygon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+impl TrackSegment {
+    /// Whether the segment enters or touches `poly`: whether a point is
+    /// within it, or a leg between two points crosses or touches an edge.
+    ///
+    /// ```
+    /// use geo_types::{LineString, Polygon};
+    /// use gpx::{TrackSegment, Waypoint};
+    ///
+    /// let park = Polygon::new(
+    ///     LineString::from(vec![(8.0, 47.0), (9.0, 47.0), (9.0, 48.0), (8.0, 48.0)]),
+    ///     vec![],
+    /// );
+    /// let mut segment = TrackSegment::new();
+    /// segment.points.push(Waypoint::new_lat_lon(47.5, 7.5).unwrap());
+    /// segment.points.push(Waypoint::new_lat_lon(47.5, 9.5).unwrap());
+    /// assert!(segment.intersects_polygon(&park));
+    /// assert_eq!(segment.fraction_within(&park), 0.0);
+    /// ```
+    pub fn intersects_polygon(&self, poly: &Polygon<f64>) -> bool {
+        self.points
+            .iter()
+            .any(|point| polygon_location(poly, point.point().0) != Location::Outside)
+            || self.points.windows(2).any(|leg| {
+                let (a, b) = (leg[0].point().0, leg[1].point().0);
+                rings(poly).any(|ring| ring.lines().any(|edge| touches(a, b, edge.start, edge.end)))
+            })
+    }
+
+    /// Gives the fraction of the points of the segment within `poly`, from
+    /// 0 to 1, or 0 for an empty segment.
+    pub fn fraction_within(&self, poly: &Polygon<f64>) -> f64 {
+        self.fraction_within_with_options(poly, &GeofenceOptions::default())
+    }
+
+    /// Like [`TrackSegment::fraction_within`], tuned by `options`. A segment
+    /// without length is measured by its points even by distance.
+    pub fn fraction_within_with_options(
+        &self,
+        poly: &Polygon<f64>,
+        options: &GeofenceOptions,
+    ) -> f64 {
+        if options.by_distance {
+            let (mut within, mut total) = (0.0, 0.0);
+            for leg in self.points.windows(2) {
+                let (a, b) = (leg[0].point(), leg[1].point());
+                let length = haversine(a, b);
+                let middle = Coord {
+                    x: (a.x() + b.x()) / 2.0,
+                    y: (a.y() + b.y()) / 2.0,
+                };
+                if polygon_location(poly, middle) != Location::Outside {
+                    within += length;
+                }
+                total += length;
+            }
+            if total > 0.0 {
+                return within / total;
+            }
+        }
+        if self.points.is_empty() {
+            return 0.0;
+        }
+        let within = self
+            .points
+            .iter()
+            .filter(|point| polygon_location(poly, point.point().0) != Location::Outside)
+            .count();
+        within as f64 / self.points.len() as f64
+    }
+}
+
+impl Gpx {
+    /// Gives the indices of the tracks with a segment that enters or
+    /// touches `poly`, see [`TrackSegment::intersects_polygon`].
+    pub fn tracks_intersecting(&self, poly: &Polygon<f64>) -> Vec<usize> {
+        self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| {
+                track
+                    .segments
+                    .iter()
+                    .any(|segment| segment.intersects_polygon(poly))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// Gives the exterior and the holes of `poly`.
+fn rings(poly: &Polygon<f64>) -> impl Iterator<Item = &LineString<f64>> {
+    std::iter::once(poly.exterior()).chain(poly.interiors())
+}
+
+/// Finds `point` inside the exterior of `poly` and outside its holes, where
+/// the edges of the holes belong to the polygon.
+fn polygon_location(poly: &Polygon<f64>, point: Coord<f64>) -> Location {
+    match ring_location(poly.exterior(), point) {
+        Location::Inside => {}
+        other => return other,
+    }
+    for hole in poly.interiors() {
+        match ring_location(hole, point) {
+            Location::Inside => return Location::Outside,
+            Location::Boundary => return Location::Boundary,
+            Location::Outside => {}
+        }
+    }
+    Location::Inside
+}
+
+/// Finds `point` relative to `ring` by casting a ray towards positive x and
+/// counting the edges it crosses. [`Polygon::new`] closes its rings, so the
+/// edges go all around.
+fn ring_location(ring: &LineString<f64>, point: Coord<f64>) -> Location {
+    let mut inside = false;
+    for edge in ring.lines() {
+        let (a, b) = (edge.start, edge.end);
+        if on_edge(point, a, b) {
+            return Location::Boundary;
+        }
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    if inside {
+        Location::Inside
+    } else {
+        Location::Outside
+    }
+}
+
+/// The sign of the turn from `a` to `b` to `c`: positive to the left,
+/// negative to the right and zero if they are on one line.
+fn orientation(a: Coord<f64>, b: Coord<f64>, c: Coord<f64>) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// How far from an edge a point may be and still count as on it, relative
+/// to the length of the edge, which takes up the rounding of points
+/// computed along diagonal edges.
+const EDGE_TOLERANCE: f64 = 1e-9;
+
+/// Whether `point` is on the edge from `a` to `b`.
+fn on_edge(point: Coord<f64>, a: Coord<f64>, b: Coord<f64>) -> bool {
+    // The orientation is the distance of the point from the line through
+    // the edge, times the length of the edge.
+    let length_squared = (b.x - a.x).powi(2) + (b.y - a.y).powi(2);
+    orientation(a, b, point).abs() <= EDGE_TOLERANCE * length_squared
+        && point.x >= a.x.min(b.x)
+        && point.x <= a.x.max(b.x)
+        && point.y >= a.y.min(b.y)
+        && point.y <= a.y.max(b.y)
+}
+
+/// Whether the leg from `a` to `b` crosses or touches the edge from `c` to
+/// `d`.
+fn touches(a: Coord<f64>, b: Coord<f64>, c: Coord<f64>, d: Coord<f64>) -> bool {
+    let (abc, abd) = (orientation(a, b, c), orientation(a, b, d));
+    let (cda, cdb) = (orientation(c, d, a), orientation(c, d, b));
+    if abc * abd < 0.0 && cda * cdb < 0.0 {
+        return true;
+    }
+    on_edge(c, a, b) || on_edge(d, a, b) || on_edge(a, c, d) || on_edge(b, c, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{LineString, Polygon};
+
+    use super::GeofenceOptions;
+    use crate::{Gpx, Track, TrackSegment, Waypoint};
+
+    fn ring(corners: &[(f64, f64)]) -> LineString<f64> {
+        LineString::from(corners.to_vec())
+    }
+
+    /// The square from 0 to 10 in both directions.
+    fn square() -> Polygon<f64> {
+        Polygon::new(
+            ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            vec![],
+        )
+    }
+
+    /// The square with a hole from 4 to 6 in both directions.
+    fn square_with_hole() -> Polygon<f64> {
+        Polygon::new(
+            square().exterior().clone(),
+            vec![ring(&[(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)])],
+        )
+    }
+
+    fn segment(points: &[(f64, f64)]) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        for &(x, y) in points {
+            segment.points.push(Waypoint::new_lat_lon(y, x).unwrap());
+        }
+        segment
+    }
+
+    #[test]
+    fn simple_square() {
+        let square = square();
+        let crossing = segment(&[(-5.0, 5.0), (2.0, 5.0), (8.0, 5.0), (15.0, 5.0)]);
+        assert!(crossing.intersects_polygon(&square));
+        assert_eq!(crossing.fraction_within(&square), 0.5);
+
+        let by_distance = GeofenceOptions { by_distance: true };
+        let fraction = crossing.fraction_within_with_options(&square, &by_distance);
+        assert!((fraction - 6.0 / 20.0).abs() < 1e-3, "{}", fraction);
+
+        let inside = segment(&[(1.0, 1.0), (9.0, 9.0)]);
+        assert_eq!(inside.fraction_within(&square), 1.0);
+        assert_eq!(
+            inside.fraction_within_with_options(&square, &by_distance),
+            1.0
+        );
+        assert_eq!(TrackSegment::new().fraction_within(&square), 0.0);
+    }
+
+    #[test]
+    fn hole_is_outside() {
+        let poly = square_with_hole();
+        let in_hole = segment(&[(5.0, 5.0), (5.5, 4.5)]);
+        assert!(!in_hole.intersects_polygon(&poly));
+        assert_eq!(in_hole.fraction_within(&poly), 0.0);
+        assert!(in_hole.intersects_polygon(&square()));
+
+        // Out of the hole, across the polygon and out of it.
+        let leaving = segment(&[(5.0, 5.0), (8.0, 5.0), (12.0, 5.0)]);
+        assert!(leaving.intersects_polygon(&poly));
+        assert_eq!(leaving.fraction_within(&poly), 1.0 / 3.0);
+
+        // The edge of the hole belongs to the polygon.
+        let on_hole_edge = segment(&[(4.0, 5.0), (5.0, 5.0)]);
+        assert_eq!(on_hole_edge.fraction_within(&poly), 0.5);
+    }
+
+    #[test]
+    fn skimming_the_boundary() {
+        let square = square();
+        // Along the top edge, from outside to outside.
+        let along = segment(&[(-2.0, 10.0), (12.0, 10.0)]);
+        assert!(along.intersects_polygon(&square));
+        assert_eq!(along.fraction_within(&square), 0.0);
+
+        // Through the corner only.
+        let corner = segment(&[(8.0, 12.0), (12.0, 8.0)]);
+        assert!(corner.intersects_polygon(&square));
+
+        // A point on an edge is within.
+        let on_edge = segment(&[(10.0, 5.0), (12.0, 5.0)]);
+        assert_eq!(on_edge.fraction_within(&square), 0.5);
+
+        // Cutting the corner with both points outside.
+        let cut = segment(&[(9.0, 11.0), (11.0, 9.0)]);
+        assert!(cut.intersects_polygon(&square));
+        assert_eq!(cut.fraction_within(&square), 0.0);
+    }
+
+    #[test]
+    fn points_on_diagonal_edges() {
+        let corners = [(8.3, 47.1), (8.9, 47.4), (8.6, 48.0), (8.0, 47.7)];
+        let diamond = Polygon::new(ring(&corners), vec![]);
+        let mut points = Vec::new();
+        for (i, &(ax, ay)) in corners.iter().enumerate() {
+            let (bx, by) = corners[(i + 1) % corners.len()];
+            for step in 1..10 {
+                let t = f64::from(step) / 10.0;
+                points.push((ax + (bx - ax) * t, ay + (by - ay) * t));
+            }
+        }
+        let on_edges = segment(&points);
+        assert_eq!(on_edges.fraction_within(&diamond), 1.0);
+        let beside = Polygon::new(ring(&[(8.0, 47.0), (8.3, 47.1), (8.0, 47.7)]), vec![]);
+        // Only the edge it shares with the diamond.
+        assert_eq!(on_edges.fraction_within(&beside), 0.25);
+    }
+
+    #[test]
+    fn entirely_outside() {
+        let square = square();
+        let outside = segment(&[(11.0, 0.0), (11.0, 10.0), (20.0, 20.0), (-1.0, 11.0)]);
+        assert!(!outside.intersects_polygon(&square));
+        assert_eq!(outside.fraction_within(&square), 0.0);
+        assert_eq!(
+            outside.fraction_within_with_options(&square, &GeofenceOptions { by_distance: true }),
+            0.0
+        );
+
+        let mut gpx = Gpx::default();
+        for segment in [outside, segment(&[(20.0, 20.0), (5.0, 5.0)])].iter() {
+            let mut track = Track::new();
+            track.segments.push(segment.clone());
+            gpx.tracks.push(track);
+        }
+        gpx.tracks[0]
+            .segments
+            .push(segment(&[(10.5, 9.5), (9.5, 10.5)]));
+        assert_eq!(gpx.tracks_intersecting(&square), [0, 1]);
+        assert_eq!(gpx.tracks_intersecting(&square_with_hole()), [0, 1]);
+        gpx.tracks[0].segments.pop();
+        assert_eq!(gpx.tracks_intersecting(&square), [1]);
+    }
+}
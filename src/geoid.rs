@@ -0,0 +1,150 @@
+//! geoid converts between the heights above the geoid that GPX elevations
+//! are, and the heights above the WGS 84 ellipsoid that receivers measure.
+//!
+//! No geoid model is included: the height of the geoid comes from the
+//! `geoidheight` of the points, or is given for an area.
+
+use crate::errors::{GpxError, GpxResult};
+use crate::types::validate_finite;
+use crate::{ChangeReport, TrackSegment, Waypoint};
+
+impl Waypoint {
+    /// Gives the elevation above mean sea level, which is what `elevation`
+    /// holds.
+    pub fn orthometric_elevation(&self) -> Option<f64> {
+        self.elevation
+    }
+
+    /// Gives the height above the WGS 84 ellipsoid, the elevation plus the
+    /// height of the geoid, if the point has both.
+    pub fn ellipsoidal_elevation(&self) -> Option<f64> {
+        Some(self.elevation? + self.extras().geoidheight?)
+    }
+
+    /// Sets the elevation from a height above the WGS 84 ellipsoid, by
+    /// subtracting the height of the geoid.
+    ///
+    /// A point without a `geoidheight`, or a height that is not finite,
+    /// gives an error and keeps its elevation.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let mut point = Waypoint::new_lat_lon(47.4, 8.5).unwrap();
+    /// point.extras_mut().geoidheight = Some(48.5);
+    /// point.set_elevation_from_ellipsoidal(456.0).unwrap();
+    /// assert_eq!(point.elevation, Some(407.5));
+    /// assert_eq!(point.ellipsoidal_elevation(), Some(456.0));
+    /// ```
+    pub fn set_elevation_from_ellipsoidal(&mut self, height: f64) -> GpxResult<()> {
+        let geoidheight = self
+            .extras()
+            .geoidheight
+            .ok_or(GpxError::MissingGeoidHeight)?;
+        self.elevation = Some(validate_finite("ele", height)? - geoidheight);
+        Ok(())
+    }
+}
+
+impl TrackSegment {
+    /// Takes the elevations of the points to be heights above the WGS 84
+    /// ellipsoid and turns them into elevations above mean sea level, with a
+    /// geoid `offset` meters above the ellipsoid throughout, which is close
+    /// enough over a small area. The points changed are reported as
+    /// modified.
+    ///
+    /// The `geoidheight` of the changed points is set to `offset`. Points
+    /// that already have a `geoidheight` are taken to be converted and left
+    /// alone, so applying an offset twice changes nothing. Points without an
+    /// elevation are left alone too, so that they don't get extras.
+    pub fn apply_geoid_offset(&mut self, offset: f64) -> ChangeReport {
+        let mut changed = 0;
+        for point in &mut self.points {
+            if point.geoidheight().is_some() {
+                continue;
+            }
+            if let Some(elevation) = &mut point.elevation {
+                *elevation -= offset;
+                point.set_geoidheight(Some(offset));
+                changed += 1;
+            }
+        }
+        let len = self.points.len();
+        ChangeReport::of_segment("apply_geoid_offset", len, len, changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use crate::{TrackSegment, Waypoint};
+
+    fn point(elevation: Option<f64>, geoidheight: Option<f64>) -> Waypoint {
+        let mut point = Waypoint::new(Point::new(8.5, 47.4));
+        point.elevation = elevation;
+        point.set_geoidheight(geoidheight);
+        point
+    }
+
+    const CASES: [(Option<f64>, Option<f64>); 4] = [
+        (Some(400.0), Some(48.5)),
+        (Some(400.0), None),
+        (None, Some(48.5)),
+        (None, None),
+    ];
+
+    #[test]
+    fn elevations() {
+        let expected = [Some(448.5), None, None, None];
+        for ((elevation, geoidheight), ellipsoidal) in CASES.iter().zip(&expected) {
+            let point = point(*elevation, *geoidheight);
+            assert_eq!(point.orthometric_elevation(), *elevation);
+            assert_eq!(point.ellipsoidal_elevation(), *ellipsoidal);
+        }
+    }
+
+    #[test]
+    fn set_elevation_from_ellipsoidal() {
+        for (elevation, geoidheight) in CASES.iter() {
+            let mut point = point(*elevation, *geoidheight);
+            let result = point.set_elevation_from_ellipsoidal(450.0);
+            if geoidheight.is_some() {
+                assert!(result.is_ok());
+                assert_eq!(point.elevation, Some(401.5));
+            } else {
+                assert!(result.is_err());
+                assert_eq!(point.elevation, *elevation);
+            }
+        }
+        let mut point = point(Some(400.0), Some(48.5));
+        assert!(point.set_elevation_from_ellipsoidal(f64::NAN).is_err());
+        assert_eq!(point.elevation, Some(400.0));
+    }
+
+    #[test]
+    fn apply_geoid_offset() {
+        let mut segment = TrackSegment::new();
+        for (elevation, geoidheight) in CASES.iter() {
+            segment.points.push(point(*elevation, *geoidheight));
+        }
+        assert_eq!(segment.apply_geoid_offset(50.0).modified, 1);
+        let heights: Vec<_> = segment
+            .points
+            .iter()
+            .map(|point| (point.elevation, point.extras().geoidheight))
+            .collect();
+        assert_eq!(
+            heights,
+            vec![
+                (Some(400.0), Some(48.5)),
+                (Some(350.0), Some(50.0)),
+                (None, Some(48.5)),
+                (None, None),
+            ]
+        );
+        assert!(segment.points[3].extras.is_none());
+        assert!(segment.apply_geoid_offset(50.0).is_unchanged());
+        assert_eq!(segment.points[1].ellipsoidal_elevation(), Some(400.0));
+    }
+}
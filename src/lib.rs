@@ -32,14 +32,139 @@
 //! ```
 
 // Export our type structs in the root, along with the read and write functions.
-pub use crate::reader::read;
+pub use crate::accuracy::{AccuracyOptions, GpsQuality};
+pub use crate::activity::{ActivityKind, ActivityOptions, ActivitySpan};
+pub use crate::annotations::{AnnotatedGpx, PointId};
+pub use crate::assembler::GpxAssembler;
+pub use crate::capabilities::{
+    DeviceProfile, ExtensionUse, GpxCapabilities, Incompatibility, Limit, Presence,
+};
+pub use crate::coverage::{FieldCounts, FieldCoverage, PointField};
+pub use crate::csv::CsvOptions;
+pub use crate::dedup::{KeepPolicy, Tolerance, WaypointKey};
+pub use crate::deep_size::SizeBreakdown;
+pub use crate::display::{Color, DisplaySchema, TrackDisplay, GPX_STYLE_NAMESPACE};
+pub use crate::distance::{TrackPosition, EARTH_RADIUS_M};
+pub use crate::elevation::{
+    ConstantElevation, ElevationProvider, ElevationTable, FillOptions, FillReport,
+};
+pub use crate::extension_sink::{ExtensionSink, PointKind};
+pub use crate::fingerprint::{FingerprintBuilder, FingerprintOptions, GpxFingerprint};
+pub use crate::gaps::{GapOptions, PointGap, SegmentGap};
+pub use crate::geofence::GeofenceOptions;
+pub use crate::numbering::NumberingScheme;
+pub use crate::parts::GpxParts;
+pub use crate::pauses::{Pause, PauseOptions};
+pub use crate::point3d::GpxPoint;
+#[doc(hidden)]
+pub use crate::point_ref::Generation;
+pub use crate::point_ref::PointRef;
+pub use crate::positions::{ElementPath, SourcePosition, SourcePositions};
+pub use crate::profile::ProfileOptions;
+pub use crate::reader::{
+    read, read_partial, read_partial_with_options, read_with_options, read_with_positions,
+    LeapSecondPolicy, LongStringPolicy, ReadOptions, VersionCompat,
+};
+pub use crate::report::{ChangeReport, SegmentChange};
+pub use crate::rollover::{TimeAnomaly, TimeAnomalyKind, TimeAnomalyOptions, GPS_WEEK_ROLLOVER};
+pub use crate::segments::ExtensionsPolicy;
+pub use crate::smooth::{SmoothMethod, SmoothOptions};
+pub use crate::sniff::DetectedFormat;
+pub use crate::split::{split, SplitBudget};
+pub use crate::stats::{StatsOptions, TrackStats};
+pub use crate::stitch::{stitch, StitchOptions};
+pub use crate::swap::{SwapDiagnosis, SwapEvidence};
+pub use crate::taxonomy::TypeMatchOptions;
+pub use crate::text::TextNormalizeOptions;
+pub use crate::times::SynthesizeTimesOptions;
+#[cfg(feature = "timezones")]
+pub use crate::timezones::{TimeZoneId, TimezoneFinder, Tzdb};
+pub use crate::transform::{ElevationUnit, SpeedUnit};
 pub use crate::types::*;
-pub use crate::writer::{write, write_with_event_writer};
+pub use crate::validate::{CoordinateAxis, CoordinateViolation};
+pub use crate::writer::{
+    write, write_route, write_route_with_options, write_to_string, write_to_vec, write_track,
+    write_track_with_options, write_waypoints, write_waypoints_with_options,
+    write_with_event_writer, write_with_options, WriteOptions,
+};
+#[cfg(feature = "xsd-validation")]
+pub use crate::xsd::{validate_xml, XsdViolation, GPX_1_0_XSD, GPX_1_1_XSD};
+#[cfg(feature = "zip")]
+pub use crate::zip::{read_zip_entries, write_zip};
 
+mod accuracy;
+mod activity;
+mod annotations;
+mod assembler;
+mod capabilities;
+#[cfg(feature = "chrono")]
+mod chrono_time;
+mod coverage;
+mod csv;
+mod dedup;
+mod deep_size;
+mod display;
+mod distance;
+mod elevation;
+mod extension_sink;
+mod extensions;
+mod fingerprint;
+mod gaps;
+mod geofence;
+mod geoid;
+mod links;
+mod namespaces;
+mod nearest;
+mod numbering;
+mod parallel;
 mod parser;
+mod parts;
+mod pauses;
+mod point3d;
+mod point_ref;
+mod point_sequence;
+mod polyline;
+mod positions;
+mod profile;
+mod quirks;
 mod reader;
+mod report;
+mod resample;
+mod reverse;
+mod rollover;
+mod segments;
+mod smooth;
+mod sniff;
+mod spikes;
+mod split;
+mod stats;
+mod stitch;
+mod swap;
+mod taxonomy;
+mod text;
+mod times;
+#[cfg(feature = "timezones")]
+mod timezones;
+mod transform;
 mod types;
+#[cfg(feature = "timezones")]
+mod tzif;
+mod validate;
 mod writer;
+#[cfg(feature = "xsd-validation")]
+mod xsd;
+#[cfg(feature = "zip")]
+mod zip;
 
+pub mod cluster;
+pub mod coord;
+pub mod diff;
+pub mod dom;
+pub mod events;
+pub mod format;
+pub mod garmin;
+pub mod patch;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testutil;
 // Errors should be namespaced away.
 pub mod errors;
@@ -0,0 +1,95 @@
+//! links finds and sets the link of a document to itself.
+
+use crate::{Link, Metadata};
+
+/// The `type` of the link of a document to its canonical URL.
+const SELF_TYPE: &str = "self";
+
+impl Metadata {
+    /// Gives the first link whose type is `self`, by convention the
+    /// canonical URL of the document.
+    pub fn self_link(&self) -> Option<&Link> {
+        self.links.iter().find(|link| is_self(link))
+    }
+
+    /// Sets the href of the link whose type is `self`, or adds such a link
+    /// after the others if there is none.
+    ///
+    /// The text of an existing self link is kept, and any further self
+    /// links are removed, so that there is only one.
+    ///
+    /// ```
+    /// use gpx::Metadata;
+    ///
+    /// let mut metadata = Metadata::default();
+    /// metadata.set_self_link("https://example.com/activities/1");
+    /// metadata.set_self_link("https://example.com/activities/2");
+    /// assert_eq!(metadata.links.len(), 1);
+    /// assert_eq!(metadata.self_link().unwrap().href, "https://example.com/activities/2");
+    /// ```
+    pub fn set_self_link(&mut self, href: impl Into<String>) {
+        let href = href.into();
+        match self.links.iter().position(is_self) {
+            Some(index) => {
+                self.links[index].href = href;
+                let mut seen = 0;
+                self.links.retain(|link| {
+                    seen += 1;
+                    seen <= index + 1 || !is_self(link)
+                });
+            }
+            None => self.links.push(Link {
+                href,
+                text: None,
+                _type: Some(SELF_TYPE.to_owned()),
+            }),
+        }
+    }
+}
+
+fn is_self(link: &Link) -> bool {
+    link._type.as_deref() == Some(SELF_TYPE)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Link, Metadata};
+
+    fn link(href: &str, _type: Option<&str>) -> Link {
+        Link {
+            href: href.to_owned(),
+            text: None,
+            _type: _type.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn set_self_link_replaces() {
+        let mut metadata = Metadata::default();
+        metadata.links.push(link("a.jpg", Some("image/jpeg")));
+        metadata.links.push(link("old", Some("self")));
+        metadata.links.push(link("b", None));
+        metadata.links[1].text = Some("This activity".into());
+
+        metadata.set_self_link("new");
+        metadata.set_self_link("newer");
+        let hrefs: Vec<_> = metadata.links.iter().map(|link| &link.href).collect();
+        assert_eq!(hrefs, vec!["a.jpg", "newer", "b"]);
+        assert_eq!(
+            metadata.self_link().unwrap().text.as_deref(),
+            Some("This activity")
+        );
+    }
+
+    #[test]
+    fn set_self_link_removes_duplicates() {
+        let mut metadata = Metadata::default();
+        metadata.links.push(link("a", Some("self")));
+        metadata.links.push(link("b", None));
+        metadata.links.push(link("c", Some("self")));
+
+        metadata.set_self_link("d");
+        let hrefs: Vec<_> = metadata.links.iter().map(|link| &link.href).collect();
+        assert_eq!(hrefs, vec!["d", "b"]);
+    }
+}
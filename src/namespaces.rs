@@ -0,0 +1,305 @@
+//! namespaces combines the prefix bindings of extension fragments from
+//! different sources, renaming prefixes that would clash.
+
+use std::collections::BTreeMap;
+use std::iter::once;
+
+use thiserror::Error;
+
+use crate::dom::{Element, Node, SharedStr};
+
+/// Bindings of prefixes to namespace URIs, like the `xmlns:prefix`
+/// declarations of a document.
+///
+/// ```
+/// use gpx::dom::Namespace;
+///
+/// let mut namespace = Namespace::new();
+/// namespace.insert("g", "http://example.com/garden");
+///
+/// let mut other = Namespace::new();
+/// other.insert("g", "http://example.com/geology");
+/// assert!(namespace.merge(&other).is_err());
+///
+/// let renames = namespace.merge_renaming(&other);
+/// assert_eq!(renames.get("g", "http://example.com/geology"), Some("g1"));
+/// assert_eq!(namespace.get("g1"), Some("http://example.com/geology"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Namespace {
+    bindings: BTreeMap<String, String>,
+}
+
+/// Two namespaces bind the same prefix to different URIs, as found by
+/// [`Namespace::merge`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("prefix `{prefix}` is bound to both `{existing}` and `{other}`")]
+pub struct PrefixConflict {
+    /// The prefix.
+    pub prefix: String,
+
+    /// The URI it is bound to in the namespace merged into.
+    pub existing: String,
+
+    /// The URI it is bound to in the namespace merged from.
+    pub other: String,
+}
+
+/// The new prefixes [`Namespace::merge_renaming`] gave to bindings, to be
+/// applied to elements with [`Element::remap_prefixes`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrefixRenames {
+    /// The new prefix by old prefix and URI.
+    renames: BTreeMap<(String, String), String>,
+}
+
+impl Namespace {
+    /// Creates a namespace without bindings.
+    pub fn new() -> Namespace {
+        Default::default()
+    }
+
+    /// Collects the bindings of the prefixed names of `element` and its
+    /// descendants, elements and attributes alike. Where a prefix is bound
+    /// to several URIs, the first one in document order is kept. The `xml`
+    /// prefix is left out, as it is always bound.
+    pub fn used_by(element: &Element) -> Namespace {
+        let mut namespace = Namespace::new();
+        visit_bindings(element, &mut |prefix, uri| {
+            if !namespace.bindings.contains_key(prefix) {
+                namespace.insert(prefix, uri);
+            }
+        });
+        namespace
+    }
+
+    /// Binds `prefix` to `uri`, and gives the URI it was bound to before.
+    pub fn insert(&mut self, prefix: impl Into<String>, uri: impl Into<String>) -> Option<String> {
+        self.bindings.insert(prefix.into(), uri.into())
+    }
+
+    /// Gives the URI `prefix` is bound to.
+    pub fn get(&self, prefix: &str) -> Option<&str> {
+        self.bindings.get(prefix).map(String::as_str)
+    }
+
+    /// Gives the prefixes and their URIs, ordered by prefix.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.bindings
+            .iter()
+            .map(|(prefix, uri)| (prefix.as_str(), uri.as_str()))
+    }
+
+    /// Adds the bindings of `other`. Fails, without changing anything, if
+    /// `other` binds a prefix to a different URI than this namespace, and
+    /// reports the first such prefix.
+    pub fn merge(&mut self, other: &Namespace) -> Result<(), PrefixConflict> {
+        for (prefix, uri) in other.iter() {
+            match self.get(prefix) {
+                Some(existing) if existing != uri => {
+                    return Err(PrefixConflict {
+                        prefix: prefix.into(),
+                        existing: existing.into(),
+                        other: uri.into(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        for (prefix, uri) in other.iter() {
+            self.insert(prefix, uri);
+        }
+        Ok(())
+    }
+
+    /// Adds the bindings of `other`, giving each prefix that is bound to a
+    /// different URI here a new one, and returns the new prefixes.
+    ///
+    /// The new prefix is one this namespace already binds to the URI, if
+    /// any, or else the old prefix with the first number that neither
+    /// namespace uses, such as `g1`.
+    pub fn merge_renaming(&mut self, other: &Namespace) -> PrefixRenames {
+        let mut renames = PrefixRenames::default();
+        for (prefix, uri) in other.iter() {
+            match self.get(prefix) {
+                None => {
+                    self.insert(prefix, uri);
+                    continue;
+                }
+                Some(existing) if existing == uri => continue,
+                Some(_) => {}
+            }
+            let bound = self
+                .iter()
+                .find(|&(_, bound_uri)| bound_uri == uri)
+                .map(|(bound, _)| bound.to_owned());
+            let renamed = bound.unwrap_or_else(|| {
+                let renamed = (1..)
+                    .map(|number| format!("{}{}", prefix, number))
+                    .find(|candidate| {
+                        self.get(candidate).is_none() && other.get(candidate).is_none()
+                    })
+                    .expect("some number is free");
+                self.insert(renamed.clone(), uri);
+                renamed
+            });
+            renames
+                .renames
+                .insert((prefix.to_owned(), uri.to_owned()), renamed);
+        }
+        renames
+    }
+}
+
+impl PrefixRenames {
+    /// Gives the new prefix for names with `prefix` in the namespace `uri`.
+    pub fn get(&self, prefix: &str, uri: &str) -> Option<&str> {
+        self.renames
+            .get(&(prefix.to_owned(), uri.to_owned()))
+            .map(String::as_str)
+    }
+
+    /// Whether no prefix was renamed.
+    pub fn is_empty(&self) -> bool {
+        self.renames.is_empty()
+    }
+}
+
+impl Element {
+    /// Gives the names of this element, its attributes and its descendants
+    /// the new prefixes of `renames`. Only names with both the old prefix
+    /// and its URI are changed.
+    pub fn remap_prefixes(&mut self, renames: &PrefixRenames) {
+        if renames.is_empty() {
+            return;
+        }
+        let names =
+            once(&mut self.name).chain(self.attributes.iter_mut().map(|attr| &mut attr.name));
+        for name in names {
+            if let (Some(prefix), Some(uri)) = (&name.prefix, &name.namespace) {
+                if let Some(renamed) = renames.get(prefix, uri) {
+                    name.prefix = Some(SharedStr::from(renamed));
+                }
+            }
+        }
+        for child in &mut self.children {
+            if let Node::Element(child) = child {
+                child.remap_prefixes(renames);
+            }
+        }
+    }
+}
+
+/// Calls `f` with the prefix and URI of every prefixed name of `element`
+/// and its descendants, in document order, except for the `xml` prefix.
+pub(crate) fn visit_bindings<'a>(element: &'a Element, f: &mut impl FnMut(&'a str, &'a str)) {
+    let names = once(&element.name).chain(element.attributes.iter().map(|attr| &attr.name));
+    for name in names {
+        if let (Some(prefix), Some(uri)) = (&name.prefix, &name.namespace) {
+            if prefix != "xml" {
+                f(prefix, uri);
+            }
+        }
+    }
+    for child in element.elements() {
+        visit_bindings(child, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Namespace, PrefixConflict};
+    use crate::dom::{Element, Node, OwnedAttribute, OwnedName};
+
+    const GARDEN: &str = "http://example.com/garden";
+    const GEOLOGY: &str = "http://example.com/geology";
+
+    fn name(local_name: &str, uri: &str, prefix: &str) -> OwnedName {
+        OwnedName::qualified(local_name, uri, Some(prefix.into()))
+    }
+
+    /// `<g:{outer}><g:{inner} g:kind="..."/>text</g:{outer}>` in `uri`.
+    fn fragment(uri: &str, outer: &str, inner: &str) -> Element {
+        let mut child = Element::new(name(inner, uri, "g"));
+        child
+            .attributes
+            .push(OwnedAttribute::new(name("kind", uri, "g"), "a"));
+        child
+            .attributes
+            .push(OwnedAttribute::new(OwnedName::local("plain"), "b"));
+        let mut element = Element::new(name(outer, uri, "g"));
+        element.children.push(Node::Element(child));
+        element.children.push(Node::Text("text".into()));
+        element
+    }
+
+    fn prefixes(element: &Element) -> Vec<Option<String>> {
+        let mut prefixes = vec![element.name.prefix.as_ref().map(ToString::to_string)];
+        for attribute in &element.attributes {
+            prefixes.push(attribute.name.prefix.as_ref().map(ToString::to_string));
+        }
+        for child in element.elements() {
+            prefixes.extend(self::prefixes(child));
+        }
+        prefixes
+    }
+
+    #[test]
+    fn merge_fragments_with_the_same_prefix() {
+        let garden = fragment(GARDEN, "bed", "plant");
+        let mut geology = fragment(GEOLOGY, "layer", "rock");
+
+        let mut namespace = Namespace::used_by(&garden);
+        let other = Namespace::used_by(&geology);
+        assert_eq!(
+            namespace.clone().merge(&other),
+            Err(PrefixConflict {
+                prefix: "g".into(),
+                existing: GARDEN.into(),
+                other: GEOLOGY.into(),
+            })
+        );
+
+        let renames = namespace.merge_renaming(&other);
+        geology.remap_prefixes(&renames);
+        let g1 = Some("g1".to_string());
+        assert_eq!(prefixes(&geology), [g1.clone(), g1.clone(), g1, None]);
+        assert_eq!(
+            namespace.iter().collect::<Vec<_>>(),
+            [("g", GARDEN), ("g1", GEOLOGY)]
+        );
+
+        // The merged fragments bind every prefix once.
+        let mut extensions = Element::new(OwnedName::local("extensions"));
+        extensions.children.push(Node::Element(garden.clone()));
+        extensions.children.push(Node::Element(geology));
+        assert_eq!(Namespace::used_by(&extensions), namespace);
+
+        // Garden names keep their prefix.
+        let mut unchanged = garden.clone();
+        unchanged.remap_prefixes(&renames);
+        assert_eq!(unchanged, garden);
+    }
+
+    #[test]
+    fn merge_reuses_bound_prefixes() {
+        let mut namespace = Namespace::new();
+        namespace.insert("g", GARDEN);
+        namespace.insert("geo", GEOLOGY);
+        let mut other = Namespace::new();
+        other.insert("g", GEOLOGY);
+        other.insert("g1", "http://example.com/other");
+        other.insert("x", GARDEN);
+
+        let renames = namespace.merge_renaming(&other);
+        assert_eq!(renames.get("g", GEOLOGY), Some("geo"));
+        assert_eq!(renames.get("x", GARDEN), None);
+        assert_eq!(namespace.get("g1"), Some("http://example.com/other"));
+        assert_eq!(namespace.get("x"), Some(GARDEN));
+
+        let mut same = Namespace::new();
+        same.insert("g", GARDEN);
+        assert_eq!(namespace.merge(&same), Ok(()));
+        assert!(namespace.merge_renaming(&same).is_empty());
+    }
+}
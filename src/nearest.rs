@@ -0,0 +1,256 @@
+//! nearest edits routes at the position closest to a given point.
+
+use geo_types::Point;
+
+use crate::distance::{haversine, EARTH_RADIUS_M};
+use crate::{Route, Waypoint};
+
+/// Legs that are less than this many meters further away than the nearest
+/// one count as equally close, so that rounding does not break ties.
+const TIE_M: f64 = 1e-6;
+
+impl Route {
+    /// Inserts `wpt` between the two consecutive points whose connecting
+    /// leg passes closest to it, and returns the index it was inserted at.
+    ///
+    /// Distances to the legs are measured along great circles, so legs near
+    /// the poles and across the antimeridian work as well. If several legs
+    /// are equally close, the earliest one wins. Into a route with fewer
+    /// than two points, `wpt` is appended.
+    ///
+    /// A point that lies beyond the start of the first leg is inserted
+    /// before the first point, and one beyond the end of the last leg after
+    /// the last point, so that the route is extended rather than doubling
+    /// back.
+    ///
+    /// ```
+    /// use gpx::{Route, Waypoint};
+    /// use geo_types::Point;
+    ///
+    /// let mut route = Route::new();
+    /// for x in [0.0, 1.0, 2.0].iter() {
+    ///     route.points.push(Waypoint::new_lat_lon(0.0, *x).unwrap());
+    /// }
+    ///
+    /// assert_eq!(route.insert_point_nearest(Waypoint::new_lat_lon(0.1, 1.5).unwrap()), 2);
+    /// assert_eq!(route.points[2].point(), Point::new(1.5, 0.1));
+    /// ```
+    pub fn insert_point_nearest(&mut self, wpt: Waypoint) -> usize {
+        let origin = wpt.point();
+        let mut nearest: Option<(usize, f64, Foot)> = None;
+        for (i, leg) in self.points.windows(2).enumerate() {
+            let (distance, foot) = distance_to_leg(origin, leg[0].point(), leg[1].point());
+            if nearest.map_or(true, |(_, closest, _)| distance < closest - TIE_M) {
+                nearest = Some((i, distance, foot));
+            }
+        }
+        let last_leg = self.points.len().saturating_sub(2);
+        let index = match nearest {
+            Some((0, _, Foot::Start)) => 0,
+            Some((i, _, Foot::End)) if i == last_leg => self.points.len(),
+            Some((i, _, _)) => i + 1,
+            None => self.points.len(),
+        };
+        self.points.insert(index, wpt);
+        index
+    }
+
+    /// Removes and gives the point closest to `point`, if it is at most
+    /// `max_distance_m` meters away. If several points are equally close,
+    /// the earliest one is removed.
+    pub fn remove_point_nearest(
+        &mut self,
+        point: &Point<f64>,
+        max_distance_m: f64,
+    ) -> Option<Waypoint> {
+        let mut nearest: Option<(usize, f64)> = None;
+        for (i, candidate) in self.points.iter().enumerate() {
+            let distance = haversine(*point, candidate.point());
+            if nearest.map_or(true, |(_, closest)| distance < closest) {
+                nearest = Some((i, distance));
+            }
+        }
+        match nearest {
+            Some((i, distance)) if distance <= max_distance_m => Some(self.points.remove(i)),
+            _ => None,
+        }
+    }
+}
+
+/// Where on a leg the point closest to another one lies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Foot {
+    /// At the start, with the other point beyond it.
+    Start,
+    /// Between the ends, or anywhere on a leg without a direction.
+    Between,
+    /// At the end, with the other point beyond it.
+    End,
+}
+
+/// Gives the distance in meters from `origin` to the great-circle arc
+/// from `a` to `b`, and where on the arc the closest point lies.
+///
+/// The points are taken as unit vectors, so this holds up near the poles
+/// and across the antimeridian, where a flat projection does not.
+fn distance_to_leg(origin: Point<f64>, a: Point<f64>, b: Point<f64>) -> (f64, Foot) {
+    let to_ends = || {
+        let (to_a, to_b) = (haversine(origin, a), haversine(origin, b));
+        if to_a <= to_b {
+            (to_a, Foot::Start)
+        } else {
+            (to_b, Foot::End)
+        }
+    };
+    let (p, u, v) = (unit_vector(origin), unit_vector(a), unit_vector(b));
+    // The normal of the plane of the great circle through `a` and `b`,
+    // which is not defined for legs without length or between antipodes.
+    let normal = cross(u, v);
+    let length = dot(normal, normal).sqrt();
+    if length < 1e-15 {
+        return (to_ends().0, Foot::Between);
+    }
+    let normal = [normal[0] / length, normal[1] / length, normal[2] / length];
+    // The closest point of the great circle lies in the direction of `p`
+    // without its part along the normal. It is on the arc if it lies on
+    // the same side of both ends as the arc.
+    let height = dot(p, normal);
+    let foot = [
+        p[0] - height * normal[0],
+        p[1] - height * normal[1],
+        p[2] - height * normal[2],
+    ];
+    let on_arc = dot(cross(u, foot), normal) >= 0.0 && dot(cross(foot, v), normal) >= 0.0;
+    if on_arc {
+        let foot_length = dot(foot, foot).sqrt();
+        (
+            EARTH_RADIUS_M * height.abs().atan2(foot_length),
+            Foot::Between,
+        )
+    } else {
+        to_ends()
+    }
+}
+
+fn unit_vector(point: Point<f64>) -> [f64; 3] {
+    let (lat, lon) = (point.y().to_radians(), point.x().to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use crate::{Route, Waypoint};
+
+    /// A route zig-zagging east, between the equator and 0.01° north.
+    fn zig_zag() -> Route {
+        let mut route = Route::new();
+        for (x, y) in &[
+            (0.0, 0.0),
+            (0.01, 0.01),
+            (0.02, 0.0),
+            (0.03, 0.01),
+            (0.04, 0.0),
+        ] {
+            route.points.push(Waypoint::new(Point::new(*x, *y)));
+        }
+        route
+    }
+
+    fn xs(route: &Route) -> Vec<f64> {
+        route.points.iter().map(|point| point.point().x()).collect()
+    }
+
+    #[test]
+    fn insert_point_nearest_finds_leg() {
+        let mut route = zig_zag();
+
+        // Just beside the third leg.
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(0.025, 0.0048))),
+            3
+        );
+        // Just beside the first leg, closer to the second point than to the
+        // second leg.
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(0.0085, 0.0095))),
+            1
+        );
+        // Beyond the ends of the route, it is extended.
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(0.05, 0.0))),
+            7
+        );
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(-0.01, -0.001))),
+            0
+        );
+        assert_eq!(
+            xs(&route),
+            vec![-0.01, 0.0, 0.0085, 0.01, 0.02, 0.025, 0.03, 0.04, 0.05]
+        );
+    }
+
+    #[test]
+    fn insert_point_nearest_tie_goes_to_earlier_leg() {
+        let mut route = zig_zag();
+
+        // Right below the second point, as close to the first as to the
+        // second leg.
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(0.01, 0.005))),
+            1
+        );
+    }
+
+    #[test]
+    fn insert_point_nearest_short_routes() {
+        let mut route = Route::new();
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(1.0, 1.0))),
+            0
+        );
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(0.0, 0.0))),
+            1
+        );
+        // A route of two identical points still has a leg.
+        route.points[1] = Waypoint::new(Point::new(1.0, 1.0));
+        assert_eq!(
+            route.insert_point_nearest(Waypoint::new(Point::new(2.0, 2.0))),
+            1
+        );
+    }
+
+    #[test]
+    fn remove_point_nearest() {
+        let mut route = zig_zag();
+
+        // 0.001° is about 111 m.
+        assert!(route
+            .remove_point_nearest(&Point::new(0.02, 0.001), 100.0)
+            .is_none());
+        let removed = route
+            .remove_point_nearest(&Point::new(0.02, 0.001), 120.0)
+            .unwrap();
+        assert_eq!(removed.point(), Point::new(0.02, 0.0));
+        assert_eq!(xs(&route), vec![0.0, 0.01, 0.03, 0.04]);
+
+        assert!(Route::new()
+            .remove_point_nearest(&Point::new(0.0, 0.0), 1.0)
+            .is_none());
+    }
+}
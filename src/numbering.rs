@@ -0,0 +1,252 @@
+//! numbering gives route points explicit sequence numbers.
+
+use crate::dom::{Element, Node, OwnedName};
+use crate::errors::{GpxError, GpxResult};
+use crate::{Route, Waypoint};
+
+/// Where [`Route::renumber_points`] stores the sequence number of a point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NumberingScheme {
+    /// Appends the number to the name of the point, as in `"Bridge 001"`.
+    /// Points without a name are named `"RPT001"`.
+    NameSuffix,
+
+    /// Stores the number as the text of the element `element` in
+    /// `namespace`, inside the extensions of the point.
+    Extension { namespace: String, element: String },
+}
+
+impl Route {
+    /// Numbers the points of the route in their current order, starting at 1.
+    ///
+    /// With [`NumberingScheme::NameSuffix`], the number is appended to the
+    /// existing name, padded to three digits. A number written before, that
+    /// is a space and three or more digits at the end of the name or a name
+    /// like `"RPT001"`, is replaced, so that renumbering doesn't add to it;
+    /// this takes names like `"Highway 101"` for numbered as well. With
+    /// [`NumberingScheme::Extension`], an existing number element is
+    /// replaced, and the extensions are created if the point has none.
+    ///
+    /// ```
+    /// use gpx::{NumberingScheme, Route, Waypoint};
+    ///
+    /// let mut route = Route::new();
+    /// let mut bridge = Waypoint::new_lat_lon(0.0, 0.0).unwrap();
+    /// bridge.name = Some(String::from("Bridge"));
+    /// route.points.push(bridge);
+    /// route.points.push(Waypoint::new_lat_lon(0.0, 1.0).unwrap());
+    ///
+    /// route.renumber_points(NumberingScheme::NameSuffix);
+    /// assert_eq!(route.points[0].name.as_deref(), Some("Bridge 001"));
+    /// assert_eq!(route.points[1].name.as_deref(), Some("RPT002"));
+    /// ```
+    pub fn renumber_points(&mut self, scheme: NumberingScheme) {
+        for (index, point) in self.points.iter_mut().enumerate() {
+            let number = index + 1;
+            match &scheme {
+                NumberingScheme::NameSuffix => {
+                    point.name = Some(match point.name.as_deref().and_then(unnumbered_name) {
+                        Some(name) => format!("{} {:03}", name, number),
+                        None => format!("RPT{:03}", number),
+                    });
+                }
+                NumberingScheme::Extension { namespace, element } => {
+                    let name = OwnedName::qualified(element.as_str(), namespace.as_str(), None);
+                    set_number_element(point, name, number);
+                }
+            }
+        }
+    }
+
+    /// Puts the points of the route in the order of their sequence numbers,
+    /// as written by [`Route::renumber_points`].
+    ///
+    /// With [`NumberingScheme::NameSuffix`], the number is read from the
+    /// digits at the end of the name. If a point has no number that can be
+    /// read, or two points have the same number, an error naming the point
+    /// index is returned and the route is left unchanged.
+    pub fn sort_points_by_number(&mut self, scheme: NumberingScheme) -> GpxResult<()> {
+        let mut numbers = Vec::with_capacity(self.points.len());
+        for (index, point) in self.points.iter().enumerate() {
+            let number = match &scheme {
+                NumberingScheme::NameSuffix => point.name.as_deref().and_then(name_number),
+                NumberingScheme::Extension { namespace, element } => {
+                    extension_number(point, namespace, element)
+                }
+            };
+            numbers.push(number.ok_or(GpxError::InvalidPointNumber(index))?);
+        }
+
+        let mut order: Vec<usize> = (0..self.points.len()).collect();
+        order.sort_by_key(|&index| numbers[index]);
+        for pair in order.windows(2) {
+            if numbers[pair[0]] == numbers[pair[1]] {
+                return Err(GpxError::DuplicatePointNumber(pair[1], pair[0]));
+            }
+        }
+
+        let mut points: Vec<Option<Waypoint>> = self.points.drain(..).map(Some).collect();
+        self.points = order
+            .into_iter()
+            .filter_map(|index| points[index].take())
+            .collect();
+        Ok(())
+    }
+}
+
+/// Gives `name` without the number [`Route::renumber_points`] appends to
+/// it, or nothing if it is a name given to a point without one.
+fn unnumbered_name(name: &str) -> Option<&str> {
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if name.len() - stem.len() < 3 {
+        return Some(name);
+    }
+    match stem.strip_suffix(' ') {
+        Some(stem) => Some(stem),
+        None if stem == "RPT" => None,
+        None => Some(name),
+    }
+}
+
+fn name_number(name: &str) -> Option<u64> {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    name[name.len() - digits..].parse().ok()
+}
+
+fn is_number_element(name: &OwnedName, namespace: &str, element: &str) -> bool {
+    name.local_name == element && name.namespace.as_deref() == Some(namespace)
+}
+
+fn extension_number(point: &Waypoint, namespace: &str, element: &str) -> Option<u64> {
+    point
+        .extras()
+        .extensions
+        .as_ref()?
+        .elements()
+        .find(|child| is_number_element(&child.name, namespace, element))?
+        .text()
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn set_number_element(point: &mut Waypoint, name: OwnedName, number: usize) {
+    let extensions = point
+        .extras_mut()
+        .extensions
+        .get_or_insert_with(|| Element::new(OwnedName::local("extensions")));
+    let namespace = name.namespace.as_deref().unwrap_or_default();
+    extensions.children.retain(|child| match child {
+        Node::Element(child) => !is_number_element(&child.name, namespace, &name.local_name),
+        Node::Text(_) => true,
+    });
+    let mut element = Element::new(name);
+    element.children.push(Node::Text(number.to_string()));
+    extensions.children.push(Node::Element(element));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use geo_types::Point;
+
+    use super::NumberingScheme;
+    use crate::errors::GpxError;
+    use crate::{read, write_to_vec, Gpx, GpxVersion, Route, Waypoint};
+
+    fn extension_scheme() -> NumberingScheme {
+        NumberingScheme::Extension {
+            namespace: String::from("http://example.com/sequence"),
+            element: String::from("number"),
+        }
+    }
+
+    fn route(names: &[Option<&str>]) -> Route {
+        let mut route = Route::new();
+        for (i, name) in names.iter().enumerate() {
+            let mut point = Waypoint::new(Point::new(i as f64, 0.0));
+            point.name = name.map(String::from);
+            route.points.push(point);
+        }
+        route
+    }
+
+    fn xs(route: &Route) -> Vec<f64> {
+        route.points.iter().map(|point| point.point().x()).collect()
+    }
+
+    #[test]
+    fn name_suffix_round_trip() {
+        let mut route = route(&[Some("Start"), None, Some("Exit 12"), None]);
+        route.renumber_points(NumberingScheme::NameSuffix);
+
+        let names: Vec<_> = route
+            .points
+            .iter()
+            .map(|p| p.name.clone().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Start 001", "RPT002", "Exit 12 003", "RPT004"]);
+
+        // Renumbering replaces the numbers.
+        route.points.swap(0, 1);
+        route.renumber_points(NumberingScheme::NameSuffix);
+        let names: Vec<_> = route
+            .points
+            .iter()
+            .map(|p| p.name.clone().unwrap())
+            .collect();
+        assert_eq!(names, vec!["RPT001", "Start 002", "Exit 12 003", "RPT004"]);
+
+        route.points.swap(0, 3);
+        route.points.swap(1, 2);
+        route
+            .sort_points_by_number(NumberingScheme::NameSuffix)
+            .unwrap();
+        assert_eq!(xs(&route), vec![1.0, 0.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn extension_round_trip() {
+        let mut route = route(&[Some("Start"), None, None]);
+        route.renumber_points(extension_scheme());
+        // Renumbering replaces the old numbers rather than adding more.
+        route.points.reverse();
+        route.renumber_points(extension_scheme());
+        route.points.swap(0, 1);
+
+        // The numbers survive writing and reading the route.
+        let gpx = Gpx {
+            version: GpxVersion::Gpx11,
+            routes: vec![route],
+            ..Default::default()
+        };
+        let data = write_to_vec(&gpx).unwrap();
+        let mut route = read(BufReader::new(data.as_slice()))
+            .unwrap()
+            .routes
+            .remove(0);
+        let extensions = route.points[0].extras().extensions.as_ref().unwrap();
+        assert_eq!(extensions.elements().count(), 1);
+
+        route.sort_points_by_number(extension_scheme()).unwrap();
+        assert_eq!(xs(&route), vec![2.0, 1.0, 0.0]);
+        assert_eq!(route.points[2].name.as_deref(), Some("Start"));
+    }
+
+    #[test]
+    fn sort_points_by_number_errors() {
+        let mut unnumbered = route(&[Some("A 001"), Some("B")]);
+        let result = unnumbered.sort_points_by_number(NumberingScheme::NameSuffix);
+        assert!(matches!(result, Err(GpxError::InvalidPointNumber(1))));
+
+        let mut duplicate = route(&[Some("A 002"), Some("B 001"), Some("C 002")]);
+        let result = duplicate.sort_points_by_number(NumberingScheme::NameSuffix);
+        assert!(matches!(result, Err(GpxError::DuplicatePointNumber(2, 0))));
+        assert_eq!(xs(&duplicate), vec![0.0, 1.0, 2.0]);
+
+        let mut missing = route(&[None]);
+        let result = missing.sort_points_by_number(extension_scheme());
+        assert!(matches!(result, Err(GpxError::InvalidPointNumber(0))));
+    }
+}
@@ -0,0 +1,140 @@
+//! parallel processes the tracks of a document on several threads, if the
+//! `rayon` feature is enabled.
+//!
+//! Without the feature, the same functions are available and do the work
+//! sequentially.
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{Gpx, Route, StatsOptions, Track, TrackStats, Waypoint};
+
+impl Gpx {
+    /// Computes the statistics of every track, like [`Gpx::track_stats`], but
+    /// with the tracks spread over rayon's thread pool.
+    pub fn par_track_stats(&self, options: &StatsOptions) -> Vec<TrackStats> {
+        #[cfg(feature = "rayon")]
+        {
+            self.tracks
+                .par_iter()
+                .map(|track| track.stats(options))
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            self.track_stats(options)
+        }
+    }
+
+    /// Calls `f` on every waypoint, route point and track point.
+    ///
+    /// Routes and tracks are spread over rayon's thread pool, so `f` may be
+    /// called on several threads at once. The points of a single route or
+    /// track are always visited in order, on one thread.
+    ///
+    /// ```
+    /// use gpx::{Gpx, Track, TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// segment.points.push(Waypoint::new_lat_lon(2.0, 1.0).unwrap());
+    /// let mut track = Track::new();
+    /// track.segments.push(segment);
+    /// let mut gpx = Gpx {
+    ///     tracks: vec![track],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// gpx.par_apply_points(|point| point.elevation = Some(0.0));
+    /// assert_eq!(gpx.tracks[0].segments[0].points[0].elevation, Some(0.0));
+    /// ```
+    pub fn par_apply_points(&mut self, f: impl Fn(&mut Waypoint) + Sync) {
+        let f = &f;
+        let apply_route = |route: &mut Route| route.points.iter_mut().for_each(f);
+        let apply_track = |track: &mut Track| {
+            for segment in &mut track.segments {
+                segment.points.iter_mut().for_each(f);
+            }
+        };
+        #[cfg(feature = "rayon")]
+        {
+            self.waypoints.par_iter_mut().for_each(f);
+            self.routes.par_iter_mut().for_each(apply_route);
+            self.tracks.par_iter_mut().for_each(apply_track);
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            self.waypoints.iter_mut().for_each(f);
+            self.routes.iter_mut().for_each(apply_route);
+            self.tracks.iter_mut().for_each(apply_track);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testutil::synthetic_gpx;
+    use crate::{Gpx, StatsOptions};
+
+    /// A document with many tracks of a few hundred points each.
+    fn large_gpx() -> Gpx {
+        synthetic_gpx(64, 500)
+    }
+
+    #[test]
+    fn par_track_stats_matches_sequential() {
+        let gpx = large_gpx();
+        let options = StatsOptions {
+            elevation_threshold: 1.5,
+            ..Default::default()
+        };
+
+        assert_eq!(gpx.par_track_stats(&options), gpx.track_stats(&options));
+    }
+
+    #[test]
+    fn par_apply_points_matches_sequential() {
+        let mut parallel = large_gpx();
+        parallel.par_apply_points(|point| point.elevation = point.elevation.map(|e| e * 2.0));
+
+        let mut sequential = large_gpx();
+        for track in &mut sequential.tracks {
+            for point in &mut track.segments[0].points {
+                point.elevation = point.elevation.map(|e| e * 2.0);
+            }
+        }
+        assert_eq!(parallel, sequential);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_apply_points_uses_threads() {
+        use std::collections::HashSet;
+        use std::sync::Mutex;
+        use std::thread::{self, ThreadId};
+
+        let threads: Mutex<HashSet<ThreadId>> = Mutex::new(HashSet::new());
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let mut gpx = large_gpx();
+        let starts: Vec<_> = gpx
+            .tracks
+            .iter()
+            .map(|track| track.segments[0].points[0].point())
+            .collect();
+
+        pool.install(|| {
+            gpx.par_apply_points(|point| {
+                // Make each track take long enough for the others to be
+                // picked up by other threads.
+                if starts.contains(&point.point()) {
+                    thread::sleep(std::time::Duration::from_millis(2));
+                }
+                threads.lock().unwrap().insert(thread::current().id());
+            })
+        });
+
+        assert!(threads.lock().unwrap().len() > 1);
+    }
+}
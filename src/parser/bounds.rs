@@ -1,11 +1,11 @@
 use std::io::Read;
 
 // use error_chain::{bail, ensure};
-use geo_types::{Coordinate, Rect};
+use geo_types::{Coord, Rect};
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{verify_starting_tag, Context};
+use crate::parser::{number, verify_starting_tag, Context};
 
 /// consume consumes a bounds element until it ends.
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Rect<f64>> {
@@ -20,8 +20,8 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Rect<f64>> {
         .find(|attr| attr.name.local_name == "maxlat")
         .ok_or(GpxError::InvalidElementLacksAttribute("maxlat", "bounds"))?;
 
-    let minlat: f64 = minlat.value.parse()?;
-    let maxlat: f64 = maxlat.value.parse()?;
+    let minlat = number::parse(&minlat.value, "minlat")?;
+    let maxlat = number::parse(&maxlat.value, "maxlat")?;
 
     let minlon = attributes
         .iter()
@@ -32,8 +32,8 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Rect<f64>> {
         .find(|attr| attr.name.local_name == "maxlon")
         .ok_or(GpxError::InvalidElementLacksAttribute("maxlon", "bounds"))?;
 
-    let minlon: f64 = minlon.value.parse()?;
-    let maxlon: f64 = maxlon.value.parse()?;
+    let minlon = number::parse(&minlon.value, "minlon")?;
+    let maxlon = number::parse(&maxlon.value, "maxlon")?;
 
     // Verify bounding box first, since Rect::new will panic if these are wrong.
     if minlon > maxlon {
@@ -43,11 +43,11 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Rect<f64>> {
     }
 
     let bounds: Rect<f64> = Rect::new(
-        Coordinate {
+        Coord {
             x: minlon,
             y: minlat,
         },
-        Coordinate {
+        Coord {
             x: maxlon,
             y: maxlat,
         },
@@ -5,7 +5,7 @@ use std::io::Read;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{string, verify_starting_tag, Context};
+use crate::parser::{number, order, string, verify_starting_tag, Context};
 use crate::GpxCopyright;
 
 /// consume consumes a GPX copyright from the `reader` until it ends.
@@ -13,6 +13,7 @@ use crate::GpxCopyright;
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<GpxCopyright> {
     let mut copyright: GpxCopyright = Default::default();
     let attributes = verify_starting_tag(context, "copyright")?;
+    let mut order = context.element_order("copyright", order::COPYRIGHT);
     let attr = attributes
         .into_iter()
         .find(|attr| attr.name.local_name == "author");
@@ -32,16 +33,21 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<GpxCopyright> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "license" => copyright.license = Some(string::consume(context, "license", false)?),
-                "year" => copyright.year = string::consume(context, "year", false)?.parse().ok(),
-                child => {
-                    return Err(GpxError::InvalidChildElement(
-                        String::from(child),
-                        "copyright",
-                    ));
+            XmlEvent::StartElement { ref name, .. } => {
+                order.check(&name.local_name)?;
+                match name.local_name.as_ref() {
+                    "license" => {
+                        copyright.license = Some(string::consume(context, "license", false)?)
+                    }
+                    "year" => copyright.year = Some(number::consume_integer(context, "year")?),
+                    child => {
+                        return Err(GpxError::InvalidChildElement(
+                            String::from(child),
+                            "copyright",
+                        ));
+                    }
                 }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != "copyright" {
                     return Err(GpxError::InvalidClosingTag(
@@ -90,6 +96,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn consume_year_with_whitespace() {
+        let copyright = consume!(
+            "<copyright author='pelmers'><year>\n  2021\n</year></copyright>",
+            GpxVersion::Gpx11
+        );
+        assert_eq!(copyright.unwrap().year, Some(2021));
+
+        let copyright = consume!(
+            "<copyright author='pelmers'><year>MMXXI</year></copyright>",
+            GpxVersion::Gpx11
+        );
+        assert!(copyright.is_err());
+    }
+
     #[test]
     fn consume_barebones() {
         let copyright = consume!(
@@ -1,34 +1,84 @@
 //! extensions handles parsing of GPX-spec extensions.
 
-// TODO: extensions are not implemented
-
 use std::io::Read;
 
 use xml::reader::XmlEvent;
 
+use crate::dom::{Element, Node};
 use crate::errors::{GpxError, GpxResult};
 use crate::parser::Context;
 
-/// consume consumes a single string as tag content.
-pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<()> {
-    let mut started = false;
+/// consume consumes an extensions element, keeping all of its content.
+pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Element> {
+    consume_element(context, "extensions")
+}
+
+/// consume_unknown consumes an element that is not part of the GPX schema,
+/// keeping all of its content.
+pub fn consume_unknown<R: Read>(context: &mut Context<R>) -> GpxResult<Element> {
+    consume_element(context, "")
+}
+
+/// consume_element consumes an element named `tagname`, or any element if
+/// `tagname` is empty, with all of its content.
+fn consume_element<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxResult<Element> {
+    // The element itself and the open elements inside it.
+    let mut open: Vec<Element> = Vec::new();
+    // Whether the open elements keep their whitespace, by `xml:space`.
+    let mut preserve: Vec<bool> = Vec::new();
+    let what = if tagname.is_empty() {
+        "element"
+    } else {
+        tagname
+    };
 
-    for event in context.reader() {
+    while let Some(event) = context.reader().next() {
         match event? {
-            XmlEvent::StartElement { name, .. } => {
-                // flip started depending on conditions
-                if &name.local_name == "extensions" {
-                    if started {
-                        return Err(GpxError::TagOpenedTwice("extensions"));
-                    }
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                if open.is_empty() && !tagname.is_empty() && name.local_name != tagname {
+                    return Err(GpxError::InvalidChildElement(name.local_name, tagname));
+                }
+                if let Some(parent) = open.last_mut() {
+                    end_text(parent, preserve.last() == Some(&true));
+                }
+                let names = context.names();
+                let element = Element {
+                    name: names.name(name),
+                    attributes: attributes
+                        .into_iter()
+                        .map(|attribute| names.attribute(attribute))
+                        .collect(),
+                    children: Vec::new(),
+                };
+                let inherited = preserve.last().copied().unwrap_or(false);
+                preserve.push(element.xml_space().unwrap_or(inherited));
+                open.push(element);
+            }
 
-                    started = true;
+            XmlEvent::EndElement { .. } => {
+                let mut element = open.pop().ok_or(GpxError::MissingOpeningTag(what))?;
+                end_text(&mut element, preserve.pop() == Some(true));
+                // Most extension elements hold a single text or child, so
+                // the spare capacity of the vectors would otherwise be most
+                // of the memory they take.
+                element.attributes.shrink_to_fit();
+                element.children.shrink_to_fit();
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(element)),
+                    None => return Ok(element),
                 }
             }
 
-            XmlEvent::EndElement { name, .. } => {
-                if &name.local_name == "extensions" {
-                    return Ok(());
+            // Text can come in several events, such as around a comment,
+            // which end up as one text node.
+            XmlEvent::Characters(text) => {
+                if let Some(parent) = open.last_mut() {
+                    match parent.children.last_mut() {
+                        Some(Node::Text(last)) => last.push_str(&text),
+                        _ => parent.children.push(Node::Text(text)),
+                    }
                 }
             }
 
@@ -36,12 +86,26 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<()> {
         }
     }
 
-    Err(GpxError::MissingClosingTag("extensions"))
+    Err(GpxError::MissingClosingTag(what))
+}
+
+/// Finishes the text at the end of the children of `element`, as an
+/// element starts or ends after it. Whitespace between elements is only
+/// layout, and dropped, unless the element asks to keep it.
+fn end_text(element: &mut Element, preserve: bool) {
+    if let Some(Node::Text(text)) = element.children.last_mut() {
+        if !preserve && text.trim().is_empty() {
+            element.children.pop();
+        } else {
+            text.shrink_to_fit();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::consume;
+    use crate::dom::{Node, OwnedName};
     use crate::GpxVersion;
 
     #[test]
@@ -57,4 +121,96 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn consume_extensions_content() {
+        let extensions = consume!(
+            "<extensions xmlns:ns=\"http://example.com/ns\">
+                <ns:a kind=\"x\"><ns:b>derp</ns:b></ns:a>
+                <tag>yadda</tag>
+            </extensions>",
+            GpxVersion::Gpx11
+        )
+        .unwrap();
+
+        assert_eq!(extensions.name.local_name, "extensions");
+        let children: Vec<_> = extensions.elements().collect();
+        assert_eq!(children.len(), 2);
+
+        let a = children[0];
+        assert_eq!(a.name.local_name, "a");
+        assert_eq!(a.name.prefix.as_deref(), Some("ns"));
+        assert_eq!(a.name.namespace.as_deref(), Some("http://example.com/ns"));
+        assert_eq!(a.attributes[0].value, "x");
+        assert_eq!(a.elements().next().unwrap().text(), "derp");
+
+        // Indentation is dropped.
+        assert!(extensions
+            .children
+            .iter()
+            .all(|child| matches!(child, Node::Element(_))));
+        assert_eq!(children[1].text(), "yadda");
+    }
+
+    #[test]
+    fn names_are_interned() {
+        let extensions = consume!(
+            "<extensions xmlns:ns=\"http://example.com/ns\">
+                <ns:a><ns:b>1</ns:b></ns:a>
+                <ns:a><ns:b>2</ns:b></ns:a>
+            </extensions>",
+            GpxVersion::Gpx11
+        )
+        .unwrap();
+
+        let names: Vec<&OwnedName> = extensions
+            .elements()
+            .flat_map(|a| a.elements().map(move |b| &b.name).chain(Some(&a.name)))
+            .collect();
+        assert_eq!(names.len(), 4);
+        assert_eq!(names[0], names[2]);
+        let shared = |name: &OwnedName| {
+            (
+                name.local_name.as_ptr(),
+                name.prefix.as_ref().unwrap().as_ptr(),
+                name.namespace.as_ref().unwrap().as_ptr(),
+            )
+        };
+        assert_eq!(shared(names[0]), shared(names[2]));
+        assert_eq!(shared(names[1]), shared(names[3]));
+        assert_eq!(names[0].prefix, names[1].prefix);
+        assert_eq!(shared(names[0]).1, shared(names[1]).1);
+    }
+
+    #[test]
+    fn text_in_pieces_is_one_node() {
+        let extensions = consume!(
+            "<extensions>
+                <a>Fish &amp; Chips</a>
+                <b>Fish <!-- and --> <![CDATA[&]]> Chips <!-- ! --> </b>
+                <c>  <!-- only layout -->  <d/> </c>
+            </extensions>",
+            GpxVersion::Gpx11
+        )
+        .unwrap();
+
+        let children: Vec<_> = extensions.elements().collect();
+        assert_eq!(
+            children[0].children,
+            [Node::Text("Fish & Chips".to_string())]
+        );
+        assert_eq!(
+            children[1].children,
+            [Node::Text("Fish  & Chips  ".to_string())]
+        );
+        assert_eq!(children[2].children.len(), 1);
+        assert!(matches!(children[2].children[0], Node::Element(_)));
+    }
+
+    #[test]
+    fn consume_unclosed_extensions() {
+        let result = consume!("<extensions><a></a>", GpxVersion::Gpx11);
+
+        assert!(result.is_err());
+    }
 }
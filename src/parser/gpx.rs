@@ -4,12 +4,17 @@ use geo_types::Rect;
 use std::io::Read;
 use xml::reader::XmlEvent;
 
+use crate::dom::{ProcessingInstruction, PrologNode, UnknownAttributes};
 use crate::errors::{GpxError, GpxResult};
+use crate::parser::link::Gpx10Url;
 use crate::parser::time::Time;
 use crate::parser::{
-    bounds, metadata, route, string, time, track, verify_starting_tag, waypoint, Context,
+    bounds, consume_salvageable, extensions, metadata, order, route, stash_child_attributes,
+    stash_unknown_attributes, string, time, track, verify_starting_tag, waypoint, Context,
 };
-use crate::{Gpx, GpxVersion, Link, Metadata, Person};
+use crate::positions::Positioned;
+use crate::sniff::wrong_format;
+use crate::{Gpx, GpxVersion, Metadata, Person};
 
 /// Convert the version string to the version enum
 fn version_string_to_version(version_str: &str) -> GpxResult<GpxVersion> {
@@ -20,20 +25,74 @@ fn version_string_to_version(version_str: &str) -> GpxResult<GpxVersion> {
     }
 }
 
+/// The GPX 1.0 counterparts of the metadata children, which are direct
+/// children of the gpx element there.
+#[derive(Default)]
+struct Gpx10Metadata {
+    author: Option<String>,
+    url: Gpx10Url,
+    email: Option<String>,
+    time: Option<Time>,
+    bounds: Option<Rect<f64>>,
+    name: Option<String>,
+    description: Option<String>,
+    keywords: Option<String>,
+    unknown_attributes: Vec<UnknownAttributes>,
+}
+
+impl Gpx10Metadata {
+    fn into_metadata(self) -> Option<Metadata> {
+        let person: Person = Person {
+            name: self.author,
+            email: self.email,
+            link: self.url.into_link(),
+        };
+        let author = if person != Default::default() {
+            Some(person)
+        } else {
+            None
+        };
+        let metadata: Metadata = Metadata {
+            name: self.name,
+            time: self.time,
+            bounds: self.bounds,
+            keywords: self.keywords,
+            description: self.description,
+            author,
+            unknown_attributes: self.unknown_attributes,
+            ..Default::default()
+        };
+
+        if metadata != Default::default() {
+            Some(metadata)
+        } else {
+            None
+        }
+    }
+}
+
 /// consume consumes an entire GPX element.
 pub fn consume<R: Read>(context: &mut Context<R>) -> Result<Gpx, GpxError> {
     let mut gpx: Gpx = Default::default();
+    consume_into(context, &mut gpx)?;
+    Ok(gpx)
+}
 
-    let mut author: Option<String> = None;
-    let mut url: Option<String> = None;
-    let mut urlname: Option<String> = None;
-    let mut email: Option<String> = None;
-    let mut time: Option<Time> = None;
-    let mut bounds: Option<Rect<f64>> = None;
-    let mut gpx_name: Option<String> = None;
-    let mut description: Option<String> = None;
-    let mut keywords: Option<String> = None;
+/// consume_into consumes an entire GPX element into `gpx`. On errors, `gpx`
+/// holds what was parsed up to that point.
+pub(crate) fn consume_into<R: Read>(context: &mut Context<R>, gpx: &mut Gpx) -> GpxResult<()> {
+    consume_parts(context, gpx, false).map(|_| ())
+}
 
+/// consume_parts consumes a GPX element into `gpx` like [`consume_into`].
+/// With `header_only`, it stops before the first waypoint, route, track or
+/// extensions and returns true, leaving them to the caller.
+pub(crate) fn consume_parts<R: Read>(
+    context: &mut Context<R>,
+    gpx: &mut Gpx,
+    header_only: bool,
+) -> GpxResult<bool> {
+    gpx.prolog = consume_prolog(context)?;
     // First we consume the gpx tag and its attributes
     let attributes = verify_starting_tag(context, "gpx")?;
     let version = attributes
@@ -47,6 +106,101 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> Result<Gpx, GpxError> {
         .iter()
         .find(|attr| attr.name.local_name == "creator");
     gpx.creator = creator.map(|c| c.value.to_owned());
+    if context.options.preserve_unknown_attributes {
+        stash_unknown_attributes(
+            &mut gpx.unknown_attributes,
+            "",
+            &attributes,
+            &["version", "creator"],
+        );
+    }
+
+    let mut gpx10 = Gpx10Metadata::default();
+    let result = consume_children(context, gpx, &mut gpx10, header_only);
+    if let Some(gpx10) = gpx10.into_metadata() {
+        match &mut gpx.metadata {
+            Some(metadata) => merge_metadata(metadata, gpx10),
+            None => gpx.metadata = Some(gpx10),
+        }
+    }
+    result
+}
+
+/// Fills in what `metadata` lacks from the GPX 1.0 style `gpx10`, for files
+/// that mix both, see [`VersionCompat::Mixed`](crate::VersionCompat::Mixed).
+fn merge_metadata(metadata: &mut Metadata, gpx10: Metadata) {
+    // The attributes go with the elements they were on.
+    let missing = |element: &str| match element {
+        "name" => metadata.name.is_none(),
+        "desc" => metadata.description.is_none(),
+        "time" => metadata.time.is_none(),
+        "keywords" => metadata.keywords.is_none(),
+        _ => false,
+    };
+    let unknown_attributes: Vec<_> = gpx10
+        .unknown_attributes
+        .into_iter()
+        .filter(|unknown| missing(&unknown.element))
+        .collect();
+    metadata.unknown_attributes.extend(unknown_attributes);
+    metadata.name = metadata.name.take().or(gpx10.name);
+    metadata.description = metadata.description.take().or(gpx10.description);
+    metadata.author = metadata.author.take().or(gpx10.author);
+    metadata.time = metadata.time.take().or(gpx10.time);
+    metadata.keywords = metadata.keywords.take().or(gpx10.keywords);
+    metadata.bounds = metadata.bounds.take().or(gpx10.bounds);
+}
+
+/// consume_prolog consumes the comments and processing instructions before
+/// the root element, making sure that the root element is `gpx`.
+fn consume_prolog<R: Read>(context: &mut Context<R>) -> GpxResult<Vec<PrologNode>> {
+    let mut prolog = Vec::new();
+    loop {
+        let node = match context.reader.peek() {
+            Some(Ok(XmlEvent::Comment(text))) => PrologNode::Comment(text.clone()),
+            Some(Ok(XmlEvent::ProcessingInstruction { name, data })) => {
+                PrologNode::ProcessingInstruction(ProcessingInstruction {
+                    name: name.clone(),
+                    data: data.clone(),
+                })
+            }
+            Some(Ok(XmlEvent::StartElement { name, .. })) => {
+                if name.local_name != "gpx" {
+                    return Err(GpxError::NotGpxDocument {
+                        root_element: name.local_name.clone(),
+                    });
+                }
+                return Ok(prolog);
+            }
+            Some(Err(_)) => match context.reader.next() {
+                Some(Err(error)) => {
+                    return Err(match wrong_format(&error) {
+                        Some(format) => GpxError::LikelyWrongFormat(format),
+                        None => GpxError::NotXmlDocument(error),
+                    })
+                }
+                _ => unreachable!("the peeked event is an error"),
+            },
+            Some(Ok(_)) => {
+                context.reader.next();
+                continue;
+            }
+            None => return Ok(prolog),
+        };
+        context.reader.next();
+        prolog.push(node);
+    }
+}
+
+fn consume_children<R: Read>(
+    context: &mut Context<R>,
+    gpx: &mut Gpx,
+    gpx10: &mut Gpx10Metadata,
+    header_only: bool,
+) -> GpxResult<bool> {
+    let mut order = context.element_order("gpx", order::GPX);
+    let allows_gpx10 = context.allows(GpxVersion::Gpx10);
+    let allows_gpx11 = context.allows(GpxVersion::Gpx11);
 
     loop {
         let next_event = {
@@ -61,87 +215,91 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> Result<Gpx, GpxError> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "metadata" if context.version != GpxVersion::Gpx10 => {
-                    gpx.metadata = Some(metadata::consume(context)?);
-                }
-                "trk" => {
-                    gpx.tracks.push(track::consume(context)?);
-                }
-                "rte" => {
-                    gpx.routes.push(route::consume(context)?);
-                }
-                "wpt" => {
-                    gpx.waypoints.push(waypoint::consume(context, "wpt")?);
-                }
-                "time" if context.version == GpxVersion::Gpx10 => {
-                    time = Some(time::consume(context)?);
-                }
-                "bounds" if context.version == GpxVersion::Gpx10 => {
-                    bounds = Some(bounds::consume(context)?);
-                }
-                "author" if context.version == GpxVersion::Gpx10 => {
-                    author = Some(string::consume(context, "author", false)?);
-                }
-                "email" if context.version == GpxVersion::Gpx10 => {
-                    email = Some(string::consume(context, "email", false)?);
+            XmlEvent::StartElement {
+                ref name,
+                ref attributes,
+                ..
+            } => {
+                order.check(&name.local_name)?;
+                if allows_gpx10 {
+                    stash_child_attributes(
+                        &context.options,
+                        &mut gpx10.unknown_attributes,
+                        &name.local_name,
+                        attributes,
+                    );
                 }
-                "url" if context.version == GpxVersion::Gpx10 => {
-                    url = Some(string::consume(context, "url", false)?);
-                }
-                "urlname" if context.version == GpxVersion::Gpx10 => {
-                    urlname = Some(string::consume(context, "urlname", false)?);
-                }
-                "name" if context.version == GpxVersion::Gpx10 => {
-                    gpx_name = Some(string::consume(context, "name", false)?);
-                }
-                "desc" if context.version == GpxVersion::Gpx10 => {
-                    description = Some(string::consume(context, "desc", true)?);
-                }
-                "keywords" if context.version == GpxVersion::Gpx10 => {
-                    keywords = Some(string::consume(context, "keywords", true)?);
-                }
-                child => {
-                    return Err(GpxError::InvalidChildElement(String::from(child), "gpx"));
+                match name.local_name.as_ref() {
+                    "wpt" | "rte" | "trk" | "extensions" if header_only => return Ok(true),
+                    "metadata" if allows_gpx11 => {
+                        context.record_position(Positioned::Metadata, 0);
+                        let (result, metadata) =
+                            consume_salvageable(context, metadata::consume_into);
+                        gpx.metadata = metadata;
+                        result?;
+                    }
+                    "trk" => {
+                        context.record_position(Positioned::Track, gpx.tracks.len());
+                        let (result, track) = consume_salvageable(context, track::consume_into);
+                        gpx.tracks.extend(track);
+                        result?;
+                    }
+                    "rte" => {
+                        context.record_position(Positioned::Route, gpx.routes.len());
+                        let (result, route) = consume_salvageable(context, route::consume_into);
+                        gpx.routes.extend(route);
+                        result?;
+                    }
+                    "wpt" => {
+                        context.record_position(Positioned::Waypoint, gpx.waypoints.len());
+                        gpx.waypoints.push(waypoint::consume(context, "wpt")?);
+                    }
+                    "extensions" => {
+                        gpx.extensions = Some(extensions::consume(context)?);
+                    }
+                    "time" if allows_gpx10 => {
+                        gpx10.time = Some(time::consume(context)?);
+                    }
+                    "bounds" if allows_gpx10 => {
+                        gpx10.bounds = Some(bounds::consume(context)?);
+                    }
+                    "author" if allows_gpx10 => {
+                        gpx10.author = Some(string::consume(context, "author", false)?);
+                    }
+                    "email" if allows_gpx10 => {
+                        gpx10.email = Some(string::consume(context, "email", false)?);
+                    }
+                    "url" if allows_gpx10 => {
+                        gpx10.url.consume(context, "url")?;
+                    }
+                    "urlname" if allows_gpx10 => {
+                        gpx10.url.consume(context, "urlname")?;
+                    }
+                    "name" if allows_gpx10 => {
+                        gpx10.name = Some(string::consume(context, "name", false)?);
+                    }
+                    "desc" if allows_gpx10 => {
+                        gpx10.description = Some(string::consume(context, "desc", true)?);
+                    }
+                    "keywords" if allows_gpx10 => {
+                        gpx10.keywords = Some(string::consume(context, "keywords", true)?);
+                    }
+                    child if !order::GPX.contains(&child) => {
+                        gpx.unknown_elements
+                            .push(extensions::consume_unknown(context)?);
+                    }
+                    child => {
+                        return Err(GpxError::InvalidChildElement(String::from(child), "gpx"));
+                    }
                 }
-            },
+            }
             XmlEvent::EndElement { name } => {
                 if name.local_name != "gpx" {
                     return Err(GpxError::InvalidClosingTag(name.local_name.clone(), "gpx"));
                 }
-                if gpx.version == GpxVersion::Gpx10 {
-                    let link = url.map(|url| Link {
-                        href: url,
-                        text: urlname,
-                        ..Default::default()
-                    });
-                    let person: Person = Person {
-                        name: author,
-                        email,
-                        link,
-                    };
-                    let author = if person != Default::default() {
-                        Some(person)
-                    } else {
-                        None
-                    };
-                    let metadata: Metadata = Metadata {
-                        name: gpx_name,
-                        time,
-                        bounds,
-                        keywords,
-                        description,
-                        author,
-                        ..Default::default()
-                    };
-
-                    if metadata != Default::default() {
-                        gpx.metadata = Some(metadata);
-                    }
-                }
                 context.reader.next();
 
-                return Ok(gpx);
+                return Ok(false);
             }
             _ => {
                 context.reader.next(); //consume and ignore this event
@@ -220,4 +378,34 @@ mod tests {
         let wpt = &gpx.waypoints[1];
         assert_eq!(wpt.point(), Point::new(10.256, -81.324));
     }
+
+    #[test]
+    fn consume_gpx_unknown_elements() {
+        let gpx = consume!(
+            "<gpx version=\"1.1\" xmlns:x=\"http://example.com/x\">
+                <x:first a=\"1\"><x:inner>text</x:inner></x:first>
+                <trk></trk>
+                <second></second>
+            </gpx>",
+            GpxVersion::Unknown
+        )
+        .unwrap();
+
+        assert_eq!(gpx.tracks.len(), 1);
+        assert_eq!(gpx.unknown_elements.len(), 2);
+        assert_eq!(gpx.unknown_elements[0].name.local_name, "first");
+        let inner = gpx.unknown_elements[0].elements().next().unwrap();
+        assert_eq!(inner.text(), "text");
+        assert_eq!(gpx.unknown_elements[1].name.local_name, "second");
+    }
+
+    #[test]
+    fn consume_gpx_known_element_of_other_version() {
+        let gpx = consume!(
+            "<gpx version=\"1.1\"><time>2016-03-27T18:57:55Z</time></gpx>",
+            GpxVersion::Unknown
+        );
+
+        assert!(gpx.is_err());
+    }
 }
@@ -5,7 +5,7 @@ use std::io::Read;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{string, verify_starting_tag, Context};
+use crate::parser::{order, string, verify_starting_tag, Context};
 use crate::Link;
 
 /// consume consumes a GPX link from the `reader` until it ends.
@@ -14,6 +14,7 @@ use crate::Link;
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Link> {
     let mut link: Link = Default::default();
     let attributes = verify_starting_tag(context, "link")?;
+    let mut order = context.element_order("link", order::LINK);
     let attr = attributes
         .into_iter()
         .find(|attr| attr.name.local_name == "href");
@@ -35,13 +36,16 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Link> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "text" => link.text = Some(string::consume(context, "text", false)?),
-                "type" => link._type = Some(string::consume(context, "type", false)?),
-                child => {
-                    return Err(GpxError::InvalidChildElement(String::from(child), "link"));
+            XmlEvent::StartElement { ref name, .. } => {
+                order.check(&name.local_name)?;
+                match name.local_name.as_ref() {
+                    "text" => link.text = Some(string::consume(context, "text", false)?),
+                    "type" => link._type = Some(string::consume(context, "type", false)?),
+                    child => {
+                        return Err(GpxError::InvalidChildElement(String::from(child), "link"));
+                    }
                 }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != "link" {
                     return Err(GpxError::InvalidClosingTag(name.local_name.clone(), "link"));
@@ -107,3 +111,38 @@ mod tests {
         assert!(link.is_err());
     }
 }
+
+/// The GPX 1.0 `<url>` and `<urlname>` children of an element, which
+/// together make up a link.
+#[derive(Default)]
+pub(crate) struct Gpx10Url {
+    url: Option<String>,
+    urlname: Option<String>,
+}
+
+impl Gpx10Url {
+    /// Consumes the `<url>` or `<urlname>` element named `tagname`.
+    pub(crate) fn consume<R: Read>(
+        &mut self,
+        context: &mut Context<R>,
+        tagname: &'static str,
+    ) -> GpxResult<()> {
+        let value = Some(string::consume(context, tagname, false)?);
+        match tagname {
+            "url" => self.url = value,
+            _ => self.urlname = value,
+        }
+        Ok(())
+    }
+
+    /// Gives the link, if there was a `<url>`. A `<urlname>` on its own has
+    /// nothing to link to and is dropped.
+    pub(crate) fn into_link(self) -> Option<Link> {
+        let text = self.urlname;
+        self.url.map(|href| Link {
+            href,
+            text,
+            ..Default::default()
+        })
+    }
+}
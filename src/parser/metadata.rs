@@ -6,13 +6,30 @@ use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
 use crate::parser::{
-    bounds, copyright, extensions, link, person, string, time, verify_starting_tag, Context,
+    bounds, copyright, extensions, link, order, person, stash_child_attributes,
+    stash_unknown_attributes, string, time, verify_starting_tag, Context,
 };
 use crate::Metadata;
 
+/// consume consumes GPX metadata from the `reader` until it ends.
+#[cfg(test)]
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Metadata> {
     let mut metadata: Metadata = Default::default();
-    verify_starting_tag(context, "metadata")?;
+    consume_into(context, &mut metadata)?;
+    Ok(metadata)
+}
+
+/// consume_into consumes GPX metadata into `metadata`. On errors, `metadata` holds what was
+/// parsed up to that point.
+pub(crate) fn consume_into<R: Read>(
+    context: &mut Context<R>,
+    metadata: &mut Metadata,
+) -> GpxResult<()> {
+    let attributes = verify_starting_tag(context, "metadata")?;
+    if context.options.preserve_unknown_attributes {
+        stash_unknown_attributes(&mut metadata.unknown_attributes, "", &attributes, &[]);
+    }
+    let mut order = context.element_order("metadata", order::METADATA);
 
     loop {
         let next_event = {
@@ -27,41 +44,54 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Metadata> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "name" => {
-                    metadata.name = Some(string::consume(context, "name", false)?);
-                }
-                "desc" => {
-                    metadata.description = Some(string::consume(context, "desc", true)?);
-                }
-                "author" => {
-                    metadata.author = Some(person::consume(context, "author")?);
-                }
-                "keywords" => {
-                    metadata.keywords = Some(string::consume(context, "keywords", true)?);
-                }
-                "time" => {
-                    metadata.time = Some(time::consume(context)?);
+            XmlEvent::StartElement {
+                ref name,
+                ref attributes,
+                ..
+            } => {
+                order.check(&name.local_name)?;
+                stash_child_attributes(
+                    &context.options,
+                    &mut metadata.unknown_attributes,
+                    &name.local_name,
+                    attributes,
+                );
+                match name.local_name.as_ref() {
+                    "name" => {
+                        metadata.name = Some(string::consume(context, "name", false)?);
+                    }
+                    "desc" => {
+                        metadata.description = Some(string::consume(context, "desc", true)?);
+                    }
+                    "author" => {
+                        metadata.author = Some(person::consume(context, "author")?);
+                    }
+                    "keywords" => {
+                        metadata.keywords = Some(string::consume(context, "keywords", true)?);
+                    }
+                    "time" => {
+                        metadata.time = Some(time::consume(context)?);
+                    }
+                    "link" => {
+                        metadata.links.push(link::consume(context)?);
+                    }
+                    "bounds" => {
+                        metadata.bounds = Some(bounds::consume(context)?);
+                    }
+                    "copyright" => {
+                        metadata.copyright = Some(copyright::consume(context)?);
+                    }
+                    "extensions" => {
+                        metadata.extensions = Some(extensions::consume(context)?);
+                    }
+                    child => {
+                        return Err(GpxError::InvalidChildElement(
+                            String::from(child),
+                            "metadata",
+                        ));
+                    }
                 }
-                "link" => {
-                    metadata.links.push(link::consume(context)?);
-                }
-                "bounds" => {
-                    metadata.bounds = Some(bounds::consume(context)?);
-                }
-                "copyright" => {
-                    metadata.copyright = Some(copyright::consume(context)?);
-                }
-                "extensions" => {
-                    extensions::consume(context)?;
-                }
-                child => {
-                    return Err(GpxError::InvalidChildElement(
-                        String::from(child),
-                        "metadata",
-                    ));
-                }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != "metadata" {
                     return Err(GpxError::InvalidClosingTag(
@@ -70,7 +100,7 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Metadata> {
                     ));
                 }
                 context.reader.next(); //consume the end tag
-                return Ok(metadata);
+                return Ok(());
             }
             _ => {
                 context.reader.next(); //consume and ignore this event
@@ -5,24 +5,24 @@
 #[macro_export]
 macro_rules! consume {
     ($xml:expr, $version:expr) => {{
-        use crate::parser::create_context;
         use std::io::BufReader;
+        use $crate::parser::create_context;
         consume(&mut create_context(
             BufReader::new($xml.as_bytes()),
             $version,
         ))
     }};
     ($xml:expr, $version:expr, $tagname:expr) => {{
-        use crate::parser::create_context;
         use std::io::BufReader;
+        use $crate::parser::create_context;
         consume(
             &mut create_context(BufReader::new($xml.as_bytes()), $version),
             $tagname,
         )
     }};
     ($xml:expr, $version:expr, $tagname:expr, $allow_empty:expr) => {{
-        use crate::parser::create_context;
         use std::io::BufReader;
+        use $crate::parser::create_context;
         consume(
             &mut create_context(BufReader::new($xml.as_bytes()), $version),
             $tagname,
@@ -39,6 +39,8 @@ pub mod fix;
 pub mod gpx;
 pub mod link;
 pub mod metadata;
+pub mod number;
+pub mod order;
 pub mod person;
 pub mod route;
 pub mod string;
@@ -47,29 +49,236 @@ pub mod track;
 pub mod tracksegment;
 pub mod waypoint;
 
-use std::io::Read;
-use std::iter::Peekable;
+use std::io::{self, BufRead, BufReader, Read};
 
 use xml::attribute::OwnedAttribute;
-use xml::reader::{Events, XmlEvent};
+use xml::common::{Position, TextPosition};
+use xml::reader::{ParserConfig2, XmlEvent};
 use xml::{EventReader, ParserConfig};
 
-use crate::errors::GpxError;
+use crate::dom::{Interner, UnknownAttributes};
+use crate::errors::{GpxError, GpxResult};
+use crate::parser::order::ElementOrder;
+use crate::positions::{PositionRecorder, Positioned, SourcePositions};
+use crate::reader::{LongStringPolicy, ReadOptions, VersionCompat};
+use crate::sniff::{sniff, wrong_format_error, SNIFF_LEN};
 use crate::types::GpxVersion;
 
+/// The events of a document, like `Peekable<Events<R>>`, but also keeping
+/// the position at which the peeked event starts.
+///
+/// Comments are only passed on before the root element, where they are kept
+/// in [`Gpx::prolog`](crate::Gpx::prolog).
+pub struct EventStream<R: Read> {
+    reader: EventReader<R>,
+    peeked: Option<Option<(xml::reader::Result<XmlEvent>, TextPosition)>>,
+    finished: bool,
+    in_root: bool,
+}
+
+impl<R: Read> EventStream<R> {
+    pub fn new(reader: EventReader<R>) -> EventStream<R> {
+        EventStream {
+            reader,
+            peeked: None,
+            finished: false,
+            in_root: false,
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&xml::reader::Result<XmlEvent>> {
+        self.peek_with_position().map(|(event, _)| event)
+    }
+
+    /// Gives the position at which the next event starts.
+    pub fn peek_position(&mut self) -> Option<TextPosition> {
+        self.peek_with_position().map(|&(_, position)| position)
+    }
+
+    fn peek_with_position(&mut self) -> Option<&(xml::reader::Result<XmlEvent>, TextPosition)> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read());
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    fn read(&mut self) -> Option<(xml::reader::Result<XmlEvent>, TextPosition)> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            let event = self.reader.next();
+            match event {
+                Ok(XmlEvent::EndDocument) | Err(_) => self.finished = true,
+                Ok(XmlEvent::StartElement { .. }) => self.in_root = true,
+                Ok(XmlEvent::Comment(_)) if self.in_root => continue,
+                _ => {}
+            }
+            return Some((event, self.reader.position()));
+        }
+    }
+}
+
+impl<R: Read> Iterator for EventStream<R> {
+    type Item = xml::reader::Result<XmlEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.read(),
+        }
+        .map(|(event, _)| event)
+    }
+}
+
 pub struct Context<R: Read> {
-    reader: Peekable<Events<R>>,
+    reader: EventStream<R>,
     version: GpxVersion,
+    options: ReadOptions,
+    positions: Option<PositionRecorder>,
+    names: Interner,
 }
 
 impl<R: Read> Context<R> {
-    pub fn new(reader: Peekable<Events<R>>, version: GpxVersion) -> Context<R> {
-        Context { reader, version }
+    pub fn new(reader: EventStream<R>, version: GpxVersion, options: ReadOptions) -> Context<R> {
+        let positions = if options.record_source_positions {
+            Some(PositionRecorder::default())
+        } else {
+            None
+        };
+        Context {
+            reader,
+            version,
+            options,
+            positions,
+            names: Interner::default(),
+        }
     }
 
-    pub fn reader(&mut self) -> &mut Peekable<Events<R>> {
+    pub fn reader(&mut self) -> &mut EventStream<R> {
         &mut self.reader
     }
+
+    /// Gives the interner for the names of extension elements, so that each
+    /// distinct name is kept once per document.
+    pub(crate) fn names(&mut self) -> &mut Interner {
+        &mut self.names
+    }
+
+    /// Records where the element about to be consumed starts, if
+    /// [`ReadOptions::record_source_positions`] is enabled. `index` is the
+    /// index of the element among its siblings of the same kind.
+    pub(crate) fn record_position(&mut self, element: Positioned, index: usize) {
+        if let Some(positions) = &mut self.positions {
+            if let Some(position) = self.reader.peek_position() {
+                positions.record(element, index, position);
+            }
+        }
+    }
+
+    /// Gives the positions recorded so far.
+    pub(crate) fn take_positions(&mut self) -> SourcePositions {
+        self.positions
+            .take()
+            .map(PositionRecorder::finish)
+            .unwrap_or_default()
+    }
+
+    /// Whether elements of the given GPX version may appear, which is the
+    /// case for the version of the file, or for both with
+    /// [`VersionCompat::Mixed`].
+    pub(crate) fn allows(&self, version: GpxVersion) -> bool {
+        let declared = match version {
+            GpxVersion::Gpx10 => self.version == GpxVersion::Gpx10,
+            _ => self.version != GpxVersion::Gpx10,
+        };
+        declared || self.options.version_compat == VersionCompat::Mixed
+    }
+
+    /// Creates the child order tracker for `parent`, honoring
+    /// [`ReadOptions::enforce_element_order`].
+    pub(crate) fn element_order(
+        &self,
+        parent: &'static str,
+        sequence: &'static [&'static str],
+    ) -> ElementOrder {
+        ElementOrder::new(parent, sequence, self.options.enforce_element_order)
+    }
+}
+
+/// Child elements that only hold text, and whose attributes are stashed
+/// together with those of their parent.
+const SIMPLE_ELEMENTS: &[&str] = &[
+    "ele",
+    "time",
+    "course",
+    "speed",
+    "magvar",
+    "geoidheight",
+    "name",
+    "cmt",
+    "desc",
+    "src",
+    "number",
+    "sym",
+    "type",
+    "fix",
+    "sat",
+    "hdop",
+    "vdop",
+    "pdop",
+    "ageofdgpsdata",
+    "dgpsid",
+    "keywords",
+];
+
+/// Keeps the attributes of `element` whose local name is not in `known` in
+/// `stash`. `element` is empty for the element of the owning struct itself.
+pub(crate) fn stash_unknown_attributes(
+    stash: &mut Vec<UnknownAttributes>,
+    element: &str,
+    attributes: &[OwnedAttribute],
+    known: &[&str],
+) {
+    let unknown: Vec<_> = attributes
+        .iter()
+        .filter(|attr| {
+            attr.name.namespace.is_some() || !known.contains(&attr.name.local_name.as_str())
+        })
+        .map(|attr| attr.clone().into())
+        .collect();
+    if !unknown.is_empty() {
+        stash.push(UnknownAttributes {
+            element: element.to_owned(),
+            attributes: unknown,
+        });
+    }
+}
+
+/// Stashes the attributes of the child `element`, if it is a simple element
+/// and [`ReadOptions::preserve_unknown_attributes`] is enabled.
+pub(crate) fn stash_child_attributes(
+    options: &ReadOptions,
+    stash: &mut Vec<UnknownAttributes>,
+    element: &str,
+    attributes: &[OwnedAttribute],
+) {
+    if options.preserve_unknown_attributes && SIMPLE_ELEMENTS.contains(&element) {
+        stash_unknown_attributes(stash, element, attributes, &[]);
+    }
+}
+
+/// Consumes a child container with `consume_into`. The child is given back
+/// if it was parsed completely or, when salvaging a broken file, with what
+/// was parsed before the error.
+pub(crate) fn consume_salvageable<R: Read, T: Default>(
+    context: &mut Context<R>,
+    consume_into: fn(&mut Context<R>, &mut T) -> GpxResult<()>,
+) -> (GpxResult<()>, Option<T>) {
+    let mut child = T::default();
+    let result = consume_into(context, &mut child);
+    let keep = result.is_ok() || context.options.salvage;
+    (result, if keep { Some(child) } else { None })
 }
 
 pub fn verify_starting_tag<R: Read>(
@@ -102,13 +311,145 @@ pub fn verify_starting_tag<R: Read>(
     }
 }
 
-pub(crate) fn create_context<R: Read>(reader: R, version: GpxVersion) -> Context<R> {
-    let parser_config = ParserConfig {
+/// The UTF-8 byte order mark.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A reader that skips a UTF-8 byte order mark and whitespace at the start
+/// of the document, which some tools write before the XML declaration.
+///
+/// The underlying reader may return data in pieces of any size, and reads
+/// interrupted while skipping are retried.
+pub struct TrimStart<R: Read> {
+    /// The first bytes, read ahead to recognize the format, then the rest.
+    inner: BufReader<io::Chain<io::Cursor<Vec<u8>>, R>>,
+    at_start: bool,
+    /// The start of something that looked like a byte order mark but was
+    /// not, to be given back before the rest.
+    pending: &'static [u8],
+}
+
+impl<R: Read> TrimStart<R> {
+    pub fn new(reader: R) -> TrimStart<R> {
+        TrimStart {
+            inner: BufReader::new(io::Cursor::new(Vec::new()).chain(reader)),
+            at_start: true,
+            pending: &[],
+        }
+    }
+
+    /// Reads the first [`SNIFF_LEN`] bytes, or all of a shorter input, to be
+    /// given back before the rest, as a slow reader may give them in pieces.
+    fn read_head(&mut self) -> io::Result<&[u8]> {
+        let (head, reader) = self.inner.get_mut().get_mut();
+        let head = head.get_mut();
+        let mut piece = [0; SNIFF_LEN];
+        while head.len() < SNIFF_LEN {
+            match reader.read(&mut piece[..SNIFF_LEN - head.len()]) {
+                Ok(0) => break,
+                Ok(size) => head.extend_from_slice(&piece[..size]),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(head)
+    }
+
+    fn skip_start(&mut self) -> io::Result<()> {
+        // Match the byte order mark a byte at a time, as a slow reader may
+        // split it.
+        let mut matched = 0;
+        while matched < BOM.len() {
+            if fill_buf(&mut self.inner)?.first() != Some(&BOM[matched]) {
+                break;
+            }
+            self.inner.consume(1);
+            matched += 1;
+        }
+        if matched != 0 && matched < BOM.len() {
+            self.pending = &BOM[..matched];
+            return Ok(());
+        }
+        loop {
+            let available = fill_buf(&mut self.inner)?;
+            let skip = available
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count();
+            let done = available.is_empty() || skip < available.len();
+            self.inner.consume(skip);
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Fills the buffer of `reader`, retrying interrupted reads.
+fn fill_buf<R: Read>(reader: &mut BufReader<R>) -> io::Result<&[u8]> {
+    loop {
+        match reader.fill_buf() {
+            Ok(_) => return Ok(reader.buffer()),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+impl<R: Read> Read for TrimStart<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.at_start {
+            // FIT headers may start with what looks like whitespace, so look
+            // before skipping it.
+            if let Some(format) = sniff(self.read_head()?) {
+                return Err(wrong_format_error(format));
+            }
+            self.skip_start()?;
+            self.at_start = false;
+        }
+        if !self.pending.is_empty() {
+            let size = self.pending.len().min(buf.len());
+            buf[..size].copy_from_slice(&self.pending[..size]);
+            self.pending = &self.pending[size..];
+            return Ok(size);
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// The shortest limit on text the XML parser is given, so that whitespace
+/// between elements never runs into it.
+const MIN_PARSER_TEXT_LIMIT: usize = 4096;
+
+/// Gives the length in bytes of text after which the XML parser stops, if
+/// [`ReadOptions::max_string_length`] asks for it.
+pub(crate) fn parser_text_limit(options: &ReadOptions) -> Option<usize> {
+    match options.long_strings {
+        LongStringPolicy::Error => options
+            .max_string_length
+            .map(|limit| limit.max(MIN_PARSER_TEXT_LIMIT)),
+        LongStringPolicy::Truncate => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn create_context<R: Read>(reader: R, version: GpxVersion) -> Context<TrimStart<R>> {
+    create_context_with_options(reader, version, ReadOptions::default())
+}
+
+pub(crate) fn create_context_with_options<R: Read>(
+    reader: R,
+    version: GpxVersion,
+    options: ReadOptions,
+) -> Context<TrimStart<R>> {
+    let mut parser_config = ParserConfig2::from(ParserConfig {
         whitespace_to_characters: true, //convert Whitespace event to Characters
         cdata_to_characters: true,      //convert CData event to Characters
+        ignore_comments: false,         //comments before the root are kept
         ..ParserConfig::new()
-    };
-    let parser = EventReader::new_with_config(reader, parser_config);
-    let events = parser.into_iter().peekable();
-    Context::new(events, version)
+    });
+    if let Some(limit) = parser_text_limit(&options) {
+        parser_config.max_data_length = limit;
+    }
+    let parser = EventReader::new_with_config(TrimStart::new(reader), parser_config);
+    Context::new(EventStream::new(parser), version, options)
 }
@@ -0,0 +1,87 @@
+//! number handles parsing of GPX-spec decimals.
+
+use std::io::Read;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::errors::GpxResult;
+use crate::parser::{string, Context};
+use crate::types::validate_finite;
+
+/// parse parses the decimal `value` of `field`. Exponents and a leading
+/// sign are accepted, NaN and infinities are not.
+pub fn parse(value: &str, field: &'static str) -> GpxResult<f64> {
+    validate_finite(field, value.trim().parse()?)
+}
+
+/// consume consumes a single decimal as tag content.
+pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxResult<f64> {
+    parse(&string::consume(context, tagname, false)?, tagname)
+}
+
+/// consume_integer consumes a single integer as tag content, such as the
+/// number of satellites. Whitespace around it is ignored.
+pub fn consume_integer<R: Read, T: FromStr<Err = ParseIntError>>(
+    context: &mut Context<R>,
+    tagname: &'static str,
+) -> GpxResult<T> {
+    Ok(string::consume(context, tagname, false)?.trim().parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::{consume, consume_integer, parse};
+    use crate::errors::GpxError;
+    use crate::parser::create_context;
+    use crate::GpxVersion;
+
+    #[test]
+    fn parse_decimals() {
+        assert_eq!(parse("1.5", "ele").unwrap(), 1.5);
+        assert_eq!(parse("+1.5", "ele").unwrap(), 1.5);
+        assert_eq!(parse("-1e2", "ele").unwrap(), -100.0);
+        assert_eq!(parse("2.5E-1", "ele").unwrap(), 0.25);
+        assert_eq!(parse(" 3 ", "ele").unwrap(), 3.0);
+    }
+
+    #[test]
+    fn parse_rejects_non_finite() {
+        for value in &["NaN", "nan", "Infinity", "-Infinity", "inf", "+inf"] {
+            match parse(value, "ele") {
+                Err(GpxError::InvalidNumericValue("ele", _)) => {}
+                other => panic!("{} parsed as {:?}", value, other),
+            }
+        }
+        assert!(matches!(
+            parse("1.5.2", "ele"),
+            Err(GpxError::ParseFloatError(_))
+        ));
+    }
+
+    #[test]
+    fn consume_around_comments_and_whitespace() {
+        let ele = consume!(
+            "<ele><!-- meters -->52<?unit m?>3.4\n</ele>",
+            GpxVersion::Gpx11,
+            "ele"
+        );
+        assert_eq!(ele.unwrap(), 523.4);
+
+        let mut context = create_context(
+            BufReader::new("<sat>\n  7 <!-- fix --> </sat>".as_bytes()),
+            GpxVersion::Gpx11,
+        );
+        assert_eq!(consume_integer::<_, u64>(&mut context, "sat").unwrap(), 7);
+
+        let mut context = create_context(
+            BufReader::new("<dgpsid> 70000 </dgpsid>".as_bytes()),
+            GpxVersion::Gpx11,
+        );
+        assert!(matches!(
+            consume_integer::<_, u16>(&mut context, "dgpsid"),
+            Err(GpxError::ParseIntegerError(_))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+//! order holds the child element sequences prescribed by the GPX schemas.
+//!
+//! The tables merge the GPX 1.0 and GPX 1.1 sequences. Elements that only
+//! exist in one of the versions never appear together in a valid file, so a
+//! single table per parent is enough for both. Files mixing the versions are
+//! held to the merged order as well.
+
+use crate::errors::{GpxError, GpxResult};
+
+pub(crate) const GPX: &[&str] = &[
+    "metadata",
+    "name",
+    "desc",
+    "author",
+    "email",
+    "url",
+    "urlname",
+    "time",
+    "keywords",
+    "bounds",
+    "wpt",
+    "rte",
+    "trk",
+    "extensions",
+];
+
+pub(crate) const METADATA: &[&str] = &[
+    "name",
+    "desc",
+    "author",
+    "copyright",
+    "link",
+    "time",
+    "keywords",
+    "bounds",
+    "extensions",
+];
+
+pub(crate) const WAYPOINT: &[&str] = &[
+    "ele",
+    "time",
+    "course",
+    "speed",
+    "magvar",
+    "geoidheight",
+    "name",
+    "cmt",
+    "desc",
+    "src",
+    "link",
+    "url",
+    "urlname",
+    "sym",
+    "type",
+    "fix",
+    "sat",
+    "hdop",
+    "vdop",
+    "pdop",
+    "ageofdgpsdata",
+    "dgpsid",
+    "extensions",
+];
+
+pub(crate) const ROUTE: &[&str] = &[
+    "name",
+    "cmt",
+    "desc",
+    "src",
+    "link",
+    "url",
+    "urlname",
+    "number",
+    "type",
+    "extensions",
+    "rtept",
+];
+
+pub(crate) const TRACK: &[&str] = &[
+    "name",
+    "cmt",
+    "desc",
+    "src",
+    "link",
+    "url",
+    "urlname",
+    "number",
+    "type",
+    "extensions",
+    "trkseg",
+];
+
+pub(crate) const TRACKSEGMENT: &[&str] = &["trkpt", "extensions"];
+
+pub(crate) const LINK: &[&str] = &["text", "type"];
+
+pub(crate) const PERSON: &[&str] = &["name", "email", "link"];
+
+pub(crate) const COPYRIGHT: &[&str] = &["year", "license"];
+
+/// ElementOrder remembers the schema position of the last seen child element
+/// of a parent, so that children appearing out of sequence can be rejected.
+pub(crate) struct ElementOrder {
+    parent: &'static str,
+    sequence: &'static [&'static str],
+    enforce: bool,
+    last: Option<usize>,
+}
+
+impl ElementOrder {
+    pub(crate) fn new(
+        parent: &'static str,
+        sequence: &'static [&'static str],
+        enforce: bool,
+    ) -> ElementOrder {
+        ElementOrder {
+            parent,
+            sequence,
+            enforce,
+            last: None,
+        }
+    }
+
+    /// Records `element` as the next child, failing if it comes before the
+    /// previously seen child in the schema sequence.
+    ///
+    /// Elements that are not part of the sequence are ignored here, the
+    /// parsers report them on their own.
+    pub(crate) fn check(&mut self, element: &str) -> GpxResult<()> {
+        if !self.enforce {
+            return Ok(());
+        }
+        let position = match self.sequence.iter().position(|&name| name == element) {
+            Some(position) => position,
+            None => return Ok(()),
+        };
+        if let Some(last) = self.last {
+            if position < last {
+                return Err(GpxError::ElementOutOfOrder {
+                    parent: self.parent,
+                    element: element.to_owned(),
+                    expected_after: self.sequence[last].to_owned(),
+                });
+            }
+        }
+        self.last = Some(position);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ElementOrder, WAYPOINT};
+
+    #[test]
+    fn accepts_schema_order() {
+        let mut order = ElementOrder::new("waypoint", WAYPOINT, true);
+
+        for element in &["ele", "time", "name", "link", "link", "extensions"] {
+            assert!(order.check(element).is_ok());
+        }
+    }
+
+    #[test]
+    fn rejects_regression() {
+        let mut order = ElementOrder::new("waypoint", WAYPOINT, true);
+
+        assert!(order.check("time").is_ok());
+        let err = order.check("ele").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "element `ele` in `waypoint` must come before `time`"
+        );
+    }
+
+    #[test]
+    fn ignores_regression_when_disabled() {
+        let mut order = ElementOrder::new("waypoint", WAYPOINT, false);
+
+        assert!(order.check("time").is_ok());
+        assert!(order.check("ele").is_ok());
+    }
+}
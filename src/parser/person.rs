@@ -5,12 +5,13 @@ use std::io::Read;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{email, link, string, verify_starting_tag, Context};
+use crate::parser::{email, link, order, string, verify_starting_tag, Context};
 use crate::Person;
 
 pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxResult<Person> {
     let mut person: Person = Default::default();
     verify_starting_tag(context, tagname)?;
+    let mut order = context.element_order("person", order::PERSON);
 
     loop {
         let next_event = {
@@ -25,14 +26,17 @@ pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxR
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "name" => person.name = Some(string::consume(context, "name", false)?),
-                "email" => person.email = Some(email::consume(context)?),
-                "link" => person.link = Some(link::consume(context)?),
-                child => {
-                    return Err(GpxError::InvalidChildElement(String::from(child), "person"));
+            XmlEvent::StartElement { ref name, .. } => {
+                order.check(&name.local_name)?;
+                match name.local_name.as_ref() {
+                    "name" => person.name = Some(string::consume(context, "name", false)?),
+                    "email" => person.email = Some(email::consume(context)?),
+                    "link" => person.link = Some(link::consume(context)?),
+                    child => {
+                        return Err(GpxError::InvalidChildElement(String::from(child), "person"));
+                    }
                 }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 // TODO: revisit this (and similar) when https://github.com/rust-lang/rfcs/pull/3137
                 // has been on stable for a few versions
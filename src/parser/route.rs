@@ -5,13 +5,44 @@ use std::io::Read;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{extensions, link, string, verify_starting_tag, waypoint, Context};
-use crate::Route;
+use crate::parser::link::Gpx10Url;
+use crate::parser::{
+    extensions, link, number, order, stash_child_attributes, stash_unknown_attributes, string,
+    verify_starting_tag, waypoint, Context,
+};
+use crate::positions::Positioned;
+use crate::{GpxVersion, Route};
 
 /// consume consumes a GPX route from the `reader` until it ends.
+#[cfg(test)]
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Route> {
     let mut route: Route = Default::default();
-    verify_starting_tag(context, "rte")?;
+    consume_into(context, &mut route)?;
+    Ok(route)
+}
+
+/// consume_into consumes a GPX route into `route`. On errors, `route` holds what was
+/// parsed up to that point.
+pub(crate) fn consume_into<R: Read>(context: &mut Context<R>, route: &mut Route) -> GpxResult<()> {
+    consume_parts(context, route, false).map(|_| ())
+}
+
+/// consume_parts consumes a GPX route into `route` like [`consume_into`]. With
+/// `header_only`, it stops before the first point and returns true, leaving
+/// the points to the caller.
+pub(crate) fn consume_parts<R: Read>(
+    context: &mut Context<R>,
+    route: &mut Route,
+    header_only: bool,
+) -> GpxResult<bool> {
+    let attributes = verify_starting_tag(context, "rte")?;
+    if context.options.preserve_unknown_attributes {
+        stash_unknown_attributes(&mut route.unknown_attributes, "", &attributes, &[]);
+    }
+    let mut order = context.element_order("route", order::ROUTE);
+
+    let mut url = Gpx10Url::default();
+    let allows_gpx10 = context.allows(GpxVersion::Gpx10);
 
     loop {
         let next_event = {
@@ -26,38 +57,56 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Route> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "name" => {
-                    route.name = Some(string::consume(context, "name", false)?);
-                }
-                "cmt" => {
-                    route.comment = Some(string::consume(context, "cmt", true)?);
-                }
-                "desc" => {
-                    route.description = Some(string::consume(context, "desc", true)?);
+            XmlEvent::StartElement {
+                ref name,
+                ref attributes,
+                ..
+            } => {
+                order.check(&name.local_name)?;
+                stash_child_attributes(
+                    &context.options,
+                    &mut route.unknown_attributes,
+                    &name.local_name,
+                    attributes,
+                );
+                match name.local_name.as_ref() {
+                    "rtept" if header_only => {
+                        route.links.extend(std::mem::take(&mut url).into_link());
+                        return Ok(true);
+                    }
+                    "name" => {
+                        route.name = Some(string::consume(context, "name", false)?);
+                    }
+                    "cmt" => {
+                        route.comment = Some(string::consume(context, "cmt", true)?);
+                    }
+                    "desc" => {
+                        route.description = Some(string::consume(context, "desc", true)?);
+                    }
+                    "src" => {
+                        route.source = Some(string::consume(context, "src", true)?);
+                    }
+                    "number" => route.number = Some(number::consume_integer(context, "number")?),
+                    "type" => {
+                        route._type = Some(string::consume(context, "type", false)?);
+                    }
+                    "rtept" => {
+                        context.record_position(Positioned::RoutePoint, route.points.len());
+                        route.points.push(waypoint::consume(context, "rtept")?);
+                    }
+                    "link" => {
+                        route.links.push(link::consume(context)?);
+                    }
+                    "url" if allows_gpx10 => url.consume(context, "url")?,
+                    "urlname" if allows_gpx10 => url.consume(context, "urlname")?,
+                    "extensions" => {
+                        route.extensions = Some(extensions::consume(context)?);
+                    }
+                    child => {
+                        return Err(GpxError::InvalidChildElement(String::from(child), "route"));
+                    }
                 }
-                "src" => {
-                    route.source = Some(string::consume(context, "src", true)?);
-                }
-                "number" => {
-                    route.number = Some(string::consume(context, "number", false)?.parse()?)
-                }
-                "type" => {
-                    route._type = Some(string::consume(context, "type", false)?);
-                }
-                "rtept" => {
-                    route.points.push(waypoint::consume(context, "rtept")?);
-                }
-                "link" => {
-                    route.links.push(link::consume(context)?);
-                }
-                "extensions" => {
-                    extensions::consume(context)?;
-                }
-                child => {
-                    return Err(GpxError::InvalidChildElement(String::from(child), "route"));
-                }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != "rte" {
                     return Err(GpxError::InvalidClosingTag(
@@ -66,7 +115,8 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Route> {
                     ));
                 }
                 context.reader.next(); //consume the end tag
-                return Ok(route);
+                route.links.extend(url.into_link());
+                return Ok(false);
             }
             _ => {
                 context.reader.next(); //consume and ignore this event
@@ -2,12 +2,18 @@
 
 use std::io::Read;
 
-use xml::reader::XmlEvent;
+use xml::reader::{ErrorKind, EventReader, ParserConfig2, XmlEvent};
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{verify_starting_tag, Context};
+use crate::parser::{parser_text_limit, verify_starting_tag, Context};
+use crate::reader::LongStringPolicy;
 
 /// consume consumes a single string as tag content.
+///
+/// The string is limited to [`ReadOptions::max_string_length`] bytes as it
+/// is read.
+///
+/// [`ReadOptions::max_string_length`]: crate::ReadOptions::max_string_length
 pub fn consume<R: Read>(
     context: &mut Context<R>,
     tagname: &'static str,
@@ -15,16 +21,49 @@ pub fn consume<R: Read>(
 ) -> GpxResult<String> {
     verify_starting_tag(context, tagname)?;
     let mut string = String::new();
+    let limit = context.options.max_string_length;
+    let policy = context.options.long_strings;
+    let parser_limit = parser_text_limit(&context.options);
 
     for event in context.reader() {
-        match event? {
+        let event = match (event, limit, parser_limit) {
+            (Err(error), Some(limit), Some(parser_limit)) if is_limit_error(&error) => {
+                // The parser stops at the first byte over its limit, so this
+                // is what was read of the string, not all of it.
+                return Err(GpxError::StringTooLong {
+                    element: tagname,
+                    length: string.len() + parser_limit + 1,
+                    limit,
+                });
+            }
+            (event, _, _) => event?,
+        };
+        match event {
             XmlEvent::StartElement { ref name, .. } => {
                 return Err(GpxError::InvalidChildElement(
                     name.local_name.clone(),
                     tagname,
                 ));
             }
-            XmlEvent::Characters(content) => string = content,
+            XmlEvent::Characters(content) => match limit {
+                Some(limit) if string.len() + content.len() > limit => match policy {
+                    LongStringPolicy::Error => {
+                        return Err(GpxError::StringTooLong {
+                            element: tagname,
+                            length: string.len() + content.len(),
+                            limit,
+                        });
+                    }
+                    LongStringPolicy::Truncate => {
+                        let mut end = limit - string.len().min(limit);
+                        while !content.is_char_boundary(end) {
+                            end -= 1;
+                        }
+                        string.push_str(&content[..end]);
+                    }
+                },
+                _ => string.push_str(&content),
+            },
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != tagname {
                     return Err(GpxError::InvalidClosingTag(
@@ -43,9 +82,28 @@ pub fn consume<R: Read>(
     Err(GpxError::MissingClosingTag(tagname))
 }
 
+/// Whether `error` is the one xml-rs gives for text longer than its
+/// `max_data_length`.
+///
+/// xml-rs gives no kind of its own for that, only a syntax error with a
+/// message, so the error is compared to one it made for a tiny document
+/// over a tiny limit, rather than to the wording of this version.
+fn is_limit_error(error: &xml::reader::Error) -> bool {
+    let message = match error.kind() {
+        ErrorKind::Syntax(message) => message,
+        _ => return false,
+    };
+    let mut config = ParserConfig2::new();
+    config.max_data_length = 1;
+    EventReader::new_with_config("<a>text</a>".as_bytes(), config)
+        .into_iter()
+        .find_map(Result::err)
+        .is_some_and(|limit| matches!(limit.kind(), ErrorKind::Syntax(limit) if limit == message))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::consume;
+    use super::{consume, is_limit_error};
     use crate::GpxVersion;
 
     #[test]
@@ -61,6 +119,33 @@ mod tests {
         assert_eq!(result.unwrap(), "hello world");
     }
 
+    #[test]
+    fn consume_entities_and_character_references() {
+        let result = consume!(
+            "<name>Fish &amp; Chips &#x1F600; &#38;<!-- not here --> &lt;3</name>",
+            GpxVersion::Gpx11,
+            "name",
+            false
+        );
+        assert_eq!(result.unwrap(), "Fish & Chips \u{1F600} & <3");
+
+        let cdata = consume!(
+            "<desc>a <![CDATA[<b> & ]]>c</desc>",
+            GpxVersion::Gpx11,
+            "desc",
+            false
+        );
+        assert_eq!(cdata.unwrap(), "a <b> & c");
+    }
+
+    #[test]
+    fn consume_long_string_with_entities() {
+        let piece = "Fish &amp; Chips &#x1F600; ";
+        let xml = format!("<desc>{}</desc>", piece.repeat(10_000));
+        let result = consume!(xml.as_str(), GpxVersion::Gpx11, "desc", false);
+        assert_eq!(result.unwrap(), "Fish & Chips \u{1F600} ".repeat(10_000));
+    }
+
     #[test]
     fn consume_new_tag() {
         // cannot start new tag inside string
@@ -114,4 +199,21 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn parser_limit_is_recognized() {
+        let mut config = xml::reader::ParserConfig2::new();
+        config.max_data_length = 8;
+        let error = xml::EventReader::new_with_config("<a>far too long</a>".as_bytes(), config)
+            .into_iter()
+            .find_map(Result::err)
+            .unwrap();
+        assert!(is_limit_error(&error));
+
+        let syntax = xml::EventReader::new("<a></b>".as_bytes())
+            .into_iter()
+            .find_map(Result::err)
+            .unwrap();
+        assert!(!is_limit_error(&syntax));
+    }
 }
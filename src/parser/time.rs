@@ -7,8 +7,12 @@ use std::io::Read;
 use serde::{Deserialize, Serialize};
 use time::{format_description::well_known::Iso8601, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
-use crate::errors::GpxResult;
+#[cfg(feature = "chrono")]
+use std::convert::TryFrom;
+
+use crate::errors::{GpxError, GpxResult};
 use crate::parser::{string, Context};
+use crate::LeapSecondPolicy;
 
 #[derive(Debug, Clone, Copy, Eq, Ord, PartialOrd, PartialEq, Hash)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
@@ -19,6 +23,112 @@ impl Time {
     pub fn format(&self) -> GpxResult<String> {
         self.0.format(&Iso8601::DEFAULT).map_err(From::from)
     }
+
+    /// Renders the time as an xsd:dateTime, with the fractional seconds and
+    /// offset as chosen by `format`.
+    ///
+    /// ```
+    /// use gpx::{FractionDigits, OffsetStyle, Time, TimeFormat};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let time: Time = (OffsetDateTime::from_unix_timestamp(1_622_548_800).unwrap()
+    ///     + Duration::microseconds(123_456))
+    /// .into();
+    /// let format = TimeFormat {
+    ///     fraction_digits: FractionDigits::Exactly(3),
+    ///     offset: OffsetStyle::Utc,
+    /// };
+    /// assert_eq!(time.format_with(&format), "2021-06-01T12:00:00.123Z");
+    /// ```
+    pub fn format_with(&self, format: &TimeFormat) -> String {
+        let time = match format.offset {
+            // xsd:dateTime has no seconds in offsets.
+            OffsetStyle::Preserve if self.0.offset().seconds_past_minute() == 0 => self.0,
+            _ => self.0.to_offset(UtcOffset::UTC),
+        };
+        let year = time.year();
+        let mut formatted = format!(
+            "{}{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            if year < 0 { "-" } else { "" },
+            year.abs(),
+            u8::from(time.month()),
+            time.day(),
+            time.hour(),
+            time.minute(),
+            time.second()
+        );
+
+        let nanoseconds = time.nanosecond();
+        let digits = match format.fraction_digits {
+            FractionDigits::None => 0,
+            FractionDigits::Exactly(digits) => usize::from(digits.min(9)),
+            FractionDigits::Trimmed => {
+                let fraction = format!("{:09}", nanoseconds);
+                fraction.trim_end_matches('0').len()
+            }
+        };
+        if digits > 0 {
+            let fraction = format!("{:09}", nanoseconds);
+            formatted.push('.');
+            formatted.push_str(&fraction[..digits]);
+        }
+
+        let offset = time.offset();
+        if offset.is_utc() {
+            formatted.push('Z');
+        } else {
+            let (hours, minutes, _) = offset.as_hms();
+            let sign = if offset.is_negative() { '-' } else { '+' };
+            formatted.push_str(&format!("{}{:02}:{:02}", sign, hours.abs(), minutes.abs()));
+        }
+        formatted
+    }
+}
+
+/// How times are rendered by [`Time::format_with`] and by the writer, see
+/// [`WriteOptions::time_format`](crate::WriteOptions::time_format).
+///
+/// The default writes nine fractional digits in UTC, as the writer always
+/// did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimeFormat {
+    /// How many digits of fractional seconds to write.
+    pub fraction_digits: FractionDigits,
+
+    /// Which offset to write the time in.
+    pub offset: OffsetStyle,
+}
+
+impl Default for TimeFormat {
+    fn default() -> TimeFormat {
+        TimeFormat {
+            fraction_digits: FractionDigits::Exactly(9),
+            offset: OffsetStyle::Utc,
+        }
+    }
+}
+
+/// The fractional seconds of a [`TimeFormat`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FractionDigits {
+    /// No fractional seconds. They are cut off, not rounded.
+    None,
+    /// Exactly this many digits, up to nine, cut off or padded with zeros.
+    Exactly(u8),
+    /// As many digits as the stored time needs, without trailing zeros, and
+    /// none for whole seconds.
+    Trimmed,
+}
+
+/// The offset of a [`TimeFormat`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OffsetStyle {
+    /// Converts the time to UTC, written as `Z`.
+    Utc,
+    /// Keeps the offset the time was read or created with, such as
+    /// `+02:00`. Offsets with seconds, which xsd:dateTime can't express,
+    /// are converted to UTC.
+    Preserve,
 }
 
 impl From<OffsetDateTime> for Time {
@@ -33,24 +143,104 @@ impl From<Time> for OffsetDateTime {
     }
 }
 
+/// Converts to chrono without losing precision.
+#[cfg(feature = "chrono")]
+impl From<Time> for chrono::DateTime<chrono::Utc> {
+    fn from(t: Time) -> Self {
+        use chrono::TimeZone;
+
+        chrono::Utc
+            .timestamp_opt(t.0.unix_timestamp(), t.0.nanosecond())
+            .single()
+            .expect("every time fits into chrono's range")
+    }
+}
+
+/// Converts from chrono without losing precision. Fails for times outside of
+/// the years -9999 to 9999. A leap second turns into the first second of the
+/// next minute, as the `time` crate has no leap seconds.
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for Time {
+    type Error = GpxError;
+
+    fn try_from(t: chrono::DateTime<chrono::Utc>) -> Result<Self, Self::Error> {
+        let seconds = OffsetDateTime::from_unix_timestamp(t.timestamp())?;
+        Ok(Time(
+            seconds + time::Duration::nanoseconds(t.timestamp_subsec_nanos().into()),
+        ))
+    }
+}
+
 /// consume consumes an element as a time.
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Time> {
     let time_str = string::consume(context, "time", false)?;
+    parse_with_leap_seconds(time_str.trim(), context.options.leap_seconds)
+}
+
+/// parse parses an xsd:dateTime, in UTC unless it has an offset, which is
+/// kept. Fractional seconds are kept down to nanoseconds. Leap seconds are
+/// rejected.
+///
+/// Neither the locale nor the `TZ` environment variable of the process make
+/// a difference.
+pub(crate) fn parse(time_str: &str) -> GpxResult<Time> {
+    parse_with_leap_seconds(time_str, LeapSecondPolicy::Reject)
+}
+
+/// Like [`parse`], with leap seconds handled as `policy` says.
+fn parse_with_leap_seconds(time_str: &str, policy: LeapSecondPolicy) -> GpxResult<Time> {
+    if let Some((start, end)) = leap_second(time_str) {
+        return match policy {
+            LeapSecondPolicy::Reject => Err(GpxError::LeapSecondTimestamp(time_str.to_owned())),
+            LeapSecondPolicy::Clamp => parse_time(&format!(
+                "{}59.999999{}",
+                &time_str[..start],
+                &time_str[end..]
+            )),
+        };
+    }
+    parse_time(time_str)
+}
 
+fn parse_time(time_str: &str) -> GpxResult<Time> {
     // Try parsing as ISO 8601 with offset
-    let time = OffsetDateTime::parse(&time_str, &Iso8601::PARSING).or_else(|_| {
+    let time = OffsetDateTime::parse(time_str, &Iso8601::PARSING).or_else(|_| {
         // Try parsing as ISO 8601 without offset, assuming UTC
-        PrimitiveDateTime::parse(&time_str, &Iso8601::PARSING).map(PrimitiveDateTime::assume_utc)
+        PrimitiveDateTime::parse(time_str, &Iso8601::PARSING).map(PrimitiveDateTime::assume_utc)
     })?;
 
-    Ok(time.to_offset(UtcOffset::UTC).into())
+    Ok(time.into())
+}
+
+/// Finds the seconds of a time with a second of 60, giving where they start
+/// and where they end, with their fraction.
+///
+/// The `time` crate reads `23:59:60Z` as the last nanosecond of the day,
+/// and fails for other times, so leap seconds are taken care of first.
+fn leap_second(time_str: &str) -> Option<(usize, usize)> {
+    let clock = time_str.find('T')? + 1;
+    let start = clock + 6;
+    let separated = time_str.get(clock + 2..clock + 3) == Some(":")
+        && time_str.get(clock + 5..start) == Some(":");
+    if !separated || time_str.get(start..start + 2) != Some("60") {
+        return None;
+    }
+    let rest = &time_str[start + 2..];
+    let fraction = match rest.strip_prefix(|c| c == '.' || c == ',') {
+        Some(digits) => 1 + digits.bytes().take_while(u8::is_ascii_digit).count(),
+        None => 0,
+    };
+    Some((start, start + 2 + fraction))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::GpxVersion;
+    use time::OffsetDateTime;
 
-    use super::consume;
+    use crate::errors::GpxError;
+    use crate::{GpxVersion, LeapSecondPolicy};
+
+    use super::{consume, parse, parse_with_leap_seconds, FractionDigits, OffsetStyle, TimeFormat};
 
     #[test]
     fn consume_time() {
@@ -95,4 +285,131 @@ mod tests {
         let result = consume!("<time>2021-10-10T09:55:20.952</time>", GpxVersion::Gpx11);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn consume_time_around_comments() {
+        let result = consume!(
+            "<time>\n  <!-- UTC -->1996-12-19T16:39:57Z<?end?>\n</time>",
+            GpxVersion::Gpx11
+        );
+        assert_eq!(result.unwrap(), parse("1996-12-19T16:39:57Z").unwrap());
+    }
+
+    #[test]
+    fn format_with_precision_and_offset() {
+        let time = parse("2021-06-01T14:00:00.123456+02:00").unwrap();
+        let format = |fraction_digits, offset| {
+            time.format_with(&TimeFormat {
+                fraction_digits,
+                offset,
+            })
+        };
+        assert_eq!(
+            format(FractionDigits::Exactly(3), OffsetStyle::Utc),
+            "2021-06-01T12:00:00.123Z"
+        );
+        assert_eq!(
+            format(FractionDigits::None, OffsetStyle::Utc),
+            "2021-06-01T12:00:00Z"
+        );
+        assert_eq!(
+            format(FractionDigits::Trimmed, OffsetStyle::Preserve),
+            "2021-06-01T14:00:00.123456+02:00"
+        );
+        assert_eq!(
+            format(FractionDigits::Exactly(12), OffsetStyle::Utc),
+            "2021-06-01T12:00:00.123456000Z"
+        );
+        let negative = parse("2021-06-01T08:30:00-03:30").unwrap();
+        let preserve = TimeFormat {
+            fraction_digits: FractionDigits::Trimmed,
+            offset: OffsetStyle::Preserve,
+        };
+        assert_eq!(negative.format_with(&preserve), "2021-06-01T08:30:00-03:30");
+
+        // The default matches the ISO 8601 rendering.
+        let utc = parse("2021-06-01T12:00:00.987654321Z").unwrap();
+        assert_eq!(
+            utc.format_with(&TimeFormat::default()),
+            utc.format().unwrap()
+        );
+        assert_eq!(
+            time.format_with(&TimeFormat::default()),
+            "2021-06-01T12:00:00.123456000Z"
+        );
+    }
+
+    #[test]
+    fn parse_keeps_full_precision() {
+        let time = parse("2021-06-01T12:00:00.123456789Z").unwrap();
+        assert_eq!(time::OffsetDateTime::from(time).nanosecond(), 123_456_789);
+        let time = parse("2021-06-01T12:00:00.5+01:00").unwrap();
+        assert_eq!(time::OffsetDateTime::from(time).nanosecond(), 500_000_000);
+        assert_eq!(
+            time,
+            parse("2021-06-01T11:00:00.500Z").unwrap(),
+            "times compare by instant"
+        );
+    }
+
+    #[test]
+    fn leap_seconds_by_policy() {
+        for leap in &[
+            "2016-12-31T23:59:60Z",
+            "2016-12-31T23:59:60.25Z",
+            "2017-01-01T05:29:60+05:30",
+        ] {
+            assert!(matches!(
+                parse(leap),
+                Err(GpxError::LeapSecondTimestamp(time)) if time == *leap
+            ));
+        }
+
+        let clamp = |time_str| {
+            OffsetDateTime::from(
+                parse_with_leap_seconds(time_str, LeapSecondPolicy::Clamp).unwrap(),
+            )
+        };
+        let clamped = clamp("2016-12-31T23:59:60.25Z");
+        assert_eq!(clamped, clamp("2016-12-31T23:59:60Z"));
+        assert_eq!(
+            (clamped.hour(), clamped.minute(), clamped.second()),
+            (23, 59, 59)
+        );
+        assert_eq!(clamped.microsecond(), 999_999);
+        let local = clamp("2017-01-01T05:29:60+05:30");
+        assert_eq!(local, clamped);
+        assert_eq!(local.offset().as_hms(), (5, 30, 0));
+
+        // Neither the minutes nor seconds of an offset are leap seconds.
+        assert!(parse("2016-12-31T23:60:00Z").is_err());
+        assert!(parse("2016-12-31T23:59:59+05:60").is_err());
+        assert!(parse_with_leap_seconds("2016-12-31T23:59:59.5Z", LeapSecondPolicy::Clamp).is_ok());
+    }
+
+    #[test]
+    fn parse_ignores_tz_environment() {
+        // Setting `TZ` in here would race with the other tests, so the test
+        // runs again in a child process that has it set.
+        if std::env::var_os("GPX_TEST_TZ_CHILD").is_none() {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .arg("--exact")
+                .arg("parser::time::tests::parse_ignores_tz_environment")
+                .env("GPX_TEST_TZ_CHILD", "1")
+                .env("TZ", "Pacific/Chatham")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(output.status.success(), "{}", stdout);
+            assert!(stdout.contains("1 passed"), "{}", stdout);
+            return;
+        }
+        let time = OffsetDateTime::from(parse("2021-06-01T17:30:00+05:30").unwrap());
+        assert_eq!(time.offset().as_hms(), (5, 30, 0));
+        assert_eq!(time.hour(), 17);
+        assert_eq!(time.unix_timestamp(), 1_622_548_800);
+        let time = OffsetDateTime::from(parse("2021-06-01T12:00:00").unwrap());
+        assert!(time.offset().is_utc());
+        assert_eq!(time.unix_timestamp(), 1_622_548_800);
+    }
 }
@@ -5,13 +5,44 @@ use std::io::Read;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{extensions, link, string, tracksegment, verify_starting_tag, Context};
-use crate::Track;
+use crate::parser::link::Gpx10Url;
+use crate::parser::{
+    consume_salvageable, extensions, link, number, order, stash_child_attributes,
+    stash_unknown_attributes, string, tracksegment, verify_starting_tag, Context,
+};
+use crate::positions::Positioned;
+use crate::{GpxVersion, Track};
 
 /// consume consumes a GPX track from the `reader` until it ends.
+#[cfg(test)]
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Track> {
     let mut track: Track = Default::default();
-    verify_starting_tag(context, "trk")?;
+    consume_into(context, &mut track)?;
+    Ok(track)
+}
+
+/// consume_into consumes a GPX track into `track`. On errors, `track` holds what was
+/// parsed up to that point.
+pub(crate) fn consume_into<R: Read>(context: &mut Context<R>, track: &mut Track) -> GpxResult<()> {
+    consume_parts(context, track, false).map(|_| ())
+}
+
+/// consume_parts consumes a GPX track into `track` like [`consume_into`]. With
+/// `header_only`, it stops before the first segment and returns true, leaving
+/// the segments to the caller.
+pub(crate) fn consume_parts<R: Read>(
+    context: &mut Context<R>,
+    track: &mut Track,
+    header_only: bool,
+) -> GpxResult<bool> {
+    let attributes = verify_starting_tag(context, "trk")?;
+    if context.options.preserve_unknown_attributes {
+        stash_unknown_attributes(&mut track.unknown_attributes, "", &attributes, &[]);
+    }
+    let mut order = context.element_order("track", order::TRACK);
+
+    let mut url = Gpx10Url::default();
+    let allows_gpx10 = context.allows(GpxVersion::Gpx10);
 
     loop {
         let next_event = {
@@ -26,44 +57,69 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<Track> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "name" => {
-                    track.name = Some(string::consume(context, "name", true)?);
-                }
-                "cmt" => {
-                    track.comment = Some(string::consume(context, "cmt", true)?);
-                }
-                "desc" => {
-                    track.description = Some(string::consume(context, "desc", true)?);
+            XmlEvent::StartElement {
+                ref name,
+                ref attributes,
+                ..
+            } => {
+                order.check(&name.local_name)?;
+                stash_child_attributes(
+                    &context.options,
+                    &mut track.unknown_attributes,
+                    &name.local_name,
+                    attributes,
+                );
+                match name.local_name.as_ref() {
+                    "trkseg" if header_only => {
+                        track.links.extend(std::mem::take(&mut url).into_link());
+                        return Ok(true);
+                    }
+                    "name" => {
+                        track.name = Some(string::consume(context, "name", true)?);
+                    }
+                    "cmt" => {
+                        track.comment = Some(string::consume(context, "cmt", true)?);
+                    }
+                    "desc" => {
+                        track.description = Some(string::consume(context, "desc", true)?);
+                    }
+                    "src" => {
+                        track.source = Some(string::consume(context, "src", true)?);
+                    }
+                    "type" => {
+                        track._type = Some(string::consume(context, "type", false)?);
+                    }
+                    "trkseg" => {
+                        context.record_position(Positioned::TrackSegment, track.segments.len());
+                        let (result, segment) =
+                            consume_salvageable(context, tracksegment::consume_into);
+                        track.segments.extend(segment);
+                        result?;
+                    }
+                    "link" => {
+                        track.links.push(link::consume(context)?);
+                    }
+                    "url" if allows_gpx10 => url.consume(context, "url")?,
+                    "urlname" if allows_gpx10 => url.consume(context, "urlname")?,
+                    "number" => track.number = Some(number::consume_integer(context, "number")?),
+                    "extensions" => {
+                        track.extensions = Some(extensions::consume(context)?);
+                    }
+                    child => {
+                        return Err(GpxError::InvalidChildElement(String::from(child), "track"));
+                    }
                 }
-                "src" => {
-                    track.source = Some(string::consume(context, "src", true)?);
-                }
-                "type" => {
-                    track._type = Some(string::consume(context, "type", false)?);
-                }
-                "trkseg" => {
-                    track.segments.push(tracksegment::consume(context)?);
-                }
-                "link" => {
-                    track.links.push(link::consume(context)?);
-                }
-                "number" => {
-                    track.number = Some(string::consume(context, "number", false)?.parse()?)
-                }
-                "extensions" => {
-                    extensions::consume(context)?;
-                }
-                child => {
-                    return Err(GpxError::InvalidChildElement(String::from(child), "track"));
-                }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != "trk" {
-                    return Err(GpxError::InvalidClosingTag(name.local_name.clone(), "track"));
+                    return Err(GpxError::InvalidClosingTag(
+                        name.local_name.clone(),
+                        "track",
+                    ));
                 }
                 context.reader.next(); //consume the end tag
-                return Ok(track);
+                track.links.extend(url.into_link());
+                return Ok(false);
             }
             _ => {
                 context.reader.next(); //consume and ignore this event
@@ -5,13 +5,26 @@ use std::io::Read;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{verify_starting_tag, waypoint, Context};
+use crate::parser::{extensions, order, verify_starting_tag, waypoint, Context};
+use crate::positions::Positioned;
 use crate::TrackSegment;
 
 /// consume consumes a GPX track segment from the `reader` until it ends.
+#[cfg(test)]
 pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<TrackSegment> {
     let mut segment: TrackSegment = Default::default();
+    consume_into(context, &mut segment)?;
+    Ok(segment)
+}
+
+/// consume_into consumes a GPX track segment into `segment`. On errors, `segment` holds what was
+/// parsed up to that point.
+pub(crate) fn consume_into<R: Read>(
+    context: &mut Context<R>,
+    segment: &mut TrackSegment,
+) -> GpxResult<()> {
     verify_starting_tag(context, "trkseg")?;
+    let mut order = context.element_order("tracksegment", order::TRACKSEGMENT);
 
     loop {
         let next_event = {
@@ -26,15 +39,22 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<TrackSegment> {
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => match name.local_name.as_ref() {
-                "trkpt" => segment.points.push(waypoint::consume(context, "trkpt")?),
-                child => {
-                    return Err(GpxError::InvalidChildElement(
-                        String::from(child),
-                        "tracksegment",
-                    ));
+            XmlEvent::StartElement { ref name, .. } => {
+                order.check(&name.local_name)?;
+                match name.local_name.as_ref() {
+                    "trkpt" => {
+                        context.record_position(Positioned::TrackPoint, segment.points.len());
+                        segment.points.push(waypoint::consume(context, "trkpt")?);
+                    }
+                    "extensions" => segment.extensions = Some(extensions::consume(context)?),
+                    child => {
+                        return Err(GpxError::InvalidChildElement(
+                            String::from(child),
+                            "tracksegment",
+                        ));
+                    }
                 }
-            },
+            }
             XmlEvent::EndElement { ref name } => {
                 if name.local_name != "trkseg" {
                     return Err(GpxError::InvalidClosingTag(
@@ -43,7 +63,7 @@ pub fn consume<R: Read>(context: &mut Context<R>) -> GpxResult<TrackSegment> {
                     ));
                 }
                 context.reader.next(); //consume the end tag
-                return Ok(segment);
+                return Ok(());
             }
             _ => {
                 context.reader.next(); //consume and ignore this event
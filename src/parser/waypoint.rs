@@ -2,16 +2,20 @@
 
 use std::io::Read;
 
-use geo_types::Point;
 use xml::reader::XmlEvent;
 
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::{extensions, fix, link, string, time, verify_starting_tag, Context};
+use crate::parser::link::Gpx10Url;
+use crate::parser::{
+    extensions, fix, link, number, order, stash_child_attributes, stash_unknown_attributes, string,
+    time, verify_starting_tag, Context,
+};
 use crate::{GpxVersion, Waypoint};
 
 /// consume consumes a GPX waypoint from the `reader` until it ends.
 pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxResult<Waypoint> {
     let attributes = verify_starting_tag(context, tagname)?;
+    let mut order = context.element_order("waypoint", order::WAYPOINT);
 
     // get required latitude and longitude
     let latitude = attributes
@@ -21,15 +25,7 @@ pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxR
             "latitude", "waypoint",
         ))?;
 
-    let latitude: f64 = latitude.value.parse()?;
-
-    if !(-90.0..=90.0).contains(&latitude) {
-        return Err(GpxError::LonLatOutOfBoundsError(
-            "latitude",
-            "[-90.0, 90.0]",
-            latitude,
-        ));
-    };
+    let latitude = number::parse(&latitude.value, "lat")?;
 
     let longitude = attributes
         .iter()
@@ -39,18 +35,18 @@ pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxR
             "waypoint",
         ))?;
 
-    let longitude: f64 = longitude.value.parse()?;
+    let longitude = number::parse(&longitude.value, "lon")?;
 
-    if !(-180.0..180.0).contains(&longitude) {
-        return Err(GpxError::LonLatOutOfBoundsError(
-            "Longitude",
-            "[-180.0, 180.0",
-            longitude,
-        ));
-    };
-
-    let mut waypoint: Waypoint = Waypoint::new(Point::new(longitude, latitude));
+    let mut waypoint = Waypoint::new_lat_lon(latitude, longitude)?;
+    // Kept apart until the end, so that the extras are only allocated for
+    // points that have any.
+    let mut unknown_attributes = Vec::new();
+    if context.options.preserve_unknown_attributes {
+        stash_unknown_attributes(&mut unknown_attributes, "", &attributes, &["lat", "lon"]);
+    }
 
+    let mut url = Gpx10Url::default();
+    let allows_gpx10 = context.allows(GpxVersion::Gpx10);
     loop {
         let next_event = {
             if let Some(next) = context.reader.peek() {
@@ -64,55 +60,78 @@ pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxR
         };
 
         match next_event {
-            XmlEvent::StartElement { ref name, .. } => {
+            XmlEvent::StartElement {
+                ref name,
+                ref attributes,
+                ..
+            } => {
+                order.check(&name.local_name)?;
+                stash_child_attributes(
+                    &context.options,
+                    &mut unknown_attributes,
+                    &name.local_name,
+                    attributes,
+                );
                 match name.local_name.as_ref() {
                     "ele" => {
                         // Cast the elevation to an f64, from a string.
                         waypoint.elevation = match string::consume(context, "ele", false) {
-                            Ok(v) => Some(v.parse()?),
+                            Ok(v) => Some(number::parse(&v, "ele")?),
                             Err(GpxError::NoStringContent) => None,
                             Err(other_err) => return Err(other_err),
                         }
                     }
-                    "speed" if context.version == GpxVersion::Gpx10 => {
+                    "speed" if allows_gpx10 => {
                         // Speed is from GPX 1.0
-                        waypoint.speed = Some(string::consume(context, "speed", false)?.parse()?);
+                        waypoint.extras_mut().speed = Some(number::consume(context, "speed")?);
                     }
                     "time" => waypoint.time = Some(time::consume(context)?),
                     "name" => waypoint.name = Some(string::consume(context, "name", true)?),
-                    "cmt" => waypoint.comment = Some(string::consume(context, "cmt", true)?),
-                    "desc" => waypoint.description = Some(string::consume(context, "desc", true)?),
-                    "src" => waypoint.source = Some(string::consume(context, "src", true)?),
-                    "link" => waypoint.links.push(link::consume(context)?),
-                    "sym" => waypoint.symbol = Some(string::consume(context, "sym", false)?),
-                    "type" => waypoint._type = Some(string::consume(context, "type", false)?),
+                    "cmt" => {
+                        waypoint.extras_mut().comment = Some(string::consume(context, "cmt", true)?)
+                    }
+                    "desc" => {
+                        waypoint.extras_mut().description =
+                            Some(string::consume(context, "desc", true)?)
+                    }
+                    "src" => {
+                        waypoint.extras_mut().source = Some(string::consume(context, "src", true)?)
+                    }
+                    "link" => waypoint.extras_mut().links.push(link::consume(context)?),
+                    "url" if allows_gpx10 => url.consume(context, "url")?,
+                    "urlname" if allows_gpx10 => url.consume(context, "urlname")?,
+                    "sym" => {
+                        waypoint.extras_mut().symbol = Some(string::consume(context, "sym", false)?)
+                    }
+                    "type" => {
+                        waypoint.extras_mut()._type = Some(string::consume(context, "type", false)?)
+                    }
 
                     // Optional accuracy information
-                    "fix" => waypoint.fix = Some(fix::consume(context)?),
+                    "fix" => waypoint.extras_mut().fix = Some(fix::consume(context)?),
                     "geoidheight" => {
-                        waypoint.geoidheight =
-                            Some(string::consume(context, "geoidheight", false)?.parse()?)
-                    }
-                    "sat" => waypoint.sat = Some(string::consume(context, "sat", false)?.parse()?),
-                    "hdop" => {
-                        waypoint.hdop = Some(string::consume(context, "hdop", false)?.parse()?)
-                    }
-                    "vdop" => {
-                        waypoint.vdop = Some(string::consume(context, "vdop", false)?.parse()?)
+                        waypoint.extras_mut().geoidheight =
+                            Some(number::consume(context, "geoidheight")?)
                     }
-                    "pdop" => {
-                        waypoint.pdop = Some(string::consume(context, "pdop", false)?.parse()?)
+                    "sat" => {
+                        waypoint.extras_mut().sat = Some(number::consume_integer(context, "sat")?)
                     }
+                    "hdop" => waypoint.extras_mut().hdop = Some(number::consume(context, "hdop")?),
+                    "vdop" => waypoint.extras_mut().vdop = Some(number::consume(context, "vdop")?),
+                    "pdop" => waypoint.extras_mut().pdop = Some(number::consume(context, "pdop")?),
                     "ageofdgpsdata" => {
-                        waypoint.dgps_age =
-                            Some(string::consume(context, "ageofdgpsdata", false)?.parse()?)
+                        waypoint.extras_mut().dgps_age =
+                            Some(number::consume(context, "ageofdgpsdata")?)
                     }
                     "dgpsid" => {
-                        waypoint.dgpsid = Some(string::consume(context, "dgpsid", false)?.parse()?)
+                        waypoint.extras_mut().dgpsid =
+                            Some(number::consume_integer(context, "dgpsid")?)
                     }
 
                     // Finally the GPX 1.1 extensions
-                    "extensions" => extensions::consume(context)?,
+                    "extensions" => {
+                        waypoint.extras_mut().extensions = Some(extensions::consume(context)?)
+                    }
                     child => {
                         return Err(GpxError::InvalidChildElement(
                             String::from(child),
@@ -129,6 +148,12 @@ pub fn consume<R: Read>(context: &mut Context<R>, tagname: &'static str) -> GpxR
                     ));
                 }
                 context.reader.next(); //consume the end tag
+                if let Some(link) = url.into_link() {
+                    waypoint.extras_mut().links.push(link);
+                }
+                if !unknown_attributes.is_empty() {
+                    waypoint.extras_mut().unknown_attributes = unknown_attributes;
+                }
                 return Ok(waypoint);
             }
             _ => {
@@ -145,6 +170,7 @@ mod tests {
     use geo_types::Point;
 
     use super::consume;
+    use crate::errors::GpxError;
     use crate::{Fix, GpxVersion};
 
     #[test]
@@ -173,21 +199,24 @@ mod tests {
         let waypoint = waypoint.unwrap();
 
         assert_eq!(waypoint.point(), Point::new(-77.0365, 38.8977));
-        assert_eq!(waypoint.name.unwrap(), "The White House");
+        assert_eq!(waypoint.name.as_deref().unwrap(), "The White House");
         assert_eq!(
-            waypoint.comment.unwrap(),
+            waypoint.extras().comment.as_deref().unwrap(),
             "This is a comment about the white house."
         );
         assert_eq!(
-            waypoint.description.unwrap(),
+            waypoint.extras().description.as_deref().unwrap(),
             "The white house is very nice!"
         );
-        assert_eq!(waypoint.source.unwrap(), "Garmin eTrex");
-        assert_eq!(waypoint._type.unwrap(), "waypoint classification");
+        assert_eq!(waypoint.extras().source.as_deref().unwrap(), "Garmin eTrex");
+        assert_eq!(
+            waypoint.extras()._type.as_deref().unwrap(),
+            "waypoint classification"
+        );
         assert_eq!(waypoint.elevation.unwrap(), 4608.12);
-        assert_eq!(waypoint.fix.unwrap(), Fix::DGPS);
-        assert_eq!(waypoint.sat.unwrap(), 4);
-        assert_eq!(waypoint.hdop.unwrap(), 6.058);
+        assert_eq!(waypoint.extras().fix.clone().unwrap(), Fix::DGPS);
+        assert_eq!(waypoint.extras().sat.unwrap(), 4);
+        assert_eq!(waypoint.extras().hdop.unwrap(), 6.058);
     }
 
     #[test]
@@ -278,4 +307,47 @@ mod tests {
 
         assert!(waypoint.is_err());
     }
+
+    #[test]
+    fn consume_non_finite_numbers() {
+        let fields = [
+            ("lat", "<wpt lat=\"{}\" lon=\"1.0\"></wpt>"),
+            ("ele", "<wpt lat=\"1.0\" lon=\"1.0\"><ele>{}</ele></wpt>"),
+            ("hdop", "<wpt lat=\"1.0\" lon=\"1.0\"><hdop>{}</hdop></wpt>"),
+        ];
+        for &(field, template) in &fields {
+            for &value in &["NaN", "Infinity", "-Infinity"] {
+                let xml = template.replace("{}", value);
+                match consume!(xml, GpxVersion::Gpx11, "wpt") {
+                    Err(GpxError::InvalidNumericValue(name, _)) => assert_eq!(name, field),
+                    other => panic!("{} parsed as {:?}", xml, other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn consume_exponent_numbers() {
+        let waypoint = consume!(
+            "<wpt lat=\"1e1\" lon=\"1e2\"><ele>1e2</ele><hdop>+2.5E-1</hdop></wpt>",
+            GpxVersion::Gpx11,
+            "wpt"
+        )
+        .unwrap();
+
+        assert_eq!(waypoint.point(), Point::new(100.0, 10.0));
+        assert_eq!(waypoint.elevation, Some(100.0));
+        assert_eq!(waypoint.extras().hdop, Some(0.25));
+
+        // 1e2 is a fine number, but not a latitude.
+        let result = consume!(
+            "<wpt lat=\"1e2\" lon=\"1.0\"></wpt>",
+            GpxVersion::Gpx11,
+            "wpt"
+        );
+        assert!(matches!(
+            result,
+            Err(GpxError::LonLatOutOfBoundsError("latitude", _, _))
+        ));
+    }
 }
@@ -0,0 +1,259 @@
+//! parts takes documents apart and puts them back together, moving the
+//! points instead of copying them.
+//!
+//! All fields of the types are public, so destructuring them works as well;
+//! these functions are shorthands for the common cases. The helpers that
+//! change points, like [`TrackSegment::reverse`] or
+//! [`TrackSegment::remove_spikes`], work in place and need no consuming
+//! variants.
+//!
+//! ```
+//! use gpx::{Gpx, Track, TrackSegment};
+//!
+//! let gpx = gpx::read(&include_bytes!("../tests/fixtures/wikipedia_example.gpx")[..]).unwrap();
+//! let (metadata, waypoints, routes, tracks, version, creator) = gpx.into_parts();
+//!
+//! // Join all segments of all tracks into one, without copying the points.
+//! let points = tracks
+//!     .into_iter()
+//!     .flat_map(Track::into_segments)
+//!     .flat_map(TrackSegment::into_points)
+//!     .collect();
+//! let track = Track::from_segments(vec![TrackSegment::from_points(points)]);
+//!
+//! let joined = Gpx::from_parts(metadata, waypoints, routes, vec![track], version, creator);
+//! assert_eq!(joined.tracks[0].segments[0].points.len(), 3);
+//! ```
+
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+
+use geo_types::Point;
+
+use crate::errors::GpxResult;
+use crate::{Gpx, GpxVersion, Metadata, Route, Track, TrackSegment, Waypoint};
+
+/// The parts of a document given by [`Gpx::into_parts`]: its metadata,
+/// waypoints, routes, tracks, version and creator.
+pub type GpxParts = (
+    Option<Metadata>,
+    Vec<Waypoint>,
+    Vec<Route>,
+    Vec<Track>,
+    GpxVersion,
+    Option<String>,
+);
+
+impl Gpx {
+    /// Takes the document apart into its metadata, waypoints, routes,
+    /// tracks, version and creator.
+    ///
+    /// The extensions, unknown elements and attributes and the prolog are
+    /// dropped. Destructure the `Gpx` instead to keep them.
+    pub fn into_parts(self) -> GpxParts {
+        (
+            self.metadata,
+            self.waypoints,
+            self.routes,
+            self.tracks,
+            self.version,
+            self.creator,
+        )
+    }
+
+    /// Puts a document together from the parts given by
+    /// [`Gpx::into_parts`].
+    pub fn from_parts(
+        metadata: Option<Metadata>,
+        waypoints: Vec<Waypoint>,
+        routes: Vec<Route>,
+        tracks: Vec<Track>,
+        version: GpxVersion,
+        creator: Option<String>,
+    ) -> Gpx {
+        Gpx {
+            version,
+            creator,
+            metadata,
+            waypoints,
+            tracks,
+            routes,
+            ..Default::default()
+        }
+    }
+}
+
+impl Track {
+    /// Creates a track with the given segments and nothing else.
+    pub fn from_segments(segments: Vec<TrackSegment>) -> Track {
+        Track {
+            segments,
+            ..Default::default()
+        }
+    }
+
+    /// Gives the segments of the track, dropping everything else.
+    pub fn into_segments(self) -> Vec<TrackSegment> {
+        self.segments
+    }
+}
+
+impl TrackSegment {
+    /// Creates a segment with the given points.
+    pub fn from_points(points: Vec<Waypoint>) -> TrackSegment {
+        TrackSegment {
+            points,
+            ..Default::default()
+        }
+    }
+
+    /// Gives the points of the segment, dropping its extensions.
+    pub fn into_points(self) -> Vec<Waypoint> {
+        self.points
+    }
+
+    /// Creates a segment with points at `points`, checking that they are in
+    /// range. **x is the longitude and y the latitude**, as everywhere in
+    /// `geo_types`.
+    ///
+    /// ```
+    /// use geo_types::Point;
+    /// use gpx::TrackSegment;
+    ///
+    /// let points = vec![Point::new(8.54, 47.37), Point::new(8.55, 47.38)];
+    /// let segment = TrackSegment::try_from_points(points).unwrap();
+    /// assert_eq!(segment.points[1].lat(), 47.38);
+    ///
+    /// assert!(TrackSegment::try_from_points(vec![Point::new(47.37, 181.0)]).is_err());
+    /// ```
+    pub fn try_from_points(
+        points: impl IntoIterator<Item = Point<f64>>,
+    ) -> GpxResult<TrackSegment> {
+        points
+            .into_iter()
+            .map(|point| Waypoint::try_from(point.0))
+            .collect()
+    }
+}
+
+impl FromIterator<Waypoint> for TrackSegment {
+    /// Collects the points into a segment without extensions. Collecting
+    /// into a `GpxResult<TrackSegment>` stops at the first error.
+    fn from_iter<I: IntoIterator<Item = Waypoint>>(points: I) -> TrackSegment {
+        TrackSegment::from_points(points.into_iter().collect())
+    }
+}
+
+impl Extend<Waypoint> for TrackSegment {
+    /// Appends the points to the end of the segment.
+    fn extend<I: IntoIterator<Item = Waypoint>>(&mut self, points: I) {
+        self.points.extend(points);
+    }
+}
+
+impl Route {
+    /// Creates a route with the given points and nothing else.
+    pub fn from_points(points: Vec<Waypoint>) -> Route {
+        Route {
+            points,
+            ..Default::default()
+        }
+    }
+
+    /// Gives the points of the route, dropping everything else.
+    pub fn into_points(self) -> Vec<Waypoint> {
+        self.points
+    }
+}
+
+impl From<Vec<Waypoint>> for TrackSegment {
+    fn from(points: Vec<Waypoint>) -> TrackSegment {
+        TrackSegment::from_points(points)
+    }
+}
+
+impl From<TrackSegment> for Vec<Waypoint> {
+    fn from(segment: TrackSegment) -> Vec<Waypoint> {
+        segment.into_points()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use crate::testutil::synthetic_gpx;
+    use crate::{Gpx, Track, TrackSegment, Waypoint};
+
+    #[test]
+    fn parts_round_trip() {
+        let original = synthetic_gpx(3, 50);
+        let (metadata, waypoints, routes, tracks, version, creator) = original.clone().into_parts();
+        let gpx = Gpx::from_parts(metadata, waypoints, routes, tracks, version, creator);
+        assert_eq!(gpx, original);
+    }
+
+    #[test]
+    fn points_are_moved() {
+        let gpx = synthetic_gpx(2, 100);
+        let buffers: Vec<*const Waypoint> = gpx
+            .tracks
+            .iter()
+            .map(|track| track.segments[0].points.as_ptr())
+            .collect();
+
+        let (metadata, waypoints, routes, tracks, version, creator) = gpx.into_parts();
+        let tracks: Vec<Track> = tracks
+            .into_iter()
+            .map(|track| {
+                let segments = track
+                    .into_segments()
+                    .into_iter()
+                    .map(|segment| {
+                        let mut points = segment.into_points();
+                        points.reverse();
+                        TrackSegment::from(points)
+                    })
+                    .collect();
+                Track::from_segments(segments)
+            })
+            .collect();
+        let gpx = Gpx::from_parts(metadata, waypoints, routes, tracks, version, creator);
+
+        for (track, buffer) in gpx.tracks.iter().zip(buffers) {
+            assert_eq!(track.segments[0].points.as_ptr(), buffer);
+        }
+        let mut expected = synthetic_gpx(2, 100);
+        for track in &mut expected.tracks {
+            track.name = None;
+            track.segments[0].points.reverse();
+        }
+        assert_eq!(gpx, expected);
+    }
+
+    #[test]
+    fn collect_and_extend_segments() {
+        let points: Vec<Waypoint> = synthetic_gpx(1, 10).tracks[0].segments[0].points.clone();
+        let segment: TrackSegment = points.iter().take(4).cloned().collect();
+        assert_eq!(segment.points, points[..4]);
+
+        let mut extended = segment.clone();
+        extended.extend(points[4..].iter().cloned());
+        assert_eq!(extended, TrackSegment::from(points.clone()));
+
+        let positions = points.iter().map(Point::from);
+        assert_eq!(
+            TrackSegment::try_from_points(positions.clone())
+                .unwrap()
+                .points
+                .len(),
+            10
+        );
+        let out_of_range = positions.chain(std::iter::once(Point::new(0.0, 91.0)));
+        assert!(TrackSegment::try_from_points(out_of_range).is_err());
+        assert_eq!(
+            TrackSegment::try_from_points(Vec::new()).unwrap(),
+            TrackSegment::new()
+        );
+    }
+}
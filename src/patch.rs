@@ -0,0 +1,456 @@
+//! patch edits a document in place, for editors that want to save a small
+//! change without writing the whole file anew.
+//!
+//! Writing a [`Gpx`](crate::Gpx) back out loses the comments, attribute
+//! order and layout of the file it was read from. The functions here take
+//! the document as text instead, find the element to change in it and
+//! replace only the bytes of that element, leaving every other byte as it
+//! was.
+//!
+//! ```
+//! use gpx::patch::{rename_waypoint, Matcher};
+//!
+//! let input = r#"<gpx version="1.1" creator="editor">
+//!   <!-- Keep this. -->
+//!   <wpt lon="8.5"   lat="47.3"><name>Hut</name></wpt>
+//! </gpx>"#;
+//! let output = rename_waypoint(input, Matcher::Name("Hut".into()), "Lake hut").unwrap();
+//! assert_eq!(output, input.replace(">Hut<", ">Lake hut<"));
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::errors::{GpxError, GpxResult};
+use crate::{read, GpxVersion, Time};
+
+/// Which of the waypoints or tracks of a document to patch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Matcher {
+    /// The first one with this name.
+    Name(String),
+    /// The one at this index, counting from 0 in document order.
+    Index(usize),
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Matcher::Name(name) => write!(f, "name `{}`", name),
+            Matcher::Index(index) => write!(f, "index {}", index),
+        }
+    }
+}
+
+/// Sets the name of the waypoint `matcher` picks to `new_name`, adding a
+/// `<name>` if it has none.
+pub fn rename_waypoint(input: &str, matcher: Matcher, new_name: &str) -> GpxResult<String> {
+    let gpx = read(input.as_bytes())?;
+    let names = gpx
+        .waypoints
+        .iter()
+        .map(|waypoint| waypoint.name.as_deref());
+    let index = find(names, &matcher, "waypoint")?;
+    let root = scan(input)?;
+    let waypoint = root.nth_child("wpt", index).expect("waypoint was read");
+    // The elements that come before the name.
+    let before = ["ele", "time", "magvar", "geoidheight"];
+    Ok(set_text(input, waypoint, "name", new_name, &before))
+}
+
+/// Sets the name of the track `matcher` picks to `new_name`, adding a
+/// `<name>` if it has none.
+///
+/// ```
+/// use gpx::patch::{rename_track, Matcher};
+///
+/// let input = r#"<gpx version="1.1" creator="editor"><trk/></gpx>"#;
+/// let output = rename_track(input, Matcher::Index(0), "Ride & hike").unwrap();
+/// assert_eq!(
+///     output,
+///     r#"<gpx version="1.1" creator="editor"><trk><name>Ride &amp; hike</name></trk></gpx>"#
+/// );
+/// ```
+pub fn rename_track(input: &str, matcher: Matcher, new_name: &str) -> GpxResult<String> {
+    let gpx = read(input.as_bytes())?;
+    let names = gpx.tracks.iter().map(|track| track.name.as_deref());
+    let index = find(names, &matcher, "track")?;
+    let root = scan(input)?;
+    let track = root.nth_child("trk", index).expect("track was read");
+    Ok(set_text(input, track, "name", new_name, &[]))
+}
+
+/// Removes the waypoint `matcher` picks. If it is on a line of its own, the
+/// whole line goes.
+pub fn delete_waypoint(input: &str, matcher: Matcher) -> GpxResult<String> {
+    let gpx = read(input.as_bytes())?;
+    let names = gpx
+        .waypoints
+        .iter()
+        .map(|waypoint| waypoint.name.as_deref());
+    let index = find(names, &matcher, "waypoint")?;
+    let root = scan(input)?;
+    let waypoint = root.nth_child("wpt", index).expect("waypoint was read");
+
+    let mut range = waypoint.range.clone();
+    let line_start = input[..range.start]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let rest = &input[range.end..];
+    let line_end = rest.find('\n').map(|newline| range.end + newline + 1);
+    if let Some(line_end) = line_end {
+        let alone = input[line_start..range.start].trim().is_empty()
+            && input[range.end..line_end].trim().is_empty();
+        if alone {
+            range = line_start..line_end;
+        }
+    }
+    Ok(splice(input, range, ""))
+}
+
+/// Sets the time of the document to `time`, in its `<metadata>` for GPX 1.1
+/// or directly in `<gpx>` for GPX 1.0, adding the elements that are
+/// missing.
+pub fn set_metadata_time(input: &str, time: Time) -> GpxResult<String> {
+    let gpx = read(input.as_bytes())?;
+    let formatted = time.format()?;
+    let root = scan(input)?;
+    if gpx.version == GpxVersion::Gpx10 {
+        let before = ["name", "desc", "author", "email", "url", "urlname"];
+        return Ok(set_text(input, &root, "time", &formatted, &before));
+    }
+    match root.nth_child("metadata", 0) {
+        Some(metadata) => {
+            let before = ["name", "desc", "author", "copyright", "link"];
+            Ok(set_text(input, metadata, "time", &formatted, &before))
+        }
+        None => {
+            let metadata = prefixed(root.name, "metadata");
+            let time = prefixed(root.name, "time");
+            let element = format!("<{0}><{1}>{2}</{1}></{0}>", metadata, time, formatted);
+            Ok(insert_child(input, &root, None, &element))
+        }
+    }
+}
+
+/// Gives the index of the first of `names` that `matcher` picks.
+fn find<'a>(
+    mut names: impl ExactSizeIterator<Item = Option<&'a str>>,
+    matcher: &Matcher,
+    what: &'static str,
+) -> GpxResult<usize> {
+    let index = match matcher {
+        Matcher::Name(wanted) => names.position(|name| name == Some(wanted.as_str())),
+        Matcher::Index(index) => Some(*index).filter(|index| *index < names.len()),
+    };
+    index.ok_or_else(|| GpxError::NoMatchingElement(what, matcher.to_string()))
+}
+
+/// Sets the text of the `child` element of `parent`, adding it after the
+/// last of the `before` children or first if there is none.
+fn set_text(input: &str, parent: &Span, child: &str, text: &str, before: &[&str]) -> String {
+    let text = escape(text);
+    if let Some(existing) = parent.children.iter().find(|span| span.local_name == child) {
+        return match &existing.content {
+            Some(content) => splice(input, content.clone(), &text),
+            None => splice(
+                input,
+                existing.range.clone(),
+                &format!("<{0}>{1}</{0}>", existing.name, text),
+            ),
+        };
+    }
+    let after = parent
+        .children
+        .iter()
+        .rev()
+        .find(|span| before.contains(&span.local_name));
+    let element = format!("<{0}>{1}</{0}>", prefixed(parent.name, child), text);
+    insert_child(input, parent, after, &element)
+}
+
+/// Inserts `element` into `parent`, right after `after` or first.
+fn insert_child(input: &str, parent: &Span, after: Option<&Span>, element: &str) -> String {
+    match (&parent.content, after) {
+        (_, Some(after)) => splice(input, after.range.end..after.range.end, element),
+        (Some(content), None) => splice(input, content.start..content.start, element),
+        // The parent is empty, `<wpt .../>`.
+        (None, None) => {
+            let close = parent.range.end - 2;
+            debug_assert_eq!(&input[close..parent.range.end], "/>");
+            let replacement = format!(">{}</{}>", element, parent.name);
+            splice(input, close..parent.range.end, &replacement)
+        }
+    }
+}
+
+/// Gives `local_name` with the prefix of its `parent`, so that it is in the
+/// same namespace.
+fn prefixed(parent: &str, local_name: &str) -> String {
+    match parent.find(':') {
+        Some(colon) => format!("{}:{}", &parent[..colon], local_name),
+        None => local_name.to_owned(),
+    }
+}
+
+fn splice(input: &str, range: Range<usize>, replacement: &str) -> String {
+    let mut output = String::with_capacity(input.len() + replacement.len());
+    output.push_str(&input[..range.start]);
+    output.push_str(replacement);
+    output.push_str(&input[range.end..]);
+    output
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// An element of the document, by where it is in the text.
+#[derive(Debug)]
+struct Span<'a> {
+    /// The name as written, with its prefix.
+    name: &'a str,
+    local_name: &'a str,
+    /// From the `<` of the start tag to after the `>` of the end tag.
+    range: Range<usize>,
+    /// Between the start and the end tag, `None` for an empty element tag.
+    content: Option<Range<usize>>,
+    /// The child elements, for the root and its children only.
+    children: Vec<Span<'a>>,
+}
+
+impl<'a> Span<'a> {
+    fn nth_child(&self, local_name: &str, index: usize) -> Option<&Span<'a>> {
+        self.children
+            .iter()
+            .filter(|span| span.local_name == local_name)
+            .nth(index)
+    }
+}
+
+/// Finds the spans of the root element, its children and their children.
+///
+/// The document has been read before, so it is well-formed, and this only
+/// needs to know enough XML to find the tags.
+fn scan(input: &str) -> GpxResult<Span<'_>> {
+    let truncated = || GpxError::MissingClosingTag("gpx");
+    // Finds the end of `marker` from `from`.
+    let skip_past = |from: usize, marker: &str| {
+        input[from..]
+            .find(marker)
+            .map(|found| from + found + marker.len())
+            .ok_or_else(truncated)
+    };
+
+    let mut open: Vec<Span<'_>> = Vec::new();
+    let mut position = 0;
+    while let Some(found) = input[position..].find('<') {
+        let start = position + found;
+        let rest = &input[start..];
+        position = if rest.starts_with("<!--") {
+            skip_past(start, "-->")?
+        } else if rest.starts_with("<![CDATA[") {
+            skip_past(start, "]]>")?
+        } else if rest.starts_with("<?") {
+            skip_past(start, "?>")?
+        } else if rest.starts_with("<!") {
+            skip_declaration(input, start).ok_or_else(truncated)?
+        } else if rest.starts_with("</") {
+            let end = skip_past(start, ">")?;
+            let mut element = open.pop().ok_or_else(truncated)?;
+            element.content = Some(element.range.end..start);
+            element.range.end = end;
+            if open.is_empty() {
+                return Ok(element);
+            }
+            add_child(&mut open, element);
+            end
+        } else {
+            let end = skip_tag(input, start).ok_or_else(truncated)?;
+            let name_end = rest[1..]
+                .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .map_or(rest.len(), |found| found + 1);
+            let name = &rest[1..name_end];
+            let local_name = name.rfind(':').map_or(name, |colon| &name[colon + 1..]);
+            let element = Span {
+                name,
+                local_name,
+                range: start..end,
+                content: None,
+                children: Vec::new(),
+            };
+            if !input[..end].ends_with("/>") {
+                open.push(element);
+            } else if open.is_empty() {
+                return Ok(element);
+            } else {
+                add_child(&mut open, element);
+            }
+            end
+        };
+    }
+    Err(truncated())
+}
+
+/// Adds `element` to the children of the last of the `open` elements, if
+/// that is the root or one of its children.
+fn add_child<'a>(open: &mut Vec<Span<'a>>, element: Span<'a>) {
+    if open.len() <= 2 {
+        if let Some(parent) = open.last_mut() {
+            parent.children.push(element);
+        }
+    }
+}
+
+/// Finds the end of the start tag at `start`, minding quoted attribute
+/// values.
+fn skip_tag(input: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (offset, c) in input[start..].char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return Some(start + offset + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Finds the end of the declaration at `start`, such as a `<!DOCTYPE>` with
+/// its internal subset.
+fn skip_declaration(input: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, c) in input[start..].char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '>' if depth == 0 => return Some(start + offset + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use time::OffsetDateTime;
+
+    use super::{delete_waypoint, rename_track, rename_waypoint, set_metadata_time, Matcher};
+    use crate::errors::GpxError;
+    use crate::read;
+
+    /// Gives the range of bytes in which `a` and `b` differ, in `a`.
+    fn changed_range(a: &str, b: &str) -> std::ops::Range<usize> {
+        let prefix = a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count();
+        let suffix = a[prefix..]
+            .bytes()
+            .rev()
+            .zip(b[prefix..].bytes().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        prefix..a.len() - suffix
+    }
+
+    #[test]
+    fn rename_second_track_of_fixture() {
+        let input = fs::read_to_string("tests/fixtures/caltopo-export.gpx").unwrap();
+        let output = rename_track(&input, Matcher::Index(1), "Day 2 <rest>").unwrap();
+
+        let name = input.find("Day 02").unwrap();
+        let escaped = "Day 2 &lt;rest&gt;";
+        assert_eq!(changed_range(&input, &output), name + 4..name + 6);
+        assert_eq!(&output[name..name + escaped.len()], escaped);
+        assert_eq!(output.len(), input.len() - "Day 02".len() + escaped.len());
+
+        let gpx = read(output.as_bytes()).unwrap();
+        assert_eq!(gpx.tracks[0].name.as_deref(), Some("Day 01"));
+        assert_eq!(gpx.tracks[1].name.as_deref(), Some("Day 2 <rest>"));
+
+        let same = rename_track(&input, Matcher::Name("Day 02".into()), "Day 02").unwrap();
+        assert_eq!(same, input);
+    }
+
+    #[test]
+    fn rename_waypoint_adds_missing_name() {
+        let input = r#"<?xml version="1.0"?>
+<!-- Exported by hand. -->
+<g:gpx xmlns:g="http://www.topografix.com/GPX/1/1" version="1.1" creator="t">
+  <g:wpt lat="1" lon="2"><g:ele>5</g:ele><g:sym>Flag</g:sym></g:wpt>
+  <g:wpt lat='3' lon='4 '/>
+</g:gpx>"#;
+        let output = rename_waypoint(input, Matcher::Index(0), "Top").unwrap();
+        assert_eq!(
+            output,
+            input.replace("</g:ele>", "</g:ele><g:name>Top</g:name>")
+        );
+        let output = rename_waypoint(input, Matcher::Index(1), "Bottom").unwrap();
+        assert_eq!(
+            output,
+            input.replace("'4 '/>", "'4 '><g:name>Bottom</g:name></g:wpt>")
+        );
+
+        match rename_waypoint(input, Matcher::Index(2), "Nowhere") {
+            Err(GpxError::NoMatchingElement("waypoint", matcher)) => {
+                assert_eq!(matcher, "index 2")
+            }
+            other => panic!("expected no match, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn delete_waypoint_line() {
+        let input = "<gpx version=\"1.1\" creator=\"t\">
+  <wpt lat=\"1\" lon=\"2\"><name>A</name></wpt>
+  <wpt lat=\"3\" lon=\"4\">
+    <name>B</name>
+  </wpt>
+  <wpt lat=\"5\" lon=\"6\"><name>C</name></wpt><!-- C -->
+</gpx>";
+        let output = delete_waypoint(input, Matcher::Name("B".into())).unwrap();
+        assert_eq!(
+            output,
+            "<gpx version=\"1.1\" creator=\"t\">
+  <wpt lat=\"1\" lon=\"2\"><name>A</name></wpt>
+  <wpt lat=\"5\" lon=\"6\"><name>C</name></wpt><!-- C -->
+</gpx>"
+        );
+        let output = delete_waypoint(input, Matcher::Name("C".into())).unwrap();
+        assert!(output.contains("\n  <!-- C -->\n"));
+        assert_eq!(read(output.as_bytes()).unwrap().waypoints.len(), 2);
+    }
+
+    #[test]
+    fn set_time_of_metadata() {
+        let time = OffsetDateTime::from_unix_timestamp(1_714_564_800)
+            .unwrap()
+            .into();
+        let input = r#"<gpx version="1.1" creator="t"><metadata><name>Trip</name><keywords>k</keywords></metadata></gpx>"#;
+        let output = set_metadata_time(input, time).unwrap();
+        assert_eq!(
+            output,
+            input.replace(
+                "</name>",
+                "</name><time>2024-05-01T12:00:00.000000000Z</time>"
+            )
+        );
+        let again = set_metadata_time(&output, time).unwrap();
+        assert_eq!(again, output);
+
+        let input = r#"<gpx version="1.1" creator="t"><wpt lat="1" lon="2"/></gpx>"#;
+        let output = set_metadata_time(input, time).unwrap();
+        assert!(output
+            .starts_with(r#"<gpx version="1.1" creator="t"><metadata><time>2024-05-01T12:00:00"#));
+
+        let input =
+            r#"<gpx version="1.0" creator="t"><name>Old</name><wpt lat="1" lon="2"/></gpx>"#;
+        let output = set_metadata_time(input, time).unwrap();
+        assert!(output.contains("</name><time>2024"));
+        let gpx = read(output.as_bytes()).unwrap();
+        assert_eq!(gpx.metadata.unwrap().time, Some(time));
+    }
+}
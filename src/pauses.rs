@@ -0,0 +1,350 @@
+//! pauses finds the stretches where a track stood still, and the time it
+//! was moving.
+
+use time::{Duration, OffsetDateTime};
+
+use crate::distance::haversine;
+use crate::{Time, Track, TrackSegment};
+
+/// Options for [`TrackSegment::detect_pauses`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PauseOptions {
+    /// Speed in meters per second below which the track counts as standing
+    /// still.
+    pub speed_threshold_mps: f64,
+
+    /// Shortest time the track has to stand still for a pause.
+    pub min_duration: Duration,
+
+    /// Pauses with less moving time than this between them are merged into
+    /// one, including the time in between.
+    pub merge_gap: Duration,
+
+    /// Whether to use the recorded `speed` of the points where there is one,
+    /// instead of deriving the speed from their positions and times.
+    pub use_recorded_speed: bool,
+}
+
+impl Default for PauseOptions {
+    fn default() -> PauseOptions {
+        PauseOptions {
+            speed_threshold_mps: 0.5,
+            min_duration: Duration::seconds(30),
+            merge_gap: Duration::seconds(10),
+            use_recorded_speed: false,
+        }
+    }
+}
+
+/// A stretch of a track segment where it stood still, as found by
+/// [`TrackSegment::detect_pauses`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pause {
+    /// Index of the point where the pause starts.
+    pub start_index: usize,
+
+    /// Index of the point where the pause ends, from which the track moves
+    /// on.
+    pub end_index: usize,
+
+    /// Time of the point at `start_index`.
+    pub start_time: Time,
+
+    /// Time of the point at `end_index`.
+    pub end_time: Time,
+
+    /// Time between the start and the end.
+    pub duration: Duration,
+}
+
+impl TrackSegment {
+    /// Finds the pauses of the segment, in order.
+    ///
+    /// The speed between two consecutive points is their distance divided by
+    /// the time between them, or the recorded speed of the second point if
+    /// [`PauseOptions::use_recorded_speed`] is set. Runs of points slower
+    /// than the threshold for at least [`PauseOptions::min_duration`] are
+    /// pauses. Points without a time are skipped, so a segment without times
+    /// has no pauses.
+    ///
+    /// ```
+    /// use gpx::{PauseOptions, TrackSegment, Waypoint};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// // Standing still for a minute, then moving 100 m in 10 seconds.
+    /// for (seconds, x) in [(0, 0.0), (60, 0.0), (70, 0.0009)].iter() {
+    ///     let mut point = Waypoint::new_lat_lon(0.0, *x).unwrap();
+    ///     point.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(*seconds)).into());
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// let pauses = segment.detect_pauses(&PauseOptions::default());
+    /// assert_eq!(pauses.len(), 1);
+    /// assert_eq!((pauses[0].start_index, pauses[0].end_index), (0, 1));
+    /// assert_eq!(pauses[0].duration, Duration::minutes(1));
+    /// ```
+    pub fn detect_pauses(&self, options: &PauseOptions) -> Vec<Pause> {
+        let timed: Vec<(usize, OffsetDateTime)> = self
+            .points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, point)| Some((index, point.time?.into())))
+            .collect();
+
+        let mut pauses: Vec<Pause> = Vec::new();
+        let mut run: Option<(usize, usize)> = None;
+        for (n, pair) in timed.windows(2).enumerate() {
+            let ((from, from_time), (to, to_time)) = (pair[0], pair[1]);
+            let (from_point, to_point) = (&self.points[from], &self.points[to]);
+            let seconds = (to_time - from_time).as_seconds_f64();
+            let distance = haversine(from_point.point(), to_point.point());
+            let speed = match to_point.extras().speed {
+                Some(speed) if options.use_recorded_speed => speed,
+                _ if seconds <= 0.0 => {
+                    if distance == 0.0 {
+                        0.0
+                    } else {
+                        f64::INFINITY
+                    }
+                }
+                _ => distance / seconds,
+            };
+            if speed < options.speed_threshold_mps {
+                let start = run.map_or(n, |(start, _)| start);
+                run = Some((start, n + 1));
+            } else if let Some((start, end)) = run.take() {
+                push_pause(&mut pauses, &timed, start, end, options);
+            }
+        }
+        if let Some((start, end)) = run {
+            push_pause(&mut pauses, &timed, start, end, options);
+        }
+        pauses
+    }
+
+    /// Gives the time between the earliest and the latest time of the
+    /// points, without the pauses found with `options`, or `None` if no
+    /// point has a time. Times that go backwards never give a negative
+    /// duration.
+    pub fn moving_duration(&self, options: &PauseOptions) -> Option<Duration> {
+        let times = self.points.iter().filter_map(|point| point.time);
+        let first = OffsetDateTime::from(times.clone().min()?);
+        let last = OffsetDateTime::from(times.max()?);
+        let paused: Duration = self
+            .detect_pauses(options)
+            .iter()
+            .map(|pause| pause.duration)
+            .sum();
+        Some((last - first - paused).max(Duration::ZERO))
+    }
+}
+
+impl Track {
+    /// Gives the moving time of all segments together, see
+    /// [`TrackSegment::moving_duration`]. The time between segments does not
+    /// count as moving. Returns `None` if no point has a time.
+    pub fn moving_duration(&self, options: &PauseOptions) -> Option<Duration> {
+        self.segments
+            .iter()
+            .filter_map(|segment| segment.moving_duration(options))
+            .fold(None, |total, duration| {
+                Some(total.unwrap_or(Duration::ZERO) + duration)
+            })
+    }
+}
+
+/// Adds the run of slow points from `timed[start]` to `timed[end]` to
+/// `pauses` if it is long enough, merging it with the previous pause if they
+/// are close.
+fn push_pause(
+    pauses: &mut Vec<Pause>,
+    timed: &[(usize, OffsetDateTime)],
+    start: usize,
+    end: usize,
+    options: &PauseOptions,
+) {
+    let ((start_index, start_time), (end_index, end_time)) = (timed[start], timed[end]);
+    if end_time - start_time < options.min_duration {
+        return;
+    }
+    if let Some(previous) = pauses.last_mut() {
+        if start_time - OffsetDateTime::from(previous.end_time) < options.merge_gap {
+            previous.end_index = end_index;
+            previous.end_time = end_time.into();
+            previous.duration = end_time - OffsetDateTime::from(previous.start_time);
+            return;
+        }
+    }
+    pauses.push(Pause {
+        start_index,
+        end_index,
+        start_time: start_time.into(),
+        end_time: end_time.into(),
+        duration: end_time - start_time,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::PauseOptions;
+    use crate::{Track, TrackSegment, Waypoint};
+
+    /// Meters per degree of longitude on the equator.
+    const METERS_PER_DEGREE: f64 = 111_195.0;
+
+    /// A segment along the equator with a point every five seconds, moving
+    /// through `phases` of (seconds, meters per second). Standing still
+    /// jitters by 20 cm.
+    fn commute(phases: &[(i64, f64)]) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        let (mut seconds, mut meters) = (0, 0.0);
+        let mut push = |seconds: i64, meters: f64| {
+            let mut point = Waypoint::new(Point::new(meters / METERS_PER_DEGREE, 0.0));
+            point.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(seconds)).into());
+            segment.points.push(point);
+        };
+        push(seconds, meters);
+        for &(duration, speed) in phases {
+            for step in 0..duration / 5 {
+                seconds += 5;
+                meters += if speed == 0.0 {
+                    if step % 2 == 0 {
+                        0.2
+                    } else {
+                        -0.2
+                    }
+                } else {
+                    5.0 * speed
+                };
+                push(seconds, meters);
+            }
+        }
+        segment
+    }
+
+    fn spans(segment: &TrackSegment, options: &PauseOptions) -> Vec<(usize, usize, i64)> {
+        segment
+            .detect_pauses(options)
+            .iter()
+            .map(|pause| {
+                (
+                    pause.start_index,
+                    pause.end_index,
+                    pause.duration.whole_seconds(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detect_two_stops() {
+        // Riding for 100 s, a stop at a light for 60 s, riding on, a stop
+        // at a shop for 100 s, and riding to the end.
+        let segment = commute(&[(100, 6.0), (60, 0.0), (140, 6.0), (100, 0.0), (100, 6.0)]);
+        let options = PauseOptions::default();
+
+        assert_eq!(spans(&segment, &options), vec![(20, 32, 60), (60, 80, 100)]);
+        let pause = &segment.detect_pauses(&options)[0];
+        assert_eq!(
+            OffsetDateTime::from(pause.start_time),
+            OffsetDateTime::UNIX_EPOCH + Duration::seconds(100)
+        );
+        assert_eq!(
+            OffsetDateTime::from(pause.end_time),
+            OffsetDateTime::UNIX_EPOCH + Duration::seconds(160)
+        );
+        assert_eq!(
+            segment.moving_duration(&options),
+            Some(Duration::seconds(340))
+        );
+
+        // Short stops do not count.
+        let strict = PauseOptions {
+            min_duration: Duration::seconds(90),
+            ..Default::default()
+        };
+        assert_eq!(spans(&segment, &strict), vec![(60, 80, 100)]);
+    }
+
+    #[test]
+    fn detect_pauses_at_start_and_end() {
+        let segment = commute(&[(40, 0.0), (50, 5.0), (45, 0.0)]);
+
+        assert_eq!(
+            spans(&segment, &PauseOptions::default()),
+            vec![(0, 8, 40), (18, 27, 45)]
+        );
+        assert_eq!(
+            segment.moving_duration(&PauseOptions::default()),
+            Some(Duration::seconds(50))
+        );
+    }
+
+    #[test]
+    fn merge_close_pauses() {
+        // Two stops, with a single moving point in between.
+        let segment = commute(&[(50, 5.0), (40, 0.0), (5, 5.0), (40, 0.0), (50, 5.0)]);
+
+        assert_eq!(
+            spans(&segment, &PauseOptions::default()),
+            vec![(10, 27, 85)]
+        );
+        let separate = PauseOptions {
+            merge_gap: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(spans(&segment, &separate), vec![(10, 18, 40), (19, 27, 40)]);
+    }
+
+    #[test]
+    fn recorded_speed() {
+        let mut segment = commute(&[(60, 5.0)]);
+        for point in &mut segment.points {
+            point.extras_mut().speed = Some(0.1);
+        }
+
+        assert!(segment.detect_pauses(&PauseOptions::default()).is_empty());
+        let recorded = PauseOptions {
+            use_recorded_speed: true,
+            ..Default::default()
+        };
+        assert_eq!(spans(&segment, &recorded), vec![(0, 12, 60)]);
+    }
+
+    #[test]
+    fn without_times() {
+        let mut segment = commute(&[(60, 0.0)]);
+        for point in &mut segment.points {
+            point.time = None;
+        }
+        let mut track = Track::new();
+        track.segments.push(segment);
+
+        assert!(track.segments[0]
+            .detect_pauses(&PauseOptions::default())
+            .is_empty());
+        assert_eq!(track.moving_duration(&PauseOptions::default()), None);
+        assert_eq!(Track::new().moving_duration(&PauseOptions::default()), None);
+    }
+
+    #[test]
+    fn track_moving_duration_skips_gaps() {
+        let mut track = Track::new();
+        track.segments.push(commute(&[(60, 5.0), (60, 0.0)]));
+        let mut later = commute(&[(30, 5.0)]);
+        for point in &mut later.points {
+            let time = OffsetDateTime::from(point.time.unwrap()) + Duration::hours(1);
+            point.time = Some(time.into());
+        }
+        track.segments.push(later);
+
+        assert_eq!(
+            track.moving_duration(&PauseOptions::default()),
+            Some(Duration::seconds(90))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! point3d keeps the elevation and time of points where geo-types has no
+//! room for them.
+//!
+//! geo-types has no third coordinate, so every conversion into it drops the
+//! elevation. They all go through [`GpxPoint::to_point_lossy`], so that this
+//! happens in one place.
+
+use std::convert::TryFrom;
+
+use geo_types::Point;
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::errors::{GpxError, GpxResult};
+use crate::types::{validate_finite, validate_latitude, validate_longitude};
+use crate::{Time, TrackSegment, Waypoint};
+
+/// A position with its elevation and time: `x` is the longitude, `y` the
+/// latitude and `z` the elevation in meters.
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use gpx::{GpxPoint, Waypoint};
+/// use geo_types::Point;
+///
+/// let mut wpt = Waypoint::new_lat_lon(52.5, 13.4).unwrap();
+/// wpt.elevation = Some(34.0);
+///
+/// let point = GpxPoint::from(&wpt);
+/// assert_eq!(point.z, Some(34.0));
+/// assert_eq!(Waypoint::try_from(point).unwrap().elevation, Some(34.0));
+///
+/// // Leaving for geo-types loses the elevation.
+/// assert_eq!(point.to_point_lossy(), Point::new(13.4, 52.5));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct GpxPoint {
+    /// The longitude, in degrees.
+    pub x: f64,
+
+    /// The latitude, in degrees.
+    pub y: f64,
+
+    /// The elevation, in meters.
+    pub z: Option<f64>,
+
+    /// The time of the point.
+    pub time: Option<OffsetDateTime>,
+}
+
+impl GpxPoint {
+    /// Creates a point.
+    pub fn new(x: f64, y: f64, z: Option<f64>, time: Option<OffsetDateTime>) -> GpxPoint {
+        GpxPoint { x, y, z, time }
+    }
+
+    /// Gives the position as a geo-types point, dropping the elevation and
+    /// time.
+    pub fn to_point_lossy(&self) -> Point<f64> {
+        Point::new(self.x, self.y)
+    }
+}
+
+impl From<&Waypoint> for GpxPoint {
+    fn from(waypoint: &Waypoint) -> GpxPoint {
+        let point = waypoint.point();
+        GpxPoint {
+            x: point.x(),
+            y: point.y(),
+            z: waypoint.elevation,
+            time: waypoint.time.map(OffsetDateTime::from),
+        }
+    }
+}
+
+impl TryFrom<GpxPoint> for Waypoint {
+    type Error = GpxError;
+
+    /// Creates a waypoint at the point, checking that the coordinates are
+    /// in range and the elevation is finite.
+    fn try_from(point: GpxPoint) -> GpxResult<Waypoint> {
+        validate_latitude(point.y)?;
+        validate_longitude(point.x)?;
+        if let Some(z) = point.z {
+            validate_finite("ele", z)?;
+        }
+        let mut waypoint = Waypoint::new(point.to_point_lossy());
+        waypoint.elevation = point.z;
+        waypoint.time = point.time.map(Time::from);
+        Ok(waypoint)
+    }
+}
+
+impl TrackSegment {
+    /// Gives the points of the segment with their elevations and times.
+    pub fn points_3d(&self) -> Vec<GpxPoint> {
+        self.points.iter().map(GpxPoint::from).collect()
+    }
+
+    /// Creates a segment from points with elevations and times, such as
+    /// those of [`TrackSegment::points_3d`]. Fails at the first point that
+    /// is out of range.
+    pub fn from_points_3d(points: impl IntoIterator<Item = GpxPoint>) -> GpxResult<TrackSegment> {
+        let points = points
+            .into_iter()
+            .map(Waypoint::try_from)
+            .collect::<GpxResult<_>>()?;
+        Ok(TrackSegment::from_points(points))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use geo_types::Point;
+    use time::OffsetDateTime;
+
+    use super::GpxPoint;
+    use crate::errors::GpxError;
+    use crate::testutil::synthetic_gpx;
+    use crate::{TrackSegment, Waypoint};
+
+    #[test]
+    fn elevation_survives_3d_path() {
+        let segment = synthetic_gpx(1, 20).tracks.remove(0).segments.remove(0);
+        let points = segment.points_3d();
+        assert!(points.iter().all(|point| point.z.is_some()));
+        assert_eq!(points[3].z, segment.points[3].elevation);
+
+        let rebuilt = TrackSegment::from_points_3d(points).unwrap();
+        assert_eq!(rebuilt, segment);
+    }
+
+    #[test]
+    fn lossy_path_drops_elevation() {
+        let mut wpt = Waypoint::new(Point::new(-121.97, 37.24));
+        wpt.elevation = Some(553.21);
+        wpt.time = Some(OffsetDateTime::UNIX_EPOCH.into());
+
+        let point = GpxPoint::from(&wpt).to_point_lossy();
+        assert_eq!(point, wpt.point());
+        let back = Waypoint::new(point);
+        assert_eq!((back.elevation, back.time), (None, None));
+
+        let mut segment = TrackSegment::new();
+        segment.points.push(wpt);
+        assert_eq!(segment.linestring().0[0], point.0);
+    }
+
+    #[test]
+    fn out_of_range_points_are_rejected() {
+        let point = GpxPoint::new(181.0, 0.0, None, None);
+        assert!(matches!(
+            Waypoint::try_from(point),
+            Err(GpxError::LonLatOutOfBoundsError(..))
+        ));
+        let points = vec![
+            GpxPoint::new(1.0, 2.0, Some(3.0), None),
+            GpxPoint::new(1.0, 2.0, Some(f64::NAN), None),
+        ];
+        assert!(matches!(
+            TrackSegment::from_points_3d(points),
+            Err(GpxError::InvalidNumericValue("ele", _))
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+//! point_ref gives handles to points that notice when they go stale,
+//! instead of indices that silently point at another point once points are
+//! removed.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use geo_types::Point;
+
+use crate::distance::haversine;
+use crate::{ElementPath, Gpx, Waypoint};
+
+/// A handle to a waypoint, route point or track point of a [`Gpx`], from
+/// [`Gpx::find_point`] or [`Gpx::nearest_point`].
+///
+/// A handle only resolves as long as the structure of the document is the
+/// one it was made from. The helpers of [`Gpx`] that remove or reorder
+/// points, like [`Gpx::remove_spikes`], [`Gpx::dedup_exact`] and
+/// [`Gpx::reverse_all`], make all handles stale, even where the point would
+/// still be found at the same path.
+///
+/// A handle only resolves in the document it was made from, or in clones of
+/// it that weren't changed by such helpers since.
+///
+/// Changes through the public fields, including the helpers of [`Track`]
+/// and [`TrackSegment`] called on them, can't be noticed, so a handle may
+/// resolve to another point after them. Make new handles after such
+/// changes.
+///
+/// [`Track`]: crate::Track
+/// [`TrackSegment`]: crate::TrackSegment
+///
+/// ```
+/// use gpx::{Gpx, Track, TrackSegment, Waypoint};
+///
+/// let mut segment = TrackSegment::new();
+/// for lon in [8.0, 8.0, 8.1].iter() {
+///     segment.points.push(Waypoint::new_lat_lon(47.0, *lon).unwrap());
+/// }
+/// let mut track = Track::new();
+/// track.segments.push(segment);
+/// let mut gpx = Gpx::default();
+/// gpx.tracks.push(track);
+///
+/// let last = gpx.find_point(|point| point.lon() == 8.1).unwrap();
+/// gpx.resolve_mut(&last).unwrap().name = Some("End".into());
+///
+/// gpx.dedup_exact(6);
+/// assert!(gpx.resolve(&last).is_none());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PointRef {
+    path: ElementPath,
+    generation: u64,
+}
+
+impl PointRef {
+    /// Gives the path of the point, as it was when the handle was made.
+    pub fn path(&self) -> ElementPath {
+        self.path
+    }
+}
+
+impl fmt::Display for PointRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+/// Hands out the stamps of [`Generation`]s.
+static NEXT_STAMP: AtomicU64 = AtomicU64::new(0);
+
+/// Tells apart the documents, and their states as changed by helpers, for
+/// [`PointRef`]. Every new document gets a stamp of its own, and helpers
+/// that change the structure give it a new one, so no two documents share
+/// a stamp unless one is a clone of the other.
+///
+/// It is no part of the data, so it makes no difference to comparisons, and
+/// isn't written or serialized. Its stamp can't be set from outside of the
+/// crate, so a struct expression gets a fresh one from `Default`.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct Generation(u64);
+
+impl Generation {
+    fn next() -> Generation {
+        Generation(NEXT_STAMP.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for Generation {
+    fn default() -> Generation {
+        Generation::next()
+    }
+}
+
+impl PartialEq for Generation {
+    fn eq(&self, _: &Generation) -> bool {
+        true
+    }
+}
+
+impl Gpx {
+    /// Gives a handle to the first point `predicate` holds for, in the order
+    /// of waypoints, route points and track points.
+    pub fn find_point(&self, mut predicate: impl FnMut(&Waypoint) -> bool) -> Option<PointRef> {
+        self.points_with_paths()
+            .find(|&(_, point)| predicate(point))
+            .map(|(path, _)| self.point_ref(path))
+    }
+
+    /// Gives a handle to the point closest to `point`, the earliest of them
+    /// if several are equally close.
+    pub fn nearest_point(&self, point: &Point<f64>) -> Option<PointRef> {
+        self.nearest_point_where(point, |_, _| true)
+    }
+
+    /// Like [`Gpx::nearest_point`], but only looks at the points `predicate`
+    /// holds for, such as the track points.
+    pub fn nearest_point_where(
+        &self,
+        point: &Point<f64>,
+        mut predicate: impl FnMut(ElementPath, &Waypoint) -> bool,
+    ) -> Option<PointRef> {
+        let mut nearest: Option<(ElementPath, f64)> = None;
+        for (path, candidate) in self.points_with_paths() {
+            if !predicate(path, candidate) {
+                continue;
+            }
+            let distance = haversine(*point, candidate.point());
+            if nearest.map_or(true, |(_, closest)| distance < closest) {
+                nearest = Some((path, distance));
+            }
+        }
+        nearest.map(|(path, _)| self.point_ref(path))
+    }
+
+    /// Gives the point of `point_ref`, or nothing if it is stale or from
+    /// another document.
+    pub fn resolve(&self, point_ref: &PointRef) -> Option<&Waypoint> {
+        if point_ref.generation != self.generation.0 {
+            return None;
+        }
+        match point_ref.path {
+            ElementPath::Waypoint(w) => self.waypoints.get(w),
+            ElementPath::RoutePoint(r, p) => self.routes.get(r)?.points.get(p),
+            ElementPath::TrackPoint(t, s, p) => self.tracks.get(t)?.segments.get(s)?.points.get(p),
+            _ => None,
+        }
+    }
+
+    /// Like [`Gpx::resolve`], for changing the point. Changing it doesn't
+    /// make handles stale.
+    pub fn resolve_mut(&mut self, point_ref: &PointRef) -> Option<&mut Waypoint> {
+        if point_ref.generation != self.generation.0 {
+            return None;
+        }
+        match point_ref.path {
+            ElementPath::Waypoint(w) => self.waypoints.get_mut(w),
+            ElementPath::RoutePoint(r, p) => self.routes.get_mut(r)?.points.get_mut(p),
+            ElementPath::TrackPoint(t, s, p) => self
+                .tracks
+                .get_mut(t)?
+                .segments
+                .get_mut(s)?
+                .points
+                .get_mut(p),
+            _ => None,
+        }
+    }
+
+    /// Gives the stamp of the structure of the document, which helpers that
+    /// remove or reorder points change, and which tells apart documents
+    /// that aren't clones of each other.
+    pub fn generation(&self) -> u64 {
+        self.generation.0
+    }
+
+    /// Makes all handles stale, for helpers that remove or reorder points.
+    pub(crate) fn bump_generation(&mut self) {
+        self.generation = Generation::next();
+    }
+
+    fn point_ref(&self, path: ElementPath) -> PointRef {
+        PointRef {
+            path,
+            generation: self.generation.0,
+        }
+    }
+
+    /// Iterates over all waypoints, route points and track points, with
+    /// their paths.
+    fn points_with_paths(&self) -> impl Iterator<Item = (ElementPath, &Waypoint)> {
+        let waypoints = self
+            .waypoints
+            .iter()
+            .enumerate()
+            .map(|(w, point)| (ElementPath::Waypoint(w), point));
+        let routes = self.routes.iter().enumerate().flat_map(|(r, route)| {
+            route
+                .points
+                .iter()
+                .enumerate()
+                .map(move |(p, point)| (ElementPath::RoutePoint(r, p), point))
+        });
+        let tracks = self.tracks.iter().enumerate().flat_map(|(t, track)| {
+            track
+                .segments
+                .iter()
+                .enumerate()
+                .flat_map(move |(s, segment)| {
+                    segment
+                        .points
+                        .iter()
+                        .enumerate()
+                        .map(move |(p, point)| (ElementPath::TrackPoint(t, s, p), point))
+                })
+        });
+        waypoints.chain(routes).chain(tracks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use crate::testutil::synthetic_gpx;
+    use crate::{ElementPath, Waypoint};
+
+    #[test]
+    fn fresh_ref_resolves_to_its_point() {
+        let mut gpx = synthetic_gpx(2, 50);
+        gpx.waypoints
+            .push(Waypoint::new_lat_lon(10.0, 10.0).unwrap());
+        let target = gpx.tracks[1].segments[0].points[20].point();
+
+        let found = gpx.find_point(|point| point.point() == target).unwrap();
+        assert_eq!(found.path(), ElementPath::TrackPoint(1, 0, 20));
+        assert_eq!(gpx.resolve(&found).unwrap().point(), target);
+
+        let nearest = gpx
+            .nearest_point(&Point::new(target.x() + 1e-7, target.y()))
+            .unwrap();
+        assert_eq!(nearest, found);
+        let waypoint = gpx
+            .nearest_point_where(&target, |path, _| matches!(path, ElementPath::Waypoint(_)))
+            .unwrap();
+        assert_eq!(waypoint.to_string(), "waypoints[0]");
+
+        // Changing the point through the handle keeps it fresh.
+        gpx.resolve_mut(&found).unwrap().name = Some("Here".into());
+        assert_eq!(gpx.resolve(&found).unwrap().name.as_deref(), Some("Here"));
+        assert!(gpx.find_point(|point| point.lat() > 90.0).is_none());
+    }
+
+    #[test]
+    fn ref_goes_stale_after_helpers() {
+        let mut gpx = synthetic_gpx(1, 50);
+        let original = gpx.clone();
+        let point_ref = gpx.find_point(|_| true).unwrap();
+
+        // Nothing is removed, but the structure may have changed.
+        gpx.remove_spikes(1000.0);
+        assert!(gpx.resolve(&point_ref).is_none());
+        assert!(gpx.resolve_mut(&point_ref).is_none());
+        assert_eq!(gpx, original);
+
+        let point_ref = gpx.find_point(|_| true).unwrap();
+        gpx.reverse_all();
+        assert!(gpx.resolve(&point_ref).is_none());
+        let point_ref = gpx.find_point(|_| true).unwrap();
+        gpx.dedup_exact(6);
+        assert!(gpx.resolve(&point_ref).is_none());
+
+        // Changes through the fields go unnoticed: a handle past the end
+        // resolves to nothing, others to whatever point is there now.
+        let last = gpx.nearest_point(&gpx.tracks[0].segments[0].points[49].point());
+        gpx.tracks[0].segments[0].points.truncate(10);
+        assert!(gpx.resolve(&last.unwrap()).is_none());
+        let point_ref = gpx.find_point(|_| true).unwrap();
+        gpx.tracks[0].segments[0].points.remove(0);
+        assert!(gpx.resolve(&point_ref).is_some());
+    }
+
+    #[test]
+    fn ref_only_resolves_in_its_document() {
+        let mut gpx = synthetic_gpx(1, 10);
+        let point_ref = gpx.find_point(|_| true).unwrap();
+        let generation = gpx.generation();
+
+        // A document alike but made anew is another one.
+        let other = synthetic_gpx(1, 10);
+        assert_eq!(other, gpx);
+        assert_ne!(other.generation(), generation);
+        assert!(other.resolve(&point_ref).is_none());
+
+        // A clone is the same until a helper changes either.
+        let clone = gpx.clone();
+        assert_eq!(clone.generation(), generation);
+        assert!(clone.resolve(&point_ref).is_some());
+        gpx.reverse_all();
+        assert_ne!(gpx.generation(), generation);
+        assert!(gpx.resolve(&point_ref).is_none());
+        assert!(clone.resolve(&point_ref).is_some());
+    }
+}
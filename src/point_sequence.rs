@@ -0,0 +1,211 @@
+//! point_sequence converts lists of points kept in extensions, like the
+//! `<ptseg>` of the GPX 1.1 drafts, from and to waypoints.
+
+use crate::dom::{Element, Node, OwnedAttribute, OwnedName};
+use crate::parser::time;
+use crate::Waypoint;
+
+impl Element {
+    /// Reads the element as a list of points, such as a `<ptseg>` of
+    /// `<pt lat="..." lon="...">` elements.
+    ///
+    /// Every child has to be a `pt` element, in any namespace, with valid
+    /// `lat` and `lon` attributes and optional `ele` and `time`, either as
+    /// attributes or as child elements. Anything else, or no points at all,
+    /// gives `None`.
+    ///
+    /// ```
+    /// use gpx::dom::{point_sequence_to_element, OwnedName};
+    /// use gpx::Waypoint;
+    ///
+    /// let mut point = Waypoint::new_lat_lon(46.56, 7.99).unwrap();
+    /// point.elevation = Some(2061.5);
+    /// let element = point_sequence_to_element(&[point.clone()], OwnedName::local("ptseg"));
+    /// assert_eq!(element.as_point_sequence(), Some(vec![point]));
+    /// ```
+    pub fn as_point_sequence(&self) -> Option<Vec<Waypoint>> {
+        let mut points = Vec::new();
+        for child in &self.children {
+            match child {
+                Node::Element(pt) => points.push(read_point(pt)?),
+                Node::Text(text) if text.trim().is_empty() => {}
+                Node::Text(_) => return None,
+            }
+        }
+        if points.is_empty() {
+            None
+        } else {
+            Some(points)
+        }
+    }
+}
+
+fn read_point(pt: &Element) -> Option<Waypoint> {
+    if pt.name.local_name.as_str() != "pt" {
+        return None;
+    }
+    let attribute = |name: &str| {
+        pt.attributes
+            .iter()
+            .find(|attribute| attribute.name.local_name.as_str() == name)
+            .map(|attribute| attribute.value.as_str())
+    };
+    let latitude = attribute("lat")?.trim().parse().ok()?;
+    let longitude = attribute("lon")?.trim().parse().ok()?;
+    let mut point = Waypoint::new_lat_lon(latitude, longitude).ok()?;
+
+    let mut elevation = attribute("ele").map(str::to_owned);
+    let mut time = attribute("time").map(str::to_owned);
+    for child in pt.elements() {
+        match child.name.local_name.as_str() {
+            "ele" => elevation = Some(child.text()),
+            "time" => time = Some(child.text()),
+            _ => return None,
+        }
+    }
+    if let Some(elevation) = elevation {
+        let elevation: f64 = elevation.trim().parse().ok()?;
+        if !elevation.is_finite() {
+            return None;
+        }
+        point.elevation = Some(elevation);
+    }
+    if let Some(time) = time {
+        point.time = Some(time::parse(time.trim()).ok()?);
+    }
+    Some(point)
+}
+
+/// Gives an element named `name` that holds `points` as `pt` elements,
+/// with `lat` and `lon` attributes and `ele` and `time` children if the
+/// points have them, in the namespace of `name`. It reads back with
+/// [`Element::as_point_sequence`].
+///
+/// Elevations that are not finite and times that can't be written as
+/// xsd:dateTime are left out.
+pub fn point_sequence_to_element(points: &[Waypoint], name: OwnedName) -> Element {
+    let child_name = |local_name: &str| OwnedName {
+        local_name: local_name.into(),
+        ..name.clone()
+    };
+    let children = points
+        .iter()
+        .map(|point| {
+            let mut pt = Element::new(child_name("pt"));
+            pt.attributes = vec![
+                OwnedAttribute::new(OwnedName::local("lat"), point.lat().to_string()),
+                OwnedAttribute::new(OwnedName::local("lon"), point.lon().to_string()),
+            ];
+            let elevation = point.elevation.filter(|elevation| elevation.is_finite());
+            if let Some(elevation) = elevation {
+                pt.children
+                    .push(text_element(child_name("ele"), elevation.to_string()));
+            }
+            if let Some(time) = point.time.and_then(|time| time.format().ok()) {
+                pt.children.push(text_element(child_name("time"), time));
+            }
+            Node::Element(pt)
+        })
+        .collect();
+    Element {
+        name,
+        attributes: Vec::new(),
+        children,
+    }
+}
+
+fn text_element(name: OwnedName, text: String) -> Node {
+    let mut element = Element::new(name);
+    element.children.push(Node::Text(text));
+    Node::Element(element)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use time::OffsetDateTime;
+
+    use super::point_sequence_to_element;
+    use crate::dom::{Element, Node, OwnedName};
+    use crate::{read, write_to_vec, Gpx, GpxVersion, Waypoint};
+
+    fn ptsegs() -> Vec<Element> {
+        let file = File::open("tests/fixtures/ptseg_extension.gpx").unwrap();
+        let gpx = read(file).unwrap();
+        let extensions = gpx.tracks[0].extensions.as_ref().unwrap();
+        extensions.elements().cloned().collect()
+    }
+
+    #[test]
+    fn read_ptseg() {
+        let points = ptsegs()[0].as_point_sequence().unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!((points[0].lat(), points[0].lon()), (46.5582, 7.9912));
+        assert_eq!(points[0].elevation, Some(2061.5));
+        let time = OffsetDateTime::from_unix_timestamp(1_689_321_731).unwrap();
+        assert_eq!(points[0].time, Some(time.into()));
+        assert_eq!(points[2].elevation, None);
+        assert_eq!(points[2].time, None);
+
+        // The extensions element itself holds `ptseg`s, not points.
+        let file = File::open("tests/fixtures/ptseg_extension.gpx").unwrap();
+        let gpx = read(file).unwrap();
+        assert_eq!(
+            gpx.tracks[0]
+                .extensions
+                .as_ref()
+                .unwrap()
+                .as_point_sequence(),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_point_gives_none() {
+        // The second point has no `lon`.
+        assert_eq!(ptsegs()[1].as_point_sequence(), None);
+
+        let mut out_of_range = ptsegs()[0].clone();
+        if let Node::Element(pt) = &mut out_of_range.children[0] {
+            pt.attributes[0].value = "91".to_string();
+        }
+        assert_eq!(out_of_range.as_point_sequence(), None);
+        assert_eq!(
+            Element::new(OwnedName::local("ptseg")).as_point_sequence(),
+            None
+        );
+    }
+
+    #[test]
+    fn point_sequence_round_trip() {
+        let original = ptsegs().remove(0);
+        let points = original.as_point_sequence().unwrap();
+        let element = point_sequence_to_element(&points, original.name.clone());
+        assert_eq!(element.as_point_sequence().as_ref(), Some(&points));
+
+        let pt = element.elements().next().unwrap();
+        assert_eq!(pt.name.prefix.as_deref(), Some("rl"));
+        let children: Vec<&str> = pt
+            .elements()
+            .map(|child| child.name.local_name.as_str())
+            .collect();
+        assert_eq!(children, ["ele", "time"]);
+
+        // Written into a document and read back.
+        let mut gpx = Gpx {
+            version: GpxVersion::Gpx11,
+            ..Default::default()
+        };
+        let mut waypoint = Waypoint::new_lat_lon(46.5, 7.9).unwrap();
+        let mut extensions = Element::new(OwnedName::local("extensions"));
+        extensions.children.push(Node::Element(element));
+        waypoint.extras_mut().extensions = Some(extensions);
+        gpx.waypoints.push(waypoint);
+        let written = write_to_vec(&gpx).unwrap();
+        let reread = read(written.as_slice()).unwrap();
+        let extensions = reread.waypoints[0].extras().extensions.as_ref().unwrap();
+        let ptseg = extensions.elements().next().unwrap();
+        assert_eq!(ptseg.as_point_sequence(), Some(points));
+    }
+}
@@ -0,0 +1,239 @@
+//! polyline converts track segments from and to encoded polylines.
+//!
+//! This is the format of Google's Encoded Polyline Algorithm, also used by
+//! Strava and OSRM among others.
+
+use std::iter::Peekable;
+
+use crate::errors::{GpxError, GpxResult};
+use crate::{Track, TrackSegment, Waypoint};
+
+impl TrackSegment {
+    /// Encodes the positions of the points as a polyline, with `precision`
+    /// decimal places: 5 for Google's format, 6 for OSRM's. An empty segment
+    /// gives an empty string.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// segment.points.push(Waypoint::new_lat_lon(38.5, -120.2).unwrap());
+    /// segment.points.push(Waypoint::new_lat_lon(40.7, -120.95).unwrap());
+    /// segment.points.push(Waypoint::new_lat_lon(43.252, -126.453).unwrap());
+    ///
+    /// assert_eq!(segment.to_polyline(5), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+    /// ```
+    pub fn to_polyline(&self, precision: u32) -> String {
+        let factor = 10f64.powi(precision as i32);
+        let mut polyline = String::new();
+        let mut previous = (0, 0);
+        for point in &self.points {
+            let current = (
+                (point.lat() * factor).round() as i64,
+                (point.lon() * factor).round() as i64,
+            );
+            encode_value(current.0 - previous.0, &mut polyline);
+            encode_value(current.1 - previous.1, &mut polyline);
+            previous = current;
+        }
+        polyline
+    }
+
+    /// Decodes a polyline with `precision` decimal places, see
+    /// [`TrackSegment::to_polyline`]. The points only have a position.
+    ///
+    /// Characters outside of the polyline alphabet, a polyline that ends in
+    /// the middle of a point, changes that overflow the position, and
+    /// positions outside of the valid ranges give an error.
+    pub fn from_polyline(polyline: &str, precision: u32) -> GpxResult<TrackSegment> {
+        let factor = 10f64.powi(precision as i32);
+        let mut bytes = polyline.bytes().enumerate().peekable();
+        let mut segment = TrackSegment::new();
+        let (mut latitude, mut longitude) = (0i64, 0i64);
+        while bytes.peek().is_some() {
+            add_value(&mut latitude, &mut bytes, polyline.len())?;
+            add_value(&mut longitude, &mut bytes, polyline.len())?;
+            let point = Waypoint::new_lat_lon(latitude as f64 / factor, longitude as f64 / factor)?;
+            segment.points.push(point);
+        }
+        Ok(segment)
+    }
+}
+
+impl Track {
+    /// Encodes each segment as a polyline, see [`TrackSegment::to_polyline`].
+    pub fn to_polylines(&self, precision: u32) -> Vec<String> {
+        self.segments
+            .iter()
+            .map(|segment| segment.to_polyline(precision))
+            .collect()
+    }
+}
+
+fn encode_value(value: i64, polyline: &mut String) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x20 {
+        polyline.push((((value & 0x1f) | 0x20) as u8 + 63) as char);
+        value >>= 5;
+    }
+    polyline.push((value as u8 + 63) as char);
+}
+
+/// Decodes the next value from `bytes` and adds it to `total`, see
+/// [`decode_value`]. A sum that overflows is an error at the offset of the
+/// value.
+fn add_value(
+    total: &mut i64,
+    bytes: &mut Peekable<impl Iterator<Item = (usize, u8)>>,
+    end: usize,
+) -> GpxResult<()> {
+    let offset = bytes.peek().map_or(end, |&(offset, _)| offset);
+    let value = decode_value(bytes, end)?;
+    *total = total
+        .checked_add(value)
+        .ok_or(GpxError::InvalidPolyline(offset))?;
+    Ok(())
+}
+
+/// Decodes the next value from `bytes`, which are numbered by their offset.
+/// Errors name the offset of the offending byte, or `end` if the polyline
+/// ends too early.
+fn decode_value(bytes: &mut impl Iterator<Item = (usize, u8)>, end: usize) -> GpxResult<i64> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(5) {
+        let (offset, byte) = bytes.next().ok_or(GpxError::InvalidPolyline(end))?;
+        if !(63..=126).contains(&byte) {
+            return Err(GpxError::InvalidPolyline(offset));
+        }
+        let chunk = u64::from(byte - 63);
+        value |= (chunk & 0x1f) << shift;
+        if chunk < 0x20 {
+            return Ok(((value >> 1) as i64) ^ -((value & 1) as i64));
+        }
+    }
+    Err(GpxError::InvalidPolyline(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use geo_types::Point;
+
+    use super::encode_value;
+    use crate::errors::GpxError;
+    use crate::testutil::Random;
+    use crate::{Track, TrackSegment, Waypoint};
+
+    fn segment(coordinates: &[(f64, f64)]) -> TrackSegment {
+        TrackSegment {
+            points: coordinates
+                .iter()
+                .map(|&(lat, lon)| Waypoint::new(Point::new(lon, lat)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn coordinates(segment: &TrackSegment) -> Vec<(f64, f64)> {
+        segment
+            .points
+            .iter()
+            .map(|point| (point.lat(), point.lon()))
+            .collect()
+    }
+
+    #[test]
+    fn google_example() {
+        let example = segment(&[(38.5, -120.2), (40.7, -120.95), (43.252, -126.453)]);
+
+        assert_eq!(example.to_polyline(5), "_p~iF~ps|U_ulLnnqC_mqNvxq`@");
+        let decoded = TrackSegment::from_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+        assert_eq!(coordinates(&decoded), coordinates(&example));
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        // Without decimal places, the halves are exact.
+        let halves = segment(&[(2.5, -2.5)]);
+        let decoded = TrackSegment::from_polyline(&halves.to_polyline(0), 0).unwrap();
+        assert_eq!(coordinates(&decoded), vec![(3.0, -3.0)]);
+    }
+
+    #[test]
+    fn empty_segments() {
+        assert_eq!(TrackSegment::new().to_polyline(5), "");
+        assert!(TrackSegment::from_polyline("", 5)
+            .unwrap()
+            .points
+            .is_empty());
+
+        let mut track = Track::new();
+        track.segments.push(segment(&[(38.5, -120.2)]));
+        track.segments.push(TrackSegment::new());
+        assert_eq!(track.to_polylines(5), vec!["_p~iF~ps|U", ""]);
+    }
+
+    #[test]
+    fn invalid_polylines() {
+        // A space is not part of the alphabet.
+        assert!(matches!(
+            TrackSegment::from_polyline("_p~iF ps|U", 5),
+            Err(GpxError::InvalidPolyline(5))
+        ));
+        // The longitude is missing.
+        assert!(matches!(
+            TrackSegment::from_polyline("_p~iF", 5),
+            Err(GpxError::InvalidPolyline(5))
+        ));
+        // The last chunk of the latitude is missing.
+        assert!(matches!(
+            TrackSegment::from_polyline("_p~i", 5),
+            Err(GpxError::InvalidPolyline(4))
+        ));
+        // A change of the latitude that overflows, after a valid point.
+        let mut overflow = String::new();
+        encode_value(1, &mut overflow);
+        encode_value(0, &mut overflow);
+        let offset = overflow.len();
+        encode_value(i64::MAX, &mut overflow);
+        encode_value(0, &mut overflow);
+        assert!(matches!(
+            TrackSegment::from_polyline(&overflow, 5),
+            Err(GpxError::InvalidPolyline(o)) if o == offset
+        ));
+        // A latitude of 1000°.
+        let far = segment(&[(1000.0, 0.0)]).to_polyline(5);
+        assert!(matches!(
+            TrackSegment::from_polyline(&far, 5),
+            Err(GpxError::LonLatOutOfBoundsError(..))
+        ));
+    }
+
+    #[test]
+    fn round_trip_random_coordinates() {
+        let mut random = Random::new(0x2545_f491_4f6c_dd1d);
+        let mut random = move || random.unit();
+
+        for &precision in &[5, 6] {
+            for _ in 0..50 {
+                let points: Vec<(f64, f64)> = (0..20)
+                    .map(|_| (random() * 180.0 - 90.0, random() * 360.0 - 180.0))
+                    .collect();
+                let original = segment(&points);
+
+                let polyline = original.to_polyline(precision);
+                let decoded = TrackSegment::from_polyline(&polyline, precision).unwrap();
+
+                assert_eq!(decoded.points.len(), points.len());
+                let tolerance = 0.5 / 10f64.powi(precision as i32) + 1e-12;
+                for (&(lat, lon), (decoded_lat, decoded_lon)) in
+                    points.iter().zip(coordinates(&decoded))
+                {
+                    assert_approx_eq!(lat, decoded_lat, tolerance);
+                    assert_approx_eq!(lon, decoded_lon, tolerance);
+                }
+                assert_eq!(decoded.to_polyline(precision), polyline);
+            }
+        }
+    }
+}
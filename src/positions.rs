@@ -0,0 +1,144 @@
+//! positions records where in the source file elements were read from.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Serialize};
+use xml::common::TextPosition;
+
+/// A position in a source file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct SourcePosition {
+    /// Line, counting from 1.
+    pub line: u64,
+
+    /// Column in characters, counting from 1.
+    pub column: u64,
+}
+
+impl From<TextPosition> for SourcePosition {
+    fn from(position: TextPosition) -> SourcePosition {
+        SourcePosition {
+            line: position.row + 1,
+            column: position.column + 1,
+        }
+    }
+}
+
+/// Identifies an element of a [`Gpx`](crate::Gpx) by the indices leading to
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ElementPath {
+    /// The `<metadata>` element.
+    Metadata,
+    /// `waypoints[.0]`.
+    Waypoint(usize),
+    /// `routes[.0]`.
+    Route(usize),
+    /// `routes[.0].points[.1]`.
+    RoutePoint(usize, usize),
+    /// `tracks[.0]`.
+    Track(usize),
+    /// `tracks[.0].segments[.1]`.
+    TrackSegment(usize, usize),
+    /// `tracks[.0].segments[.1].points[.2]`.
+    TrackPoint(usize, usize, usize),
+}
+
+impl fmt::Display for ElementPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ElementPath::Metadata => write!(f, "metadata"),
+            ElementPath::Waypoint(w) => write!(f, "waypoints[{}]", w),
+            ElementPath::Route(r) => write!(f, "routes[{}]", r),
+            ElementPath::RoutePoint(r, p) => write!(f, "routes[{}].points[{}]", r, p),
+            ElementPath::Track(t) => write!(f, "tracks[{}]", t),
+            ElementPath::TrackSegment(t, s) => write!(f, "tracks[{}].segments[{}]", t, s),
+            ElementPath::TrackPoint(t, s, p) => {
+                write!(f, "tracks[{}].segments[{}].points[{}]", t, s, p)
+            }
+        }
+    }
+}
+
+/// The positions of the start tags of the metadata, waypoints, routes,
+/// route points, tracks, track segments and track points of a file, as
+/// returned by [`read_with_positions`](crate::read_with_positions).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SourcePositions {
+    positions: BTreeMap<ElementPath, SourcePosition>,
+}
+
+impl SourcePositions {
+    /// Gives the position of the start tag of the element at `path`.
+    pub fn get(&self, path: &ElementPath) -> Option<SourcePosition> {
+        self.positions.get(path).copied()
+    }
+
+    /// Gives all recorded positions, in the order of their paths.
+    pub fn iter(&self) -> impl Iterator<Item = (&ElementPath, &SourcePosition)> {
+        self.positions.iter()
+    }
+
+    /// Gives the number of recorded positions.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Whether no positions were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// The kinds of elements whose positions are recorded.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Positioned {
+    Metadata,
+    Waypoint,
+    Route,
+    RoutePoint,
+    Track,
+    TrackSegment,
+    TrackPoint,
+}
+
+/// Collects positions while parsing, keeping track of the indices of the
+/// route, track and segment being parsed.
+#[derive(Debug, Default)]
+pub(crate) struct PositionRecorder {
+    positions: SourcePositions,
+    route: usize,
+    track: usize,
+    segment: usize,
+}
+
+impl PositionRecorder {
+    pub(crate) fn record(&mut self, element: Positioned, index: usize, position: TextPosition) {
+        let path = match element {
+            Positioned::Metadata => ElementPath::Metadata,
+            Positioned::Waypoint => ElementPath::Waypoint(index),
+            Positioned::Route => {
+                self.route = index;
+                ElementPath::Route(index)
+            }
+            Positioned::RoutePoint => ElementPath::RoutePoint(self.route, index),
+            Positioned::Track => {
+                self.track = index;
+                ElementPath::Track(index)
+            }
+            Positioned::TrackSegment => {
+                self.segment = index;
+                ElementPath::TrackSegment(self.track, index)
+            }
+            Positioned::TrackPoint => ElementPath::TrackPoint(self.track, self.segment, index),
+        };
+        self.positions.positions.insert(path, position.into());
+    }
+
+    pub(crate) fn finish(self) -> SourcePositions {
+        self.positions
+    }
+}
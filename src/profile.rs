@@ -0,0 +1,290 @@
+//! profile gives the elevation along a track or route as pairs of distance
+//! and elevation, ready to be plotted.
+
+use crate::{Route, Track, TrackSegment, Waypoint};
+
+/// Options for [`Track::elevation_profile`] and the other profiles.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileOptions {
+    /// Whether the distance starts at 0 again in every segment of a track.
+    /// By default, it goes on from the end of the previous segment, without
+    /// the gap between the segments.
+    pub reset_per_segment: bool,
+
+    /// The number of evenly spaced samples to give instead of one pair per
+    /// point, with the elevations interpolated linearly.
+    pub samples: Option<usize>,
+
+    /// The number of pairs to average each elevation over, centered on it
+    /// and fewer at the ends. This is done after resampling.
+    pub smoothing_window: Option<usize>,
+}
+
+impl TrackSegment {
+    /// Gives the elevation profile of the segment, as pairs of the distance
+    /// from the first point in meters and the elevation, tuned by
+    /// `options`.
+    ///
+    /// Points without an elevation are left out, but the distance covered
+    /// through them is still counted. The distances never go down, and
+    /// repeat for repeated points.
+    ///
+    /// ```
+    /// use gpx::{ProfileOptions, TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// for (i, elevation) in [Some(400.0), None, Some(420.0)].iter().enumerate() {
+    ///     let mut point = Waypoint::new_lat_lon(47.0 + i as f64 * 0.001, 8.0).unwrap();
+    ///     point.elevation = *elevation;
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// let profile = segment.elevation_profile(&ProfileOptions::default());
+    /// assert_eq!(profile.len(), 2);
+    /// assert_eq!(profile[0], (0.0, 400.0));
+    /// assert!((profile[1].0 - 222.4).abs() < 0.1);
+    /// ```
+    pub fn elevation_profile(&self, options: &ProfileOptions) -> Vec<(f64, f64)> {
+        let distances = self.cumulative_distances();
+        finish(pairs(&self.points, &distances), options)
+    }
+}
+
+impl Track {
+    /// Gives the elevation profile of the track, see
+    /// [`TrackSegment::elevation_profile`].
+    ///
+    /// With [`ProfileOptions::reset_per_segment`], the profile of each
+    /// segment is resampled and smoothed on its own, and they follow each
+    /// other in the result. Otherwise the segments make up one profile.
+    pub fn elevation_profile(&self, options: &ProfileOptions) -> Vec<(f64, f64)> {
+        let distances = self.cumulative_distances(options.reset_per_segment);
+        let segments = self.segments.iter().zip(&distances);
+        if options.reset_per_segment {
+            segments
+                .flat_map(|(segment, distances)| finish(pairs(&segment.points, distances), options))
+                .collect()
+        } else {
+            let profile = segments
+                .flat_map(|(segment, distances)| pairs(&segment.points, distances))
+                .collect();
+            finish(profile, options)
+        }
+    }
+}
+
+impl Route {
+    /// Gives the elevation profile of the route, see
+    /// [`TrackSegment::elevation_profile`].
+    pub fn elevation_profile(&self, options: &ProfileOptions) -> Vec<(f64, f64)> {
+        let distances = route_distances(self);
+        finish(pairs(&self.points, &distances), options)
+    }
+}
+
+fn route_distances(route: &Route) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut previous: Option<&Waypoint> = None;
+    route
+        .points
+        .iter()
+        .map(|point| {
+            if let Some(previous) = previous {
+                total += previous.haversine_distance_to(point);
+            }
+            previous = Some(point);
+            total
+        })
+        .collect()
+}
+
+/// Pairs the distances with the elevations of the points that have one.
+fn pairs(points: &[Waypoint], distances: &[f64]) -> Vec<(f64, f64)> {
+    points
+        .iter()
+        .zip(distances)
+        .filter_map(|(point, distance)| {
+            let elevation = point.elevation.filter(|elevation| elevation.is_finite())?;
+            Some((*distance, elevation))
+        })
+        .collect()
+}
+
+fn finish(profile: Vec<(f64, f64)>, options: &ProfileOptions) -> Vec<(f64, f64)> {
+    let profile = match options.samples {
+        Some(samples) => resample(&profile, samples),
+        None => profile,
+    };
+    match options.smoothing_window {
+        Some(window) if window > 1 => smooth(&profile, window),
+        _ => profile,
+    }
+}
+
+/// Gives `samples` pairs evenly spaced from the first to the last distance
+/// of `profile`, interpolating the elevations.
+fn resample(profile: &[(f64, f64)], samples: usize) -> Vec<(f64, f64)> {
+    let (first, last) = match (profile.first(), profile.last()) {
+        (Some(first), Some(last)) => (first.0, last.0),
+        _ => return Vec::new(),
+    };
+    let mut upper = 0;
+    (0..samples)
+        .map(|i| {
+            let distance = if samples > 1 {
+                first + (last - first) * (i as f64 / (samples - 1) as f64)
+            } else {
+                first
+            };
+            // The last sample is exactly at the end, rounding aside.
+            let distance = distance.min(last);
+            while profile[upper].0 < distance {
+                upper += 1;
+            }
+            if upper == 0 || profile[upper].0 == distance {
+                return (distance, profile[upper].1);
+            }
+            let (a, b) = (profile[upper - 1], profile[upper]);
+            let fraction = (distance - a.0) / (b.0 - a.0);
+            (distance, a.1 * (1.0 - fraction) + b.1 * fraction)
+        })
+        .collect()
+}
+
+/// Averages each elevation of `profile` with its neighbours, `window` pairs
+/// in all.
+fn smooth(profile: &[(f64, f64)], window: usize) -> Vec<(f64, f64)> {
+    let before = (window - 1) / 2;
+    let after = window - 1 - before;
+    (0..profile.len())
+        .map(|i| {
+            let neighbours = &profile[i.saturating_sub(before)..(i + after + 1).min(profile.len())];
+            let sum: f64 = neighbours.iter().map(|(_, elevation)| elevation).sum();
+            (profile[i].0, sum / neighbours.len() as f64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProfileOptions;
+    use crate::{Route, Track, TrackSegment, Waypoint, EARTH_RADIUS_M};
+
+    /// The distance of 0.001° along a meridian.
+    const STEP: f64 = EARTH_RADIUS_M * 0.001 * std::f64::consts::PI / 180.0;
+
+    /// A climb north along a meridian, 10 meters per point.
+    fn climb(points: usize) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        for i in 0..points {
+            let mut point = Waypoint::new_lat_lon(i as f64 * 0.001, 8.0).unwrap();
+            point.elevation = Some(500.0 + i as f64 * 10.0);
+            segment.points.push(point);
+        }
+        segment
+    }
+
+    fn assert_profile(profile: &[(f64, f64)], expected: &[(f64, f64)]) {
+        assert_eq!(profile.len(), expected.len(), "{:?}", profile);
+        for (actual, expected) in profile.iter().zip(expected) {
+            assert!((actual.0 - expected.0).abs() < 1e-6, "{:?}", profile);
+            assert!((actual.1 - expected.1).abs() < 1e-9, "{:?}", profile);
+        }
+    }
+
+    #[test]
+    fn climb_profile() {
+        let segment = climb(5);
+        let expected: Vec<(f64, f64)> = (0..5)
+            .map(|i| (i as f64 * STEP, 500.0 + i as f64 * 10.0))
+            .collect();
+        assert_profile(
+            &segment.elevation_profile(&ProfileOptions::default()),
+            &expected,
+        );
+
+        let route = Route {
+            points: segment.points.clone(),
+            ..Default::default()
+        };
+        assert_profile(
+            &route.elevation_profile(&ProfileOptions::default()),
+            &expected,
+        );
+
+        let resampled = ProfileOptions {
+            samples: Some(9),
+            ..Default::default()
+        };
+        let expected: Vec<(f64, f64)> = (0..9)
+            .map(|i| (i as f64 * STEP / 2.0, 500.0 + i as f64 * 5.0))
+            .collect();
+        assert_profile(&segment.elevation_profile(&resampled), &expected);
+
+        let smoothed = ProfileOptions {
+            smoothing_window: Some(3),
+            ..Default::default()
+        };
+        let profile = segment.elevation_profile(&smoothed);
+        let elevations: Vec<f64> = profile.iter().map(|(_, elevation)| *elevation).collect();
+        assert_eq!(elevations, [505.0, 510.0, 520.0, 530.0, 535.0]);
+    }
+
+    #[test]
+    fn missing_elevations_keep_the_distance() {
+        let mut segment = climb(10);
+        for point in &mut segment.points[3..8] {
+            point.elevation = None;
+        }
+        // A repeated point.
+        segment.points.insert(1, segment.points[1].clone());
+
+        let profile = segment.elevation_profile(&ProfileOptions::default());
+        let distances: Vec<f64> = profile.iter().map(|(distance, _)| *distance).collect();
+        assert_eq!(profile.len(), 6);
+        assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(distances[1], distances[2]);
+        assert!((distances[3] - 2.0 * STEP).abs() < 1e-6);
+        assert!((distances[4] - 8.0 * STEP).abs() < 1e-6);
+        assert_eq!(profile[4].1, 580.0);
+
+        let resampled = ProfileOptions {
+            samples: Some(10),
+            ..Default::default()
+        };
+        let profile = segment.elevation_profile(&resampled);
+        assert!((profile[9].0 - 9.0 * STEP).abs() < 1e-6);
+        // Interpolated across the gap.
+        assert!((profile[5].1 - 550.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn track_profile_across_segments() {
+        let mut track = Track::new();
+        track.segments.push(climb(3));
+        track.segments.push(TrackSegment::new());
+        track.segments.push(climb(3));
+
+        let continuous = track.elevation_profile(&ProfileOptions::default());
+        let distances: Vec<f64> = continuous.iter().map(|(distance, _)| *distance).collect();
+        assert_eq!(distances.len(), 6);
+        assert!((distances[5] - 4.0 * STEP).abs() < 1e-6);
+        assert_eq!(distances[2], distances[3]);
+
+        let reset = ProfileOptions {
+            reset_per_segment: true,
+            samples: Some(2),
+            ..Default::default()
+        };
+        let profile = track.elevation_profile(&reset);
+        assert_profile(
+            &profile,
+            &[
+                (0.0, 500.0),
+                (2.0 * STEP, 520.0),
+                (0.0, 500.0),
+                (2.0 * STEP, 520.0),
+            ],
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! quirks repairs the known bugs of particular producers after reading,
+//! recognized by the `creator` attribute of the document.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::Gpx;
+
+/// A quirk registered with [`ReadOptions::register_quirk`](crate::ReadOptions::register_quirk).
+type Quirk = Arc<dyn Fn(&mut Gpx) + Send + Sync>;
+
+/// A built-in quirk and the creator pattern it applies to.
+type BuiltIn = (&'static str, fn(&mut Gpx));
+
+/// The built-in quirks, by creator pattern.
+///
+/// Each fix only changes what is broken in the way described, so a file
+/// from a matching creator without the bug comes through unchanged.
+const BUILT_IN: &[BuiltIn] = &[
+    // Handhelds whose firmware predates the GPS week rollover of April 2019
+    // report times 1024 weeks in the past from then on.
+    ("eTrex*", repair_week_rollover),
+    ("GPSMAP 60*", repair_week_rollover),
+    // Activity exports that start every segment with the recorded first
+    // point written twice, which gives a zero-length first leg.
+    ("StravaGPX*", drop_repeated_first_points),
+];
+
+/// The quirks to apply after reading, see
+/// [`ReadOptions::apply_creator_quirks`](crate::ReadOptions::apply_creator_quirks).
+#[derive(Clone, Default)]
+pub(crate) struct Quirks {
+    pub(crate) built_in: bool,
+    pub(crate) registered: Vec<(String, Quirk)>,
+}
+
+impl Quirks {
+    /// Applies the quirks whose pattern matches the creator of `gpx`: the
+    /// built-in ones first if enabled, then the registered ones in the
+    /// order they were registered.
+    pub(crate) fn apply(&self, gpx: &mut Gpx) {
+        let creator = match &gpx.creator {
+            Some(creator) => creator.clone(),
+            None => return,
+        };
+        if self.built_in {
+            for (pattern, quirk) in BUILT_IN {
+                if matches(pattern, &creator) {
+                    quirk(gpx);
+                }
+            }
+        }
+        for (pattern, quirk) in &self.registered {
+            if matches(pattern, &creator) {
+                quirk(gpx);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Quirks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let patterns: Vec<&str> = self.registered.iter().map(|(p, _)| p.as_str()).collect();
+        f.debug_struct("Quirks")
+            .field("built_in", &self.built_in)
+            .field("registered", &patterns)
+            .finish()
+    }
+}
+
+/// Whether `creator` matches `pattern`, in which `*` stands for any run of
+/// characters and everything else has to match exactly.
+fn matches(pattern: &str, creator: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match creator.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // No `*` at all.
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn repair_week_rollover(gpx: &mut Gpx) {
+    for track in &mut gpx.tracks {
+        track.repair_week_rollover();
+    }
+}
+
+fn drop_repeated_first_points(gpx: &mut Gpx) {
+    let segments = gpx.tracks.iter_mut().flat_map(|track| &mut track.segments);
+    for segment in segments {
+        if segment.points.len() >= 2 && segment.points[0] == segment.points[1] {
+            segment.points.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn creator_patterns() {
+        let cases = [
+            ("StravaGPX", "StravaGPX", true),
+            ("StravaGPX", "StravaGPX iPhone", false),
+            ("StravaGPX*", "StravaGPX iPhone", true),
+            ("*Connect", "Garmin Connect", true),
+            ("*Connect", "Garmin Connect IQ", false),
+            ("Garmin*64*", "Garmin GPSMAP 64s", true),
+            ("Garmin*64*", "Garmin eTrex 32x", false),
+            ("*", "", true),
+            ("a*a", "a", false),
+            ("", "", true),
+        ];
+        for (pattern, creator, expected) in cases.iter() {
+            assert_eq!(
+                matches(pattern, creator),
+                *expected,
+                "{} {}",
+                pattern,
+                creator
+            );
+        }
+    }
+}
@@ -3,14 +3,299 @@
 use std::io::Read;
 
 use crate::errors::GpxResult;
-use crate::parser::{create_context, gpx};
+use crate::parser::{create_context_with_options, gpx};
+use crate::positions::SourcePositions;
+use crate::quirks::Quirks;
 use crate::{Gpx, GpxVersion};
 
+/// Options to tune how GPX files are read.
+///
+/// The defaults match the behavior of [`read`]. Options are set in a builder
+/// style and the result is handed to [`read_with_options`].
+///
+/// ```
+/// use gpx::ReadOptions;
+///
+/// let options = ReadOptions::new().enforce_element_order(true);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    pub(crate) enforce_element_order: bool,
+    pub(crate) preserve_unknown_attributes: bool,
+    pub(crate) record_source_positions: bool,
+    pub(crate) salvage: bool,
+    pub(crate) max_string_length: Option<usize>,
+    pub(crate) long_strings: LongStringPolicy,
+    pub(crate) version_compat: VersionCompat,
+    pub(crate) quirks: Quirks,
+    pub(crate) leap_seconds: LeapSecondPolicy,
+}
+
+/// What happens to strings longer than [`ReadOptions::max_string_length`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum LongStringPolicy {
+    /// Fail with a
+    /// [`GpxError::StringTooLong`](crate::errors::GpxError::StringTooLong).
+    #[default]
+    Error,
+    /// Keep as many bytes from the start of the string as the limit allows,
+    /// without splitting a character.
+    Truncate,
+}
+
+/// Which GPX version the elements of a file may come from, see
+/// [`ReadOptions::version_compat`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum VersionCompat {
+    /// Only elements of the version the file declares.
+    #[default]
+    Strict,
+    /// Elements of GPX 1.0 and 1.1, whatever the file declares.
+    Mixed,
+}
+
+/// What happens to times with a leap second, such as
+/// `2016-12-31T23:59:60Z`, see [`ReadOptions::leap_seconds`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum LeapSecondPolicy {
+    /// Fail with a
+    /// [`GpxError::LeapSecondTimestamp`](crate::errors::GpxError::LeapSecondTimestamp).
+    #[default]
+    Reject,
+    /// Read the second as `:59.999999`, dropping any fractional seconds.
+    Clamp,
+}
+
+impl ReadOptions {
+    /// Creates the default options.
+    pub fn new() -> ReadOptions {
+        Default::default()
+    }
+
+    /// Whether child elements must appear in the order prescribed by the
+    /// GPX schema.
+    ///
+    /// By default children are accepted in any order. When enabled, a child
+    /// appearing before one of its preceding siblings in the schema sequence
+    /// (for example `<time>` before `<ele>` in a waypoint) gives a
+    /// [`GpxError::ElementOutOfOrder`](crate::errors::GpxError::ElementOutOfOrder).
+    pub fn enforce_element_order(mut self, enforce: bool) -> ReadOptions {
+        self.enforce_element_order = enforce;
+        self
+    }
+
+    /// Whether attributes the GPX schema does not define are kept.
+    ///
+    /// Such attributes never cause an error, but by default they are
+    /// dropped. When enabled, they are stored in the `unknown_attributes`
+    /// field of the enclosing [`Gpx`], [`Metadata`](crate::Metadata),
+    /// [`Waypoint`](crate::Waypoint), [`Route`](crate::Route) or
+    /// [`Track`](crate::Track), and are written back out by
+    /// [`write`](crate::write). This covers the attributes of the element
+    /// itself and of its simple, text-only children, such as `xml:lang` on
+    /// `<name>` or `<desc>`.
+    ///
+    /// ```
+    /// use gpx::{read_with_options, ReadOptions};
+    ///
+    /// let data = r#"<gpx version="1.1"><trk><name xml:lang="en">Loop</name></trk></gpx>"#;
+    /// let options = ReadOptions::new().preserve_unknown_attributes(true);
+    /// let gpx = read_with_options(data.as_bytes(), options).unwrap();
+    ///
+    /// let stashed = &gpx.tracks[0].unknown_attributes[0];
+    /// assert_eq!(stashed.element, "name");
+    /// assert_eq!(stashed.attributes[0].name.local_name, "lang");
+    /// assert_eq!(stashed.attributes[0].value, "en");
+    /// ```
+    pub fn preserve_unknown_attributes(mut self, preserve: bool) -> ReadOptions {
+        self.preserve_unknown_attributes = preserve;
+        self
+    }
+
+    /// Whether to record where in the file elements start, see
+    /// [`read_with_positions`].
+    ///
+    /// This is off by default, as the positions take up memory for every
+    /// point. Readers other than [`read_with_positions`] ignore the option.
+    pub fn record_source_positions(mut self, record: bool) -> ReadOptions {
+        self.record_source_positions = record;
+        self
+    }
+
+    /// Limits the length in bytes of the text of elements that only hold a
+    /// string, such as `<name>`, `<desc>`, `<cmt>`, `<keywords>` or the text
+    /// of a link. What happens to longer strings is given by
+    /// [`ReadOptions::long_strings`]. By default there is no limit.
+    ///
+    /// With [`LongStringPolicy::Error`], the XML parser is told to stop at
+    /// the limit too, so an oversized value is never read into memory as a
+    /// whole. This applies to all text of the document, including comments
+    /// and extensions, although never below 4 KiB, and the length in the
+    /// error is then how much was read before stopping. Truncating has to
+    /// read each value completely before cutting it.
+    ///
+    /// ```
+    /// use gpx::errors::GpxError;
+    /// use gpx::{read_with_options, LongStringPolicy, ReadOptions};
+    ///
+    /// let data = r#"<gpx version="1.1"><trk><name>Lakeside loop</name></trk></gpx>"#;
+    ///
+    /// let strict = ReadOptions::new().max_string_length(8);
+    /// assert!(matches!(
+    ///     read_with_options(data.as_bytes(), strict),
+    ///     Err(GpxError::StringTooLong { element: "name", length: 13, limit: 8 })
+    /// ));
+    ///
+    /// let truncating = ReadOptions::new()
+    ///     .max_string_length(8)
+    ///     .long_strings(LongStringPolicy::Truncate);
+    /// let gpx = read_with_options(data.as_bytes(), truncating).unwrap();
+    /// assert_eq!(gpx.tracks[0].name.as_deref(), Some("Lakeside"));
+    /// ```
+    pub fn max_string_length(mut self, limit: usize) -> ReadOptions {
+        self.max_string_length = Some(limit);
+        self
+    }
+
+    /// What happens to strings longer than
+    /// [`ReadOptions::max_string_length`]. They are an error by default.
+    pub fn long_strings(mut self, policy: LongStringPolicy) -> ReadOptions {
+        self.long_strings = policy;
+        self
+    }
+
+    /// Whether files may mix elements of GPX 1.0 and 1.1.
+    ///
+    /// Some producers declare one version but write elements of the other,
+    /// which is an error by default. With [`VersionCompat::Mixed`] both are
+    /// read into the same fields: `<url>` and `<urlname>` become a link,
+    /// the GPX 1.0 `<author>` and `<email>` become the author of the
+    /// metadata, and a `<metadata>` element in a GPX 1.0 file is read as in
+    /// GPX 1.1. Where both give a value, the one from `<metadata>` is kept.
+    /// The [`Gpx::version`](crate::Gpx::version) is still the declared one.
+    ///
+    /// ```
+    /// use gpx::{read_with_options, ReadOptions, VersionCompat};
+    ///
+    /// let data = r#"<gpx version="1.1">
+    ///   <wpt lat="47.1" lon="8.5">
+    ///     <url>https://example.com/summit</url>
+    ///     <urlname>Summit</urlname>
+    ///   </wpt>
+    /// </gpx>"#;
+    /// assert!(read_with_options(data.as_bytes(), ReadOptions::new()).is_err());
+    ///
+    /// let mixed = ReadOptions::new().version_compat(VersionCompat::Mixed);
+    /// let gpx = read_with_options(data.as_bytes(), mixed).unwrap();
+    /// assert_eq!(gpx.waypoints[0].extras().links[0].href, "https://example.com/summit");
+    /// assert_eq!(gpx.waypoints[0].extras().links[0].text.as_deref(), Some("Summit"));
+    /// ```
+    pub fn version_compat(mut self, compat: VersionCompat) -> ReadOptions {
+        self.version_compat = compat;
+        self
+    }
+
+    /// What happens to times with a second of 60, which some GNSS receivers
+    /// write for leap seconds. The `time` crate, like most software, has no
+    /// leap seconds, so they are rejected by default.
+    ///
+    /// With [`LeapSecondPolicy::Clamp`], the second becomes `:59.999999`,
+    /// so the point is kept, still comes after the ones before it, and
+    /// comes before the next whole second. The offset of the time is kept.
+    ///
+    /// ```
+    /// use gpx::errors::GpxError;
+    /// use gpx::{read_with_options, LeapSecondPolicy, ReadOptions};
+    ///
+    /// let data = r#"<gpx version="1.1">
+    ///   <wpt lat="47.1" lon="8.5"><time>2016-12-31T23:59:60Z</time></wpt>
+    /// </gpx>"#;
+    /// assert!(matches!(
+    ///     read_with_options(data.as_bytes(), ReadOptions::new()),
+    ///     Err(GpxError::LeapSecondTimestamp(time)) if time == "2016-12-31T23:59:60Z"
+    /// ));
+    ///
+    /// let clamping = ReadOptions::new().leap_seconds(LeapSecondPolicy::Clamp);
+    /// let gpx = read_with_options(data.as_bytes(), clamping).unwrap();
+    /// let time = time::OffsetDateTime::from(gpx.waypoints[0].time.unwrap());
+    /// assert_eq!((time.second(), time.microsecond()), (59, 999_999));
+    /// ```
+    pub fn leap_seconds(mut self, policy: LeapSecondPolicy) -> ReadOptions {
+        self.leap_seconds = policy;
+        self
+    }
+
+    /// Whether to repair the known bugs of particular producers, recognized
+    /// by the `creator` attribute of the document, once it is read.
+    ///
+    /// The built-in table moves times in tracks from older Garmin eTrex and
+    /// GPSMAP 60 handhelds forward across a GPS week rollover, see
+    /// [`Track::repair_week_rollover`](crate::Track::repair_week_rollover),
+    /// and drops a first track point that `StravaGPX` repeated at the start
+    /// of a segment. This is off by default, so files are read as they are.
+    ///
+    /// Quirks are applied by [`read_with_options`], [`read_with_positions`]
+    /// and [`read_partial_with_options`], but not when reading
+    /// [`events`](crate::events). The source positions are those of the
+    /// elements as they are in the file.
+    pub fn apply_creator_quirks(mut self, apply: bool) -> ReadOptions {
+        self.quirks.built_in = apply;
+        self
+    }
+
+    /// Registers a quirk of your own, which is applied to documents whose
+    /// creator matches `creator_pattern` once they are read, after the
+    /// built-in ones, whether or not [`ReadOptions::apply_creator_quirks`]
+    /// is enabled. In the pattern, `*` stands for any run of characters and
+    /// everything else has to match exactly. Documents without a creator
+    /// are left alone.
+    ///
+    /// ```
+    /// use gpx::{read_with_options, ReadOptions};
+    ///
+    /// let data = r#"<gpx version="1.1" creator="Tracker 2.3">
+    ///   <wpt lat="47.1" lon="8.5"><ele>40800</ele></wpt>
+    /// </gpx>"#;
+    ///
+    /// // This tracker writes elevations in centimeters.
+    /// let options = ReadOptions::new().register_quirk(
+    ///     "Tracker *",
+    ///     Box::new(|gpx| {
+    ///         for waypoint in &mut gpx.waypoints {
+    ///             waypoint.elevation = waypoint.elevation.map(|ele| ele / 100.0);
+    ///         }
+    ///     }),
+    /// );
+    /// let gpx = read_with_options(data.as_bytes(), options).unwrap();
+    /// assert_eq!(gpx.waypoints[0].elevation, Some(408.0));
+    /// ```
+    pub fn register_quirk(
+        mut self,
+        creator_pattern: &str,
+        f: Box<dyn Fn(&mut Gpx) + Send + Sync>,
+    ) -> ReadOptions {
+        self.quirks
+            .registered
+            .push((creator_pattern.to_owned(), f.into()));
+        self
+    }
+}
+
 /// Reads an activity in GPX format.
 ///
 /// Takes any `std::io::Read` as its reader, and returns a
 /// `Result<Gpx>`.
 ///
+/// The reader is buffered internally, so there is no need to wrap it in a
+/// `BufReader`. Streams such as HTTP response bodies can be read directly:
+/// reads may return any amount of data, interrupted reads are retried, and
+/// anything after the closing `</gpx>` tag is ignored.
+///
+/// Gzip and zip data, PDF documents and FIT files are recognized from their
+/// first bytes, and give a
+/// [`GpxError::LikelyWrongFormat`](crate::errors::GpxError::LikelyWrongFormat)
+/// instead of an XML syntax error.
+///
 /// ```
 /// use std::io::BufReader;
 /// use gpx::read;
@@ -33,5 +318,104 @@ use crate::{Gpx, GpxVersion};
 /// }
 /// ```
 pub fn read<R: Read>(reader: R) -> GpxResult<Gpx> {
-    gpx::consume(&mut create_context(reader, GpxVersion::Unknown))
+    read_with_options(reader, ReadOptions::default())
+}
+
+/// Reads an activity in GPX format, using the given [`ReadOptions`].
+///
+/// ```
+/// use gpx::{read_with_options, ReadOptions};
+///
+/// let data = "<gpx version=\"1.1\"><wpt lat=\"1\" lon=\"2\"><time>2022-01-01T00:00:00Z</time><ele>4</ele></wpt></gpx>";
+///
+/// assert!(read_with_options(data.as_bytes(), ReadOptions::new()).is_ok());
+///
+/// let strict = ReadOptions::new().enforce_element_order(true);
+/// assert!(read_with_options(data.as_bytes(), strict).is_err());
+/// ```
+pub fn read_with_options<R: Read>(reader: R, options: ReadOptions) -> GpxResult<Gpx> {
+    let quirks = options.quirks.clone();
+    let mut gpx = gpx::consume(&mut create_context_with_options(
+        reader,
+        GpxVersion::Unknown,
+        options,
+    ))?;
+    quirks.apply(&mut gpx);
+    Ok(gpx)
+}
+
+/// Reads an activity in GPX format, using the given [`ReadOptions`], and
+/// gives the positions in the file where its elements start.
+///
+/// Positions are recorded for the metadata, waypoints, routes, route points,
+/// tracks, track segments and track points, and point at their start tags.
+/// They are only recorded if [`ReadOptions::record_source_positions`] is
+/// enabled; otherwise the returned [`SourcePositions`] are empty.
+///
+/// ```
+/// use gpx::{read_with_positions, ElementPath, ReadOptions};
+///
+/// let data = "<gpx version=\"1.1\">
+/// <trk><trkseg>
+///   <trkpt lat=\"1\" lon=\"2\"></trkpt>
+///   <trkpt lat=\"1\" lon=\"3\"></trkpt>
+/// </trkseg></trk>
+/// </gpx>";
+///
+/// let options = ReadOptions::new().record_source_positions(true);
+/// let (gpx, positions) = read_with_positions(data.as_bytes(), options).unwrap();
+///
+/// let position = positions.get(&ElementPath::TrackPoint(0, 0, 1)).unwrap();
+/// assert_eq!((position.line, position.column), (4, 3));
+/// ```
+pub fn read_with_positions<R: Read>(
+    reader: R,
+    options: ReadOptions,
+) -> GpxResult<(Gpx, SourcePositions)> {
+    let quirks = options.quirks.clone();
+    let mut context = create_context_with_options(reader, GpxVersion::Unknown, options);
+    let mut gpx = gpx::consume(&mut context)?;
+    quirks.apply(&mut gpx);
+    Ok((gpx, context.take_positions()))
+}
+
+/// Reads as much as possible of a GPX document that may be broken, for
+/// example because the program writing it crashed half-way.
+///
+/// Unlike [`read`], this always gives a [`Gpx`]: when an error is
+/// encountered, it holds everything parsed up to that point, and the error
+/// is returned next to it. Routes, tracks and track segments that were
+/// interrupted are kept with the points completed before the error. The
+/// point being parsed when the error occurred is discarded.
+///
+/// ```
+/// use gpx::read_partial;
+///
+/// let data = r#"<gpx version="1.1"><trk><trkseg>
+///     <trkpt lat="1" lon="2"></trkpt>
+///     <trkpt lat="1" lon="3"><ele>12"#;
+///
+/// let (result, gpx) = read_partial(data.as_bytes());
+/// assert!(result.is_err());
+/// assert_eq!(gpx.tracks[0].segments[0].points.len(), 1);
+/// ```
+pub fn read_partial<R: Read>(reader: R) -> (GpxResult<()>, Gpx) {
+    read_partial_with_options(reader, ReadOptions::default())
+}
+
+/// Reads as much as possible of a GPX document that may be broken, using
+/// the given [`ReadOptions`]. See [`read_partial`].
+pub fn read_partial_with_options<R: Read>(
+    reader: R,
+    mut options: ReadOptions,
+) -> (GpxResult<()>, Gpx) {
+    options.salvage = true;
+    let quirks = options.quirks.clone();
+    let mut gpx = Gpx::default();
+    let result = gpx::consume_into(
+        &mut create_context_with_options(reader, GpxVersion::Unknown, options),
+        &mut gpx,
+    );
+    quirks.apply(&mut gpx);
+    (result, gpx)
 }
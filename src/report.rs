@@ -0,0 +1,496 @@
+//! report describes what an operation that removes or changes points did to
+//! a document, as returned by [`Gpx::remove_spikes`] and the like.
+
+use std::fmt;
+
+#[cfg(feature = "use-serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Gpx, Track, TrackSegment};
+
+/// What one or more operations did to the points of a document: waypoints,
+/// route points and track points alike.
+///
+/// The counts always balance: `before == after + removed`. Points that an
+/// operation only moves, such as into a new segment, are neither removed nor
+/// modified.
+///
+/// Operations on a single [`Track`] report its segments as those of track 0,
+/// and operations on a single [`TrackSegment`] report it as segment 0 of
+/// track 0.
+///
+/// ```
+/// use gpx::{Gpx, Track, TrackSegment, Waypoint};
+///
+/// let mut segment = TrackSegment::new();
+/// for _ in 0..3 {
+///     segment.points.push(Waypoint::new_lat_lon(47.0, 8.0).unwrap());
+/// }
+/// let mut track = Track::new();
+/// track.segments.push(segment);
+/// let mut gpx = Gpx::default();
+/// gpx.tracks.push(track);
+///
+/// let report = gpx.dedup_exact(6);
+/// assert_eq!((report.before, report.after, report.removed), (3, 1, 2));
+/// assert_eq!(report.to_string(), "dedup_exact: removed 2 and modified 0 of 3 points");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct ChangeReport {
+    /// The names of the operations, in the order they ran, such as
+    /// `remove_spikes`.
+    pub operations: Vec<String>,
+
+    /// The number of points before the first operation.
+    pub before: usize,
+
+    /// The number of points after the last operation.
+    pub after: usize,
+
+    /// The number of points removed.
+    pub removed: usize,
+
+    /// The number of points that were kept but changed.
+    pub modified: usize,
+
+    /// The track segments that had points removed or changed, ordered by
+    /// track and segment.
+    pub segments: Vec<SegmentChange>,
+}
+
+/// What happened to the points of one track segment, as part of a
+/// [`ChangeReport`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+pub struct SegmentChange {
+    /// The index of the track, before the operation.
+    pub track: usize,
+
+    /// The index of the segment in the track, before the operation.
+    pub segment: usize,
+
+    /// The number of points before.
+    pub before: usize,
+
+    /// The number of points after, in this segment and any segments split
+    /// off from it.
+    pub after: usize,
+
+    /// The number of points removed.
+    pub removed: usize,
+
+    /// The number of points that were kept but changed.
+    pub modified: usize,
+}
+
+impl ChangeReport {
+    /// Starts the report of `operation` on a document with `before` points.
+    pub(crate) fn new(operation: &str, before: usize) -> ChangeReport {
+        ChangeReport {
+            operations: vec![operation.to_owned()],
+            before,
+            after: before,
+            ..Default::default()
+        }
+    }
+
+    /// Reports `operation` on a single segment, which had `before` and has
+    /// `after` points.
+    pub(crate) fn of_segment(
+        operation: &str,
+        before: usize,
+        after: usize,
+        modified: usize,
+    ) -> ChangeReport {
+        let mut report = ChangeReport::new(operation, before);
+        report.record_segment(0, 0, before, after, modified);
+        report
+    }
+
+    /// Counts points removed or changed outside of track segments.
+    pub(crate) fn record(&mut self, removed: usize, modified: usize) {
+        self.after -= removed;
+        self.removed += removed;
+        self.modified += modified;
+    }
+
+    /// Counts the changes of the points of a segment, which had `before`
+    /// and has `after` points. A segment that gained points, which can only
+    /// have been moved there, counts none as removed.
+    pub(crate) fn record_segment(
+        &mut self,
+        track: usize,
+        segment: usize,
+        before: usize,
+        after: usize,
+        modified: usize,
+    ) {
+        let removed = before.saturating_sub(after);
+        if removed == 0 && modified == 0 {
+            return;
+        }
+        self.record(removed, modified);
+        self.segments.push(SegmentChange {
+            track,
+            segment,
+            before,
+            after,
+            removed,
+            modified,
+        });
+    }
+
+    /// Counts the changes that `part`, the report of an operation on a
+    /// single segment, has for segment `segment` of track `track`.
+    pub(crate) fn record_part(&mut self, track: usize, segment: usize, part: &ChangeReport) {
+        self.record_segment(track, segment, part.before, part.after, part.modified);
+    }
+
+    /// Whether no point was removed or changed.
+    pub fn is_unchanged(&self) -> bool {
+        self.removed == 0 && self.modified == 0
+    }
+
+    /// Adds the changes of `other`, an operation on the document this
+    /// report left behind, so that the report sums up both.
+    ///
+    /// The segments of both reports are combined by their indices, which
+    /// only match if the first operation didn't remove tracks or segments.
+    pub fn merge(&mut self, other: ChangeReport) {
+        if self.operations.is_empty() {
+            *self = other;
+            return;
+        }
+        self.operations.extend(other.operations);
+        self.after = other.after;
+        self.removed += other.removed;
+        self.modified += other.modified;
+        for change in other.segments {
+            let key = (change.track, change.segment);
+            match self
+                .segments
+                .binary_search_by_key(&key, |existing| (existing.track, existing.segment))
+            {
+                Ok(index) => {
+                    let existing = &mut self.segments[index];
+                    existing.after = change.after;
+                    existing.removed += change.removed;
+                    existing.modified += change.modified;
+                }
+                Err(index) => self.segments.insert(index, change),
+            }
+        }
+    }
+}
+
+impl fmt::Display for ChangeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: removed {} and modified {} of {} points",
+            self.operations.join(", "),
+            self.removed,
+            self.modified,
+            self.before
+        )
+    }
+}
+
+impl Track {
+    /// Runs `f` on every segment, and reports the changes it reports as
+    /// done by `operation`.
+    pub(crate) fn report_segments(
+        &mut self,
+        operation: &str,
+        mut f: impl FnMut(&mut TrackSegment) -> ChangeReport,
+    ) -> ChangeReport {
+        let mut report = ChangeReport::new(operation, self.point_count());
+        for (s, segment) in self.segments.iter_mut().enumerate() {
+            report.record_part(0, s, &f(segment));
+        }
+        report
+    }
+}
+
+impl Gpx {
+    /// Runs `f` on every track segment, and reports the changes it reports
+    /// as done by `operation`.
+    pub(crate) fn report_segments(
+        &mut self,
+        operation: &str,
+        mut f: impl FnMut(&mut TrackSegment) -> ChangeReport,
+    ) -> ChangeReport {
+        self.bump_generation();
+        let mut report = ChangeReport::new(operation, self.all_points().count());
+        for (t, track) in self.tracks.iter_mut().enumerate() {
+            for (s, segment) in track.segments.iter_mut().enumerate() {
+                report.record_part(t, s, &f(segment));
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use time::OffsetDateTime;
+
+    use super::{ChangeReport, SegmentChange};
+    use crate::{
+        read, AccuracyOptions, ExtensionsPolicy, Gpx, KeepPolicy, SynthesizeTimesOptions,
+        TextNormalizeOptions, Tolerance, GPS_WEEK_ROLLOVER,
+    };
+
+    fn fixture() -> Gpx {
+        read(File::open("tests/fixtures/change_report.gpx").unwrap()).unwrap()
+    }
+
+    /// Checks that `report` balances and matches what is left of `gpx`.
+    fn assert_balanced(report: &ChangeReport, gpx: &Gpx) {
+        assert_balanced_with(report, 21, gpx.all_points().count());
+    }
+
+    /// Checks that `report` balances, starting with `before` and leaving
+    /// `after` points.
+    fn assert_balanced_with(report: &ChangeReport, before: usize, after: usize) {
+        assert_eq!(report.before, before, "{}", report);
+        assert_eq!(report.before, report.after + report.removed, "{}", report);
+        assert_eq!(report.after, after, "{}", report);
+        let segments = report.segments.iter();
+        assert!(segments.clone().map(|change| change.removed).sum::<usize>() <= report.removed);
+        assert!(
+            segments
+                .clone()
+                .map(|change| change.modified)
+                .sum::<usize>()
+                <= report.modified
+        );
+        for change in segments {
+            assert_eq!(change.before, change.after + change.removed, "{:?}", change);
+        }
+    }
+
+    fn change(track: usize, segment: usize, before: usize, after: usize) -> SegmentChange {
+        SegmentChange {
+            track,
+            segment,
+            before,
+            after,
+            removed: before - after,
+            modified: 0,
+        }
+    }
+
+    #[test]
+    fn operations_balance() {
+        let mut gpx = fixture();
+        let report = gpx.remove_spikes(50.0);
+        assert_balanced(&report, &gpx);
+        assert_eq!(report.operations, ["remove_spikes"]);
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut gpx = fixture();
+        let report = gpx.remove_spikes_by_distance(1000.0);
+        assert_balanced(&report, &gpx);
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut gpx = fixture();
+        let report = gpx.dedup_exact(6);
+        assert_balanced(&report, &gpx);
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut gpx = fixture();
+        let report = gpx.retain_accuracy_below(50.0, &AccuracyOptions::default());
+        assert_balanced(&report, &gpx);
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut gpx = fixture();
+        let report = gpx.dedup_tracks(&Tolerance::default(), KeepPolicy::First);
+        assert_balanced(&report, &gpx);
+        assert_eq!(report.segments, [change(1, 0, 4, 0)]);
+
+        let mut gpx = fixture();
+        let report = gpx.retain_waypoints_of_type("poi");
+        assert_balanced(&report, &gpx);
+        assert_eq!((report.removed, report.segments.len()), (1, 0));
+
+        let mut gpx = fixture();
+        let report = gpx.strip_times();
+        assert_balanced(&report, &gpx);
+        assert_eq!((report.removed, report.modified), (0, 16));
+        assert_eq!(
+            report
+                .segments
+                .iter()
+                .map(|c| c.modified)
+                .collect::<Vec<_>>(),
+            [8, 4, 4]
+        );
+
+        let mut gpx = fixture();
+        assert!(!gpx.dedup_exact(6).is_unchanged());
+        assert!(gpx.dedup_exact(6).is_unchanged());
+    }
+
+    #[test]
+    fn point_edits_balance() {
+        let mut gpx = fixture();
+        let report = gpx.normalize_text(&TextNormalizeOptions::default());
+        assert_balanced(&report, &gpx);
+        assert_eq!((report.modified, report.segments.len()), (1, 0));
+
+        let mut gpx = fixture();
+        let report = gpx.strip_extensions();
+        assert_balanced(&report, &gpx);
+        assert_eq!(report.modified, 2);
+        assert_eq!(
+            report.segments,
+            [SegmentChange {
+                modified: 1,
+                ..change(0, 0, 8, 8)
+            }]
+        );
+
+        let mut gpx = fixture();
+        let report = gpx.retain_extensions(|name| name.local_name == "heartrate");
+        assert_balanced(&report, &gpx);
+        assert_eq!((report.modified, report.segments.len()), (1, 0));
+    }
+
+    #[test]
+    fn track_operations_balance() {
+        let track = || fixture().tracks.remove(0);
+
+        let mut spiked = track();
+        let report = spiked.remove_spikes(50.0);
+        assert_balanced_with(&report, 12, spiked.point_count());
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut spiked = track();
+        let report = spiked.remove_spikes_by_distance(1000.0);
+        assert_balanced_with(&report, 12, spiked.point_count());
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut rolled = track();
+        for point in &mut rolled.segments[1].points[2..] {
+            let time = OffsetDateTime::from(point.time.unwrap()) - GPS_WEEK_ROLLOVER;
+            point.time = Some(time.into());
+        }
+        let report = rolled.repair_week_rollover();
+        assert_balanced_with(&report, 12, 12);
+        assert_eq!(
+            report.segments,
+            [SegmentChange {
+                modified: 2,
+                ..change(0, 1, 4, 4)
+            }]
+        );
+
+        let mut timed = track();
+        let start = OffsetDateTime::UNIX_EPOCH;
+        assert!(timed.synthesize_times(start, 1.0).unwrap().is_unchanged());
+        let overwrite = SynthesizeTimesOptions {
+            overwrite: true,
+            ..Default::default()
+        };
+        let report = timed
+            .synthesize_times_with_options(start, 1.0, &overwrite)
+            .unwrap();
+        assert_balanced_with(&report, 12, 12);
+        assert_eq!((report.modified, report.segments.len()), (12, 2));
+
+        // Splitting and merging only moves points.
+        let mut split = track();
+        let report = split.split_segment(0, 3);
+        assert_balanced_with(&report, 12, split.point_count());
+        assert!(report.is_unchanged());
+        assert_eq!(split.segments.len(), 3);
+        let report = split.merge_segments(ExtensionsPolicy::KeepFirst);
+        assert_balanced_with(&report, 12, split.point_count());
+        assert!(report.is_unchanged());
+        assert_eq!(split.segments.len(), 1);
+    }
+
+    #[test]
+    fn segment_operations_balance() {
+        let segment = || fixture().tracks.remove(0).segments.remove(0);
+
+        let mut deduped = segment();
+        let report = deduped.dedup_exact(6);
+        assert_balanced_with(&report, 8, deduped.points.len());
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut accurate = segment();
+        let report = accurate.retain_accuracy_below(50.0, &AccuracyOptions::default());
+        assert_balanced_with(&report, 8, accurate.points.len());
+        assert_eq!(report.segments, [change(0, 0, 8, 7)]);
+
+        let mut orthometric = segment();
+        let report = orthometric.apply_geoid_offset(47.0);
+        assert_balanced_with(&report, 8, 8);
+        assert_eq!(
+            report.segments,
+            [SegmentChange {
+                modified: 2,
+                ..change(0, 0, 8, 8)
+            }]
+        );
+    }
+
+    #[test]
+    fn growing_segment_removes_nothing() {
+        let mut report = ChangeReport::new("test", 3);
+        report.record_segment(0, 0, 1, 3, 1);
+        assert_eq!((report.after, report.removed, report.modified), (3, 0, 1));
+        assert_eq!(report.segments[0].removed, 0);
+    }
+
+    #[test]
+    fn merge_a_pipeline() {
+        let mut gpx = fixture();
+        let mut report = gpx.remove_spikes(50.0);
+        report.merge(gpx.dedup_exact(6));
+        report.merge(gpx.retain_waypoints_of_type("poi"));
+        report.merge(gpx.strip_times());
+        assert_balanced(&report, &gpx);
+        assert_eq!(
+            report.operations,
+            [
+                "remove_spikes",
+                "dedup_exact",
+                "retain_waypoints_of_type",
+                "strip_times"
+            ]
+        );
+        assert_eq!((report.removed, report.modified), (3, 14));
+        assert_eq!(
+            report.segments,
+            [
+                SegmentChange {
+                    modified: 6,
+                    ..change(0, 0, 8, 6)
+                },
+                SegmentChange {
+                    modified: 4,
+                    ..change(0, 1, 4, 4)
+                },
+                SegmentChange {
+                    modified: 4,
+                    ..change(1, 0, 4, 4)
+                },
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "remove_spikes, dedup_exact, retain_waypoints_of_type, strip_times: \
+             removed 3 and modified 14 of 21 points"
+        );
+
+        let mut empty = ChangeReport::default();
+        empty.merge(report.clone());
+        assert_eq!(empty, report);
+    }
+}
@@ -0,0 +1,312 @@
+//! resample puts the points of a track segment at uniform intervals.
+
+use time::{Duration, OffsetDateTime};
+
+use crate::distance::{interpolate_elevation, interpolate_point};
+use crate::errors::{GpxError, GpxResult};
+use crate::{TrackSegment, Waypoint};
+
+impl TrackSegment {
+    /// Resamples the segment to one point every `interval`, starting at the
+    /// time of the first point. The last point is always included, even if
+    /// it is closer than `interval` to the previous one.
+    ///
+    /// Positions and elevations are interpolated linearly between the
+    /// original points, which are expected to be in chronological order.
+    /// The new points only carry a position, an elevation and a time; all
+    /// other data of the original points, such as names or extensions,
+    /// cannot be interpolated and is dropped. The extensions of the segment
+    /// itself are kept.
+    ///
+    /// Every point needs a timestamp. Otherwise an error naming the index of
+    /// the first point without one is returned. A zero or negative
+    /// `interval` is an error as well.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let start = OffsetDateTime::UNIX_EPOCH;
+    /// let mut segment = TrackSegment::new();
+    /// for (seconds, x) in [(0, 0.0), (10, 1.0)].iter() {
+    ///     let mut point = Waypoint::new_lat_lon(0.0, *x).unwrap();
+    ///     point.time = Some((start + Duration::seconds(*seconds)).into());
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// let resampled = segment.resample_by_time(Duration::seconds(4)).unwrap();
+    /// let xs: Vec<f64> = resampled.points.iter().map(|p| p.point().x()).collect();
+    /// assert_eq!(xs, vec![0.0, 0.4, 0.8, 1.0]);
+    /// ```
+    pub fn resample_by_time(&self, interval: Duration) -> GpxResult<TrackSegment> {
+        if interval <= Duration::ZERO {
+            return Err(GpxError::NonPositiveInterval);
+        }
+        let mut times = Vec::with_capacity(self.points.len());
+        for (index, point) in self.points.iter().enumerate() {
+            match point.time {
+                Some(time) => times.push(OffsetDateTime::from(time)),
+                None => return Err(GpxError::MissingTimestamp(index)),
+            }
+        }
+        let start = match times.first() {
+            Some(&start) => start,
+            None => {
+                return Ok(TrackSegment {
+                    points: Vec::new(),
+                    extensions: self.extensions.clone(),
+                })
+            }
+        };
+
+        let keys: Vec<f64> = times
+            .iter()
+            .map(|&time| (time - start).as_seconds_f64())
+            .collect();
+        let samples = sample_positions(&keys, interval.as_seconds_f64());
+        let last = samples.len() - 1;
+        let points = samples
+            .into_iter()
+            .enumerate()
+            .map(|(n, (index, fraction))| {
+                let mut point = interpolated_waypoint(&self.points, index, fraction);
+                // Use the exact sample time rather than the interpolated one,
+                // which suffers from rounding.
+                if n != last {
+                    point.time = Some((start + interval * n as f64).into());
+                }
+                point
+            })
+            .collect();
+        Ok(TrackSegment {
+            points,
+            extensions: self.extensions.clone(),
+        })
+    }
+
+    /// Resamples the segment to one point every `meters` along the path,
+    /// using the distances of [`TrackSegment::cumulative_distances`]. The
+    /// first and last point are always included.
+    ///
+    /// Positions, elevations and times are interpolated linearly between the
+    /// original points, elevations and times only where both bracketing
+    /// points have them. As with [`TrackSegment::resample_by_time`], all
+    /// other data of the original points is dropped, while the extensions of
+    /// the segment are kept.
+    ///
+    /// If `meters` is not positive, every original point is kept.
+    pub fn resample_by_distance(&self, meters: f64) -> TrackSegment {
+        let samples = if meters > 0.0 {
+            sample_positions(&self.cumulative_distances(), meters)
+        } else {
+            (0..self.points.len()).map(|index| (index, 0.0)).collect()
+        };
+        TrackSegment {
+            points: samples
+                .into_iter()
+                .map(|(index, fraction)| interpolated_waypoint(&self.points, index, fraction))
+                .collect(),
+            extensions: self.extensions.clone(),
+        }
+    }
+}
+
+/// Finds the samples at `keys[0]`, `keys[0] + step`, ... below the last key,
+/// followed by the last key itself. Each sample is given as the index of the
+/// key at or before it, and the fraction of the way to the next key.
+///
+/// `keys` must be sorted and `step` positive.
+fn sample_positions(keys: &[f64], step: f64) -> Vec<(usize, f64)> {
+    let (first, last) = match (keys.first(), keys.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let mut samples = Vec::new();
+    let mut index = 0;
+    for n in 0.. {
+        let target = first + step * n as f64;
+        if target >= last {
+            break;
+        }
+        while index + 2 < keys.len() && keys[index + 1] <= target {
+            index += 1;
+        }
+        let span = keys[index + 1] - keys[index];
+        let fraction = if span > 0.0 {
+            ((target - keys[index]) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        samples.push((index, fraction));
+    }
+    samples.push((keys.len() - 1, 0.0));
+    samples
+}
+
+/// Creates the point `fraction` of the way from `points[index]` to the next
+/// point.
+fn interpolated_waypoint(points: &[Waypoint], index: usize, fraction: f64) -> Waypoint {
+    let a = &points[index];
+    let b = points.get(index + 1).unwrap_or(a);
+    let mut point = Waypoint::new(interpolate_point(a.point(), b.point(), fraction));
+    point.elevation = interpolate_elevation(a, b, fraction);
+    point.time = match (a.time, b.time) {
+        (Some(ta), Some(tb)) => {
+            let (ta, tb) = (OffsetDateTime::from(ta), OffsetDateTime::from(tb));
+            Some((ta + (tb - ta) * fraction).into())
+        }
+        _ => None,
+    };
+    point
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use crate::errors::GpxError;
+    use crate::{TrackSegment, Waypoint};
+
+    /// Distance between two points on the equator, 0.001° of longitude apart.
+    const SPACING: f64 = 111.19508;
+
+    fn point(x: f64, elevation: Option<f64>, seconds: Option<i64>) -> Waypoint {
+        let mut wpt = Waypoint::new(Point::new(x, 0.0));
+        wpt.elevation = elevation;
+        wpt.time = seconds.map(|s| (OffsetDateTime::UNIX_EPOCH + Duration::seconds(s)).into());
+        wpt
+    }
+
+    fn seconds(wpt: &Waypoint) -> f64 {
+        (OffsetDateTime::from(wpt.time.unwrap()) - OffsetDateTime::UNIX_EPOCH).as_seconds_f64()
+    }
+
+    #[test]
+    fn resample_by_time_linear() {
+        let segment = TrackSegment {
+            points: vec![
+                point(0.0, Some(100.0), Some(0)),
+                point(0.01, Some(200.0), Some(10)),
+            ],
+            ..Default::default()
+        };
+
+        let resampled = segment.resample_by_time(Duration::seconds(1)).unwrap();
+
+        assert_eq!(resampled.points.len(), 11);
+        for (i, wpt) in resampled.points.iter().enumerate() {
+            assert_approx_eq!(wpt.point().x(), i as f64 * 0.001, 1e-12);
+            assert_approx_eq!(wpt.elevation.unwrap(), 100.0 + i as f64 * 10.0, 1e-9);
+            assert_eq!(seconds(wpt), i as f64);
+        }
+    }
+
+    #[test]
+    fn resample_by_time_keeps_last_point() {
+        let segment = TrackSegment {
+            points: vec![
+                point(0.0, None, Some(0)),
+                point(1.0, None, Some(4)),
+                point(2.0, None, Some(5)),
+            ],
+            ..Default::default()
+        };
+
+        let resampled = segment.resample_by_time(Duration::seconds(2)).unwrap();
+
+        let times: Vec<f64> = resampled.points.iter().map(seconds).collect();
+        assert_eq!(times, vec![0.0, 2.0, 4.0, 5.0]);
+        assert_approx_eq!(resampled.points[1].point().x(), 0.5, 1e-12);
+        assert_eq!(resampled.points[3].point().x(), 2.0);
+        assert_eq!(resampled.points[0].elevation, None);
+    }
+
+    #[test]
+    fn resample_by_time_missing_timestamp() {
+        let segment = TrackSegment {
+            points: vec![
+                point(0.0, None, Some(0)),
+                point(1.0, None, None),
+                point(2.0, None, Some(5)),
+            ],
+            ..Default::default()
+        };
+
+        let result = segment.resample_by_time(Duration::seconds(1));
+        assert!(matches!(result, Err(GpxError::MissingTimestamp(1))));
+    }
+
+    #[test]
+    fn resample_by_time_invalid_interval() {
+        let segment = TrackSegment {
+            points: vec![point(0.0, None, Some(0)), point(1.0, None, Some(5))],
+            ..Default::default()
+        };
+
+        let result = segment.resample_by_time(Duration::ZERO);
+        assert!(matches!(result, Err(GpxError::NonPositiveInterval)));
+    }
+
+    #[test]
+    fn resample_by_time_short_segments() {
+        assert!(TrackSegment::new()
+            .resample_by_time(Duration::seconds(1))
+            .unwrap()
+            .points
+            .is_empty());
+
+        let single = TrackSegment {
+            points: vec![point(3.0, Some(1.0), Some(7))],
+            ..Default::default()
+        };
+        let resampled = single.resample_by_time(Duration::seconds(1)).unwrap();
+        assert_eq!(resampled.points, vec![point(3.0, Some(1.0), Some(7))]);
+    }
+
+    #[test]
+    fn resample_by_distance_uniform() {
+        let segment = TrackSegment {
+            points: vec![
+                point(0.0, Some(0.0), Some(0)),
+                point(0.001, Some(10.0), Some(60)),
+                point(0.003, None, Some(120)),
+            ],
+            ..Default::default()
+        };
+
+        let resampled = segment.resample_by_distance(100.0);
+
+        let xs: Vec<f64> = resampled.points.iter().map(|p| p.point().x()).collect();
+        assert_eq!(xs.len(), 5);
+        for (i, x) in xs[..4].iter().enumerate() {
+            assert_approx_eq!(*x, i as f64 * 100.0 / SPACING * 0.001, 1e-7);
+        }
+        assert_eq!(xs[4], 0.003);
+        assert_approx_eq!(
+            resampled.points[1].elevation.unwrap(),
+            100.0 / SPACING * 10.0,
+            1e-3
+        );
+        assert_eq!(resampled.points[2].elevation, None);
+        assert_approx_eq!(seconds(&resampled.points[1]), 100.0 / SPACING * 60.0, 1e-3);
+        assert_eq!(seconds(&resampled.points[4]), 120.0);
+    }
+
+    #[test]
+    fn resample_by_distance_drops_other_data() {
+        let mut first = point(0.0, None, None);
+        first.name = Some(String::from("Start"));
+        let segment = TrackSegment {
+            points: vec![first, point(0.001, None, None)],
+            ..Default::default()
+        };
+
+        let resampled = segment.resample_by_distance(1000.0);
+
+        assert_eq!(resampled.points.len(), 2);
+        assert_eq!(resampled.points[0].name, None);
+        assert_eq!(resampled.points[1].point().x(), 0.001);
+    }
+}
@@ -0,0 +1,310 @@
+//! reverse turns routes and tracks around.
+
+use time::OffsetDateTime;
+
+use crate::errors::{GpxError, GpxResult};
+use crate::{Gpx, Route, Track, TrackSegment, Waypoint};
+
+impl Route {
+    /// Reverses the order of the route points.
+    ///
+    /// Timestamps are left untouched, see [`Route::reverse_with_time_remap`].
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    /// Reverses the route and reassigns the timestamps of its points, so that
+    /// the reversed route keeps the original durations between points but
+    /// runs forward in time, starting at `new_start`.
+    ///
+    /// Every point needs a timestamp. Otherwise an error naming the index of
+    /// the first point without one is returned and the route is unchanged.
+    /// If a new time falls after the last supported date, a
+    /// [`GpxError::RemappedTimeOutOfRange`] is returned and the route is
+    /// unchanged as well.
+    pub fn reverse_with_time_remap(&mut self, new_start: OffsetDateTime) -> GpxResult<()> {
+        remap_reversed_times(self.points.iter_mut(), new_start)?;
+        self.reverse();
+        Ok(())
+    }
+}
+
+impl TrackSegment {
+    /// Reverses the order of the segment's points.
+    ///
+    /// Timestamps are left untouched, see
+    /// [`TrackSegment::reverse_with_time_remap`].
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    /// Reverses the segment and reassigns the timestamps of its points, so
+    /// that the reversed segment keeps the original durations between points
+    /// but runs forward in time, starting at `new_start`.
+    ///
+    /// Every point needs a timestamp. Otherwise an error naming the index of
+    /// the first point without one is returned and the segment is unchanged.
+    /// If a new time falls after the last supported date, a
+    /// [`GpxError::RemappedTimeOutOfRange`] is returned and the segment is
+    /// unchanged as well.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let start = OffsetDateTime::UNIX_EPOCH;
+    /// let mut segment = TrackSegment::new();
+    /// for (i, x) in [0.0, 1.0, 2.0].iter().enumerate() {
+    ///     let mut point = Waypoint::new_lat_lon(0.0, *x).unwrap();
+    ///     point.time = Some((start + Duration::seconds(10 * i as i64)).into());
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// segment.reverse_with_time_remap(start).unwrap();
+    /// assert_eq!(segment.points[0].point().x(), 2.0);
+    /// assert_eq!(segment.points[0].time, Some(start.into()));
+    /// assert_eq!(segment.points[2].time, Some((start + Duration::seconds(20)).into()));
+    /// ```
+    pub fn reverse_with_time_remap(&mut self, new_start: OffsetDateTime) -> GpxResult<()> {
+        remap_reversed_times(self.points.iter_mut(), new_start)?;
+        self.reverse();
+        Ok(())
+    }
+}
+
+impl Track {
+    /// Reverses the order of the segments, and the points of each segment.
+    ///
+    /// Timestamps are left untouched, see [`Track::reverse_with_time_remap`].
+    pub fn reverse(&mut self) {
+        self.segments.reverse();
+        for segment in &mut self.segments {
+            segment.reverse();
+        }
+    }
+
+    /// Reverses the track and reassigns the timestamps of its points, so that
+    /// the reversed track keeps the original durations between points (also
+    /// across segment boundaries) but runs forward in time, starting at
+    /// `new_start`.
+    ///
+    /// Every point needs a timestamp. Otherwise an error naming the index of
+    /// the first point without one, counted across all segments, is
+    /// returned and the track is unchanged.
+    /// If a new time falls after the last supported date, a
+    /// [`GpxError::RemappedTimeOutOfRange`] is returned and the track is
+    /// unchanged as well.
+    pub fn reverse_with_time_remap(&mut self, new_start: OffsetDateTime) -> GpxResult<()> {
+        remap_reversed_times(
+            self.segments
+                .iter_mut()
+                .flat_map(|seg| seg.points.iter_mut()),
+            new_start,
+        )?;
+        self.reverse();
+        Ok(())
+    }
+}
+
+impl Gpx {
+    /// Reverses all routes and tracks, see [`Route::reverse`] and
+    /// [`Track::reverse`].
+    ///
+    /// The order of the routes and tracks themselves, as well as the
+    /// standalone waypoints, are not changed.
+    pub fn reverse_all(&mut self) {
+        self.bump_generation();
+        for route in &mut self.routes {
+            route.reverse();
+        }
+        for track in &mut self.tracks {
+            track.reverse();
+        }
+    }
+}
+
+/// Gives each point the time `new_start + (latest - time)`, which is the time
+/// it has once the points are reversed. Nothing is changed unless all points
+/// have a timestamp and all new times are in the range of dates.
+fn remap_reversed_times<'a, I>(points: I, new_start: OffsetDateTime) -> GpxResult<()>
+where
+    I: Iterator<Item = &'a mut Waypoint>,
+{
+    let points: Vec<&mut Waypoint> = points.collect();
+    let mut times = Vec::with_capacity(points.len());
+    for (index, point) in points.iter().enumerate() {
+        match point.time {
+            Some(time) => times.push(OffsetDateTime::from(time)),
+            None => return Err(GpxError::MissingTimestamp(index)),
+        }
+    }
+    let latest = match times.iter().max() {
+        Some(&latest) => latest,
+        None => return Ok(()),
+    };
+    let mut remapped = Vec::with_capacity(times.len());
+    for time in times {
+        let time = new_start
+            .checked_add(latest - time)
+            .ok_or(GpxError::RemappedTimeOutOfRange)?;
+        remapped.push(time);
+    }
+    for (point, time) in points.into_iter().zip(remapped) {
+        point.time = Some(time.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+
+    use crate::errors::GpxError;
+    use crate::{Gpx, Route, Track, TrackSegment, Waypoint};
+
+    fn point(x: f64, seconds: Option<i64>) -> Waypoint {
+        let mut wpt = Waypoint::new(Point::new(x, 0.0));
+        wpt.time = seconds.map(|s| (OffsetDateTime::UNIX_EPOCH + Duration::seconds(s)).into());
+        wpt
+    }
+
+    fn seconds(wpt: &Waypoint) -> i64 {
+        (OffsetDateTime::from(wpt.time.unwrap()) - OffsetDateTime::UNIX_EPOCH).whole_seconds()
+    }
+
+    fn segment(points: &[(f64, i64)]) -> TrackSegment {
+        TrackSegment {
+            points: points.iter().map(|&(x, s)| point(x, Some(s))).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reverse_route() {
+        let mut route = Route::new();
+        route.points = vec![point(1.0, Some(0)), point(2.0, None), point(3.0, Some(5))];
+
+        route.reverse();
+
+        let xs: Vec<f64> = route.points.iter().map(|p| p.point().x()).collect();
+        assert_eq!(xs, vec![3.0, 2.0, 1.0]);
+        // Times are left as they were.
+        assert_eq!(seconds(&route.points[0]), 5);
+    }
+
+    #[test]
+    fn reverse_track() {
+        let mut track = Track::new();
+        track.segments.push(segment(&[(1.0, 0), (2.0, 1)]));
+        track.segments.push(segment(&[(3.0, 2), (4.0, 3)]));
+
+        track.reverse();
+
+        let xs: Vec<Vec<f64>> = track
+            .segments
+            .iter()
+            .map(|seg| seg.points.iter().map(|p| p.point().x()).collect())
+            .collect();
+        assert_eq!(xs, vec![vec![4.0, 3.0], vec![2.0, 1.0]]);
+    }
+
+    #[test]
+    fn reverse_track_with_time_remap() {
+        let mut track = Track::new();
+        track.segments.push(segment(&[(1.0, 0), (2.0, 10)]));
+        track.segments.push(segment(&[(3.0, 70), (4.0, 75)]));
+
+        let start = OffsetDateTime::UNIX_EPOCH + Duration::seconds(1000);
+        track.reverse_with_time_remap(start).unwrap();
+
+        let times: Vec<Vec<i64>> = track
+            .segments
+            .iter()
+            .map(|seg| seg.points.iter().map(seconds).collect())
+            .collect();
+        assert_eq!(times, vec![vec![1000, 1005], vec![1065, 1075]]);
+        assert_eq!(track.segments[0].points[0].point().x(), 4.0);
+    }
+
+    #[test]
+    fn reverse_route_with_time_remap() {
+        let mut route = Route::new();
+        route.points = vec![
+            point(1.0, Some(0)),
+            point(2.0, Some(3)),
+            point(3.0, Some(7)),
+        ];
+
+        route
+            .reverse_with_time_remap(OffsetDateTime::UNIX_EPOCH)
+            .unwrap();
+
+        let times: Vec<i64> = route.points.iter().map(seconds).collect();
+        assert_eq!(times, vec![0, 4, 7]);
+    }
+
+    #[test]
+    fn reverse_with_time_remap_missing_time() {
+        let mut track = Track::new();
+        track.segments.push(segment(&[(1.0, 0), (2.0, 10)]));
+        track.segments.push(TrackSegment {
+            points: vec![point(3.0, Some(20)), point(4.0, None)],
+            ..Default::default()
+        });
+        let original = track.clone();
+
+        let result = track.reverse_with_time_remap(OffsetDateTime::UNIX_EPOCH);
+
+        assert!(matches!(result, Err(GpxError::MissingTimestamp(3))));
+        assert_eq!(track, original);
+    }
+
+    #[test]
+    fn reverse_with_time_remap_near_max_date() {
+        let new_start = PrimitiveDateTime::MAX.assume_utc() - Duration::seconds(5);
+        let mut route = Route::new();
+        route.points = vec![point(1.0, Some(0)), point(2.0, Some(10))];
+        let original = route.clone();
+
+        let result = route.reverse_with_time_remap(new_start);
+
+        assert!(matches!(result, Err(GpxError::RemappedTimeOutOfRange)));
+        assert_eq!(route, original);
+
+        let mut segment = segment(&[(1.0, 0), (2.0, 5)]);
+        segment.reverse_with_time_remap(new_start).unwrap();
+        assert_eq!(
+            OffsetDateTime::from(segment.points[1].time.unwrap()),
+            PrimitiveDateTime::MAX.assume_utc()
+        );
+    }
+
+    #[test]
+    fn reverse_empty_segment_with_time_remap() {
+        let mut segment = TrackSegment::new();
+        assert!(segment
+            .reverse_with_time_remap(OffsetDateTime::UNIX_EPOCH)
+            .is_ok());
+    }
+
+    #[test]
+    fn reverse_all() {
+        let mut track = Track::new();
+        track.segments.push(segment(&[(1.0, 0), (2.0, 1)]));
+        let mut route = Route::new();
+        route.points = vec![point(5.0, None), point(6.0, None)];
+        let mut gpx = Gpx {
+            waypoints: vec![point(7.0, None), point(8.0, None)],
+            tracks: vec![track],
+            routes: vec![route],
+            ..Default::default()
+        };
+
+        gpx.reverse_all();
+
+        assert_eq!(gpx.tracks[0].segments[0].points[0].point().x(), 2.0);
+        assert_eq!(gpx.routes[0].points[0].point().x(), 6.0);
+        assert_eq!(gpx.waypoints[0].point().x(), 7.0);
+    }
+}
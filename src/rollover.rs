@@ -0,0 +1,266 @@
+//! rollover finds times that jump within a segment, such as those of
+//! receivers whose week counter rolled over, and repairs the latter.
+//!
+//! GPS counts weeks with ten bits, so receivers that do not know which
+//! epoch they are in report times exactly 1024 weeks in the past.
+
+use time::{Duration, OffsetDateTime};
+
+use crate::{ChangeReport, Time, Track, TrackSegment};
+
+/// The time a GPS week rollover takes off, 1024 weeks.
+pub const GPS_WEEK_ROLLOVER: Duration = Duration::seconds(1024 * 7 * 24 * 60 * 60);
+
+/// Options for [`TrackSegment::detect_time_anomalies_with_options`] and
+/// [`TrackSegment::repair_week_rollover_with_options`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeAnomalyOptions {
+    /// How far a jump between consecutive points may be from 1024 weeks
+    /// to still count as a week rollover. It covers the time that actually
+    /// passed between the two points.
+    pub rollover_tolerance: Duration,
+}
+
+impl Default for TimeAnomalyOptions {
+    fn default() -> TimeAnomalyOptions {
+        TimeAnomalyOptions {
+            rollover_tolerance: Duration::days(1),
+        }
+    }
+}
+
+/// What kind of jump a [`TimeAnomaly`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeAnomalyKind {
+    /// The time goes back by about 1024 weeks, where the week counter
+    /// rolled over.
+    RolloverBack,
+    /// The time goes forward by about 1024 weeks, where rolled over times
+    /// end.
+    RolloverForward,
+    /// The time goes back by any other amount, such as where a logger reset
+    /// its clock to midnight.
+    Backwards,
+}
+
+/// A jump in time between consecutive points of a segment, as found by
+/// [`TrackSegment::detect_time_anomalies`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeAnomaly {
+    /// Index of the point after the jump. Points without a time are passed
+    /// over, so the point before it need not be the one just before.
+    pub index: usize,
+
+    /// What kind of jump this is.
+    pub kind: TimeAnomalyKind,
+
+    /// The time of the point after the jump minus that of the one before.
+    pub jump: Duration,
+}
+
+impl TrackSegment {
+    /// Finds where the times of consecutive points go backwards or jump by
+    /// about 1024 weeks, with the default [`TimeAnomalyOptions`].
+    ///
+    /// Gaps forward of any other length, such as a pause of some days, are
+    /// not anomalies.
+    pub fn detect_time_anomalies(&self) -> Vec<TimeAnomaly> {
+        self.detect_time_anomalies_with_options(&TimeAnomalyOptions::default())
+    }
+
+    /// Like [`TrackSegment::detect_time_anomalies`], tuned by `options`.
+    pub fn detect_time_anomalies_with_options(
+        &self,
+        options: &TimeAnomalyOptions,
+    ) -> Vec<TimeAnomaly> {
+        let mut anomalies = Vec::new();
+        let mut previous: Option<OffsetDateTime> = None;
+        for (index, point) in self.points.iter().enumerate() {
+            let time = match point.time {
+                Some(time) => OffsetDateTime::from(time),
+                None => continue,
+            };
+            if let Some(previous) = previous {
+                let jump = time - previous;
+                let kind = if is_rollover(-jump, options) {
+                    Some(TimeAnomalyKind::RolloverBack)
+                } else if is_rollover(jump, options) {
+                    Some(TimeAnomalyKind::RolloverForward)
+                } else if jump.is_negative() {
+                    Some(TimeAnomalyKind::Backwards)
+                } else {
+                    None
+                };
+                if let Some(kind) = kind {
+                    anomalies.push(TimeAnomaly { index, kind, jump });
+                }
+            }
+            previous = Some(time);
+        }
+        anomalies
+    }
+
+    /// Moves the times that are about 1024 weeks older than those of their
+    /// neighbors forward by 1024 weeks, with the default
+    /// [`TimeAnomalyOptions`], and reports the points moved as modified.
+    ///
+    /// A segment that starts with rolled over times is repaired as well,
+    /// from the jump forward where they end.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint, GPS_WEEK_ROLLOVER};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let start = OffsetDateTime::from_unix_timestamp(1_683_712_800).unwrap();
+    /// let mut segment = TrackSegment::new();
+    /// for i in 0..4 {
+    ///     let mut time = start + Duration::seconds(i * 10);
+    ///     if i >= 2 {
+    ///         time -= GPS_WEEK_ROLLOVER;
+    ///     }
+    ///     let mut point = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+    ///     point.time = Some(time.into());
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// assert_eq!(segment.repair_week_rollover().modified, 2);
+    /// assert!(segment.detect_time_anomalies().is_empty());
+    /// ```
+    pub fn repair_week_rollover(&mut self) -> ChangeReport {
+        self.repair_week_rollover_with_options(&TimeAnomalyOptions::default())
+    }
+
+    /// Like [`TrackSegment::repair_week_rollover`], tuned by `options`.
+    pub fn repair_week_rollover_with_options(
+        &mut self,
+        options: &TimeAnomalyOptions,
+    ) -> ChangeReport {
+        // The number of rollovers to undo for each point, relative to the
+        // first timed one.
+        let mut rollovers = vec![0i64; self.points.len()];
+        let mut current = 0;
+        for anomaly in self.detect_time_anomalies_with_options(options) {
+            let step = match anomaly.kind {
+                TimeAnomalyKind::RolloverBack => 1,
+                TimeAnomalyKind::RolloverForward => -1,
+                TimeAnomalyKind::Backwards => continue,
+            };
+            current += step;
+            for count in &mut rollovers[anomaly.index..] {
+                *count = current;
+            }
+        }
+        let least = rollovers.iter().copied().min().unwrap_or(0);
+        let mut repaired = 0;
+        for (point, count) in self.points.iter_mut().zip(rollovers) {
+            let count = count - least;
+            if let (Some(time), true) = (point.time, count > 0) {
+                let time = OffsetDateTime::from(time) + GPS_WEEK_ROLLOVER * count as i32;
+                point.time = Some(Time::from(time));
+                repaired += 1;
+            }
+        }
+        let len = self.points.len();
+        ChangeReport::of_segment("repair_week_rollover", len, len, repaired)
+    }
+}
+
+impl Track {
+    /// Repairs week rollovers in every segment, see
+    /// [`TrackSegment::repair_week_rollover`].
+    pub fn repair_week_rollover(&mut self) -> ChangeReport {
+        self.report_segments("repair_week_rollover", TrackSegment::repair_week_rollover)
+    }
+}
+
+/// Whether `jump` is about 1024 weeks forward.
+fn is_rollover(jump: Duration, options: &TimeAnomalyOptions) -> bool {
+    (jump - GPS_WEEK_ROLLOVER).abs() <= options.rollover_tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use time::{Duration, OffsetDateTime};
+
+    use super::{TimeAnomalyKind, GPS_WEEK_ROLLOVER};
+    use crate::{read, Gpx, TrackSegment};
+
+    fn fixture() -> Gpx {
+        let file = File::open("tests/fixtures/week_rollover.gpx").unwrap();
+        read(BufReader::new(file)).unwrap()
+    }
+
+    fn times(segment: &TrackSegment) -> Vec<OffsetDateTime> {
+        segment
+            .points
+            .iter()
+            .map(|point| point.time.unwrap().into())
+            .collect()
+    }
+
+    #[test]
+    fn rollover_halfway_is_repaired() {
+        let mut segment = fixture().tracks.remove(0).segments.remove(0);
+        let anomalies = segment.detect_time_anomalies();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].index, 5);
+        assert_eq!(anomalies[0].kind, TimeAnomalyKind::RolloverBack);
+        assert_eq!(anomalies[0].jump, Duration::seconds(10) - GPS_WEEK_ROLLOVER);
+
+        assert_eq!(segment.repair_week_rollover().modified, 5);
+        let times = times(&segment);
+        assert!(times
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] == Duration::seconds(10)));
+        assert!(segment.detect_time_anomalies().is_empty());
+        assert_eq!(segment.repair_week_rollover().modified, 0);
+    }
+
+    #[test]
+    fn rolled_over_start_is_repaired() {
+        let mut segment = fixture().tracks.remove(0).segments.remove(0);
+        segment.repair_week_rollover();
+        let expected = times(&segment);
+        for point in &mut segment.points[..3] {
+            let time = OffsetDateTime::from(point.time.unwrap()) - GPS_WEEK_ROLLOVER;
+            point.time = Some(time.into());
+        }
+        let anomalies = segment.detect_time_anomalies();
+        assert_eq!(anomalies[0].kind, TimeAnomalyKind::RolloverForward);
+
+        assert_eq!(segment.repair_week_rollover().modified, 3);
+        assert_eq!(times(&segment), expected);
+    }
+
+    #[test]
+    fn days_long_gap_is_not_an_anomaly() {
+        let mut gpx = fixture();
+        let mut track = gpx.tracks.remove(1);
+        let before = track.clone();
+        assert!(track.segments[0].detect_time_anomalies().is_empty());
+        assert_eq!(track.repair_week_rollover().modified, 0);
+        assert_eq!(track, before);
+    }
+
+    #[test]
+    fn backwards_jump() {
+        let mut segment = fixture().tracks.remove(1).segments.remove(0);
+        // The logger reset its clock to midnight.
+        let midnight = OffsetDateTime::from(segment.points[2].time.unwrap())
+            .replace_time(time::Time::MIDNIGHT);
+        segment.points[2].time = Some(midnight.into());
+        segment.points[1].time = None;
+
+        let anomalies = segment.detect_time_anomalies();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(
+            (anomalies[0].index, anomalies[0].kind),
+            (2, TimeAnomalyKind::Backwards)
+        );
+        assert!(anomalies[0].jump.is_negative());
+        assert_eq!(segment.repair_week_rollover().modified, 0);
+    }
+}
@@ -0,0 +1,252 @@
+//! segments splits and merges track segments.
+//!
+//! Segments carry extensions of their own, so every operation that produces
+//! new segments has to decide where those go:
+//!
+//! * [`TrackSegment::split_off`] copies them into both halves,
+//! * [`Track::merge_segments`] follows an [`ExtensionsPolicy`],
+//! * [`TrackSegment::resample_by_time`], [`TrackSegment::resample_by_distance`]
+//!   and the operations that edit a segment in place, such as
+//!   [`TrackSegment::remove_spikes`] and [`TrackSegment::reverse`], leave them
+//!   untouched.
+
+use crate::{ChangeReport, Track, TrackSegment};
+
+/// What [`Track::merge_segments`] does with the extensions of the merged
+/// segments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtensionsPolicy {
+    /// Keep the extensions of the first segment that has any, and drop the
+    /// others.
+    KeepFirst,
+    /// Combine the children of all extensions into one element, in the order
+    /// of the segments.
+    Combine,
+    /// Drop all segment extensions.
+    Drop,
+}
+
+impl TrackSegment {
+    /// Splits the segment in two at `index`, keeping the points before it
+    /// and giving a new segment with the points from `index` on. Both halves
+    /// get the extensions of the segment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of points, like
+    /// [`Vec::split_off`].
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// for x in [0.0, 1.0, 2.0].iter() {
+    ///     segment.points.push(Waypoint::new_lat_lon(0.0, *x).unwrap());
+    /// }
+    ///
+    /// let second = segment.split_off(1);
+    /// assert_eq!(segment.points.len(), 1);
+    /// assert_eq!(second.points.len(), 2);
+    /// ```
+    pub fn split_off(&mut self, index: usize) -> TrackSegment {
+        TrackSegment {
+            points: self.points.split_off(index),
+            extensions: self.extensions.clone(),
+        }
+    }
+}
+
+impl Track {
+    /// Splits the segment at `segment` in two at point `index`, see
+    /// [`TrackSegment::split_off`]. The second half is inserted right after
+    /// the first. The points are only moved, so the report has none removed
+    /// or modified.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no segment `segment`, or if `index` is greater
+    /// than its number of points.
+    pub fn split_segment(&mut self, segment: usize, index: usize) -> ChangeReport {
+        let second = self.segments[segment].split_off(index);
+        self.segments.insert(segment + 1, second);
+        ChangeReport::new("split_segment", self.point_count())
+    }
+
+    /// Merges all segments into one, with the points in the order of the
+    /// segments. The extensions of the segments are handled as given by
+    /// `policy`. A track without segments is left as it is. The points are
+    /// only moved, so the report has none removed or modified.
+    pub fn merge_segments(&mut self, policy: ExtensionsPolicy) -> ChangeReport {
+        let report = ChangeReport::new("merge_segments", self.point_count());
+        let mut segments = std::mem::take(&mut self.segments).into_iter();
+        let mut merged = match segments.next() {
+            Some(first) => first,
+            None => return report,
+        };
+        if policy == ExtensionsPolicy::Drop {
+            merged.extensions = None;
+        }
+        for segment in segments {
+            merged.points.extend(segment.points);
+            merged.extensions = match (policy, merged.extensions, segment.extensions) {
+                (ExtensionsPolicy::Drop, _, _) => None,
+                (ExtensionsPolicy::KeepFirst, None, next) => next,
+                (ExtensionsPolicy::KeepFirst, kept, _) => kept,
+                (ExtensionsPolicy::Combine, Some(mut kept), Some(next)) => {
+                    kept.children.extend(next.children);
+                    Some(kept)
+                }
+                (ExtensionsPolicy::Combine, kept, next) => kept.or(next),
+            };
+        }
+        self.segments.push(merged);
+        report
+    }
+
+    /// Gives the number of points in all segments.
+    pub(crate) fn point_count(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.points.len())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use geo_types::Point;
+
+    use super::ExtensionsPolicy;
+    use crate::dom::Element;
+    use crate::{read, Track, TrackSegment, Waypoint};
+
+    fn child_names(extensions: &Option<Element>) -> Vec<&str> {
+        extensions
+            .iter()
+            .flat_map(Element::elements)
+            .map(|element| element.name.local_name.as_str())
+            .collect()
+    }
+
+    fn fixture_track() -> Track {
+        let file = File::open("tests/fixtures/segment_extensions.gpx").unwrap();
+        let mut gpx = read(BufReader::new(file)).unwrap();
+        gpx.tracks.remove(0)
+    }
+
+    #[test]
+    fn split_copies_extensions() {
+        let mut track = fixture_track();
+        assert_eq!(track.segments.len(), 3);
+
+        track.split_segment(0, 2);
+        assert_eq!(track.segments.len(), 4);
+        assert_eq!(track.segments[0].points.len(), 2);
+        assert_eq!(track.segments[1].points.len(), 1);
+        assert_eq!(child_names(&track.segments[0].extensions), vec!["Color"]);
+        assert_eq!(track.segments[0].extensions, track.segments[1].extensions);
+
+        let mut empty = TrackSegment::new();
+        assert!(empty.split_off(0).points.is_empty());
+    }
+
+    #[test]
+    fn merge_keeps_first_extensions() {
+        let mut track = fixture_track();
+        let first = track.segments[0].extensions.clone();
+
+        track.merge_segments(ExtensionsPolicy::KeepFirst);
+        assert_eq!(track.segments.len(), 1);
+        assert_eq!(track.segments[0].points.len(), 7);
+        assert_eq!(track.segments[0].extensions, first);
+
+        // Segments without extensions do not count as the first.
+        let mut track = fixture_track();
+        let second = track.segments[1].extensions.clone();
+        track.segments[0].extensions = None;
+        track.merge_segments(ExtensionsPolicy::KeepFirst);
+        assert_eq!(track.segments[0].extensions, second);
+    }
+
+    #[test]
+    fn merge_combines_extensions() {
+        let mut track = fixture_track();
+
+        track.merge_segments(ExtensionsPolicy::Combine);
+        assert_eq!(
+            child_names(&track.segments[0].extensions),
+            vec!["Color", "Color", "Note"]
+        );
+    }
+
+    #[test]
+    fn merge_drops_extensions() {
+        let mut track = fixture_track();
+
+        track.merge_segments(ExtensionsPolicy::Drop);
+        assert_eq!(track.segments[0].extensions, None);
+        assert_eq!(track.segments[0].points.len(), 7);
+
+        let mut empty = Track::new();
+        empty.merge_segments(ExtensionsPolicy::Drop);
+        assert!(empty.segments.is_empty());
+    }
+
+    #[test]
+    fn merge_keeps_point_order() {
+        let mut track = Track::new();
+        for xs in &[[0.0, 1.0], [2.0, 3.0]] {
+            let mut segment = TrackSegment::new();
+            for x in xs {
+                segment.points.push(Waypoint::new(Point::new(*x, 0.0)));
+            }
+            track.segments.push(segment);
+        }
+
+        track.merge_segments(ExtensionsPolicy::KeepFirst);
+        let xs: Vec<f64> = track.segments[0]
+            .points
+            .iter()
+            .map(|point| point.point().x())
+            .collect();
+        assert_eq!(xs, vec![0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn resample_keeps_extensions() {
+        let track = fixture_track();
+        let segment = &track.segments[0];
+
+        let by_distance = segment.resample_by_distance(5.0);
+        assert_eq!(by_distance.extensions, segment.extensions);
+        let by_time = segment
+            .resample_by_time(time::Duration::seconds(2))
+            .unwrap();
+        assert_eq!(by_time.extensions, segment.extensions);
+        let empty = TrackSegment {
+            points: Vec::new(),
+            extensions: segment.extensions.clone(),
+        };
+        assert_eq!(
+            empty
+                .resample_by_time(time::Duration::seconds(2))
+                .unwrap()
+                .extensions,
+            segment.extensions
+        );
+    }
+
+    #[test]
+    fn in_place_edits_keep_extensions() {
+        let mut track = fixture_track();
+        let extensions = track.segments[2].extensions.clone();
+
+        track.segments[2].reverse();
+        track.segments[2].remove_spikes(1.0);
+        track.segments[2].remove_spikes_by_distance(1.0);
+        assert_eq!(track.segments[2].extensions, extensions);
+    }
+}
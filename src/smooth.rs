@@ -0,0 +1,518 @@
+//! smooth takes the jitter out of recorded positions, keeping every point.
+
+use geo_types::Point;
+use time::OffsetDateTime;
+
+use crate::distance::{haversine, interpolate_point, EARTH_RADIUS_M};
+use crate::{ChangeReport, TrackSegment, Waypoint};
+
+/// How [`TrackSegment::smooth_positions`] filters the positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmoothMethod {
+    /// Averages each point with its neighbours, `window` points in all and
+    /// centered on it, weighing them less the further they are from it and
+    /// not at all from `radius_m` meters on. A point further than that from
+    /// all its neighbours, like a spike, stays where it is, so remove those
+    /// first with [`TrackSegment::remove_spikes`].
+    MovingAverage {
+        /// The number of points to average over, at least 3 to have any
+        /// effect.
+        window: usize,
+
+        /// The distance in meters at which neighbours stop counting.
+        radius_m: f64,
+    },
+
+    /// Runs a constant velocity Kalman filter forwards and a smoother
+    /// backwards over the points, taking the time between them from their
+    /// timestamps, or one second where they have none.
+    Kalman {
+        /// How far a recorded position is off, in meters. Less than a
+        /// millimeter, which includes zero and less, is taken as a
+        /// millimeter, which leaves the points about where they are.
+        measurement_noise_m: f64,
+
+        /// How much the speed changes, in meters per second squared.
+        acceleration_noise_mps2: f64,
+    },
+}
+
+/// Options for [`TrackSegment::smooth_positions`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmoothOptions {
+    /// How to filter the positions.
+    pub method: SmoothMethod,
+
+    /// The furthest a point is moved from where it was recorded, in meters.
+    /// Points that the filter would move further are moved this far towards
+    /// where it would put them. This applies to elevations as well.
+    pub max_displacement_m: f64,
+
+    /// Whether to filter the elevations too.
+    pub elevation: bool,
+}
+
+impl Default for SmoothOptions {
+    fn default() -> SmoothOptions {
+        SmoothOptions {
+            method: SmoothMethod::MovingAverage {
+                window: 5,
+                radius_m: 25.0,
+            },
+            max_displacement_m: 10.0,
+            elevation: false,
+        }
+    }
+}
+
+impl TrackSegment {
+    /// Moves the points to where the filter of `options` puts them, and
+    /// reports the points moved as modified.
+    ///
+    /// The first and last point stay where they are, and only the positions
+    /// and, if asked to, the elevations change. Points are never moved further
+    /// than [`SmoothOptions::max_displacement_m`].
+    ///
+    /// ```
+    /// use gpx::{SmoothOptions, TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// for (i, jitter) in [0.0, 0.00002, -0.00002, 0.00002, 0.0].iter().enumerate() {
+    ///     segment.points.push(Waypoint::new_lat_lon(47.0 + jitter, 8.0 + i as f64 * 0.0001).unwrap());
+    /// }
+    /// assert_eq!(segment.smooth_positions(&SmoothOptions::default()).modified, 3);
+    /// assert!((segment.points[2].lat() - 47.0).abs() < 0.00002);
+    /// ```
+    pub fn smooth_positions(&mut self, options: &SmoothOptions) -> ChangeReport {
+        let count = self.points.len();
+        if count < 3 {
+            return ChangeReport::of_segment("smooth_positions", count, count, 0);
+        }
+        let origin = self.points[0].point();
+        let recorded: Vec<_> = self
+            .points
+            .iter()
+            .map(|point| {
+                let (x, y) = to_meters(origin, point.point());
+                [
+                    Some(x),
+                    Some(y),
+                    point.elevation.filter(|_| options.elevation),
+                ]
+            })
+            .collect();
+        let mut axes = recorded.clone();
+        match options.method {
+            SmoothMethod::MovingAverage { window, radius_m } => {
+                axes = moving_average(&self.points, &recorded, window, radius_m)
+            }
+            SmoothMethod::Kalman {
+                measurement_noise_m,
+                acceleration_noise_mps2,
+            } => {
+                let gaps = time_gaps(&self.points);
+                for axis in 0..3 {
+                    let values: Vec<_> = axes.iter().map(|values| values[axis]).collect();
+                    let smoothed = kalman(
+                        &values,
+                        &gaps,
+                        measurement_noise_m.max(MIN_MEASUREMENT_NOISE_M).powi(2),
+                        acceleration_noise_mps2.powi(2),
+                    );
+                    for (values, value) in axes.iter_mut().zip(smoothed) {
+                        values[axis] = value;
+                    }
+                }
+            }
+        }
+
+        let limit = options.max_displacement_m.max(0.0);
+        let mut moved = 0;
+        for ((point, values), unfiltered) in self.points[1..count - 1]
+            .iter_mut()
+            .zip(&axes[1..count - 1])
+            .zip(&recorded[1..count - 1])
+        {
+            let mut changed = false;
+            // Converting to meters and back isn't exact, and shouldn't move
+            // points the filter leaves where they are.
+            let filtered = values[..2] != unfiltered[..2];
+            if let ([Some(x), Some(y), _], true) = (*values, filtered) {
+                let recorded = point.point();
+                let mut target = from_meters(origin, x, y);
+                let distance = haversine(recorded, target);
+                if distance > limit {
+                    target = interpolate_point(recorded, target, limit / distance);
+                }
+                if target != recorded && target.x().is_finite() && target.y().is_finite() {
+                    point.set_point(target);
+                    changed = true;
+                }
+            }
+            if let (Some(recorded), Some(elevation)) = (point.elevation, values[2]) {
+                let elevation = recorded + (elevation - recorded).clamp(-limit, limit);
+                if elevation != recorded && elevation.is_finite() {
+                    point.elevation = Some(elevation);
+                    changed = true;
+                }
+            }
+            moved += changed as usize;
+        }
+        ChangeReport::of_segment("smooth_positions", count, count, moved)
+    }
+}
+
+/// The least measurement noise the Kalman filter works with, as it divides
+/// by the measurement variance.
+const MIN_MEASUREMENT_NOISE_M: f64 = 1e-3;
+
+/// Meters per degree of latitude.
+const METERS_PER_DEGREE: f64 = EARTH_RADIUS_M * std::f64::consts::PI / 180.0;
+
+/// Gives `point` as meters east and north of `origin`, which is good enough
+/// for the few meters a filter looks at.
+fn to_meters(origin: Point<f64>, point: Point<f64>) -> (f64, f64) {
+    let delta_lon = (point.x() - origin.x() + 180.0).rem_euclid(360.0) - 180.0;
+    (
+        delta_lon * METERS_PER_DEGREE * origin.y().to_radians().cos(),
+        (point.y() - origin.y()) * METERS_PER_DEGREE,
+    )
+}
+
+/// Undoes [`to_meters`].
+fn from_meters(origin: Point<f64>, x: f64, y: f64) -> Point<f64> {
+    let delta_lon = x / (METERS_PER_DEGREE * origin.y().to_radians().cos());
+    interpolate_point(
+        origin,
+        Point::new(origin.x() + delta_lon, origin.y() + y / METERS_PER_DEGREE),
+        1.0,
+    )
+}
+
+/// Averages the values of each point with those of its neighbours that have
+/// them, weighing each by how close it is to the point.
+fn moving_average(
+    points: &[Waypoint],
+    axes: &[[Option<f64>; 3]],
+    window: usize,
+    radius_m: f64,
+) -> Vec<[Option<f64>; 3]> {
+    let before = window.saturating_sub(1) / 2;
+    let after = window.saturating_sub(1) - before;
+    (0..points.len())
+        .map(|i| {
+            let mut sums = [0.0; 3];
+            let mut weights = [0.0; 3];
+            for j in i.saturating_sub(before)..(i + after + 1).min(points.len()) {
+                let weight = if j == i {
+                    1.0
+                } else {
+                    1.0 - haversine(points[i].point(), points[j].point()) / radius_m
+                };
+                for axis in 0..3 {
+                    match axes[j][axis] {
+                        Some(value) if weight > 0.0 => {
+                            sums[axis] += weight * value;
+                            weights[axis] += weight;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let mut averaged = axes[i];
+            for axis in 0..3 {
+                if averaged[axis].is_some() && weights[axis] > 0.0 {
+                    averaged[axis] = Some(sums[axis] / weights[axis]);
+                }
+            }
+            averaged
+        })
+        .collect()
+}
+
+/// Gives the seconds from each point to the next, one where either has no
+/// time or time doesn't go forward.
+fn time_gaps(points: &[Waypoint]) -> Vec<f64> {
+    points
+        .windows(2)
+        .map(|pair| match (pair[0].time, pair[1].time) {
+            (Some(a), Some(b)) if b > a => {
+                (OffsetDateTime::from(b) - OffsetDateTime::from(a)).as_seconds_f64()
+            }
+            _ => 1.0,
+        })
+        .collect()
+}
+
+/// A 2x2 matrix, by rows.
+type Matrix = [[f64; 2]; 2];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    [
+        [
+            a[0][0] * b[0][0] + a[0][1] * b[1][0],
+            a[0][0] * b[0][1] + a[0][1] * b[1][1],
+        ],
+        [
+            a[1][0] * b[0][0] + a[1][1] * b[1][0],
+            a[1][0] * b[0][1] + a[1][1] * b[1][1],
+        ],
+    ]
+}
+
+fn transpose(a: &Matrix) -> Matrix {
+    [[a[0][0], a[1][0]], [a[0][1], a[1][1]]]
+}
+
+/// Filters one axis of positions with a constant velocity model, of which
+/// `gaps` are the seconds between them, and smoothes the result backwards
+/// with the Rauch-Tung-Striebel smoother. Missing values stay missing and
+/// don't correct the filter.
+fn kalman(
+    values: &[Option<f64>],
+    gaps: &[f64],
+    measurement_variance: f64,
+    acceleration_variance: f64,
+) -> Vec<Option<f64>> {
+    let first = match values.iter().flatten().next() {
+        Some(&first) => first,
+        None => return values.to_vec(),
+    };
+    // The filtered states by point, and the states predicted for each point
+    // from the one before.
+    let mut filtered: Vec<([f64; 2], Matrix)> = Vec::with_capacity(values.len());
+    let mut predicted: Vec<([f64; 2], Matrix)> = Vec::with_capacity(values.len());
+    let mut state = [first, 0.0];
+    let mut covariance = [[measurement_variance, 0.0], [0.0, 1e4]];
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            let dt = gaps[i - 1];
+            let transition = [[1.0, dt], [0.0, 1.0]];
+            state = [state[0] + dt * state[1], state[1]];
+            covariance = multiply(&multiply(&transition, &covariance), &transpose(&transition));
+            let q = acceleration_variance;
+            covariance[0][0] += q * dt.powi(3) / 3.0;
+            covariance[0][1] += q * dt.powi(2) / 2.0;
+            covariance[1][0] += q * dt.powi(2) / 2.0;
+            covariance[1][1] += q * dt;
+        }
+        predicted.push((state, covariance));
+        if let Some(value) = value {
+            let innovation = value - state[0];
+            let variance = covariance[0][0] + measurement_variance;
+            let gain = [covariance[0][0] / variance, covariance[1][0] / variance];
+            state = [
+                state[0] + gain[0] * innovation,
+                state[1] + gain[1] * innovation,
+            ];
+            covariance = [
+                [
+                    (1.0 - gain[0]) * covariance[0][0],
+                    (1.0 - gain[0]) * covariance[0][1],
+                ],
+                [
+                    covariance[1][0] - gain[1] * covariance[0][0],
+                    covariance[1][1] - gain[1] * covariance[0][1],
+                ],
+            ];
+        }
+        filtered.push((state, covariance));
+    }
+
+    let mut smoothed = vec![filtered[values.len() - 1].0; values.len()];
+    for i in (0..values.len() - 1).rev() {
+        let (state, covariance) = filtered[i];
+        let (next_state, next_covariance) = predicted[i + 1];
+        let transition = [[1.0, gaps[i]], [0.0, 1.0]];
+        let determinant = next_covariance[0][0] * next_covariance[1][1]
+            - next_covariance[0][1] * next_covariance[1][0];
+        if determinant.abs() < f64::EPSILON {
+            smoothed[i] = state;
+            continue;
+        }
+        let inverse = [
+            [
+                next_covariance[1][1] / determinant,
+                -next_covariance[0][1] / determinant,
+            ],
+            [
+                -next_covariance[1][0] / determinant,
+                next_covariance[0][0] / determinant,
+            ],
+        ];
+        let gain = multiply(&multiply(&covariance, &transpose(&transition)), &inverse);
+        let delta = [
+            smoothed[i + 1][0] - next_state[0],
+            smoothed[i + 1][1] - next_state[1],
+        ];
+        smoothed[i] = [
+            state[0] + gain[0][0] * delta[0] + gain[0][1] * delta[1],
+            state[1] + gain[1][0] * delta[0] + gain[1][1] * delta[1],
+        ];
+    }
+    values
+        .iter()
+        .zip(smoothed)
+        .map(|(value, state)| value.map(|_| state[0]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use super::{SmoothMethod, SmoothOptions, METERS_PER_DEGREE};
+    use crate::distance::haversine;
+    use crate::{TrackSegment, Waypoint};
+
+    const LATITUDE: f64 = 47.0;
+
+    /// Points 5 meters apart going east along 47° N, zig-zagging up to 3
+    /// meters north and south of it, one per second.
+    fn zig_zag(points: usize) -> TrackSegment {
+        let step = 5.0 / (METERS_PER_DEGREE * LATITUDE.to_radians().cos());
+        let start = OffsetDateTime::from_unix_timestamp(1_619_863_200).unwrap();
+        let mut segment = TrackSegment::new();
+        for i in 0..points {
+            let offset = [0.0, 3.0, -2.0, 2.5, -3.0, 1.0][i % 6];
+            let mut point =
+                Waypoint::new_lat_lon(LATITUDE + offset / METERS_PER_DEGREE, 8.0 + i as f64 * step)
+                    .unwrap();
+            point.elevation = Some(500.0 + offset);
+            point.time = Some((start + Duration::seconds(i as i64)).into());
+            point.name = Some(format!("{}", i));
+            segment.points.push(point);
+        }
+        segment
+    }
+
+    /// The root mean square of how far the points are off the line, in
+    /// meters.
+    fn deviation(segment: &TrackSegment) -> f64 {
+        let squares: f64 = segment
+            .points
+            .iter()
+            .map(|point| ((point.lat() - LATITUDE) * METERS_PER_DEGREE).powi(2))
+            .sum();
+        (squares / segment.points.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn smooths_towards_the_line() {
+        let kalman = SmoothMethod::Kalman {
+            measurement_noise_m: 3.0,
+            acceleration_noise_mps2: 0.5,
+        };
+        let average = SmoothOptions::default().method;
+        for method in [average, kalman].iter() {
+            let original = zig_zag(60);
+            let mut segment = original.clone();
+            let options = SmoothOptions {
+                method: *method,
+                ..Default::default()
+            };
+            let report = segment.smooth_positions(&options);
+            assert_eq!(report.modified, 58, "{:?}", method);
+            assert_eq!((report.before, report.after, report.removed), (60, 60, 0));
+            assert!(
+                deviation(&segment) < deviation(&original) / 2.0,
+                "{:?}: {} from {}",
+                method,
+                deviation(&segment),
+                deviation(&original)
+            );
+
+            assert_eq!(segment.points.len(), original.points.len());
+            assert_eq!(segment.points[0], original.points[0]);
+            assert_eq!(segment.points[59], original.points[59]);
+            for (smoothed, recorded) in segment.points.iter().zip(&original.points) {
+                assert_eq!(smoothed.time, recorded.time);
+                assert_eq!(smoothed.name, recorded.name);
+                assert_eq!(smoothed.elevation, recorded.elevation);
+            }
+        }
+    }
+
+    #[test]
+    fn kalman_without_measurement_noise() {
+        let original = zig_zag(30);
+        for noise in [0.0, -1.0, f64::NAN].iter() {
+            let mut segment = original.clone();
+            let options = SmoothOptions {
+                method: SmoothMethod::Kalman {
+                    measurement_noise_m: *noise,
+                    acceleration_noise_mps2: 0.5,
+                },
+                elevation: true,
+                ..Default::default()
+            };
+            segment.smooth_positions(&options);
+            for (smoothed, recorded) in segment.points.iter().zip(&original.points) {
+                assert!(smoothed.lat().is_finite() && smoothed.lon().is_finite());
+                assert!(haversine(smoothed.point(), recorded.point()) < 0.01);
+                assert!((smoothed.elevation.unwrap() - recorded.elevation.unwrap()).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn smooths_elevations_if_asked() {
+        let original = zig_zag(30);
+        let mut segment = original.clone();
+        segment.smooth_positions(&SmoothOptions {
+            elevation: true,
+            ..Default::default()
+        });
+        let spread = |segment: &TrackSegment| {
+            segment.points[1..29]
+                .iter()
+                .map(|point| (point.elevation.unwrap() - 500.0).abs())
+                .fold(0.0, f64::max)
+        };
+        assert!(spread(&segment) < spread(&original), "{}", spread(&segment));
+    }
+
+    #[test]
+    fn clamps_the_displacement() {
+        let mut original = zig_zag(21);
+        let outlier = &mut original.points[10];
+        outlier
+            .set_lat(LATITUDE + 200.0 / METERS_PER_DEGREE)
+            .unwrap();
+        outlier.elevation = Some(900.0);
+        for method in [
+            SmoothMethod::MovingAverage {
+                window: 5,
+                radius_m: 500.0,
+            },
+            SmoothMethod::Kalman {
+                measurement_noise_m: 3.0,
+                acceleration_noise_mps2: 0.5,
+            },
+        ]
+        .iter()
+        {
+            let mut segment = original.clone();
+            let options = SmoothOptions {
+                method: *method,
+                max_displacement_m: 4.0,
+                elevation: true,
+            };
+            segment.smooth_positions(&options);
+            for (smoothed, recorded) in segment.points.iter().zip(&original.points) {
+                let moved = haversine(smoothed.point(), recorded.point());
+                assert!(moved <= 4.0 + 1e-6, "{:?}: {}", method, moved);
+                let risen = smoothed.elevation.unwrap() - recorded.elevation.unwrap();
+                assert!(risen.abs() <= 4.0, "{:?}: {}", method, risen);
+            }
+            let moved = haversine(segment.points[10].point(), original.points[10].point());
+            assert!(moved > 3.9, "{:?}: {}", method, moved);
+        }
+
+        let mut short = zig_zag(2);
+        assert!(short
+            .smooth_positions(&SmoothOptions::default())
+            .is_unchanged());
+        assert_eq!(short, zig_zag(2));
+    }
+}
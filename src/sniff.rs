@@ -0,0 +1,116 @@
+//! sniff recognizes the files most often given to the reader by mistake,
+//! from their first bytes, so that they fail with an error naming them
+//! rather than with an XML syntax error.
+
+use std::fmt;
+use std::io;
+
+/// A format that is not GPX, recognized by the reader from the first bytes
+/// of its input. See
+/// [`GpxError::LikelyWrongFormat`](crate::errors::GpxError::LikelyWrongFormat).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DetectedFormat {
+    /// Data compressed with gzip, such as a `.gpx.gz` file.
+    Gzip,
+    /// A zip archive, such as a `.kmz` file or a download of several
+    /// activities.
+    Zip,
+    /// A PDF document.
+    Pdf,
+    /// A FIT file, as recorded by Garmin devices among others.
+    Fit,
+}
+
+impl DetectedFormat {
+    /// What to do to get at the GPX document, if there may be one.
+    pub(crate) fn hint(self) -> &'static str {
+        match self {
+            DetectedFormat::Gzip => ", decompress it first",
+            DetectedFormat::Zip => ", extract the GPX file from it first",
+            DetectedFormat::Pdf | DetectedFormat::Fit => "",
+        }
+    }
+}
+
+impl fmt::Display for DetectedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DetectedFormat::Gzip => "gzip compressed data",
+            DetectedFormat::Zip => "a zip archive",
+            DetectedFormat::Pdf => "a PDF document",
+            DetectedFormat::Fit => "a FIT file",
+        })
+    }
+}
+
+/// The number of bytes [`sniff`] looks at.
+pub(crate) const SNIFF_LEN: usize = 12;
+
+/// Recognizes the format of the data that starts with `head`.
+pub(crate) fn sniff(head: &[u8]) -> Option<DetectedFormat> {
+    if head.starts_with(b"\x1f\x8b") {
+        Some(DetectedFormat::Gzip)
+    } else if head.starts_with(b"PK\x03\x04") {
+        Some(DetectedFormat::Zip)
+    } else if head.starts_with(b"%PDF") {
+        Some(DetectedFormat::Pdf)
+    } else if matches!(head.first(), Some(12) | Some(14)) && head.get(8..12) == Some(b".FIT") {
+        Some(DetectedFormat::Fit)
+    } else {
+        None
+    }
+}
+
+/// The error the reader gives the XML parser for input in a
+/// [`DetectedFormat`], to be recognized by [`wrong_format`].
+#[derive(Debug)]
+struct WrongFormat(DetectedFormat);
+
+impl fmt::Display for WrongFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the input is {}", self.0)
+    }
+}
+
+impl std::error::Error for WrongFormat {}
+
+pub(crate) fn wrong_format_error(format: DetectedFormat) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, WrongFormat(format))
+}
+
+/// Gives the format of the input if `error` is the one of
+/// [`wrong_format_error`].
+pub(crate) fn wrong_format(error: &xml::reader::Error) -> Option<DetectedFormat> {
+    match error.kind() {
+        xml::reader::ErrorKind::Io(error) => error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<WrongFormat>())
+            .map(|wrong| wrong.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sniff, DetectedFormat};
+
+    #[test]
+    fn signatures() {
+        let cases: [(&[u8], Option<DetectedFormat>); 7] = [
+            (b"\x1f\x8b\x08\x00", Some(DetectedFormat::Gzip)),
+            (b"PK\x03\x04\x14\x00", Some(DetectedFormat::Zip)),
+            (b"%PDF-1.7\n", Some(DetectedFormat::Pdf)),
+            (
+                b"\x0e\x20\x8b\x08\x00\x00\x00\x00.FIT\x00\x00",
+                Some(DetectedFormat::Fit),
+            ),
+            (b"\x0e\x20\x8b\x08\x00\x00\x00\x00.FIX", None),
+            (b"<?xml version=\"1.0\"?>", None),
+            (b"\x1f", None),
+        ];
+        for (head, format) in cases.iter() {
+            assert_eq!(sniff(head), *format, "{:?}", head);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! spikes removes points that GPS glitches placed far away from the rest of
+//! a track.
+
+use time::OffsetDateTime;
+
+use crate::distance::haversine;
+use crate::{ChangeReport, Gpx, Track, TrackSegment, Waypoint};
+
+impl TrackSegment {
+    /// Removes the points that could only be reached from the previous
+    /// point at more than `max_speed_mps` meters per second, and reports
+    /// them as removed.
+    ///
+    /// Each point is compared with the last point that was kept, so a run of
+    /// bad points is removed as a whole. Points without a time, or following
+    /// a kept point without one, are kept. The first and the last point are
+    /// never removed.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// for (i, x) in [0.0, 0.0001, 0.03, 0.0003].iter().enumerate() {
+    ///     let mut point = Waypoint::new_lat_lon(0.0, *x).unwrap();
+    ///     point.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(i as i64)).into());
+    ///     segment.points.push(point);
+    /// }
+    ///
+    /// // The third point is 3 km away, one second after the second one.
+    /// assert_eq!(segment.remove_spikes(50.0).removed, 1);
+    /// assert_eq!(segment.points.len(), 3);
+    /// ```
+    pub fn remove_spikes(&mut self, max_speed_mps: f64) -> ChangeReport {
+        let before = self.points.len();
+        let keep = keep_mask(&self.points, |kept, index| {
+            let (kept, point) = (&self.points[kept], &self.points[index]);
+            let (kept_time, time) = match (kept.time, point.time) {
+                (Some(kept_time), Some(time)) => (kept_time, time),
+                _ => return true,
+            };
+            let seconds = (OffsetDateTime::from(time) - OffsetDateTime::from(kept_time))
+                .as_seconds_f64()
+                .abs();
+            let distance = haversine(kept.point(), point.point());
+            if seconds == 0.0 {
+                distance == 0.0
+            } else {
+                distance / seconds <= max_speed_mps
+            }
+        });
+        retain(&mut self.points, &keep);
+        ChangeReport::of_segment("remove_spikes", before, self.points.len(), 0)
+    }
+
+    /// Removes the points that are more than `max_jump_m` meters away from
+    /// the previous point, for segments without times, and reports them as
+    /// removed.
+    ///
+    /// A point that far away only counts as a spike if a later point comes
+    /// back to within `max_jump_m` of the last kept point. All points in
+    /// between are removed, and a jump that the track never comes back from
+    /// is kept. The first and the last point are never removed.
+    pub fn remove_spikes_by_distance(&mut self, max_jump_m: f64) -> ChangeReport {
+        let before = self.points.len();
+        let points = &self.points;
+        let near =
+            |a: usize, b: usize| haversine(points[a].point(), points[b].point()) <= max_jump_m;
+        // The index up to which points belong to the current run of spikes.
+        let mut spikes_until = 0;
+        let keep = keep_mask(points, |kept, index| {
+            if index < spikes_until {
+                return false;
+            }
+            if near(kept, index) {
+                return true;
+            }
+            match (index + 1..points.len()).find(|&later| near(kept, later)) {
+                Some(later) => {
+                    spikes_until = later;
+                    false
+                }
+                None => true,
+            }
+        });
+        retain(&mut self.points, &keep);
+        ChangeReport::of_segment("remove_spikes_by_distance", before, self.points.len(), 0)
+    }
+}
+
+impl Track {
+    /// Removes spikes from every segment, see
+    /// [`TrackSegment::remove_spikes`].
+    pub fn remove_spikes(&mut self, max_speed_mps: f64) -> ChangeReport {
+        self.report_segments("remove_spikes", |segment| {
+            segment.remove_spikes(max_speed_mps)
+        })
+    }
+
+    /// Removes spikes from every segment, see
+    /// [`TrackSegment::remove_spikes_by_distance`].
+    pub fn remove_spikes_by_distance(&mut self, max_jump_m: f64) -> ChangeReport {
+        self.report_segments("remove_spikes_by_distance", |segment| {
+            segment.remove_spikes_by_distance(max_jump_m)
+        })
+    }
+}
+
+impl Gpx {
+    /// Removes spikes from every track segment, see
+    /// [`TrackSegment::remove_spikes`].
+    pub fn remove_spikes(&mut self, max_speed_mps: f64) -> ChangeReport {
+        self.report_segments("remove_spikes", |segment| {
+            segment.remove_spikes(max_speed_mps)
+        })
+    }
+
+    /// Removes spikes from every track segment, see
+    /// [`TrackSegment::remove_spikes_by_distance`].
+    pub fn remove_spikes_by_distance(&mut self, max_jump_m: f64) -> ChangeReport {
+        self.report_segments("remove_spikes_by_distance", |segment| {
+            segment.remove_spikes_by_distance(max_jump_m)
+        })
+    }
+}
+
+/// Decides for each point whether to keep it, calling `keep` with the index
+/// of the last kept point and the index of the point in question. The first
+/// and the last point are always kept.
+fn keep_mask(points: &[Waypoint], mut keep: impl FnMut(usize, usize) -> bool) -> Vec<bool> {
+    let last = points.len().saturating_sub(1);
+    let mut kept = 0;
+    (0..points.len())
+        .map(|index| {
+            if index == 0 || index == last {
+                return true;
+            }
+            let keep = keep(kept, index);
+            if keep {
+                kept = index;
+            }
+            keep
+        })
+        .collect()
+}
+
+/// Removes the points not marked in `keep`.
+fn retain(points: &mut Vec<Waypoint>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    points.retain(|_| *keep.next().unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use crate::{Track, TrackSegment, Waypoint};
+
+    /// A segment heading east along the equator, one point every ten
+    /// seconds, with `x` in degrees of longitude.
+    fn segment(xs: &[f64], with_time: bool) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        for (i, &x) in xs.iter().enumerate() {
+            let mut point = Waypoint::new(Point::new(x, 0.0));
+            if with_time {
+                point.time =
+                    Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(10 * i as i64)).into());
+            }
+            segment.points.push(point);
+        }
+        segment
+    }
+
+    fn xs(segment: &TrackSegment) -> Vec<f64> {
+        segment
+            .points
+            .iter()
+            .map(|point| point.point().x())
+            .collect()
+    }
+
+    // 0.001° of longitude on the equator is about 111 m, so 0.001° every ten
+    // seconds is about 11 m/s.
+
+    #[test]
+    fn remove_single_spike() {
+        let mut spiked = segment(&[0.0, 0.001, 0.002, 0.03, 0.004, 0.005], true);
+
+        assert_eq!(spiked.remove_spikes(20.0).removed, 1);
+        assert_eq!(xs(&spiked), vec![0.0, 0.001, 0.002, 0.004, 0.005]);
+    }
+
+    #[test]
+    fn remove_run_of_spikes() {
+        let mut spiked = segment(&[0.0, 0.001, 0.03, 0.031, 0.03, 0.005, 0.006], true);
+
+        assert_eq!(spiked.remove_spikes(20.0).removed, 3);
+        assert_eq!(xs(&spiked), vec![0.0, 0.001, 0.005, 0.006]);
+    }
+
+    #[test]
+    fn keep_fast_section_under_threshold() {
+        // About 19.5 m/s between the second and the fourth point.
+        let points = [0.0, 0.001, 0.00275, 0.0045, 0.0055];
+        let mut fast = segment(&points, true);
+
+        assert_eq!(fast.remove_spikes(20.0).removed, 0);
+        assert_eq!(xs(&fast), points.to_vec());
+    }
+
+    #[test]
+    fn keep_first_and_last_point() {
+        let mut spiked = segment(&[0.5, 0.001, 0.002, 0.7], true);
+
+        // The first point is kept, so everything after it looks like a
+        // spike, apart from the last point.
+        assert_eq!(spiked.remove_spikes(20.0).removed, 2);
+        assert_eq!(xs(&spiked), vec![0.5, 0.7]);
+    }
+
+    #[test]
+    fn remove_spikes_without_times() {
+        let mut untimed = segment(&[0.0, 0.001, 0.03, 0.003], false);
+
+        assert_eq!(untimed.remove_spikes(20.0).removed, 0);
+    }
+
+    #[test]
+    fn remove_spikes_by_distance() {
+        let mut single = segment(&[0.0, 0.001, 0.03, 0.002, 0.003], false);
+        assert_eq!(single.remove_spikes_by_distance(500.0).removed, 1);
+        assert_eq!(xs(&single), vec![0.0, 0.001, 0.002, 0.003]);
+
+        let mut run = segment(&[0.0, 0.001, 0.03, 0.031, 0.03, 0.002, 0.003], false);
+        assert_eq!(run.remove_spikes_by_distance(500.0).removed, 3);
+        assert_eq!(xs(&run), vec![0.0, 0.001, 0.002, 0.003]);
+
+        // A jump the track never comes back from is not a spike.
+        let points = [0.0, 0.001, 0.03, 0.031, 0.032];
+        let mut jump = segment(&points, false);
+        assert_eq!(jump.remove_spikes_by_distance(500.0).removed, 0);
+        assert_eq!(xs(&jump), points.to_vec());
+    }
+
+    #[test]
+    fn track_remove_spikes() {
+        let mut track = Track::new();
+        track
+            .segments
+            .push(segment(&[0.0, 0.03, 0.002, 0.003], true));
+        track
+            .segments
+            .push(segment(&[0.1, 0.101, 0.2, 0.103], true));
+
+        let report = track.clone().remove_spikes(20.0);
+        assert_eq!((report.before, report.after, report.removed), (8, 6, 2));
+        assert_eq!(
+            report
+                .segments
+                .iter()
+                .map(|change| (change.track, change.segment, change.removed))
+                .collect::<Vec<_>>(),
+            [(0, 0, 1), (0, 1, 1)]
+        );
+        assert_eq!(track.remove_spikes_by_distance(500.0).removed, 2);
+    }
+}
@@ -0,0 +1,365 @@
+//! split cuts a document into several smaller ones, for devices that
+//! refuse files over a number of points or bytes.
+
+use crate::writer::{estimated_track_size, estimated_waypoint_size, ESTIMATE_SEGMENT};
+use crate::{Gpx, Track, TrackSegment, Waypoint};
+
+/// The most a document given by [`split`] may hold. Budgets that are not
+/// given don't limit the documents.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SplitBudget {
+    /// The most track points in a document.
+    pub max_points: Option<usize>,
+
+    /// The most bytes a document may take when written, as estimated by
+    /// [`Gpx::estimated_output_size`].
+    pub max_size: Option<usize>,
+}
+
+/// Splits `gpx` into documents that each stay within `budget`.
+///
+/// The waypoints and routes all go into the first document, and the tracks
+/// fill the documents in order, cut between their segments or points as
+/// needed. A track that is cut continues in the next document as a copy
+/// with the same name and data. A segment that is cut starts again with
+/// its last point in the previous document, so that the parts connect; the
+/// repeated point counts towards the budget like any other. Each document
+/// has at least one point of its own, so a budget too small for that and
+/// the repeated point is exceeded.
+///
+/// Every document has the version, creator and a copy of the metadata of
+/// `gpx`, with bounds fitted to its points if `gpx` has bounds. If there is
+/// more than one document, their names are numbered like "Ride, part 1 of
+/// 3". The extensions and unknown elements and attributes of the document
+/// go into the first one.
+///
+/// ```
+/// use gpx::{Gpx, SplitBudget, Track, TrackSegment, Waypoint};
+///
+/// let mut segment = TrackSegment::new();
+/// for i in 0..5 {
+///     segment.points.push(Waypoint::new_lat_lon(47.0, 8.0 + i as f64 * 0.001).unwrap());
+/// }
+/// let mut track = Track::new();
+/// track.segments.push(segment);
+/// let mut gpx = Gpx::default();
+/// gpx.tracks.push(track);
+///
+/// let budget = SplitBudget {
+///     max_points: Some(3),
+///     ..Default::default()
+/// };
+/// let parts = gpx::split(&gpx, &budget);
+/// assert_eq!(parts.len(), 2);
+/// assert_eq!(parts[0].tracks[0].segments[0].points.len(), 3);
+/// // The second part starts at the last point of the first one.
+/// assert_eq!(parts[1].tracks[0].segments[0].points.len(), 3);
+/// ```
+pub fn split(gpx: &Gpx, budget: &SplitBudget) -> Vec<Gpx> {
+    let first = Gpx {
+        version: gpx.version,
+        creator: gpx.creator.clone(),
+        metadata: gpx.metadata.clone(),
+        waypoints: gpx.waypoints.clone(),
+        tracks: Vec::new(),
+        routes: gpx.routes.clone(),
+        extensions: gpx.extensions.clone(),
+        unknown_elements: gpx.unknown_elements.clone(),
+        unknown_attributes: gpx.unknown_attributes.clone(),
+        prolog: gpx.prolog.clone(),
+        ..Default::default()
+    };
+    let mut splitter = Splitter {
+        gpx,
+        budget,
+        size: first.estimated_output_size(),
+        points: 0,
+        has_content: !first.waypoints.is_empty() || !first.routes.is_empty(),
+        current: first,
+        done: Vec::new(),
+    };
+
+    for track in &gpx.tracks {
+        let mut track_open = false;
+        for segment in &track.segments {
+            let mut segment_open = false;
+            for (index, point) in segment.points.iter().enumerate() {
+                let mut cost = estimated_waypoint_size(point);
+                if !segment_open {
+                    cost += ESTIMATE_SEGMENT;
+                }
+                if !track_open {
+                    cost += estimated_track_size(track);
+                }
+                if splitter.exceeds(cost) {
+                    splitter.next_document();
+                    track_open = false;
+                    segment_open = false;
+                }
+                if !track_open {
+                    splitter.open_track(track);
+                    track_open = true;
+                }
+                if !segment_open {
+                    splitter.open_segment(segment);
+                    segment_open = true;
+                    if index > 0 {
+                        splitter.push(&segment.points[index - 1]);
+                    }
+                }
+                splitter.push(point);
+                splitter.has_content = true;
+            }
+            // Keep segments without points.
+            if !segment_open {
+                if !track_open {
+                    splitter.open_track(track);
+                    track_open = true;
+                }
+                splitter.open_segment(segment);
+            }
+        }
+        // Keep tracks without segments.
+        if !track_open {
+            splitter.open_track(track);
+        }
+    }
+    splitter.finish()
+}
+
+struct Splitter<'a> {
+    gpx: &'a Gpx,
+    budget: &'a SplitBudget,
+    /// The document being filled.
+    current: Gpx,
+    /// The estimated size of `current`.
+    size: usize,
+    /// The number of track points in `current`.
+    points: usize,
+    /// Whether `current` has anything besides a point repeated from the
+    /// previous document, and may thus be finished.
+    has_content: bool,
+    done: Vec<Gpx>,
+}
+
+impl Splitter<'_> {
+    /// Whether adding a point of estimated size `cost` to the current
+    /// document would go over the budget.
+    fn exceeds(&self, cost: usize) -> bool {
+        let too_many = self
+            .budget
+            .max_points
+            .is_some_and(|max| self.points + 1 > max);
+        let too_large = self
+            .budget
+            .max_size
+            .is_some_and(|max| self.size + cost > max);
+        self.has_content && (too_many || too_large)
+    }
+
+    /// Finishes the current document and starts an empty one.
+    fn next_document(&mut self) {
+        let next = Gpx {
+            version: self.gpx.version,
+            creator: self.gpx.creator.clone(),
+            metadata: self.gpx.metadata.clone(),
+            ..Default::default()
+        };
+        self.size = next.estimated_output_size();
+        self.points = 0;
+        self.has_content = false;
+        let current = std::mem::replace(&mut self.current, next);
+        self.done.push(current);
+    }
+
+    /// Adds a copy of `track` without its segments to the current document.
+    fn open_track(&mut self, track: &Track) {
+        self.size += estimated_track_size(track);
+        self.current.tracks.push(Track {
+            name: track.name.clone(),
+            comment: track.comment.clone(),
+            description: track.description.clone(),
+            source: track.source.clone(),
+            links: track.links.clone(),
+            _type: track._type.clone(),
+            number: track.number,
+            extensions: track.extensions.clone(),
+            segments: Vec::new(),
+            unknown_attributes: track.unknown_attributes.clone(),
+        });
+    }
+
+    /// Adds a copy of `segment` without its points to the last track of the
+    /// current document.
+    fn open_segment(&mut self, segment: &TrackSegment) {
+        self.size += ESTIMATE_SEGMENT;
+        let track = self.current.tracks.last_mut().expect("track is open");
+        track.segments.push(TrackSegment {
+            points: Vec::new(),
+            extensions: segment.extensions.clone(),
+        });
+    }
+
+    /// Adds `point` to the last segment of the current document.
+    fn push(&mut self, point: &Waypoint) {
+        self.size += estimated_waypoint_size(point);
+        self.points += 1;
+        let track = self.current.tracks.last_mut().expect("track is open");
+        let segment = track.segments.last_mut().expect("segment is open");
+        segment.points.push(point.clone());
+    }
+
+    /// Gives all documents, with their names numbered and their bounds
+    /// fitted.
+    fn finish(mut self) -> Vec<Gpx> {
+        self.done.push(self.current);
+        let count = self.done.len();
+        for (index, part) in self.done.iter_mut().enumerate() {
+            let bounds = part.point_bounds();
+            if let Some(metadata) = &mut part.metadata {
+                if metadata.bounds.is_some() {
+                    metadata.bounds = bounds;
+                }
+                if count > 1 {
+                    if let Some(name) = &mut metadata.name {
+                        *name = format!("{}, part {} of {}", name, index + 1, count);
+                    }
+                }
+            }
+        }
+        self.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split, SplitBudget};
+    use crate::testutil::synthetic_gpx;
+    use crate::writer::{estimated_track_size, estimated_waypoint_size, ESTIMATE_SEGMENT};
+    use crate::{Gpx, Waypoint};
+
+    fn points(gpx: &Gpx) -> Vec<&Waypoint> {
+        gpx.tracks
+            .iter()
+            .flat_map(|track| &track.segments)
+            .flat_map(|segment| &segment.points)
+            .collect()
+    }
+
+    #[test]
+    fn split_by_points() {
+        let mut gpx = synthetic_gpx(1, 25_000);
+        gpx.waypoints
+            .push(Waypoint::new_lat_lon(47.0, 11.0).unwrap());
+        let budget = SplitBudget {
+            max_points: Some(10_000),
+            ..Default::default()
+        };
+
+        let parts = split(&gpx, &budget);
+        assert_eq!(parts.len(), 3);
+        let counts: Vec<usize> = parts.iter().map(|part| points(part).len()).collect();
+        assert_eq!(counts, [10_000, 10_000, 5_002]);
+        assert_eq!(parts[0].waypoints.len(), 1);
+        assert!(parts[1].waypoints.is_empty());
+
+        let original = points(&gpx);
+        assert_eq!(points(&parts[1])[0], original[9_999]);
+        assert_eq!(points(&parts[2])[0], original[19_998]);
+
+        let mut merged = points(&parts[0]);
+        for part in &parts[1..] {
+            assert!(part.validate().is_ok());
+            assert_eq!(part.tracks[0].name, gpx.tracks[0].name);
+            // Drop the repeated boundary point.
+            merged.extend(points(part).into_iter().skip(1));
+        }
+        assert!(parts[0].validate().is_ok());
+        assert_eq!(merged, original);
+
+        let names: Vec<&str> = parts
+            .iter()
+            .map(|part| part.metadata.as_ref().unwrap().name.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Synthetic tracks, part 1 of 3",
+                "Synthetic tracks, part 2 of 3",
+                "Synthetic tracks, part 3 of 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn split_between_segments_and_tracks() {
+        let gpx = synthetic_gpx(3, 100);
+        let budget = SplitBudget {
+            max_points: Some(100),
+            ..Default::default()
+        };
+
+        let parts = split(&gpx, &budget);
+        assert_eq!(parts.len(), 3);
+        for (part, track) in parts.iter().zip(&gpx.tracks) {
+            assert_eq!(part.tracks.len(), 1);
+            assert_eq!(&part.tracks[0], track);
+        }
+    }
+
+    #[test]
+    fn budgets_count_repeated_points() {
+        let gpx = synthetic_gpx(2, 30);
+        for max_points in 2..12 {
+            let budget = SplitBudget {
+                max_points: Some(max_points),
+                ..Default::default()
+            };
+            for part in split(&gpx, &budget) {
+                assert!(points(&part).len() <= max_points);
+            }
+        }
+
+        // Enough room for the track, a segment and two points.
+        let point = estimated_waypoint_size(&gpx.tracks[0].segments[0].points[0]);
+        let empty = Gpx {
+            tracks: Vec::new(),
+            ..gpx.clone()
+        };
+        let smallest = empty.estimated_output_size()
+            + estimated_track_size(&gpx.tracks[0])
+            + ESTIMATE_SEGMENT
+            + 2 * point;
+        for max_size in (smallest..smallest + 20 * point).step_by(7) {
+            let budget = SplitBudget {
+                max_size: Some(max_size),
+                ..Default::default()
+            };
+            for part in split(&gpx, &budget) {
+                assert!(part.estimated_output_size() <= max_size);
+            }
+        }
+    }
+
+    #[test]
+    fn split_by_size() {
+        let gpx = synthetic_gpx(2, 2_000);
+        let max_size = 100_000;
+        let budget = SplitBudget {
+            max_size: Some(max_size),
+            ..Default::default()
+        };
+
+        let parts = split(&gpx, &budget);
+        assert!(parts.len() > 1);
+        for part in &parts {
+            assert!(part.estimated_output_size() <= max_size);
+            let mut written = Vec::new();
+            crate::write(part, &mut written).unwrap();
+            assert!(written.len() <= max_size);
+        }
+
+        let unlimited = split(&gpx, &SplitBudget::default());
+        assert_eq!(unlimited, [gpx]);
+    }
+}
@@ -0,0 +1,346 @@
+//! stats sums up the distance, climb and duration of tracks.
+
+use time::{Duration, OffsetDateTime};
+
+use crate::distance::track_length;
+use crate::{Gpx, PauseOptions, Track, TrackSegment};
+
+/// Options for [`Track::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsOptions {
+    /// Smallest change in elevation, in meters, that counts as climbing or
+    /// descending. Smaller ups and downs, which are usually noise, are
+    /// ignored until they add up to this much. The default of 0 counts every
+    /// change.
+    pub elevation_threshold: f64,
+
+    /// How pauses are found for [`TrackStats::moving_duration`].
+    pub pauses: PauseOptions,
+}
+
+impl Default for StatsOptions {
+    fn default() -> StatsOptions {
+        StatsOptions {
+            elevation_threshold: 0.0,
+            pauses: PauseOptions::default(),
+        }
+    }
+}
+
+/// Statistics of a track, as computed by [`Track::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackStats {
+    /// Length of the track in meters, without the gaps between segments.
+    pub distance: f64,
+
+    /// Total climb in meters.
+    pub elevation_gain: f64,
+
+    /// Total descent in meters, as a positive number.
+    pub elevation_loss: f64,
+
+    /// Time between the earliest and the latest point, if any point has a
+    /// time.
+    pub duration: Option<Duration>,
+
+    /// Time spent moving, see [`Track::moving_duration`].
+    pub moving_duration: Option<Duration>,
+
+    /// Number of points in all segments.
+    pub points: usize,
+}
+
+impl TrackStats {
+    /// Gives the distance divided by the duration, in meters per second.
+    ///
+    /// Returns `None` if there is no duration or it is zero, as for a
+    /// single point or points that all have the same time.
+    pub fn average_speed(&self) -> Option<f64> {
+        speed(self.distance, self.duration?)
+    }
+
+    /// Gives the distance divided by the moving duration, in meters per
+    /// second. Returns `None` where [`TrackStats::average_speed`] does, and
+    /// if the track never moved.
+    pub fn average_moving_speed(&self) -> Option<f64> {
+        speed(self.distance, self.moving_duration?)
+    }
+}
+
+fn speed(distance: f64, duration: Duration) -> Option<f64> {
+    let seconds = duration.as_seconds_f64();
+    let speed = distance / seconds;
+    if seconds > 0.0 && speed.is_finite() {
+        Some(speed)
+    } else {
+        None
+    }
+}
+
+impl Track {
+    /// Computes the statistics of the track.
+    ///
+    /// Elevations are compared within each segment only, and points without
+    /// an elevation, or with one that is not finite, are skipped. Sums that
+    /// would overflow stay at `f64::MAX`.
+    ///
+    /// ```
+    /// use gpx::{StatsOptions, Track, TrackSegment, Waypoint};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// for (x, elevation) in [(0.0, 10.0), (0.001, 15.0), (0.002, 12.0)].iter() {
+    ///     let mut point = Waypoint::new_lat_lon(0.0, *x).unwrap();
+    ///     point.elevation = Some(*elevation);
+    ///     segment.points.push(point);
+    /// }
+    /// let mut track = Track::new();
+    /// track.segments.push(segment);
+    ///
+    /// let stats = track.stats(&StatsOptions::default());
+    /// assert_eq!(stats.points, 3);
+    /// assert_eq!(stats.elevation_gain, 5.0);
+    /// assert_eq!(stats.elevation_loss, 3.0);
+    /// assert!((stats.distance - 222.39).abs() < 0.01);
+    /// ```
+    pub fn stats(&self, options: &StatsOptions) -> TrackStats {
+        let (mut elevation_gain, mut elevation_loss) = (0.0, 0.0);
+        for segment in &self.segments {
+            let (gain, loss) = elevation_changes(segment, options.elevation_threshold);
+            elevation_gain = (elevation_gain + gain).min(f64::MAX);
+            elevation_loss = (elevation_loss + loss).min(f64::MAX);
+        }
+
+        let points = self
+            .segments
+            .iter()
+            .flat_map(|segment| segment.points.iter());
+        let times = points.clone().filter_map(|point| point.time);
+        let duration = match (times.clone().min(), times.max()) {
+            (Some(earliest), Some(latest)) => {
+                Some(OffsetDateTime::from(latest) - OffsetDateTime::from(earliest))
+            }
+            _ => None,
+        };
+
+        TrackStats {
+            distance: track_length(self),
+            elevation_gain,
+            elevation_loss,
+            duration,
+            moving_duration: self.moving_duration(&options.pauses),
+            points: points.count(),
+        }
+    }
+}
+
+impl Gpx {
+    /// Computes the statistics of every track, see [`Track::stats`].
+    pub fn track_stats(&self, options: &StatsOptions) -> Vec<TrackStats> {
+        self.tracks
+            .iter()
+            .map(|track| track.stats(options))
+            .collect()
+    }
+}
+
+/// Sums up the climb and descent of `segment`, counting changes once they
+/// reach `threshold`.
+fn elevation_changes(segment: &TrackSegment, threshold: f64) -> (f64, f64) {
+    let (mut gain, mut loss) = (0.0, 0.0);
+    let mut reference: Option<f64> = None;
+    let elevations = segment
+        .points
+        .iter()
+        .filter_map(|point| point.elevation)
+        .filter(|elevation| elevation.is_finite());
+    for elevation in elevations {
+        let last = match reference {
+            Some(last) => last,
+            None => {
+                reference = Some(elevation);
+                continue;
+            }
+        };
+        // Elevations of opposite signs near the ends of f64 overflow.
+        let change = (elevation - last).clamp(-f64::MAX, f64::MAX);
+        if change.abs() >= threshold && change != 0.0 {
+            if change > 0.0 {
+                gain = (gain + change).min(f64::MAX);
+            } else {
+                loss = (loss - change).min(f64::MAX);
+            }
+            reference = Some(elevation);
+        }
+    }
+    (gain, loss)
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::StatsOptions;
+    use crate::{Track, TrackSegment, Waypoint};
+
+    fn segment(elevations: &[Option<f64>], start: i64) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        for (i, &elevation) in elevations.iter().enumerate() {
+            let mut point = Waypoint::new(Point::new(i as f64 * 0.001, 0.0));
+            point.elevation = elevation;
+            point.time =
+                Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(start + i as i64)).into());
+            segment.points.push(point);
+        }
+        segment
+    }
+
+    #[test]
+    fn stats_sums_up_segments() {
+        let mut track = Track::new();
+        track
+            .segments
+            .push(segment(&[Some(100.0), Some(110.0), None, Some(105.0)], 0));
+        track.segments.push(segment(&[Some(0.0), Some(2.0)], 60));
+
+        let stats = track.stats(&StatsOptions::default());
+
+        assert_eq!(stats.points, 6);
+        // The jump from 105 m to 0 m between the segments is not a descent.
+        assert_eq!(stats.elevation_gain, 12.0);
+        assert_eq!(stats.elevation_loss, 5.0);
+        assert_eq!(stats.duration, Some(Duration::seconds(61)));
+        // The minute between the segments is not moving time.
+        assert_eq!(stats.moving_duration, Some(Duration::seconds(4)));
+        let expected = track.segments[0].cumulative_distances()[3]
+            + track.segments[1].cumulative_distances()[1];
+        assert_eq!(stats.distance, expected);
+    }
+
+    #[test]
+    fn stats_elevation_threshold() {
+        let mut track = Track::new();
+        // Noise of 1 m on an overall climb of 10 m, then a 4 m descent.
+        let elevations = [0.0, 1.0, 0.0, 1.0, 3.0, 2.0, 5.0, 10.0, 9.0, 6.0];
+        let elevations: Vec<_> = elevations.iter().copied().map(Some).collect();
+        track.segments.push(segment(&elevations, 0));
+
+        let all = track.stats(&StatsOptions::default());
+        assert_eq!((all.elevation_gain, all.elevation_loss), (12.0, 6.0));
+
+        let options = StatsOptions {
+            elevation_threshold: 2.0,
+            ..Default::default()
+        };
+        let smoothed = track.stats(&options);
+        assert_eq!(
+            (smoothed.elevation_gain, smoothed.elevation_loss),
+            (10.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn stats_with_degenerate_times() {
+        let options = StatsOptions::default();
+        let track = |segment: TrackSegment| {
+            let mut track = Track::new();
+            track.segments.push(segment);
+            track
+        };
+
+        let single = track(segment(&[Some(1.0)], 0)).stats(&options);
+        assert_eq!(single.distance, 0.0);
+        assert_eq!(single.duration, Some(Duration::ZERO));
+        assert_eq!(single.average_speed(), None);
+        assert_eq!(single.average_moving_speed(), None);
+
+        // Points apart, but all at the same time.
+        let mut same = segment(&[None; 4], 0);
+        let first = same.points[0].time;
+        for point in &mut same.points {
+            point.time = first;
+        }
+        let stats = track(same).stats(&options);
+        assert!(stats.distance > 0.0);
+        assert_eq!(stats.duration, Some(Duration::ZERO));
+        assert_eq!(stats.moving_duration, Some(Duration::ZERO));
+        assert_eq!(stats.average_speed(), None);
+
+        let mut duplicate = segment(&[None; 4], 0);
+        duplicate.points[2].time = duplicate.points[1].time;
+        let stats = track(duplicate).stats(&options);
+        assert_eq!(stats.duration, Some(Duration::seconds(3)));
+        assert!(stats.average_speed().unwrap().is_finite());
+
+        let mut reversed = segment(&[None; 4], 0);
+        reversed.points.reverse();
+        let stats = track(reversed).stats(&options);
+        assert_eq!(stats.duration, Some(Duration::seconds(3)));
+        assert!(stats.moving_duration.unwrap() >= Duration::ZERO);
+        assert!(stats.average_moving_speed().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn stats_with_extreme_values() {
+        let options = StatsOptions::default();
+        let mut track = Track::new();
+        let elevations = [
+            Some(f64::MAX),
+            Some(-f64::MAX),
+            Some(f64::NAN),
+            Some(f64::MAX),
+            Some(f64::INFINITY),
+            Some(f64::MIN_POSITIVE),
+        ];
+        let mut segment = segment(&elevations, 0);
+        for point in &mut segment.points {
+            point.extras_mut().speed = Some(f64::MAX);
+        }
+        track.segments.push(segment.clone());
+        track.segments.push(segment);
+
+        let stats = track.stats(&options);
+        assert_eq!(stats.elevation_gain, f64::MAX);
+        assert_eq!(stats.elevation_loss, f64::MAX);
+        let recorded = StatsOptions {
+            pauses: crate::PauseOptions {
+                use_recorded_speed: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            track.stats(&recorded).moving_duration,
+            Some(Duration::seconds(10))
+        );
+
+        // Times as far apart as the format allows.
+        let points = &mut track.segments[0].points;
+        points[0].time = Some(
+            OffsetDateTime::UNIX_EPOCH
+                .replace_date(time::Date::from_calendar_date(1, time::Month::January, 1).unwrap())
+                .into(),
+        );
+        points[5].time = Some(
+            OffsetDateTime::UNIX_EPOCH
+                .replace_date(
+                    time::Date::from_calendar_date(9999, time::Month::December, 31).unwrap(),
+                )
+                .into(),
+        );
+        let stats = track.stats(&options);
+        assert!(stats.duration.unwrap() > Duration::days(3_600_000));
+        assert!(stats.average_speed().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn stats_of_empty_track() {
+        let stats = Track::new().stats(&StatsOptions::default());
+
+        assert_eq!(stats.points, 0);
+        assert_eq!(stats.distance, 0.0);
+        assert_eq!(stats.duration, None);
+        assert_eq!(stats.moving_duration, None);
+        assert_eq!(stats.average_speed(), None);
+    }
+}
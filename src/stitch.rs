@@ -0,0 +1,343 @@
+//! stitch joins tracks recorded one after another, such as the files of
+//! a ride that was interrupted to swap batteries, into one track.
+
+use std::cmp::Ordering;
+
+use time::{Duration, OffsetDateTime};
+
+use crate::errors::{GpxError, GpxResult};
+use crate::{Track, Waypoint};
+
+/// Options for [`stitch`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StitchOptions {
+    /// Joins where the next track starts at most this long after the
+    /// previous one ended are merged into one segment.
+    pub merge_gap: Option<Duration>,
+
+    /// Joins where the next track starts at most this many meters from
+    /// where the previous one ended are merged into one segment.
+    pub merge_distance_m: Option<f64>,
+}
+
+impl StitchOptions {
+    /// Whether the join from `last` to `first` is merged: all thresholds
+    /// that are given have to hold, and at least one has to be given. The
+    /// time is only checked if both points have one.
+    fn merges(&self, last: &Waypoint, first: &Waypoint) -> bool {
+        if self.merge_gap.is_none() && self.merge_distance_m.is_none() {
+            return false;
+        }
+        let close_in_time = match (self.merge_gap, last.time, first.time) {
+            (Some(gap), Some(last), Some(first)) => {
+                OffsetDateTime::from(first) - OffsetDateTime::from(last) <= gap
+            }
+            _ => true,
+        };
+        let close_in_space = self.merge_distance_m.map_or(true, |distance| {
+            last.haversine_distance_to(first) <= distance
+        });
+        close_in_time && close_in_space
+    }
+}
+
+/// Joins `tracks` into one track, in the order they were recorded.
+///
+/// If every track has a time, they are ordered by their first time, and
+/// the points at the start of a track that are not later than the end of
+/// the previous ones are dropped as a duplicate of them. A track whose
+/// times all lie within those of another one cannot be put in order and
+/// gives a [`GpxError::InterleavedTracks`]. Otherwise the tracks are
+/// chained by proximity: the chain starts with the track whose start is
+/// farthest from the ends of all others, and goes on with the track that
+/// starts closest to where the chain ends.
+///
+/// The segments of all tracks follow each other, and the first segment of
+/// a track is appended to the last one of the previous track if `options`
+/// ask to merge the join. The name, links and all other data of the
+/// stitched track are those of the first track. Tracks without points are
+/// dropped.
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use gpx::{stitch, StitchOptions};
+///
+/// let mut tracks = Vec::new();
+/// for part in [3, 1, 2].iter() {
+///     let file = File::open(format!("tests/fixtures/stitch_part{}.gpx", part)).unwrap();
+///     tracks.extend(gpx::read(BufReader::new(file)).unwrap().tracks);
+/// }
+///
+/// let track = stitch(tracks, &StitchOptions::default()).unwrap();
+/// assert_eq!(track.name.as_deref(), Some("Ride, part 1"));
+/// assert_eq!(track.segments.len(), 3);
+/// ```
+pub fn stitch(tracks: Vec<Track>, options: &StitchOptions) -> GpxResult<Track> {
+    let tracks: Vec<(usize, Track)> = tracks
+        .into_iter()
+        .enumerate()
+        .filter(|(_, track)| first_point(track).is_some())
+        .collect();
+    let ordered = match time_order(&tracks) {
+        Some(order) => order?,
+        None => proximity_order(&tracks),
+    };
+    let mut tracks: Vec<Option<Track>> = tracks.into_iter().map(|(_, track)| Some(track)).collect();
+
+    let mut stitched: Option<Track> = None;
+    let mut end: Option<OffsetDateTime> = None;
+    for index in ordered {
+        let mut track = tracks[index].take().unwrap();
+        if let Some(end) = end {
+            trim_start(&mut track, end);
+        }
+        if let Some(last) = last_time(&track) {
+            end = Some(end.map_or(last, |end| end.max(last)));
+        }
+        let stitched = match &mut stitched {
+            Some(stitched) => stitched,
+            None => {
+                stitched = Some(track);
+                continue;
+            }
+        };
+        let mut segments = track.segments.into_iter();
+        if let (Some(previous), Some(next)) = (stitched.segments.last_mut(), segments.next()) {
+            match (previous.points.last(), next.points.first()) {
+                (Some(last), Some(first)) if options.merges(last, first) => {
+                    previous.points.extend(next.points)
+                }
+                _ => stitched.segments.push(next),
+            }
+        }
+        stitched.segments.extend(segments);
+    }
+    Ok(stitched.unwrap_or_default())
+}
+
+fn first_point(track: &Track) -> Option<&Waypoint> {
+    track
+        .segments
+        .iter()
+        .flat_map(|segment| segment.points.first())
+        .next()
+}
+
+fn last_point(track: &Track) -> Option<&Waypoint> {
+    track
+        .segments
+        .iter()
+        .rev()
+        .flat_map(|segment| segment.points.last())
+        .next()
+}
+
+fn times(track: &Track) -> impl DoubleEndedIterator<Item = OffsetDateTime> + '_ {
+    track
+        .segments
+        .iter()
+        .flat_map(|segment| &segment.points)
+        .filter_map(|point| point.time.map(OffsetDateTime::from))
+}
+
+fn first_time(track: &Track) -> Option<OffsetDateTime> {
+    times(track).next()
+}
+
+fn last_time(track: &Track) -> Option<OffsetDateTime> {
+    times(track).next_back()
+}
+
+/// Orders `tracks` by their first times, if all have one.
+fn time_order(tracks: &[(usize, Track)]) -> Option<GpxResult<Vec<usize>>> {
+    let mut ranges = Vec::with_capacity(tracks.len());
+    for (index, (_, track)) in tracks.iter().enumerate() {
+        ranges.push((first_time(track)?, last_time(track)?, index));
+    }
+    ranges.sort_by_key(|&(start, end, _)| (start, end));
+    // The track that ends last so far, which every later track has to
+    // outlast.
+    let mut latest: Option<(OffsetDateTime, usize)> = None;
+    for &(_, end, index) in &ranges {
+        if let Some((latest_end, latest_index)) = latest {
+            if end <= latest_end {
+                let (a, b) = (tracks[latest_index].0, tracks[index].0);
+                return Some(Err(GpxError::InterleavedTracks(a.min(b), a.max(b))));
+            }
+        }
+        latest = Some((end, index));
+    }
+    Some(Ok(ranges.into_iter().map(|(_, _, index)| index).collect()))
+}
+
+/// Orders `tracks` into a chain of nearest ends and starts.
+fn proximity_order(tracks: &[(usize, Track)]) -> Vec<usize> {
+    let starts: Vec<&Waypoint> = tracks.iter().flat_map(|(_, t)| first_point(t)).collect();
+    let ends: Vec<&Waypoint> = tracks.iter().flat_map(|(_, t)| last_point(t)).collect();
+    let nearest_end = |start: usize| {
+        (0..ends.len())
+            .filter(|&end| end != start)
+            .map(|end| ends[end].haversine_distance_to(starts[start]))
+            .fold(f64::INFINITY, f64::min)
+    };
+    let first = match (0..starts.len())
+        .map(|start| (nearest_end(start), start))
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+    {
+        Some((_, first)) => first,
+        None => return Vec::new(),
+    };
+
+    let mut order = vec![first];
+    let mut left: Vec<usize> = (0..starts.len()).filter(|&i| i != first).collect();
+    while !left.is_empty() {
+        let end = ends[*order.last().unwrap()];
+        let (position, _) = left
+            .iter()
+            .enumerate()
+            .map(|(position, &start)| (position, end.haversine_distance_to(starts[start])))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+            .unwrap();
+        order.push(left.remove(position));
+    }
+    order
+}
+
+/// Drops the points at the start of `track` up to the first one later than
+/// `end`, and the segments left empty.
+fn trim_start(track: &mut Track, end: OffsetDateTime) {
+    for segment in &mut track.segments {
+        let later = segment.points.iter().position(|point| {
+            point
+                .time
+                .is_some_and(|time| OffsetDateTime::from(time) > end)
+        });
+        match later {
+            Some(index) => {
+                segment.points.drain(..index);
+                break;
+            }
+            None => segment.points.clear(),
+        }
+    }
+    track.segments.retain(|segment| !segment.points.is_empty());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use time::{Duration, OffsetDateTime};
+
+    use super::{stitch, StitchOptions};
+    use crate::errors::GpxError;
+    use crate::{read, Track};
+
+    fn fixture(name: &str) -> Track {
+        let file = File::open(format!("tests/fixtures/{}.gpx", name)).unwrap();
+        read(BufReader::new(file)).unwrap().tracks.remove(0)
+    }
+
+    fn parts(order: &[usize]) -> Vec<Track> {
+        order
+            .iter()
+            .map(|part| fixture(&format!("stitch_part{}", part)))
+            .collect()
+    }
+
+    fn longitudes(track: &Track) -> Vec<f64> {
+        track
+            .segments
+            .iter()
+            .flat_map(|segment| &segment.points)
+            .map(|point| point.point().x())
+            .collect()
+    }
+
+    fn times(track: &Track) -> Vec<OffsetDateTime> {
+        track
+            .segments
+            .iter()
+            .flat_map(|segment| &segment.points)
+            .map(|point| point.time.unwrap().into())
+            .collect()
+    }
+
+    #[test]
+    fn clean_gaps_in_time_order() {
+        let track = stitch(parts(&[2, 3, 1]), &StitchOptions::default()).unwrap();
+        assert_eq!(track.name.as_deref(), Some("Ride, part 1"));
+        assert_eq!(track.segments.len(), 3);
+        let longitudes = longitudes(&track);
+        assert_eq!(longitudes.len(), 24);
+        assert!(longitudes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(times(&track).windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn merge_thresholds() {
+        // The parts are 70 seconds and 38 meters apart.
+        let cases = [
+            (Some(Duration::seconds(90)), None, 1),
+            (Some(Duration::seconds(60)), None, 3),
+            (None, Some(50.0), 1),
+            (Some(Duration::seconds(90)), Some(30.0), 3),
+        ];
+        for (merge_gap, merge_distance_m, segments) in cases.iter() {
+            let options = StitchOptions {
+                merge_gap: *merge_gap,
+                merge_distance_m: *merge_distance_m,
+            };
+            let track = stitch(parts(&[3, 1, 2]), &options).unwrap();
+            assert_eq!(track.segments.len(), *segments, "{:?}", options);
+            assert_eq!(longitudes(&track).len(), 24);
+        }
+    }
+
+    #[test]
+    fn overlap_is_trimmed() {
+        let tracks = vec![fixture("stitch_overlap_b"), fixture("stitch_overlap_a")];
+        let track = stitch(tracks, &StitchOptions::default()).unwrap();
+        assert_eq!(track.name.as_deref(), Some("Before the swap"));
+        // The last 30 seconds of the first track were recorded twice.
+        assert_eq!(track.segments[0].points.len(), 12);
+        assert_eq!(track.segments[1].points.len(), 8);
+        let times = times(&track);
+        assert_eq!(times[12] - times[11], Duration::seconds(10));
+        assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn contained_track_is_an_error() {
+        let mut inner = fixture("stitch_overlap_a");
+        inner.segments[0].points.truncate(6);
+        inner.segments[0].points.drain(..2);
+        let tracks = vec![fixture("stitch_overlap_a"), Track::new(), inner];
+        match stitch(tracks, &StitchOptions::default()) {
+            Err(GpxError::InterleavedTracks(0, 2)) => {}
+            other => panic!("expected interleaved tracks, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn proximity_without_times() {
+        let mut tracks = parts(&[3, 1, 2]);
+        for track in &mut tracks {
+            for point in &mut track.segments[0].points {
+                point.time = None;
+            }
+        }
+        let track = stitch(tracks, &StitchOptions::default()).unwrap();
+        assert_eq!(track.name.as_deref(), Some("Ride, part 1"));
+        let longitudes = longitudes(&track);
+        assert_eq!(longitudes.len(), 24);
+        assert!(longitudes.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(
+            stitch(Vec::new(), &StitchOptions::default()).unwrap(),
+            Track::new()
+        );
+    }
+}
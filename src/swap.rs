@@ -0,0 +1,281 @@
+//! swap detects and repairs documents whose producer exchanged latitude and
+//! longitude.
+//!
+//! Detection is a heuristic, so nothing is changed automatically: the caller
+//! looks at the [`SwapDiagnosis`] and decides whether to call
+//! [`Gpx::swap_coordinates`] or [`Track::swap_coordinates`].
+
+use geo_types::{Point, Rect};
+
+use crate::distance::haversine;
+use crate::{Gpx, Track, Waypoint};
+
+/// How many sample points a [`SwapEvidence`] keeps at most.
+const MAX_SAMPLES: usize = 3;
+
+/// The result of [`Gpx::detect_swapped_coordinates`], with the evidence for
+/// each part of the document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapDiagnosis {
+    /// The evidence for each track, in the order of the tracks.
+    pub tracks: Vec<SwapEvidence>,
+
+    /// The evidence for the standalone waypoints, taken together.
+    pub waypoints: SwapEvidence,
+}
+
+impl SwapDiagnosis {
+    /// Whether any part of the document looks swapped.
+    pub fn any_likely_swapped(&self) -> bool {
+        self.waypoints.likely_swapped || self.tracks.iter().any(|track| track.likely_swapped)
+    }
+}
+
+/// What speaks for the points of a track, or for the standalone waypoints,
+/// having latitude and longitude exchanged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SwapEvidence {
+    /// The number of points looked at.
+    pub points: usize,
+
+    /// The number of points whose latitude is outside of [-90, 90] while
+    /// their longitude is inside of it. Reading a file rejects such points,
+    /// but documents built in code or read leniently may have them.
+    pub latitudes_out_of_range: usize,
+
+    /// The number of points outside of the bounds in the metadata that are
+    /// inside of them when swapped. Always 0 if there are no bounds.
+    pub inside_bounds_only_swapped: usize,
+
+    /// The distance in meters from the center of the points to the center
+    /// of all other points of the document, or `None` if there are no other
+    /// points.
+    pub distance_to_rest_m: Option<f64>,
+
+    /// Like `distance_to_rest_m`, but with the points of this part swapped.
+    ///
+    /// A much smaller distance means the points only cluster with the rest
+    /// of the document when swapped. That alone is not conclusive, since it
+    /// is just as true for the rest when this part is correct, so it does
+    /// not count for `likely_swapped`.
+    pub swapped_distance_to_rest_m: Option<f64>,
+
+    /// Up to three of the points that count for `latitudes_out_of_range` or
+    /// `inside_bounds_only_swapped`, as they are in the document.
+    pub samples: Vec<Point<f64>>,
+
+    /// Whether most points look swapped, by their latitude or by the
+    /// bounds.
+    pub likely_swapped: bool,
+}
+
+impl Gpx {
+    /// Looks for signs of latitude and longitude being exchanged, for each
+    /// track and for the standalone waypoints.
+    ///
+    /// A part of the document is likely swapped if most of its points have
+    /// a latitude out of range, or lie outside of the bounds in the metadata
+    /// but inside of them when swapped. Points that merely have a larger
+    /// latitude than longitude, such as in the southern hemisphere near the
+    /// prime meridian, are not suspicious on their own. Route points count
+    /// as the rest of the document, but get no evidence of their own.
+    ///
+    /// ```
+    /// use gpx::{Gpx, Waypoint};
+    /// use geo_types::Point;
+    ///
+    /// let mut gpx = Gpx::default();
+    /// // Tokyo, written as (lat, lon) instead of (lon, lat).
+    /// gpx.waypoints.push(Waypoint::new(Point::new(35.68, 139.69)));
+    ///
+    /// let diagnosis = gpx.detect_swapped_coordinates();
+    /// assert!(diagnosis.waypoints.likely_swapped);
+    ///
+    /// gpx.swap_coordinates();
+    /// assert_eq!(gpx.waypoints[0].point(), Point::new(139.69, 35.68));
+    /// ```
+    pub fn detect_swapped_coordinates(&self) -> SwapDiagnosis {
+        let bounds = self.metadata.as_ref().and_then(|metadata| metadata.bounds);
+        let groups: Vec<Vec<Point<f64>>> = std::iter::once(self.waypoints.iter().collect())
+            .chain(self.tracks.iter().map(|track| {
+                track
+                    .segments
+                    .iter()
+                    .flat_map(|segment| &segment.points)
+                    .collect()
+            }))
+            .chain(std::iter::once(
+                self.routes.iter().flat_map(|route| &route.points).collect(),
+            ))
+            .map(|points: Vec<&Waypoint>| points.into_iter().map(Waypoint::point).collect())
+            .collect();
+
+        let mut evidence = (0..groups.len() - 1).map(|index| {
+            let rest = groups
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .flat_map(|(_, points)| points.iter().copied());
+            examine(&groups[index], centroid(rest), bounds)
+        });
+        let waypoints = evidence.next().unwrap_or_default();
+        SwapDiagnosis {
+            tracks: evidence.collect(),
+            waypoints,
+        }
+    }
+
+    /// Exchanges the longitude and latitude of every waypoint, route point
+    /// and track point. Bounds in the metadata are recomputed, as by
+    /// [`Gpx::map_coordinates`].
+    pub fn swap_coordinates(&mut self) {
+        self.map_coordinates(swap);
+    }
+}
+
+impl Track {
+    /// Exchanges the longitude and latitude of every point of the track,
+    /// for when only some tracks of a document are swapped.
+    pub fn swap_coordinates(&mut self) {
+        for point in self
+            .segments
+            .iter_mut()
+            .flat_map(|segment| &mut segment.points)
+        {
+            point.set_point(swap(point.point()));
+        }
+    }
+}
+
+fn swap(point: Point<f64>) -> Point<f64> {
+    Point::new(point.y(), point.x())
+}
+
+fn examine(
+    points: &[Point<f64>],
+    rest: Option<Point<f64>>,
+    bounds: Option<Rect<f64>>,
+) -> SwapEvidence {
+    let mut evidence = SwapEvidence {
+        points: points.len(),
+        ..Default::default()
+    };
+    for &point in points {
+        let out_of_range = point.y().abs() > 90.0 && point.x().abs() <= 90.0;
+        let only_swapped =
+            bounds.is_some_and(|bounds| !contains(bounds, point) && contains(bounds, swap(point)));
+        evidence.latitudes_out_of_range += out_of_range as usize;
+        evidence.inside_bounds_only_swapped += only_swapped as usize;
+        if (out_of_range || only_swapped) && evidence.samples.len() < MAX_SAMPLES {
+            evidence.samples.push(point);
+        }
+    }
+    if let (Some(center), Some(rest)) = (centroid(points.iter().copied()), rest) {
+        evidence.distance_to_rest_m = Some(haversine(center, rest));
+        evidence.swapped_distance_to_rest_m = Some(haversine(swap(center), rest));
+    }
+    let most = |count: usize| count > 0 && count * 2 > points.len();
+    evidence.likely_swapped =
+        most(evidence.latitudes_out_of_range) || most(evidence.inside_bounds_only_swapped);
+    evidence
+}
+
+fn contains(bounds: Rect<f64>, point: Point<f64>) -> bool {
+    let (min, max) = (bounds.min(), bounds.max());
+    (min.x..=max.x).contains(&point.x()) && (min.y..=max.y).contains(&point.y())
+}
+
+/// Gives the mean of the coordinates of `points`, if there are any.
+fn centroid(points: impl Iterator<Item = Point<f64>>) -> Option<Point<f64>> {
+    let (count, sum) = points.fold((0, Point::new(0.0, 0.0)), |(count, sum), point| {
+        (count + 1, sum + point)
+    });
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use geo_types::Point;
+
+    use crate::{read, Gpx, Waypoint};
+
+    fn fixture(name: &str) -> Gpx {
+        let file = File::open(format!("tests/fixtures/{}", name)).unwrap();
+        read(BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn swapped_track_is_diagnosed() {
+        let mut gpx = fixture("swapped_coordinates.gpx");
+        let diagnosis = gpx.detect_swapped_coordinates();
+
+        let track = &diagnosis.tracks[0];
+        assert!(track.likely_swapped);
+        assert_eq!(track.points, 5);
+        assert_eq!(track.latitudes_out_of_range, 0);
+        assert_eq!(track.inside_bounds_only_swapped, 5);
+        assert_eq!(track.samples.len(), 3);
+        assert_eq!(track.samples[0], Point::new(47.3769, 8.5417));
+        assert!(track.distance_to_rest_m.unwrap() > 4_000_000.0);
+        assert!(track.swapped_distance_to_rest_m.unwrap() < 1_000.0);
+        assert!(!diagnosis.waypoints.likely_swapped);
+        assert!(diagnosis.any_likely_swapped());
+
+        gpx.tracks[0].swap_coordinates();
+        assert!(!gpx.detect_swapped_coordinates().any_likely_swapped());
+        assert_eq!(
+            gpx.tracks[0].segments[0].points[0].point(),
+            Point::new(8.5417, 47.3769)
+        );
+    }
+
+    #[test]
+    fn southern_hemisphere_near_meridian_is_not_swapped() {
+        let diagnosis = fixture("southern_antimeridian.gpx").detect_swapped_coordinates();
+        assert!(!diagnosis.any_likely_swapped());
+        assert_eq!(diagnosis.tracks[0].inside_bounds_only_swapped, 0);
+        assert!(diagnosis.tracks[0].samples.is_empty());
+        assert!(diagnosis.tracks[0].distance_to_rest_m.unwrap() < 10_000.0);
+
+        // Near the prime meridian, the latitude is larger than the
+        // longitude, and swapping would still give valid coordinates.
+        let mut gpx = Gpx::default();
+        for x in [0.12, 0.35, 1.8].iter() {
+            gpx.waypoints.push(Waypoint::new(Point::new(*x, -33.9)));
+        }
+        let diagnosis = gpx.detect_swapped_coordinates();
+        assert!(!diagnosis.any_likely_swapped());
+        assert_eq!(diagnosis.waypoints.distance_to_rest_m, None);
+    }
+
+    #[test]
+    fn out_of_range_latitudes() {
+        let mut gpx = Gpx::default();
+        gpx.waypoints.push(Waypoint::new(Point::new(35.68, 139.69)));
+        gpx.waypoints
+            .push(Waypoint::new(Point::new(-33.87, 151.21)));
+        gpx.waypoints.push(Waypoint::new(Point::new(139.0, 35.0)));
+
+        let evidence = gpx.detect_swapped_coordinates().waypoints;
+        assert_eq!(evidence.latitudes_out_of_range, 2);
+        assert_eq!(
+            evidence.samples,
+            vec![Point::new(35.68, 139.69), Point::new(-33.87, 151.21)]
+        );
+        assert!(evidence.likely_swapped);
+        assert!(Gpx::default()
+            .detect_swapped_coordinates()
+            .tracks
+            .is_empty());
+
+        gpx.swap_coordinates();
+        assert_eq!(gpx.waypoints[2].point(), Point::new(35.0, 139.0));
+    }
+}
@@ -0,0 +1,200 @@
+//! taxonomy sorts waypoints by hierarchical `<type>` values, such as
+//! `poi/food/cafe`.
+
+use std::collections::BTreeMap;
+
+use crate::{ChangeReport, Gpx, Waypoint};
+
+/// Options for matching types, as with
+/// [`Gpx::waypoints_of_type_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TypeMatchOptions {
+    /// Whether `poi/Food` and `poi/food` are different types. Otherwise
+    /// ASCII letters match regardless of case.
+    pub case_sensitive: bool,
+}
+
+impl Waypoint {
+    /// Gives the parts of the type, split at `/`, such as `["poi", "food",
+    /// "cafe"]` for `poi/food/cafe`, or nothing for a waypoint without a
+    /// type. Whitespace around the parts and empty parts are left out.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let mut waypoint = Waypoint::new_lat_lon(47.37, 8.54).unwrap();
+    /// assert!(waypoint.type_path().is_empty());
+    /// waypoint.extras_mut()._type = Some("poi/food/cafe".into());
+    /// assert_eq!(waypoint.type_path(), ["poi", "food", "cafe"]);
+    /// ```
+    pub fn type_path(&self) -> Vec<&str> {
+        self.extras()
+            ._type
+            .as_deref()
+            .map_or_else(Vec::new, split_type)
+    }
+
+    /// Whether the type of the waypoint is `prefix` or below it, comparing
+    /// whole parts, so that `poi/food` matches `poi/food/cafe` but `poi/foo`
+    /// doesn't match `poi/food`.
+    fn has_type_prefix(&self, prefix: &[&str], options: &TypeMatchOptions) -> bool {
+        let path = self.type_path();
+        path.len() >= prefix.len()
+            && path.iter().zip(prefix).all(|(part, expected)| {
+                if options.case_sensitive {
+                    part == expected
+                } else {
+                    part.eq_ignore_ascii_case(expected)
+                }
+            })
+    }
+}
+
+impl Gpx {
+    /// Gives the waypoints whose type is `prefix` or below it, see
+    /// [`Waypoint::type_path`], ignoring ASCII case. An empty prefix matches
+    /// all waypoints.
+    ///
+    /// ```
+    /// use gpx::{Gpx, Waypoint};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// for _type in ["poi/food/cafe", "poi/football"].iter() {
+    ///     let mut waypoint = Waypoint::new_lat_lon(47.37, 8.54).unwrap();
+    ///     waypoint.extras_mut()._type = Some(_type.to_string());
+    ///     gpx.waypoints.push(waypoint);
+    /// }
+    /// assert_eq!(gpx.waypoints_of_type("POI/Food").len(), 1);
+    /// assert_eq!(gpx.waypoints_of_type("poi/foo").len(), 0);
+    /// ```
+    pub fn waypoints_of_type(&self, prefix: &str) -> Vec<&Waypoint> {
+        self.waypoints_of_type_with_options(prefix, &TypeMatchOptions::default())
+    }
+
+    /// Like [`Gpx::waypoints_of_type`], tuned by `options`.
+    pub fn waypoints_of_type_with_options(
+        &self,
+        prefix: &str,
+        options: &TypeMatchOptions,
+    ) -> Vec<&Waypoint> {
+        let prefix = split_type(prefix);
+        self.waypoints
+            .iter()
+            .filter(|waypoint| waypoint.has_type_prefix(&prefix, options))
+            .collect()
+    }
+
+    /// Gives the indices of the waypoints of every type, by the type as it
+    /// is written. Waypoints without a type are left out.
+    pub fn group_waypoints_by_type(&self) -> BTreeMap<String, Vec<usize>> {
+        let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, waypoint) in self.waypoints.iter().enumerate() {
+            if let Some(_type) = &waypoint.extras()._type {
+                groups.entry(_type.clone()).or_default().push(index);
+            }
+        }
+        groups
+    }
+
+    /// Removes the waypoints whose type is not `prefix` or below it, see
+    /// [`Gpx::waypoints_of_type`].
+    pub fn retain_waypoints_of_type(&mut self, prefix: &str) -> ChangeReport {
+        self.retain_waypoints_of_type_with_options(prefix, &TypeMatchOptions::default())
+    }
+
+    /// Like [`Gpx::retain_waypoints_of_type`], tuned by `options`.
+    pub fn retain_waypoints_of_type_with_options(
+        &mut self,
+        prefix: &str,
+        options: &TypeMatchOptions,
+    ) -> ChangeReport {
+        self.bump_generation();
+        let prefix = split_type(prefix);
+        let mut report = ChangeReport::new("retain_waypoints_of_type", self.all_points().count());
+        let before = self.waypoints.len();
+        self.waypoints
+            .retain(|waypoint| waypoint.has_type_prefix(&prefix, options));
+        report.record(before - self.waypoints.len(), 0);
+        report
+    }
+}
+
+fn split_type(_type: &str) -> Vec<&str> {
+    _type
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::TypeMatchOptions;
+    use crate::{read, Gpx, Waypoint};
+
+    fn fixture() -> Gpx {
+        read(File::open("tests/fixtures/waypoint_types.gpx").unwrap()).unwrap()
+    }
+
+    fn names(waypoints: &[&Waypoint]) -> Vec<String> {
+        waypoints
+            .iter()
+            .map(|waypoint| waypoint.name.clone().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn prefix_matches_whole_parts() {
+        let gpx = fixture();
+        assert_eq!(
+            names(&gpx.waypoints_of_type("poi/food")),
+            ["Café Schober", "Zeughauskeller", "Sprüngli", "Food bank"]
+        );
+        assert_eq!(
+            names(&gpx.waypoints_of_type("poi/food/cafe/")),
+            ["Café Schober", "Sprüngli"]
+        );
+        assert!(gpx.waypoints_of_type("poi/foo").is_empty());
+        assert!(gpx.waypoints_of_type("poi/foot").is_empty());
+        assert!(gpx.waypoints_of_type("poi/food/cafe/outdoor").is_empty());
+        assert_eq!(gpx.waypoints_of_type("poi").len(), 5);
+        assert_eq!(gpx.waypoints_of_type("").len(), 7);
+
+        let sensitive = TypeMatchOptions {
+            case_sensitive: true,
+        };
+        assert_eq!(
+            names(&gpx.waypoints_of_type_with_options("poi/food", &sensitive)),
+            ["Café Schober", "Sprüngli", "Food bank"]
+        );
+    }
+
+    #[test]
+    fn group_and_retain_by_type() {
+        let mut gpx = fixture();
+        let groups = gpx.group_waypoints_by_type();
+        let expected: Vec<(&str, Vec<usize>)> = vec![
+            ("POI/Food/Restaurant", vec![2]),
+            ("poi/food", vec![6]),
+            ("poi/food/cafe", vec![0, 5]),
+            ("poi/football", vec![3]),
+            ("transport/rail", vec![1]),
+        ];
+        assert_eq!(
+            groups
+                .iter()
+                .map(|(key, indices)| (key.as_str(), indices.clone()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        assert_eq!(gpx.retain_waypoints_of_type("Poi/Food").removed, 3);
+        let kept: Vec<&Waypoint> = gpx.waypoints.iter().collect();
+        assert_eq!(
+            names(&kept),
+            ["Café Schober", "Zeughauskeller", "Sprüngli", "Food bank"]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! testutil generates plausible documents for tests, if the `test-util`
+//! feature is enabled.
+//!
+//! The tracks head roughly east in gentle curves, with increasing times and
+//! a gentle elevation profile. The plain generators always give the same
+//! data; the `_seeded` ones add some noise, which is the same for the same
+//! seed.
+//!
+//! ```
+//! use gpx::testutil::synthetic_gpx;
+//!
+//! let gpx = synthetic_gpx(2, 100);
+//! assert_eq!(gpx.tracks.len(), 2);
+//! assert_eq!(gpx.tracks[0].segments[0].points.len(), 100);
+//! assert!(gpx.validate().is_ok());
+//! ```
+
+use geo_types::Point;
+use time::{Duration, OffsetDateTime};
+
+use crate::{Gpx, GpxVersion, Metadata, Track, TrackSegment, Waypoint};
+
+/// Meters per degree of latitude, near enough.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Generates a track with one segment of `points` points, starting at
+/// `start` at `start_time`, with the points `spacing_m` meters and
+/// `interval` apart.
+pub fn synthetic_track(
+    points: usize,
+    start: Point<f64>,
+    start_time: OffsetDateTime,
+    spacing_m: f64,
+    interval: Duration,
+) -> Track {
+    generate_track(points, start, start_time, spacing_m, interval, None)
+}
+
+/// Like [`synthetic_track`], but with the heading, spacing and elevation of
+/// the points varied by noise generated from `seed`.
+pub fn synthetic_track_seeded(
+    seed: u64,
+    points: usize,
+    start: Point<f64>,
+    start_time: OffsetDateTime,
+    spacing_m: f64,
+    interval: Duration,
+) -> Track {
+    let mut random = Random::new(seed);
+    generate_track(
+        points,
+        start,
+        start_time,
+        spacing_m,
+        interval,
+        Some(&mut random),
+    )
+}
+
+/// Generates a GPX 1.1 document with `tracks` tracks of `points_per_track`
+/// points each, see [`synthetic_track`].
+///
+/// The tracks start 0.01° of latitude apart, an hour after one another, with
+/// a point every 10 meters and 5 seconds.
+pub fn synthetic_gpx(tracks: usize, points_per_track: usize) -> Gpx {
+    generate_gpx(tracks, points_per_track, None)
+}
+
+/// Like [`synthetic_gpx`], but with the tracks of
+/// [`synthetic_track_seeded`].
+pub fn synthetic_gpx_seeded(seed: u64, tracks: usize, points_per_track: usize) -> Gpx {
+    let mut random = Random::new(seed);
+    generate_gpx(tracks, points_per_track, Some(&mut random))
+}
+
+fn generate_gpx(tracks: usize, points_per_track: usize, mut random: Option<&mut Random>) -> Gpx {
+    let start_time = OffsetDateTime::from_unix_timestamp(1_609_488_000).unwrap();
+    let tracks = (0..tracks)
+        .map(|t| {
+            let mut track = generate_track(
+                points_per_track,
+                Point::new(11.0, 47.0 + t as f64 * 0.01),
+                start_time + Duration::hours(t as i64),
+                10.0,
+                Duration::seconds(5),
+                random.as_deref_mut(),
+            );
+            track.name = Some(format!("Synthetic track {}", t + 1));
+            track
+        })
+        .collect();
+    Gpx {
+        version: GpxVersion::Gpx11,
+        creator: Some("gpx testutil".into()),
+        metadata: Some(Metadata {
+            name: Some("Synthetic tracks".into()),
+            time: Some(start_time.into()),
+            ..Default::default()
+        }),
+        tracks,
+        ..Default::default()
+    }
+}
+
+fn generate_track(
+    points: usize,
+    start: Point<f64>,
+    start_time: OffsetDateTime,
+    spacing_m: f64,
+    interval: Duration,
+    mut random: Option<&mut Random>,
+) -> Track {
+    let mut segment = TrackSegment::new();
+    let (mut position, mut heading, mut time) = (start, 90f64, start_time);
+    for i in 0..points {
+        let mut noise = |scale: f64| random.as_mut().map_or(0.0, |r| r.signed() * scale);
+        let mut point = Waypoint::new(position);
+        point.elevation = Some(200.0 + 30.0 * (i as f64 / 100.0).sin() + noise(0.5));
+        point.time = Some(time.into());
+        segment.points.push(point);
+        time += interval;
+
+        heading += 0.4 * (i as f64 / 50.0).cos() + noise(2.0);
+        let distance = spacing_m * (1.0 + noise(0.1));
+        let latitude = position.y() + distance * heading.to_radians().cos() / METERS_PER_DEGREE;
+        let longitude = position.x()
+            + distance * heading.to_radians().sin()
+                / (METERS_PER_DEGREE * position.y().to_radians().cos());
+        position = Point::new(longitude, latitude);
+    }
+    let mut track = Track::new();
+    track.segments.push(segment);
+    track
+}
+
+/// A small linear congruential generator, to stay reproducible without
+/// extra dependencies. The tests of other modules use it too.
+pub(crate) struct Random(u64);
+
+impl Random {
+    /// Starts the numbers that `seed` gives.
+    pub(crate) fn new(seed: u64) -> Random {
+        Random(seed)
+    }
+
+    /// Gives a number from 0 on, less than 1.
+    pub(crate) fn unit(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Gives a number between -1 and 1.
+    fn signed(&mut self) -> f64 {
+        self.unit() * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::{synthetic_gpx, synthetic_gpx_seeded, synthetic_track};
+    use crate::{read, write_to_string};
+
+    #[test]
+    fn synthetic_track_is_plausible() {
+        let start_time = OffsetDateTime::UNIX_EPOCH;
+        let track = synthetic_track(
+            200,
+            Point::new(-122.3, 47.6),
+            start_time,
+            25.0,
+            Duration::seconds(10),
+        );
+        let points = &track.segments[0].points;
+
+        assert_eq!(points.len(), 200);
+        assert_eq!(points[0].point(), Point::new(-122.3, 47.6));
+        assert_eq!(points[0].time, Some(start_time.into()));
+        assert!(points.windows(2).all(|pair| pair[0].time < pair[1].time));
+        let distances = track.segments[0].cumulative_distances();
+        for pair in distances.windows(2) {
+            assert!((pair[1] - pair[0] - 25.0).abs() < 0.5);
+        }
+        assert!(points
+            .iter()
+            .all(|point| (170.0..=230.0).contains(&point.elevation.unwrap())));
+    }
+
+    #[test]
+    fn synthetic_gpx_survives_write_and_read() {
+        let gpx = synthetic_gpx(3, 50);
+        assert!(gpx.validate().is_ok());
+
+        let xml = write_to_string(&gpx).unwrap();
+        assert_eq!(read(xml.as_bytes()).unwrap(), gpx);
+    }
+
+    #[test]
+    fn seeded_generators_are_reproducible() {
+        let gpx = synthetic_gpx_seeded(7, 2, 100);
+
+        assert!(gpx.validate().is_ok());
+        assert_eq!(gpx, synthetic_gpx_seeded(7, 2, 100));
+        assert_ne!(gpx, synthetic_gpx_seeded(8, 2, 100));
+        assert_ne!(gpx, synthetic_gpx(2, 100));
+    }
+}
@@ -0,0 +1,386 @@
+//! text cleans up the text fields of a document, such as names pasted
+//! from phones with invisible characters in them.
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{ChangeReport, Gpx, Link, Person, Route, Track, Waypoint};
+
+/// Options for [`Gpx::normalize_text`]. Everything is enabled by default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextNormalizeOptions {
+    /// Whether to bring text into Unicode normalization form C, so that an
+    /// `é` written as `e` and a combining accent, as in macOS file names,
+    /// becomes the single character. This needs the `unicode` feature, and
+    /// is ignored without it.
+    pub nfc: bool,
+
+    /// Whether to remove control characters and the invisible U+200B zero
+    /// width space, U+2060 word joiner and U+FEFF byte order mark. Tabs and
+    /// line breaks are kept in descriptions, and become spaces elsewhere.
+    /// Windows and old Mac line breaks become `\n` first.
+    /// The zero width joiner and non-joiner are kept, as emoji and some
+    /// scripts need them.
+    pub strip_invisible: bool,
+
+    /// Whether to replace runs of whitespace with a single space, in all
+    /// fields but descriptions.
+    pub collapse_whitespace: bool,
+
+    /// Whether to remove whitespace at the start and the end.
+    pub trim: bool,
+}
+
+impl Default for TextNormalizeOptions {
+    fn default() -> TextNormalizeOptions {
+        TextNormalizeOptions {
+            nfc: true,
+            strip_invisible: true,
+            collapse_whitespace: true,
+            trim: true,
+        }
+    }
+}
+
+impl Gpx {
+    /// Cleans up the text of every field meant to be read, as tuned by
+    /// `options`. A field that is left empty is removed. The report counts
+    /// the points with a changed field as modified; the document, its
+    /// metadata, routes and tracks, not being points, are left out.
+    ///
+    /// These are the names, comments, descriptions, sources, symbols and
+    /// types of the waypoints, routes, tracks and points, the texts of their
+    /// links, and the creator, name, description, keywords, author and
+    /// copyright holder of the document. Addresses, URLs and MIME types are
+    /// left as they are. Descriptions may span several lines, and keep their
+    /// tabs, line breaks and runs of whitespace.
+    ///
+    /// ```
+    /// use gpx::{Gpx, TextNormalizeOptions, Waypoint};
+    ///
+    /// let mut gpx = Gpx::default();
+    /// let mut waypoint = Waypoint::new_lat_lon(47.3, 8.5).unwrap();
+    /// waypoint.name = Some(" Lake\u{200B}side \t hut ".to_string());
+    /// waypoint.extras_mut().description = Some("Open in summer.\nCash only.".to_string());
+    /// gpx.waypoints.push(waypoint);
+    ///
+    /// assert_eq!(gpx.normalize_text(&TextNormalizeOptions::default()).modified, 1);
+    /// assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Lakeside hut"));
+    /// assert_eq!(
+    ///     gpx.waypoints[0].extras().description.as_deref(),
+    ///     Some("Open in summer.\nCash only.")
+    /// );
+    /// ```
+    pub fn normalize_text(&mut self, options: &TextNormalizeOptions) -> ChangeReport {
+        let mut report = ChangeReport::new("normalize_text", self.all_points().count());
+        let mut normalizer = Normalizer { options, fields: 0 };
+        normalizer.line(&mut self.creator);
+        if let Some(metadata) = &mut self.metadata {
+            normalizer.line(&mut metadata.name);
+            normalizer.paragraphs(&mut metadata.description);
+            normalizer.person(&mut metadata.author);
+            normalizer.links(&mut metadata.links);
+            normalizer.line(&mut metadata.keywords);
+            if let Some(copyright) = &mut metadata.copyright {
+                normalizer.line(&mut copyright.author);
+            }
+        }
+        let mut modified = normalizer.points(&mut self.waypoints);
+        for route in &mut self.routes {
+            modified += normalizer.route(route);
+        }
+        report.record(0, modified);
+        for (t, track) in self.tracks.iter_mut().enumerate() {
+            normalizer.track(track);
+            for (s, segment) in track.segments.iter_mut().enumerate() {
+                let len = segment.points.len();
+                let modified = normalizer.points(&mut segment.points);
+                report.record_segment(t, s, len, len, modified);
+            }
+        }
+        report
+    }
+}
+
+struct Normalizer<'a> {
+    options: &'a TextNormalizeOptions,
+    /// The number of fields changed so far.
+    fields: usize,
+}
+
+impl Normalizer<'_> {
+    /// Normalizes the points, and gives how many of them were changed.
+    fn points(&mut self, points: &mut [Waypoint]) -> usize {
+        let mut changed = 0;
+        for point in points {
+            let fields = self.fields;
+            self.waypoint(point);
+            changed += (self.fields > fields) as usize;
+        }
+        changed
+    }
+
+    fn waypoint(&mut self, waypoint: &mut Waypoint) {
+        self.line(&mut waypoint.name);
+        if let Some(extras) = waypoint.allocated_extras_mut() {
+            self.line(&mut extras.comment);
+            self.paragraphs(&mut extras.description);
+            self.line(&mut extras.source);
+            self.links(&mut extras.links);
+            self.line(&mut extras.symbol);
+            self.line(&mut extras._type);
+        }
+    }
+
+    /// Normalizes the route and its points, and gives how many of the
+    /// points were changed.
+    fn route(&mut self, route: &mut Route) -> usize {
+        self.line(&mut route.name);
+        self.line(&mut route.comment);
+        self.paragraphs(&mut route.description);
+        self.line(&mut route.source);
+        self.links(&mut route.links);
+        self.line(&mut route._type);
+        self.points(&mut route.points)
+    }
+
+    fn track(&mut self, track: &mut Track) {
+        self.line(&mut track.name);
+        self.line(&mut track.comment);
+        self.paragraphs(&mut track.description);
+        self.line(&mut track.source);
+        self.links(&mut track.links);
+        self.line(&mut track._type);
+    }
+
+    fn person(&mut self, person: &mut Option<Person>) {
+        if let Some(person) = person {
+            self.line(&mut person.name);
+            if let Some(link) = &mut person.link {
+                self.line(&mut link.text);
+            }
+        }
+    }
+
+    fn links(&mut self, links: &mut [Link]) {
+        for link in links {
+            self.line(&mut link.text);
+        }
+    }
+
+    /// Normalizes a field of a single line.
+    fn line(&mut self, field: &mut Option<String>) {
+        self.field(field, false);
+    }
+
+    /// Normalizes a field that may span several lines.
+    fn paragraphs(&mut self, field: &mut Option<String>) {
+        self.field(field, true);
+    }
+
+    fn field(&mut self, field: &mut Option<String>, multiline: bool) {
+        let text = match field {
+            Some(text) => text,
+            None => return,
+        };
+        let normalized = normalize(text, self.options, multiline);
+        if normalized != *text {
+            self.fields += 1;
+            *field = if normalized.is_empty() {
+                None
+            } else {
+                Some(normalized)
+            };
+        }
+    }
+}
+
+fn normalize(text: &str, options: &TextNormalizeOptions, multiline: bool) -> String {
+    let mut text = text.to_owned();
+    if options.strip_invisible {
+        text = text
+            .replace("\r\n", "\n")
+            .chars()
+            .filter_map(|c| match c {
+                '\t' | '\n' if multiline => Some(c),
+                '\r' if multiline => Some('\n'),
+                '\t' | '\n' | '\r' => Some(' '),
+                '\u{200B}' | '\u{2060}' | '\u{FEFF}' => None,
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect();
+    }
+    #[cfg(feature = "unicode")]
+    {
+        if options.nfc {
+            text = text.nfc().collect();
+        }
+    }
+    if options.collapse_whitespace && !multiline {
+        text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    if options.trim {
+        text = text.trim().to_owned();
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use super::TextNormalizeOptions;
+    use crate::{Gpx, Link, Metadata, Person, Track, TrackSegment, Waypoint};
+
+    #[test]
+    fn every_field_is_visited() {
+        let dirty = || Some("\u{FEFF}Rigi\u{200B}".to_string());
+        let link = Link {
+            href: "https://example.com/\u{200B}".to_string(),
+            text: dirty(),
+            ..Default::default()
+        };
+
+        let mut gpx = Gpx {
+            creator: dirty(),
+            ..Default::default()
+        };
+        gpx.metadata = Some(Metadata {
+            keywords: dirty(),
+            author: Some(Person {
+                name: dirty(),
+                link: Some(link.clone()),
+                ..Default::default()
+            }),
+            links: vec![link.clone()],
+            ..Default::default()
+        });
+        let mut point = Waypoint::new(Point::new(8.5, 47.0));
+        point.extras_mut().symbol = dirty();
+        let mut track = Track::new();
+        track.name = Some("Rigi".to_string());
+        track.links.push(link);
+        let mut segment = TrackSegment::new();
+        segment.points.push(point);
+        track.segments.push(segment);
+        gpx.tracks.push(track);
+
+        let report = gpx.normalize_text(&TextNormalizeOptions::default());
+        assert_eq!((report.modified, report.segments.len()), (1, 1));
+        assert_eq!(gpx.creator.as_deref(), Some("Rigi"));
+        let metadata = gpx.metadata.as_ref().unwrap();
+        assert_eq!(metadata.keywords.as_deref(), Some("Rigi"));
+        assert_eq!(metadata.links[0].text.as_deref(), Some("Rigi"));
+        let author = metadata.author.as_ref().unwrap();
+        assert_eq!(author.name.as_deref(), Some("Rigi"));
+        assert_eq!(author.link.as_ref().unwrap().text.as_deref(), Some("Rigi"));
+        assert_eq!(gpx.tracks[0].links[0].text.as_deref(), Some("Rigi"));
+        assert_eq!(
+            gpx.tracks[0].segments[0].points[0]
+                .extras()
+                .symbol
+                .as_deref(),
+            Some("Rigi")
+        );
+        // URLs are left alone.
+        assert_eq!(gpx.tracks[0].links[0].href, "https://example.com/\u{200B}");
+        let copy = gpx.clone();
+        assert!(gpx
+            .normalize_text(&TextNormalizeOptions::default())
+            .is_unchanged());
+        assert_eq!(gpx, copy);
+    }
+
+    #[test]
+    fn invisible_name_is_removed() {
+        let mut waypoint = Waypoint::new(Point::new(8.5, 47.0));
+        waypoint.name = Some(" \u{200B}\u{7} ".to_string());
+        waypoint.extras_mut().comment = Some("Kiosk\u{200D}".to_string());
+        let mut gpx = Gpx::default();
+        gpx.waypoints.push(waypoint);
+
+        assert_eq!(
+            gpx.normalize_text(&TextNormalizeOptions::default())
+                .modified,
+            1
+        );
+        assert_eq!(gpx.waypoints[0].name, None);
+        assert_eq!(
+            gpx.waypoints[0].extras().comment.as_deref(),
+            Some("Kiosk\u{200D}")
+        );
+    }
+
+    #[test]
+    fn description_keeps_lines() {
+        let mut waypoint = Waypoint::new(Point::new(8.5, 47.0));
+        waypoint.extras_mut().description =
+            Some("  Hut\u{200B}\n\n\tOpen  daily\u{1} \n".to_string());
+        waypoint.name = Some("Hut\nnorth".to_string());
+        let mut gpx = Gpx::default();
+        gpx.waypoints.push(waypoint);
+
+        gpx.normalize_text(&TextNormalizeOptions::default());
+        assert_eq!(
+            gpx.waypoints[0].extras().description.as_deref(),
+            Some("Hut\n\n\tOpen  daily")
+        );
+        assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Hut north"));
+
+        let untrimmed = TextNormalizeOptions {
+            collapse_whitespace: false,
+            trim: false,
+            ..Default::default()
+        };
+        let mut gpx = Gpx::default();
+        let mut waypoint = Waypoint::new(Point::new(8.5, 47.0));
+        waypoint.name = Some(" Hut \u{200B} north ".to_string());
+        gpx.waypoints.push(waypoint);
+        gpx.normalize_text(&untrimmed);
+        assert_eq!(gpx.waypoints[0].name.as_deref(), Some(" Hut  north "));
+
+        // Windows line breaks become plain ones, and spaces in names.
+        let mut waypoint = Waypoint::new(Point::new(8.5, 47.0));
+        waypoint.set_description(Some("Hut\r\nOpen daily\rCash only\r\n".into()));
+        waypoint.name = Some("Hut\r\nnorth".to_string());
+        gpx.waypoints = vec![waypoint];
+        let plain = TextNormalizeOptions {
+            nfc: false,
+            ..Default::default()
+        };
+        gpx.normalize_text(&plain);
+        assert_eq!(
+            gpx.waypoints[0].description(),
+            Some("Hut\nOpen daily\nCash only")
+        );
+        assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Hut north"));
+        gpx.waypoints[0].name = Some(" Hut\r\nnorth ".to_string());
+        gpx.normalize_text(&untrimmed);
+        assert_eq!(gpx.waypoints[0].name.as_deref(), Some(" Hut north "));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn decomposed_name_is_composed() {
+        let mut waypoint = Waypoint::new(Point::new(2.35, 48.85));
+        waypoint.name = Some("Caf\u{65}\u{200B}\u{301}".to_string());
+        let mut gpx = Gpx::default();
+        gpx.waypoints.push(waypoint);
+
+        assert_eq!(
+            gpx.normalize_text(&TextNormalizeOptions::default())
+                .modified,
+            1
+        );
+        let name = gpx.waypoints[0].name.as_ref().unwrap();
+        assert_eq!(name.as_bytes(), b"Caf\xC3\xA9");
+
+        let decomposed = TextNormalizeOptions {
+            nfc: false,
+            ..Default::default()
+        };
+        gpx.waypoints[0].name = Some("Caf\u{65}\u{301}".to_string());
+        assert!(gpx.normalize_text(&decomposed).is_unchanged());
+    }
+}
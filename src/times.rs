@@ -0,0 +1,380 @@
+//! times removes the timestamps of a document, or makes up timestamps for
+//! points that were drawn rather than recorded.
+
+use geo_types::Point;
+use time::{Duration, OffsetDateTime};
+
+use crate::distance::haversine;
+use crate::errors::{GpxError, GpxResult};
+use crate::{ChangeReport, Gpx, Track, TrackSegment, Waypoint};
+
+/// Options for [`TrackSegment::synthesize_times_with_options`] and
+/// [`Track::synthesize_times_with_options`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SynthesizeTimesOptions {
+    /// Whether to replace the times points already have. Otherwise they are
+    /// kept, and the clock continues from them.
+    pub overwrite: bool,
+
+    /// Time added between the last point of a segment and the first point
+    /// of the next one, on top of the time to travel between them.
+    pub segment_pause: Duration,
+}
+
+impl Default for SynthesizeTimesOptions {
+    fn default() -> SynthesizeTimesOptions {
+        SynthesizeTimesOptions {
+            overwrite: false,
+            segment_pause: Duration::ZERO,
+        }
+    }
+}
+
+impl Gpx {
+    /// Removes all timestamps, from the metadata, waypoints, route points
+    /// and track points. The report counts the points that had a time as
+    /// modified; the metadata, not being a point, is left out.
+    pub fn strip_times(&mut self) -> ChangeReport {
+        let mut report = ChangeReport::new("strip_times", self.all_points().count());
+        if let Some(metadata) = &mut self.metadata {
+            metadata.time = None;
+        }
+        let strip = |points: &mut [Waypoint]| {
+            let mut stripped = 0;
+            for point in points {
+                stripped += point.time.take().is_some() as usize;
+            }
+            stripped
+        };
+        let mut stripped = strip(&mut self.waypoints);
+        for route in &mut self.routes {
+            stripped += strip(&mut route.points);
+        }
+        report.record(0, stripped);
+        for (t, track) in self.tracks.iter_mut().enumerate() {
+            for (s, segment) in track.segments.iter_mut().enumerate() {
+                let len = segment.points.len();
+                report.record_segment(t, s, len, len, strip(&mut segment.points));
+            }
+        }
+        report
+    }
+}
+
+impl TrackSegment {
+    /// Gives the points times as if they were travelled at `speed_mps`
+    /// meters per second, starting at `start`, with the default
+    /// [`SynthesizeTimesOptions`], and reports the points given a time as
+    /// modified.
+    ///
+    /// Consecutive points are their haversine distance divided by the speed
+    /// apart. Points at the same position are one second apart, so the
+    /// times keep increasing. Times points already have are kept, and the
+    /// clock continues from them. A speed that is not positive and finite
+    /// is an error, and so are times beyond the range of dates, such as
+    /// from a speed so slow that the points would be centuries apart.
+    ///
+    /// ```
+    /// use gpx::{TrackSegment, Waypoint};
+    /// use time::{Duration, OffsetDateTime};
+    ///
+    /// let mut segment = TrackSegment::new();
+    /// for x in [0.0, 0.001, 0.002].iter() {
+    ///     segment.points.push(Waypoint::new_lat_lon(0.0, *x).unwrap());
+    /// }
+    ///
+    /// // Walking the 111 m between the points at 1 m/s.
+    /// let start = OffsetDateTime::UNIX_EPOCH;
+    /// assert_eq!(segment.synthesize_times(start, 1.0).unwrap().modified, 3);
+    /// let last = OffsetDateTime::from(segment.points[2].time.unwrap());
+    /// assert_eq!((last - start).whole_seconds(), 222);
+    /// ```
+    pub fn synthesize_times(
+        &mut self,
+        start: OffsetDateTime,
+        speed_mps: f64,
+    ) -> GpxResult<ChangeReport> {
+        self.synthesize_times_with_options(start, speed_mps, &SynthesizeTimesOptions::default())
+    }
+
+    /// Like [`TrackSegment::synthesize_times`], tuned by `options`.
+    pub fn synthesize_times_with_options(
+        &mut self,
+        start: OffsetDateTime,
+        speed_mps: f64,
+        options: &SynthesizeTimesOptions,
+    ) -> GpxResult<ChangeReport> {
+        let mut clock = Clock::new(start, speed_mps, options)?;
+        clock.advance(self)
+    }
+}
+
+impl Track {
+    /// Synthesizes the times of all segments like
+    /// [`TrackSegment::synthesize_times`], with the default
+    /// [`SynthesizeTimesOptions`].
+    ///
+    /// The clock continues across segments: the first point of a segment is
+    /// the time to travel from the last point of the previous one later,
+    /// plus [`SynthesizeTimesOptions::segment_pause`].
+    pub fn synthesize_times(
+        &mut self,
+        start: OffsetDateTime,
+        speed_mps: f64,
+    ) -> GpxResult<ChangeReport> {
+        self.synthesize_times_with_options(start, speed_mps, &SynthesizeTimesOptions::default())
+    }
+
+    /// Like [`Track::synthesize_times`], tuned by `options`.
+    pub fn synthesize_times_with_options(
+        &mut self,
+        start: OffsetDateTime,
+        speed_mps: f64,
+        options: &SynthesizeTimesOptions,
+    ) -> GpxResult<ChangeReport> {
+        let mut clock = Clock::new(start, speed_mps, options)?;
+        let mut report = ChangeReport::new("synthesize_times", self.point_count());
+        for (s, segment) in self.segments.iter_mut().enumerate() {
+            if clock.last.is_some() && !segment.points.is_empty() {
+                clock.time = clock
+                    .time
+                    .checked_add(options.segment_pause)
+                    .ok_or(GpxError::SynthesizedTimeOutOfRange)?;
+            }
+            report.record_part(0, s, &clock.advance(segment)?);
+        }
+        Ok(report)
+    }
+}
+
+/// The time and position of the last point given a time.
+struct Clock<'a> {
+    time: OffsetDateTime,
+    last: Option<Point<f64>>,
+    speed_mps: f64,
+    options: &'a SynthesizeTimesOptions,
+}
+
+impl<'a> Clock<'a> {
+    fn new(
+        start: OffsetDateTime,
+        speed_mps: f64,
+        options: &'a SynthesizeTimesOptions,
+    ) -> GpxResult<Clock<'a>> {
+        if !(speed_mps.is_finite() && speed_mps > 0.0) {
+            return Err(GpxError::NonPositiveSpeed(speed_mps));
+        }
+        Ok(Clock {
+            time: start,
+            last: None,
+            speed_mps,
+            options,
+        })
+    }
+
+    /// Gives the points of `segment` their times, and reports those that
+    /// were set as modified. Times beyond the range of dates are an error,
+    /// leaving the points before with their new times.
+    fn advance(&mut self, segment: &mut TrackSegment) -> GpxResult<ChangeReport> {
+        let mut set = 0;
+        for point in &mut segment.points {
+            let position = point.point();
+            if let Some(last) = self.last {
+                let seconds = haversine(last, position) / self.speed_mps;
+                // Spacings beyond what a duration holds are out of the
+                // range of dates anyway.
+                if seconds.is_nan() || seconds >= i64::MAX as f64 {
+                    return Err(GpxError::SynthesizedTimeOutOfRange);
+                }
+                let spacing = Duration::seconds_f64(seconds);
+                let spacing = if spacing > Duration::ZERO {
+                    spacing
+                } else {
+                    Duration::SECOND
+                };
+                self.time = self
+                    .time
+                    .checked_add(spacing)
+                    .ok_or(GpxError::SynthesizedTimeOutOfRange)?;
+            }
+            match point.time {
+                Some(time) if !self.options.overwrite => self.time = time.into(),
+                _ => {
+                    point.time = Some(self.time.into());
+                    set += 1;
+                }
+            }
+            self.last = Some(position);
+        }
+        let len = segment.points.len();
+        Ok(ChangeReport::of_segment("synthesize_times", len, len, set))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::SynthesizeTimesOptions;
+    use crate::errors::GpxError;
+    use crate::testutil::synthetic_gpx;
+    use crate::{Track, TrackSegment, Waypoint, EARTH_RADIUS_M};
+
+    /// A segment along the equator with points `step` degrees apart,
+    /// repeating the second point.
+    fn segment(step: f64) -> TrackSegment {
+        let mut segment = TrackSegment::new();
+        for i in [0, 1, 1, 2, 3, 4].iter() {
+            segment
+                .points
+                .push(Waypoint::new(Point::new(*i as f64 * step, 0.0)));
+        }
+        segment
+    }
+
+    fn seconds(segment: &TrackSegment) -> Vec<f64> {
+        segment
+            .points
+            .iter()
+            .map(|point| {
+                (OffsetDateTime::from(point.time.unwrap()) - OffsetDateTime::UNIX_EPOCH)
+                    .as_seconds_f64()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spacing_follows_speed() {
+        let mut segment = segment(0.01);
+        let start = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(segment.synthesize_times(start, 4.0).unwrap().modified, 6);
+
+        let leg = EARTH_RADIUS_M * 0.01f64.to_radians() / 4.0;
+        let seconds = seconds(&segment);
+        let expected = [
+            0.0,
+            leg,
+            leg + 1.0,
+            2.0 * leg + 1.0,
+            3.0 * leg + 1.0,
+            4.0 * leg + 1.0,
+        ];
+        for (actual, expected) in seconds.iter().zip(&expected) {
+            assert_approx_eq!(actual, expected, 1e-6);
+        }
+        assert!(seconds.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn existing_times_survive() {
+        let mut segment = segment(0.001);
+        let kept = OffsetDateTime::UNIX_EPOCH + Duration::hours(1);
+        segment.points[3].time = Some(kept.into());
+        assert_eq!(
+            segment
+                .synthesize_times(OffsetDateTime::UNIX_EPOCH, 2.0)
+                .unwrap()
+                .modified,
+            5
+        );
+        assert_eq!(segment.points[3].time, Some(kept.into()));
+        // The clock continues from the kept time.
+        let seconds = seconds(&segment);
+        assert_approx_eq!(seconds[4] - seconds[3], seconds[1] - seconds[0], 1e-6);
+
+        let options = SynthesizeTimesOptions {
+            overwrite: true,
+            ..Default::default()
+        };
+        let start = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(
+            segment
+                .synthesize_times_with_options(start, 2.0, &options)
+                .unwrap()
+                .modified,
+            6
+        );
+        assert!(OffsetDateTime::from(segment.points[3].time.unwrap()) < kept);
+    }
+
+    #[test]
+    fn track_clock_continues_across_segments() {
+        let mut track = Track::new();
+        track.segments.push(segment(0.001));
+        track.segments.push(TrackSegment::new());
+        track.segments.push(segment(0.001));
+        let options = SynthesizeTimesOptions {
+            segment_pause: Duration::minutes(5),
+            ..Default::default()
+        };
+        let start = OffsetDateTime::UNIX_EPOCH;
+        let report = track
+            .synthesize_times_with_options(start, 1.0, &options)
+            .unwrap();
+        assert_eq!((report.before, report.modified), (12, 12));
+        assert_eq!(report.segments.len(), 2);
+        assert_eq!(report.segments[1].segment, 2);
+
+        let first = seconds(&track.segments[0]);
+        let second = seconds(&track.segments[2]);
+        let leg = EARTH_RADIUS_M * 0.004f64.to_radians();
+        assert_approx_eq!(second[0] - first[5], leg + 300.0, 1e-6);
+        assert!(first
+            .iter()
+            .chain(&second)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn invalid_speed() {
+        let start = OffsetDateTime::UNIX_EPOCH;
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY].iter() {
+            assert!(segment(0.001).synthesize_times(start, *speed).is_err());
+        }
+    }
+
+    #[test]
+    fn times_out_of_range() {
+        let start = OffsetDateTime::UNIX_EPOCH;
+        // Legs longer than a duration holds, and legs of thousands of years
+        // that add up to more than the range of dates.
+        for speed in [1e-300, 1e-9].iter() {
+            assert!(matches!(
+                segment(0.001).synthesize_times(start, *speed),
+                Err(GpxError::SynthesizedTimeOutOfRange)
+            ));
+        }
+
+        let mut track = Track::new();
+        track.segments.push(segment(0.001));
+        track.segments.push(segment(0.001));
+        let options = SynthesizeTimesOptions {
+            segment_pause: Duration::MAX,
+            ..Default::default()
+        };
+        assert!(matches!(
+            track.synthesize_times_with_options(start, 1.0, &options),
+            Err(GpxError::SynthesizedTimeOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn strip_times() {
+        let mut gpx = synthetic_gpx(2, 10);
+        gpx.metadata.get_or_insert_with(Default::default).time =
+            Some(OffsetDateTime::UNIX_EPOCH.into());
+        let timed = gpx
+            .all_points()
+            .filter(|point| point.time.is_some())
+            .count();
+        assert!(timed > 0);
+
+        assert_eq!(gpx.strip_times().modified, timed);
+        assert!(gpx.all_points().all(|point| point.time.is_none()));
+        assert!(gpx.metadata.unwrap().time.is_none());
+    }
+}
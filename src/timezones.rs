@@ -0,0 +1,340 @@
+//! timezones gives the local times of points, with the time zones at their
+//! positions as found by a [`TimezoneFinder`], and their offsets as read
+//! from the tz database by a [`Tzdb`].
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use geo_types::Point;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::tzif::Zone;
+use crate::{Gpx, Track, Waypoint};
+
+/// The name of a time zone in the tz database, such as `Europe/Zurich`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeZoneId(pub String);
+
+impl TimeZoneId {
+    /// Gives the name of the zone.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TimeZoneId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for TimeZoneId {
+    fn from(name: &str) -> TimeZoneId {
+        TimeZoneId(name.to_owned())
+    }
+}
+
+/// Finds the time zone at a position, and the offset from UTC a zone has at
+/// some time.
+///
+/// Finding the zone is left to a crate such as `tzf-rs`, which any function
+/// from a point to a zone plugs in as. The offsets come from the tz database
+/// of the system by default, see [`Tzdb::system`].
+///
+/// ```
+/// use geo_types::Point;
+/// use gpx::{TimeZoneId, TimezoneFinder, Waypoint};
+/// use time::{OffsetDateTime, UtcOffset};
+///
+/// /// Puts every point in Zurich, ignoring summer time.
+/// struct Zurich;
+///
+/// impl TimezoneFinder for Zurich {
+///     fn tz_for(&self, _point: &Point<f64>) -> Option<TimeZoneId> {
+///         Some("Europe/Zurich".into())
+///     }
+///
+///     fn offset_at(&self, _zone: &TimeZoneId, _time: OffsetDateTime) -> Option<UtcOffset> {
+///         UtcOffset::from_hms(1, 0, 0).ok()
+///     }
+/// }
+///
+/// let mut waypoint = Waypoint::new_lat_lon(47.37, 8.54).unwrap();
+/// waypoint.time = Some(OffsetDateTime::from_unix_timestamp(1_609_488_000).unwrap().into());
+/// let local = waypoint.local_time(&Zurich).unwrap();
+/// assert_eq!((local.hour(), local.minute()), (9, 0));
+///
+/// // With tzf-rs, it would be `finder.get_tz_name(point.x(), point.y())`.
+/// let lookup = |_point: &Point<f64>| Some(TimeZoneId::from("Europe/Zurich"));
+/// assert_eq!(lookup.tz_for(&waypoint.point()).unwrap().name(), "Europe/Zurich");
+/// ```
+pub trait TimezoneFinder {
+    /// Gives the zone at `point`, or nothing if it is in none, such as out
+    /// at sea for some lookups.
+    fn tz_for(&self, point: &Point<f64>) -> Option<TimeZoneId>;
+
+    /// Gives the offset from UTC of `zone` at the instant `time`, with
+    /// summer time if it applies then, or nothing for unknown zones.
+    ///
+    /// By default, the zone is read from [`Tzdb::system`] on every call.
+    /// Finders that look up many times can keep a [`Tzdb`], which reads
+    /// each zone once.
+    fn offset_at(&self, zone: &TimeZoneId, time: OffsetDateTime) -> Option<UtcOffset> {
+        Tzdb::system().offset_at(zone, time)
+    }
+}
+
+impl<F> TimezoneFinder for F
+where
+    F: Fn(&Point<f64>) -> Option<TimeZoneId>,
+{
+    fn tz_for(&self, point: &Point<f64>) -> Option<TimeZoneId> {
+        self(point)
+    }
+}
+
+/// The tz database, as compiled into a directory of TZif files such as
+/// `/usr/share/zoneinfo`. Zones are read when they are first asked for.
+#[derive(Debug)]
+pub struct Tzdb {
+    dir: PathBuf,
+    zones: Mutex<HashMap<TimeZoneId, Option<Arc<Zone>>>>,
+}
+
+impl Tzdb {
+    /// Gives the tz database of the system, in the directory that the
+    /// `TZDIR` environment variable names, or else in
+    /// `/usr/share/zoneinfo`.
+    pub fn system() -> Tzdb {
+        let dir = env::var_os("TZDIR").unwrap_or_else(|| "/usr/share/zoneinfo".into());
+        Tzdb::from_dir(dir)
+    }
+
+    /// Gives the tz database in `dir`, with a file such as
+    /// `Europe/Zurich` for each zone.
+    pub fn from_dir(dir: impl Into<PathBuf>) -> Tzdb {
+        Tzdb {
+            dir: dir.into(),
+            zones: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Gives the offset from UTC of `zone` at the instant `time`, or
+    /// nothing if the zone has no valid file in the database.
+    pub fn offset_at(&self, zone: &TimeZoneId, time: OffsetDateTime) -> Option<UtcOffset> {
+        let zone = self.zone(zone)?;
+        UtcOffset::from_whole_seconds(zone.offset_at(time.unix_timestamp())).ok()
+    }
+
+    fn zone(&self, id: &TimeZoneId) -> Option<Arc<Zone>> {
+        let mut zones = self.zones.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(zone) = zones.get(id) {
+            return zone.clone();
+        }
+        // Names are paths below the directory, which must not lead out of
+        // it.
+        let name = id.name();
+        let valid = !name.is_empty()
+            && name.split('/').all(|part| {
+                !part.is_empty()
+                    && part != "."
+                    && part != ".."
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "_+-.".contains(c))
+            });
+        let zone = if valid {
+            fs::read(self.dir.join(name))
+                .ok()
+                .and_then(|data| Zone::parse(&data))
+                .map(Arc::new)
+        } else {
+            None
+        };
+        zones.insert(id.clone(), zone.clone());
+        zone
+    }
+}
+
+impl Waypoint {
+    /// Gives the time of the waypoint in the time zone at its position, or
+    /// nothing if it has no time, `finder` knows no zone for it, or the local
+    /// time falls outside of the range of dates.
+    pub fn local_time(&self, finder: &impl TimezoneFinder) -> Option<OffsetDateTime> {
+        let time = OffsetDateTime::from(self.time?);
+        let zone = finder.tz_for(&self.point())?;
+        time.checked_to_offset(finder.offset_at(&zone, time)?)
+    }
+}
+
+impl Track {
+    /// Gives the local time of the first point of the track with a time,
+    /// see [`Waypoint::local_time`].
+    pub fn local_start_time(&self, finder: &impl TimezoneFinder) -> Option<OffsetDateTime> {
+        self.segments
+            .iter()
+            .flat_map(|segment| &segment.points)
+            .find(|point| point.time.is_some())?
+            .local_time(finder)
+    }
+}
+
+impl Gpx {
+    /// Gives the time zone of the first point with a time, in the order of
+    /// waypoints, route points and track points, for showing the times of
+    /// the document in.
+    pub fn infer_timezone(&self, finder: &impl TimezoneFinder) -> Option<TimeZoneId> {
+        let point = self.all_points().find(|point| point.time.is_some())?;
+        finder.tz_for(&point.point())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+    use super::{TimeZoneId, TimezoneFinder, Tzdb};
+    use crate::{Gpx, Track, TrackSegment, Waypoint};
+
+    /// New York west of 0°, Zurich east of it, with the switches to summer
+    /// time of 2021.
+    struct Stub;
+
+    fn utc(month: Month, day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2021, month, day)
+            .unwrap()
+            .with_hms(hour, minute, 0)
+            .unwrap()
+            .assume_utc()
+    }
+
+    impl TimezoneFinder for Stub {
+        fn tz_for(&self, point: &Point<f64>) -> Option<TimeZoneId> {
+            match point.x() {
+                x if x.abs() > 90.0 => None,
+                x if x < 0.0 => Some("America/New_York".into()),
+                _ => Some("Europe/Zurich".into()),
+            }
+        }
+
+        fn offset_at(&self, zone: &TimeZoneId, time: OffsetDateTime) -> Option<UtcOffset> {
+            let (standard, summer_from) = match zone.name() {
+                "America/New_York" => (-5, utc(Month::March, 14, 7, 0)),
+                "Europe/Zurich" => (1, utc(Month::March, 28, 1, 0)),
+                _ => return None,
+            };
+            let hours = if time >= summer_from {
+                standard + 1
+            } else {
+                standard
+            };
+            UtcOffset::from_hms(hours, 0, 0).ok()
+        }
+    }
+
+    fn point(lon: f64, time: Option<OffsetDateTime>) -> Waypoint {
+        let mut point = Waypoint::new(Point::new(lon, 47.0));
+        point.time = time.map(Into::into);
+        point
+    }
+
+    /// The hour, minute and offset in hours of a local time.
+    fn clock(time: OffsetDateTime) -> (u8, u8, i8) {
+        (time.hour(), time.minute(), time.offset().whole_hours())
+    }
+
+    #[test]
+    fn local_time_across_summer_time() {
+        let before = point(8.5, Some(utc(Month::March, 28, 0, 59)));
+        let after = point(8.5, Some(utc(Month::March, 28, 1, 0)));
+        assert_eq!(clock(before.local_time(&Stub).unwrap()), (1, 59, 1));
+        assert_eq!(clock(after.local_time(&Stub).unwrap()), (3, 0, 2));
+
+        // New York switches two weeks earlier, at 2:00 local time.
+        let new_york = point(-74.0, Some(utc(Month::March, 7, 12, 2)));
+        assert_eq!(clock(new_york.local_time(&Stub).unwrap()), (7, 2, -5));
+        let new_york = point(-74.0, Some(utc(Month::March, 21, 11, 2)));
+        assert_eq!(clock(new_york.local_time(&Stub).unwrap()), (7, 2, -4));
+
+        assert_eq!(point(8.5, None).local_time(&Stub), None);
+        assert_eq!(
+            point(120.0, Some(utc(Month::May, 1, 0, 0))).local_time(&Stub),
+            None
+        );
+
+        // Zurich is already in the next year, past the last supported date.
+        let last = PrimitiveDateTime::MAX.assume_utc() - Duration::minutes(30);
+        assert_eq!(point(8.5, Some(last)).local_time(&Stub), None);
+        assert_eq!(
+            clock(point(-74.0, Some(last)).local_time(&Stub).unwrap()),
+            (19, 29, -4)
+        );
+    }
+
+    #[test]
+    fn start_time_and_zone_of_a_document() {
+        let mut segment = TrackSegment::new();
+        segment.points.push(point(-74.0, None));
+        segment
+            .points
+            .push(point(-74.0, Some(utc(Month::June, 1, 11, 2))));
+        let mut track = Track::new();
+        track.segments.push(TrackSegment::new());
+        track.segments.push(segment);
+        assert_eq!(clock(track.local_start_time(&Stub).unwrap()), (7, 2, -4));
+        assert_eq!(Track::new().local_start_time(&Stub), None);
+
+        let mut gpx = Gpx::default();
+        assert_eq!(gpx.infer_timezone(&Stub), None);
+        gpx.tracks.push(track);
+        gpx.waypoints.push(point(8.5, None));
+        assert_eq!(
+            gpx.infer_timezone(&Stub),
+            Some(TimeZoneId::from("America/New_York"))
+        );
+        gpx.waypoints
+            .push(point(8.5, Some(utc(Month::June, 1, 9, 0))));
+        assert_eq!(
+            gpx.infer_timezone(&Stub).unwrap().to_string(),
+            "Europe/Zurich"
+        );
+    }
+
+    #[test]
+    fn offsets_from_tzdb() {
+        let tzdb = Tzdb::from_dir("tests/fixtures/zoneinfo");
+        let zurich = TimeZoneId::from("Europe/Zurich");
+        let offset = |time| tzdb.offset_at(&zurich, time).unwrap().whole_seconds();
+        assert_eq!(offset(utc(Month::March, 28, 0, 59)), 3600);
+        assert_eq!(offset(utc(Month::March, 28, 1, 0)), 7200);
+
+        for missing in [
+            "Europe/Atlantis",
+            "../zoneinfo/Europe/Zurich",
+            "/etc/passwd",
+            "",
+        ]
+        .iter()
+        {
+            let missing = TimeZoneId::from(*missing);
+            assert_eq!(tzdb.offset_at(&missing, utc(Month::May, 1, 0, 0)), None);
+        }
+    }
+
+    #[test]
+    fn lookup_functions_are_finders() {
+        let lookup = |point: &Point<f64>| Stub.tz_for(point);
+        let mut gpx = Gpx::default();
+        gpx.waypoints
+            .push(point(-74.0, Some(utc(Month::June, 1, 9, 0))));
+        assert_eq!(
+            gpx.infer_timezone(&lookup),
+            Some(TimeZoneId::from("America/New_York"))
+        );
+    }
+}
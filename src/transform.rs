@@ -0,0 +1,346 @@
+//! transform changes the coordinates, elevations and speeds of all points of
+//! a document, such as when converting between datums or units.
+
+use geo_types::{coord, Point, Rect};
+
+use crate::errors::{GpxError, GpxResult};
+use crate::positions::ElementPath;
+use crate::{Gpx, Waypoint};
+
+/// Units that elevations may wrongly be given in, for
+/// [`Gpx::convert_elevation_from`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElevationUnit {
+    Meters,
+    Feet,
+}
+
+/// Units that speeds may wrongly be given in, for [`Gpx::convert_speed_from`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeedUnit {
+    /// Meters per second.
+    Mps,
+    /// Kilometers per hour.
+    Kmh,
+    /// Miles per hour.
+    Mph,
+    /// Nautical miles per hour.
+    Knots,
+}
+
+impl Gpx {
+    /// Replaces the position of every waypoint, route point and track point
+    /// by what `f` gives for it.
+    ///
+    /// If the metadata has bounds, they are recomputed to enclose the new
+    /// positions, or cleared if there are no points. Bounds are a plain
+    /// rectangle of latitudes and longitudes, so for points on both sides of
+    /// the antimeridian they span nearly all longitudes.
+    ///
+    /// ```
+    /// use gpx::{Gpx, Waypoint};
+    /// use geo_types::Point;
+    ///
+    /// let mut gpx = Gpx::default();
+    /// gpx.waypoints.push(Waypoint::new_lat_lon(47.0, 11.0).unwrap());
+    ///
+    /// gpx.map_coordinates(|point| Point::new(point.x() + 0.5, point.y()));
+    /// assert_eq!(gpx.waypoints[0].point(), Point::new(11.5, 47.0));
+    /// ```
+    pub fn map_coordinates(&mut self, mut f: impl FnMut(Point<f64>) -> Point<f64>) {
+        // The closure never fails.
+        let _ = self.try_map_coordinates(|point| Ok(f(point)));
+    }
+
+    /// Like [`Gpx::map_coordinates`], but stops at the first point `f` fails
+    /// for, with a [`GpxError::CoordinateMappingError`] naming that point.
+    ///
+    /// The points before the failing one keep their new positions, and the
+    /// bounds are recomputed all the same.
+    pub fn try_map_coordinates(
+        &mut self,
+        mut f: impl FnMut(Point<f64>) -> GpxResult<Point<f64>>,
+    ) -> GpxResult<()> {
+        let result = self.try_for_each_point(|path, waypoint| {
+            let point = f(waypoint.point())
+                .map_err(|error| GpxError::CoordinateMappingError(path, Box::new(error)))?;
+            waypoint.set_point(point);
+            Ok(())
+        });
+        let bounds = self.point_bounds();
+        if let Some(metadata) = &mut self.metadata {
+            if metadata.bounds.is_some() {
+                metadata.bounds = bounds;
+            }
+        }
+        result
+    }
+
+    /// Replaces the elevation of every waypoint, route point and track point
+    /// that has one by what `f` gives for it.
+    ///
+    /// ```
+    /// use gpx::{Gpx, Waypoint};
+    ///
+    /// let mut waypoint = Waypoint::new_lat_lon(47.0, 11.0).unwrap();
+    /// waypoint.elevation = Some(600.0);
+    /// let mut gpx = Gpx::default();
+    /// gpx.waypoints.push(waypoint);
+    ///
+    /// // Subtract the geoid height to go from ellipsoidal to orthometric heights.
+    /// gpx.map_elevation(|elevation| elevation - 49.0);
+    /// assert_eq!(gpx.waypoints[0].elevation, Some(551.0));
+    /// ```
+    pub fn map_elevation(&mut self, mut f: impl FnMut(f64) -> f64) {
+        let _ = self.try_for_each_point(|_, waypoint| {
+            waypoint.elevation = waypoint.elevation.map(&mut f);
+            Ok(())
+        });
+    }
+
+    /// Converts the elevations and geoid heights of every waypoint, route
+    /// point and track point from `unit` to meters, as GPX requires.
+    ///
+    /// Converting from [`ElevationUnit::Meters`] leaves the document as it
+    /// is. Converting twice converts twice, so call this only once for data
+    /// known to be in `unit`.
+    ///
+    /// ```
+    /// use gpx::{ElevationUnit, Gpx, Waypoint};
+    ///
+    /// let mut waypoint = Waypoint::new_lat_lon(40.01, -105.27).unwrap();
+    /// waypoint.elevation = Some(5430.0);
+    /// let mut gpx = Gpx::default();
+    /// gpx.waypoints.push(waypoint);
+    ///
+    /// gpx.convert_elevation_from(ElevationUnit::Feet);
+    /// assert!((gpx.waypoints[0].elevation.unwrap() - 1655.064).abs() < 1e-9);
+    /// ```
+    pub fn convert_elevation_from(&mut self, unit: ElevationUnit) {
+        let factor = match unit {
+            ElevationUnit::Meters => return,
+            ElevationUnit::Feet => 0.3048,
+        };
+        let _ = self.try_for_each_point(|_, waypoint| {
+            waypoint.elevation = waypoint.elevation.map(|elevation| elevation * factor);
+            if let Some(extras) = waypoint.allocated_extras_mut() {
+                extras.geoidheight = extras.geoidheight.map(|height| height * factor);
+            }
+            Ok(())
+        });
+    }
+
+    /// Converts the speeds of every waypoint, route point and track point
+    /// from `unit` to meters per second, as GPX requires.
+    ///
+    /// Like [`Gpx::convert_elevation_from`], converting from
+    /// [`SpeedUnit::Mps`] leaves the document as it is.
+    pub fn convert_speed_from(&mut self, unit: SpeedUnit) {
+        let factor = match unit {
+            SpeedUnit::Mps => return,
+            SpeedUnit::Kmh => 1000.0 / 3600.0,
+            SpeedUnit::Mph => 1609.344 / 3600.0,
+            SpeedUnit::Knots => 1852.0 / 3600.0,
+        };
+        let _ = self.try_for_each_point(|_, waypoint| {
+            if let Some(extras) = waypoint.allocated_extras_mut() {
+                extras.speed = extras.speed.map(|speed| speed * factor);
+            }
+            Ok(())
+        });
+    }
+
+    /// Calls `f` on every waypoint, route point and track point, with its
+    /// path, until it fails.
+    fn try_for_each_point(
+        &mut self,
+        mut f: impl FnMut(ElementPath, &mut Waypoint) -> GpxResult<()>,
+    ) -> GpxResult<()> {
+        for (w, waypoint) in self.waypoints.iter_mut().enumerate() {
+            f(ElementPath::Waypoint(w), waypoint)?;
+        }
+        for (r, route) in self.routes.iter_mut().enumerate() {
+            for (p, point) in route.points.iter_mut().enumerate() {
+                f(ElementPath::RoutePoint(r, p), point)?;
+            }
+        }
+        for (t, track) in self.tracks.iter_mut().enumerate() {
+            for (s, segment) in track.segments.iter_mut().enumerate() {
+                for (p, point) in segment.points.iter_mut().enumerate() {
+                    f(ElementPath::TrackPoint(t, s, p), point)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives the smallest rectangle containing all points, if there are any.
+    /// It never crosses the antimeridian, which `<bounds>` cannot express.
+    pub(crate) fn point_bounds(&self) -> Option<Rect<f64>> {
+        let route_points = self.routes.iter().flat_map(|route| &route.points);
+        let track_points = self
+            .tracks
+            .iter()
+            .flat_map(|track| &track.segments)
+            .flat_map(|segment| &segment.points);
+        let mut points = self
+            .waypoints
+            .iter()
+            .chain(route_points)
+            .chain(track_points)
+            .map(Waypoint::point);
+
+        let first = points.next()?;
+        let (mut min, mut max) = (first, first);
+        for point in points {
+            min = Point::new(min.x().min(point.x()), min.y().min(point.y()));
+            max = Point::new(max.x().max(point.x()), max.y().max(point.y()));
+        }
+        Some(Rect::new(
+            coord! { x: min.x(), y: min.y() },
+            coord! { x: max.x(), y: max.y() },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use assert_approx_eq::assert_approx_eq;
+    use geo_types::{coord, Point, Rect};
+
+    use super::{ElevationUnit, SpeedUnit};
+    use crate::errors::GpxError;
+    use crate::positions::ElementPath;
+    use crate::{read, Gpx, Metadata, Route, Track, TrackSegment, Waypoint};
+
+    fn gpx() -> Gpx {
+        let mut segment = TrackSegment::new();
+        segment.points.push(Waypoint::new(Point::new(11.0, 47.0)));
+        segment.points.push(Waypoint::new(Point::new(11.2, 47.1)));
+        let mut track = Track::new();
+        track.segments.push(segment);
+        let mut route = Route::new();
+        route.points.push(Waypoint::new(Point::new(10.9, 47.2)));
+        let mut waypoint = Waypoint::new(Point::new(11.1, 46.9));
+        waypoint.elevation = Some(500.0);
+
+        Gpx {
+            metadata: Some(Metadata {
+                bounds: Some(Rect::new(
+                    coord! { x: 10.9, y: 46.9 },
+                    coord! { x: 11.2, y: 47.2 },
+                )),
+                ..Default::default()
+            }),
+            waypoints: vec![waypoint],
+            routes: vec![route],
+            tracks: vec![track],
+            ..Default::default()
+        }
+    }
+
+    fn points(gpx: &Gpx) -> Vec<Point<f64>> {
+        let mut points = vec![gpx.waypoints[0].point(), gpx.routes[0].points[0].point()];
+        points.extend(gpx.tracks[0].segments[0].points.iter().map(Waypoint::point));
+        points
+    }
+
+    #[test]
+    fn map_coordinates_shifts_all_points() {
+        let mut shifted = gpx();
+        shifted.map_coordinates(|point| Point::new(point.x() + 0.001, point.y() + 0.001));
+
+        for (before, after) in points(&gpx()).iter().zip(points(&shifted)) {
+            assert_eq!(after, Point::new(before.x() + 0.001, before.y() + 0.001));
+        }
+        let bounds = shifted.metadata.unwrap().bounds.unwrap();
+        assert_eq!(bounds.min(), coord! { x: 10.9 + 0.001, y: 46.9 + 0.001 });
+        assert_eq!(bounds.max(), coord! { x: 11.2 + 0.001, y: 47.2 + 0.001 });
+    }
+
+    #[test]
+    fn map_coordinates_keeps_missing_bounds() {
+        let mut gpx = gpx();
+        gpx.metadata.as_mut().unwrap().bounds = None;
+        gpx.map_coordinates(|point| Point::new(point.x() + 0.001, point.y()));
+
+        assert_eq!(gpx.metadata.unwrap().bounds, None);
+    }
+
+    #[test]
+    fn try_map_coordinates_names_failing_point() {
+        let mut gpx = gpx();
+        let result = gpx.try_map_coordinates(|point| {
+            if point.x() > 11.1 {
+                Err(GpxError::OutOfBounds("longitude"))
+            } else {
+                Ok(Point::new(point.x() - 1.0, point.y()))
+            }
+        });
+
+        match result {
+            Err(GpxError::CoordinateMappingError(path, _)) => {
+                assert_eq!(path, ElementPath::TrackPoint(0, 0, 1));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        // The points before have been mapped, and the bounds follow them.
+        assert_eq!(gpx.tracks[0].segments[0].points[0].point().x(), 10.0);
+        let bounds = gpx.metadata.unwrap().bounds.unwrap();
+        assert_eq!(bounds.min().x, 9.9);
+        assert_eq!(bounds.max().x, 11.2);
+    }
+
+    #[test]
+    fn convert_units_of_fixture() {
+        let file = File::open("tests/fixtures/elevation_in_feet.gpx").unwrap();
+        let mut gpx = read(BufReader::new(file)).unwrap();
+        let original = gpx.clone();
+
+        gpx.convert_elevation_from(ElevationUnit::Meters);
+        gpx.convert_speed_from(SpeedUnit::Mps);
+        assert_eq!(gpx, original);
+
+        gpx.convert_elevation_from(ElevationUnit::Feet);
+        assert_approx_eq!(gpx.waypoints[0].elevation.unwrap(), 4418.6856, 1e-9);
+        assert_approx_eq!(gpx.waypoints[0].extras().geoidheight.unwrap(), -15.24, 1e-9);
+        assert_approx_eq!(gpx.routes[0].points[0].elevation.unwrap(), 1609.344, 1e-9);
+        let points = &gpx.tracks[0].segments[0].points;
+        assert_approx_eq!(points[0].elevation.unwrap(), 30.48, 1e-9);
+        assert_approx_eq!(points[1].elevation.unwrap(), 33.528, 1e-9);
+        assert_eq!(points[2].elevation, None);
+
+        gpx.convert_speed_from(SpeedUnit::Knots);
+        let points = &gpx.tracks[0].segments[0].points;
+        assert_approx_eq!(points[0].extras().speed.unwrap(), 5.144_444, 1e-6);
+        assert_approx_eq!(points[1].extras().speed.unwrap(), 6.173_333, 1e-6);
+        assert_eq!(gpx.waypoints[0].extras().speed, None);
+    }
+
+    #[test]
+    fn convert_speed_units() {
+        let speed = |unit: SpeedUnit| {
+            let mut gpx = gpx();
+            gpx.waypoints[0].extras_mut().speed = Some(36.0);
+            gpx.convert_speed_from(unit);
+            gpx.waypoints[0].extras().speed.unwrap()
+        };
+
+        assert_eq!(speed(SpeedUnit::Mps), 36.0);
+        assert_approx_eq!(speed(SpeedUnit::Kmh), 10.0, 1e-12);
+        assert_approx_eq!(speed(SpeedUnit::Mph), 16.09344, 1e-12);
+        assert_approx_eq!(speed(SpeedUnit::Knots), 18.52, 1e-12);
+    }
+
+    #[test]
+    fn map_elevation_skips_missing_elevations() {
+        let mut gpx = gpx();
+        gpx.map_elevation(|elevation| elevation + 1.5);
+
+        assert_eq!(gpx.waypoints[0].elevation, Some(501.5));
+        assert_eq!(gpx.tracks[0].segments[0].points[0].elevation, None);
+    }
+}
@@ -1,14 +1,23 @@
 //! generic types for GPX
 
-pub use crate::parser::time::Time;
-use geo_types::{Geometry, LineString, MultiLineString, Point, Rect};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use crate::dom::{Element, PrologNode, UnknownAttributes};
+use crate::errors::{GpxError, GpxResult};
+pub use crate::parser::time::{FractionDigits, OffsetStyle, Time, TimeFormat};
+use crate::point3d::GpxPoint;
+use crate::point_ref::Generation;
+use geo_types::{Coord, Geometry, LineString, MultiLineString, Point, Rect};
 #[cfg(feature = "use-serde")]
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 /// Allowable GPX versions. Currently, only GPX 1.0 and GPX 1.1 are accepted.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
 pub enum GpxVersion {
+    #[default]
     Unknown,
     Gpx10,
     Gpx11,
@@ -20,9 +29,60 @@ impl std::fmt::Display for GpxVersion {
     }
 }
 
-impl Default for GpxVersion {
-    fn default() -> GpxVersion {
-        GpxVersion::Unknown
+impl PartialOrd for GpxVersion {
+    /// Orders the known versions by their number. `Unknown` could be any
+    /// version, so it is only equal to itself and not comparable to the
+    /// others, which is why there is no `Ord`. To sort versions, give
+    /// `Unknown` a place with [`slice::sort_by_key`].
+    ///
+    /// ```
+    /// use gpx::GpxVersion;
+    ///
+    /// assert!(GpxVersion::Gpx10 < GpxVersion::Gpx11);
+    /// assert!(GpxVersion::Gpx11 >= GpxVersion::Gpx10);
+    /// assert_eq!(GpxVersion::Unknown.partial_cmp(&GpxVersion::Gpx11), None);
+    /// assert!(!(GpxVersion::Unknown < GpxVersion::Gpx11));
+    /// ```
+    fn partial_cmp(&self, other: &GpxVersion) -> Option<Ordering> {
+        let number = |version: &GpxVersion| match version {
+            GpxVersion::Unknown => None,
+            GpxVersion::Gpx10 => Some(10),
+            GpxVersion::Gpx11 => Some(11),
+        };
+        match (number(self), number(other)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            (None, None) => Some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl GpxVersion {
+    /// Gives the XML namespace of the version, or `None` for `Unknown`.
+    pub fn namespace_uri(&self) -> Option<&'static str> {
+        match self {
+            GpxVersion::Unknown => None,
+            GpxVersion::Gpx10 => Some("http://www.topografix.com/GPX/1/0"),
+            GpxVersion::Gpx11 => Some("http://www.topografix.com/GPX/1/1"),
+        }
+    }
+
+    /// Whether the version has the `<metadata>` element. GPX 1.0 has the
+    /// name, author and the like as children of `<gpx>` instead.
+    pub fn supports_metadata_element(&self) -> bool {
+        *self >= GpxVersion::Gpx11
+    }
+
+    /// Whether the version has the `<link>` element. GPX 1.0 has a single
+    /// `<url>` and `<urlname>` instead.
+    pub fn supports_link_element(&self) -> bool {
+        *self >= GpxVersion::Gpx11
+    }
+
+    /// Whether the version has the `<extensions>` element. GPX 1.0 allows
+    /// elements of other namespaces anywhere instead.
+    pub fn supports_extensions_element(&self) -> bool {
+        *self >= GpxVersion::Gpx11
     }
 }
 
@@ -47,13 +107,98 @@ pub struct Gpx {
 
     /// A list of routes with a list of point-by-point directions
     pub routes: Vec<Route>,
+
+    /// The `<extensions>` element of the document, with its content kept as found.
+    pub extensions: Option<Element>,
+
+    /// Children of `<gpx>` that are not part of the GPX schema, such as
+    /// elements of a later GPX revision or of a vendor, in document order.
+    /// They are written after all other children.
+    pub unknown_elements: Vec<Element>,
+
+    /// Attributes not defined by the GPX schema, see
+    /// [`ReadOptions::preserve_unknown_attributes`](crate::ReadOptions::preserve_unknown_attributes).
+    pub unknown_attributes: Vec<UnknownAttributes>,
+
+    /// Comments and processing instructions before the `<gpx>` element, in
+    /// document order. The XML declaration is not part of them.
+    pub prolog: Vec<PrologNode>,
+
+    /// Changes with the structure of the document, see [`Gpx::generation`].
+    /// Leave it to `..Default::default()` in struct expressions.
+    #[doc(hidden)]
+    #[cfg_attr(feature = "use-serde", serde(skip))]
+    pub generation: Generation,
+}
+
+impl Gpx {
+    /// Gives the earliest timestamp found anywhere in the document.
+    ///
+    /// This looks at the times of all waypoints, route points and track
+    /// points, but not at `metadata.time` (the creation time of the file).
+    ///
+    /// ```
+    /// use gpx::{Gpx, Waypoint};
+    /// use time::OffsetDateTime;
+    ///
+    /// let mut gpx = Gpx::default();
+    /// assert_eq!(gpx.earliest_time(), None);
+    ///
+    /// let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// wpt.time = Some(OffsetDateTime::UNIX_EPOCH.into());
+    /// gpx.waypoints.push(wpt);
+    /// assert_eq!(gpx.earliest_time(), Some(OffsetDateTime::UNIX_EPOCH.into()));
+    /// ```
+    pub fn earliest_time(&self) -> Option<Time> {
+        self.all_points().filter_map(|wpt| wpt.time).min()
+    }
+
+    /// Gives the latest timestamp found anywhere in the document.
+    ///
+    /// Like [`Gpx::earliest_time`], `metadata.time` is not taken into account.
+    pub fn latest_time(&self) -> Option<Time> {
+        self.all_points().filter_map(|wpt| wpt.time).max()
+    }
+
+    /// Fills in `metadata.time` if it is missing.
+    ///
+    /// Many files omit the creation time of the document. If that is the
+    /// case, this sets it to the earliest timestamp found in the document
+    /// (see [`Gpx::earliest_time`]), creating the [`Metadata`] if needed.
+    /// An existing `metadata.time` is never overwritten.
+    ///
+    /// Returns whether a change was made.
+    pub fn ensure_metadata_time(&mut self) -> bool {
+        if matches!(self.metadata, Some(Metadata { time: Some(_), .. })) {
+            return false;
+        }
+        let time = match self.earliest_time() {
+            Some(time) => time,
+            None => return false,
+        };
+        self.metadata.get_or_insert_with(Default::default).time = Some(time);
+        true
+    }
+
+    /// Iterates over all waypoints, route points and track points.
+    pub(crate) fn all_points(&self) -> impl Iterator<Item = &Waypoint> {
+        self.waypoints
+            .iter()
+            .chain(self.routes.iter().flat_map(|rte| rte.points.iter()))
+            .chain(
+                self.tracks
+                    .iter()
+                    .flat_map(|trk| trk.segments.iter())
+                    .flat_map(|seg| seg.points.iter()),
+            )
+    }
 }
 
 /// Information about the copyright holder and any license governing use of this file.
 ///
 /// By linking to an appropriate license, you may place your data into the
 /// public domain or grant additional usage rights.
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
 pub struct GpxCopyright {
     pub author: Option<String>,
@@ -91,8 +236,17 @@ pub struct Metadata {
     pub copyright: Option<GpxCopyright>,
 
     /// Bounds for the tracks in the GPX.
+    ///
+    /// The minimum longitude is never larger than the maximum, so bounds of
+    /// points across the antimeridian span nearly all longitudes.
     pub bounds: Option<Rect<f64>>,
-    /*extensions: GpxExtensionsType,*/
+
+    /// The `<extensions>` element of the metadata, with its content kept as found.
+    pub extensions: Option<Element>,
+
+    /// Attributes not defined by the GPX schema, see
+    /// [`ReadOptions::preserve_unknown_attributes`](crate::ReadOptions::preserve_unknown_attributes).
+    pub unknown_attributes: Vec<UnknownAttributes>,
 }
 
 /// Route represents an ordered list of waypoints representing a series of turn points leading to a destination.
@@ -121,16 +275,28 @@ pub struct Route {
     /// Type (classification) of route.
     pub _type: Option<String>,
 
+    /// The `<extensions>` element of the route, with its content kept as found.
+    pub extensions: Option<Element>,
+
     /// Each Waypoint holds the coordinates, elevation, timestamp, and metadata
     /// for a single point in a track.
     pub points: Vec<Waypoint>,
+
+    /// Attributes not defined by the GPX schema, see
+    /// [`ReadOptions::preserve_unknown_attributes`](crate::ReadOptions::preserve_unknown_attributes).
+    pub unknown_attributes: Vec<UnknownAttributes>,
 }
 
 impl Route {
     /// Gives the linestring of the segment's points, the sequence of points that
     /// comprises the track segment.
+    ///
+    /// The elevations and times are dropped, see [`GpxPoint::to_point_lossy`].
     pub fn linestring(&self) -> LineString<f64> {
-        self.points.iter().map(|wpt| wpt.point()).collect()
+        self.points
+            .iter()
+            .map(|wpt| GpxPoint::from(wpt).to_point_lossy())
+            .collect()
     }
 
     /// Creates a new Route with default values.
@@ -140,12 +306,11 @@ impl Route {
     /// extern crate geo_types;
     ///
     /// use gpx::{Route, Waypoint};
-    /// use geo_types::Point;
     ///
     /// fn main() {
     ///     let mut route: Route = Route::new();
     ///
-    ///     let point = Waypoint::new(Point::new(-121.97, 37.24));
+    ///     let point = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
     ///     route.points.push(point);
     /// }
     ///
@@ -186,12 +351,18 @@ pub struct Track {
     /// GPS number of track
     pub number: Option<u32>,
 
+    /// The `<extensions>` element of the track, with its content kept as found.
+    pub extensions: Option<Element>,
+
     /// A Track Segment holds a list of Track Points which are logically
     /// connected in order. To represent a single GPS track where GPS reception
     /// was lost, or the GPS receiver was turned off, start a new Track Segment
     /// for each continuous span of track data.
     pub segments: Vec<TrackSegment>,
-    /* extensions */
+
+    /// Attributes not defined by the GPX schema, see
+    /// [`ReadOptions::preserve_unknown_attributes`](crate::ReadOptions::preserve_unknown_attributes).
+    pub unknown_attributes: Vec<UnknownAttributes>,
     /* trkSeg */
 }
 
@@ -234,14 +405,21 @@ pub struct TrackSegment {
     /// Each Waypoint holds the coordinates, elevation, timestamp, and metadata
     /// for a single point in a track.
     pub points: Vec<Waypoint>,
-    /* extensions */
+
+    /// The `<extensions>` element of the segment, with its content kept as found.
+    pub extensions: Option<Element>,
 }
 
 impl TrackSegment {
     /// Gives the linestring of the segment's points, the sequence of points that
     /// comprises the track segment.
+    ///
+    /// The elevations and times are dropped, see [`GpxPoint::to_point_lossy`].
     pub fn linestring(&self) -> LineString<f64> {
-        self.points.iter().map(|wpt| wpt.point()).collect()
+        self.points
+            .iter()
+            .map(|wpt| GpxPoint::from(wpt).to_point_lossy())
+            .collect()
     }
 
     /// Creates a new TrackSegment with default values.
@@ -251,12 +429,11 @@ impl TrackSegment {
     /// extern crate geo_types;
     ///
     /// use gpx::{TrackSegment, Waypoint};
-    /// use geo_types::Point;
     ///
     /// fn main() {
     ///     let mut trkseg: TrackSegment = TrackSegment::new();
     ///
-    ///     let point = Waypoint::new(Point::new(-121.97, 37.24));
+    ///     let point = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
     ///     trkseg.points.push(point);
     /// }
     pub fn new() -> TrackSegment {
@@ -271,32 +448,41 @@ impl From<TrackSegment> for Geometry<f64> {
 }
 
 // A Version of geo_types::Point that has the Default trait implemented, which
-// allows us to initialise the GpxPoint with default values compactly
+// allows us to initialise the DefaultPoint with default values compactly
 // in the Waypoint::new function below
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
-struct GpxPoint(Point<f64>);
+#[cfg_attr(feature = "use-serde", serde(rename = "GpxPoint"))]
+pub(crate) struct DefaultPoint(Point<f64>);
 
-impl Default for GpxPoint {
-    fn default() -> GpxPoint {
-        GpxPoint(Point::new(0 as f64, 0 as f64))
+impl Default for DefaultPoint {
+    fn default() -> DefaultPoint {
+        DefaultPoint(Point::new(0 as f64, 0 as f64))
     }
 }
 
 /// Waypoint represents a waypoint, point of interest, or named feature on a
 /// map.
-#[derive(Clone, Default, Debug, PartialEq)]
-#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+///
+/// The fields that most points leave out, such as `hdop` or `links`, are
+/// kept in [`WaypointExtras`], which is only allocated once one of them is
+/// set. They are read and set one at a time with methods named like them,
+/// such as [`Waypoint::hdop`] and [`Waypoint::set_hdop`], or all together
+/// with [`Waypoint::extras`] and [`Waypoint::extras_mut`]. That keeps a
+/// point with just a position, elevation and time small.
+///
+/// With the `use-serde` feature, the extras are serialized among the other
+/// fields, as they were before they moved.
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "use-serde", derive(Deserialize))]
+#[cfg_attr(feature = "use-serde", serde(from = "SerdeWaypoint"))]
 pub struct Waypoint {
     /// The geographical point.
-    point: GpxPoint,
+    pub(crate) point: DefaultPoint,
 
     /// Elevation (in meters) of the point.
     pub elevation: Option<f64>,
 
-    /// Speed (in meters per second) (only in GPX 1.0)
-    pub speed: Option<f64>,
-
     /// Creation/modification timestamp for element. Date and time in are in
     /// Univeral Coordinated Time (UTC), not local time! Conforms to ISO 8601
     /// specification for date/time representation. Fractional seconds are
@@ -309,6 +495,18 @@ pub struct Waypoint {
     /// application to validate the field before sending it to the GPS.
     pub name: Option<String>,
 
+    /// The other fields, if any of them is set.
+    pub(crate) extras: Option<Box<WaypointExtras>>,
+}
+
+/// The fields of a [`Waypoint`] that most points don't have.
+#[derive(Clone, Default, Debug, PartialEq)]
+#[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "use-serde", serde(default))]
+pub struct WaypointExtras {
+    /// Speed (in meters per second) (only in GPX 1.0)
+    pub speed: Option<f64>,
+
     /// GPS waypoint comment. Sent to GPS as comment.
     pub comment: Option<String>,
 
@@ -361,32 +559,308 @@ pub struct Waypoint {
 
     /// ID of DGPS station used in differential correction, in the range [0, 1023].
     pub dgpsid: Option<u16>,
-    // <extensions> extensionsType </extensions> [0..1] ?
+
+    /// The `<extensions>` element of the waypoint, with its content kept as found.
+    pub extensions: Option<Element>,
+
+    /// Attributes not defined by the GPX schema, see
+    /// [`ReadOptions::preserve_unknown_attributes`](crate::ReadOptions::preserve_unknown_attributes).
+    pub unknown_attributes: Vec<UnknownAttributes>,
+}
+
+/// The extras of waypoints that have none.
+#[allow(deprecated)]
+static NO_EXTRAS: WaypointExtras = WaypointExtras {
+    speed: None,
+    comment: None,
+    description: None,
+    source: None,
+    links: Vec::new(),
+    symbol: None,
+    _type: None,
+    geoidheight: None,
+    fix: None,
+    sat: None,
+    hdop: None,
+    vdop: None,
+    pdop: None,
+    age: None,
+    dgps_age: None,
+    dgpsid: None,
+    extensions: None,
+    unknown_attributes: Vec::new(),
+};
+
+impl PartialEq for Waypoint {
+    /// Compares the fields, whether or not the extras were allocated.
+    fn eq(&self, other: &Waypoint) -> bool {
+        // Taken apart so that a new field can't be forgotten here.
+        let Waypoint {
+            point,
+            elevation,
+            time,
+            name,
+            extras: _,
+        } = self;
+        *point == other.point
+            && *elevation == other.elevation
+            && *time == other.time
+            && *name == other.name
+            && self.extras() == other.extras()
+    }
+}
+
+/// The fields of a [`Waypoint`] as they are deserialized, with the extras
+/// among the others.
+#[cfg(feature = "use-serde")]
+#[derive(Deserialize)]
+#[serde(rename = "Waypoint")]
+#[allow(deprecated)]
+struct SerdeWaypoint {
+    point: DefaultPoint,
+    elevation: Option<f64>,
+    speed: Option<f64>,
+    time: Option<Time>,
+    name: Option<String>,
+    comment: Option<String>,
+    description: Option<String>,
+    source: Option<String>,
+    links: Vec<Link>,
+    symbol: Option<String>,
+    _type: Option<String>,
+    geoidheight: Option<f64>,
+    fix: Option<Fix>,
+    sat: Option<u64>,
+    hdop: Option<f64>,
+    vdop: Option<f64>,
+    pdop: Option<f64>,
+    age: Option<f64>,
+    dgps_age: Option<f64>,
+    dgpsid: Option<u16>,
+    extensions: Option<Element>,
+    unknown_attributes: Vec<UnknownAttributes>,
+}
+
+#[cfg(feature = "use-serde")]
+impl From<SerdeWaypoint> for Waypoint {
+    #[allow(deprecated)]
+    fn from(flat: SerdeWaypoint) -> Waypoint {
+        let mut waypoint = Waypoint {
+            point: flat.point,
+            elevation: flat.elevation,
+            time: flat.time,
+            name: flat.name,
+            extras: Some(Box::new(WaypointExtras {
+                speed: flat.speed,
+                comment: flat.comment,
+                description: flat.description,
+                source: flat.source,
+                links: flat.links,
+                symbol: flat.symbol,
+                _type: flat._type,
+                geoidheight: flat.geoidheight,
+                fix: flat.fix,
+                sat: flat.sat,
+                hdop: flat.hdop,
+                vdop: flat.vdop,
+                pdop: flat.pdop,
+                age: flat.age,
+                dgps_age: flat.dgps_age,
+                dgpsid: flat.dgpsid,
+                extensions: flat.extensions,
+                unknown_attributes: flat.unknown_attributes,
+            })),
+        };
+        waypoint.shrink_extras();
+        waypoint
+    }
+}
+
+/// Serializes the fields in the order of [`SerdeWaypoint`], without copying
+/// them into one.
+#[cfg(feature = "use-serde")]
+impl Serialize for Waypoint {
+    #[allow(deprecated)]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let extras = self.extras();
+        let mut state = serializer.serialize_struct("Waypoint", 22)?;
+        state.serialize_field("point", &self.point)?;
+        state.serialize_field("elevation", &self.elevation)?;
+        state.serialize_field("speed", &extras.speed)?;
+        state.serialize_field("time", &self.time)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("comment", &extras.comment)?;
+        state.serialize_field("description", &extras.description)?;
+        state.serialize_field("source", &extras.source)?;
+        state.serialize_field("links", &extras.links)?;
+        state.serialize_field("symbol", &extras.symbol)?;
+        state.serialize_field("_type", &extras._type)?;
+        state.serialize_field("geoidheight", &extras.geoidheight)?;
+        state.serialize_field("fix", &extras.fix)?;
+        state.serialize_field("sat", &extras.sat)?;
+        state.serialize_field("hdop", &extras.hdop)?;
+        state.serialize_field("vdop", &extras.vdop)?;
+        state.serialize_field("pdop", &extras.pdop)?;
+        state.serialize_field("age", &extras.age)?;
+        state.serialize_field("dgps_age", &extras.dgps_age)?;
+        state.serialize_field("dgpsid", &extras.dgpsid)?;
+        state.serialize_field("extensions", &extras.extensions)?;
+        state.serialize_field("unknown_attributes", &extras.unknown_attributes)?;
+        state.end()
+    }
+}
+
+/// Defines a getter and a setter for each of the [`WaypointExtras`], where
+/// setting nothing doesn't allocate them. Strings are given as `&str` and
+/// lists as slices.
+macro_rules! extras_accessors {
+    ($($field:ident, $setter:ident: $kind:tt $ty:ty;)*) => {
+        impl Waypoint {
+            $(
+                extras_accessors!(@get $kind $field $ty);
+
+                #[doc = concat!(
+                    "Sets the [`", stringify!($field), "`](WaypointExtras::",
+                    stringify!($field), ") of the waypoint."
+                )]
+                pub fn $setter(&mut self, $field: extras_accessors!(@owned $kind $ty)) {
+                    if extras_accessors!(@is_set $kind $field) || self.extras.is_some() {
+                        self.extras_mut().$field = $field;
+                    }
+                }
+            )*
+        }
+    };
+    (@get copy $field:ident $ty:ty) => {
+        #[doc = concat!(
+            "Gives the [`", stringify!($field), "`](WaypointExtras::",
+            stringify!($field), ") of the waypoint."
+        )]
+        pub fn $field(&self) -> Option<$ty> {
+            self.extras().$field
+        }
+    };
+    (@get str $field:ident $ty:ty) => {
+        #[doc = concat!(
+            "Gives the [`", stringify!($field), "`](WaypointExtras::",
+            stringify!($field), ") of the waypoint."
+        )]
+        pub fn $field(&self) -> Option<&str> {
+            self.extras().$field.as_deref()
+        }
+    };
+    (@get ref $field:ident $ty:ty) => {
+        #[doc = concat!(
+            "Gives the [`", stringify!($field), "`](WaypointExtras::",
+            stringify!($field), ") of the waypoint."
+        )]
+        pub fn $field(&self) -> Option<&$ty> {
+            self.extras().$field.as_ref()
+        }
+    };
+    (@get list $field:ident $ty:ty) => {
+        #[doc = concat!(
+            "Gives the [`", stringify!($field), "`](WaypointExtras::",
+            stringify!($field), ") of the waypoint."
+        )]
+        pub fn $field(&self) -> &[$ty] {
+            &self.extras().$field
+        }
+    };
+    (@owned list $ty:ty) => { Vec<$ty> };
+    (@owned $kind:tt $ty:ty) => { Option<$ty> };
+    (@is_set list $field:ident) => { !$field.is_empty() };
+    (@is_set $kind:tt $field:ident) => { $field.is_some() };
+}
+
+extras_accessors! {
+    speed, set_speed: copy f64;
+    comment, set_comment: str String;
+    description, set_description: str String;
+    source, set_source: str String;
+    links, set_links: list Link;
+    symbol, set_symbol: str String;
+    _type, set_type: str String;
+    geoidheight, set_geoidheight: copy f64;
+    fix, set_fix: ref Fix;
+    sat, set_sat: copy u64;
+    hdop, set_hdop: copy f64;
+    vdop, set_vdop: copy f64;
+    pdop, set_pdop: copy f64;
+    dgps_age, set_dgps_age: copy f64;
+    dgpsid, set_dgpsid: copy u16;
+    extensions, set_extensions: ref Element;
+    unknown_attributes, set_unknown_attributes: list UnknownAttributes;
 }
 
 impl Waypoint {
-    /// Gives the geographical point of the waypoint.
+    /// Gives the fields that most points don't have. They are all empty if
+    /// none of them was set.
     ///
     /// ```
-    /// extern crate geo_types;
-    /// extern crate gpx;
+    /// use gpx::Waypoint;
+    ///
+    /// let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// assert_eq!(wpt.extras().hdop, None);
+    ///
+    /// wpt.extras_mut().hdop = Some(1.2);
+    /// assert_eq!(wpt.extras().hdop, Some(1.2));
+    /// ```
+    pub fn extras(&self) -> &WaypointExtras {
+        self.extras.as_deref().unwrap_or(&NO_EXTRAS)
+    }
+
+    /// Gives the fields that most points don't have, to change them. This
+    /// allocates them if the waypoint has none yet, see
+    /// [`Waypoint::shrink_extras`].
+    pub fn extras_mut(&mut self) -> &mut WaypointExtras {
+        self.extras.get_or_insert_with(Default::default)
+    }
+
+    /// Gives the extras to change them, if they were allocated, so that
+    /// changes to fields that are usually empty don't allocate them.
+    pub(crate) fn allocated_extras_mut(&mut self) -> Option<&mut WaypointExtras> {
+        self.extras.as_deref_mut()
+    }
+
+    /// Frees the extras if all of them are empty, as after clearing them,
+    /// and returns whether they were freed or there were none.
+    pub fn shrink_extras(&mut self) -> bool {
+        if self.extras() == &NO_EXTRAS {
+            self.extras = None;
+        }
+        self.extras.is_none()
+    }
+}
+
+impl Waypoint {
+    /// Gives the geographical point of the waypoint.
+    ///
+    /// The point holds the longitude as `x` and the latitude as `y`, see
+    /// [`lat`](Waypoint::lat) and [`lon`](Waypoint::lon).
     ///
+    /// ```
     /// use gpx::Waypoint;
-    /// use geo_types::Point;
     ///
-    /// fn main() {
-    ///     // Kind of useless, but it shows the point.
-    ///     let wpt = Waypoint::new(Point::new(-121.97, 37.24));
-    ///     let point = wpt.point();
+    /// let wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// let point = wpt.point();
     ///
-    ///     println!("waypoint latitude: {}, longitude: {}", point.x(), point.y());
-    /// }
+    /// assert_eq!((point.x(), point.y()), (-121.97, 37.24));
     /// ```
     pub fn point(&self) -> Point<f64> {
-        self.point.0 //.0 to extract the geo_types::Point from the tuple struct GpxPoint
+        self.point.0 //.0 to extract the geo_types::Point from the tuple struct DefaultPoint
+    }
+
+    /// Moves the waypoint to `point`.
+    pub(crate) fn set_point(&mut self, point: Point<f64>) {
+        self.point = DefaultPoint(point);
     }
 
-    /// Creates a new Waypoint from a given geographical point.
+    /// Creates a new Waypoint from a given geographical point, with the
+    /// longitude as `x` and the latitude as `y`. The coordinates are not
+    /// checked, see [`new_lat_lon`](Waypoint::new_lat_lon) for that.
     ///
     /// ```
     /// extern crate geo_types;
@@ -404,20 +878,177 @@ impl Waypoint {
     /// ```
     pub fn new(point: Point<f64>) -> Waypoint {
         Waypoint {
-            point: GpxPoint(point),
+            point: DefaultPoint(point),
             ..Default::default()
         }
     }
+
+    /// Creates a new Waypoint at `latitude` and `longitude`, checking that
+    /// they are in range.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// wpt.elevation = Some(553.21);
+    ///
+    /// assert!(Waypoint::new_lat_lon(-121.97, 37.24).is_err());
+    /// ```
+    pub fn new_lat_lon(latitude: f64, longitude: f64) -> GpxResult<Waypoint> {
+        validate_latitude(latitude)?;
+        validate_longitude(longitude)?;
+        Ok(Waypoint::new(Point::new(longitude, latitude)))
+    }
+
+    /// Gives the latitude of the waypoint, in degrees.
+    pub fn lat(&self) -> f64 {
+        self.point.0.y()
+    }
+
+    /// Gives the longitude of the waypoint, in degrees.
+    pub fn lon(&self) -> f64 {
+        self.point.0.x()
+    }
+
+    /// Sets the latitude of the waypoint, which has to lie within
+    /// [-90.0, 90.0]. The waypoint is left as it is otherwise.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// wpt.set_lat(37.25).unwrap();
+    /// assert!(wpt.set_lat(91.0).is_err());
+    ///
+    /// assert_eq!((wpt.lat(), wpt.lon()), (37.25, -121.97));
+    /// ```
+    pub fn set_lat(&mut self, latitude: f64) -> GpxResult<()> {
+        validate_latitude(latitude)?;
+        self.point.0.set_y(latitude);
+        Ok(())
+    }
+
+    /// Sets the longitude of the waypoint, which has to lie within
+    /// [-180.0, 180.0). The waypoint is left as it is otherwise.
+    pub fn set_lon(&mut self, longitude: f64) -> GpxResult<()> {
+        validate_longitude(longitude)?;
+        self.point.0.set_x(longitude);
+        Ok(())
+    }
+}
+
+impl Waypoint {
+    /// Gives the waypoint as a `(latitude, longitude, elevation, time)` tuple.
+    ///
+    /// ```
+    /// use gpx::Waypoint;
+    ///
+    /// let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// wpt.elevation = Some(553.21);
+    ///
+    /// assert_eq!(wpt.to_tuple(), (37.24, -121.97, Some(553.21), None));
+    /// ```
+    pub fn to_tuple(&self) -> (f64, f64, Option<f64>, Option<OffsetDateTime>) {
+        (
+            self.lat(),
+            self.lon(),
+            self.elevation,
+            self.time.map(OffsetDateTime::from),
+        )
+    }
+}
+
+impl TryFrom<(f64, f64, Option<f64>, Option<OffsetDateTime>)> for Waypoint {
+    type Error = GpxError;
+
+    /// Creates a waypoint from a `(latitude, longitude, elevation, time)` tuple,
+    /// checking that the coordinates are in range.
+    fn try_from(
+        (latitude, longitude, elevation, time): (f64, f64, Option<f64>, Option<OffsetDateTime>),
+    ) -> GpxResult<Waypoint> {
+        let mut waypoint = Waypoint::new_lat_lon(latitude, longitude)?;
+        waypoint.elevation = elevation;
+        waypoint.time = time.map(Time::from);
+        Ok(waypoint)
+    }
+}
+
+impl From<Point<f64>> for Waypoint {
+    /// Creates a waypoint at `point`, like [`Waypoint::new`], without
+    /// checking the range. **x is the longitude and y the latitude**, as
+    /// everywhere in `geo_types`.
+    fn from(point: Point<f64>) -> Waypoint {
+        Waypoint::new(point)
+    }
+}
+
+impl TryFrom<Coord<f64>> for Waypoint {
+    type Error = GpxError;
+
+    /// Creates a waypoint at `coord`, checking that it is in range. **x is
+    /// the longitude and y the latitude**, as everywhere in `geo_types`.
+    fn try_from(coord: Coord<f64>) -> GpxResult<Waypoint> {
+        Waypoint::new_lat_lon(coord.y, coord.x)
+    }
+}
+
+impl From<&Waypoint> for Point<f64> {
+    /// Gives the position of `waypoint`, see [`Waypoint::point`]. **x is the
+    /// longitude and y the latitude**, as everywhere in `geo_types`.
+    fn from(waypoint: &Waypoint) -> Point<f64> {
+        waypoint.point()
+    }
+}
+
+impl From<&Waypoint> for Coord<f64> {
+    /// Gives the position of `waypoint`. **x is the longitude and y the
+    /// latitude**, as everywhere in `geo_types`.
+    fn from(waypoint: &Waypoint) -> Coord<f64> {
+        waypoint.point().0
+    }
+}
+
+/// Checks that `value` of `field` is neither NaN nor infinite.
+pub(crate) fn validate_finite(field: &'static str, value: f64) -> GpxResult<f64> {
+    if !value.is_finite() {
+        return Err(GpxError::InvalidNumericValue(field, value.to_string()));
+    }
+    Ok(value)
+}
+
+/// Checks that `latitude` lies within [-90.0, 90.0].
+pub(crate) fn validate_latitude(latitude: f64) -> GpxResult<()> {
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(GpxError::LonLatOutOfBoundsError(
+            "latitude",
+            "[-90.0, 90.0]",
+            latitude,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `longitude` lies within [-180.0, 180.0).
+pub(crate) fn validate_longitude(longitude: f64) -> GpxResult<()> {
+    if !(-180.0..180.0).contains(&longitude) {
+        return Err(GpxError::LonLatOutOfBoundsError(
+            "Longitude",
+            "[-180.0, 180.0",
+            longitude,
+        ));
+    }
+    Ok(())
 }
 
 impl From<Waypoint> for Geometry<f64> {
+    /// Drops everything but the position, see [`GpxPoint::to_point_lossy`].
     fn from(waypoint: Waypoint) -> Geometry<f64> {
-        Geometry::Point(waypoint.point())
+        Geometry::Point(GpxPoint::from(&waypoint).to_point_lossy())
     }
 }
 
 /// Person represents a person or organization.
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
 pub struct Person {
     /// Name of person or organization.
@@ -427,6 +1058,9 @@ pub struct Person {
     pub email: Option<String>,
 
     /// Link to Web site or other external information about person.
+    ///
+    /// The GPX schema allows only one link for a person, unlike for the
+    /// metadata, waypoints, routes and tracks.
     pub link: Option<Link>,
 }
 
@@ -434,7 +1068,7 @@ pub struct Person {
 ///
 /// An external resource could be a web page, digital photo,
 /// video clip, etc., with additional information.
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
 pub struct Link {
     /// URL of hyperlink.
@@ -448,7 +1082,7 @@ pub struct Link {
 }
 
 /// Type of the GPS fix.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "use-serde", derive(Serialize, Deserialize))]
 pub enum Fix {
     /// The GPS had no fix. To signify "the fix info is unknown", leave out the Fix entirely.
@@ -464,3 +1098,318 @@ pub enum Fix {
     /// Other values that are not in the specification.
     Other(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+    use time::{Duration, OffsetDateTime};
+
+    use super::*;
+
+    fn point_at(seconds: i64) -> Waypoint {
+        let mut wpt = Waypoint::new(Point::new(-121.97, 37.24));
+        wpt.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(seconds)).into());
+        wpt
+    }
+
+    fn timed_gpx() -> Gpx {
+        let mut segment = TrackSegment::new();
+        segment.points.push(point_at(300));
+        segment.points.push(point_at(400));
+        let mut track = Track::new();
+        track.segments.push(segment);
+
+        let mut route = Route::new();
+        route.points.push(point_at(200));
+
+        Gpx {
+            waypoints: vec![point_at(500), Waypoint::new(Point::new(0., 0.))],
+            tracks: vec![track],
+            routes: vec![route],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn earliest_and_latest_time() {
+        let gpx = timed_gpx();
+
+        assert_eq!(gpx.earliest_time(), point_at(200).time);
+        assert_eq!(gpx.latest_time(), point_at(500).time);
+
+        assert_eq!(Gpx::default().earliest_time(), None);
+        assert_eq!(Gpx::default().latest_time(), None);
+    }
+
+    #[test]
+    fn ensure_metadata_time_creates_metadata() {
+        let mut gpx = timed_gpx();
+        assert!(gpx.metadata.is_none());
+
+        assert!(gpx.ensure_metadata_time());
+        assert_eq!(gpx.metadata.unwrap().time, point_at(200).time);
+    }
+
+    #[test]
+    fn ensure_metadata_time_keeps_existing_time() {
+        let mut gpx = timed_gpx();
+        gpx.metadata = Some(Metadata {
+            name: Some("keep me".into()),
+            time: point_at(1000).time,
+            ..Default::default()
+        });
+
+        assert!(!gpx.ensure_metadata_time());
+        let metadata = gpx.metadata.unwrap();
+        assert_eq!(metadata.time, point_at(1000).time);
+        assert_eq!(metadata.name.unwrap(), "keep me");
+    }
+
+    #[test]
+    fn waypoint_tuple_round_trip() {
+        let wpt = point_at(60);
+        let tuple = wpt.to_tuple();
+        assert_eq!(
+            tuple,
+            (
+                37.24,
+                -121.97,
+                None,
+                Some(OffsetDateTime::UNIX_EPOCH + Duration::seconds(60))
+            )
+        );
+
+        let converted = Waypoint::try_from(tuple).unwrap();
+        assert_eq!(converted, wpt);
+    }
+
+    #[test]
+    fn waypoint_try_from_tuple_out_of_range() {
+        assert!(Waypoint::try_from((90.1, 0.0, None, None)).is_err());
+        assert!(Waypoint::try_from((0.0, 180.0, None, None)).is_err());
+        assert!(Waypoint::try_from((-90.0, -180.0, Some(-12.5), None)).is_ok());
+    }
+
+    #[test]
+    fn version_ordering() {
+        use GpxVersion::*;
+
+        assert!(Gpx10 < Gpx11);
+        assert_eq!(Gpx11.partial_cmp(&Gpx10), Some(Ordering::Greater));
+        assert_eq!(Gpx11.partial_cmp(&Gpx11), Some(Ordering::Equal));
+        assert_eq!(Unknown.partial_cmp(&Unknown), Some(Ordering::Equal));
+        for known in [Gpx10, Gpx11].iter() {
+            assert_eq!(Unknown.partial_cmp(known), None);
+            assert_eq!(known.partial_cmp(&Unknown), None);
+            assert_eq!((Unknown < *known, Unknown >= *known), (false, false));
+        }
+
+        let mut versions = vec![Gpx11, Unknown, Gpx10];
+        versions.sort_by_key(|version| version.namespace_uri());
+        assert_eq!(versions, [Unknown, Gpx10, Gpx11]);
+    }
+
+    #[test]
+    fn version_capabilities() {
+        let cases = [
+            (GpxVersion::Unknown, None, false),
+            (
+                GpxVersion::Gpx10,
+                Some("http://www.topografix.com/GPX/1/0"),
+                false,
+            ),
+            (
+                GpxVersion::Gpx11,
+                Some("http://www.topografix.com/GPX/1/1"),
+                true,
+            ),
+        ];
+        for (version, namespace, gpx11) in cases.iter() {
+            assert_eq!(version.namespace_uri(), *namespace);
+            assert_eq!(version.supports_metadata_element(), *gpx11);
+            assert_eq!(version.supports_link_element(), *gpx11);
+            assert_eq!(version.supports_extensions_element(), *gpx11);
+        }
+    }
+
+    #[test]
+    fn waypoint_lat_lon_are_y_and_x() {
+        let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+        assert_eq!(wpt.point(), Point::new(-121.97, 37.24));
+        assert_eq!((wpt.lat(), wpt.lon()), (37.24, -121.97));
+
+        wpt.set_lat(-45.5).unwrap();
+        wpt.set_lon(179.5).unwrap();
+        assert_eq!(wpt.point(), Point::new(179.5, -45.5));
+    }
+
+    #[test]
+    fn waypoint_lat_lon_out_of_range() {
+        assert!(matches!(
+            Waypoint::new_lat_lon(90.1, 0.0),
+            Err(GpxError::LonLatOutOfBoundsError("latitude", _, _))
+        ));
+        assert!(matches!(
+            Waypoint::new_lat_lon(0.0, 180.0),
+            Err(GpxError::LonLatOutOfBoundsError("Longitude", _, _))
+        ));
+        assert!(Waypoint::new_lat_lon(f64::NAN, 0.0).is_err());
+        assert!(Waypoint::new_lat_lon(0.0, f64::INFINITY).is_err());
+
+        let mut wpt = Waypoint::new_lat_lon(-90.0, -180.0).unwrap();
+        assert!(wpt.set_lat(-90.5).is_err());
+        assert!(wpt.set_lon(f64::NAN).is_err());
+        assert!(wpt.set_lon(-180.1).is_err());
+        assert_eq!((wpt.lat(), wpt.lon()), (-90.0, -180.0));
+    }
+
+    #[test]
+    fn ensure_metadata_time_without_times() {
+        let mut gpx = Gpx::default();
+
+        assert!(!gpx.ensure_metadata_time());
+        assert!(gpx.metadata.is_none());
+    }
+
+    #[test]
+    fn waypoint_extras_only_when_needed() {
+        // Down from about 500 bytes with every field inline.
+        assert!(std::mem::size_of::<Waypoint>() <= 96);
+
+        let gpx = crate::read(
+            r#"<gpx version="1.1" creator="test"><trk><trkseg>
+                <trkpt lat="47.0" lon="8.0"><ele>400</ele><time>2021-05-01T10:00:00Z</time></trkpt>
+                <trkpt lat="47.1" lon="8.1"><ele>401</ele><hdop>1.5</hdop></trkpt>
+            </trkseg></trk></gpx>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let points = &gpx.tracks[0].segments[0].points;
+        assert!(points[0].extras.is_none());
+        assert_eq!(points[1].extras().hdop, Some(1.5));
+
+        let mut wpt = points[1].clone();
+        assert!(!wpt.shrink_extras());
+        wpt.extras_mut().hdop = None;
+        assert!(wpt.shrink_extras());
+        assert_eq!(
+            wpt,
+            Waypoint {
+                extras: None,
+                ..points[1].clone()
+            }
+        );
+    }
+
+    #[test]
+    fn waypoint_extras_accessors() {
+        let mut wpt = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+        wpt.set_hdop(None);
+        wpt.set_comment(None);
+        wpt.set_links(Vec::new());
+        assert!(wpt.extras.is_none(), "setting nothing allocates nothing");
+
+        wpt.set_hdop(Some(1.5));
+        wpt.set_comment(Some("Bench".into()));
+        wpt.set_fix(Some(Fix::DGPS));
+        wpt.set_links(vec![Link {
+            href: "https://example.com".into(),
+            ..Default::default()
+        }]);
+        assert_eq!(wpt.hdop(), Some(1.5));
+        assert_eq!(wpt.comment(), Some("Bench"));
+        assert_eq!(wpt.fix(), Some(&Fix::DGPS));
+        assert_eq!(wpt.links()[0].href, "https://example.com");
+        assert_eq!(wpt.extras().hdop, Some(1.5));
+
+        wpt.set_hdop(None);
+        wpt.set_comment(None);
+        wpt.set_fix(None);
+        wpt.set_links(Vec::new());
+        assert!(wpt.shrink_extras());
+        assert_eq!(wpt.hdop(), None);
+        assert!(wpt.links().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "use-serde")]
+    fn waypoint_serializes_extras_among_fields() {
+        let mut wpt = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+        wpt.elevation = Some(400.0);
+        wpt.set_hdop(Some(1.5));
+        wpt.set_symbol(Some("Flag".into()));
+        let json = serde_json::to_value(&wpt).unwrap();
+        assert_eq!(json["hdop"], 1.5);
+        assert_eq!(json["symbol"], "Flag");
+        assert_eq!(
+            serde_json::from_value::<Waypoint>(json.clone()).unwrap(),
+            wpt
+        );
+
+        // Formats that don't describe themselves give the fields in order,
+        // without their names.
+        let fields: Vec<serde_json::Value> = [
+            "point",
+            "elevation",
+            "speed",
+            "time",
+            "name",
+            "comment",
+            "description",
+            "source",
+            "links",
+            "symbol",
+            "_type",
+            "geoidheight",
+            "fix",
+            "sat",
+            "hdop",
+            "vdop",
+            "pdop",
+            "age",
+            "dgps_age",
+            "dgpsid",
+            "extensions",
+            "unknown_attributes",
+        ]
+        .iter()
+        .map(|field| json[*field].clone())
+        .collect();
+        assert_eq!(json.as_object().unwrap().len(), fields.len());
+        let positional: Waypoint = serde_json::from_value(fields.into()).unwrap();
+        assert_eq!(positional, wpt);
+
+        let plain = Waypoint::new_lat_lon(47.0, 8.0).unwrap();
+        let plain = serde_json::to_value(&plain).unwrap();
+        assert!(serde_json::from_value::<Waypoint>(plain)
+            .unwrap()
+            .extras
+            .is_none());
+    }
+
+    #[test]
+    fn waypoint_point_conversions() {
+        let point = Point::new(-121.97, 37.24);
+        let wpt = Waypoint::from(point);
+        assert_eq!((wpt.lat(), wpt.lon()), (37.24, -121.97));
+        assert_eq!(Point::from(&wpt), point);
+        assert_eq!(Coord::from(&wpt), point.0);
+
+        // Like Waypoint::new, From doesn't check the range.
+        assert_eq!(Waypoint::from(Point::new(200.0, 0.0)).lon(), 200.0);
+
+        let coord = Coord {
+            x: -121.97,
+            y: 37.24,
+        };
+        assert_eq!(Waypoint::try_from(coord).unwrap(), wpt);
+        let swapped = Coord {
+            x: 37.24,
+            y: -121.97,
+        };
+        assert!(matches!(
+            Waypoint::try_from(swapped),
+            Err(GpxError::LonLatOutOfBoundsError("latitude", _, _))
+        ));
+    }
+}
@@ -0,0 +1,454 @@
+//! tzif reads the compiled zones of the tz database, in the TZif format of
+//! RFC 8536, as far as needed for the offset from UTC at a given time.
+
+use std::convert::TryFrom;
+
+use time::util::is_leap_year;
+use time::{Date, Month};
+
+/// The offsets of a zone, as read from its TZif file.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Zone {
+    /// The times at which the offset changes, in seconds since the Unix
+    /// epoch, with the offset in seconds from then on.
+    transitions: Vec<(i64, i32)>,
+    /// The offset before the first transition.
+    initial: i32,
+    /// The rule for the times after the last transition, if there is one.
+    rule: Option<Rule>,
+}
+
+impl Zone {
+    /// Reads a zone from the contents of its TZif file.
+    pub(crate) fn parse(data: &[u8]) -> Option<Zone> {
+        let (version, counts, rest) = header(data)?;
+        if version < b'2' {
+            return block(rest, &counts, 4).map(|(zone, _)| zone);
+        }
+        // Skip the block with 32-bit times, and read the one with 64-bit
+        // times and the footer after it.
+        let rest = rest.get(counts.block_len(4)..)?;
+        let (_, counts, rest) = header(rest)?;
+        let (mut zone, footer) = block(rest, &counts, 8)?;
+        let footer = footer.strip_prefix(b"\n")?;
+        let end = footer.iter().position(|&b| b == b'\n')?;
+        let footer = std::str::from_utf8(&footer[..end]).ok()?;
+        if !footer.is_empty() {
+            zone.rule = Some(Rule::parse(footer)?);
+        }
+        Some(zone)
+    }
+
+    /// Gives the offset in seconds at `time`, in seconds since the Unix
+    /// epoch.
+    pub(crate) fn offset_at(&self, time: i64) -> i32 {
+        let index = self.transitions.partition_point(|&(at, _)| at <= time);
+        match &self.rule {
+            Some(rule) if index == self.transitions.len() => rule.offset_at(time),
+            _ if index == 0 => self.initial,
+            _ => self.transitions[index - 1].1,
+        }
+    }
+}
+
+/// The numbers of records in a block of a TZif file.
+struct Counts {
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Counts {
+    /// Gives the length of a block with times `time_len` bytes long.
+    fn block_len(&self, time_len: usize) -> usize {
+        self.timecnt * (time_len + 1)
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_len + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+/// Reads the header of a block, giving the version, the counts and what
+/// follows the header.
+fn header(data: &[u8]) -> Option<(u8, Counts, &[u8])> {
+    if data.len() < 44 || &data[..4] != b"TZif" {
+        return None;
+    }
+    let count = |i: usize| {
+        let at = 20 + 4 * i;
+        u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]) as usize
+    };
+    let counts = Counts {
+        isutcnt: count(0),
+        isstdcnt: count(1),
+        leapcnt: count(2),
+        timecnt: count(3),
+        typecnt: count(4),
+        charcnt: count(5),
+    };
+    Some((data[4], counts, &data[44..]))
+}
+
+/// Reads the transitions and time types of a block with times `time_len`
+/// bytes long, giving the zone without a rule and what follows the block.
+fn block<'a>(data: &'a [u8], counts: &Counts, time_len: usize) -> Option<(Zone, &'a [u8])> {
+    if counts.typecnt == 0 || data.len() < counts.block_len(time_len) {
+        return None;
+    }
+    let (times, rest) = data.split_at(counts.timecnt * time_len);
+    let (indices, rest) = rest.split_at(counts.timecnt);
+    let offsets = rest[..counts.typecnt * 6]
+        .chunks(6)
+        .map(|record| i32::from_be_bytes([record[0], record[1], record[2], record[3]]))
+        .collect::<Vec<_>>();
+    let transitions = times
+        .chunks(time_len)
+        .zip(indices)
+        .map(|(time, &index)| {
+            let time = match *time {
+                [a, b, c, d] => i32::from_be_bytes([a, b, c, d]) as i64,
+                [a, b, c, d, e, f, g, h] => i64::from_be_bytes([a, b, c, d, e, f, g, h]),
+                _ => unreachable!(),
+            };
+            Some((time, *offsets.get(index as usize)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let zone = Zone {
+        transitions,
+        initial: offsets[0],
+        rule: None,
+    };
+    Some((zone, &data[counts.block_len(time_len)..]))
+}
+
+/// A POSIX TZ string, such as `CET-1CEST,M3.5.0,M10.5.0/3`, which gives
+/// the offsets of a zone for all years to come.
+#[derive(Clone, Debug, PartialEq)]
+struct Rule {
+    /// The standard offset, in seconds east of UTC.
+    standard: i32,
+    /// The summer time offset, and when it starts and ends.
+    summer: Option<(i32, Change, Change)>,
+}
+
+/// The day and the local time of a change between standard and summer
+/// time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Change {
+    day: ChangeDay,
+    /// The local time of the change, in seconds after midnight. It may be
+    /// negative or more than a day.
+    time: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ChangeDay {
+    /// `Jn`, the day of the year from 1 on, never counting February 29.
+    Julian(u16),
+    /// `n`, the day of the year from 0 on, counting February 29.
+    Ordinal(u16),
+    /// `Mm.w.d`, the `d`th day of the week from Sunday on, in week `w` of
+    /// month `m`, with week 5 the last.
+    Month(u8, u8, u8),
+}
+
+impl Rule {
+    fn parse(text: &str) -> Option<Rule> {
+        let mut text = Cursor(text);
+        text.name()?;
+        let standard = -text.duration(MAX_OFFSET_HOURS)?;
+        if text.0.is_empty() {
+            return Some(Rule {
+                standard,
+                summer: None,
+            });
+        }
+        text.name()?;
+        let summer = if text.0.starts_with(',') || text.0.is_empty() {
+            standard + 3600
+        } else {
+            -text.duration(MAX_OFFSET_HOURS)?
+        };
+        let (start, end) = if text.0.is_empty() {
+            // The rules of the United States, which POSIX leaves open but
+            // implementations assume.
+            (
+                Change {
+                    day: ChangeDay::Month(3, 2, 0),
+                    time: 7200,
+                },
+                Change {
+                    day: ChangeDay::Month(11, 1, 0),
+                    time: 7200,
+                },
+            )
+        } else {
+            text.expect(',')?;
+            let start = text.change()?;
+            text.expect(',')?;
+            (start, text.change()?)
+        };
+        if !text.0.is_empty() {
+            return None;
+        }
+        Some(Rule {
+            standard,
+            summer: Some((summer, start, end)),
+        })
+    }
+
+    fn offset_at(&self, time: i64) -> i32 {
+        let (summer, start, end) = match self.summer {
+            Some(summer) => summer,
+            None => return self.standard,
+        };
+        let year = match Date::from_julian_day(
+            ((time + self.standard as i64).div_euclid(86400) + 2_440_588) as i32,
+        ) {
+            Ok(date) => date.year(),
+            Err(_) => return self.standard,
+        };
+        let (start, end) = match (
+            start.instant(year, self.standard),
+            end.instant(year, summer),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return self.standard,
+        };
+        let in_summer = if start <= end {
+            start <= time && time < end
+        } else {
+            // Summer time over the new year, as in the southern hemisphere.
+            time < end || start <= time
+        };
+        if in_summer {
+            summer
+        } else {
+            self.standard
+        }
+    }
+}
+
+impl Change {
+    /// Gives the instant of the change in `year`, at a time when the
+    /// offset is `offset`.
+    fn instant(self, year: i32, offset: i32) -> Option<i64> {
+        let leap = is_leap_year(year);
+        let date = match self.day {
+            ChangeDay::Julian(day) => {
+                Date::from_ordinal_date(year, day + (leap && day >= 60) as u16).ok()?
+            }
+            ChangeDay::Ordinal(day) => Date::from_ordinal_date(year, day + 1).ok()?,
+            ChangeDay::Month(month, week, weekday) => {
+                let month = Month::try_from(month).ok()?;
+                let first = Date::from_calendar_date(year, month, 1).ok()?;
+                let first_weekday = first.weekday().number_days_from_sunday();
+                let mut day = 1 + (weekday + 7 - first_weekday) % 7 + 7 * (week - 1);
+                while day > days_in_month(year, month) {
+                    day -= 7;
+                }
+                Date::from_calendar_date(year, month, day).ok()?
+            }
+        };
+        let midnight = date.midnight().assume_utc().unix_timestamp();
+        Some(midnight + self.time as i64 - offset as i64)
+    }
+}
+
+fn days_in_month(year: i32, month: Month) -> u8 {
+    match month {
+        Month::February if is_leap_year(year) => 29,
+        Month::February => 28,
+        Month::April | Month::June | Month::September | Month::November => 30,
+        _ => 31,
+    }
+}
+
+/// The most hours of an offset in a TZ string, as POSIX allows.
+const MAX_OFFSET_HOURS: i32 = 24;
+
+/// The most hours of the time of a change, as RFC 8536 extends POSIX to.
+const MAX_CHANGE_HOURS: i32 = 167;
+
+/// What is left of a TZ string to parse.
+struct Cursor<'a>(&'a str);
+
+impl Cursor<'_> {
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.0 = self.0.strip_prefix(c)?;
+        Some(())
+    }
+
+    /// Skips a zone abbreviation, such as `CET` or `<+0530>`.
+    fn name(&mut self) -> Option<()> {
+        let len = if let Some(quoted) = self.0.strip_prefix('<') {
+            quoted.find('>')? + 2
+        } else {
+            self.0
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(self.0.len())
+        };
+        if len < 3 {
+            return None;
+        }
+        self.0 = &self.0[len..];
+        Some(())
+    }
+
+    fn number(&mut self) -> Option<i32> {
+        let len = self
+            .0
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.0.len());
+        let number = self.0[..len].parse().ok()?;
+        self.0 = &self.0[len..];
+        Some(number)
+    }
+
+    /// Reads `[+-]hh[:mm[:ss]]`, giving it in seconds, with at most
+    /// `max_hours` hours.
+    fn duration(&mut self, max_hours: i32) -> Option<i32> {
+        let sign = if self.expect('-').is_some() {
+            -1
+        } else {
+            self.expect('+');
+            1
+        };
+        let hours = self.number()?;
+        if hours > max_hours {
+            return None;
+        }
+        let mut seconds = hours.checked_mul(3600)?;
+        for unit in [60, 1].iter() {
+            if self.expect(':').is_none() {
+                break;
+            }
+            match self.number()? {
+                part @ 0..=59 => seconds = seconds.checked_add(part * unit)?,
+                _ => return None,
+            }
+        }
+        Some(sign * seconds)
+    }
+
+    /// Reads the day of a change, and its time after a `/`.
+    fn change(&mut self) -> Option<Change> {
+        let day = if self.expect('J').is_some() {
+            match self.number()? {
+                day @ 1..=365 => ChangeDay::Julian(day as u16),
+                _ => return None,
+            }
+        } else if self.expect('M').is_some() {
+            let month = self.number()?;
+            self.expect('.')?;
+            let week = self.number()?;
+            self.expect('.')?;
+            let weekday = self.number()?;
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+                return None;
+            }
+            ChangeDay::Month(month as u8, week as u8, weekday as u8)
+        } else {
+            match self.number()? {
+                day @ 0..=365 => ChangeDay::Ordinal(day as u16),
+                _ => return None,
+            }
+        };
+        let time = if self.expect('/').is_some() {
+            self.duration(MAX_CHANGE_HOURS)?
+        } else {
+            7200
+        };
+        Some(Change { day, time })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use time::{Date, Month};
+
+    use super::{Rule, Zone};
+
+    fn at(year: i32, month: Month, day: u8, hour: u8) -> i64 {
+        Date::from_calendar_date(year, month, day)
+            .unwrap()
+            .with_hms(hour, 0, 0)
+            .unwrap()
+            .assume_utc()
+            .unix_timestamp()
+    }
+
+    #[test]
+    fn zone_from_file() {
+        let data = fs::read("tests/fixtures/zoneinfo/Europe/Zurich").unwrap();
+        let zone = Zone::parse(&data).unwrap();
+        // Before and after the switch to summer time of 2021, from the
+        // transitions.
+        assert_eq!(zone.offset_at(at(2021, Month::March, 28, 0)), 3600);
+        assert_eq!(zone.offset_at(at(2021, Month::March, 28, 1)), 7200);
+        // Past the transitions in the file, from the rule in its footer.
+        assert_eq!(zone.offset_at(at(2050, Month::March, 27, 0)), 3600);
+        assert_eq!(zone.offset_at(at(2050, Month::March, 27, 1)), 7200);
+        assert_eq!(zone.offset_at(at(2050, Month::October, 30, 0)), 7200);
+        assert_eq!(zone.offset_at(at(2050, Month::October, 30, 1)), 3600);
+        // Local mean time, before the zone had a standard time at all.
+        assert_eq!(zone.offset_at(at(1850, Month::June, 1, 0)), 2048);
+
+        assert_eq!(Zone::parse(b"TZif2"), None);
+        assert_eq!(Zone::parse(&data[..100]), None);
+    }
+
+    #[test]
+    fn rules() {
+        let rule = Rule::parse("<+0530>-5:30").unwrap();
+        assert_eq!(rule.offset_at(at(2021, Month::June, 1, 0)), 19800);
+
+        // New York, with the switches at 2:00 local time.
+        let rule = Rule::parse("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(rule.offset_at(at(2021, Month::March, 14, 6)), -18000);
+        assert_eq!(rule.offset_at(at(2021, Month::March, 14, 7)), -14400);
+        assert_eq!(rule.offset_at(at(2021, Month::November, 7, 5)), -14400);
+        assert_eq!(rule.offset_at(at(2021, Month::November, 7, 6)), -18000);
+        assert_eq!(Rule::parse("EST5EDT"), Some(rule));
+
+        // Sydney, with summer time over the new year.
+        let rule = Rule::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(rule.offset_at(at(2021, Month::January, 1, 0)), 39600);
+        assert_eq!(rule.offset_at(at(2021, Month::June, 1, 0)), 36000);
+        assert_eq!(rule.offset_at(at(2021, Month::December, 1, 0)), 39600);
+
+        // Day numbers, with and without February 29, in a leap year.
+        let julian = Rule::parse("AAA0BBB,J60/0,J61/0").unwrap();
+        assert_eq!(julian.offset_at(at(2024, Month::March, 1, 0)), 3600);
+        let ordinal = Rule::parse("AAA0BBB,59/0,60/0").unwrap();
+        assert_eq!(ordinal.offset_at(at(2024, Month::February, 29, 0)), 3600);
+
+        // Changes up to a week after the day, at the most hours allowed.
+        let late = Rule::parse("AAA0BBB,M3.2.0/167,M11.1.0/-167").unwrap();
+        assert_eq!(late.offset_at(at(2021, Month::March, 20, 22)), 0);
+        assert_eq!(late.offset_at(at(2021, Month::March, 21, 0)), 3600);
+
+        for invalid in [
+            "",
+            "A0",
+            "CET-1CEST,M13.1.0,M10.5.0",
+            "CET-1CEST,M3.5.0",
+            "AAA25",
+            "AAA1:60",
+            "AAA999999999",
+            "AAA0BBB,M3.2.0/168,M11.1.0",
+            "AAA0BBB,M3.2.0/2:00:99,M11.1.0",
+        ]
+        .iter()
+        {
+            assert_eq!(Rule::parse(invalid), None, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! validate checks documents that were built in code.
+
+use std::fmt;
+
+use crate::errors::{GpxError, GpxResult};
+use crate::positions::ElementPath;
+use crate::types::{validate_finite, validate_latitude, validate_longitude};
+use crate::{Gpx, Link, Waypoint};
+
+/// The coordinate of a [`CoordinateViolation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CoordinateAxis {
+    /// The latitude, which must lie within [-90.0, 90.0].
+    Latitude,
+    /// The longitude, which must lie within [-180.0, 180.0).
+    Longitude,
+}
+
+impl CoordinateAxis {
+    /// Gives the range of valid values, as in "[-180.0, 180.0)".
+    pub fn range(self) -> &'static str {
+        match self {
+            CoordinateAxis::Latitude => "[-90.0, 90.0]",
+            CoordinateAxis::Longitude => "[-180.0, 180.0)",
+        }
+    }
+
+    /// Whether `value` is valid. NaN never is.
+    pub fn contains(self, value: f64) -> bool {
+        match self {
+            CoordinateAxis::Latitude => (-90.0..=90.0).contains(&value),
+            CoordinateAxis::Longitude => (-180.0..180.0).contains(&value),
+        }
+    }
+}
+
+impl fmt::Display for CoordinateAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoordinateAxis::Latitude => write!(f, "latitude"),
+            CoordinateAxis::Longitude => write!(f, "longitude"),
+        }
+    }
+}
+
+/// A coordinate out of its range, as found by [`Gpx::invalid_coordinates`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoordinateViolation {
+    /// The point with the coordinate.
+    pub path: ElementPath,
+
+    /// Which coordinate is out of range.
+    pub axis: CoordinateAxis,
+
+    /// The value of the coordinate.
+    pub value: f64,
+}
+
+impl CoordinateViolation {
+    /// Gives the range the coordinate should be in, see
+    /// [`CoordinateAxis::range`].
+    pub fn range(&self) -> &'static str {
+        self.axis.range()
+    }
+}
+
+impl fmt::Display for CoordinateViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} is not within {}",
+            self.path,
+            self.axis,
+            self.value,
+            self.range()
+        )
+    }
+}
+
+impl Gpx {
+    /// Checks the numbers of the document the same way reading a file does:
+    /// coordinates must lie within their ranges, and no decimal may be NaN
+    /// or infinite. Returns the first problem found.
+    ///
+    /// Documents that were read from a file always pass, but values set in
+    /// code are not checked until the document is written.
+    ///
+    /// ```
+    /// use gpx::{Gpx, Waypoint};
+    ///
+    /// let mut wpt = Waypoint::new_lat_lon(37.24, -121.97).unwrap();
+    /// wpt.elevation = Some(f64::NAN);
+    /// let gpx = Gpx {
+    ///     waypoints: vec![wpt],
+    ///     ..Default::default()
+    /// };
+    /// assert!(gpx.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> GpxResult<()> {
+        if let Some(bounds) = self.metadata.as_ref().and_then(|metadata| metadata.bounds) {
+            validate_finite("minlat", bounds.min().y)?;
+            validate_finite("maxlat", bounds.max().y)?;
+            validate_finite("minlon", bounds.min().x)?;
+            validate_finite("maxlon", bounds.max().x)?;
+        }
+        let route_points = self.routes.iter().flat_map(|route| route.points.iter());
+        let track_points = self
+            .tracks
+            .iter()
+            .flat_map(|track| track.segments.iter())
+            .flat_map(|segment| segment.points.iter());
+        for point in self
+            .waypoints
+            .iter()
+            .chain(route_points)
+            .chain(track_points)
+        {
+            validate_waypoint(point)?;
+        }
+        Ok(())
+    }
+
+    /// Gives every coordinate of a point of the document that is out of its
+    /// range or NaN, in document order: waypoints, route points, then track
+    /// points.
+    ///
+    /// Unlike [`Gpx::validate`], this doesn't stop at the first problem and
+    /// only looks at coordinates, to show all of them to be fixed at once.
+    ///
+    /// ```
+    /// use geo_types::Point;
+    /// use gpx::{CoordinateAxis, ElementPath, Gpx, Waypoint};
+    ///
+    /// let gpx = Gpx {
+    ///     waypoints: vec![
+    ///         Waypoint::new(Point::new(8.5, 47.4)),
+    ///         Waypoint::new(Point::new(8.5, 97.4)),
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    /// let violations = gpx.invalid_coordinates();
+    /// assert_eq!(violations.len(), 1);
+    /// assert_eq!(violations[0].path, ElementPath::Waypoint(1));
+    /// assert_eq!(violations[0].axis, CoordinateAxis::Latitude);
+    /// assert_eq!(violations[0].range(), "[-90.0, 90.0]");
+    /// ```
+    pub fn invalid_coordinates(&self) -> Vec<CoordinateViolation> {
+        let mut violations = Vec::new();
+        let mut check_point = |path: ElementPath, point: &Waypoint| {
+            let coordinates = [
+                (CoordinateAxis::Latitude, point.lat()),
+                (CoordinateAxis::Longitude, point.lon()),
+            ];
+            for &(axis, value) in &coordinates {
+                if !axis.contains(value) {
+                    violations.push(CoordinateViolation { path, axis, value });
+                }
+            }
+        };
+        for (w, point) in self.waypoints.iter().enumerate() {
+            check_point(ElementPath::Waypoint(w), point);
+        }
+        for (r, route) in self.routes.iter().enumerate() {
+            for (p, point) in route.points.iter().enumerate() {
+                check_point(ElementPath::RoutePoint(r, p), point);
+            }
+        }
+        for (t, track) in self.tracks.iter().enumerate() {
+            for (s, segment) in track.segments.iter().enumerate() {
+                for (p, point) in segment.points.iter().enumerate() {
+                    check_point(ElementPath::TrackPoint(t, s, p), point);
+                }
+            }
+        }
+        violations
+    }
+}
+
+impl Link {
+    /// Checks that the href is a plausible URI, absolute or relative: it
+    /// may only contain the characters URIs allow, `%` only as part of an
+    /// escape like `%20`, and a scheme, if there is one, must be well-formed.
+    ///
+    /// Hrefs are read as they are, so links in files often fail this check,
+    /// for example because of spaces or Windows paths. See
+    /// [`WriteOptions::percent_encode_links`](crate::WriteOptions::percent_encode_links)
+    /// to fix that when writing.
+    ///
+    /// ```
+    /// use gpx::Link;
+    ///
+    /// let mut link = Link {
+    ///     href: "photos/img_001.jpg".into(),
+    ///     ..Default::default()
+    /// };
+    /// assert!(link.validate().is_ok());
+    ///
+    /// link.href = "C:\\Users\\me\\pic.jpg".into();
+    /// assert!(link.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> GpxResult<()> {
+        let invalid = || Err(GpxError::InvalidLinkHref(self.href.clone()));
+        let bytes = self.href.as_bytes();
+        for (index, &byte) in bytes.iter().enumerate() {
+            let valid = is_uri_byte(byte)
+                || byte == b'%'
+                    && bytes.get(index + 1).is_some_and(u8::is_ascii_hexdigit)
+                    && bytes.get(index + 2).is_some_and(u8::is_ascii_hexdigit);
+            if !valid {
+                return invalid();
+            }
+        }
+        // A colon before the first `/`, `?` or `#` ends the scheme, which
+        // relative references cannot have.
+        let first_part = self.href.split(['/', '?', '#']);
+        if let Some((scheme, _)) = first_part.take(1).find_map(|part| part.split_once(':')) {
+            let mut chars = scheme.chars();
+            let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+            if !valid {
+                return invalid();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `byte` may appear in a URI as it is, that is, whether it is an
+/// unreserved or a reserved character of RFC 3986.
+pub(crate) fn is_uri_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~:/?#[]@!$&'()*+,;=".contains(&byte)
+}
+
+fn validate_waypoint(point: &Waypoint) -> GpxResult<()> {
+    validate_latitude(validate_finite("lat", point.lat())?)?;
+    validate_longitude(validate_finite("lon", point.lon())?)?;
+    let decimals = [
+        ("ele", point.elevation),
+        ("speed", point.extras().speed),
+        ("geoidheight", point.extras().geoidheight),
+        ("hdop", point.extras().hdop),
+        ("vdop", point.extras().vdop),
+        ("pdop", point.extras().pdop),
+        ("ageofdgpsdata", point.extras().dgps_age),
+    ];
+    for &(field, value) in &decimals {
+        if let Some(value) = value {
+            validate_finite(field, value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::Point;
+
+    use super::{CoordinateAxis, CoordinateViolation};
+    use crate::{ElementPath, Gpx, Route, Track, TrackSegment, Waypoint};
+
+    fn point(lon: f64, lat: f64) -> Waypoint {
+        Waypoint::new(Point::new(lon, lat))
+    }
+
+    #[test]
+    fn all_invalid_coordinates() {
+        let mut route = Route::new();
+        route.points = vec![point(8.0, 47.0), point(180.0, 47.0)];
+        let mut first = TrackSegment::new();
+        first.points = vec![point(8.0, 47.0); 3];
+        let mut second = TrackSegment::new();
+        second.points = vec![point(8.0, 47.0), point(8.0, -90.5), point(8.0, 47.0)];
+        let mut track = Track::new();
+        track.segments = vec![first, second];
+        let gpx = Gpx {
+            waypoints: vec![
+                point(-180.0, 90.0),
+                point(8.0, f64::NAN),
+                point(-181.0, 0.0),
+            ],
+            routes: vec![route],
+            tracks: vec![Track::new(), track],
+            ..Default::default()
+        };
+
+        let violations = gpx.invalid_coordinates();
+        let found: Vec<(ElementPath, CoordinateAxis)> = violations
+            .iter()
+            .map(|violation| (violation.path, violation.axis))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (ElementPath::Waypoint(1), CoordinateAxis::Latitude),
+                (ElementPath::Waypoint(2), CoordinateAxis::Longitude),
+                (ElementPath::RoutePoint(0, 1), CoordinateAxis::Longitude),
+                (ElementPath::TrackPoint(1, 1, 1), CoordinateAxis::Latitude),
+            ]
+        );
+        assert!(violations[0].value.is_nan());
+        assert_eq!(
+            violations[3],
+            CoordinateViolation {
+                path: ElementPath::TrackPoint(1, 1, 1),
+                axis: CoordinateAxis::Latitude,
+                value: -90.5,
+            }
+        );
+        assert_eq!(
+            violations[2].to_string(),
+            "routes[0].points[1]: longitude 180 is not within [-180.0, 180.0)"
+        );
+    }
+}
@@ -1,13 +1,23 @@
 //! Writes an activity to GPX format.
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::io::Write;
 
 use geo_types::Rect;
+use time::OffsetDateTime;
+use xml::common::XmlVersion;
+use xml::name::Name;
+use xml::writer::events::StartElementBuilder;
 use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
 
+use crate::dom::{Element, Node, OwnedAttribute, OwnedName, PrologNode, UnknownAttributes};
 use crate::errors::{GpxError, GpxResult};
-use crate::parser::time::Time;
+use crate::extension_sink::{write_point_extensions, PointExtensionWriter, PointKind};
+use crate::namespaces::visit_bindings;
+use crate::parser::time::{FractionDigits, Time, TimeFormat};
 use crate::types::*;
+use crate::validate::is_uri_byte;
 use crate::{Gpx, GpxVersion};
 
 /// Writes an activity to GPX format.
@@ -17,6 +27,11 @@ use crate::{Gpx, GpxVersion};
 ///
 /// [`Result<(), GpxError>`]: std::result::Result<T>
 ///
+/// Extensions are written with the prefixes they were read with. Their
+/// namespaces are declared on the root element, except where a prefix is
+/// bound to different namespaces in different places, which is then
+/// declared again on the elements using the other namespace.
+///
 /// ```
 /// use gpx::write;
 /// use gpx::Gpx;
@@ -29,10 +44,261 @@ use crate::{Gpx, GpxVersion};
 /// write(&data, std::io::stdout()).unwrap();
 /// ```
 pub fn write<W: Write>(gpx: &Gpx, writer: W) -> GpxResult<()> {
-    let mut writer = EmitterConfig::new()
-        .perform_indent(true)
-        .create_writer(writer);
-    write_with_event_writer(gpx, &mut writer)
+    write_with_options(gpx, writer, WriteOptions::default())
+}
+
+/// Options to tune how GPX files are written.
+///
+/// The defaults match the behavior of [`write`]. Options are set in a builder
+/// style and the result is handed to [`write_with_options`].
+///
+/// ```
+/// use gpx::WriteOptions;
+///
+/// let options = WriteOptions::new().generated_by_comment(true);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    pub(crate) generated_by_comment: bool,
+    pub(crate) percent_encode_links: bool,
+    pub(crate) point_extensions: Option<PointExtensionWriter>,
+    pub(crate) namespaces: Vec<(String, String)>,
+    pub(crate) preserve_space: bool,
+    pub(crate) time_format: TimeFormat,
+    pub(crate) indent: bool,
+    pub(crate) xml_declaration: bool,
+    pub(crate) omit_defaults: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            generated_by_comment: false,
+            percent_encode_links: false,
+            point_extensions: None,
+            namespaces: Vec::new(),
+            preserve_space: false,
+            time_format: TimeFormat::default(),
+            indent: true,
+            xml_declaration: true,
+            omit_defaults: false,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Creates the default options.
+    pub fn new() -> WriteOptions {
+        Default::default()
+    }
+
+    /// Creates options for the smallest output that still holds all data,
+    /// for devices where every byte counts: no indentation, no XML
+    /// declaration, times without trailing zeros in their fractional
+    /// seconds and no elements that say nothing, see
+    /// [`WriteOptions::omit_defaults`].
+    ///
+    /// ```
+    /// use gpx::{write_with_options, Gpx, GpxVersion, Metadata, WriteOptions};
+    ///
+    /// let mut data: Gpx = Default::default();
+    /// data.version = GpxVersion::Gpx11;
+    /// data.creator = Some("app".into());
+    /// data.metadata = Some(Metadata::default());
+    ///
+    /// let mut buffer = Vec::new();
+    /// write_with_options(&data, &mut buffer, WriteOptions::minimal()).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buffer).unwrap(),
+    ///     r#"<gpx xmlns="http://www.topografix.com/GPX/1/1" version="1.1" creator="app"/>"#
+    /// );
+    /// ```
+    pub fn minimal() -> WriteOptions {
+        WriteOptions::new()
+            .indent(false)
+            .xml_declaration(false)
+            .omit_defaults(true)
+            .time_format(TimeFormat {
+                fraction_digits: FractionDigits::Trimmed,
+                ..TimeFormat::default()
+            })
+    }
+
+    /// Whether to add a comment before the `<gpx>` element, naming this
+    /// crate and the time of writing. It comes after the comments and
+    /// processing instructions of [`Gpx::prolog`].
+    pub fn generated_by_comment(mut self, add: bool) -> WriteOptions {
+        self.generated_by_comment = add;
+        self
+    }
+
+    /// Whether to percent-encode the characters of link hrefs that are not
+    /// allowed in a URI, such as spaces and backslashes. Existing
+    /// percent-encoded sequences are left alone. The links of the document
+    /// itself are not changed.
+    pub fn percent_encode_links(mut self, encode: bool) -> WriteOptions {
+        self.percent_encode_links = encode;
+        self
+    }
+
+    /// Declares `prefix` for the namespace `uri` on the `<gpx>` element, so
+    /// that the elements using it need not declare it themselves. This is
+    /// mostly useful with [`WriteOptions::point_extension_writer`], as the
+    /// namespaces of extensions kept as elements are declared there anyway.
+    pub fn declare_namespace(
+        mut self,
+        prefix: impl Into<String>,
+        uri: impl Into<String>,
+    ) -> WriteOptions {
+        self.namespaces.push((prefix.into(), uri.into()));
+        self
+    }
+
+    /// Whether to mark descriptions and comments that span several lines or
+    /// start or end with whitespace with `xml:space="preserve"`, telling
+    /// other readers to keep their layout, such as of a table. Their text is
+    /// written as it is either way. The GPX schema allows no attributes on
+    /// these elements, so strict validators reject the marked files.
+    pub fn preserve_space(mut self, preserve: bool) -> WriteOptions {
+        self.preserve_space = preserve;
+        self
+    }
+
+    /// How to write the times of the metadata and of all points, such as
+    /// always with milliseconds, or in the offset they were read with.
+    /// Defaults to nine fractional digits in UTC.
+    ///
+    /// ```
+    /// use gpx::{FractionDigits, OffsetStyle, TimeFormat, WriteOptions};
+    ///
+    /// let options = WriteOptions::new().time_format(TimeFormat {
+    ///     fraction_digits: FractionDigits::Exactly(3),
+    ///     offset: OffsetStyle::Utc,
+    /// });
+    /// ```
+    pub fn time_format(mut self, format: TimeFormat) -> WriteOptions {
+        self.time_format = format;
+        self
+    }
+
+    /// Whether to put every element on a line of its own, indented by its
+    /// depth, and a space before the `/>` of empty elements. Defaults to
+    /// true.
+    pub fn indent(mut self, indent: bool) -> WriteOptions {
+        self.indent = indent;
+        self
+    }
+
+    /// Whether to start with `<?xml version="1.0" encoding="utf-8"?>`.
+    /// Defaults to true. Without it, readers take the document to be
+    /// UTF-8, which it always is.
+    pub fn xml_declaration(mut self, declaration: bool) -> WriteOptions {
+        self.xml_declaration = declaration;
+        self
+    }
+
+    /// Whether to leave out elements that tell a reader nothing it wouldn't
+    /// assume without them: `<metadata>` and `<extensions>` without anything
+    /// in them, and `<fix>none</fix>`. Reading the document back gives
+    /// `None` for these.
+    pub fn omit_defaults(mut self, omit: bool) -> WriteOptions {
+        self.omit_defaults = omit;
+        self
+    }
+
+    pub(crate) fn emitter_config(&self) -> EmitterConfig {
+        EmitterConfig::new()
+            .perform_indent(self.indent)
+            .pad_self_closing(self.indent)
+            .write_document_declaration(self.xml_declaration)
+    }
+
+    fn link_href<'a>(&self, href: &'a str) -> Cow<'a, str> {
+        if self.percent_encode_links {
+            percent_encode(href)
+        } else {
+            Cow::Borrowed(href)
+        }
+    }
+}
+
+/// Percent-encodes the bytes of `href` that may not appear in a URI. A `%`
+/// that does not start an escape is encoded as well.
+fn percent_encode(href: &str) -> Cow<'_, str> {
+    let bytes = href.as_bytes();
+    let escaped = |index: usize| {
+        bytes.get(index) == Some(&b'%')
+            && bytes.get(index + 1).is_some_and(u8::is_ascii_hexdigit)
+            && bytes.get(index + 2).is_some_and(u8::is_ascii_hexdigit)
+    };
+    if (0..bytes.len()).all(|index| is_uri_byte(bytes[index]) || escaped(index)) {
+        return Cow::Borrowed(href);
+    }
+    let mut encoded = String::with_capacity(href.len() + 8);
+    for (index, &byte) in bytes.iter().enumerate() {
+        if is_uri_byte(byte) || escaped(index) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+/// Writes an activity to GPX format, as tuned by `options`.
+///
+/// ```
+/// use gpx::{write_with_options, Gpx, GpxVersion, WriteOptions};
+///
+/// let mut data: Gpx = Default::default();
+/// data.version = GpxVersion::Gpx11;
+///
+/// let mut buffer = Vec::new();
+/// let options = WriteOptions::new().generated_by_comment(true);
+/// write_with_options(&data, &mut buffer, options).unwrap();
+/// assert!(String::from_utf8(buffer).unwrap().contains("<!-- Generated by gpx"));
+/// ```
+pub fn write_with_options<W: Write>(gpx: &Gpx, writer: W, options: WriteOptions) -> GpxResult<()> {
+    let mut writer = options.emitter_config().create_writer(writer);
+    write_declaration(&options, &mut writer)?;
+    write_document(gpx, &mut writer, &options)
+}
+
+/// Writes an activity to GPX format, into a newly allocated buffer.
+///
+/// The buffer is sized up front using [`Gpx::estimated_output_size`].
+///
+/// ```
+/// use gpx::{write_to_vec, Gpx, GpxVersion};
+///
+/// let mut data: Gpx = Default::default();
+/// data.version = GpxVersion::Gpx11;
+///
+/// let bytes = write_to_vec(&data).unwrap();
+/// assert!(bytes.starts_with(b"<?xml"));
+/// ```
+pub fn write_to_vec(gpx: &Gpx) -> GpxResult<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(gpx.estimated_output_size());
+    write(gpx, &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Writes an activity to GPX format, into a `String`.
+///
+/// See [`write_to_vec`].
+///
+/// ```
+/// use gpx::{write_to_string, Gpx, GpxVersion};
+///
+/// let mut data: Gpx = Default::default();
+/// data.version = GpxVersion::Gpx11;
+///
+/// let xml = write_to_string(&data).unwrap();
+/// assert!(xml.contains("version=\"1.1\""));
+/// ```
+pub fn write_to_string(gpx: &Gpx) -> GpxResult<String> {
+    let buffer = write_to_vec(gpx)?;
+    Ok(String::from_utf8(buffer).expect("the writer only emits UTF-8"))
 }
 
 /// Writes an activity to GPX format.
@@ -57,32 +323,405 @@ pub fn write<W: Write>(gpx: &Gpx, writer: W) -> GpxResult<()> {
 /// write_with_event_writer(&data, &mut writer).unwrap();
 /// ```
 pub fn write_with_event_writer<W: Write>(gpx: &Gpx, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    let creator: &str = gpx
-        .creator
-        .as_deref()
-        .unwrap_or("https://github.com/georust/gpx");
+    write_document(gpx, writer, &WriteOptions::default())
+}
+
+/// Writes a single track as a complete GPX document, without building a
+/// [`Gpx`] around it.
+///
+/// The document has no metadata and the given `creator`. The extensions of
+/// the track, its segments and its points are written as usual.
+///
+/// ```
+/// use gpx::{write_track, GpxVersion, Track};
+///
+/// let mut track = Track::new();
+/// track.name = Some("Day 1".into());
+///
+/// let mut buffer = Vec::new();
+/// write_track(&track, GpxVersion::Gpx11, "my app", &mut buffer).unwrap();
+/// assert!(String::from_utf8(buffer).unwrap().contains("<name>Day 1</name>"));
+/// ```
+pub fn write_track<W: Write>(
+    track: &Track,
+    version: GpxVersion,
+    creator: &str,
+    writer: W,
+) -> GpxResult<()> {
+    write_track_with_options(track, version, creator, writer, WriteOptions::default())
+}
+
+/// Writes a single track as a complete GPX document, with the given
+/// [`WriteOptions`], see [`write_track`].
+///
+/// ```
+/// use gpx::{write_track_with_options, GpxVersion, Track, WriteOptions};
+///
+/// let track = Track::new();
+///
+/// let mut buffer = Vec::new();
+/// let options = WriteOptions::new().generated_by_comment(true);
+/// write_track_with_options(&track, GpxVersion::Gpx11, "my app", &mut buffer, options).unwrap();
+/// assert!(String::from_utf8(buffer).unwrap().contains("<!-- Generated by gpx"));
+/// ```
+pub fn write_track_with_options<W: Write>(
+    track: &Track,
+    version: GpxVersion,
+    creator: &str,
+    writer: W,
+    options: WriteOptions,
+) -> GpxResult<()> {
+    write_standalone(
+        version,
+        creator,
+        extension_namespaces(track.all_extensions()),
+        writer,
+        &options,
+        |options, writer| write_track_element(track, version, options, writer),
+    )
+}
+
+/// Writes a single route as a complete GPX document, see [`write_track`].
+pub fn write_route<W: Write>(
+    route: &Route,
+    version: GpxVersion,
+    creator: &str,
+    writer: W,
+) -> GpxResult<()> {
+    write_route_with_options(route, version, creator, writer, WriteOptions::default())
+}
+
+/// Writes a single route as a complete GPX document, with the given
+/// [`WriteOptions`], see [`write_track`].
+pub fn write_route_with_options<W: Write>(
+    route: &Route,
+    version: GpxVersion,
+    creator: &str,
+    writer: W,
+    options: WriteOptions,
+) -> GpxResult<()> {
+    write_standalone(
+        version,
+        creator,
+        extension_namespaces(route.all_extensions()),
+        writer,
+        &options,
+        |options, writer| write_route_element(route, version, options, writer),
+    )
+}
+
+/// Writes waypoints as a complete GPX document, see [`write_track`].
+pub fn write_waypoints<W: Write>(
+    waypoints: &[Waypoint],
+    version: GpxVersion,
+    creator: &str,
+    writer: W,
+) -> GpxResult<()> {
+    write_waypoints_with_options(waypoints, version, creator, writer, WriteOptions::default())
+}
+
+/// Writes waypoints as a complete GPX document, with the given
+/// [`WriteOptions`], see [`write_track`].
+pub fn write_waypoints_with_options<W: Write>(
+    waypoints: &[Waypoint],
+    version: GpxVersion,
+    creator: &str,
+    writer: W,
+    options: WriteOptions,
+) -> GpxResult<()> {
+    write_standalone(
+        version,
+        creator,
+        extension_namespaces(
+            waypoints
+                .iter()
+                .filter_map(|waypoint| waypoint.extras().extensions.as_ref()),
+        ),
+        writer,
+        &options,
+        |options, writer| {
+            for waypoint in waypoints {
+                write_waypoint("wpt", waypoint, options, writer)?;
+            }
+            Ok(())
+        },
+    )
+}
+
+fn write_standalone<W: Write>(
+    version: GpxVersion,
+    creator: &str,
+    namespaces: BTreeMap<&str, &str>,
+    writer: W,
+    options: &WriteOptions,
+    content: impl FnOnce(&WriteOptions, &mut EventWriter<W>) -> GpxResult<()>,
+) -> GpxResult<()> {
+    let mut writer = options.emitter_config().create_writer(writer);
+    write_declaration(options, &mut writer)?;
+    write_generated_by_comment(options, &mut writer)?;
     write_xml_event(
-        XmlEvent::start_element("gpx")
-            .attr("version", version_to_version_string(gpx.version)?)
-            .attr("xmlns", version_to_xml_url(gpx.version)?)
-            .attr("creator", creator),
+        gpx_start_element(version, creator, &namespaces, options)?,
+        &mut writer,
+    )?;
+    content(options, &mut writer)?;
+    write_xml_event(XmlEvent::end_element(), &mut writer)?;
+    Ok(())
+}
+
+pub(crate) fn gpx_start_element<'a>(
+    version: GpxVersion,
+    creator: &'a str,
+    namespaces: &BTreeMap<&'a str, &'a str>,
+    options: &'a WriteOptions,
+) -> GpxResult<StartElementBuilder<'a>> {
+    let mut start = XmlEvent::start_element("gpx")
+        .default_ns(version_to_xml_url(version)?)
+        .attr("version", version_to_version_string(version)?)
+        .attr("creator", creator);
+    // Declare the prefixes of the extensions once, instead of on every
+    // extension element.
+    let mut namespaces = namespaces.clone();
+    for (prefix, namespace) in &options.namespaces {
+        namespaces.insert(prefix, namespace);
+    }
+    for (prefix, namespace) in namespaces {
+        start = start.ns(prefix, namespace);
+    }
+    Ok(start)
+}
+
+/// The creator written for documents that do not name one.
+pub(crate) const DEFAULT_CREATOR: &str = "https://github.com/georust/gpx";
+
+/// Writes the comment of [`WriteOptions::generated_by_comment`], if enabled.
+/// Writes the XML declaration up front, as the emitter only adds it on its
+/// own before elements and processing instructions, not before comments.
+pub(crate) fn write_declaration<W: Write>(
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    if options.xml_declaration {
+        write_xml_event(
+            XmlEvent::StartDocument {
+                version: XmlVersion::Version10,
+                encoding: Some("UTF-8"),
+                standalone: None,
+            },
+            writer,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_generated_by_comment<W: Write>(
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    if options.generated_by_comment {
+        let now = Time::from(OffsetDateTime::now_utc()).format()?;
+        let comment = format!(
+            " Generated by gpx {} at {} ",
+            env!("CARGO_PKG_VERSION"),
+            now
+        );
+        write_xml_event(XmlEvent::comment(&comment), writer)?;
+    }
+    Ok(())
+}
+
+fn write_document<W: Write>(
+    gpx: &Gpx,
+    writer: &mut EventWriter<W>,
+    options: &WriteOptions,
+) -> GpxResult<()> {
+    for node in &gpx.prolog {
+        match node {
+            PrologNode::Comment(text) => write_xml_event(XmlEvent::comment(text), writer)?,
+            PrologNode::ProcessingInstruction(instruction) => write_xml_event(
+                XmlEvent::processing_instruction(&instruction.name, instruction.data.as_deref()),
+                writer,
+            )?,
+        }
+    }
+    write_generated_by_comment(options, writer)?;
+    let creator: &str = gpx.creator.as_deref().unwrap_or(DEFAULT_CREATOR);
+    let namespaces = extension_namespaces(gpx.all_extensions().chain(&gpx.unknown_elements));
+    let start = gpx_start_element(gpx.version, creator, &namespaces, options)?;
+    write_xml_event(
+        with_unknown_attributes(start, "", &gpx.unknown_attributes),
         writer,
     )?;
-    write_metadata(gpx, writer)?;
+    write_metadata(gpx.version, gpx.metadata.as_ref(), options, writer)?;
     for point in &gpx.waypoints {
-        write_waypoint("wpt", point, writer)?;
+        write_waypoint("wpt", point, options, writer)?;
+    }
+    for route in &gpx.routes {
+        write_route_element(route, gpx.version, options, writer)?;
     }
     for track in &gpx.tracks {
-        write_track(track, writer)?;
+        write_track_element(track, gpx.version, options, writer)?;
     }
-    for route in &gpx.routes {
-        write_route(route, writer)?;
+    write_extensions_if_exists(&gpx.extensions, options, writer)?;
+    for element in &gpx.unknown_elements {
+        write_element(element, writer)?;
     }
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
 
-fn write_xml_event<'a, W, E>(event: E, writer: &mut EventWriter<W>) -> GpxResult<()>
+// Rough sizes of the written XML, in bytes, for estimated_output_size. They
+// include the indentation xml-rs adds and err on the large side, so that a
+// buffer of the estimated size rarely has to grow.
+const ESTIMATE_DOCUMENT: usize = 256;
+const ESTIMATE_CONTAINER: usize = 64;
+const ESTIMATE_POINT: usize = 72;
+const ESTIMATE_CLOSING_TAG: usize = 16;
+const ESTIMATE_VALUE: usize = 40;
+const ESTIMATE_TIME: usize = 48;
+
+impl Gpx {
+    /// Gives a cheap estimate of the number of bytes [`write()`] produces for
+    /// this document.
+    ///
+    /// The estimate is meant for pre-allocating buffers: it only looks at
+    /// which fields are set, not at how their values are formatted. For
+    /// typical documents it is close to, and usually slightly above, the
+    /// actual size.
+    pub fn estimated_output_size(&self) -> usize {
+        let metadata = self.metadata.as_ref().map_or(0, |metadata| {
+            ESTIMATE_CONTAINER * 4 + metadata.links.len() * ESTIMATE_CONTAINER
+        });
+        let waypoints: usize = self.waypoints.iter().map(estimated_waypoint_size).sum();
+        let routes: usize = self
+            .routes
+            .iter()
+            .map(|route| {
+                ESTIMATE_CONTAINER
+                    + estimated_strings_size(&[&route.name, &route.comment, &route.description])
+                    + route
+                        .points
+                        .iter()
+                        .map(estimated_waypoint_size)
+                        .sum::<usize>()
+            })
+            .sum();
+        let tracks: usize = self
+            .tracks
+            .iter()
+            .map(|track| {
+                estimated_track_size(track)
+                    + track
+                        .segments
+                        .iter()
+                        .map(|segment| {
+                            ESTIMATE_SEGMENT
+                                + segment
+                                    .points
+                                    .iter()
+                                    .map(estimated_waypoint_size)
+                                    .sum::<usize>()
+                        })
+                        .sum::<usize>()
+            })
+            .sum();
+        ESTIMATE_DOCUMENT + metadata + waypoints + routes + tracks
+    }
+}
+
+/// The estimated size of an empty `<trkseg>`.
+pub(crate) const ESTIMATE_SEGMENT: usize = ESTIMATE_CONTAINER;
+
+/// Gives the estimated size of `track` without its segments.
+pub(crate) fn estimated_track_size(track: &Track) -> usize {
+    ESTIMATE_CONTAINER + estimated_strings_size(&[&track.name, &track.comment, &track.description])
+}
+
+fn estimated_strings_size(strings: &[&Option<String>]) -> usize {
+    strings
+        .iter()
+        .filter_map(|string| string.as_ref())
+        .map(|string| ESTIMATE_VALUE + string.len())
+        .sum()
+}
+
+pub(crate) fn estimated_waypoint_size(waypoint: &Waypoint) -> usize {
+    let values = [
+        waypoint.elevation,
+        waypoint.extras().geoidheight,
+        waypoint.extras().hdop,
+        waypoint.extras().vdop,
+        waypoint.extras().pdop,
+        waypoint.extras().dgps_age,
+    ]
+    .iter()
+    .filter(|value| value.is_some())
+    .count()
+        + waypoint.extras().sat.is_some() as usize
+        + waypoint.extras().dgpsid.is_some() as usize
+        + waypoint.extras().fix.is_some() as usize;
+    let strings = estimated_strings_size(&[
+        &waypoint.name,
+        &waypoint.extras().comment,
+        &waypoint.extras().description,
+        &waypoint.extras().source,
+        &waypoint.extras().symbol,
+        &waypoint.extras()._type,
+    ]);
+    let time = if waypoint.time.is_some() {
+        ESTIMATE_TIME
+    } else {
+        0
+    };
+    let children = values * ESTIMATE_VALUE
+        + strings
+        + time
+        + waypoint.extras().links.len() * ESTIMATE_CONTAINER;
+    if children == 0 {
+        ESTIMATE_POINT
+    } else {
+        ESTIMATE_POINT + ESTIMATE_CLOSING_TAG + children
+    }
+}
+
+/// Adds the stashed attributes of `element` to the start tag, declaring the
+/// namespace prefixes they use.
+fn with_unknown_attributes<'a>(
+    mut start: StartElementBuilder<'a>,
+    element: &str,
+    unknown: &'a [UnknownAttributes],
+) -> StartElementBuilder<'a> {
+    let attributes = unknown
+        .iter()
+        .filter(|stashed| stashed.element == element)
+        .flat_map(|stashed| &stashed.attributes);
+    for attribute in attributes {
+        start = with_attribute(start, attribute);
+    }
+    start
+}
+
+/// Adds `attribute` to the start tag, declaring its namespace prefix.
+fn with_attribute<'a>(
+    start: StartElementBuilder<'a>,
+    attribute: &'a OwnedAttribute,
+) -> StartElementBuilder<'a> {
+    with_namespace(start, &attribute.name).attr(attribute.name.borrow(), &attribute.value)
+}
+
+/// Declares the namespace of `name` on the start tag. The writer leaves out
+/// declarations that are already in scope.
+pub(crate) fn with_namespace<'a>(
+    start: StartElementBuilder<'a>,
+    name: &'a OwnedName,
+) -> StartElementBuilder<'a> {
+    match (&name.prefix, &name.namespace) {
+        (Some(prefix), _) if prefix == "xml" => start,
+        (prefix, Some(namespace)) => start.ns(prefix.as_deref().unwrap_or(""), namespace.as_str()),
+        (_, None) => start,
+    }
+}
+
+pub(crate) fn write_xml_event<'a, W, E>(event: E, writer: &mut EventWriter<W>) -> GpxResult<()>
 where
     W: Write,
     E: Into<XmlEvent<'a>>,
@@ -99,63 +738,109 @@ fn version_to_version_string(version: GpxVersion) -> GpxResult<&'static str> {
 }
 
 fn version_to_xml_url(version: GpxVersion) -> GpxResult<&'static str> {
-    match version {
-        GpxVersion::Gpx10 => Ok("http://www.topografix.com/GPX/1/0"),
-        GpxVersion::Gpx11 => Ok("http://www.topografix.com/GPX/1/1"),
-        version => Err(GpxError::UnknownVersionError(version)),
-    }
+    version
+        .namespace_uri()
+        .ok_or(GpxError::UnknownVersionError(version))
 }
 
-fn write_metadata<W: Write>(gpx: &Gpx, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    match gpx.version {
-        GpxVersion::Gpx10 => write_gpx10_metadata(gpx, writer),
-        GpxVersion::Gpx11 => write_gpx11_metadata(gpx, writer),
-        version => Err(GpxError::UnknownVersionError(version)),
+pub(crate) fn write_metadata<W: Write>(
+    version: GpxVersion,
+    metadata: Option<&Metadata>,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    version_to_xml_url(version)?;
+    match metadata {
+        Some(metadata) if options.omit_defaults && is_blank_metadata(metadata) => Ok(()),
+        Some(metadata) if version.supports_metadata_element() => {
+            write_gpx11_metadata(metadata, options, writer)
+        }
+        Some(metadata) => write_gpx10_metadata(metadata, options, writer),
+        None => Ok(()),
     }
 }
 
-fn write_gpx10_metadata<W: Write>(gpx: &Gpx, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    if gpx.metadata.is_none() {
-        return Ok(());
-    }
-    let metadata = gpx.metadata.as_ref().unwrap();
-    write_string_if_exists("name", &metadata.name, writer)?;
-    write_string_if_exists("desc", &metadata.description, writer)?;
+/// Whether `metadata` holds nothing worth writing.
+fn is_blank_metadata(metadata: &Metadata) -> bool {
+    let Metadata {
+        name,
+        description,
+        author,
+        links,
+        time,
+        keywords,
+        copyright,
+        bounds,
+        extensions,
+        unknown_attributes,
+    } = metadata;
+    name.is_none()
+        && description.is_none()
+        && author.is_none()
+        && links.is_empty()
+        && time.is_none()
+        && keywords.is_none()
+        && copyright.is_none()
+        && bounds.is_none()
+        && extensions.as_ref().map_or(true, is_blank)
+        && unknown_attributes.is_empty()
+}
+
+fn write_gpx10_metadata<W: Write>(
+    metadata: &Metadata,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    let unknown = &metadata.unknown_attributes;
+    write_string_if_exists("name", &metadata.name, unknown, writer)?;
+    write_text_if_exists("desc", &metadata.description, unknown, options, writer)?;
     if let Some(author) = metadata.author.as_ref() {
-        write_string_if_exists("author", &author.name, writer)?;
+        write_string_if_exists("author", &author.name, &[], writer)?;
         write_email_if_exists(&author.email, writer)?;
         if let Some(link) = author.link.as_ref() {
-            write_string("url", &link.href, writer)?;
-            write_string_if_exists("urlname", &link.text, writer)?;
+            write_gpx10_url(link, &[], options, writer)?;
         }
     }
-    write_string_if_exists("keywords", &metadata.keywords, writer)?;
-    write_time_if_exists(&metadata.time, writer)?;
+    write_time_if_exists(&metadata.time, unknown, options, writer)?;
+    write_string_if_exists("keywords", &metadata.keywords, unknown, writer)?;
     write_bounds_if_exists(&metadata.bounds, writer)?;
     Ok(())
 }
 
-fn write_gpx11_metadata<W: Write>(gpx: &Gpx, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    if gpx.metadata.is_none() {
-        return Ok(());
-    }
-    let metadata = gpx.metadata.as_ref().unwrap();
-    write_xml_event(XmlEvent::start_element("metadata"), writer)?;
-    write_string_if_exists("name", &metadata.name, writer)?;
-    write_string_if_exists("desc", &metadata.description, writer)?;
-    write_person_if_exists("author", &metadata.author, writer)?;
-    write_string_if_exists("keywords", &metadata.keywords, writer)?;
-    write_time_if_exists(&metadata.time, writer)?;
+fn write_gpx11_metadata<W: Write>(
+    metadata: &Metadata,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    let unknown = &metadata.unknown_attributes;
+    write_xml_event(
+        with_unknown_attributes(XmlEvent::start_element("metadata"), "", unknown),
+        writer,
+    )?;
+    write_string_if_exists("name", &metadata.name, unknown, writer)?;
+    write_text_if_exists("desc", &metadata.description, unknown, options, writer)?;
+    write_person_if_exists("author", &metadata.author, options, writer)?;
     for link in &metadata.links {
-        write_link(link, writer)?;
+        write_link(link, options, writer)?;
     }
+    write_time_if_exists(&metadata.time, unknown, options, writer)?;
+    write_string_if_exists("keywords", &metadata.keywords, unknown, writer)?;
     write_bounds_if_exists(&metadata.bounds, writer)?;
+    write_extensions_if_exists(&metadata.extensions, options, writer)?;
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
 
-fn write_string<W: Write>(key: &str, value: &str, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    write_xml_event(XmlEvent::start_element(key), writer)?;
+fn write_string<W: Write>(
+    key: &str,
+    value: &str,
+    unknown: &[UnknownAttributes],
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    write_xml_event(
+        with_unknown_attributes(XmlEvent::start_element(key), key, unknown),
+        writer,
+    )?;
     write_xml_event(XmlEvent::characters(value), writer)?;
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
@@ -164,21 +849,64 @@ fn write_string<W: Write>(key: &str, value: &str, writer: &mut EventWriter<W>) -
 fn write_string_if_exists<W: Write>(
     key: &str,
     value: &Option<String>,
+    unknown: &[UnknownAttributes],
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
     if let Some(ref value) = value {
-        write_string(key, value, writer)?;
+        write_string(key, value, unknown, writer)?;
     }
     Ok(())
 }
 
+/// Writes a description or comment, which may need its layout kept, see
+/// [`WriteOptions::preserve_space`].
+fn write_text_if_exists<W: Write>(
+    key: &str,
+    value: &Option<String>,
+    unknown: &[UnknownAttributes],
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let mut start = with_unknown_attributes(XmlEvent::start_element(key), key, unknown);
+    let stashed = unknown
+        .iter()
+        .filter(|stashed| stashed.element == key)
+        .flat_map(|stashed| &stashed.attributes)
+        .any(|attribute| {
+            attribute.name.local_name.as_str() == "space"
+                && attribute.name.prefix.as_deref() == Some("xml")
+        });
+    if options.preserve_space && !stashed && has_layout(value) {
+        start = start.attr(Name::prefixed("space", "xml"), "preserve");
+    }
+    write_xml_event(start, writer)?;
+    write_xml_event(XmlEvent::characters(value), writer)?;
+    write_xml_event(XmlEvent::end_element(), writer)?;
+    Ok(())
+}
+
+/// Whether `text` has whitespace that a reader might take for mere layout.
+fn has_layout(text: &str) -> bool {
+    text.contains('\n')
+        || text.starts_with(char::is_whitespace)
+        || text.ends_with(char::is_whitespace)
+}
+
 fn write_value_if_exists<W: Write, T: ToString>(
     key: &str,
     value: &Option<T>,
+    unknown: &[UnknownAttributes],
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
     if let Some(ref value) = value {
-        write_xml_event(XmlEvent::start_element(key), writer)?;
+        write_xml_event(
+            with_unknown_attributes(XmlEvent::start_element(key), key, unknown),
+            writer,
+        )?;
         let value = &value.to_string();
         write_xml_event(XmlEvent::characters(value), writer)?;
         write_xml_event(XmlEvent::end_element(), writer)?;
@@ -186,6 +914,28 @@ fn write_value_if_exists<W: Write, T: ToString>(
     Ok(())
 }
 
+fn write_decimal_if_exists<W: Write>(
+    key: &'static str,
+    value: &Option<f64>,
+    unknown: &[UnknownAttributes],
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    if let Some(value) = *value {
+        write_string(key, &format_decimal(key, value)?, unknown, writer)?;
+    }
+    Ok(())
+}
+
+/// Formats the decimal `value` of `field`, refusing NaN and infinities,
+/// which the schema does not allow.
+///
+/// All decimals are written through here. `Display` gives the shortest
+/// decimal that parses back to the very same `f64`, so values survive a
+/// write and read unchanged, down to the last bit.
+fn format_decimal(field: &'static str, value: f64) -> GpxResult<String> {
+    Ok(validate_finite(field, value)?.to_string())
+}
+
 fn write_email_if_exists<W: Write>(
     email: &Option<String>,
     writer: &mut EventWriter<W>,
@@ -210,23 +960,58 @@ fn write_email_if_exists<W: Write>(
     Ok(())
 }
 
-fn write_link<W: Write>(link: &Link, writer: &mut EventWriter<W>) -> GpxResult<()> {
+fn write_link<W: Write>(
+    link: &Link,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
     write_xml_event(
-        XmlEvent::start_element("link").attr("href", &link.href),
+        XmlEvent::start_element("link").attr("href", &options.link_href(&link.href)),
         writer,
     )?;
-    write_string_if_exists("text", &link.text, writer)?;
-    write_string_if_exists("type", &link._type, writer)?;
+    write_string_if_exists("text", &link.text, &[], writer)?;
+    write_string_if_exists("type", &link._type, &[], writer)?;
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
 
+/// Writes `link` as the `<url>` and `<urlname>` of GPX 1.0.
+fn write_gpx10_url<W: Write>(
+    link: &Link,
+    unknown: &[UnknownAttributes],
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    write_string("url", &options.link_href(&link.href), unknown, writer)?;
+    write_string_if_exists("urlname", &link.text, unknown, writer)
+}
+
+/// Writes the links of a route or track, as `<link>` elements or, in GPX
+/// 1.0, which has room for only one, the first of them as `<url>` and
+/// `<urlname>`.
+fn write_container_links<W: Write>(
+    links: &[Link],
+    version: GpxVersion,
+    unknown: &[UnknownAttributes],
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    match (version, links.first()) {
+        (GpxVersion::Gpx10, Some(link)) => write_gpx10_url(link, unknown, options, writer),
+        (GpxVersion::Gpx10, None) => Ok(()),
+        _ => links
+            .iter()
+            .try_for_each(|link| write_link(link, options, writer)),
+    }
+}
+
 fn write_link_if_exists<W: Write>(
     link: &Option<Link>,
+    options: &WriteOptions,
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
     if let Some(ref link) = link {
-        write_link(link, writer)?;
+        write_link(link, options, writer)?;
     }
     Ok(())
 }
@@ -234,13 +1019,14 @@ fn write_link_if_exists<W: Write>(
 fn write_person_if_exists<W: Write>(
     key: &str,
     value: &Option<Person>,
+    options: &WriteOptions,
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
     if let Some(ref value) = value {
         write_xml_event(XmlEvent::start_element(key), writer)?;
-        write_string_if_exists("name", &value.name, writer)?;
+        write_string_if_exists("name", &value.name, &[], writer)?;
         write_email_if_exists(&value.email, writer)?;
-        write_link_if_exists(&value.link, writer)?;
+        write_link_if_exists(&value.link, options, writer)?;
         write_xml_event(XmlEvent::end_element(), writer)?;
     }
     Ok(())
@@ -248,11 +1034,19 @@ fn write_person_if_exists<W: Write>(
 
 fn write_time_if_exists<W: Write>(
     time: &Option<Time>,
+    unknown: &[UnknownAttributes],
+    options: &WriteOptions,
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
     if let Some(ref time) = time {
-        write_xml_event(XmlEvent::start_element("time"), writer)?;
-        write_xml_event(XmlEvent::characters(&time.format()?), writer)?;
+        write_xml_event(
+            with_unknown_attributes(XmlEvent::start_element("time"), "time", unknown),
+            writer,
+        )?;
+        write_xml_event(
+            XmlEvent::characters(&time.format_with(&options.time_format)),
+            writer,
+        )?;
         write_xml_event(XmlEvent::end_element(), writer)?;
     }
     Ok(())
@@ -265,10 +1059,10 @@ fn write_bounds_if_exists<W: Write>(
     if let Some(ref bounds) = bounds {
         write_xml_event(
             XmlEvent::start_element("bounds")
-                .attr("minlat", &bounds.min().y.to_string())
-                .attr("maxlat", &bounds.max().y.to_string())
-                .attr("minlon", &bounds.min().x.to_string())
-                .attr("maxlon", &bounds.max().x.to_string()),
+                .attr("minlat", &format_decimal("minlat", bounds.min().y)?)
+                .attr("maxlat", &format_decimal("maxlat", bounds.max().y)?)
+                .attr("minlon", &format_decimal("minlon", bounds.min().x)?)
+                .attr("maxlon", &format_decimal("maxlon", bounds.max().x)?),
             writer,
         )?;
         write_xml_event(XmlEvent::end_element(), writer)?;
@@ -276,9 +1070,20 @@ fn write_bounds_if_exists<W: Write>(
     Ok(())
 }
 
-fn write_fix_if_exists<W: Write>(fix: &Option<Fix>, writer: &mut EventWriter<W>) -> GpxResult<()> {
+fn write_fix_if_exists<W: Write>(
+    fix: &Option<Fix>,
+    unknown: &[UnknownAttributes],
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    if options.omit_defaults && fix == &Some(Fix::None) {
+        return Ok(());
+    }
     if let Some(ref fix) = fix {
-        write_xml_event(XmlEvent::start_element("fix"), writer)?;
+        write_xml_event(
+            with_unknown_attributes(XmlEvent::start_element("fix"), "fix", unknown),
+            writer,
+        )?;
         let fix_str = match fix {
             Fix::None => "none",
             Fix::TwoDimensional => "2d",
@@ -293,84 +1098,262 @@ fn write_fix_if_exists<W: Write>(fix: &Option<Fix>, writer: &mut EventWriter<W>)
     Ok(())
 }
 
-fn write_track<W: Write>(track: &Track, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    write_xml_event(XmlEvent::start_element("trk"), writer)?;
-    write_string_if_exists("name", &track.name, writer)?;
-    write_string_if_exists("cmt", &track.comment, writer)?;
-    write_string_if_exists("desc", &track.description, writer)?;
-    write_string_if_exists("src", &track.source, writer)?;
-    for link in &track.links {
-        write_link(link, writer)?;
-    }
-    write_string_if_exists("type", &track._type, writer)?;
+fn write_track_element<W: Write>(
+    track: &Track,
+    version: GpxVersion,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    write_track_start(track, version, options, writer)?;
     for segment in &track.segments {
-        write_track_segment(segment, writer)?;
+        write_track_segment(segment, options, writer)?;
     }
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
 
-fn write_route<W: Write>(route: &Route, writer: &mut EventWriter<W>) -> GpxResult<()> {
-    write_xml_event(XmlEvent::start_element("rte"), writer)?;
-    write_string_if_exists("name", &route.name, writer)?;
-    write_string_if_exists("cmt", &route.comment, writer)?;
-    write_string_if_exists("desc", &route.description, writer)?;
-    write_string_if_exists("src", &route.source, writer)?;
-    for link in &route.links {
-        write_link(link, writer)?;
-    }
-    write_value_if_exists("number", &route.number, writer)?;
-    write_string_if_exists("type", &route._type, writer)?;
+/// Writes the start tag of `track` and its children other than the
+/// segments.
+pub(crate) fn write_track_start<W: Write>(
+    track: &Track,
+    version: GpxVersion,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    let unknown = &track.unknown_attributes;
+    write_xml_event(
+        with_unknown_attributes(XmlEvent::start_element("trk"), "", unknown),
+        writer,
+    )?;
+    write_string_if_exists("name", &track.name, unknown, writer)?;
+    write_text_if_exists("cmt", &track.comment, unknown, options, writer)?;
+    write_text_if_exists("desc", &track.description, unknown, options, writer)?;
+    write_string_if_exists("src", &track.source, unknown, writer)?;
+    write_container_links(&track.links, version, unknown, options, writer)?;
+    write_value_if_exists("number", &track.number, unknown, writer)?;
+    write_string_if_exists("type", &track._type, unknown, writer)?;
+    write_extensions_if_exists(&track.extensions, options, writer)?;
+    Ok(())
+}
+
+fn write_route_element<W: Write>(
+    route: &Route,
+    version: GpxVersion,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    write_route_start(route, version, options, writer)?;
     for point in &route.points {
-        write_waypoint("rtept", point, writer)?;
+        write_waypoint("rtept", point, options, writer)?;
     }
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
 
-fn write_track_segment<W: Write>(
+/// Writes the start tag of `route` and its children other than the points.
+pub(crate) fn write_route_start<W: Write>(
+    route: &Route,
+    version: GpxVersion,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    let unknown = &route.unknown_attributes;
+    write_xml_event(
+        with_unknown_attributes(XmlEvent::start_element("rte"), "", unknown),
+        writer,
+    )?;
+    write_string_if_exists("name", &route.name, unknown, writer)?;
+    write_text_if_exists("cmt", &route.comment, unknown, options, writer)?;
+    write_text_if_exists("desc", &route.description, unknown, options, writer)?;
+    write_string_if_exists("src", &route.source, unknown, writer)?;
+    write_container_links(&route.links, version, unknown, options, writer)?;
+    write_value_if_exists("number", &route.number, unknown, writer)?;
+    write_string_if_exists("type", &route._type, unknown, writer)?;
+    write_extensions_if_exists(&route.extensions, options, writer)?;
+    Ok(())
+}
+
+pub(crate) fn write_track_segment<W: Write>(
     segment: &TrackSegment,
+    options: &WriteOptions,
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
     write_xml_event(XmlEvent::start_element("trkseg"), writer)?;
     for point in &segment.points {
-        write_waypoint("trkpt", point, writer)?;
+        write_waypoint("trkpt", point, options, writer)?;
     }
+    write_extensions_if_exists(&segment.extensions, options, writer)?;
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
 
-fn write_waypoint<W: Write>(
+pub(crate) fn write_waypoint<W: Write>(
     tagname: &str,
     waypoint: &Waypoint,
+    options: &WriteOptions,
     writer: &mut EventWriter<W>,
 ) -> GpxResult<()> {
+    let unknown = &waypoint.extras().unknown_attributes;
     write_xml_event(
-        XmlEvent::start_element(tagname)
-            .attr("lat", &waypoint.point().y().to_string())
-            .attr("lon", &waypoint.point().x().to_string()),
+        with_unknown_attributes(
+            XmlEvent::start_element(tagname)
+                .attr("lat", &format_decimal("lat", waypoint.lat())?)
+                .attr("lon", &format_decimal("lon", waypoint.lon())?),
+            "",
+            unknown,
+        ),
         writer,
     )?;
-    write_value_if_exists("ele", &waypoint.elevation, writer)?;
+    write_decimal_if_exists("ele", &waypoint.elevation, unknown, writer)?;
     // TODO: write speed if GPX version == 1.0
-    write_time_if_exists(&waypoint.time, writer)?;
-    write_value_if_exists("geoidheight", &waypoint.geoidheight, writer)?;
-    write_string_if_exists("name", &waypoint.name, writer)?;
-    write_string_if_exists("cmt", &waypoint.comment, writer)?;
-    write_string_if_exists("desc", &waypoint.description, writer)?;
-    write_string_if_exists("src", &waypoint.source, writer)?;
-    for link in &waypoint.links {
-        write_link(link, writer)?;
-    }
-    write_string_if_exists("sym", &waypoint.symbol, writer)?;
-    write_string_if_exists("type", &waypoint._type, writer)?;
-    write_fix_if_exists(&waypoint.fix, writer)?;
-    write_value_if_exists("sat", &waypoint.sat, writer)?;
-    write_value_if_exists("hdop", &waypoint.hdop, writer)?;
-    write_value_if_exists("vdop", &waypoint.vdop, writer)?;
-    write_value_if_exists("pdop", &waypoint.pdop, writer)?;
-    write_value_if_exists("ageofdgpsdata", &waypoint.dgps_age, writer)?;
-    write_value_if_exists("dgpsid", &waypoint.dgpsid, writer)?;
+    write_time_if_exists(&waypoint.time, unknown, options, writer)?;
+    write_decimal_if_exists(
+        "geoidheight",
+        &waypoint.extras().geoidheight,
+        unknown,
+        writer,
+    )?;
+    write_string_if_exists("name", &waypoint.name, unknown, writer)?;
+    write_text_if_exists("cmt", &waypoint.extras().comment, unknown, options, writer)?;
+    write_text_if_exists(
+        "desc",
+        &waypoint.extras().description,
+        unknown,
+        options,
+        writer,
+    )?;
+    write_string_if_exists("src", &waypoint.extras().source, unknown, writer)?;
+    for link in &waypoint.extras().links {
+        write_link(link, options, writer)?;
+    }
+    write_string_if_exists("sym", &waypoint.extras().symbol, unknown, writer)?;
+    write_string_if_exists("type", &waypoint.extras()._type, unknown, writer)?;
+    write_fix_if_exists(&waypoint.extras().fix, unknown, options, writer)?;
+    write_value_if_exists("sat", &waypoint.extras().sat, unknown, writer)?;
+    write_decimal_if_exists("hdop", &waypoint.extras().hdop, unknown, writer)?;
+    write_decimal_if_exists("vdop", &waypoint.extras().vdop, unknown, writer)?;
+    write_decimal_if_exists("pdop", &waypoint.extras().pdop, unknown, writer)?;
+    write_decimal_if_exists(
+        "ageofdgpsdata",
+        &waypoint.extras().dgps_age,
+        unknown,
+        writer,
+    )?;
+    write_value_if_exists("dgpsid", &waypoint.extras().dgpsid, unknown, writer)?;
+    match &options.point_extensions {
+        Some(hook) => {
+            let kind = match tagname {
+                "rtept" => PointKind::RoutePoint,
+                "trkpt" => PointKind::TrackPoint,
+                _ => PointKind::Waypoint,
+            };
+            write_point_extensions(waypoint, kind, hook, writer)?;
+        }
+        None => write_extensions_if_exists(&waypoint.extras().extensions, options, writer)?,
+    }
+    write_xml_event(XmlEvent::end_element(), writer)?;
+    Ok(())
+}
+
+/// Collects the prefixes used in `elements`, with the namespace each is
+/// first used for.
+fn extension_namespaces<'a>(
+    elements: impl IntoIterator<Item = &'a Element>,
+) -> BTreeMap<&'a str, &'a str> {
+    let mut namespaces = BTreeMap::new();
+    for element in elements {
+        visit_bindings(element, &mut |prefix, namespace| {
+            namespaces.entry(prefix).or_insert(namespace);
+        });
+    }
+    namespaces
+}
+
+fn write_extensions_if_exists<W: Write>(
+    extensions: &Option<Element>,
+    options: &WriteOptions,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    match extensions {
+        Some(extensions) if options.omit_defaults && is_blank(extensions) => {}
+        Some(extensions) => write_element(extensions, writer)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Whether `element` has no attributes and nothing but whitespace in it.
+fn is_blank(element: &Element) -> bool {
+    element.attributes.is_empty()
+        && element.children.iter().all(|child| match child {
+            Node::Text(text) => text.trim().is_empty(),
+            Node::Element(_) => false,
+        })
+}
+
+pub(crate) fn write_element<W: Write>(
+    element: &Element,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    write_element_open(element, writer)?;
     write_xml_event(XmlEvent::end_element(), writer)?;
     Ok(())
 }
+
+/// Writes `element` with its content, but leaves it open.
+pub(crate) fn write_element_open<W: Write>(
+    element: &Element,
+    writer: &mut EventWriter<W>,
+) -> GpxResult<()> {
+    write_start_tag(element, writer)?;
+    // The children left to write of `element` and of the elements open
+    // inside it, and whether these keep their whitespace. Keeping them here
+    // rather than recursing lets deeply nested extensions be written without
+    // running out of stack.
+    let preserve = element.xml_space().unwrap_or(false);
+    let mut open = vec![(element.children.iter(), preserve)];
+    if preserve && !element.children.is_empty() {
+        keep_layout(writer)?;
+    }
+    while let Some((children, preserve)) = open.last_mut() {
+        let preserve = *preserve;
+        match children.next() {
+            Some(Node::Element(child)) => {
+                write_start_tag(child, writer)?;
+                let preserve = child.xml_space().unwrap_or(preserve);
+                if preserve && !child.children.is_empty() {
+                    keep_layout(writer)?;
+                }
+                open.push((child.children.iter(), preserve));
+            }
+            Some(Node::Text(text)) => write_xml_event(XmlEvent::characters(text), writer)?,
+            None => {
+                open.pop();
+                if let Some((_, parent_preserves)) = open.last() {
+                    write_xml_event(XmlEvent::end_element(), writer)?;
+                    if *parent_preserves {
+                        keep_layout(writer)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keeps the writer from indenting the next tag inside the open element, as
+/// it never indents a tag that follows text.
+fn keep_layout<W: Write>(writer: &mut EventWriter<W>) -> GpxResult<()> {
+    write_xml_event(XmlEvent::characters(""), writer)
+}
+
+fn write_start_tag<W: Write>(element: &Element, writer: &mut EventWriter<W>) -> GpxResult<()> {
+    let mut start = with_namespace(
+        XmlEvent::start_element(element.name.borrow()),
+        &element.name,
+    );
+    for attribute in &element.attributes {
+        start = with_attribute(start, attribute);
+    }
+    write_xml_event(start, writer)
+}
@@ -0,0 +1,974 @@
+//! xsd checks raw XML against the GPX schemas, if the `xsd-validation`
+//! feature is enabled.
+//!
+//! The schemas are bundled and interpreted as far as the GPX schemas need:
+//! sequences of elements with their number of occurrences, `xsd:any`
+//! extension points, attributes with their `use` and `fixed` values, and
+//! simple types restricted by ranges and enumerations. Patterns, which only
+//! GPX 1.0 uses for email addresses, are not checked.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+
+use xml::attribute::OwnedAttribute;
+use xml::common::Position;
+use xml::name::OwnedName;
+use xml::reader::{EventReader, ParserConfig, XmlEvent};
+
+use crate::positions::SourcePosition;
+
+/// The GPX 1.0 schema, without its documentation.
+pub const GPX_1_0_XSD: &str = include_str!("../schemas/gpx-1.0.xsd");
+
+/// The GPX 1.1 schema, without its documentation.
+pub const GPX_1_1_XSD: &str = include_str!("../schemas/gpx-1.1.xsd");
+
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// A place where a document does not follow the GPX schema, as found by
+/// [`validate_xml`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XsdViolation {
+    /// What is wrong.
+    pub message: String,
+
+    /// Where the offending element starts.
+    pub position: SourcePosition,
+}
+
+impl fmt::Display for XsdViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.position.line, self.position.column, self.message
+        )
+    }
+}
+
+/// Checks the XML from `reader` against the GPX schema of the namespace of
+/// its root element, GPX 1.0 or 1.1, and gives all violations found.
+///
+/// This looks at the XML itself, independently of [`read`](crate::read),
+/// which is more lenient in some places and stricter in others. Malformed
+/// XML ends the check with a violation describing the syntax error.
+///
+/// ```
+/// use gpx::validate_xml;
+///
+/// let xml = r#"<gpx xmlns="http://www.topografix.com/GPX/1/1" version="1.1" creator="me">
+///   <wpt lat="47.1" lon="200"/>
+/// </gpx>"#;
+///
+/// let violations = validate_xml(xml.as_bytes()).unwrap_err();
+/// assert_eq!(violations.len(), 1);
+/// assert_eq!(violations[0].position.line, 2);
+/// ```
+pub fn validate_xml<R: Read>(reader: R) -> Result<(), Vec<XsdViolation>> {
+    let config = ParserConfig {
+        whitespace_to_characters: true,
+        cdata_to_characters: true,
+        ..ParserConfig::new()
+    };
+    let mut events = EventReader::new_with_config(reader, config);
+    let schemas = [Schema::parse(GPX_1_0_XSD), Schema::parse(GPX_1_1_XSD)];
+    let mut validator = Validator {
+        schemas: &schemas,
+        schema: None,
+        frames: Vec::new(),
+        violations: Vec::new(),
+    };
+    loop {
+        let event = events.next();
+        let position = SourcePosition::from(events.position());
+        match event {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => validator.start_element(name, &attributes, position),
+            Ok(XmlEvent::EndElement { .. }) => validator.end_element(),
+            Ok(XmlEvent::Characters(text)) => validator.characters(&text, position),
+            Ok(XmlEvent::EndDocument) => break,
+            Ok(_) => {}
+            Err(error) => {
+                validator.report(position, error.msg().to_string());
+                break;
+            }
+        }
+    }
+    if validator.violations.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.violations)
+    }
+}
+
+/// The built-in simple types the GPX schemas use.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Builtin {
+    String,
+    AnyUri,
+    Decimal,
+    Integer,
+    NonNegativeInteger,
+    DateTime,
+    GYear,
+}
+
+impl Builtin {
+    fn from_name(name: &str) -> Option<Builtin> {
+        Some(match name.strip_prefix("xsd:")? {
+            "string" => Builtin::String,
+            "anyURI" => Builtin::AnyUri,
+            "decimal" => Builtin::Decimal,
+            "integer" => Builtin::Integer,
+            "nonNegativeInteger" => Builtin::NonNegativeInteger,
+            "dateTime" => Builtin::DateTime,
+            "gYear" => Builtin::GYear,
+            _ => return None,
+        })
+    }
+
+    /// Checks the lexical form of `value`, giving what it should be if it
+    /// is wrong.
+    fn check(self, value: &str) -> Result<(), &'static str> {
+        let trimmed = value.trim();
+        let valid = match self {
+            Builtin::String | Builtin::AnyUri => return Ok(()),
+            Builtin::Decimal => is_decimal(trimmed),
+            Builtin::Integer => is_integer(trimmed),
+            Builtin::NonNegativeInteger => {
+                is_integer(trimmed) && (!trimmed.starts_with('-') || is_zero(trimmed))
+            }
+            Builtin::DateTime => is_date_time(trimmed),
+            Builtin::GYear => is_year(trimmed),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(match self {
+                Builtin::Decimal => "a decimal number",
+                Builtin::Integer => "an integer",
+                Builtin::NonNegativeInteger => "a non-negative integer",
+                Builtin::DateTime => "a date and time like 2001-02-03T04:05:06Z",
+                _ => "a year",
+            })
+        }
+    }
+}
+
+/// A simple type restricting a built-in type.
+#[derive(Clone, Debug)]
+struct SimpleType {
+    base: Builtin,
+    min_inclusive: Option<f64>,
+    max_inclusive: Option<f64>,
+    max_exclusive: Option<f64>,
+    enumeration: Vec<String>,
+}
+
+impl SimpleType {
+    fn builtin(base: Builtin) -> SimpleType {
+        SimpleType {
+            base,
+            min_inclusive: None,
+            max_inclusive: None,
+            max_exclusive: None,
+            enumeration: Vec::new(),
+        }
+    }
+
+    fn check(&self, value: &str) -> Result<(), String> {
+        self.base
+            .check(value)
+            .map_err(|expected| format!("must be {}", expected))?;
+        if !self.enumeration.is_empty() && !self.enumeration.iter().any(|item| item == value) {
+            return Err(format!("must be one of {}", self.enumeration.join(", ")));
+        }
+        if let Ok(number) = value.trim().parse::<f64>() {
+            if let Some(min) = self.min_inclusive.filter(|&min| number < min) {
+                return Err(format!("must be at least {}", min));
+            }
+            if let Some(max) = self.max_inclusive.filter(|&max| number > max) {
+                return Err(format!("must be at most {}", max));
+            }
+            if let Some(max) = self.max_exclusive.filter(|&max| number >= max) {
+                return Err(format!("must be less than {}", max));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The type of an element or attribute, as referenced in the schema.
+#[derive(Debug)]
+enum TypeRef {
+    Named(String),
+    Anonymous(ComplexType),
+}
+
+#[derive(Debug)]
+struct AttributeDecl {
+    name: String,
+    type_ref: String,
+    required: bool,
+    fixed: Option<String>,
+}
+
+#[derive(Debug)]
+enum Term {
+    Element { name: String, type_ref: TypeRef },
+    Any,
+}
+
+#[derive(Debug)]
+struct Particle {
+    term: Term,
+    min: u32,
+    max: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+struct ComplexType {
+    particles: Vec<Particle>,
+    attributes: Vec<AttributeDecl>,
+}
+
+enum TypeDef {
+    Simple(SimpleType),
+    Complex(ComplexType),
+}
+
+/// A GPX schema, read from one of the bundled XSDs.
+struct Schema {
+    namespace: String,
+    root: TypeRef,
+    types: HashMap<String, TypeDef>,
+}
+
+impl Schema {
+    fn parse(xsd: &str) -> Schema {
+        let root = XsdElement::parse(xsd);
+        let mut schema = Schema {
+            namespace: root.attribute("targetNamespace").unwrap_or("").into(),
+            root: TypeRef::Named(String::new()),
+            types: HashMap::new(),
+        };
+        for child in &root.children {
+            let name = child.attribute("name").unwrap_or("").to_string();
+            match child.name.as_str() {
+                "element" => schema.root = element_type(child),
+                "complexType" => {
+                    schema
+                        .types
+                        .insert(name, TypeDef::Complex(complex_type(child)));
+                }
+                "simpleType" => {
+                    schema
+                        .types
+                        .insert(name, TypeDef::Simple(simple_type(child)));
+                }
+                _ => {}
+            }
+        }
+        schema
+    }
+
+    fn resolve<'a>(&'a self, type_ref: &'a TypeRef) -> Option<Resolved<'a>> {
+        match type_ref {
+            TypeRef::Anonymous(complex) => Some(Resolved::Complex(complex)),
+            TypeRef::Named(name) => self.resolve_name(name),
+        }
+    }
+
+    fn resolve_name<'a>(&'a self, name: &str) -> Option<Resolved<'a>> {
+        if let Some(builtin) = Builtin::from_name(name) {
+            return Some(Resolved::Simple(SimpleType::builtin(builtin)));
+        }
+        match self.types.get(name)? {
+            TypeDef::Simple(simple) => Some(Resolved::Simple(simple.clone())),
+            TypeDef::Complex(complex) => Some(Resolved::Complex(complex)),
+        }
+    }
+}
+
+enum Resolved<'a> {
+    Simple(SimpleType),
+    Complex(&'a ComplexType),
+}
+
+fn element_type(element: &XsdElement) -> TypeRef {
+    match element.attribute("type") {
+        Some(name) => TypeRef::Named(name.into()),
+        None => TypeRef::Anonymous(
+            element
+                .child("complexType")
+                .map(complex_type)
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+fn complex_type(element: &XsdElement) -> ComplexType {
+    let mut complex = ComplexType::default();
+    if let Some(sequence) = element.child("sequence") {
+        for child in &sequence.children {
+            let term = match child.name.as_str() {
+                "element" => Term::Element {
+                    name: child.attribute("name").unwrap_or("").into(),
+                    type_ref: element_type(child),
+                },
+                "any" => Term::Any,
+                _ => continue,
+            };
+            let min = child
+                .attribute("minOccurs")
+                .map_or(1, |min| min.parse().unwrap_or(1));
+            let max = match child.attribute("maxOccurs") {
+                Some("unbounded") => None,
+                Some(max) => Some(max.parse().unwrap_or(1)),
+                None => Some(1),
+            };
+            complex.particles.push(Particle { term, min, max });
+        }
+    }
+    for attribute in element.children.iter().filter(|c| c.name == "attribute") {
+        complex.attributes.push(AttributeDecl {
+            name: attribute.attribute("name").unwrap_or("").into(),
+            type_ref: attribute.attribute("type").unwrap_or("xsd:string").into(),
+            required: attribute.attribute("use") == Some("required"),
+            fixed: attribute.attribute("fixed").map(String::from),
+        });
+    }
+    complex
+}
+
+fn simple_type(element: &XsdElement) -> SimpleType {
+    let restriction = element.child("restriction");
+    let base = restriction
+        .and_then(|restriction| restriction.attribute("base"))
+        .and_then(Builtin::from_name)
+        .unwrap_or(Builtin::String);
+    let mut simple = SimpleType::builtin(base);
+    for facet in restriction.iter().flat_map(|r| r.children.iter()) {
+        let value = facet.attribute("value").unwrap_or("");
+        match facet.name.as_str() {
+            "minInclusive" => simple.min_inclusive = value.parse().ok(),
+            "maxInclusive" => simple.max_inclusive = value.parse().ok(),
+            "maxExclusive" => simple.max_exclusive = value.parse().ok(),
+            "enumeration" => simple.enumeration.push(value.into()),
+            _ => {}
+        }
+    }
+    simple
+}
+
+/// An element of the schema document, with just what interpreting it needs.
+struct XsdElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XsdElement>,
+}
+
+impl XsdElement {
+    fn parse(xsd: &str) -> XsdElement {
+        let mut open: Vec<XsdElement> = Vec::new();
+        for event in EventReader::from_str(xsd) {
+            match event.expect("the bundled schemas are well-formed") {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => open.push(XsdElement {
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|attribute| (attribute.name.local_name, attribute.value))
+                        .collect(),
+                    children: Vec::new(),
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = open.pop().expect("the bundled schemas are well-formed");
+                    match open.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return element,
+                    }
+                }
+                _ => {}
+            }
+        }
+        unreachable!("the bundled schemas are well-formed")
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&XsdElement> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// What is known about an open element of the document being checked.
+enum Frame<'a> {
+    Complex {
+        name: String,
+        complex: &'a ComplexType,
+        /// The particle the last child matched, and how often it did so in a
+        /// row.
+        particle: usize,
+        count: u32,
+        position: SourcePosition,
+    },
+    Simple {
+        name: String,
+        simple: SimpleType,
+        text: String,
+        position: SourcePosition,
+    },
+    /// An element whose content is not checked, such as an extension or an
+    /// element that was already reported.
+    Skip,
+}
+
+struct Validator<'a> {
+    schemas: &'a [Schema],
+    schema: Option<&'a Schema>,
+    frames: Vec<Frame<'a>>,
+    violations: Vec<XsdViolation>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, position: SourcePosition, message: String) {
+        self.violations.push(XsdViolation { message, position });
+    }
+
+    fn start_element(
+        &mut self,
+        name: OwnedName,
+        attributes: &[OwnedAttribute],
+        position: SourcePosition,
+    ) {
+        let frame = match (self.schema, self.frames.last_mut()) {
+            (None, None) => self.start_root(&name, attributes, position),
+            (Some(schema), Some(Frame::Complex { .. })) => {
+                self.start_child(schema, &name, attributes, position)
+            }
+            (_, Some(Frame::Simple { name: parent, .. })) => {
+                let message = format!(
+                    "`{}` is not allowed in `{}`, which only holds text",
+                    name.local_name, parent
+                );
+                self.report(position, message);
+                Frame::Skip
+            }
+            _ => Frame::Skip,
+        };
+        self.frames.push(frame);
+    }
+
+    fn start_root(
+        &mut self,
+        name: &OwnedName,
+        attributes: &[OwnedAttribute],
+        position: SourcePosition,
+    ) -> Frame<'a> {
+        let schemas = self.schemas;
+        let schema = schemas
+            .iter()
+            .find(|schema| name.namespace.as_deref() == Some(schema.namespace.as_str()));
+        let schema = match schema {
+            Some(schema) if name.local_name == "gpx" => schema,
+            _ => {
+                let message = match name.namespace.as_deref() {
+                    _ if name.local_name != "gpx" => {
+                        format!("the root element must be `gpx`, not `{}`", qualified(name))
+                    }
+                    Some(namespace) => format!(
+                        "`gpx` must be in the GPX 1.0 or 1.1 namespace, not `{}`",
+                        namespace
+                    ),
+                    None => "`gpx` must be in the GPX 1.0 or 1.1 namespace".to_string(),
+                };
+                self.report(position, message);
+                return Frame::Skip;
+            }
+        };
+        self.schema = Some(schema);
+        self.element_frame(schema, &schema.root, name, attributes, position)
+    }
+
+    fn start_child(
+        &mut self,
+        schema: &'a Schema,
+        name: &OwnedName,
+        attributes: &[OwnedAttribute],
+        position: SourcePosition,
+    ) -> Frame<'a> {
+        let (parent, complex, particle, count) = match self.frames.last_mut() {
+            Some(Frame::Complex {
+                name,
+                complex,
+                particle,
+                count,
+                ..
+            }) => (name.clone(), *complex, particle, count),
+            _ => return Frame::Skip,
+        };
+        let matches = |index: usize| -> bool {
+            match &complex.particles[index].term {
+                Term::Element { name: element, .. } => {
+                    &name.local_name == element
+                        && name.namespace.as_deref() == Some(schema.namespace.as_str())
+                }
+                Term::Any => name.namespace.as_deref() != Some(schema.namespace.as_str()),
+            }
+        };
+
+        let current = *particle;
+        let found = if current < complex.particles.len() && matches(current) {
+            Some(current)
+        } else {
+            (current + 1..complex.particles.len()).find(|&index| matches(index))
+        };
+        let message = match found {
+            Some(index) if index == current => {
+                let max = complex.particles[index].max;
+                if max.map_or(true, |max| *count < max) {
+                    *count += 1;
+                    None
+                } else {
+                    Some(match max {
+                        Some(1) => {
+                            format!("`{}` may appear only once in `{}`", name.local_name, parent)
+                        }
+                        _ => format!(
+                            "`{}` may appear at most {} times in `{}`",
+                            name.local_name,
+                            max.unwrap_or_default(),
+                            parent
+                        ),
+                    })
+                }
+            }
+            Some(index) => {
+                let skipped = if *count == 0 { current } else { current + 1 };
+                *particle = index;
+                *count = 1;
+                missing(complex, skipped..index).map(|missing| {
+                    format!(
+                        "`{}` is missing `{}` before `{}`",
+                        parent, missing, name.local_name
+                    )
+                })
+            }
+            None => {
+                let earlier = (0..current).any(matches);
+                match &complex.particles.get(current).map(|p| &p.term) {
+                    Some(Term::Element { name: after, .. }) if earlier => Some(format!(
+                        "`{}` must come before `{}` in `{}`",
+                        name.local_name, after, parent
+                    )),
+                    _ => Some(format!(
+                        "`{}` is not allowed in `{}`",
+                        name.local_name, parent
+                    )),
+                }
+            }
+        };
+        if found.is_none() {
+            self.report(position, message.unwrap_or_default());
+            return Frame::Skip;
+        }
+        if let Some(message) = message {
+            self.report(position, message);
+        }
+        match &complex.particles[found.unwrap_or_default()].term {
+            Term::Element { type_ref, .. } => {
+                self.element_frame(schema, type_ref, name, attributes, position)
+            }
+            Term::Any => Frame::Skip,
+        }
+    }
+
+    /// Checks the attributes of an element of the given type, and gives the
+    /// frame to check its content with.
+    fn element_frame(
+        &mut self,
+        schema: &'a Schema,
+        type_ref: &'a TypeRef,
+        name: &OwnedName,
+        attributes: &[OwnedAttribute],
+        position: SourcePosition,
+    ) -> Frame<'a> {
+        let element = name.local_name.clone();
+        let (frame, declared): (Frame<'a>, &[AttributeDecl]) = match schema.resolve(type_ref) {
+            Some(Resolved::Complex(complex)) => (
+                Frame::Complex {
+                    name: element.clone(),
+                    complex,
+                    particle: 0,
+                    count: 0,
+                    position,
+                },
+                &complex.attributes,
+            ),
+            Some(Resolved::Simple(simple)) => (
+                Frame::Simple {
+                    name: element.clone(),
+                    simple,
+                    text: String::new(),
+                    position,
+                },
+                &[],
+            ),
+            None => (Frame::Skip, &[]),
+        };
+
+        for attribute in attributes {
+            if attribute.name.namespace.as_deref() == Some(XSI_NAMESPACE) {
+                continue;
+            }
+            let declaration = declared.iter().find(|declaration| {
+                attribute.name.namespace.is_none() && attribute.name.local_name == declaration.name
+            });
+            let declaration = match declaration {
+                Some(declaration) => declaration,
+                None => {
+                    let message = format!(
+                        "the attribute `{}` is not allowed on `{}`",
+                        qualified(&attribute.name),
+                        element
+                    );
+                    self.report(position, message);
+                    continue;
+                }
+            };
+            let checked = match (
+                &declaration.fixed,
+                schema.resolve_name(&declaration.type_ref),
+            ) {
+                (Some(fixed), _) if &attribute.value != fixed => Err(format!("must be {}", fixed)),
+                (_, Some(Resolved::Simple(simple))) => simple.check(&attribute.value),
+                _ => Ok(()),
+            };
+            if let Err(problem) = checked {
+                let message = format!(
+                    "the value `{}` of the attribute `{}` of `{}` {}",
+                    attribute.value, declaration.name, element, problem
+                );
+                self.report(position, message);
+            }
+        }
+        for declaration in declared.iter().filter(|declaration| declaration.required) {
+            let present = attributes.iter().any(|attribute| {
+                attribute.name.namespace.is_none() && attribute.name.local_name == declaration.name
+            });
+            if !present {
+                let message = format!(
+                    "`{}` is missing the attribute `{}`",
+                    element, declaration.name
+                );
+                self.report(position, message);
+            }
+        }
+        frame
+    }
+
+    fn end_element(&mut self) {
+        match self.frames.pop() {
+            Some(Frame::Complex {
+                name,
+                complex,
+                particle,
+                count,
+                position,
+            }) => {
+                let skipped = if count == 0 { particle } else { particle + 1 };
+                if let Some(missing) = missing(complex, skipped..complex.particles.len()) {
+                    self.report(position, format!("`{}` is missing `{}`", name, missing));
+                }
+            }
+            Some(Frame::Simple {
+                name,
+                simple,
+                text,
+                position,
+            }) => {
+                if let Err(problem) = simple.check(&text) {
+                    let message = format!("the value `{}` of `{}` {}", text, name, problem);
+                    self.report(position, message);
+                }
+            }
+            Some(Frame::Skip) | None => {}
+        }
+    }
+
+    fn characters(&mut self, text: &str, position: SourcePosition) {
+        let message = match self.frames.last_mut() {
+            Some(Frame::Simple { text: buffer, .. }) => {
+                buffer.push_str(text);
+                return;
+            }
+            Some(Frame::Complex { name, .. }) if !text.trim().is_empty() => {
+                format!("text is not allowed directly in `{}`", name)
+            }
+            _ => return,
+        };
+        // Point at the text itself rather than at the whitespace before it.
+        let mut position = position;
+        let leading = &text[..text.len() - text.trim_start().len()];
+        for c in leading.chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        self.report(position, message);
+    }
+}
+
+/// Gives `name` as written, with its prefix if it has one.
+fn qualified(name: &OwnedName) -> String {
+    match &name.prefix {
+        Some(prefix) => format!("{}:{}", prefix, name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+/// Gives the name of the first particle in `range` that has to appear, but
+/// did not.
+fn missing(complex: &ComplexType, range: std::ops::Range<usize>) -> Option<&str> {
+    complex.particles[range]
+        .iter()
+        .filter(|particle| particle.min > 0)
+        .find_map(|particle| match &particle.term {
+            Term::Element { name, .. } => Some(name.as_str()),
+            Term::Any => None,
+        })
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn strip_sign(text: &str) -> &str {
+    text.strip_prefix(|c| c == '+' || c == '-').unwrap_or(text)
+}
+
+fn is_integer(text: &str) -> bool {
+    is_digits(strip_sign(text))
+}
+
+fn is_zero(text: &str) -> bool {
+    strip_sign(text).bytes().all(|byte| byte == b'0')
+}
+
+fn is_decimal(text: &str) -> bool {
+    let unsigned = strip_sign(text);
+    match unsigned.split_once('.') {
+        Some((whole, fraction)) => {
+            (whole.is_empty() || is_digits(whole))
+                && (fraction.is_empty() || is_digits(fraction))
+                && !(whole.is_empty() && fraction.is_empty())
+        }
+        None => is_digits(unsigned),
+    }
+}
+
+/// Strips a time zone, `Z` or like `+01:00`, from the end of a date or time.
+fn strip_time_zone(text: &str) -> &str {
+    if let Some(rest) = text.strip_suffix('Z') {
+        return rest;
+    }
+    let split = text.len().saturating_sub(6);
+    match (text.get(..split), text.get(split..)) {
+        (Some(rest), Some(zone))
+            if zone.is_ascii()
+                && (zone.starts_with('+') || zone.starts_with('-'))
+                && is_two_digits(&zone[1..3], 14)
+                && &zone[3..4] == ":"
+                && is_two_digits(&zone[4..], 59) =>
+        {
+            rest
+        }
+        _ => text,
+    }
+}
+
+fn is_two_digits(text: &str, max: u32) -> bool {
+    text.len() == 2 && is_digits(text) && text.parse::<u32>().is_ok_and(|n| n <= max)
+}
+
+fn is_year(text: &str) -> bool {
+    let year = strip_time_zone(text);
+    let year = year.strip_prefix('-').unwrap_or(year);
+    year.len() >= 4 && is_digits(year)
+}
+
+fn is_date_time(text: &str) -> bool {
+    let (date, time) = match strip_time_zone(text).split_once('T') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let date = date.strip_prefix('-').unwrap_or(date);
+    let mut date_parts = date.rsplitn(3, '-');
+    let (day, month, year) = match (date_parts.next(), date_parts.next(), date_parts.next()) {
+        (Some(day), Some(month), Some(year)) => (day, month, year),
+        _ => return false,
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+    let mut time_parts = time.split(':');
+    let (hour, minute, second) = match (time_parts.next(), time_parts.next(), time_parts.next()) {
+        (Some(hour), Some(minute), Some(second)) if time_parts.next().is_none() => {
+            (hour, minute, second)
+        }
+        _ => return false,
+    };
+    if !(year.len() >= 4 && is_digits(year) && is_two_digits(month, 12) && month != "00") {
+        return false;
+    }
+    // Whether a year is a leap year only depends on its last four digits.
+    let leap = year[year.len() - 4..]
+        .parse::<u32>()
+        .is_ok_and(|year| year % 4 == 0 && (year % 100 != 0 || year % 400 == 0));
+    let days = match month {
+        "02" if leap => 29,
+        "02" => 28,
+        "04" | "06" | "09" | "11" => 30,
+        _ => 31,
+    };
+    // The end of a day may be written as 24:00:00.
+    let end_of_day = hour == "24" && minute == "00" && second == "00";
+    is_two_digits(day, days)
+        && day != "00"
+        && (is_two_digits(hour, 23) || end_of_day)
+        && is_two_digits(minute, 59)
+        && is_two_digits(second, 60)
+        && fraction.map_or(true, |fraction| {
+            is_digits(fraction) && (!end_of_day || is_zero(fraction))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use super::{is_date_time, validate_xml};
+
+    fn violations(xml: &str) -> Vec<(u64, String)> {
+        validate_xml(xml.as_bytes())
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|violation| (violation.position.line, violation.message))
+            .collect()
+    }
+
+    fn fixture(name: &str) -> Result<(), Vec<(u64, String)>> {
+        let file = File::open(format!("tests/fixtures/{}", name)).unwrap();
+        validate_xml(BufReader::new(file)).map_err(|violations| {
+            violations
+                .into_iter()
+                .map(|violation| (violation.position.line, violation.message))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn valid_documents_pass() {
+        assert_eq!(fixture("wikipedia_example.gpx"), Ok(()));
+        assert_eq!(fixture("garmin_poi.gpx"), Ok(()));
+        assert_eq!(fixture("elevation_in_feet.gpx"), Ok(()));
+    }
+
+    #[test]
+    fn violations_have_lines() {
+        assert_eq!(
+            fixture("xsd_violations.gpx"),
+            Err(vec![
+                (5, "`ele` must come before `name` in `wpt`".into()),
+                (7, "`wpt` is missing the attribute `lat`".into()),
+                (
+                    10,
+                    "the value `188.5300` of the attribute `lon` of `wpt` must be less than 180"
+                        .into()
+                ),
+                (
+                    16,
+                    "the value `high` of `ele` must be a decimal number".into()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn date_times() {
+        for valid in &[
+            "2021-02-28T12:00:00Z",
+            "2020-02-29T12:00:00Z",
+            "2000-02-29T12:00:00Z",
+            "2021-04-30T12:00:00.5+02:00",
+            "2021-12-31T24:00:00Z",
+            "2021-12-31T24:00:00.000",
+        ] {
+            assert!(is_date_time(valid), "{}", valid);
+        }
+        for invalid in &[
+            "2021-02-29T12:00:00Z",
+            "1900-02-29T12:00:00Z",
+            "2021-02-30T12:00:00Z",
+            "2021-04-31T12:00:00Z",
+            "2021-12-31T24:00:01Z",
+            "2021-12-31T24:30:00Z",
+            "2021-12-31T24:00:00.1Z",
+            "2021-13-01T12:00:00Z",
+        ] {
+            assert!(!is_date_time(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn content_checks() {
+        let xml = r#"<gpx xmlns="http://www.topografix.com/GPX/1/1" creator="me" version="1.0">
+  <metadata>
+    <link><text>no href</text></link>
+    <time>2021-02-30T25:00:00Z</time>
+    <time>2021-03-01T00:00:00Z</time>
+  </metadata>
+  loose text
+  <extensions><x:anything xmlns:x="http://example.com"><name/></x:anything></extensions>
+  <rte><name>misplaced</name></rte>
+</gpx>"#;
+        assert_eq!(
+            violations(xml),
+            vec![
+                (
+                    1,
+                    "the value `1.0` of the attribute `version` of `gpx` must be 1.1".into()
+                ),
+                (3, "`link` is missing the attribute `href`".into()),
+                (
+                    4,
+                    "the value `2021-02-30T25:00:00Z` of `time` must be a date and time like \
+                     2001-02-03T04:05:06Z"
+                        .into()
+                ),
+                (5, "`time` may appear only once in `metadata`".into()),
+                (7, "text is not allowed directly in `gpx`".into()),
+                (9, "`rte` must come before `extensions` in `gpx`".into()),
+            ]
+        );
+
+        let xml = r#"<gpx xmlns="http://www.topografix.com/GPX/1/1" creator="me" version="1.1">
+  <wpt lat="1" lon="2"><ele>1</ele>"#;
+        assert_eq!(violations(xml).len(), 1);
+    }
+}
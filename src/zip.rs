@@ -0,0 +1,343 @@
+//! zip bundles several documents into one zip archive, such as one file
+//! per track of an export, and reads them back out of one.
+//!
+//! Only what GPX files need is supported: single-disk archives without
+//! Zip64, holding entries that are stored or deflated.
+
+use std::collections::HashSet;
+use std::io::{Read, Seek, Write};
+
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::errors::{GpxError, GpxResult};
+use crate::{read, write_with_options, Gpx, WriteOptions};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+/// The version needed to extract deflated entries, 2.0.
+const VERSION: u16 = 20;
+/// The flag for names encoded in UTF-8.
+const UTF8_NAMES: u16 = 0x0800;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// The largest entry [`read_zip_entries`] inflates, which keeps a small
+/// archive of highly compressed data from filling the memory.
+const MAX_ENTRY_SIZE: usize = 1 << 30;
+
+/// Writes `entries` to `writer` as a zip archive, each [`Gpx`] as a
+/// deflated entry with the given name, written as tuned by `options`.
+///
+/// Names that do not end in `.gpx` get it appended, and have to be unique
+/// after that, or a [`GpxError::DuplicateZipEntry`] is returned. The time
+/// of an entry is the time in the metadata of its document, or the start
+/// of 1980 if it has none, which is the earliest time a zip archive can
+/// give. Errors writing a document, including those of `writer`, are
+/// wrapped in a [`GpxError::ZipEntry`] naming it.
+///
+/// ```
+/// use std::io::Cursor;
+/// use gpx::{read_zip_entries, write_zip, Gpx, GpxVersion, Track, WriteOptions};
+///
+/// let mut entries = Vec::new();
+/// for name in ["Morning", "Evening"].iter() {
+///     let mut gpx = Gpx::default();
+///     gpx.version = GpxVersion::Gpx11;
+///     let mut track = Track::new();
+///     track.name = Some(name.to_string());
+///     gpx.tracks.push(track);
+///     entries.push((name.to_string(), gpx));
+/// }
+///
+/// let mut archive = Cursor::new(Vec::new());
+/// write_zip(entries, &mut archive, &WriteOptions::new()).unwrap();
+///
+/// archive.set_position(0);
+/// let entries = read_zip_entries(archive).unwrap();
+/// assert_eq!(entries[1].0, "Evening.gpx");
+/// assert_eq!(entries[1].1.tracks[0].name.as_deref(), Some("Evening"));
+/// ```
+pub fn write_zip<W: Write + Seek>(
+    entries: impl IntoIterator<Item = (String, Gpx)>,
+    mut writer: W,
+    options: &WriteOptions,
+) -> GpxResult<()> {
+    let start = writer.stream_position()?;
+    let mut names = HashSet::new();
+    let mut central_directory = Vec::new();
+    let mut count: u16 = 0;
+    for (name, gpx) in entries {
+        let name = if name.ends_with(".gpx") {
+            name
+        } else {
+            name + ".gpx"
+        };
+        if !names.insert(name.clone()) {
+            return Err(GpxError::DuplicateZipEntry(name));
+        }
+        count = count
+            .checked_add(1)
+            .ok_or(GpxError::InvalidZip("more than 65535 entries"))?;
+        let (offset, header) = write_entry(&mut writer, start, &name, &gpx, options)
+            .map_err(|error| GpxError::ZipEntry(name.clone(), Box::new(error)))?;
+
+        put_u32(&mut central_directory, CENTRAL_HEADER);
+        put_u16(&mut central_directory, VERSION);
+        central_directory.extend_from_slice(&header.fields);
+        // No comment, on the first disk, without attributes.
+        central_directory.extend_from_slice(&[0; 10]);
+        put_u32(&mut central_directory, offset);
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = writer.stream_position()? - start;
+    let mut end = Vec::new();
+    put_u32(&mut end, END_OF_CENTRAL_DIRECTORY);
+    put_u32(&mut end, 0);
+    put_u16(&mut end, count);
+    put_u16(&mut end, count);
+    put_u32(&mut end, u32_field(central_directory.len() as u64)?);
+    put_u32(&mut end, u32_field(directory_offset)?);
+    put_u16(&mut end, 0);
+    writer.write_all(&central_directory)?;
+    writer.write_all(&end)?;
+    Ok(())
+}
+
+/// Reads the `.gpx` entries of a zip archive, such as one written by
+/// [`write_zip`], in the order they are in the archive. Other entries are
+/// skipped.
+///
+/// Errors reading a document are wrapped in a [`GpxError::ZipEntry`]
+/// naming it, and archives this module cannot read give a
+/// [`GpxError::InvalidZip`].
+pub fn read_zip_entries<R: Read>(mut reader: R) -> GpxResult<Vec<(String, Gpx)>> {
+    let mut archive = Vec::new();
+    reader.read_to_end(&mut archive)?;
+    let end = find_end_of_central_directory(&archive)?;
+    let entries = le16(&archive, end + 10)? as usize;
+    let mut position = le32(&archive, end + 16)? as usize;
+
+    let mut documents = Vec::new();
+    for _ in 0..entries {
+        if le32(&archive, position)? != CENTRAL_HEADER {
+            return Err(GpxError::InvalidZip("broken central directory"));
+        }
+        let method = le16(&archive, position + 10)?;
+        let crc = le32(&archive, position + 16)?;
+        let compressed = le32(&archive, position + 20)? as usize;
+        let name_length = le16(&archive, position + 28)? as usize;
+        let extra_length = le16(&archive, position + 30)? as usize;
+        let comment_length = le16(&archive, position + 32)? as usize;
+        let offset = le32(&archive, position + 42)? as usize;
+        let name = bytes(&archive, position + 46, name_length)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        position += 46 + name_length + extra_length + comment_length;
+        if !name.to_lowercase().ends_with(".gpx") {
+            continue;
+        }
+
+        let gpx = read_entry(&archive, offset, method, crc, compressed)
+            .map_err(|error| GpxError::ZipEntry(name.clone(), Box::new(error)))?;
+        documents.push((name, gpx));
+    }
+    Ok(documents)
+}
+
+/// An entry ready to write: the fields its local and central headers
+/// share, from the version needed to the length of the extra field, and
+/// its compressed data.
+struct EntryHeader {
+    fields: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// Writes the local header and the data of an entry, giving the offset of
+/// the header and the fields the central directory repeats.
+fn write_entry<W: Write + Seek>(
+    writer: &mut W,
+    start: u64,
+    name: &str,
+    gpx: &Gpx,
+    options: &WriteOptions,
+) -> GpxResult<(u32, EntryHeader)> {
+    let offset = u32_field(writer.stream_position()? - start)?;
+    let header = entry_header(name, gpx, options)?;
+
+    let mut local = Vec::new();
+    put_u32(&mut local, LOCAL_HEADER);
+    local.extend_from_slice(&header.fields);
+    local.extend_from_slice(name.as_bytes());
+    writer.write_all(&local)?;
+    writer.write_all(&header.data)?;
+    Ok((offset, header))
+}
+
+fn entry_header(name: &str, gpx: &Gpx, options: &WriteOptions) -> GpxResult<EntryHeader> {
+    let mut document = Vec::new();
+    write_with_options(gpx, &mut document, options.clone())?;
+    let data = compress_to_vec(&document, 6);
+    let (time, date) = dos_time(gpx.metadata.as_ref().and_then(|metadata| metadata.time));
+
+    let mut fields = Vec::new();
+    put_u16(&mut fields, VERSION);
+    put_u16(&mut fields, UTF8_NAMES);
+    put_u16(&mut fields, DEFLATED);
+    put_u16(&mut fields, time);
+    put_u16(&mut fields, date);
+    put_u32(&mut fields, crc32(&document));
+    put_u32(&mut fields, u32_field(data.len() as u64)?);
+    put_u32(&mut fields, u32_field(document.len() as u64)?);
+    if name.len() > u16::MAX as usize {
+        return Err(GpxError::InvalidZip("name longer than 65535 bytes"));
+    }
+    put_u16(&mut fields, name.len() as u16);
+    put_u16(&mut fields, 0);
+    Ok(EntryHeader { fields, data })
+}
+
+fn read_entry(
+    archive: &[u8],
+    offset: usize,
+    method: u16,
+    crc: u32,
+    compressed: usize,
+) -> GpxResult<Gpx> {
+    if le32(archive, offset)? != LOCAL_HEADER {
+        return Err(GpxError::InvalidZip("broken local header"));
+    }
+    let name_length = le16(archive, offset + 26)? as usize;
+    let extra_length = le16(archive, offset + 28)? as usize;
+    let data = bytes(
+        archive,
+        offset + 30 + name_length + extra_length,
+        compressed,
+    )?;
+    let document = match method {
+        STORED => data.to_vec(),
+        DEFLATED => decompress_to_vec_with_limit(data, MAX_ENTRY_SIZE)
+            .map_err(|_| GpxError::InvalidZip("broken deflated data"))?,
+        _ => return Err(GpxError::InvalidZip("unsupported compression method")),
+    };
+    if crc32(&document) != crc {
+        return Err(GpxError::InvalidZip("checksum mismatch"));
+    }
+    read(document.as_slice())
+}
+
+/// Finds the end of central directory record, which is followed by a
+/// comment of at most 65535 bytes.
+fn find_end_of_central_directory(archive: &[u8]) -> GpxResult<usize> {
+    let last = archive
+        .len()
+        .checked_sub(22)
+        .ok_or(GpxError::InvalidZip("too short"))?;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last)
+        .rev()
+        .find(|&position| le32(archive, position).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or(GpxError::InvalidZip("no end of central directory"))
+}
+
+/// Gives the time and date fields of an entry, as MS-DOS stores them: in
+/// local time, which is taken to be UTC, from 1980 to 2107, with even
+/// seconds.
+fn dos_time(time: Option<crate::Time>) -> (u16, u16) {
+    let time = match time {
+        Some(time) => OffsetDateTime::from(time).to_offset(UtcOffset::UTC),
+        None => return (pack_time(0, 0, 0), pack_date(1980, 1, 1)),
+    };
+    if time.year() < 1980 {
+        return (pack_time(0, 0, 0), pack_date(1980, 1, 1));
+    }
+    if time.year() > 2107 {
+        return (pack_time(23, 59, 58), pack_date(2107, 12, 31));
+    }
+    (
+        pack_time(time.hour(), time.minute(), time.second()),
+        pack_date(time.year(), time.month().into(), time.day()),
+    )
+}
+
+fn pack_time(hour: u8, minute: u8, second: u8) -> u16 {
+    (u16::from(hour) << 11) | (u16::from(minute) << 5) | (u16::from(second) / 2)
+}
+
+fn pack_date(year: i32, month: u8, day: u8) -> u16 {
+    (((year - 1980) as u16) << 9) | (u16::from(month) << 5) | u16::from(day)
+}
+
+/// The CRC-32 of zip archives, with the reversed polynomial 0xEDB88320.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn u32_field(value: u64) -> GpxResult<u32> {
+    if value > u32::MAX as u64 {
+        return Err(GpxError::InvalidZip("larger than 4 GiB"));
+    }
+    Ok(value as u32)
+}
+
+fn put_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn bytes(archive: &[u8], position: usize, length: usize) -> GpxResult<&[u8]> {
+    position
+        .checked_add(length)
+        .and_then(|end| archive.get(position..end))
+        .ok_or(GpxError::InvalidZip("truncated"))
+}
+
+fn le16(archive: &[u8], position: usize) -> GpxResult<u16> {
+    let field = bytes(archive, position, 2)?;
+    Ok(u16::from_le_bytes([field[0], field[1]]))
+}
+
+fn le32(archive: &[u8], position: usize) -> GpxResult<u32> {
+    let field = bytes(archive, position, 4)?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{OffsetDateTime, UtcOffset};
+
+    use super::{crc32, dos_time};
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn dos_times() {
+        let time = OffsetDateTime::from_unix_timestamp(1_683_721_817)
+            .unwrap()
+            .to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        let (time, date) = dos_time(Some(time.into()));
+        assert_eq!((time, date), (0x63C8, 0x56AA));
+        assert_eq!(dos_time(None), (0, 0x21));
+        let before = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(dos_time(Some(before.into())), (0, 0x21));
+    }
+}
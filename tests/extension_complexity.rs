@@ -0,0 +1,101 @@
+//! Reads extensions of pathological shapes, as found by fuzzing, which have
+//! to take time linear in their size.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use gpx::dom::{Element, Node};
+use gpx::{read, Gpx};
+
+/// Generous for a debug build, but far below what quadratic work on these
+/// sizes takes.
+const BUDGET: Duration = Duration::from_secs(2);
+
+fn document(extensions: &str) -> String {
+    format!(
+        r#"<?xml version="1.0"?>
+<gpx version="1.1" creator="fuzz" xmlns="http://www.topografix.com/GPX/1/1" xmlns:x="http://example.com/x">
+<extensions>{}</extensions>
+</gpx>"#,
+        extensions
+    )
+}
+
+fn timed_read(xml: &str) -> Gpx {
+    let start = Instant::now();
+    let gpx = read(xml.as_bytes()).unwrap();
+    let elapsed = start.elapsed();
+    assert!(elapsed < BUDGET, "reading took {:?}", elapsed);
+
+    let start = Instant::now();
+    let mut written = Vec::new();
+    gpx::write(&gpx, &mut written).unwrap();
+    let elapsed = start.elapsed();
+    assert!(elapsed < BUDGET, "writing took {:?}", elapsed);
+    gpx
+}
+
+fn only_child(extensions: &Element) -> &Element {
+    let mut elements = extensions.elements();
+    let child = elements.next().unwrap();
+    assert!(elements.next().is_none());
+    child
+}
+
+#[test]
+fn many_attributes() {
+    let mut attributes = String::new();
+    // Half of them with a namespace. The XML reader allows up to 65 536
+    // attributes on one element.
+    for i in 0..25_000 {
+        write!(attributes, r#" a{}="{}" x:b{}="{}""#, i, i, i, i).unwrap();
+    }
+    let xml = document(&format!("<x:e{}/>", attributes));
+    let gpx = timed_read(&xml);
+
+    let element = only_child(gpx.extensions.as_ref().unwrap());
+    assert_eq!(element.attributes.len(), 50_000);
+    assert_eq!(element.attributes[0].name.local_name, "a0");
+    assert_eq!(element.attributes[1].name.local_name, "b0");
+    assert_eq!(
+        element.attributes[1].name.namespace.as_deref(),
+        Some("http://example.com/x")
+    );
+    let last = element.attributes.last().unwrap();
+    assert_eq!(last.value, "24999");
+}
+
+#[test]
+fn many_siblings() {
+    let mut siblings = String::new();
+    for i in 0..10_000 {
+        writeln!(siblings, "<x:s>{}</x:s>", i).unwrap();
+    }
+    let gpx = timed_read(&document(&siblings));
+
+    let extensions = gpx.extensions.unwrap();
+    assert_eq!(extensions.children.len(), 10_000);
+    for (i, child) in extensions.elements().enumerate() {
+        assert_eq!(child.name.local_name, "s");
+        assert_eq!(child.children, [Node::Text(i.to_string())]);
+    }
+}
+
+#[test]
+fn deep_nesting() {
+    let depth = 1_000;
+    let xml = document(&format!(
+        "{}{}",
+        "<x:d>".repeat(depth),
+        "</x:d>".repeat(depth)
+    ));
+    let gpx = timed_read(&xml);
+
+    let mut element = only_child(gpx.extensions.as_ref().unwrap());
+    let mut levels = 1;
+    while let Some(child) = element.elements().next() {
+        element = child;
+        levels += 1;
+    }
+    assert_eq!(levels, depth);
+}
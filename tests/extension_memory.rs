@@ -0,0 +1,264 @@
+//! Measures the memory taken by extensions when reading and writing. This
+//! counts the bytes allocated by the whole test binary, so the tests take
+//! turns.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use geo_types::Point;
+
+use gpx::dom::{Element, Node, OwnedAttribute, OwnedName, SharedStr};
+use gpx::WriteOptions;
+use gpx::{write_with_options, Gpx, GpxVersion, PointKind, Track, TrackSegment, Waypoint};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Held by the test that is counting.
+struct Turn;
+
+impl Turn {
+    fn take() -> Turn {
+        while TAKEN.swap(true, Ordering::SeqCst) {
+            thread::yield_now();
+        }
+        Turn
+    }
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        TAKEN.store(false, Ordering::SeqCst);
+    }
+}
+
+fn grow(size: usize) {
+    let live = LIVE.fetch_add(size, Ordering::SeqCst) + size;
+    PEAK.fetch_max(live, Ordering::SeqCst);
+    ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        grow(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        grow(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A Garmin style track of `points` points, each with heart rate, cadence
+/// and temperature.
+fn garmin_track(points: usize) -> String {
+    let mut data = String::from(
+        "<gpx version=\"1.1\" creator=\"test\" xmlns=\"http://www.topografix.com/GPX/1/1\" \
+         xmlns:gpxtpx=\"http://www.garmin.com/xmlschemas/TrackPointExtension/v1\">\
+         <trk><trkseg>\n",
+    );
+    for i in 0..points {
+        writeln!(
+            data,
+            "<trkpt lat=\"47.{:06}\" lon=\"8.{:06}\"><extensions><gpxtpx:TrackPointExtension>\
+             <gpxtpx:atemp>{}</gpxtpx:atemp><gpxtpx:hr>{}</gpxtpx:hr><gpxtpx:cad>{}</gpxtpx:cad>\
+             </gpxtpx:TrackPointExtension></extensions></trkpt>",
+            i,
+            i,
+            20 + i % 5,
+            120 + i % 40,
+            80 + i % 10
+        )
+        .unwrap();
+    }
+    data.push_str("</trkseg></trk></gpx>\n");
+    data
+}
+
+/// Copies `name`, giving each of its parts an allocation of its own.
+fn unshare_name(name: &OwnedName) -> OwnedName {
+    let copy = |part: &SharedStr| SharedStr::from(part.to_string());
+    OwnedName {
+        local_name: copy(&name.local_name),
+        namespace: name.namespace.as_ref().map(copy),
+        prefix: name.prefix.as_ref().map(copy),
+    }
+}
+
+/// Copies `element` the way the reader kept it before names were interned:
+/// every name with allocations of its own, and the vectors as they grew
+/// while the element was read.
+fn unshare(element: &Element) -> Element {
+    let mut copy = Element::new(unshare_name(&element.name));
+    for attribute in &element.attributes {
+        copy.attributes.push(OwnedAttribute::new(
+            unshare_name(&attribute.name),
+            &attribute.value,
+        ));
+    }
+    for child in &element.children {
+        copy.children.push(match child {
+            Node::Element(element) => Node::Element(unshare(element)),
+            Node::Text(text) => Node::Text(text.clone()),
+        });
+    }
+    copy
+}
+
+/// Gives the bytes freed by dropping the extensions of all points.
+fn extension_bytes(gpx: &mut Gpx) -> usize {
+    let before = LIVE.load(Ordering::SeqCst);
+    for point in &mut gpx.tracks[0].segments[0].points {
+        point.extras_mut().extensions = None;
+    }
+    before - LIVE.load(Ordering::SeqCst)
+}
+
+#[test]
+fn interned_names_halve_extension_memory() {
+    let _turn = Turn::take();
+    let data = garmin_track(20_000);
+    let mut interned = gpx::read(data.as_bytes()).unwrap();
+    let mut unshared = interned.clone();
+    for point in &mut unshared.tracks[0].segments[0].points {
+        point.extras_mut().extensions = point.extras().extensions.as_ref().map(unshare);
+    }
+    assert_eq!(interned, unshared);
+
+    let interned = extension_bytes(&mut interned);
+    let unshared = extension_bytes(&mut unshared);
+    assert!(
+        interned * 2 <= unshared,
+        "interned names keep {} bytes, unshared {}",
+        interned,
+        unshared
+    );
+}
+
+/// A track of `points` points, with a heart rate kept next to it.
+fn track_and_heart_rates(points: usize) -> (Gpx, Vec<u8>) {
+    let mut segment = TrackSegment::new();
+    let mut heart_rates = Vec::new();
+    for i in 0..points {
+        segment
+            .points
+            .push(Waypoint::new(Point::new(8.0 + i as f64 * 1e-5, 47.0)));
+        heart_rates.push(100 + (i % 80) as u8);
+    }
+    let mut track = Track::new();
+    track.segments.push(segment);
+    let gpx = Gpx {
+        version: GpxVersion::Gpx11,
+        tracks: vec![track],
+        ..Default::default()
+    };
+    (gpx, heart_rates)
+}
+
+/// Writes the heart rates of `track_and_heart_rates` as Garmin extensions.
+fn heart_rate_options(heart_rates: Vec<u8>) -> WriteOptions {
+    let ns = "http://www.garmin.com/xmlschemas/TrackPointExtension/v1";
+    let extension = OwnedName::qualified("TrackPointExtension", ns, Some("gpxtpx".into()));
+    let hr = OwnedName::qualified("hr", ns, Some("gpxtpx".into()));
+    let mut index = 0;
+    let mut text = String::new();
+    WriteOptions::new()
+        .declare_namespace("gpxtpx", ns)
+        .point_extension_writer(move |_, kind, sink| {
+            assert_eq!(kind, PointKind::TrackPoint);
+            text.clear();
+            write!(text, "{}", heart_rates[index]).unwrap();
+            index += 1;
+            sink.start_element(&extension)?;
+            sink.start_element(&hr)?;
+            sink.text(&text)?;
+            sink.end_element()?;
+            sink.end_element()
+        })
+}
+
+#[test]
+fn point_extension_writer_builds_no_elements() {
+    let _turn = Turn::take();
+    const POINTS: usize = 10_000;
+    let (gpx, heart_rates) = track_and_heart_rates(POINTS);
+
+    // The same extensions, built as elements in advance.
+    let ns = "http://www.garmin.com/xmlschemas/TrackPointExtension/v1";
+    let mut built = gpx.clone();
+    for (point, rate) in built.tracks[0].segments[0]
+        .points
+        .iter_mut()
+        .zip(&heart_rates)
+    {
+        let mut hr = Element::new(OwnedName::qualified("hr", ns, Some("gpxtpx".into())));
+        hr.children.push(Node::Text(rate.to_string()));
+        let mut extension = Element::new(OwnedName::qualified(
+            "TrackPointExtension",
+            ns,
+            Some("gpxtpx".into()),
+        ));
+        extension.children.push(Node::Element(hr));
+        let mut extensions = Element::new(OwnedName::local("extensions"));
+        extensions.children.push(Node::Element(extension));
+        point.extras_mut().extensions = Some(extensions);
+    }
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    gpx::write(&built, io::sink()).unwrap();
+    let from_elements = ALLOCATIONS.load(Ordering::SeqCst) - before;
+    drop(built);
+
+    // The sink allocates no more than writing the elements does, which
+    // leaves only what the XML writer allocates itself, and it never holds
+    // more than a little at once.
+    let options = heart_rate_options(heart_rates.clone());
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    let live = LIVE.load(Ordering::SeqCst);
+    PEAK.store(live, Ordering::SeqCst);
+    write_with_options(&gpx, io::sink(), options).unwrap();
+    let from_sink = ALLOCATIONS.load(Ordering::SeqCst) - before;
+    let peak = PEAK.load(Ordering::SeqCst) - live;
+    assert!(
+        from_sink <= from_elements + 100,
+        "{} allocations from the sink, {} from elements",
+        from_sink,
+        from_elements
+    );
+    assert!(peak < 64 * 1024, "{} bytes held while writing", peak);
+
+    let mut buffer = Vec::new();
+    write_with_options(&gpx, &mut buffer, heart_rate_options(heart_rates.clone())).unwrap();
+    let written = gpx::read(buffer.as_slice()).unwrap();
+    let points = &written.tracks[0].segments[0].points;
+    assert_eq!(points.len(), POINTS);
+    for (point, expected) in points.iter().zip(&heart_rates) {
+        let extension = point
+            .extras()
+            .extensions
+            .as_ref()
+            .unwrap()
+            .elements()
+            .next()
+            .unwrap();
+        let hr = extension.elements().next().unwrap();
+        assert_eq!(hr.name.local_name, "hr");
+        assert_eq!(hr.text(), expected.to_string());
+    }
+}
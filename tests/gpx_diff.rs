@@ -0,0 +1,48 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+
+use gpx::diff::{format_diff, DiffOptions};
+use gpx::{read, Gpx};
+
+fn read_fixture(name: &str) -> Gpx {
+    let file = File::open(format!("tests/fixtures/{}.gpx", name)).unwrap();
+    read(BufReader::new(file)).unwrap()
+}
+
+/// Compares the diff between `<name>_before.gpx` and `<name>_after.gpx` with
+/// the snapshot in `snapshot.txt`.
+fn check_diff(name: &str, options: &DiffOptions, snapshot: &str) {
+    let before = read_fixture(&format!("{}_before", name));
+    let after = read_fixture(&format!("{}_after", name));
+    let expected = fs::read_to_string(format!("tests/fixtures/{}", snapshot)).unwrap();
+
+    assert_eq!(format_diff(&before, &after, options), expected);
+    assert_eq!(format_diff(&before, &before, options), "");
+}
+
+#[test]
+fn gpx_diff_simplified_track() {
+    check_diff(
+        "diff_simplify",
+        &DiffOptions::default(),
+        "diff_simplify.txt",
+    );
+}
+
+#[test]
+fn gpx_diff_simplified_track_listing_points() {
+    let options = DiffOptions {
+        list_points: true,
+        ..Default::default()
+    };
+    check_diff("diff_simplify", &options, "diff_simplify_points.txt");
+}
+
+#[test]
+fn gpx_diff_metadata() {
+    check_diff(
+        "diff_metadata",
+        &DiffOptions::default(),
+        "diff_metadata.txt",
+    );
+}
@@ -0,0 +1,142 @@
+//! Documents from the corners of the map: below sea level, next to the
+//! south pole and across the antimeridian.
+//!
+//! The expected distances were computed independently, with Vincenty's
+//! formula on a sphere of the same radius.
+
+use std::fs::File;
+use std::io::BufReader;
+
+use assert_approx_eq::assert_approx_eq;
+use geo::algorithm::haversine_distance::HaversineDistance;
+use geo_types::Point;
+
+use gpx::{read, write, Gpx, StatsOptions, Track, Waypoint};
+
+fn fixture(name: &str) -> Gpx {
+    let file = File::open(format!("tests/fixtures/{}", name)).unwrap();
+    read(BufReader::new(file)).unwrap()
+}
+
+fn points(track: &Track) -> Vec<Point<f64>> {
+    track.segments[0]
+        .points
+        .iter()
+        .map(Waypoint::point)
+        .collect()
+}
+
+/// Gives the length of `track` as measured by the geo crate.
+fn geo_length(track: &Track) -> f64 {
+    points(track)
+        .windows(2)
+        .map(|pair| pair[0].haversine_distance(&pair[1]))
+        .sum()
+}
+
+fn length(track: &Track) -> f64 {
+    *track.segments[0].cumulative_distances().last().unwrap()
+}
+
+#[test]
+fn dead_sea_negative_elevation() {
+    let gpx = fixture("dead_sea.gpx");
+    assert_eq!(gpx.waypoints[0].elevation, Some(-430.5));
+    let track = &gpx.tracks[0];
+    let elevations: Vec<_> = track.segments[0]
+        .points
+        .iter()
+        .map(|point| point.elevation.unwrap())
+        .collect();
+    assert_eq!(elevations, vec![-352.0, -389.5, -412.0, -405.5, -430.5]);
+    assert!(gpx.validate().is_ok());
+
+    assert_approx_eq!(length(track), 2426.7155, 1e-3);
+    assert_approx_eq!(length(track), geo_length(track), 1e-6);
+
+    let stats = track.stats(&StatsOptions::default());
+    assert_approx_eq!(stats.elevation_gain, 6.5);
+    assert_approx_eq!(stats.elevation_loss, 85.0);
+
+    // Halfway along the first leg, the elevation is between the two.
+    let halfway = track.point_at_distance(659.6485 / 2.0).unwrap();
+    assert_approx_eq!(halfway.elevation.unwrap(), -370.75, 1e-3);
+
+    let mut written = Vec::new();
+    write(&gpx, &mut written).unwrap();
+    #[cfg(feature = "xsd-validation")]
+    assert_eq!(gpx::validate_xml(written.as_slice()), Ok(()));
+    assert_eq!(read(written.as_slice()).unwrap(), gpx);
+}
+
+#[test]
+fn antarctic_distances() {
+    let gpx = fixture("antarctic.gpx");
+    let track = &gpx.tracks[0];
+    assert!(gpx.validate().is_ok());
+
+    // Four quarters of a circle 0.1° around the pole.
+    let distances = track.segments[0].cumulative_distances();
+    assert_approx_eq!(distances[1], 15725.3551, 1e-3);
+    assert_approx_eq!(length(track), 62901.4202, 1e-3);
+    assert_approx_eq!(length(track), geo_length(track), 1e-6);
+
+    // The last two points of the route are on opposite sides of the pole.
+    let route = &gpx.routes[0].points;
+    assert_approx_eq!(route[2].haversine_distance_to(&route[3]), 22239.0160, 1e-3);
+}
+
+#[test]
+fn antarctic_nearest_leg() {
+    let mut route = fixture("antarctic.gpx").routes.remove(0);
+
+    // 1.1 km from the last leg, which goes across the pole, but more than
+    // 2 km from the others. On a flat projection around the point, the
+    // last leg looks more than 10 km away.
+    let near_pole = Waypoint::new(Point::new(90.0, -89.99));
+    assert_eq!(route.insert_point_nearest(near_pole), 3);
+}
+
+#[test]
+fn antimeridian_crossing() {
+    let mut gpx = fixture("antimeridian_crossing.gpx");
+    let track = &gpx.tracks[0];
+    assert!(gpx.validate().is_ok());
+
+    let route = &gpx.routes[0].points;
+    assert_approx_eq!(route[0].haversine_distance_to(&route[1]), 21289.8428, 1e-3);
+
+    let distances = track.segments[0].cumulative_distances();
+    assert_approx_eq!(distances[2] - distances[1], 3078.4539, 1e-3);
+    assert_approx_eq!(length(track), 9235.3616, 1e-3);
+    assert_approx_eq!(length(track), geo_length(track), 1e-6);
+
+    // Interpolated positions stay near the antimeridian rather than going
+    // around the globe.
+    let halfway = track.point_at_distance(length(track) / 2.0).unwrap();
+    assert_eq!(halfway.point, 1);
+    assert!(halfway.position.x().abs() > 179.99);
+    assert_approx_eq!(halfway.position.y(), -16.83, 1e-5);
+    let resampled = track.segments[0].resample_by_distance(500.0);
+    assert!(resampled.points.len() > 10);
+    assert!(resampled
+        .points
+        .iter()
+        .all(|point| point.point().x().abs() >= 179.97 - 1e-9));
+
+    // Bounds are a plain rectangle, so across the antimeridian they span
+    // all longitudes in between.
+    gpx.map_coordinates(|point| point);
+    let bounds = gpx.metadata.unwrap().bounds.unwrap();
+    assert_eq!((bounds.min().x, bounds.max().x), (-179.99, 179.99));
+}
+
+#[test]
+fn antimeridian_nearest_leg() {
+    let mut route = fixture("antimeridian_crossing.gpx").routes.remove(0);
+    route.points.push(Waypoint::new(Point::new(-179.9, -17.0)));
+
+    // Just south of the ferry, on the east side of the antimeridian.
+    let east = Waypoint::new(Point::new(-179.95, -16.81));
+    assert_eq!(route.insert_point_nearest(east), 1);
+}
@@ -0,0 +1,304 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+
+use time::{Duration, OffsetDateTime};
+
+use gpx::errors::GpxError;
+use gpx::events::{self, GpxEvent, GpxEventWriter};
+use gpx::{read, Gpx, GpxVersion, Time, TrackSegment};
+
+/// Builds a document from its events, as `read` would.
+fn collect(events: impl Iterator<Item = Result<GpxEvent, GpxError>>) -> Gpx {
+    let mut gpx = Gpx::default();
+    for event in events {
+        match event.unwrap() {
+            GpxEvent::StartGpx { version, creator } => {
+                gpx.version = version;
+                gpx.creator = creator;
+            }
+            GpxEvent::Metadata(metadata) => gpx.metadata = Some(metadata),
+            GpxEvent::Waypoint(waypoint) => gpx.waypoints.push(waypoint),
+            GpxEvent::StartRoute(route) => gpx.routes.push(route),
+            GpxEvent::RoutePoint(point) => gpx.routes.last_mut().unwrap().points.push(point),
+            GpxEvent::StartTrack(track) => gpx.tracks.push(track),
+            GpxEvent::StartSegment => {
+                let track = gpx.tracks.last_mut().unwrap();
+                track.segments.push(TrackSegment::new());
+            }
+            GpxEvent::Point(point) => {
+                let track = gpx.tracks.last_mut().unwrap();
+                track.segments.last_mut().unwrap().points.push(point);
+            }
+            GpxEvent::Extensions(extensions) => match gpx.tracks.last_mut() {
+                Some(track) if !track.segments.is_empty() && gpx.extensions.is_none() => {
+                    track.segments.last_mut().unwrap().extensions = Some(extensions)
+                }
+                _ => gpx.extensions = Some(extensions),
+            },
+            GpxEvent::EndRoute | GpxEvent::EndSegment | GpxEvent::EndTrack | GpxEvent::EndGpx => {}
+        }
+    }
+    gpx
+}
+
+fn open(name: &str) -> BufReader<File> {
+    BufReader::new(File::open(format!("tests/fixtures/{}", name)).unwrap())
+}
+
+#[test]
+fn events_give_the_same_document_as_read() {
+    for name in [
+        "wikipedia_example.gpx",
+        "viking_with_route_extensions.gpx",
+        "segment_extensions.gpx",
+        "garmin_with_extensions.gpx",
+        "gpsies_example.gpx",
+    ]
+    .iter()
+    {
+        let mut expected = read(open(name)).unwrap();
+        expected.prolog.clear();
+        expected.unknown_elements.clear();
+        assert_eq!(collect(events::read(open(name))), expected, "{}", name);
+
+        // Writing the events gives the same document as writing it whole.
+        let written =
+            events::write(events::read(open(name)).map(Result::unwrap), Vec::new()).unwrap();
+        assert_eq!(read(written.as_slice()).unwrap(), expected, "{}", name);
+    }
+}
+
+#[test]
+fn gpx_10_metadata_is_one_event() {
+    let data = r#"<gpx version="1.0" creator="old">
+  <name>Old file</name>
+  <time>2020-05-01T10:00:00Z</time>
+  <wpt lat="1" lon="2"/>
+</gpx>"#;
+    let events: Vec<GpxEvent> = events::read(data.as_bytes()).map(Result::unwrap).collect();
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events[0],
+        GpxEvent::StartGpx {
+            version: GpxVersion::Gpx10,
+            creator: Some("old".into()),
+        }
+    );
+    match &events[1] {
+        GpxEvent::Metadata(metadata) => {
+            assert_eq!(metadata.name.as_deref(), Some("Old file"));
+            assert!(metadata.time.is_some());
+        }
+        event => panic!("expected metadata, got {:?}", event),
+    }
+    assert!(matches!(events[2], GpxEvent::Waypoint(_)));
+    assert_eq!(events[3], GpxEvent::EndGpx);
+
+    let empty = "<gpx version=\"1.1\"><trk><name>Empty</name></trk></gpx>";
+    let events: Vec<GpxEvent> = events::read(empty.as_bytes()).map(Result::unwrap).collect();
+    assert_eq!(events.len(), 4);
+    assert!(matches!(events[1], GpxEvent::StartTrack(_)));
+    assert_eq!(events[2], GpxEvent::EndTrack);
+}
+
+#[test]
+fn reading_stops_at_errors() {
+    // The name of the track comes after its segment.
+    let data = r#"<gpx version="1.1"><trk><trkseg/><name>Late</name></trk></gpx>"#;
+    let events: Vec<_> = events::read(data.as_bytes()).collect();
+    assert_eq!(events.len(), 5);
+    assert!(matches!(events[3], Ok(GpxEvent::EndSegment)));
+    match &events[4] {
+        Err(GpxError::InvalidChildElement(child, "track")) => assert_eq!(child, "name"),
+        event => panic!("unexpected {:?}", event),
+    }
+
+    let truncated = r#"<gpx version="1.1"><trk><trkseg><trkpt lat="1" lon="2"/>"#;
+    let last = events::read(truncated.as_bytes()).last().unwrap();
+    assert!(last.is_err());
+}
+
+#[test]
+fn writer_checks_nesting() {
+    let mut writer = GpxEventWriter::new(Vec::new());
+    match writer.write(GpxEvent::EndGpx) {
+        Err(GpxError::UnexpectedEvent { event, parent }) => {
+            assert_eq!((event, parent), ("EndGpx", "start"));
+        }
+        result => panic!("unexpected {:?}", result),
+    }
+    writer
+        .write(GpxEvent::StartGpx {
+            version: GpxVersion::Gpx11,
+            creator: None,
+        })
+        .unwrap();
+    writer
+        .write(GpxEvent::StartTrack(Default::default()))
+        .unwrap();
+    let point = GpxEvent::Point(gpx::Waypoint::new(geo_types::Point::new(1.0, 2.0)));
+    assert!(matches!(
+        writer.write(point.clone()),
+        Err(GpxError::UnexpectedEvent {
+            event: "Point",
+            parent: "track"
+        })
+    ));
+    writer.write(GpxEvent::StartSegment).unwrap();
+    writer.write(point).unwrap();
+    assert!(writer
+        .write(GpxEvent::Metadata(Default::default()))
+        .is_err());
+    writer.write(GpxEvent::EndSegment).unwrap();
+    assert!(writer.write(GpxEvent::EndRoute).is_err());
+    match writer.finish() {
+        Err(GpxError::UnfinishedEvents(parent)) => assert_eq!(parent, "track"),
+        result => panic!("unexpected {:?}", result.map(|_| ())),
+    }
+}
+
+/// A document with one track of `points` points, generated while it is
+/// read.
+struct GeneratedTrack {
+    points: usize,
+    next: usize,
+    chunk: Vec<u8>,
+    offset: usize,
+}
+
+impl GeneratedTrack {
+    fn new(points: usize) -> GeneratedTrack {
+        GeneratedTrack {
+            points,
+            next: 0,
+            chunk: b"<gpx version=\"1.1\" creator=\"generator\">\n<trk><name>Long</name><trkseg>\n"
+                .to_vec(),
+            offset: 0,
+        }
+    }
+}
+
+impl Read for GeneratedTrack {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset == self.chunk.len() {
+            self.chunk.clear();
+            self.offset = 0;
+            if self.next < self.points {
+                let i = self.next;
+                writeln!(
+                    self.chunk,
+                    "<trkpt lat=\"47.{:06}\" lon=\"8.{:06}\"><ele>400</ele>\
+                     <time>2023-01-01T{:02}:{:02}:{:02}Z</time></trkpt>",
+                    i,
+                    i,
+                    i / 3600,
+                    i / 60 % 60,
+                    i % 60
+                )?;
+                self.next += 1;
+            } else if self.next == self.points {
+                self.chunk.extend_from_slice(b"</trkseg></trk>\n</gpx>\n");
+                self.next += 1;
+            }
+        }
+        let size = (self.chunk.len() - self.offset).min(buf.len());
+        buf[..size].copy_from_slice(&self.chunk[self.offset..self.offset + size]);
+        self.offset += size;
+        Ok(size)
+    }
+}
+
+/// The writing end of a pipe that holds at most a few chunks.
+struct PipeWriter(SyncSender<Vec<u8>>);
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "reader is gone"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The reading end of a [`PipeWriter`].
+struct PipeReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    offset: usize,
+    read: usize,
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.offset = 0;
+                }
+                Err(_) => return Ok(0),
+            }
+        }
+        let size = (self.chunk.len() - self.offset).min(buf.len());
+        buf[..size].copy_from_slice(&self.chunk[self.offset..self.offset + size]);
+        self.offset += size;
+        self.read += size;
+        Ok(size)
+    }
+}
+
+#[test]
+fn copy_transform_shifts_times() {
+    // The input is generated while it is read, and the output goes through
+    // a pipe of a few chunks to the second reader, so neither the input nor
+    // the output is ever held in memory as a whole.
+    const POINTS: usize = 30_000;
+    let (sender, receiver) = sync_channel(16);
+    let copy = thread::spawn(move || {
+        let mut writer = GpxEventWriter::new(PipeWriter(sender));
+        for event in events::read(GeneratedTrack::new(POINTS)) {
+            let event = match event? {
+                GpxEvent::Point(mut point) => {
+                    point.time = point
+                        .time
+                        .map(|time| Time::from(OffsetDateTime::from(time) + Duration::hours(1)));
+                    GpxEvent::Point(point)
+                }
+                event => event,
+            };
+            writer.write(event)?;
+        }
+        writer.finish().map(|_| ())
+    });
+
+    let mut output = PipeReader {
+        receiver,
+        chunk: Vec::new(),
+        offset: 0,
+        read: 0,
+    };
+    let mut points = 0;
+    for event in events::read(&mut output) {
+        match event.unwrap() {
+            GpxEvent::StartTrack(track) => assert_eq!(track.name.as_deref(), Some("Long")),
+            GpxEvent::Point(point) => {
+                let time = OffsetDateTime::from(point.time.unwrap());
+                let expected = OffsetDateTime::from_unix_timestamp(1_672_531_200 + 3600).unwrap()
+                    + Duration::seconds(points as i64);
+                assert_eq!(time, expected);
+                assert_eq!(point.elevation, Some(400.0));
+                points += 1;
+            }
+            _ => {}
+        }
+    }
+    copy.join().unwrap().unwrap();
+    assert_eq!(points, POINTS);
+    assert!(output.read > 2_000_000);
+}
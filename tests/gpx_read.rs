@@ -3,17 +3,21 @@
 // .gpx files to see how usage might be.
 
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufReader, Read};
 
 use assert_approx_eq::assert_approx_eq;
 use geo::algorithm::haversine_distance::HaversineDistance;
 use geo::euclidean_length::EuclideanLength;
 use geo_types::{Geometry, Point};
 
-use gpx::{read, Fix};
+use gpx::errors::GpxError;
+use gpx::{
+    read, read_partial, read_with_options, read_with_positions, DetectedFormat, ElementPath, Fix,
+    LeapSecondPolicy, LongStringPolicy, ReadOptions, SourcePosition, VersionCompat,
+};
 use std::error::Error;
 
-use time::{Date, Month, PrimitiveDateTime, Time};
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time};
 
 #[test]
 fn gpx_reader_read_test_badxml() {
@@ -26,6 +30,88 @@ fn gpx_reader_read_test_badxml() {
     assert!(result.is_err());
 }
 
+#[test]
+fn gpx_reader_read_test_leading_bom_and_whitespace() {
+    for filename in &[
+        "tests/fixtures/bom_declaration.gpx",
+        "tests/fixtures/blank_line_declaration.gpx",
+    ] {
+        let file = File::open(filename).unwrap();
+        let gpx = read(BufReader::new(file)).unwrap();
+
+        assert_eq!(gpx.creator.as_deref(), Some("leading bytes"));
+        assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Start"));
+    }
+}
+
+#[test]
+fn gpx_reader_read_test_not_gpx() {
+    let file = File::open("tests/fixtures/html_error_page.gpx").unwrap();
+    match read(BufReader::new(file)) {
+        Err(GpxError::NotGpxDocument { root_element }) => assert_eq!(root_element, "html"),
+        other => panic!("expected NotGpxDocument, got {:?}", other),
+    }
+
+    let file = File::open("tests/fixtures/json_response.gpx").unwrap();
+    let result = read(BufReader::new(file));
+    assert!(matches!(result, Err(GpxError::NotXmlDocument(_))));
+
+    assert!(matches!(
+        read("".as_bytes()),
+        Err(GpxError::NotXmlDocument(_))
+    ));
+}
+
+#[test]
+fn gpx_reader_read_test_wrong_format() {
+    let cases: [(&[u8], DetectedFormat); 4] = [
+        (
+            b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\x03",
+            DetectedFormat::Gzip,
+        ),
+        (b"PK\x03\x04\x14\x00\x00\x00\x08\x00", DetectedFormat::Zip),
+        (b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n", DetectedFormat::Pdf),
+        (
+            b"\x0e\x20\x8b\x08\x2a\x01\x00\x00.FIT\x00\x00\x40\x00",
+            DetectedFormat::Fit,
+        ),
+    ];
+    for (data, format) in cases.iter() {
+        match read(*data) {
+            Err(GpxError::LikelyWrongFormat(detected)) => assert_eq!(detected, *format),
+            other => panic!("expected {:?}, got {:?}", format, other),
+        }
+    }
+    for (data, format) in cases.iter() {
+        match read(OneByteReader(data)) {
+            Err(GpxError::LikelyWrongFormat(detected)) => assert_eq!(detected, *format),
+            other => panic!("expected {:?} a byte at a time, got {:?}", format, other),
+        }
+    }
+    let error = read(cases[0].0).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "not a GPX document but gzip compressed data, decompress it first"
+    );
+
+    // Nothing of a document is lost to the sniffing, even when it comes a
+    // byte at a time.
+    let document = std::fs::read("tests/fixtures/wikipedia_example.gpx").unwrap();
+    let expected = read(document.as_slice()).unwrap();
+    assert_eq!(read(OneByteReader(&document)).unwrap(), expected);
+    assert_eq!(
+        gpx::events::read(document.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .len(),
+        gpx::events::read(OneByteReader(&document)).count()
+    );
+    assert!(matches!(
+        gpx::events::read(cases[2].0).next(),
+        Some(Err(GpxError::LikelyWrongFormat(DetectedFormat::Pdf)))
+    ));
+}
+
 #[test]
 fn gpx_reader_read_test_wikipedia() {
     // Should not give an error, and should have all the correct data.
@@ -136,7 +222,7 @@ fn gpx_reader_read_test_empty_elevation() {
         for segment in &track.segments {
             for point in &segment.points {
                 let elevation = point.elevation.is_none();
-                assert_eq!(elevation, true);
+                assert!(elevation);
             }
         }
     }
@@ -206,7 +292,7 @@ fn gpx_reader_read_test_garmin_activity() {
 
         let after = PrimitiveDateTime::new(
             Date::from_calendar_date(2017, Month::July, 30).unwrap(),
-            Time::from_hms(0, 0, 0).unwrap().into(),
+            Time::from_hms(0, 0, 0).unwrap(),
         )
         .assume_utc()
         .into();
@@ -223,12 +309,12 @@ fn gpx_reader_read_test_garmin_activity() {
 
         // It's missing almost all fields, actually.
         assert!(point.name.is_none());
-        assert!(point.comment.is_none());
-        assert!(point.description.is_none());
-        assert!(point.source.is_none());
-        assert!(point.symbol.is_none());
-        assert!(point._type.is_none());
-        assert_eq!(point.links.len(), 0);
+        assert!(point.extras().comment.is_none());
+        assert!(point.extras().description.is_none());
+        assert!(point.extras().source.is_none());
+        assert!(point.extras().symbol.is_none());
+        assert!(point.extras()._type.is_none());
+        assert_eq!(point.extras().links.len(), 0);
     }
 }
 
@@ -276,12 +362,12 @@ fn gpx_reader_read_test_lovers_lane() {
 
         // It's missing almost all fields, actually.
         assert!(point.name.is_none());
-        assert!(point.comment.is_none());
-        assert!(point.description.is_none());
-        assert!(point.source.is_none());
-        assert!(point.symbol.is_none());
-        assert!(point._type.is_none());
-        assert_eq!(point.links.len(), 0);
+        assert!(point.extras().comment.is_none());
+        assert!(point.extras().description.is_none());
+        assert!(point.extras().source.is_none());
+        assert!(point.extras().symbol.is_none());
+        assert!(point.extras()._type.is_none());
+        assert_eq!(point.extras().links.len(), 0);
     }
 }
 
@@ -314,24 +400,24 @@ fn gpx_reader_read_test_with_accuracy() {
     assert_eq!(segment.points.len(), 3);
     let points = &segment.points;
 
-    assert_eq!(points[0].fix, Some(Fix::DGPS));
-    assert_eq!(points[0].sat.unwrap(), 4);
-    assert_eq!(points[0].hdop.unwrap(), 5.);
-    assert_eq!(points[0].vdop.unwrap(), 6.2);
-    assert_eq!(points[0].pdop.unwrap(), 728.);
-    assert_eq!(points[0].dgps_age.unwrap(), 1.);
-    assert_eq!(points[0].dgpsid.unwrap(), 3);
-
-    assert_eq!(points[1].fix, Some(Fix::ThreeDimensional));
-    assert_eq!(points[1].sat.unwrap(), 5);
-    assert_eq!(points[1].hdop.unwrap(), 3.6);
-    assert_eq!(points[1].vdop.unwrap(), 5.);
-    assert_eq!(points[1].pdop.unwrap(), 619.1);
-    assert_eq!(points[1].dgps_age.unwrap(), 2.01);
-    assert_eq!(points[1].dgpsid.unwrap(), 4);
+    assert_eq!(points[0].extras().fix, Some(Fix::DGPS));
+    assert_eq!(points[0].extras().sat.unwrap(), 4);
+    assert_eq!(points[0].extras().hdop.unwrap(), 5.);
+    assert_eq!(points[0].extras().vdop.unwrap(), 6.2);
+    assert_eq!(points[0].extras().pdop.unwrap(), 728.);
+    assert_eq!(points[0].extras().dgps_age.unwrap(), 1.);
+    assert_eq!(points[0].extras().dgpsid.unwrap(), 3);
+
+    assert_eq!(points[1].extras().fix, Some(Fix::ThreeDimensional));
+    assert_eq!(points[1].extras().sat.unwrap(), 5);
+    assert_eq!(points[1].extras().hdop.unwrap(), 3.6);
+    assert_eq!(points[1].extras().vdop.unwrap(), 5.);
+    assert_eq!(points[1].extras().pdop.unwrap(), 619.1);
+    assert_eq!(points[1].extras().dgps_age.unwrap(), 2.01);
+    assert_eq!(points[1].extras().dgpsid.unwrap(), 4);
 
     assert_eq!(
-        points[2].fix,
+        points[2].extras().fix,
         Some(Fix::Other("something_not_in_the_spec".to_string()))
     );
 }
@@ -483,6 +569,18 @@ fn garmin_with_extensions() {
 
     assert_eq!(points.len(), 35);
     assert_eq!(points[0].elevation, Some(860.0));
+
+    // The track extensions are kept.
+    let extensions = track.extensions.as_ref().unwrap();
+    let track_extension = extensions.elements().next().unwrap();
+    assert_eq!(track_extension.name.local_name, "TrackExtension");
+    assert_eq!(
+        track_extension.name.namespace.as_deref(),
+        Some("http://www.garmin.com/xmlschemas/GpxExtensions/v3")
+    );
+    let color = track_extension.elements().next().unwrap();
+    assert_eq!(color.name.local_name, "DisplayColor");
+    assert_eq!(color.text(), "Cyan");
 }
 
 #[test]
@@ -534,3 +632,706 @@ fn outdooractive_export() {
     assert_eq!(points.len(), 9);
     assert_eq!(points[0].point().y(), -3.173433);
 }
+
+#[test]
+fn enforce_element_order_in_waypoint() {
+    let strict = ReadOptions::new().enforce_element_order(true);
+
+    let file = File::open("tests/fixtures/waypoint_time_before_ele.gpx").unwrap();
+    let gpx = read(BufReader::new(file)).unwrap();
+    assert_eq!(gpx.waypoints[0].elevation, Some(4.46));
+
+    let file = File::open("tests/fixtures/waypoint_time_before_ele.gpx").unwrap();
+    match read_with_options(BufReader::new(file), strict) {
+        Err(GpxError::ElementOutOfOrder {
+            parent,
+            element,
+            expected_after,
+        }) => {
+            assert_eq!(parent, "waypoint");
+            assert_eq!(element, "ele");
+            assert_eq!(expected_after, "time");
+        }
+        other => panic!("expected ElementOutOfOrder, got {:?}", other),
+    }
+}
+
+#[test]
+fn enforce_element_order_metadata_after_tracks() {
+    let strict = ReadOptions::new().enforce_element_order(true);
+
+    let file = File::open("tests/fixtures/metadata_after_tracks.gpx").unwrap();
+    let gpx = read(BufReader::new(file)).unwrap();
+    assert_eq!(gpx.tracks.len(), 1);
+    assert_eq!(
+        gpx.metadata.unwrap().name,
+        Some(String::from("Metadata at the end"))
+    );
+
+    let file = File::open("tests/fixtures/metadata_after_tracks.gpx").unwrap();
+    match read_with_options(BufReader::new(file), strict) {
+        Err(GpxError::ElementOutOfOrder {
+            parent,
+            element,
+            expected_after,
+        }) => {
+            assert_eq!(parent, "gpx");
+            assert_eq!(element, "metadata");
+            assert_eq!(expected_after, "trk");
+        }
+        other => panic!("expected ElementOutOfOrder, got {:?}", other),
+    }
+}
+
+#[test]
+fn enforce_element_order_accepts_valid_file() {
+    let strict = ReadOptions::new().enforce_element_order(true);
+
+    let file = File::open("tests/fixtures/wikipedia_example.gpx").unwrap();
+    assert!(read_with_options(BufReader::new(file), strict).is_ok());
+}
+
+#[test]
+fn unknown_attributes_are_ignored_by_default() {
+    let file = File::open("tests/fixtures/unknown_attributes.gpx").unwrap();
+    let result = read(BufReader::new(file)).unwrap();
+
+    assert_eq!(result.metadata.unwrap().name.unwrap(), "Morning loop");
+    assert_eq!(result.tracks[0].name.as_deref(), Some("Loop"));
+    assert_eq!(result.tracks[0].segments[0].points.len(), 2);
+    assert!(result.unknown_attributes.is_empty());
+    assert!(result.tracks[0].unknown_attributes.is_empty());
+    assert!(result.tracks[0].segments[0].points[0]
+        .extras()
+        .unknown_attributes
+        .is_empty());
+}
+
+#[test]
+fn unknown_attributes_are_preserved() {
+    let options = ReadOptions::new().preserve_unknown_attributes(true);
+    let file = File::open("tests/fixtures/unknown_attributes.gpx").unwrap();
+    let result = read_with_options(BufReader::new(file), options).unwrap();
+
+    let root = &result.unknown_attributes;
+    assert_eq!(root.len(), 1);
+    assert_eq!(root[0].element, "");
+    assert_eq!(root[0].attributes.len(), 1);
+    assert_eq!(root[0].attributes[0].name.local_name, "schemaLocation");
+    assert_eq!(root[0].attributes[0].name.prefix.as_deref(), Some("xsi"));
+
+    let metadata = result.metadata.unwrap().unknown_attributes;
+    let elements: Vec<&str> = metadata.iter().map(|u| u.element.as_str()).collect();
+    assert_eq!(elements, vec!["name", "desc"]);
+    let lang = &metadata[0].attributes[0];
+    assert_eq!(lang.name.local_name, "lang");
+    assert_eq!(lang.name.namespace.as_deref(), Some(gpx::dom::NS_XML_URI));
+    assert_eq!(lang.value, "en");
+
+    let point = &result.tracks[0].segments[0].points[0];
+    assert_eq!(point.extras().unknown_attributes.len(), 1);
+    let names: Vec<&str> = point.extras().unknown_attributes[0]
+        .attributes
+        .iter()
+        .map(|attr| attr.name.local_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["hr", "accuracy"]);
+
+    let second = &result.tracks[0].segments[0].points[1];
+    assert_eq!(second.extras().unknown_attributes[0].element, "ele");
+    assert_eq!(
+        second.extras().unknown_attributes[0].attributes[0].value,
+        "m"
+    );
+}
+
+#[test]
+fn unknown_attributes_are_preserved_in_gpx10() {
+    let options = ReadOptions::new().preserve_unknown_attributes(true);
+    let file = File::open("tests/fixtures/unknown_attributes_gpx10.gpx").unwrap();
+    let result = read_with_options(BufReader::new(file), options).unwrap();
+
+    let metadata = result.metadata.unwrap();
+    assert_eq!(metadata.name.as_deref(), Some("Morning loop"));
+    let elements: Vec<&str> = metadata
+        .unknown_attributes
+        .iter()
+        .map(|u| u.element.as_str())
+        .collect();
+    assert_eq!(elements, vec!["name", "desc"]);
+    let lang = &metadata.unknown_attributes[1].attributes[0];
+    assert_eq!(lang.name.local_name, "lang");
+    assert_eq!(lang.name.namespace.as_deref(), Some(gpx::dom::NS_XML_URI));
+    assert_eq!(lang.value, "en");
+    assert_eq!(result.tracks[0].unknown_attributes[0].element, "name");
+}
+
+#[test]
+fn read_partial_complete_file() {
+    let file = File::open("tests/fixtures/wikipedia_example.gpx").unwrap();
+    let (result, salvaged) = read_partial(BufReader::new(file));
+
+    assert!(result.is_ok());
+    let file = File::open("tests/fixtures/wikipedia_example.gpx").unwrap();
+    assert_eq!(salvaged, read(BufReader::new(file)).unwrap());
+}
+
+#[test]
+fn read_partial_truncated_file() {
+    let data = std::fs::read_to_string("tests/fixtures/wikipedia_example.gpx").unwrap();
+    let second_point = data.match_indices("<trkpt").nth(1).unwrap().0;
+    let first_point_end = data.find("</trkpt>").unwrap() + "</trkpt>".len();
+    let third_elevation = data.find("<ele>6.8").unwrap() + "<ele>6.8".len();
+    let track_end = data.find("</trk>").unwrap();
+
+    let cases = [
+        // In the middle of the lat attribute of the second point.
+        (second_point + "<trkpt lat=\"47.64".len(), 1),
+        // Between the first two points.
+        (first_point_end, 1),
+        // In the middle of the elevation of the third point, which is dropped.
+        (third_elevation, 2),
+        // In the middle of the closing tags.
+        (track_end + "</tr".len(), 3),
+        // Only the closing gpx tag is missing.
+        (data.find("</gpx>").unwrap(), 3),
+    ];
+    for &(offset, points) in &cases {
+        let (result, salvaged) = read_partial(&data.as_bytes()[..offset]);
+
+        assert!(result.is_err(), "no error when cut at {}", offset);
+        assert!(salvaged.metadata.unwrap().time.is_some());
+        let track = &salvaged.tracks[0];
+        assert_eq!(track.name.as_deref(), Some("Example GPX Document"));
+        assert_eq!(track.segments.len(), 1);
+        assert_eq!(
+            track.segments[0].points.len(),
+            points,
+            "wrong number of points when cut at {}",
+            offset
+        );
+        for point in &track.segments[0].points {
+            assert!(point.elevation.is_some());
+            assert!(point.time.is_some());
+        }
+    }
+}
+
+#[test]
+fn read_partial_gpx10_metadata() {
+    let data = "<gpx version=\"1.0\"><name>Partial</name><trk><trkseg><trkpt lat=\"1\" lon=\"2\"/>";
+    let (result, salvaged) = read_partial(data.as_bytes());
+
+    assert!(result.is_err());
+    assert_eq!(salvaged.metadata.unwrap().name.as_deref(), Some("Partial"));
+    assert_eq!(salvaged.tracks[0].segments[0].points.len(), 1);
+}
+
+#[test]
+fn mixed_extensions() {
+    let file = File::open("tests/fixtures/mixed_extensions.gpx").unwrap();
+    let result = read(BufReader::new(file)).unwrap();
+
+    assert!(result.metadata.unwrap().extensions.is_some());
+    assert!(result.waypoints[0].extras().extensions.is_some());
+    assert!(result.routes[0].extensions.is_some());
+    assert!(result.routes[0].points[0].extras().extensions.is_some());
+    assert!(result.tracks[0].extensions.is_some());
+
+    let segment = &result.tracks[0].segments[0];
+    assert_eq!(segment.points.len(), 3);
+    assert_eq!(segment.extensions.as_ref().unwrap().text(), "");
+    let names: Vec<&str> = segment.points[0]
+        .extras()
+        .extensions
+        .as_ref()
+        .unwrap()
+        .elements()
+        .map(|element| element.name.prefix.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, vec!["gpxtpx", "gpxx"]);
+}
+
+#[test]
+fn link_hrefs_are_read_verbatim() {
+    let file = File::open("tests/fixtures/link_hrefs.gpx").unwrap();
+    let result = read(BufReader::new(file)).unwrap();
+
+    let metadata_link = &result.metadata.unwrap().links[0];
+    assert!(metadata_link.validate().is_ok());
+
+    let links = &result.waypoints[0].extras().links;
+    let hrefs: Vec<&str> = links.iter().map(|link| link.href.as_str()).collect();
+    assert_eq!(
+        hrefs,
+        vec![
+            "photos/img_001.jpg",
+            "photos/summit view 50%.jpg",
+            "C:\\Users\\me\\pic.jpg",
+            "photos/already%20encoded.jpg",
+        ]
+    );
+    let valid: Vec<bool> = links.iter().map(|link| link.validate().is_ok()).collect();
+    assert_eq!(valid, vec![true, false, false, true]);
+}
+
+#[test]
+fn read_with_positions_records_start_tags() {
+    let file = File::open("tests/fixtures/wikipedia_example.gpx").unwrap();
+    let options = ReadOptions::new().record_source_positions(true);
+    let (gpx, positions) = read_with_positions(BufReader::new(file), options).unwrap();
+
+    assert_eq!(gpx.tracks[0].segments[0].points.len(), 3);
+    // The metadata, the track, its segment and its three points.
+    assert_eq!(positions.len(), 6);
+    assert_eq!(
+        positions.get(&ElementPath::Metadata),
+        Some(SourcePosition { line: 4, column: 3 })
+    );
+    assert_eq!(
+        positions.get(&ElementPath::TrackPoint(0, 0, 0)),
+        Some(SourcePosition {
+            line: 13,
+            column: 7
+        })
+    );
+    assert_eq!(
+        positions.get(&ElementPath::TrackPoint(0, 0, 2)),
+        Some(SourcePosition {
+            line: 21,
+            column: 7
+        })
+    );
+    assert_eq!(positions.get(&ElementPath::TrackPoint(0, 0, 3)), None);
+    assert_eq!(
+        ElementPath::TrackPoint(0, 0, 2).to_string(),
+        "tracks[0].segments[0].points[2]"
+    );
+}
+
+#[test]
+fn read_with_positions_disabled() {
+    let file = File::open("tests/fixtures/wikipedia_example.gpx").unwrap();
+    let (gpx, positions) = read_with_positions(BufReader::new(file), ReadOptions::new()).unwrap();
+
+    assert_eq!(gpx.tracks.len(), 1);
+    assert!(positions.is_empty());
+}
+
+/// Gives one byte per read, like a slow network stream.
+struct OneByteReader<'a>(&'a [u8]);
+
+impl Read for OneByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+/// Gives chunks of varying sizes, and fails with `Interrupted` every few
+/// reads.
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    reads: usize,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        if self.reads % 3 == 0 {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        let size = (self.reads * 7919 % 61 + 1)
+            .min(buf.len())
+            .min(self.data.len());
+        buf[..size].copy_from_slice(&self.data[..size]);
+        self.data = &self.data[size..];
+        Ok(size)
+    }
+}
+
+#[test]
+fn short_reads_parse_like_full_reads() {
+    let fixtures = [
+        "tests/fixtures/wikipedia_example.gpx",
+        "tests/fixtures/garmin-activity.gpx",
+        "tests/fixtures/mixed_extensions.gpx",
+        "tests/fixtures/prolog.gpx",
+        "tests/fixtures/bom_declaration.gpx",
+        "tests/fixtures/blank_line_declaration.gpx",
+    ];
+    for fixture in fixtures.iter() {
+        let mut data = std::fs::read(fixture).unwrap();
+        // Streams often end with a newline after the closing tag.
+        data.extend_from_slice(b"\n\n");
+        let expected = read(data.as_slice()).unwrap();
+
+        let one_byte = read(OneByteReader(&data));
+        assert_eq!(one_byte.unwrap(), expected, "{}", fixture);
+        let chunked = read(ChunkedReader {
+            data: &data,
+            reads: 0,
+        });
+        assert_eq!(chunked.unwrap(), expected, "{}", fixture);
+    }
+
+    // A byte order mark split across reads, followed by a blank line.
+    let mut data = b"\xEF\xBB\xBF\r\n".to_vec();
+    data.extend(std::fs::read("tests/fixtures/wikipedia_example.gpx").unwrap());
+    let expected = read(data.as_slice()).unwrap();
+    assert_eq!(read(OneByteReader(&data)).unwrap(), expected);
+}
+
+/// A document with a waypoint whose `<desc>` is `length` bytes of `x`,
+/// generated as it is read, that counts how much was read.
+struct OversizedDesc {
+    head: &'static [u8],
+    remaining: usize,
+    tail: &'static [u8],
+    read: usize,
+}
+
+impl OversizedDesc {
+    fn new(length: usize) -> OversizedDesc {
+        OversizedDesc {
+            head:
+                b"<gpx version=\"1.1\" creator=\"test\">\n  <wpt lat=\"1\" lon=\"2\">\n    <desc>",
+            remaining: length,
+            tail: b"</desc>\n    <name>Photo</name>\n  </wpt>\n</gpx>\n",
+            read: 0,
+        }
+    }
+}
+
+impl Read for OversizedDesc {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = if !self.head.is_empty() {
+            let size = self.head.len().min(buf.len());
+            buf[..size].copy_from_slice(&self.head[..size]);
+            self.head = &self.head[size..];
+            size
+        } else if self.remaining > 0 {
+            let size = self.remaining.min(buf.len());
+            buf[..size].iter_mut().for_each(|byte| *byte = b'x');
+            self.remaining -= size;
+            size
+        } else {
+            let size = self.tail.len().min(buf.len());
+            buf[..size].copy_from_slice(&self.tail[..size]);
+            self.tail = &self.tail[size..];
+            size
+        };
+        self.read += size;
+        Ok(size)
+    }
+}
+
+#[test]
+fn max_string_length_truncates() {
+    let options = ReadOptions::new()
+        .max_string_length(1000)
+        .long_strings(LongStringPolicy::Truncate);
+    let gpx = read_with_options(OversizedDesc::new(100_000), options.clone()).unwrap();
+    let waypoint = &gpx.waypoints[0];
+    assert_eq!(
+        waypoint.extras().description.as_deref(),
+        Some("x".repeat(1000).as_str())
+    );
+    assert_eq!(waypoint.name.as_deref(), Some("Photo"));
+
+    // Characters are not split, so the result may be a little shorter.
+    let data = r#"<gpx version="1.1"><wpt lat="1" lon="2"><name>Zürich</name></wpt></gpx>"#;
+    let short = ReadOptions::new()
+        .max_string_length(2)
+        .long_strings(LongStringPolicy::Truncate);
+    let gpx = read_with_options(data.as_bytes(), short).unwrap();
+    assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Z"));
+
+    // Strings within the limit are read as they are.
+    let gpx = read_with_options(OversizedDesc::new(1000), options).unwrap();
+    assert_eq!(
+        gpx.waypoints[0]
+            .extras()
+            .description
+            .as_ref()
+            .unwrap()
+            .len(),
+        1000
+    );
+}
+
+#[test]
+fn max_string_length_errors() {
+    let options = ReadOptions::new().max_string_length(1000);
+    match read_with_options(OversizedDesc::new(100_000), options.clone()) {
+        Err(GpxError::StringTooLong {
+            element: "desc",
+            length,
+            limit: 1000,
+        }) => assert!(length > 1000),
+        other => panic!("expected StringTooLong, got {:?}", other),
+    }
+
+    // The parser stops at its limit of at least 4 KiB, after the text
+    // before a comment was read.
+    let data = format!(
+        r#"<gpx version="1.1"><wpt lat="1" lon="2"><desc>{}<!-- more -->{}</desc></wpt></gpx>"#,
+        "a".repeat(3000),
+        "b".repeat(10_000)
+    );
+    match read_with_options(data.as_bytes(), ReadOptions::new().max_string_length(3500)) {
+        Err(GpxError::StringTooLong { length, .. }) => assert_eq!(length, 3000 + 4097),
+        other => panic!("expected StringTooLong, got {:?}", other),
+    }
+
+    // Reading stops long before the end of a huge value.
+    let mut reader = OversizedDesc::new(200 * 1024 * 1024);
+    let result = read_with_options(&mut reader, options.clone());
+    assert!(matches!(result, Err(GpxError::StringTooLong { .. })));
+    assert!(reader.read < 1024 * 1024, "read {} bytes", reader.read);
+
+    let gpx = read_with_options(OversizedDesc::new(1000), options).unwrap();
+    assert_eq!(
+        gpx.waypoints[0]
+            .extras()
+            .description
+            .as_ref()
+            .unwrap()
+            .len(),
+        1000
+    );
+}
+
+fn read_mixed_fixture(name: &str, compat: VersionCompat) -> Result<gpx::Gpx, GpxError> {
+    let file = File::open(format!("tests/fixtures/{}", name)).unwrap();
+    let options = ReadOptions::new().version_compat(compat);
+    read_with_options(BufReader::new(file), options)
+}
+
+#[test]
+fn mixed_versions_are_rejected_by_default() {
+    for name in ["mixed_version_11.gpx", "mixed_version_10.gpx"].iter() {
+        assert!(read_mixed_fixture(name, VersionCompat::Strict).is_err());
+        let file = File::open(format!("tests/fixtures/{}", name)).unwrap();
+        assert!(read(BufReader::new(file)).is_err());
+    }
+}
+
+#[test]
+fn mixed_versions_in_gpx_11() {
+    let gpx = read_mixed_fixture("mixed_version_11.gpx", VersionCompat::Mixed).unwrap();
+    assert_eq!(gpx.version, gpx::GpxVersion::Gpx11);
+
+    let metadata = gpx.metadata.unwrap();
+    assert_eq!(metadata.name.as_deref(), Some("Alpine Traverse"));
+    assert_eq!(
+        metadata.description.as_deref(),
+        Some("Two days over the pass")
+    );
+    assert_eq!(metadata.keywords.as_deref(), Some("hiking, alps"));
+    assert!(metadata.time.is_some());
+    let author = metadata.author.unwrap();
+    assert_eq!(author.name.as_deref(), Some("Jane Doe"));
+    assert_eq!(author.email.as_deref(), Some("jane@example.com"));
+    let link = author.link.unwrap();
+    assert_eq!(link.href, "https://example.com/traverse");
+    assert_eq!(link.text.as_deref(), Some("Trip report"));
+
+    let waypoint = &gpx.waypoints[0];
+    assert_eq!(waypoint.extras().speed, Some(1.2));
+    assert_eq!(waypoint.extras().links.len(), 1);
+    assert_eq!(waypoint.extras().links[0].href, "https://example.com/pass");
+    assert_eq!(
+        waypoint.extras().links[0].text.as_deref(),
+        Some("Pass photos")
+    );
+
+    assert_eq!(gpx.routes[0].links[0].href, "https://example.com/descent");
+    assert_eq!(gpx.routes[0].links[0].text, None);
+
+    let links = &gpx.tracks[0].links;
+    assert_eq!(links.len(), 2);
+    assert_eq!(links[0].href, "https://example.com/day1");
+    assert_eq!(links[1].href, "https://example.com/day1.gpx");
+    assert_eq!(links[1].text.as_deref(), Some("Day 1 download"));
+}
+
+#[test]
+fn mixed_versions_in_gpx_10() {
+    let gpx = read_mixed_fixture("mixed_version_10.gpx", VersionCompat::Mixed).unwrap();
+    assert_eq!(gpx.version, gpx::GpxVersion::Gpx10);
+    assert_eq!(gpx.waypoints.len(), 1);
+
+    // The values from <metadata> win, the others fill the gaps.
+    let metadata = gpx.metadata.unwrap();
+    assert_eq!(metadata.name.as_deref(), Some("Morning Ride"));
+    assert!(metadata.time.is_some());
+    let author = metadata.author.unwrap();
+    assert_eq!(author.name.as_deref(), Some("John Roe"));
+    assert_eq!(author.email.as_deref(), Some("john@example.com"));
+    assert_eq!(metadata.links[0].href, "https://example.com/ride");
+    assert_eq!(metadata.links[0].text.as_deref(), Some("Ride page"));
+}
+
+fn read_quirks_fixture(options: ReadOptions) -> gpx::Gpx {
+    let file = File::open("tests/fixtures/quirks_etrex.gpx").unwrap();
+    read_with_options(BufReader::new(file), options).unwrap()
+}
+
+#[test]
+fn creator_quirks() {
+    let years = |gpx: &gpx::Gpx| -> Vec<i32> {
+        gpx.tracks[0].segments[0]
+            .points
+            .iter()
+            .map(|point| time::OffsetDateTime::from(point.time.unwrap()).year())
+            .collect()
+    };
+
+    let plain = read_quirks_fixture(ReadOptions::new());
+    assert_eq!(years(&plain), vec![2019, 2019, 2019, 1999, 1999]);
+
+    let repaired = read_quirks_fixture(ReadOptions::new().apply_creator_quirks(true));
+    assert_eq!(years(&repaired), vec![2019; 5]);
+    assert!(repaired.tracks[0].segments[0]
+        .detect_time_anomalies()
+        .is_empty());
+}
+
+#[test]
+fn registered_quirks() {
+    let drop_name = |pattern: &str| {
+        ReadOptions::new().register_quirk(
+            pattern,
+            Box::new(|gpx: &mut gpx::Gpx| gpx.tracks[0].name = None),
+        )
+    };
+
+    let gpx = read_quirks_fixture(drop_name("eTrex*"));
+    assert_eq!(gpx.tracks[0].name, None);
+    // Registered quirks apply without the built-in ones.
+    assert_eq!(
+        gpx.tracks[0].segments[0].points[4].time,
+        read_quirks_fixture(ReadOptions::new()).tracks[0].segments[0].points[4].time
+    );
+
+    let gpx = read_quirks_fixture(drop_name("GPSMAP*"));
+    assert_eq!(gpx.tracks[0].name.as_deref(), Some("After the rollover"));
+}
+
+#[test]
+fn gpx_reader_read_gpx10_urls() {
+    let file = File::open("tests/fixtures/links_gpx10.gpx").unwrap();
+    let gpx = read(BufReader::new(file)).unwrap();
+
+    let author = gpx.metadata.unwrap().author.unwrap();
+    assert_eq!(author.name.as_deref(), Some("A. Hiker"));
+    let link = author.link.unwrap();
+    assert_eq!(link.href, "https://example.com/tours/3");
+    assert_eq!(link.text.as_deref(), Some("Tour page"));
+
+    let waypoint = &gpx.waypoints[0].extras().links;
+    assert_eq!(waypoint.len(), 1);
+    assert_eq!(waypoint[0].href, "https://example.com/huts/7.jpg");
+    assert_eq!(waypoint[0].text.as_deref(), Some("Hut photo"));
+    assert_eq!(waypoint[0]._type, None);
+
+    let route = &gpx.routes[0].links;
+    assert_eq!(route[0].href, "https://example.com/tours/3.gpx");
+    assert_eq!(route[0].text, None);
+
+    let track = &gpx.tracks[0].links;
+    assert_eq!(track[0].href, "https://example.com/tours/3/photos");
+    assert_eq!(track[0].text.as_deref(), Some("Photos"));
+}
+
+#[test]
+fn gpx_reader_read_values_around_comments() {
+    let gpx = read(File::open("tests/fixtures/value_comments.gpx").unwrap()).unwrap();
+    let start = OffsetDateTime::from_unix_timestamp(1_619_863_200).unwrap();
+    let metadata_time = gpx.metadata.unwrap().time.unwrap();
+    assert_eq!(
+        OffsetDateTime::from(metadata_time),
+        start - Duration::minutes(1)
+    );
+    assert_eq!(gpx.tracks[0].number, Some(3));
+
+    let points = &gpx.tracks[0].segments[0].points;
+    assert_eq!(points[0].elevation, Some(523.4));
+    assert_eq!(points[0].time.map(OffsetDateTime::from), Some(start));
+    assert_eq!(points[0].extras().sat, Some(7));
+    assert_eq!(points[1].elevation, Some(524.5));
+    assert_eq!(
+        points[1].time.map(OffsetDateTime::from),
+        Some(start + Duration::seconds(5))
+    );
+    assert_eq!(points[1].extras().hdop, Some(1.2));
+    assert_eq!(points[1].extras().sat, Some(12));
+    assert_eq!(points[1].extras().dgpsid, Some(42));
+}
+
+#[test]
+fn gpx_reader_read_leap_seconds() {
+    let read_leap = |options| {
+        let file = File::open("tests/fixtures/leap_second.gpx").unwrap();
+        read_with_options(BufReader::new(file), options)
+    };
+    match read_leap(ReadOptions::new()) {
+        Err(GpxError::LeapSecondTimestamp(time)) => assert_eq!(time, "2016-12-31T23:59:60.5Z"),
+        other => panic!("expected a leap second error, got {:?}", other.map(|_| ())),
+    }
+
+    let gpx = read_leap(ReadOptions::new().leap_seconds(LeapSecondPolicy::Clamp)).unwrap();
+    let times: Vec<OffsetDateTime> = gpx.tracks[0].segments[0]
+        .points
+        .iter()
+        .map(|point| point.time.unwrap().into())
+        .collect();
+    let midnight = OffsetDateTime::from_unix_timestamp(1_483_228_800).unwrap();
+    assert_eq!(
+        times,
+        [
+            midnight - Duration::milliseconds(500),
+            midnight - Duration::microseconds(1),
+            midnight + Duration::milliseconds(500),
+        ]
+    );
+}
+
+#[test]
+fn gpx_reader_read_keeps_offsets() {
+    // The offsets in the file count, not the time zone of the process. The
+    // test runs again in a child process with `TZ` set, as setting it in
+    // here would race with the other tests.
+    if std::env::var_os("GPX_TEST_TZ_CHILD").is_none() {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--exact")
+            .arg("gpx_reader_read_keeps_offsets")
+            .env("GPX_TEST_TZ_CHILD", "1")
+            .env("TZ", "America/St_Johns")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}", stdout);
+        assert!(stdout.contains("1 passed"), "{}", stdout);
+        return;
+    }
+    let gpx = read(File::open("tests/fixtures/ist_offset.gpx").unwrap()).unwrap();
+    let ist = |time: Option<gpx::Time>| {
+        let time = OffsetDateTime::from(time.unwrap());
+        (time.unix_timestamp(), time.offset().as_hms())
+    };
+    assert_eq!(ist(gpx.metadata.unwrap().time), (1_622_548_800, (5, 30, 0)));
+    let waypoint = OffsetDateTime::from(gpx.waypoints[0].time.unwrap());
+    assert_eq!((waypoint.hour(), waypoint.minute()), (17, 45));
+    assert_eq!(waypoint.millisecond(), 250);
+    assert_eq!(waypoint.offset().whole_minutes(), 330);
+
+    let points = &gpx.tracks[0].segments[0].points;
+    assert_eq!(ist(points[0].time), (1_622_550_600, (5, 30, 0)));
+    assert_eq!(ist(points[1].time), (1_622_550_605, (0, 0, 0)));
+}
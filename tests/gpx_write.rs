@@ -1,8 +1,17 @@
 use std::fs::File;
 use std::io::BufReader;
 
-use gpx::{read, write};
-use gpx::{Gpx, Link, Waypoint};
+use geo_types::Point;
+use time::{Duration, OffsetDateTime};
+
+use gpx::dom::{Element, Node, OwnedName, ProcessingInstruction, PrologNode};
+use gpx::errors::GpxError;
+use gpx::{read, read_with_options, write, write_to_string, write_to_vec, ReadOptions};
+use gpx::{write_route, write_track, write_waypoints, write_with_options, WriteOptions};
+use gpx::{write_route_with_options, write_track_with_options, write_waypoints_with_options};
+use gpx::{ExtensionSink, PointKind};
+use gpx::{FractionDigits, OffsetStyle, TimeFormat};
+use gpx::{Gpx, GpxVersion, Link, Metadata, Route, Track, TrackSegment, Waypoint};
 
 #[test]
 fn gpx_writer_write_unknown_gpx_version() {
@@ -35,6 +44,914 @@ fn gpx_writer_write_test_outdooractive_export() {
     check_write_for_example_file("tests/fixtures/outdooractive-export.gpx");
 }
 
+#[test]
+fn gpx_writer_write_in_schema_order() {
+    for filename in &[
+        "tests/fixtures/wikipedia_example.gpx",
+        "tests/fixtures/ecology-trail-and-lovers-lane-loop.gpx",
+        "tests/fixtures/metadata_after_tracks.gpx",
+    ] {
+        let gpx = read_test_gpx_file(filename);
+        let mut buffer: Vec<u8> = Vec::new();
+        write(&gpx, &mut buffer).unwrap();
+
+        let strict = ReadOptions::new().enforce_element_order(true);
+        assert!(read_with_options(buffer.as_slice(), strict).is_ok());
+    }
+}
+
+#[test]
+fn gpx_writer_write_unknown_attributes() {
+    let options = ReadOptions::new().preserve_unknown_attributes(true);
+    let file = File::open("tests/fixtures/unknown_attributes.gpx").unwrap();
+    let reference_gpx = read_with_options(BufReader::new(file), options.clone()).unwrap();
+
+    let mut buffer: Vec<u8> = Vec::new();
+    write(&reference_gpx, &mut buffer).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.contains(r#"<name xml:lang="en">Loop</name>"#));
+    assert!(written.contains(r#"xmlns:vendor="http://example.com/vendor""#));
+
+    let written_gpx = read_with_options(written.as_bytes(), options).unwrap();
+    assert_eq!(written_gpx, reference_gpx);
+}
+
+#[test]
+fn gpx_writer_write_unknown_attributes_gpx10() {
+    let options = ReadOptions::new().preserve_unknown_attributes(true);
+    let file = File::open("tests/fixtures/unknown_attributes_gpx10.gpx").unwrap();
+    let reference_gpx = read_with_options(BufReader::new(file), options.clone()).unwrap();
+
+    let written = write_to_string(&reference_gpx).unwrap();
+    assert!(written.contains(r#"<name xml:lang="en">Morning loop</name>"#));
+    assert!(written.contains(r#"<desc xml:lang="en">A short loop around the lake.</desc>"#));
+
+    let written_gpx = read_with_options(written.as_bytes(), options).unwrap();
+    assert_eq!(written_gpx, reference_gpx);
+}
+
+#[test]
+fn gpx_writer_write_extensions() {
+    for filename in &[
+        "tests/fixtures/garmin_with_extensions.gpx",
+        "tests/fixtures/mixed_extensions.gpx",
+    ] {
+        let reference_gpx = read_test_gpx_file(filename);
+        let written_gpx = write_and_reread_gpx(&reference_gpx);
+
+        assert_eq!(written_gpx, reference_gpx);
+    }
+}
+
+#[test]
+fn gpx_writer_write_metadata_extensions() {
+    let gpx = read_test_gpx_file("tests/fixtures/osm_trace_export.gpx");
+    let extensions = gpx.metadata.as_ref().unwrap().extensions.as_ref().unwrap();
+    let children: Vec<_> = extensions.elements().collect();
+    assert_eq!(children.len(), 2);
+    let changeset = children[0];
+    assert_eq!(changeset.name.local_name, "changeset");
+    let id = &changeset.attributes[0];
+    assert_eq!(
+        (id.name.local_name.as_str(), id.value.as_str()),
+        ("id", "109876543")
+    );
+    assert_eq!(changeset.elements().count(), 3);
+    assert_eq!(changeset.text().trim(), "");
+
+    let mut buffer = Vec::new();
+    write(&gpx, &mut buffer).unwrap();
+    let xml = String::from_utf8(buffer).unwrap();
+    // Extensions are the last child of the metadata.
+    let end = xml.find("</extensions>").unwrap() + "</extensions>".len();
+    assert!(xml[end..].trim_start().starts_with("</metadata>"));
+    let written = read(xml.as_bytes()).unwrap();
+    assert_eq!(
+        written.metadata.unwrap().extensions.as_ref(),
+        Some(extensions)
+    );
+}
+
+#[test]
+fn gpx_writer_keeps_extension_prefixes() {
+    for filename in &[
+        "tests/fixtures/garmin_with_extensions.gpx",
+        "tests/fixtures/mixed_extensions.gpx",
+        "tests/fixtures/conflicting_prefixes.gpx",
+    ] {
+        let original = std::fs::read_to_string(filename).unwrap();
+        let written = write_to_string(&read(original.as_bytes()).unwrap()).unwrap();
+
+        let tags = extension_tags(&original);
+        assert!(tags.iter().any(|tag| tag.contains(':')));
+        assert_eq!(extension_tags(&written), tags);
+    }
+
+    // A prefix bound to a different namespace than at the root is declared
+    // again where it is used.
+    let original = std::fs::read_to_string("tests/fixtures/conflicting_prefixes.gpx").unwrap();
+    let written = write_to_string(&read(original.as_bytes()).unwrap()).unwrap();
+    assert!(written.contains(r#"<ns:second xmlns:ns="http://example.com/second" ns:unit="m">"#));
+    assert!(written.contains(r#"<plain xmlns="http://example.com/default">"#));
+}
+
+#[test]
+fn gpx_writer_keeps_unknown_elements() {
+    let original = std::fs::read_to_string("tests/fixtures/unknown_elements.gpx").unwrap();
+    let gpx = read(original.as_bytes()).unwrap();
+    assert_eq!(gpx.tracks.len(), 2);
+    let names: Vec<_> = gpx
+        .unknown_elements
+        .iter()
+        .map(|element| element.name.local_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["stuff", "meta"]);
+
+    let written = write_to_string(&gpx).unwrap();
+    let reread = read(written.as_bytes()).unwrap();
+    assert_eq!(reread.unknown_elements, gpx.unknown_elements);
+    assert_eq!(reread, gpx);
+    assert!(written.contains(r#"<foo:stuff foo:version="2">"#));
+    assert!(written.contains(r#"<meta xmlns="http://example.com/vendor">"#));
+}
+
+#[test]
+fn gpx_writer_write_single_track() {
+    let gpx = read_test_gpx_file("tests/fixtures/caltopo-export.gpx");
+    assert_eq!(gpx.tracks.len(), 2);
+
+    let mut buffer = Vec::new();
+    write_track(
+        &gpx.tracks[1],
+        GpxVersion::Gpx11,
+        "single track",
+        &mut buffer,
+    )
+    .unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.contains("Yellow"));
+    assert!(!written.contains("Red"));
+
+    let single = read(written.as_bytes()).unwrap();
+    assert!(single.validate().is_ok());
+    assert_eq!(single.creator.as_deref(), Some("single track"));
+    assert_eq!(single.metadata, None);
+    assert_eq!(single.tracks, vec![gpx.tracks[1].clone()]);
+    assert!(single.waypoints.is_empty());
+    assert!(single.routes.is_empty());
+}
+
+#[test]
+fn gpx_writer_write_single_route_and_waypoints() {
+    let gpx = read_test_gpx_file("tests/fixtures/caltopo-export.gpx");
+    let points = &gpx.tracks[0].segments[0].points;
+    let mut route = Route::new();
+    route.name = Some("Day 01".into());
+    route.points = points.clone();
+
+    let mut buffer = Vec::new();
+    write_route(&route, GpxVersion::Gpx11, "single route", &mut buffer).unwrap();
+    let single = read(buffer.as_slice()).unwrap();
+    assert!(single.validate().is_ok());
+    assert_eq!(single.routes, vec![route]);
+    assert!(single.tracks.is_empty());
+
+    let mut buffer = Vec::new();
+    write_waypoints(points, GpxVersion::Gpx10, "waypoints", &mut buffer).unwrap();
+    let single = read(buffer.as_slice()).unwrap();
+    assert_eq!(single.version, GpxVersion::Gpx10);
+    assert_eq!(&single.waypoints, points);
+}
+
+#[test]
+fn gpx_writer_write_single_parts_with_options() {
+    let gpx = read_test_gpx_file("tests/fixtures/caltopo-export.gpx");
+    let points = &gpx.tracks[0].segments[0].points;
+    let mut route = Route::new();
+    route.points = points.clone();
+
+    let mut buffer = Vec::new();
+    let options = WriteOptions::minimal();
+    write_track_with_options(&gpx.tracks[1], GpxVersion::Gpx11, "a", &mut buffer, options).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.starts_with("<gpx"));
+    assert!(!written.contains('\n'));
+    assert_eq!(
+        read(written.as_bytes()).unwrap().tracks,
+        vec![gpx.tracks[1].clone()]
+    );
+
+    let mut buffer = Vec::new();
+    let options = WriteOptions::new().generated_by_comment(true);
+    write_route_with_options(&route, GpxVersion::Gpx11, "a", &mut buffer, options).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.contains("<!-- Generated by gpx"));
+    assert_eq!(read(written.as_bytes()).unwrap().routes, vec![route]);
+
+    let mut buffer = Vec::new();
+    let options = WriteOptions::new().xml_declaration(false);
+    write_waypoints_with_options(points, GpxVersion::Gpx10, "a", &mut buffer, options).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(!written.contains("<?xml"));
+    assert_eq!(&read(written.as_bytes()).unwrap().waypoints, points);
+}
+
+#[test]
+fn gpx_writer_percent_encode_links() {
+    let gpx = read_test_gpx_file("tests/fixtures/link_hrefs.gpx");
+
+    let mut buffer = Vec::new();
+    let options = WriteOptions::new().percent_encode_links(true);
+    write_with_options(&gpx, &mut buffer, options).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.contains(r#"href="photos/summit%20view%2050%25.jpg""#));
+    assert!(written.contains(r#"href="C:%5CUsers%5Cme%5Cpic.jpg""#));
+    assert!(written.contains(r#"href="photos/already%20encoded.jpg""#));
+    assert!(written.contains(r#"href="https://example.com/trips/2019?day=1#map""#));
+    // The document itself keeps the links as they were read.
+    assert_eq!(
+        gpx.waypoints[0].extras().links[1].href,
+        "photos/summit view 50%.jpg"
+    );
+
+    let reread = read(written.as_bytes()).unwrap();
+    assert!(reread.waypoints[0]
+        .extras()
+        .links
+        .iter()
+        .all(|link| link.validate().is_ok()));
+
+    // Without the option the hrefs are written verbatim.
+    let written = write_to_string(&gpx).unwrap();
+    assert!(written.contains(r#"href="photos/summit view 50%.jpg""#));
+    assert_eq!(read(written.as_bytes()).unwrap(), gpx);
+}
+
+#[test]
+fn gpx_writer_write_links_everywhere() {
+    let gpx = read_test_gpx_file("tests/fixtures/links_everywhere.gpx");
+    let check = |gpx: &Gpx| {
+        let metadata = gpx.metadata.as_ref().unwrap();
+        let links = [
+            metadata.author.as_ref().unwrap().link.as_ref().unwrap(),
+            &metadata.links[0],
+            &gpx.waypoints[0].extras().links[0],
+            &gpx.routes[0].links[0],
+            &gpx.tracks[0].links[0],
+        ];
+        let expected = [
+            ("https://example.com/hikers/a", "Profile", "text/html"),
+            ("https://example.com/tours/3", "Tour page", "text/html"),
+            ("https://example.com/huts/7.jpg", "Hut photo", "image/jpeg"),
+            (
+                "https://example.com/tours/3.gpx",
+                "Download",
+                "application/gpx+xml",
+            ),
+            ("https://example.com/tours/3/photos", "Photos", "text/html"),
+        ];
+        for (link, (href, text, _type)) in links.iter().zip(&expected) {
+            assert_eq!(link.href, *href);
+            assert_eq!(link.text.as_deref(), Some(*text), "{}", href);
+            assert_eq!(link._type.as_deref(), Some(*_type), "{}", href);
+        }
+    };
+    check(&gpx);
+
+    let written = write_to_string(&gpx).unwrap();
+    check(&read(written.as_bytes()).unwrap());
+    // Every link has its text before its type.
+    let links: Vec<&str> = written.split("</link>").collect();
+    assert_eq!(links.len(), 6);
+    for link in &links[..5] {
+        assert!(link.find("<text>").unwrap() < link.find("<type>").unwrap());
+    }
+}
+
+#[test]
+fn gpx_writer_keeps_all_links() {
+    let mut gpx = read_test_gpx_file("tests/fixtures/metadata_links.gpx");
+    let hrefs =
+        |links: &[Link]| -> Vec<String> { links.iter().map(|link| link.href.clone()).collect() };
+    let metadata = gpx.metadata.as_ref().unwrap();
+    assert_eq!(
+        hrefs(&metadata.links),
+        vec![
+            "https://example.com/photos/1.jpg",
+            "https://example.com/activities/4711",
+            "https://example.com/routes/12",
+        ]
+    );
+    assert_eq!(
+        metadata.self_link().unwrap().text.as_deref(),
+        Some("This activity")
+    );
+    assert!(metadata.author.as_ref().unwrap().link.is_some());
+
+    let written = write_and_reread_gpx(&gpx);
+    assert_eq!(written, gpx);
+    assert_eq!(hrefs(&written.waypoints[0].extras().links).len(), 2);
+    assert_eq!(hrefs(&written.routes[0].links).len(), 2);
+    assert_eq!(hrefs(&written.tracks[0].links).len(), 2);
+
+    let metadata = gpx.metadata.as_mut().unwrap();
+    metadata.set_self_link("https://example.com/activities/4712");
+    metadata.set_self_link("https://example.com/activities/4713");
+    let written = write_and_reread_gpx(&gpx);
+    assert_eq!(
+        hrefs(&written.metadata.unwrap().links),
+        vec![
+            "https://example.com/photos/1.jpg",
+            "https://example.com/activities/4713",
+            "https://example.com/routes/12",
+        ]
+    );
+}
+
+#[test]
+fn gpx_writer_keeps_prolog() {
+    let original = std::fs::read_to_string("tests/fixtures/prolog.gpx").unwrap();
+    let gpx = read(original.as_bytes()).unwrap();
+    let expected = vec![
+        PrologNode::ProcessingInstruction(ProcessingInstruction {
+            name: "gpx-pipeline".into(),
+            data: Some("version=\"3\"".into()),
+        }),
+        PrologNode::Comment(" Exported by the pipeline ".into()),
+        PrologNode::Comment(" Do not edit by hand ".into()),
+    ];
+    assert_eq!(gpx.prolog, expected);
+    assert_eq!(gpx.waypoints[0].name.as_deref(), Some("Start"));
+
+    let written = write_to_string(&gpx).unwrap();
+    let pipeline = written.find("<?gpx-pipeline version=\"3\"?>").unwrap();
+    let exported = written.find("<!-- Exported by the pipeline -->").unwrap();
+    let edit = written.find("<!-- Do not edit by hand -->").unwrap();
+    let root = written.find("<gpx").unwrap();
+    assert!(written.starts_with("<?xml"));
+    assert!(pipeline < exported && exported < edit && edit < root);
+    assert!(!written.contains("not kept"));
+
+    let reread = read(written.as_bytes()).unwrap();
+    assert_eq!(reread.prolog, expected);
+}
+
+#[test]
+fn gpx_writer_escapes_entities() {
+    let gpx = read_test_gpx_file("tests/fixtures/entities.gpx");
+    let waypoint = &gpx.waypoints[0];
+    assert_eq!(waypoint.name.as_deref(), Some("Fish & Chips"));
+    assert_eq!(
+        waypoint.extras().description.as_deref(),
+        Some("<3 the \u{e9}clair & \u{1F600}, <b>fresh</b> & hot")
+    );
+    let note = &waypoint.extras().extensions.as_ref().unwrap().children[0];
+    match note {
+        Node::Element(note) => {
+            assert_eq!(note.children, [Node::Text("one piece & more".into())])
+        }
+        other => panic!("expected an element, got {:?}", other),
+    }
+
+    let written = write_to_string(&gpx).unwrap();
+    assert!(written.contains("<name>Fish &amp; Chips</name>"));
+    assert!(written.contains("&lt;b&gt;fresh&lt;/b&gt; &amp; hot"));
+    assert!(written.contains("<ext:note>one piece &amp; more</ext:note>"));
+    assert_eq!(read(written.as_bytes()).unwrap(), gpx);
+}
+
+#[test]
+fn gpx_writer_minimal() {
+    let gpx = read_test_gpx_file("tests/fixtures/garmin-activity.gpx");
+    let default = write_to_vec(&gpx).unwrap();
+    let mut minimal = Vec::new();
+    write_with_options(&gpx, &mut minimal, WriteOptions::minimal()).unwrap();
+    assert!(
+        minimal.len() * 10 <= default.len() * 7,
+        "{} of {} bytes",
+        minimal.len(),
+        default.len()
+    );
+    assert!(minimal.starts_with(b"<gpx "));
+    assert!(!minimal.contains(&b'\n'));
+    assert_eq!(
+        read(minimal.as_slice()).unwrap(),
+        read(default.as_slice()).unwrap()
+    );
+}
+
+#[test]
+fn gpx_writer_omit_defaults() {
+    let mut gpx = read_test_gpx_file("tests/fixtures/with_accuracy.gpx");
+    gpx.metadata = Some(Metadata {
+        extensions: Some(Element::new(OwnedName::local("extensions"))),
+        ..Default::default()
+    });
+    let point = &mut gpx.tracks[0].segments[0].points[0];
+    point.extras_mut().fix = Some(gpx::Fix::None);
+    point.extras_mut().extensions = Some(Element::new(OwnedName::local("extensions")));
+
+    let mut buffer = Vec::new();
+    write_with_options(&gpx, &mut buffer, WriteOptions::new().omit_defaults(true)).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(!written.contains("<metadata"));
+    assert!(!written.contains("<extensions"));
+    assert!(!written.contains("<fix>none</fix>"));
+
+    let mut expected = gpx.clone();
+    expected.metadata = None;
+    let point = &mut expected.tracks[0].segments[0].points[0];
+    point.extras_mut().fix = None;
+    point.extras_mut().extensions = None;
+    assert_eq!(read(written.as_bytes()).unwrap(), expected);
+}
+
+#[test]
+fn gpx_writer_generated_by_comment() {
+    let mut gpx = read(File::open("tests/fixtures/prolog.gpx").unwrap()).unwrap();
+    gpx.prolog.truncate(1);
+    let mut buffer = Vec::new();
+    write_with_options(
+        &gpx,
+        &mut buffer,
+        WriteOptions::new().generated_by_comment(true),
+    )
+    .unwrap();
+
+    let reread = read(buffer.as_slice()).unwrap();
+    assert_eq!(reread.prolog.len(), 2);
+    assert_eq!(reread.prolog[0], gpx.prolog[0]);
+    match &reread.prolog[1] {
+        PrologNode::Comment(text) => {
+            assert!(text.starts_with(" Generated by gpx "));
+            assert!(text.ends_with("Z "));
+        }
+        other => panic!("expected a comment, got {:?}", other),
+    }
+}
+
+#[test]
+fn gpx_writer_keeps_layout_of_descriptions() {
+    let table = "
+  Leg | km  | climb
+  ----+-----+------
+  A-B | 4.2 |   120
+
+  B-C | 1.0 |    15
+";
+    let sheet = "<x:sheet xml:space=\"preserve\">\n  <x:leg> A-B </x:leg>\n  <x:leg><x:km>1.0</x:km> </x:leg>\n</x:sheet>";
+    let xml = format!(
+        r#"<?xml version="1.0"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1" xmlns:x="http://example.com/x">
+<wpt lat="47.0" lon="8.0"><cmt>Hut</cmt><desc>{}</desc><extensions>{}</extensions></wpt>
+</gpx>"#,
+        table, sheet
+    );
+    let gpx = read(xml.as_bytes()).unwrap();
+    assert_eq!(
+        gpx.waypoints[0].extras().description.as_deref(),
+        Some(table)
+    );
+    let extensions = gpx.waypoints[0].extras().extensions.as_ref().unwrap();
+    assert_eq!(extensions.elements().next().unwrap().children.len(), 5);
+
+    let mut buffer = Vec::new();
+    write_with_options(&gpx, &mut buffer, WriteOptions::new().preserve_space(true)).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.contains(&format!("<desc xml:space=\"preserve\">{}</desc>", table)));
+    assert!(written.contains("<cmt>Hut</cmt>"));
+    assert!(written.contains(sheet));
+
+    let reread = read(written.as_bytes()).unwrap();
+    assert_eq!(
+        reread.waypoints[0].extras().description.as_deref(),
+        Some(table)
+    );
+    assert_eq!(
+        reread.waypoints[0].extras().extensions,
+        gpx.waypoints[0].extras().extensions
+    );
+
+    // Without the option, the text is still written as it is.
+    let written = write_to_string(&gpx).unwrap();
+    assert!(written.contains(&format!("<desc>{}</desc>", table)));
+}
+
+/// Gives the start and end tags within the `<extensions>` elements of `xml`,
+/// up to the end of their names.
+fn extension_tags(xml: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    for (start, _) in xml.match_indices("<extensions>") {
+        let end = start + xml[start..].find("</extensions>").unwrap();
+        for (offset, _) in xml[start..end].match_indices('<').skip(1) {
+            let tag = &xml[start + offset..end];
+            let length = tag
+                .find(|c: char| c.is_whitespace() || c == '>' || c == '/' && !tag.starts_with("</"))
+                .unwrap_or(tag.len());
+            tags.push(&tag[..length]);
+        }
+    }
+    tags
+}
+
+#[test]
+fn gpx_writer_retain_extensions() {
+    const GPXTPX: &str = "http://www.garmin.com/xmlschemas/TrackPointExtension/v1";
+
+    let mut gpx = read_test_gpx_file("tests/fixtures/mixed_extensions.gpx");
+    let original_size = write_to_vec(&gpx).unwrap().len();
+
+    gpx.retain_extensions(|name| name.namespace.as_deref() == Some(GPXTPX));
+    let written = write_to_vec(&gpx).unwrap();
+    assert!(written.len() < original_size);
+
+    let gpx = read(written.as_slice()).unwrap();
+    assert!(gpx.metadata.unwrap().extensions.is_none());
+    assert!(gpx.waypoints[0].extras().extensions.is_none());
+    assert!(gpx.routes[0].extensions.is_none());
+    assert!(gpx.routes[0].points[0].extras().extensions.is_none());
+    assert!(gpx.tracks[0].extensions.is_none());
+
+    let segment = &gpx.tracks[0].segments[0];
+    assert!(segment.extensions.is_none());
+    for point in &segment.points[..2] {
+        let extensions = point.extras().extensions.as_ref().unwrap();
+        assert!(extensions
+            .elements()
+            .all(|element| element.name.namespace.as_deref() == Some(GPXTPX)));
+        assert_eq!(extensions.elements().count(), 1);
+    }
+    assert!(segment.points[2].extras().extensions.is_none());
+
+    let mut gpx = read_test_gpx_file("tests/fixtures/mixed_extensions.gpx");
+    gpx.strip_extensions();
+    let stripped = write_to_vec(&gpx).unwrap();
+    assert!(stripped.len() < written.len());
+    assert!(!String::from_utf8(stripped).unwrap().contains("extensions"));
+}
+
+#[test]
+fn gpx_writer_write_to_string() {
+    let gpx = read_test_gpx_file("tests/fixtures/wikipedia_example.gpx");
+    let mut buffer: Vec<u8> = Vec::new();
+    write(&gpx, &mut buffer).unwrap();
+
+    assert_eq!(write_to_vec(&gpx).unwrap(), buffer);
+    assert_eq!(write_to_string(&gpx).unwrap().as_bytes(), buffer.as_slice());
+}
+
+#[test]
+fn gpx_writer_estimated_output_size() {
+    for &full in &[true, false] {
+        let gpx = generated_gpx(100_000, full);
+        let estimate = gpx.estimated_output_size();
+
+        let written = write_to_vec(&gpx).unwrap();
+        assert!(written.len() <= estimate);
+        assert!(estimate <= 2 * written.len());
+        // The buffer never had to grow beyond its initial allocation.
+        assert_eq!(written.capacity(), estimate);
+    }
+}
+
+#[test]
+fn gpx_writer_write_gpx10_containers() {
+    let reference_gpx = read_test_gpx_file("tests/fixtures/containers_gpx10.gpx");
+    let written = write_to_string(&reference_gpx).unwrap();
+    let gpx = read(written.as_bytes()).unwrap();
+    assert_eq!(gpx.version, GpxVersion::Gpx10);
+    assert_eq!(gpx, reference_gpx);
+    assert!(!written.contains("<link"));
+
+    let route = &gpx.routes[0];
+    assert_eq!(route.name.as_deref(), Some("Planned"));
+    assert_eq!(route.comment.as_deref(), Some("Take the ridge if dry"));
+    assert_eq!(route.description.as_deref(), Some("Up to the hut and back"));
+    assert_eq!(route.source.as_deref(), Some("Drawn on a map"));
+    assert_eq!(
+        route.links,
+        [Link {
+            href: "https://example.com/tours/3.gpx".into(),
+            text: Some("Tour file".into()),
+            ..Default::default()
+        }]
+    );
+    assert_eq!(route.number, Some(3));
+    assert_eq!(route.points.len(), 2);
+
+    let track = &gpx.tracks[0];
+    assert_eq!(track.name.as_deref(), Some("Walked"));
+    assert_eq!(track.comment.as_deref(), Some("Rain after noon"));
+    assert_eq!(track.description.as_deref(), Some("The ridge was closed"));
+    assert_eq!(track.source.as_deref(), Some("Handheld receiver"));
+    assert_eq!(
+        track.links,
+        [Link {
+            href: "https://example.com/tours/3/photos".into(),
+            text: Some("Photos".into()),
+            ..Default::default()
+        }]
+    );
+    assert_eq!(track.number, Some(4));
+    assert_eq!(track.segments[0].points.len(), 2);
+
+    // In the order of the GPX 1.0 schema.
+    for (start, end) in [("<rte>", "<rtept"), ("<trk>", "<trkseg>")].iter() {
+        let container = &written[written.find(start).unwrap()..written.find(end).unwrap()];
+        let positions: Vec<usize> = [
+            "<name>",
+            "<cmt>",
+            "<desc>",
+            "<src>",
+            "<url>",
+            "<urlname>",
+            "<number>",
+        ]
+        .iter()
+        .map(|tag| container.find(tag).unwrap())
+        .collect();
+        assert!(
+            positions.windows(2).all(|pair| pair[0] < pair[1]),
+            "{}",
+            container
+        );
+    }
+
+    // GPX 1.1 keeps the links as links, and the track number too.
+    let mut gpx11 = reference_gpx.clone();
+    gpx11.version = GpxVersion::Gpx11;
+    let written = write_to_string(&gpx11).unwrap();
+    assert!(!written.contains("<url>"));
+    assert_eq!(read(written.as_bytes()).unwrap(), gpx11);
+}
+
+#[test]
+fn gpx_writer_write_comments_and_sources() {
+    let original = std::fs::read_to_string("tests/fixtures/comments_and_sources.gpx").unwrap();
+    let gpx10 = original
+        .replace("version=\"1.1\"", "version=\"1.0\"")
+        .replace("GPX/1/1", "GPX/1/0");
+
+    for data in &[original, gpx10] {
+        let reference_gpx = read(data.as_bytes()).unwrap();
+        let gpx = write_and_reread_gpx(&reference_gpx);
+        assert_eq!(gpx, reference_gpx);
+
+        let waypoint = &gpx.waypoints[0];
+        let route = &gpx.routes[0];
+        let track = &gpx.tracks[0];
+        let track_point = &track.segments[0].points[0];
+        let levels = [
+            (
+                "waypoint",
+                &waypoint.extras().comment,
+                &waypoint.extras().source,
+            ),
+            ("route", &route.comment, &route.source),
+            (
+                "route point",
+                &route.points[0].extras().comment,
+                &route.points[0].extras().source,
+            ),
+            ("track", &track.comment, &track.source),
+            (
+                "track point",
+                &track_point.extras().comment,
+                &track_point.extras().source,
+            ),
+        ];
+        for (level, comment, source) in levels.iter() {
+            assert_eq!(
+                comment.as_deref(),
+                Some(format!("{} comment", level).as_str())
+            );
+            assert_eq!(
+                source.as_deref(),
+                Some(format!("{} source", level).as_str())
+            );
+        }
+    }
+}
+
+#[test]
+fn gpx_writer_refuses_non_finite_numbers() {
+    let mut gpx = read_test_gpx_file("tests/fixtures/wikipedia_example.gpx");
+    assert!(gpx.validate().is_ok());
+    gpx.tracks[0].segments[0].points[1].elevation = Some(f64::NAN);
+
+    assert!(matches!(
+        gpx.validate(),
+        Err(GpxError::InvalidNumericValue("ele", _))
+    ));
+    let result = write_to_vec(&gpx);
+    assert!(matches!(
+        result,
+        Err(GpxError::InvalidNumericValue("ele", _))
+    ));
+
+    gpx.tracks[0].segments[0].points[1].elevation = None;
+    gpx.tracks[0].segments[0].points[2].extras_mut().hdop = Some(f64::INFINITY);
+    assert!(matches!(
+        write_to_vec(&gpx),
+        Err(GpxError::InvalidNumericValue("hdop", _))
+    ));
+}
+
+#[test]
+fn gpx_writer_point_extension_writer() {
+    let mut gpx = generated_gpx(3, false);
+    let mut waypoint = Waypoint::new(Point::new(8.5, 47.3));
+    let mut existing = Element::new(OwnedName::local("extensions"));
+    existing
+        .children
+        .push(Node::Element(Element::new(OwnedName::local("old"))));
+    waypoint.extras_mut().extensions = Some(existing);
+    gpx.waypoints.push(waypoint);
+    let mut route = Route::new();
+    route.points.push(Waypoint::new(Point::new(8.6, 47.4)));
+    gpx.routes.push(route);
+
+    let ns = "https://example.com/sensors";
+    let kind = OwnedName::qualified("kind", ns, Some("s".into()));
+    let unit = OwnedName::local("unit");
+    let options = WriteOptions::new().point_extension_writer(move |point, point_kind, sink| {
+        // Only the first track point gets extensions.
+        if point_kind == PointKind::TrackPoint && point.point().x() > -121.999_99 {
+            return Ok(());
+        }
+        sink.start_element(&kind)?;
+        sink.attribute(&unit, "none")?;
+        sink.text(&format!("{:?}", point_kind))?;
+        sink.end_element()
+    });
+    let mut buffer = Vec::new();
+    write_with_options(&gpx, &mut buffer, options).unwrap();
+    let written = read(buffer.as_slice()).unwrap();
+
+    let texts = |point: &Waypoint| -> Vec<String> {
+        match &point.extras().extensions {
+            Some(extensions) => extensions.elements().map(Element::text).collect(),
+            None => Vec::new(),
+        }
+    };
+    assert_eq!(texts(&written.waypoints[0]), vec!["", "Waypoint"]);
+    assert_eq!(texts(&written.routes[0].points[0]), vec!["RoutePoint"]);
+    let points = &written.tracks[0].segments[0].points;
+    assert_eq!(texts(&points[0]), vec!["TrackPoint"]);
+    assert!(points[1].extras().extensions.is_none());
+
+    let element = points[0]
+        .extras()
+        .extensions
+        .as_ref()
+        .unwrap()
+        .elements()
+        .next();
+    let element = element.unwrap();
+    assert_eq!(element.name.namespace.as_deref(), Some(ns));
+    assert_eq!(element.attributes[0].value, "none");
+}
+
+#[test]
+fn gpx_writer_point_extension_writer_checks_nesting() {
+    let gpx = generated_gpx(1, false);
+    let name = OwnedName::local("a");
+    type Case = fn(&mut ExtensionSink, &OwnedName) -> Result<(), GpxError>;
+    let cases: Vec<Case> = vec![
+        |sink, _| sink.text("loose"),
+        |sink, _| sink.end_element(),
+        |sink, name| sink.attribute(name, "1"),
+        |sink, name| {
+            sink.start_element(name)?;
+            sink.text("a")?;
+            sink.attribute(name, "1")
+        },
+        |sink, name| sink.start_element(name),
+    ];
+    for case in cases {
+        let name = name.clone();
+        let options =
+            WriteOptions::new().point_extension_writer(move |_, _, sink| case(sink, &name));
+        let result = write_with_options(&gpx, Vec::new(), options);
+        assert!(
+            matches!(result, Err(GpxError::MisnestedExtension(_))),
+            "{:?}",
+            result
+        );
+    }
+}
+
+/// A single track with `count` points. With `full`, each point has an
+/// elevation and a time as well.
+fn generated_gpx(count: usize, full: bool) -> Gpx {
+    let mut segment = TrackSegment::new();
+    for i in 0..count {
+        let mut point = Waypoint::new(Point::new(
+            -122.0 + i as f64 * 0.000_012_345_678_9,
+            47.0 + i as f64 * 0.000_009_876_5,
+        ));
+        if full {
+            point.elevation = Some(100.0 + i as f64 * 0.37);
+            point.time = Some((OffsetDateTime::UNIX_EPOCH + Duration::seconds(i as i64)).into());
+        }
+        segment.points.push(point);
+    }
+    let mut track = Track::new();
+    track.name = Some(String::from("Generated"));
+    track.segments.push(segment);
+    Gpx {
+        version: GpxVersion::Gpx11,
+        tracks: vec![track],
+        ..Default::default()
+    }
+}
+
+#[test]
+#[cfg(feature = "zip")]
+fn gpx_writer_write_zip() {
+    use std::io::Cursor;
+
+    let files = ["wikipedia_example", "garmin-activity", "with_accuracy"];
+    let sources: Vec<Gpx> = files
+        .iter()
+        .map(|file| read_test_gpx_file(&format!("tests/fixtures/{}.gpx", file)))
+        .collect();
+    // The first name is given with its extension, the others without.
+    let names = ["wikipedia_example.gpx", "garmin-activity", "with_accuracy"];
+    let entries = names
+        .iter()
+        .map(|name| name.to_string())
+        .zip(sources.iter().cloned());
+    let mut archive = Cursor::new(Vec::new());
+    gpx::write_zip(entries, &mut archive, &WriteOptions::new()).unwrap();
+
+    archive.set_position(0);
+    let entries = gpx::read_zip_entries(archive).unwrap();
+    let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "wikipedia_example.gpx",
+            "garmin-activity.gpx",
+            "with_accuracy.gpx"
+        ]
+    );
+    for ((_, written), source) in entries.iter().zip(&sources) {
+        check_metadata_equal(source, written);
+        check_points_equal(source, written);
+    }
+
+    let twice = vec![
+        ("track".to_string(), sources[0].clone()),
+        ("track.gpx".to_string(), sources[1].clone()),
+    ];
+    match gpx::write_zip(twice, Cursor::new(Vec::new()), &WriteOptions::new()) {
+        Err(GpxError::DuplicateZipEntry(name)) => assert_eq!(name, "track.gpx"),
+        other => panic!("expected a duplicate entry, got {:?}", other),
+    }
+
+    let mut unknown = sources[0].clone();
+    unknown.version = GpxVersion::Unknown;
+    let entries = vec![
+        ("first".to_string(), sources[0].clone()),
+        ("second".to_string(), unknown),
+    ];
+    match gpx::write_zip(entries, Cursor::new(Vec::new()), &WriteOptions::new()) {
+        Err(GpxError::ZipEntry(name, _)) => assert_eq!(name, "second.gpx"),
+        other => panic!("expected an entry error, got {:?}", other),
+    }
+
+    // A disk that fills up in the second entry.
+    struct Full(Cursor<Vec<u8>>);
+    impl std::io::Write for Full {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0.position() + buf.len() as u64 > 1000 {
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, "full"));
+            }
+            self.0.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl std::io::Seek for Full {
+        fn seek(&mut self, position: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.0.seek(position)
+        }
+    }
+    let entries = vec![
+        ("first".to_string(), sources[0].clone()),
+        ("second".to_string(), sources[1].clone()),
+    ];
+    match gpx::write_zip(entries, Full(Cursor::new(Vec::new())), &WriteOptions::new()) {
+        Err(GpxError::ZipEntry(name, error)) => {
+            assert_eq!(name, "second.gpx");
+            assert!(matches!(*error, GpxError::IoError(_)));
+        }
+        other => panic!("expected an entry error, got {:?}", other),
+    }
+}
+
 fn check_write_for_example_file(filename: &str) {
     let reference_gpx = read_test_gpx_file(filename);
     let written_gpx = write_and_reread_gpx(&reference_gpx);
@@ -43,6 +960,161 @@ fn check_write_for_example_file(filename: &str) {
     check_points_equal(&reference_gpx, &written_gpx);
 }
 
+#[test]
+fn gpx_writer_time_format() {
+    let start = OffsetDateTime::from_unix_timestamp(1_622_548_800).unwrap();
+    let time = |micros: i64| Some((start + Duration::microseconds(micros)).into());
+    let mut gpx = Gpx {
+        version: GpxVersion::Gpx11,
+        metadata: Some(Metadata {
+            time: time(123_456),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let mut waypoint = Waypoint::new(Point::new(8.5, 47.4));
+    waypoint.time = time(0);
+    gpx.waypoints.push(waypoint);
+    let mut segment = TrackSegment::new();
+    let mut point = Waypoint::new(Point::new(8.5, 47.4));
+    point.time = time(5_000);
+    segment.points.push(point);
+    let mut track = Track::new();
+    track.segments.push(segment);
+    gpx.tracks.push(track);
+
+    let write_times = |gpx: &Gpx, fraction_digits| {
+        let options = WriteOptions::new().time_format(TimeFormat {
+            fraction_digits,
+            offset: OffsetStyle::Utc,
+        });
+        let mut buffer = Vec::new();
+        write_with_options(gpx, &mut buffer, options).unwrap();
+        let xml = String::from_utf8(buffer).unwrap();
+        let times: Vec<String> = xml
+            .split("<time>")
+            .skip(1)
+            .map(|rest| rest[..rest.find("</time>").unwrap()].to_string())
+            .collect();
+        (xml, times)
+    };
+
+    let (_, times) = write_times(&gpx, FractionDigits::Exactly(3));
+    assert_eq!(
+        times,
+        [
+            "2021-06-01T12:00:00.123Z",
+            "2021-06-01T12:00:00.000Z",
+            "2021-06-01T12:00:00.005Z"
+        ]
+    );
+    let (_, times) = write_times(&gpx, FractionDigits::None);
+    assert!(times.iter().all(|time| time == "2021-06-01T12:00:00Z"));
+
+    // Reading keeps every digit that was written.
+    let (xml, _) = write_times(&gpx, FractionDigits::Trimmed);
+    let reread = read(xml.as_bytes()).unwrap();
+    let (_, times) = write_times(&reread, FractionDigits::Trimmed);
+    assert_eq!(
+        times,
+        [
+            "2021-06-01T12:00:00.123456Z",
+            "2021-06-01T12:00:00Z",
+            "2021-06-01T12:00:00.005Z"
+        ]
+    );
+}
+
+/// A xorshift generator, for reproducible random values without a
+/// dependency.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Gives a value from `-limit` to `limit`, alternating between evenly
+    /// spread ones and random bit patterns, which reach the tiny and
+    /// subnormal numbers as well.
+    fn decimal(&mut self, limit: f64) -> f64 {
+        let bits = self.next();
+        let value = f64::from_bits(bits);
+        if bits & 1 == 0 && value.is_finite() && value.abs() <= limit {
+            value
+        } else {
+            ((bits >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0) * limit
+        }
+    }
+}
+
+#[test]
+fn gpx_writer_decimals_round_trip_exactly() {
+    let mut random = Xorshift(0x9E37_79B9_7F4A_7C15);
+    let edge_cases = [
+        (0.0, -0.0, 5e-324),
+        (-90.0, -180.0, f64::MAX),
+        (90.0, 179.999_999_999_999_97, f64::MIN_POSITIVE),
+        (0.1 + 0.2, 1.0 / 3.0, -f64::MAX),
+        (89.999_999_999_999_99, -0.000_001_234_567_890_123_4, 1e21),
+    ];
+    let mut waypoints = Vec::new();
+    for (lat, lon, ele) in edge_cases.iter() {
+        let mut waypoint = Waypoint::new(Point::new(*lon, *lat));
+        waypoint.elevation = Some(*ele);
+        waypoints.push(waypoint);
+    }
+    for _ in 0..5000 {
+        let mut waypoint = Waypoint::new(Point::new(random.decimal(180.0), random.decimal(90.0)));
+        waypoint.elevation = Some(random.decimal(f64::MAX));
+        let extras = waypoint.extras_mut();
+        extras.geoidheight = Some(random.decimal(100.0));
+        extras.hdop = Some(random.decimal(50.0).abs());
+        extras.vdop = Some(random.decimal(50.0).abs());
+        extras.pdop = Some(random.decimal(50.0).abs());
+        extras.dgps_age = Some(random.decimal(1e6).abs());
+        waypoints.push(waypoint);
+    }
+    let mut gpx = Gpx {
+        version: GpxVersion::Gpx11,
+        metadata: Some(Metadata {
+            bounds: Some(geo_types::Rect::new(
+                (random.decimal(180.0), random.decimal(90.0)),
+                (random.decimal(180.0), random.decimal(90.0)),
+            )),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    gpx.waypoints = waypoints;
+
+    let reread = read(write_to_string(&gpx).unwrap().as_bytes()).unwrap();
+    let bits = |gpx: &Gpx| -> Vec<u64> {
+        let bounds = gpx.metadata.as_ref().unwrap().bounds.unwrap();
+        let mut bits = vec![
+            bounds.min().x,
+            bounds.min().y,
+            bounds.max().x,
+            bounds.max().y,
+        ];
+        for waypoint in &gpx.waypoints {
+            let extras = waypoint.extras();
+            bits.extend(&[waypoint.lat(), waypoint.lon()]);
+            bits.extend(waypoint.elevation);
+            bits.extend(extras.geoidheight);
+            bits.extend(extras.hdop);
+            bits.extend(extras.vdop);
+            bits.extend(extras.pdop);
+            bits.extend(extras.dgps_age);
+        }
+        bits.into_iter().map(f64::to_bits).collect()
+    };
+    assert_eq!(bits(&reread), bits(&gpx));
+}
+
 fn read_test_gpx_file(filename: &str) -> Gpx {
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
@@ -55,7 +1127,7 @@ fn read_test_gpx_file(filename: &str) -> Gpx {
 
 fn write_and_reread_gpx(reference_gpx: &Gpx) -> Gpx {
     let mut buffer: Vec<u8> = Vec::new();
-    let result = write(&reference_gpx, &mut buffer);
+    let result = write(reference_gpx, &mut buffer);
     assert!(result.is_ok());
 
     let written_gpx = read(buffer.as_slice()).unwrap();
@@ -78,7 +1150,7 @@ fn check_metadata_equal(reference_gpx: &Gpx, written_gpx: &Gpx) {
     check_links_equal(&reference.links, &written.links);
 }
 
-fn check_links_equal(reference: &Vec<Link>, written: &Vec<Link>) {
+fn check_links_equal(reference: &[Link], written: &[Link]) {
     assert_eq!(reference.len(), written.len());
     for (r, w) in reference.iter().zip(written) {
         assert_eq!(r.href, w.href);
@@ -98,27 +1170,47 @@ fn check_points_equal(reference: &Gpx, written: &Gpx) {
     }
 }
 
-fn check_waypoints_equal(reference: &Vec<Waypoint>, written: &Vec<Waypoint>) {
+fn check_waypoints_equal(reference: &[Waypoint], written: &[Waypoint]) {
     assert_eq!(reference.len(), written.len());
     for (r_wp, w_wp) in reference.iter().zip(written) {
         assert_eq!(r_wp.point(), w_wp.point());
         assert_eq!(r_wp.elevation, w_wp.elevation);
-        assert_eq!(r_wp.speed, w_wp.speed);
+        assert_eq!(r_wp.extras().speed, w_wp.extras().speed);
         assert_eq!(r_wp.time, w_wp.time);
-        assert_eq!(r_wp.geoidheight, w_wp.geoidheight);
+        assert_eq!(r_wp.extras().geoidheight, w_wp.extras().geoidheight);
         assert_eq!(r_wp.name, w_wp.name);
-        assert_eq!(r_wp.comment, w_wp.comment);
-        assert_eq!(r_wp.description, w_wp.description);
-        assert_eq!(r_wp.source, w_wp.source);
-        check_links_equal(&r_wp.links, &w_wp.links);
-        assert_eq!(r_wp.symbol, w_wp.symbol);
-        assert_eq!(r_wp._type, w_wp._type);
-        assert_eq!(r_wp.fix, w_wp.fix);
-        assert_eq!(r_wp.sat, w_wp.sat);
-        assert_eq!(r_wp.hdop, w_wp.hdop);
-        assert_eq!(r_wp.vdop, w_wp.vdop);
-        assert_eq!(r_wp.pdop, w_wp.pdop);
-        assert_eq!(r_wp.dgps_age, w_wp.dgps_age);
-        assert_eq!(r_wp.dgpsid, w_wp.dgpsid);
+        assert_eq!(r_wp.extras().comment, w_wp.extras().comment);
+        assert_eq!(r_wp.extras().description, w_wp.extras().description);
+        assert_eq!(r_wp.extras().source, w_wp.extras().source);
+        check_links_equal(&r_wp.extras().links, &w_wp.extras().links);
+        assert_eq!(r_wp.extras().symbol, w_wp.extras().symbol);
+        assert_eq!(r_wp.extras()._type, w_wp.extras()._type);
+        assert_eq!(r_wp.extras().fix, w_wp.extras().fix);
+        assert_eq!(r_wp.extras().sat, w_wp.extras().sat);
+        assert_eq!(r_wp.extras().hdop, w_wp.extras().hdop);
+        assert_eq!(r_wp.extras().vdop, w_wp.extras().vdop);
+        assert_eq!(r_wp.extras().pdop, w_wp.extras().pdop);
+        assert_eq!(r_wp.extras().dgps_age, w_wp.extras().dgps_age);
+        assert_eq!(r_wp.extras().dgpsid, w_wp.extras().dgpsid);
+    }
+}
+
+#[test]
+fn gpx_writer_preserves_read_offsets() {
+    let gpx = read(File::open("tests/fixtures/ist_offset.gpx").unwrap()).unwrap();
+    let options = WriteOptions::new().time_format(TimeFormat {
+        fraction_digits: FractionDigits::Trimmed,
+        offset: OffsetStyle::Preserve,
+    });
+    let mut buffer = Vec::new();
+    write_with_options(&gpx, &mut buffer, options).unwrap();
+    let xml = String::from_utf8(buffer).unwrap();
+    for time in &[
+        "<time>2021-06-01T17:30:00+05:30</time>",
+        "<time>2021-06-01T17:45:30.25+05:30</time>",
+        "<time>2021-06-01T18:00:00+05:30</time>",
+        "<time>2021-06-01T12:30:05Z</time>",
+    ] {
+        assert!(xml.contains(time), "{} missing in {}", time, xml);
     }
 }